@@ -0,0 +1,21 @@
+-- ============================================
+-- SmartSpecPro Workspace Index Schema
+-- Version: 1
+-- Location: ~/SmartSpec/workspaces/.workspace-index.db
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS workspaces (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    path TEXT NOT NULL UNIQUE,
+    git_remote TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    last_accessed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    is_active BOOLEAN DEFAULT 1,
+    metadata_json TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_workspaces_last_accessed
+    ON workspaces(last_accessed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_workspaces_active
+    ON workspaces(is_active, last_accessed_at DESC);
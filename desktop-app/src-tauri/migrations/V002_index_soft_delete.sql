@@ -0,0 +1,10 @@
+-- ============================================
+-- SmartSpecPro Workspace Index Schema
+-- Version: 2
+-- Soft-deleted workspaces are kept in the index until purged
+-- ============================================
+
+ALTER TABLE workspaces ADD COLUMN deleted_at DATETIME;
+
+CREATE INDEX IF NOT EXISTS idx_workspaces_deleted
+    ON workspaces(is_active, deleted_at);
@@ -0,0 +1,33 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 2
+-- Full-text search for long-term memory
+-- ============================================
+
+CREATE VIRTUAL TABLE IF NOT EXISTS memory_long_fts USING fts5(
+    title,
+    content,
+    content='memory_long',
+    content_rowid='id'
+);
+
+-- Triggers to keep FTS in sync
+CREATE TRIGGER IF NOT EXISTS memory_long_ai AFTER INSERT ON memory_long BEGIN
+    INSERT INTO memory_long_fts(rowid, title, content)
+    VALUES (new.id, new.title, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_long_ad AFTER DELETE ON memory_long BEGIN
+    INSERT INTO memory_long_fts(memory_long_fts, rowid, title, content)
+    VALUES ('delete', old.id, old.title, old.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_long_au AFTER UPDATE ON memory_long BEGIN
+    INSERT INTO memory_long_fts(memory_long_fts, rowid, title, content)
+    VALUES ('delete', old.id, old.title, old.content);
+    INSERT INTO memory_long_fts(rowid, title, content)
+    VALUES (new.id, new.title, new.content);
+END;
+
+-- Index memories written before this table existed
+INSERT INTO memory_long_fts(memory_long_fts) VALUES ('rebuild');
@@ -0,0 +1,32 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 4
+-- Full-text search for chat history
+-- ============================================
+
+CREATE VIRTUAL TABLE IF NOT EXISTS chat_messages_fts USING fts5(
+    content,
+    content='chat_messages',
+    content_rowid='id'
+);
+
+-- Triggers to keep FTS in sync
+CREATE TRIGGER IF NOT EXISTS chat_messages_ai AFTER INSERT ON chat_messages BEGIN
+    INSERT INTO chat_messages_fts(rowid, content)
+    VALUES (new.id, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_messages_ad AFTER DELETE ON chat_messages BEGIN
+    INSERT INTO chat_messages_fts(chat_messages_fts, rowid, content)
+    VALUES ('delete', old.id, old.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_messages_au AFTER UPDATE OF content ON chat_messages BEGIN
+    INSERT INTO chat_messages_fts(chat_messages_fts, rowid, content)
+    VALUES ('delete', old.id, old.content);
+    INSERT INTO chat_messages_fts(rowid, content)
+    VALUES (new.id, new.content);
+END;
+
+-- Index messages written before this table existed
+INSERT INTO chat_messages_fts(chat_messages_fts) VALUES ('rebuild');
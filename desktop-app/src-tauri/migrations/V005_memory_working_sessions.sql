@@ -0,0 +1,32 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 5
+-- Align working memory with MemoryManager: session scoped entries with
+-- category, title, pin order and source. job_id becomes optional.
+-- ============================================
+
+CREATE TABLE memory_working_v5 (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_id TEXT,
+    session_id TEXT,
+    category TEXT NOT NULL DEFAULT 'note',
+    title TEXT NOT NULL DEFAULT '',
+    content TEXT NOT NULL,
+    metadata_json TEXT,
+    is_pinned BOOLEAN DEFAULT 0,
+    pin_order INTEGER DEFAULT 0,
+    source TEXT NOT NULL DEFAULT 'user',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO memory_working_v5 (id, job_id, category, content, metadata_json, is_pinned, pin_order, created_at, updated_at)
+SELECT id, job_id, type, content, metadata_json, pinned, priority, created_at, updated_at
+FROM memory_working;
+
+DROP TABLE memory_working;
+ALTER TABLE memory_working_v5 RENAME TO memory_working;
+
+CREATE INDEX IF NOT EXISTS idx_memory_working_job ON memory_working(job_id);
+CREATE INDEX IF NOT EXISTS idx_memory_working_session ON memory_working(session_id, category);
+CREATE INDEX IF NOT EXISTS idx_memory_working_pinned ON memory_working(is_pinned, pin_order);
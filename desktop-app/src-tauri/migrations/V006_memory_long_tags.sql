@@ -0,0 +1,8 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 6
+-- Columns MemoryManager reads and writes on long-term memory
+-- ============================================
+
+ALTER TABLE memory_long ADD COLUMN tags_json TEXT;
+ALTER TABLE memory_long ADD COLUMN embedding_json TEXT;
@@ -0,0 +1,22 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 7
+-- Key metrics of each quality report, for trends over time
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS quality_snapshots (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    commit_sha TEXT,
+    overall_score REAL NOT NULL,
+    complexity REAL NOT NULL,
+    maintainability REAL NOT NULL,
+    documentation_coverage REAL NOT NULL,
+    code_coverage REAL,
+    test_count INTEGER NOT NULL,
+    issue_count INTEGER NOT NULL,
+    lines_of_code INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_quality_snapshots_project ON quality_snapshots(project_id, created_at);
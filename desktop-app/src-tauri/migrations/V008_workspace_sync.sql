@@ -0,0 +1,70 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 8
+-- Stable ids and bookkeeping for syncing knowledge and memory
+-- ============================================
+
+-- Row ids differ between machines, so synced rows are matched by sync_id
+ALTER TABLE knowledge ADD COLUMN sync_id TEXT;
+ALTER TABLE memory_long ADD COLUMN sync_id TEXT;
+
+UPDATE knowledge SET sync_id = lower(hex(randomblob(16))) WHERE sync_id IS NULL;
+UPDATE memory_long SET sync_id = lower(hex(randomblob(16))) WHERE sync_id IS NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_knowledge_sync_id ON knowledge(sync_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_memory_long_sync_id ON memory_long(sync_id);
+
+-- The FTS update triggers fired on any column, so assigning a sync_id
+-- right after an insert reindexed a row before it was indexed. Limit them
+-- to the indexed columns.
+DROP TRIGGER IF EXISTS knowledge_au;
+CREATE TRIGGER knowledge_au AFTER UPDATE OF title, content, tags_json ON knowledge BEGIN
+    INSERT INTO knowledge_fts(knowledge_fts, rowid, title, content, tags_json)
+    VALUES ('delete', old.id, old.title, old.content, old.tags_json);
+    INSERT INTO knowledge_fts(rowid, title, content, tags_json)
+    VALUES (new.id, new.title, new.content, new.tags_json);
+END;
+
+DROP TRIGGER IF EXISTS memory_long_au;
+CREATE TRIGGER memory_long_au AFTER UPDATE OF title, content ON memory_long BEGIN
+    INSERT INTO memory_long_fts(memory_long_fts, rowid, title, content)
+    VALUES ('delete', old.id, old.title, old.content);
+    INSERT INTO memory_long_fts(rowid, title, content)
+    VALUES (new.id, new.title, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS knowledge_sync_id AFTER INSERT ON knowledge
+WHEN new.sync_id IS NULL BEGIN
+    UPDATE knowledge SET sync_id = lower(hex(randomblob(16))) WHERE id = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_long_sync_id AFTER INSERT ON memory_long
+WHEN new.sync_id IS NULL BEGIN
+    UPDATE memory_long SET sync_id = lower(hex(randomblob(16))) WHERE id = new.id;
+END;
+
+-- Content hash of each row as of the last successful sync (the merge base)
+CREATE TABLE IF NOT EXISTS sync_state (
+    table_name TEXT NOT NULL,
+    sync_id TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    PRIMARY KEY (table_name, sync_id)
+);
+
+-- Rows changed on both sides since the last sync. The newer edit is kept;
+-- the other version stays here until the user resolves it.
+CREATE TABLE IF NOT EXISTS sync_conflicts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    table_name TEXT NOT NULL,
+    sync_id TEXT NOT NULL,
+    local_hash TEXT NOT NULL,
+    remote_hash TEXT NOT NULL,
+    local_json TEXT NOT NULL,
+    remote_json TEXT NOT NULL,
+    winner TEXT NOT NULL CHECK(winner IN ('local', 'remote')),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    resolved_at DATETIME,
+    UNIQUE (table_name, sync_id, local_hash, remote_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_conflicts_open ON sync_conflicts(resolved_at);
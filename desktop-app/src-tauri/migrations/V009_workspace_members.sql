@@ -0,0 +1,29 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 9
+-- Team members and the invite tokens that grant them access
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS workspace_members (
+    user_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    role TEXT NOT NULL CHECK(role IN ('owner', 'admin', 'editor', 'viewer')),
+    added_by TEXT,
+    added_at INTEGER NOT NULL,
+    last_active_at INTEGER NOT NULL
+);
+
+-- Only a hash of each token is stored
+CREATE TABLE IF NOT EXISTS workspace_invites (
+    id TEXT PRIMARY KEY,
+    token_hash TEXT NOT NULL UNIQUE,
+    user_id TEXT NOT NULL,
+    role TEXT NOT NULL CHECK(role IN ('owner', 'admin', 'editor', 'viewer')),
+    created_by TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    revoked_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_workspace_invites_user ON workspace_invites(user_id);
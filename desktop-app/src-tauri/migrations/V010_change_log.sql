@@ -0,0 +1,310 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 10
+-- Change log for incremental backups
+-- ============================================
+
+-- While the database belongs to a backup chain, every insert, update and
+-- delete records the table and rowid it touched. An incremental backup
+-- copies the current state of the rows logged since the previous backup,
+-- then prunes the log up to that point. Without a chain nothing would ever
+-- prune it, so nothing is logged.
+CREATE TABLE IF NOT EXISTS change_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    table_name TEXT NOT NULL,
+    row_id INTEGER NOT NULL
+);
+
+CREATE TRIGGER IF NOT EXISTS workspace_info_log_ai AFTER INSERT ON workspace_info
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_info', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS workspace_info_log_au AFTER UPDATE ON workspace_info
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_info', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'workspace_info', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS workspace_info_log_ad AFTER DELETE ON workspace_info
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_info', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_short_log_ai AFTER INSERT ON memory_short
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_short', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS memory_short_log_au AFTER UPDATE ON memory_short
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_short', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'memory_short', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS memory_short_log_ad AFTER DELETE ON memory_short
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_short', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_working_log_ai AFTER INSERT ON memory_working
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_working', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS memory_working_log_au AFTER UPDATE ON memory_working
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_working', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'memory_working', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS memory_working_log_ad AFTER DELETE ON memory_working
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_working', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_long_log_ai AFTER INSERT ON memory_long
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_long', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS memory_long_log_au AFTER UPDATE ON memory_long
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_long', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'memory_long', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS memory_long_log_ad AFTER DELETE ON memory_long
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_long', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_links_log_ai AFTER INSERT ON memory_links
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_links', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS memory_links_log_au AFTER UPDATE ON memory_links
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_links', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'memory_links', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS memory_links_log_ad AFTER DELETE ON memory_links
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_links', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS knowledge_log_ai AFTER INSERT ON knowledge
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS knowledge_log_au AFTER UPDATE ON knowledge
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'knowledge', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS knowledge_log_ad AFTER DELETE ON knowledge
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS jobs_log_ai AFTER INSERT ON jobs
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('jobs', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS jobs_log_au AFTER UPDATE ON jobs
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('jobs', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'jobs', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS jobs_log_ad AFTER DELETE ON jobs
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('jobs', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS tasks_log_ai AFTER INSERT ON tasks
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('tasks', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS tasks_log_au AFTER UPDATE ON tasks
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('tasks', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'tasks', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS tasks_log_ad AFTER DELETE ON tasks
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('tasks', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS task_dependencies_log_ai AFTER INSERT ON task_dependencies
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('task_dependencies', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS task_dependencies_log_au AFTER UPDATE ON task_dependencies
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('task_dependencies', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'task_dependencies', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS task_dependencies_log_ad AFTER DELETE ON task_dependencies
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('task_dependencies', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_sessions_log_ai AFTER INSERT ON chat_sessions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('chat_sessions', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS chat_sessions_log_au AFTER UPDATE ON chat_sessions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('chat_sessions', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'chat_sessions', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS chat_sessions_log_ad AFTER DELETE ON chat_sessions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('chat_sessions', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS chat_messages_log_ai AFTER INSERT ON chat_messages
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('chat_messages', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS chat_messages_log_au AFTER UPDATE ON chat_messages
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('chat_messages', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'chat_messages', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS chat_messages_log_ad AFTER DELETE ON chat_messages
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('chat_messages', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS file_operations_log_ai AFTER INSERT ON file_operations
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('file_operations', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS file_operations_log_au AFTER UPDATE ON file_operations
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('file_operations', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'file_operations', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS file_operations_log_ad AFTER DELETE ON file_operations
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('file_operations', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS checkpoints_log_ai AFTER INSERT ON checkpoints
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('checkpoints', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS checkpoints_log_au AFTER UPDATE ON checkpoints
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('checkpoints', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'checkpoints', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS checkpoints_log_ad AFTER DELETE ON checkpoints
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('checkpoints', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS skill_executions_log_ai AFTER INSERT ON skill_executions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('skill_executions', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS skill_executions_log_au AFTER UPDATE ON skill_executions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('skill_executions', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'skill_executions', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS skill_executions_log_ad AFTER DELETE ON skill_executions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('skill_executions', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS usage_log_log_ai AFTER INSERT ON usage_log
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('usage_log', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS usage_log_log_au AFTER UPDATE ON usage_log
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('usage_log', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'usage_log', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS usage_log_log_ad AFTER DELETE ON usage_log
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('usage_log', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS settings_log_ai AFTER INSERT ON settings
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('settings', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS settings_log_au AFTER UPDATE ON settings
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('settings', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'settings', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS settings_log_ad AFTER DELETE ON settings
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('settings', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS quality_snapshots_log_ai AFTER INSERT ON quality_snapshots
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('quality_snapshots', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS quality_snapshots_log_au AFTER UPDATE ON quality_snapshots
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('quality_snapshots', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'quality_snapshots', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS quality_snapshots_log_ad AFTER DELETE ON quality_snapshots
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('quality_snapshots', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_state_log_ai AFTER INSERT ON sync_state
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('sync_state', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS sync_state_log_au AFTER UPDATE ON sync_state
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('sync_state', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'sync_state', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS sync_state_log_ad AFTER DELETE ON sync_state
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('sync_state', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_conflicts_log_ai AFTER INSERT ON sync_conflicts
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('sync_conflicts', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS sync_conflicts_log_au AFTER UPDATE ON sync_conflicts
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('sync_conflicts', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'sync_conflicts', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS sync_conflicts_log_ad AFTER DELETE ON sync_conflicts
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('sync_conflicts', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS workspace_members_log_ai AFTER INSERT ON workspace_members
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_members', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS workspace_members_log_au AFTER UPDATE ON workspace_members
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_members', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'workspace_members', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS workspace_members_log_ad AFTER DELETE ON workspace_members
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_members', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS workspace_invites_log_ai AFTER INSERT ON workspace_invites
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_invites', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS workspace_invites_log_au AFTER UPDATE ON workspace_invites
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_invites', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'workspace_invites', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS workspace_invites_log_ad AFTER DELETE ON workspace_invites
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('workspace_invites', old.rowid);
+END;
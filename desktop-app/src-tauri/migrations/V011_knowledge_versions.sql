@@ -0,0 +1,37 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 11
+-- Prior versions of knowledge entries
+-- ============================================
+
+-- Each update of a knowledge entry first copies the row as it was. Versions
+-- are numbered per entry from 1; the live row is always the newest state.
+CREATE TABLE IF NOT EXISTS knowledge_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    knowledge_id INTEGER NOT NULL REFERENCES knowledge(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    tags_json TEXT,
+    file_refs_json TEXT,
+    -- When the row held this state
+    updated_at DATETIME,
+    -- When it was replaced
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(knowledge_id, version)
+);
+
+CREATE TRIGGER IF NOT EXISTS knowledge_versions_log_ai AFTER INSERT ON knowledge_versions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge_versions', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS knowledge_versions_log_au AFTER UPDATE ON knowledge_versions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge_versions', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'knowledge_versions', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS knowledge_versions_log_ad AFTER DELETE ON knowledge_versions
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge_versions', old.rowid);
+END;
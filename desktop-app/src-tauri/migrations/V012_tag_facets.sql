@@ -0,0 +1,105 @@
+-- ============================================
+-- SmartSpecPro Workspace Database Schema
+-- Version: 12
+-- Normalized tags for knowledge and long-term memory
+-- ============================================
+
+-- One row per tag in a row's tags_json, kept in step by triggers so every
+-- writer (commands, MemoryManager, sync) is covered. Existing rows are
+-- backfilled below.
+
+-- ============================================
+-- Knowledge Tags
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS knowledge_tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    knowledge_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    UNIQUE(knowledge_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_knowledge_tags_tag ON knowledge_tags(tag);
+
+CREATE TRIGGER IF NOT EXISTS knowledge_tags_ai AFTER INSERT ON knowledge BEGIN
+    INSERT OR IGNORE INTO knowledge_tags (knowledge_id, tag)
+    SELECT new.id, trim(value) FROM json_each(CASE WHEN json_valid(new.tags_json) THEN new.tags_json ELSE '[]' END)
+    WHERE type = 'text' AND trim(value) != '';
+END;
+
+CREATE TRIGGER IF NOT EXISTS knowledge_tags_au AFTER UPDATE OF tags_json ON knowledge BEGIN
+    DELETE FROM knowledge_tags WHERE knowledge_id = old.id;
+    INSERT OR IGNORE INTO knowledge_tags (knowledge_id, tag)
+    SELECT new.id, trim(value) FROM json_each(CASE WHEN json_valid(new.tags_json) THEN new.tags_json ELSE '[]' END)
+    WHERE type = 'text' AND trim(value) != '';
+END;
+
+CREATE TRIGGER IF NOT EXISTS knowledge_tags_ad AFTER DELETE ON knowledge BEGIN
+    DELETE FROM knowledge_tags WHERE knowledge_id = old.id;
+END;
+
+INSERT OR IGNORE INTO knowledge_tags (knowledge_id, tag)
+SELECT k.id, trim(j.value) FROM knowledge k, json_each(CASE WHEN json_valid(k.tags_json) THEN k.tags_json ELSE '[]' END) j
+WHERE j.type = 'text' AND trim(j.value) != '';
+
+-- ============================================
+-- Long-term Memory Tags
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS memory_long_tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    memory_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    UNIQUE(memory_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_memory_long_tags_tag ON memory_long_tags(tag);
+
+CREATE TRIGGER IF NOT EXISTS memory_long_tags_ai AFTER INSERT ON memory_long BEGIN
+    INSERT OR IGNORE INTO memory_long_tags (memory_id, tag)
+    SELECT new.id, trim(value) FROM json_each(CASE WHEN json_valid(new.tags_json) THEN new.tags_json ELSE '[]' END)
+    WHERE type = 'text' AND trim(value) != '';
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_long_tags_au AFTER UPDATE OF tags_json ON memory_long BEGIN
+    DELETE FROM memory_long_tags WHERE memory_id = old.id;
+    INSERT OR IGNORE INTO memory_long_tags (memory_id, tag)
+    SELECT new.id, trim(value) FROM json_each(CASE WHEN json_valid(new.tags_json) THEN new.tags_json ELSE '[]' END)
+    WHERE type = 'text' AND trim(value) != '';
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_long_tags_ad AFTER DELETE ON memory_long BEGIN
+    DELETE FROM memory_long_tags WHERE memory_id = old.id;
+END;
+
+INSERT OR IGNORE INTO memory_long_tags (memory_id, tag)
+SELECT m.id, trim(j.value) FROM memory_long m, json_each(CASE WHEN json_valid(m.tags_json) THEN m.tags_json ELSE '[]' END) j
+WHERE j.type = 'text' AND trim(j.value) != '';
+
+CREATE TRIGGER IF NOT EXISTS knowledge_tags_log_ai AFTER INSERT ON knowledge_tags
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge_tags', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS knowledge_tags_log_au AFTER UPDATE ON knowledge_tags
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge_tags', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'knowledge_tags', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS knowledge_tags_log_ad AFTER DELETE ON knowledge_tags
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('knowledge_tags', old.rowid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_long_tags_log_ai AFTER INSERT ON memory_long_tags
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_long_tags', new.rowid);
+END;
+CREATE TRIGGER IF NOT EXISTS memory_long_tags_log_au AFTER UPDATE ON memory_long_tags
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_long_tags', old.rowid);
+    INSERT INTO change_log (table_name, row_id) SELECT 'memory_long_tags', new.rowid WHERE new.rowid != old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS memory_long_tags_log_ad AFTER DELETE ON memory_long_tags
+    WHEN EXISTS (SELECT 1 FROM workspace_info WHERE key = 'backup_chain_id') BEGIN
+    INSERT INTO change_log (table_name, row_id) VALUES ('memory_long_tags', old.rowid);
+END;
@@ -9,16 +9,45 @@ It provides a JSON-based interface for executing workflows and getting results.
 import sys
 import json
 import argparse
+import threading
+import time
 from pathlib import Path
 from typing import Dict, Any, Optional
 from datetime import datetime
 
+# Version of the JSON messages read by the app (BRIDGE_SCHEMA_VERSION in
+# python_bridge.rs). Bump both together when a message shape changes.
+SCHEMA_VERSION = 1
+
 
 class JsonStreamLogger:
     """Logger that outputs JSON Lines to stdout"""
     
-    def __init__(self, workflow_id: str):
+    def __init__(self, workflow_id: str, request_id: Optional[str] = None):
         self.workflow_id = workflow_id
+        self.request_id = request_id
+        self._lock = threading.Lock()
+    
+    def emit(self, data: Dict[str, Any], stream=None):
+        """Write one message, tagged with the request id the app is waiting on"""
+        data["schema_version"] = SCHEMA_VERSION
+        if self.request_id:
+            data["request_id"] = self.request_id
+        with self._lock:
+            print(json.dumps(data), file=stream or sys.stdout, flush=True)
+    
+    def start_heartbeat(self, interval: float):
+        """Send a heartbeat every `interval` seconds until the process exits"""
+        def beat():
+            while True:
+                self.emit({
+                    "type": "heartbeat",
+                    "workflow_id": self.workflow_id,
+                    "timestamp": datetime.utcnow().isoformat() + "Z"
+                })
+                time.sleep(interval)
+        
+        threading.Thread(target=beat, daemon=True).start()
     
     def log(self, level: str, message: str, **kwargs):
         """Log a message"""
@@ -30,7 +59,7 @@ class JsonStreamLogger:
             "timestamp": datetime.utcnow().isoformat() + "Z",
             **kwargs
         }
-        print(json.dumps(data), flush=True)
+        self.emit(data)
     
     def progress(self, step: str, progress: float, message: str):
         """Report progress"""
@@ -42,7 +71,17 @@ class JsonStreamLogger:
             "message": message,
             "timestamp": datetime.utcnow().isoformat() + "Z"
         }
-        print(json.dumps(data), flush=True)
+        self.emit(data)
+    
+    def artifact(self, path: str, kind: Optional[str] = None):
+        """Declare a file produced by the workflow so the app collects it"""
+        self.emit({
+            "type": "artifact",
+            "workflow_id": self.workflow_id,
+            "path": path,
+            "kind": kind,
+            "timestamp": datetime.utcnow().isoformat() + "Z"
+        })
     
     def error(self, code: str, message: str, **kwargs):
         """Log an error"""
@@ -54,15 +93,20 @@ class JsonStreamLogger:
             "timestamp": datetime.utcnow().isoformat() + "Z",
             **kwargs
         }
-        print(json.dumps(data), file=sys.stderr, flush=True)
+        self.emit(data, sys.stderr)
 
 
 class WorkflowRunner:
     """Execute Kilo Code CLI workflows"""
     
-    def __init__(self, workflow_id: str, smartspec_root: Optional[Path] = None):
+    def __init__(
+        self,
+        workflow_id: str,
+        smartspec_root: Optional[Path] = None,
+        request_id: Optional[str] = None,
+    ):
         self.workflow_id = workflow_id
-        self.logger = JsonStreamLogger(workflow_id)
+        self.logger = JsonStreamLogger(workflow_id, request_id)
         
         # Find SmartSpec root
         if smartspec_root:
@@ -89,12 +133,12 @@ class WorkflowRunner:
         try:
             # Log start
             self.logger.log("info", f"Starting workflow: {workflow_name}")
-            print(json.dumps({
+            self.logger.emit({
                 "type": "started",
                 "workflow_id": self.workflow_id,
                 "workflow_name": workflow_name,
                 "timestamp": datetime.utcnow().isoformat() + "Z"
-            }), flush=True)
+            })
             
             # Execute workflow
             self.logger.progress("execute", 0.0, "Initializing...")
@@ -103,24 +147,24 @@ class WorkflowRunner:
             
             # Log completion
             self.logger.log("info", "Workflow completed successfully")
-            print(json.dumps({
+            self.logger.emit({
                 "type": "completed",
                 "workflow_id": self.workflow_id,
                 "result": result,
                 "timestamp": datetime.utcnow().isoformat() + "Z"
-            }), flush=True)
+            })
             
             return result
             
         except Exception as e:
             # Log error
             self.logger.error("WORKFLOW_FAILED", str(e))
-            print(json.dumps({
+            self.logger.emit({
                 "type": "failed",
                 "workflow_id": self.workflow_id,
                 "error": str(e),
                 "timestamp": datetime.utcnow().isoformat() + "Z"
-            }), flush=True)
+            })
             raise
     
     def _execute_workflow(self, workflow_name: str, args: Dict[str, Any]) -> Dict[str, Any]:
@@ -149,10 +193,13 @@ class WorkflowRunner:
                 platform=platform
             )
             
+            artifacts = result.get("artifacts", []) if isinstance(result, dict) else []
+            artifacts = [a if isinstance(a, dict) else {"path": str(a)} for a in artifacts]
             return {
                 "success": True,
                 "spec_id": spec_id,
-                "output": result
+                "output": result,
+                "artifacts": artifacts
             }
             
         except ImportError as e:
@@ -160,7 +207,6 @@ class WorkflowRunner:
             # Fallback: simulate workflow execution
             self.logger.log("warning", "Kilo Code CLI not found, simulating workflow")
             
-            import time
             for i in range(5):
                 self.logger.progress("simulate", i / 4, f"Step {i+1}/5")
                 time.sleep(0.5)
@@ -169,6 +215,7 @@ class WorkflowRunner:
                 "success": True,
                 "spec_id": spec_id,
                 "output": "Simulated workflow execution (CLI not found)",
+                "artifacts": [],
                 "simulated": True
             }
 
@@ -177,8 +224,11 @@ def cmd_run_workflow(args):
     """Run a workflow"""
     runner = WorkflowRunner(
         args.workflow_id,
-        smartspec_root=Path(args.smartspec_root) if args.smartspec_root else None
+        smartspec_root=Path(args.smartspec_root) if args.smartspec_root else None,
+        request_id=args.request_id
     )
+    if args.heartbeat_interval > 0:
+        runner.logger.start_heartbeat(args.heartbeat_interval)
     
     workflow_args = {
         "spec_id": args.spec_id,
@@ -202,6 +252,7 @@ def cmd_list_workflows(args):
         
         output = {
             "type": "workflows_list",
+            "schema_version": SCHEMA_VERSION,
             "workflows": workflows,
             "count": len(workflows),
             "timestamp": datetime.utcnow().isoformat() + "Z"
@@ -225,6 +276,7 @@ def cmd_list_workflows(args):
         
         output = {
             "type": "workflows_list",
+            "schema_version": SCHEMA_VERSION,
             "workflows": workflows,
             "count": len(workflows),
             "simulated": True,
@@ -307,6 +359,8 @@ def main():
     # run-workflow command
     run_parser = subparsers.add_parser("run-workflow", help="Run a workflow")
     run_parser.add_argument("--workflow-id", required=True, help="Unique workflow ID")
+    run_parser.add_argument("--request-id", help="Correlation ID echoed on every message")
+    run_parser.add_argument("--heartbeat-interval", type=float, default=0, help="Seconds between heartbeats (0 disables)")
     run_parser.add_argument("--workflow-name", required=True, help="Workflow name")
     run_parser.add_argument("--spec-id", required=True, help="Spec ID")
     run_parser.add_argument("--category", default="core", help="Spec category")
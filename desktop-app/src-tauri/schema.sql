@@ -29,6 +29,7 @@ CREATE TABLE IF NOT EXISTS executions (
     error TEXT,
     started_at INTEGER NOT NULL,
     completed_at INTEGER,
+    config_snapshot TEXT,  -- JSON string
     FOREIGN KEY (workflow_id) REFERENCES workflows(id) ON DELETE CASCADE
 );
 
@@ -56,6 +57,18 @@ CREATE TABLE IF NOT EXISTS configs (
 CREATE INDEX IF NOT EXISTS idx_configs_workflow_id ON configs(workflow_id);
 CREATE INDEX IF NOT EXISTS idx_configs_key ON configs(key);
 
+-- Config templates table
+-- Stores reusable config parameter sets, shared across workflows and workspaces
+CREATE TABLE IF NOT EXISTS config_templates (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    variables TEXT NOT NULL,  -- JSON array
+    entries TEXT NOT NULL,  -- JSON array
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
 -- Metadata table
 -- Stores database version and other metadata
 CREATE TABLE IF NOT EXISTS metadata (
@@ -2,18 +2,55 @@
 //
 // Provides commands for:
 // - Smart suggestions
+// - LLM code review and bug prediction over workspace files
 // - Code completion
 // - Quality analysis
-// - Auto-documentation
+// - Auto-documentation as reviewable diffs
 
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::ai_enhancement::{
-    AiEnhancementService, Suggestion, CompletionRequest, CompletionResult,
-    QualityReport, DocumentationRequest, DocumentationResult, AiSettings,
+    self, AiEnhancementService, Suggestion, CompletionRequest, CompletionResult,
+    QualityReport, AiSettings, AnalysisKind, DocSyntax, FileAnalysis,
 };
+use crate::chat_commands::ChatState;
+use crate::cli_commands::CliState;
+use crate::cli_service::CodeSuggestion;
+use crate::llm_service::{LlmModel, LlmService};
+use crate::memory_monitor::{CacheEvictor, MemoryPressure};
+use crate::rate_limiter::{AI_ANALYZE_CODE_KEY, AI_PREDICT_BUGS_KEY, KEYED_RATE_LIMITER};
+use crate::workspace_commands::AppState as WorkspaceAppState;
+use crate::workspace_data::{QualityTrendPoint, DEFAULT_QUALITY_REGRESSION_PCT};
+use crate::workspace_sync;
+
+/// Mode whose selected model runs code analysis
+const CODE_ANALYSIS_MODE: &str = "code_analysis";
+
+/// Mode whose selected model writes documentation
+const DOCUMENTATION_MODE: &str = "documentation";
+
+/// Reports returned by `ai_get_quality_trend` when no limit is given
+const DEFAULT_QUALITY_TREND_LIMIT: usize = 50;
+
+/// The LLM service with a key configured, plus the model selected for `mode`
+/// and the source tokens that fit in one request to it
+async fn llm_for_mode(app: &AppHandle, mode: &str) -> Result<(Arc<LlmService>, String, usize), String> {
+    let llm_service = match app.try_state::<Arc<Mutex<ChatState>>>() {
+        Some(chat) => Arc::clone(&chat.lock().await.llm_service),
+        None => return Err("LLM service not initialized".to_string()),
+    };
+    if !llm_service.has_api_key().await {
+        return Err(format!("No API key configured for {}", mode.replace('_', " ")));
+    }
+
+    let model_id = llm_service.get_model_for_mode(mode).await;
+    let context_length = LlmModel::get_model_by_id(&model_id)
+        .map(|m| m.context_length)
+        .unwrap_or(ai_enhancement::DEFAULT_CONTEXT_LENGTH);
+    Ok((llm_service, model_id, ai_enhancement::chunk_token_budget(context_length)))
+}
 
 // ============================================
 // State Types
@@ -31,19 +68,119 @@ impl AiEnhancementState {
     }
 }
 
+/// Cached LLM findings are re-requested on demand, so memory pressure can
+/// drop them. A busy state is skipped rather than waited on.
+impl CacheEvictor for Mutex<AiEnhancementState> {
+    fn name(&self) -> &str {
+        "ai_analysis_cache"
+    }
+
+    fn entries(&self) -> usize {
+        self.try_lock().map(|state| state.service.analysis_cache_len()).unwrap_or(0)
+    }
+
+    fn evict(&self, _level: MemoryPressure) -> usize {
+        self.try_lock().map(|mut state| state.service.clear_analysis_cache()).unwrap_or(0)
+    }
+}
+
 // ============================================
 // Suggestion Commands
 // ============================================
 
+/// Take one request from `key`'s bucket, or say when to retry
+async fn throttle(key: &str, what: &str) -> Result<(), String> {
+    let decision = KEYED_RATE_LIMITER.check(key, 1).await;
+    if !decision.allowed {
+        return Err(format!(
+            "{} is rate limited, try again in {} s",
+            what,
+            decision.retry_after_ms.div_ceil(1000)
+        ));
+    }
+    Ok(())
+}
+
+/// Read each file from the workspace and analyze it with the code-analysis
+/// model. Unchanged files are answered from the content-hash cache.
+async fn analyze_files(
+    app: &AppHandle,
+    state: &Arc<Mutex<AiEnhancementState>>,
+    cli_state: &Arc<Mutex<CliState>>,
+    project_id: &str,
+    file_paths: &[String],
+    kind: AnalysisKind,
+) -> Result<Vec<FileAnalysis>, String> {
+    let cli_service = Arc::clone(&cli_state.lock().await.service);
+
+    let mut results = Vec::new();
+    for path in file_paths {
+        let file = cli_service.read_file(path).await?;
+        let content_hash = ai_enhancement::content_hash(&file.content);
+        if file.is_binary {
+            results.push(FileAnalysis {
+                file: path.clone(),
+                content_hash,
+                findings: Vec::new(),
+                from_cache: false,
+                skipped: Some("Binary file".to_string()),
+            });
+            continue;
+        }
+
+        let cached = state.lock().await.service.cached_findings(kind, &content_hash, path);
+        let (findings, from_cache) = match cached {
+            Some(findings) => (findings, true),
+            None => {
+                let (llm_service, model_id, chunk_tokens) = llm_for_mode(app, CODE_ANALYSIS_MODE).await?;
+                let findings = ai_enhancement::analyze_source(
+                    llm_service.as_ref(),
+                    Some(&model_id),
+                    kind,
+                    path,
+                    &file.content,
+                    chunk_tokens,
+                ).await?;
+                state.lock().await.service.cache_findings(kind, &content_hash, &findings);
+                (findings, false)
+            }
+        };
+
+        state.lock().await.service.record_findings(project_id, kind, &findings);
+        results.push(FileAnalysis {
+            file: path.clone(),
+            content_hash,
+            findings,
+            from_cache,
+            skipped: None,
+        });
+    }
+
+    Ok(results)
+}
+
 #[tauri::command]
 pub async fn ai_analyze_code(
+    app: AppHandle,
     state: State<'_, Arc<Mutex<AiEnhancementState>>>,
+    cli_state: State<'_, Arc<Mutex<CliState>>>,
     project_id: String,
-    content: String,
-    file_path: String,
-) -> Result<Vec<Suggestion>, String> {
-    let mut state = state.lock().await;
-    Ok(state.service.analyze_code(&project_id, &content, &file_path))
+    file_paths: Vec<String>,
+) -> Result<Vec<FileAnalysis>, String> {
+    throttle(AI_ANALYZE_CODE_KEY, "Code analysis").await?;
+    analyze_files(&app, &state, &cli_state, &project_id, &file_paths, AnalysisKind::Review).await
+}
+
+#[tauri::command]
+pub async fn ai_predict_bugs(
+    app: AppHandle,
+    state: State<'_, Arc<Mutex<AiEnhancementState>>>,
+    cli_state: State<'_, Arc<Mutex<CliState>>>,
+    project_id: String,
+    file_paths: Vec<String>,
+) -> Result<Vec<FileAnalysis>, String> {
+    throttle(AI_PREDICT_BUGS_KEY, "Bug prediction").await?;
+    analyze_files(&app, &state, &cli_state, &project_id, &file_paths, AnalysisKind::Bugs).await
 }
 
 #[tauri::command]
@@ -92,14 +229,50 @@ pub async fn ai_get_completions(
 // Quality Analysis Commands
 // ============================================
 
+/// Analyze quality and add the report's metrics to the workspace's history,
+/// tagged with the current commit when the workspace project is a git repository
 #[tauri::command]
 pub async fn ai_analyze_quality(
     state: State<'_, Arc<Mutex<AiEnhancementState>>>,
+    workspace_state: State<'_, WorkspaceAppState>,
+    workspace_id: String,
     project_id: String,
     files: Vec<(String, String)>,
 ) -> Result<QualityReport, String> {
-    let mut state = state.lock().await;
-    Ok(state.service.analyze_quality(&project_id, &files))
+    let report = state.lock().await.service.analyze_quality(&project_id, &files);
+
+    let workspace = workspace_state.db_manager
+        .get_workspace(&workspace_id)
+        .map_err(|e| e.to_string())?;
+    let commit_sha = workspace_sync::project_git(&workspace).head_commit().ok().flatten();
+    workspace_state.data_ops
+        .record_quality_snapshot(&workspace_id, &report.snapshot(commit_sha))
+        .map_err(|e| e.to_string())?;
+
+    Ok(report)
+}
+
+/// Recorded quality metrics, oldest first, with changes between reports.
+/// A metric getting worse by more than `regression_threshold` percent is
+/// flagged as a regression.
+#[tauri::command]
+pub async fn ai_get_quality_trend(
+    workspace_state: State<'_, WorkspaceAppState>,
+    workspace_id: String,
+    project_id: String,
+    since: Option<i64>,
+    limit: Option<usize>,
+    regression_threshold: Option<f64>,
+) -> Result<Vec<QualityTrendPoint>, String> {
+    workspace_state.data_ops
+        .get_quality_trend(
+            &workspace_id,
+            &project_id,
+            since,
+            limit.unwrap_or(DEFAULT_QUALITY_TREND_LIMIT),
+            regression_threshold.unwrap_or(DEFAULT_QUALITY_REGRESSION_PCT),
+        )
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -117,13 +290,32 @@ pub async fn ai_get_quality_report(
 // Documentation Commands
 // ============================================
 
+/// Generate documentation for the undocumented items in a workspace file.
+/// The result is queued as a pending CLI suggestion; applying its hunks with
+/// `cli_apply_diff` writes the docs into the file.
 #[tauri::command]
-pub async fn ai_generate_documentation(
-    state: State<'_, Arc<Mutex<AiEnhancementState>>>,
-    request: DocumentationRequest,
-) -> Result<DocumentationResult, String> {
-    let state = state.lock().await;
-    Ok(state.service.generate_documentation(&request))
+pub async fn ai_generate_docs(
+    app: AppHandle,
+    cli_state: State<'_, Arc<Mutex<CliState>>>,
+    file_path: String,
+) -> Result<CodeSuggestion, String> {
+    let cli_service = Arc::clone(&cli_state.lock().await.service);
+    let file = cli_service.read_file(&file_path).await?;
+    let syntax = DocSyntax::for_language(&file.language)
+        .ok_or_else(|| format!("Documentation generation is not supported for {} files", file.language))?;
+
+    let (llm_service, model_id, chunk_tokens) = llm_for_mode(&app, DOCUMENTATION_MODE).await?;
+    let suggestion = ai_enhancement::generate_docs(
+        llm_service.as_ref(),
+        Some(&model_id),
+        &file_path,
+        &file.content,
+        syntax,
+        chunk_tokens,
+    ).await?;
+
+    cli_service.add_suggestion(suggestion.clone()).await;
+    Ok(suggestion)
 }
 
 // ============================================
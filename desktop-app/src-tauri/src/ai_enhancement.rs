@@ -3,14 +3,33 @@
 // Provides:
 // - Smart suggestions
 // - Code completion
-// - Bug prediction
+// - LLM code review and bug prediction anchored to line spans
 // - Quality analysis
-// - Auto-documentation
+// - Auto-documentation applied to source as reviewable diffs
 
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+use crate::cli_service::{compute_diff, CodeSuggestion, SuggestionStatus, DEFAULT_DIFF_CONTEXT};
+use crate::llm_service::{ChatCompletion, ChatMessage};
+use crate::workspace_data::QualitySnapshot;
+
+/// Context window assumed when the selected model is not in the catalogue
+pub const DEFAULT_CONTEXT_LENGTH: i32 = 16_000;
+
+/// Bounds on the source sent per request, in estimated tokens
+const MIN_CHUNK_TOKENS: usize = 500;
+const MAX_CHUNK_TOKENS: usize = 8_000;
+
+/// Tokens kept free for the instructions and the model's reply
+const PROMPT_OVERHEAD_TOKENS: i32 = 1_500;
+
+/// Lines repeated at the start of each chunk so issues spanning a boundary
+/// are seen whole by at least one request
+const CHUNK_OVERLAP_LINES: usize = 10;
+
 // ============================================
 // AI Enhancement Types
 // ============================================
@@ -45,7 +64,7 @@ pub enum SuggestionType {
     BestPractice,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Impact {
     Critical,
@@ -72,6 +91,74 @@ pub enum ActionType {
     CreateTask,
 }
 
+// ============================================
+// Code Analysis Types
+// ============================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisKind {
+    /// General review: correctness, security, performance, maintainability
+    Review,
+    /// Only code likely to misbehave at runtime
+    Bugs,
+}
+
+impl AnalysisKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnalysisKind::Review => "review",
+            AnalysisKind::Bugs => "bugs",
+        }
+    }
+
+    fn focus(&self) -> &'static str {
+        match self {
+            AnalysisKind::Review => "Report correctness, security, performance and maintainability problems.",
+            AnalysisKind::Bugs => "Report only code likely to fail at runtime: logic errors, crashes, unhandled errors, resource leaks, races and off-by-one mistakes.",
+        }
+    }
+
+    fn suggestion_type(&self) -> SuggestionType {
+        match self {
+            AnalysisKind::Review => SuggestionType::CodeImprovement,
+            AnalysisKind::Bugs => SuggestionType::BugPrediction,
+        }
+    }
+}
+
+/// A finding anchored to 1-based, inclusive line numbers in `file`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeFinding {
+    /// Derived from the file, span and message, so re-analysis yields the same id
+    pub id: String,
+    pub file: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub severity: Impact,
+    pub message: String,
+    pub suggested_fix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAnalysis {
+    pub file: String,
+    pub content_hash: String,
+    pub findings: Vec<CodeFinding>,
+    /// Served from the content-hash cache without calling the model
+    pub from_cache: bool,
+    /// Why the file was not analyzed, e.g. binary content
+    pub skipped: Option<String>,
+}
+
+/// A slice of a file as sent to the model, each line prefixed with its number
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeChunk {
+    pub start_line: u32,
+    pub end_line: u32,
+    pub text: String,
+}
+
 // ============================================
 // Code Completion Types
 // ============================================
@@ -178,54 +265,61 @@ pub struct QualityMetrics {
     pub lines_of_code: u32,
 }
 
+impl QualityReport {
+    /// Key metrics for the workspace's quality history
+    pub fn snapshot(&self, commit_sha: Option<String>) -> QualitySnapshot {
+        QualitySnapshot {
+            id: self.id.clone(),
+            project_id: self.project_id.clone(),
+            commit_sha,
+            overall_score: self.overall_score,
+            complexity: self.metrics.complexity,
+            maintainability: self.metrics.maintainability,
+            documentation_coverage: self.metrics.documentation_coverage,
+            code_coverage: self.metrics.code_coverage,
+            test_count: self.metrics.test_count,
+            issue_count: self.metrics.issue_count,
+            lines_of_code: self.metrics.lines_of_code,
+            created_at: self.created_at,
+        }
+    }
+}
+
 // ============================================
 // Auto-Documentation Types
 // ============================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DocumentationRequest {
-    pub content: String,
-    pub language: String,
-    pub doc_type: DocumentationType,
-    pub style: DocumentationStyle,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum DocumentationType {
-    Function,
-    Class,
-    Module,
-    Api,
-    Readme,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum DocumentationStyle {
-    Jsdoc,
-    Docstring,
-    Markdown,
-    Rustdoc,
-    Javadoc,
+/// Comment syntax used for generated documentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocSyntax {
+    /// `///` lines above the item
+    Rust,
+    /// `/** */` block above the item
+    JsDoc,
+    /// Docstring as the first statement of the body
+    Python,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DocumentationResult {
-    pub documentation: String,
-    pub summary: String,
-    pub parameters: Vec<ParameterDoc>,
-    pub returns: Option<String>,
-    pub examples: Vec<String>,
+impl DocSyntax {
+    /// Syntax for a language as reported by `CliService::read_file`
+    pub fn for_language(language: &str) -> Option<Self> {
+        match language {
+            "rust" => Some(DocSyntax::Rust),
+            "typescript" | "javascript" => Some(DocSyntax::JsDoc),
+            "python" => Some(DocSyntax::Python),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ParameterDoc {
-    pub name: String,
-    pub param_type: String,
-    pub description: String,
-    pub optional: bool,
-    pub default: Option<String>,
+/// An undocumented item and where its documentation goes
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocTarget {
+    /// 1-based line of the declaration
+    pub line: u32,
+    /// 0-based index of the line the documentation is inserted before
+    pub insert_at: usize,
+    pub indent: String,
 }
 
 // ============================================
@@ -236,6 +330,8 @@ pub struct AiEnhancementService {
     pub suggestions: HashMap<String, Vec<Suggestion>>,
     pub quality_reports: HashMap<String, QualityReport>,
     pub settings: AiSettings,
+    /// Findings by analysis kind and file content hash
+    analysis_cache: HashMap<(AnalysisKind, String), Vec<CodeFinding>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -274,6 +370,7 @@ impl AiEnhancementService {
             suggestions: HashMap::new(),
             quality_reports: HashMap::new(),
             settings: AiSettings::default(),
+            analysis_cache: HashMap::new(),
         }
     }
 
@@ -281,101 +378,75 @@ impl AiEnhancementService {
     // Suggestions
     // ============================================
 
-    pub fn analyze_code(&mut self, project_id: &str, content: &str, file_path: &str) -> Vec<Suggestion> {
-        let mut suggestions = Vec::new();
-        let now = chrono::Utc::now().timestamp();
+    /// Findings from an earlier analysis of identical content, re-pointed at
+    /// `file` in case the content has moved
+    pub fn cached_findings(&self, kind: AnalysisKind, content_hash: &str, file: &str) -> Option<Vec<CodeFinding>> {
+        let findings = self.analysis_cache.get(&(kind, content_hash.to_string()))?;
+        Some(findings.iter().map(|f| {
+            let mut finding = f.clone();
+            finding.file = file.to_string();
+            finding.id = finding_id(kind, &finding);
+            finding
+        }).collect())
+    }
 
-        // Simulate AI analysis - in production, this would call LLM
-        
-        // Check for common issues
-        if content.contains("TODO") {
-            suggestions.push(Suggestion {
-                id: Uuid::new_v4().to_string(),
-                suggestion_type: SuggestionType::Documentation,
-                title: "Unresolved TODO comment".to_string(),
-                description: "Found TODO comment that should be addressed".to_string(),
-                confidence: 0.95,
-                impact: Impact::Low,
-                code_snippet: None,
-                file_path: Some(file_path.to_string()),
-                line_range: None,
-                actions: vec![
-                    SuggestionAction {
-                        action_type: ActionType::CreateTask,
-                        label: "Create Task".to_string(),
-                        data: serde_json::json!({}),
-                    },
-                    SuggestionAction {
-                        action_type: ActionType::Dismiss,
-                        label: "Dismiss".to_string(),
-                        data: serde_json::json!({}),
-                    },
-                ],
-                created_at: now,
-                dismissed: false,
-                applied: false,
-            });
-        }
+    pub fn cache_findings(&mut self, kind: AnalysisKind, content_hash: &str, findings: &[CodeFinding]) {
+        self.analysis_cache.insert((kind, content_hash.to_string()), findings.to_vec());
+    }
 
-        if content.contains("console.log") || content.contains("println!") {
-            suggestions.push(Suggestion {
-                id: Uuid::new_v4().to_string(),
-                suggestion_type: SuggestionType::BestPractice,
-                title: "Debug statement found".to_string(),
-                description: "Consider removing debug statements before production".to_string(),
-                confidence: 0.85,
-                impact: Impact::Low,
-                code_snippet: None,
-                file_path: Some(file_path.to_string()),
-                line_range: None,
-                actions: vec![
-                    SuggestionAction {
-                        action_type: ActionType::ApplyFix,
-                        label: "Remove".to_string(),
-                        data: serde_json::json!({}),
-                    },
-                ],
-                created_at: now,
-                dismissed: false,
-                applied: false,
-            });
-        }
+    pub fn analysis_cache_len(&self) -> usize {
+        self.analysis_cache.len()
+    }
+
+    /// Drop all cached findings; returns how many were held
+    pub fn clear_analysis_cache(&mut self) -> usize {
+        let count = self.analysis_cache.len();
+        self.analysis_cache.clear();
+        count
+    }
 
-        if content.contains("password") && content.contains("=") {
+    /// Store findings as project suggestions. A finding seen before keeps its
+    /// suggestion, including whether it was dismissed or applied.
+    pub fn record_findings(&mut self, project_id: &str, kind: AnalysisKind, findings: &[CodeFinding]) {
+        let now = chrono::Utc::now().timestamp();
+        let suggestions = self.suggestions.entry(project_id.to_string()).or_default();
+
+        for finding in findings {
+            if suggestions.iter().any(|s| s.id == finding.id) {
+                continue;
+            }
+            let mut actions = vec![SuggestionAction {
+                action_type: ActionType::ViewDetails,
+                label: "View Details".to_string(),
+                data: serde_json::json!({
+                    "file": finding.file,
+                    "start_line": finding.start_line,
+                    "end_line": finding.end_line,
+                }),
+            }];
+            if let Some(fix) = &finding.suggested_fix {
+                actions.push(SuggestionAction {
+                    action_type: ActionType::ApplyFix,
+                    label: "Apply Fix".to_string(),
+                    data: serde_json::json!({ "fix": fix }),
+                });
+            }
             suggestions.push(Suggestion {
-                id: Uuid::new_v4().to_string(),
-                suggestion_type: SuggestionType::SecurityIssue,
-                title: "Potential hardcoded password".to_string(),
-                description: "Avoid hardcoding sensitive information. Use environment variables.".to_string(),
-                confidence: 0.75,
-                impact: Impact::High,
-                code_snippet: None,
-                file_path: Some(file_path.to_string()),
-                line_range: None,
-                actions: vec![
-                    SuggestionAction {
-                        action_type: ActionType::ViewDetails,
-                        label: "View Details".to_string(),
-                        data: serde_json::json!({}),
-                    },
-                    SuggestionAction {
-                        action_type: ActionType::LearnMore,
-                        label: "Learn More".to_string(),
-                        data: serde_json::json!({"url": "https://docs.example.com/security"}),
-                    },
-                ],
+                id: finding.id.clone(),
+                suggestion_type: kind.suggestion_type(),
+                title: finding.message.lines().next().unwrap_or_default().to_string(),
+                description: finding.message.clone(),
+                confidence: 1.0,
+                impact: finding.severity,
+                code_snippet: finding.suggested_fix.clone(),
+                file_path: Some(finding.file.clone()),
+                line_range: Some((finding.start_line, finding.end_line)),
+                actions,
                 created_at: now,
                 dismissed: false,
                 applied: false,
             });
         }
-
-        // Store suggestions
-        self.suggestions.entry(project_id.to_string())
-            .or_insert_with(Vec::new)
-            .extend(suggestions.clone());
-
-        suggestions
     }
 
     pub fn get_suggestions(&self, project_id: &str) -> Vec<&Suggestion> {
@@ -480,6 +551,7 @@ impl AiEnhancementService {
             }
         }
 
+        let issue_count = issues.len() as u32;
         let report = QualityReport {
             id: Uuid::new_v4().to_string(),
             project_id: project_id.to_string(),
@@ -523,7 +595,7 @@ impl AiEnhancementService {
                 maintainability: 82.0,
                 documentation_coverage: 65.0,
                 test_count: 45,
-                issue_count: 0,
+                issue_count,
                 lines_of_code: total_lines,
             },
             created_at: now,
@@ -537,26 +609,6 @@ impl AiEnhancementService {
         self.quality_reports.get(project_id)
     }
 
-    // ============================================
-    // Auto-Documentation
-    // ============================================
-
-    pub fn generate_documentation(&self, request: &DocumentationRequest) -> DocumentationResult {
-        // Simulate documentation generation - in production, this would call LLM
-        DocumentationResult {
-            documentation: format!(
-                "/**\n * Auto-generated documentation\n * \n * @description This is a placeholder documentation.\n */\n{}",
-                request.content
-            ),
-            summary: "Auto-generated documentation for the provided code.".to_string(),
-            parameters: vec![],
-            returns: Some("The result of the operation".to_string()),
-            examples: vec![
-                "// Example usage\nconst result = myFunction();".to_string(),
-            ],
-        }
-    }
-
     // ============================================
     // Settings
     // ============================================
@@ -569,3 +621,857 @@ impl AiEnhancementService {
         self.settings = settings;
     }
 }
+
+// ============================================
+// Code Analysis
+// ============================================
+
+/// Send the instructions and prompt as one exchange and return the reply text
+async fn complete(
+    llm: &dyn ChatCompletion,
+    model_id: Option<&str>,
+    system: &str,
+    prompt: String,
+) -> anyhow::Result<String> {
+    let message = |role: &str, content: String| ChatMessage {
+        role: role.to_string(),
+        content,
+        tool_calls: None,
+        tool_call_id: None,
+    };
+    let response = llm.chat(
+        vec![message("system", system.to_string()), message("user", prompt)],
+        model_id,
+        Some(0.0),
+        None,
+    ).await?;
+    Ok(response.choices.into_iter().next().map(|c| c.message.content).unwrap_or_default())
+}
+
+/// The outermost JSON array in a reply, ignoring code fences or prose around it
+fn json_array(reply: &str) -> Option<&str> {
+    match (reply.find('['), reply.rfind(']')) {
+        (Some(start), Some(end)) if start < end => Some(&reply[start..=end]),
+        _ => None,
+    }
+}
+
+pub fn content_hash(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+/// Source tokens to send per request for a model with `context_length`,
+/// leaving the rest of the window for instructions and the reply
+pub fn chunk_token_budget(context_length: i32) -> usize {
+    let budget = (context_length / 2 - PROMPT_OVERHEAD_TOKENS).max(0) as usize;
+    budget.clamp(MIN_CHUNK_TOKENS, MAX_CHUNK_TOKENS)
+}
+
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Split `content` into line-numbered chunks of roughly `max_tokens`, each
+/// starting `overlap_lines` before the previous one ended. A single line
+/// longer than the budget gets a chunk of its own.
+pub fn chunk_source(content: &str, max_tokens: usize, overlap_lines: usize) -> Vec<CodeChunk> {
+    let lines: Vec<String> = content.lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:>5}| {}\n", i + 1, line))
+        .collect();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start;
+        let mut tokens = 0;
+        while end < lines.len() {
+            let line_tokens = estimate_tokens(&lines[end]);
+            if end > start && tokens + line_tokens > max_tokens {
+                break;
+            }
+            tokens += line_tokens;
+            end += 1;
+        }
+
+        chunks.push(CodeChunk {
+            start_line: start as u32 + 1,
+            end_line: end as u32,
+            text: lines[start..end].concat(),
+        });
+        if end == lines.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap_lines).max(start + 1);
+    }
+    chunks
+}
+
+fn finding_id(kind: AnalysisKind, finding: &CodeFinding) -> String {
+    let key = format!(
+        "{}\n{}\n{}\n{}\n{}",
+        kind.as_str(),
+        finding.file,
+        finding.start_line,
+        finding.end_line,
+        normalize_message(&finding.message)
+    );
+    content_hash(&key)[..16].to_string()
+}
+
+fn normalize_message(message: &str) -> String {
+    message.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Lower is more severe
+fn severity_rank(severity: Impact) -> u8 {
+    match severity {
+        Impact::Critical => 0,
+        Impact::High => 1,
+        Impact::Medium => 2,
+        Impact::Low => 3,
+        Impact::Info => 4,
+    }
+}
+
+#[derive(Deserialize)]
+struct RawFinding {
+    start_line: u32,
+    end_line: Option<u32>,
+    severity: Option<String>,
+    message: String,
+    suggested_fix: Option<String>,
+}
+
+/// Findings in the model reply. Those starting outside the chunk are dropped
+/// and ends are clamped to it.
+fn parse_findings(reply: &str, chunk: &CodeChunk, file: &str) -> Result<Vec<CodeFinding>, String> {
+    let json = json_array(reply)
+        .ok_or_else(|| "Model reply did not contain a JSON array of findings".to_string())?;
+    let raw: Vec<RawFinding> = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse model findings: {}", e))?;
+
+    Ok(raw.into_iter()
+        .filter(|f| (chunk.start_line..=chunk.end_line).contains(&f.start_line) && !f.message.trim().is_empty())
+        .map(|f| CodeFinding {
+            id: String::new(),
+            file: file.to_string(),
+            start_line: f.start_line,
+            end_line: f.end_line.unwrap_or(f.start_line).clamp(f.start_line, chunk.end_line),
+            severity: match f.severity.as_deref().map(str::to_lowercase).as_deref() {
+                Some("critical") => Impact::Critical,
+                Some("high") => Impact::High,
+                Some("low") => Impact::Low,
+                Some("info") => Impact::Info,
+                _ => Impact::Medium,
+            },
+            message: f.message.trim().to_string(),
+            suggested_fix: f.suggested_fix.filter(|fix| !fix.trim().is_empty()),
+        })
+        .collect())
+}
+
+/// Combine findings from all chunks. Overlapping findings are the same issue
+/// when they say the same thing or cover exactly the same lines; the merged
+/// finding spans both at the higher severity.
+pub fn merge_findings(kind: AnalysisKind, mut findings: Vec<CodeFinding>) -> Vec<CodeFinding> {
+    findings.sort_by_key(|f| (f.start_line, f.end_line));
+
+    let mut merged: Vec<CodeFinding> = Vec::new();
+    for finding in findings {
+        let duplicate = merged.iter_mut().find(|m| {
+            m.file == finding.file
+                && m.start_line <= finding.end_line
+                && finding.start_line <= m.end_line
+                && ((m.start_line, m.end_line) == (finding.start_line, finding.end_line)
+                    || normalize_message(&m.message) == normalize_message(&finding.message))
+        });
+        match duplicate {
+            Some(existing) => {
+                existing.end_line = existing.end_line.max(finding.end_line);
+                if severity_rank(finding.severity) < severity_rank(existing.severity) {
+                    existing.severity = finding.severity;
+                }
+                if existing.suggested_fix.is_none() {
+                    existing.suggested_fix = finding.suggested_fix;
+                }
+            }
+            None => merged.push(finding),
+        }
+    }
+
+    for finding in &mut merged {
+        finding.id = finding_id(kind, finding);
+    }
+    merged
+}
+
+/// Analyze one file with the model, one request per chunk
+pub async fn analyze_source(
+    llm: &dyn ChatCompletion,
+    model_id: Option<&str>,
+    kind: AnalysisKind,
+    file: &str,
+    content: &str,
+    chunk_tokens: usize,
+) -> Result<Vec<CodeFinding>, String> {
+    let total_lines = content.lines().count();
+    let system = format!(
+        "You analyze source code. {} Reply with only a JSON array. Each element is an object with \
+         \"start_line\" and \"end_line\" (the numbers shown before each line, inclusive), \
+         \"severity\" (critical, high, medium, low or info), \"message\", and \"suggested_fix\" \
+         (replacement code, or null). Reply [] when there is nothing to report.",
+        kind.focus()
+    );
+    let mut findings = Vec::new();
+    for chunk in chunk_source(content, chunk_tokens, CHUNK_OVERLAP_LINES) {
+        let prompt = format!(
+            "File: {}\nLines {}-{} of {}\n\n{}",
+            file, chunk.start_line, chunk.end_line, total_lines, chunk.text
+        );
+        let reply = complete(llm, model_id, &system, prompt)
+            .await
+            .map_err(|e| format!("Analysis of {} failed: {}", file, e))?;
+        findings.extend(parse_findings(&reply, &chunk, file)?);
+    }
+
+    Ok(merge_findings(kind, findings))
+}
+
+// ============================================
+// Auto-Documentation
+// ============================================
+
+/// Lines of each item shown to the model, starting at its declaration
+const DOC_SNIPPET_LINES: usize = 20;
+
+fn leading_indent(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Remove any of `prefixes` from the start of `text`, repeatedly and in any order
+fn strip_prefixes<'a>(mut text: &'a str, prefixes: &[&str]) -> &'a str {
+    loop {
+        let before = text;
+        for prefix in prefixes {
+            if let Some(rest) = text.strip_prefix(prefix) {
+                text = rest.trim_start();
+            }
+        }
+        if text == before {
+            return text;
+        }
+    }
+}
+
+fn is_rust_item(trimmed: &str) -> bool {
+    let rest = strip_prefixes(trimmed, &["pub(crate) ", "pub(super) ", "pub ", "async ", "unsafe ", "extern \"C\" "]);
+    ["fn ", "struct ", "enum ", "trait ", "type ", "mod ", "const ", "static ", "union "]
+        .iter()
+        .any(|keyword| rest.starts_with(keyword))
+}
+
+/// Declarations, classes and top-level functions bound to a name. Other
+/// indented bindings are usually locals and are left alone.
+fn is_js_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let rest = strip_prefixes(trimmed, &["export ", "default ", "declare ", "abstract ", "async "]);
+    if ["function ", "function*", "class ", "interface ", "enum "].iter().any(|keyword| rest.starts_with(keyword)) {
+        return true;
+    }
+    if rest.starts_with("type ") {
+        return rest.contains('=');
+    }
+    if !leading_indent(line).is_empty() && !trimmed.starts_with("export ") {
+        return false;
+    }
+    let value = ["const ", "let "].iter()
+        .find_map(|keyword| rest.strip_prefix(keyword))
+        .and_then(|binding| binding.split_once('='))
+        .map(|(_, value)| value.trim_start());
+    match value {
+        Some(value) => {
+            value.starts_with("async") || value.starts_with("function")
+                || ((value.starts_with('(') || value.starts_with('<')) && rest.contains("=>"))
+        }
+        None => false,
+    }
+}
+
+/// Items above which documentation goes: walk up over attributes or
+/// decorators and check the line above them for an existing comment
+fn doc_target_above(lines: &[&str], index: usize, syntax: DocSyntax) -> Option<DocTarget> {
+    let attribute = if syntax == DocSyntax::Rust { "#[" } else { "@" };
+    let mut insert_at = index;
+    while insert_at > 0 && lines[insert_at - 1].trim_start().starts_with(attribute) {
+        insert_at -= 1;
+    }
+
+    if syntax == DocSyntax::Rust
+        && lines[insert_at..index].iter().any(|l| l.contains("#[doc") || l.contains("test]"))
+    {
+        return None;
+    }
+    if insert_at > 0 {
+        let above = lines[insert_at - 1].trim();
+        let documented = match syntax {
+            DocSyntax::Rust => above.starts_with("///") || above.ends_with("*/"),
+            _ => above.ends_with("*/") || above.starts_with("//"),
+        };
+        if documented {
+            return None;
+        }
+    }
+
+    Some(DocTarget {
+        line: index as u32 + 1,
+        insert_at,
+        indent: leading_indent(lines[index]).to_string(),
+    })
+}
+
+/// Python docstrings go after the signature, which may span several lines.
+/// One-line bodies and bodies that already start with a string are skipped.
+fn python_doc_target(lines: &[&str], index: usize) -> Option<DocTarget> {
+    let mut depth = 0i32;
+    let mut end = index;
+    loop {
+        for c in lines[end].chars() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth <= 0 {
+            break;
+        }
+        end += 1;
+        if end == lines.len() {
+            return None;
+        }
+    }
+    if !lines[end].trim_end().ends_with(':') || !lines[end].ends_with('\n') {
+        return None;
+    }
+
+    let body = lines[end + 1..].iter().find(|l| !l.trim().is_empty())?;
+    let body_text = body.trim_start();
+    if ["\"", "'", "r\"", "r'"].iter().any(|quote| body_text.starts_with(quote)) {
+        return None;
+    }
+    let def_indent = leading_indent(lines[index]);
+    let body_indent = leading_indent(body);
+    let indent = if body_indent.len() > def_indent.len() {
+        body_indent.to_string()
+    } else {
+        format!("{}    ", def_indent)
+    };
+
+    Some(DocTarget {
+        line: index as u32 + 1,
+        insert_at: end + 1,
+        indent,
+    })
+}
+
+/// Find undocumented items. Rust test modules, conventionally at the end of
+/// the file under a top-level `#[cfg(test)]`, are not documented.
+pub fn find_doc_targets(content: &str, syntax: DocSyntax) -> Vec<DocTarget> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut targets = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let target = match syntax {
+            DocSyntax::Rust => {
+                if line.trim_end() == "#[cfg(test)]" {
+                    break;
+                }
+                if !is_rust_item(trimmed) {
+                    continue;
+                }
+                doc_target_above(&lines, index, syntax)
+            }
+            DocSyntax::JsDoc => {
+                if !is_js_item(line) {
+                    continue;
+                }
+                doc_target_above(&lines, index, syntax)
+            }
+            DocSyntax::Python => {
+                let rest = strip_prefixes(trimmed, &["async "]);
+                if !rest.starts_with("def ") && !rest.starts_with("class ") {
+                    continue;
+                }
+                python_doc_target(&lines, index)
+            }
+        };
+        targets.extend(target);
+    }
+    targets
+}
+
+/// Strip comment markers the model may have added despite instructions
+fn clean_doc_text(text: &str) -> String {
+    let lines: Vec<&str> = text.lines()
+        .map(|line| {
+            let line = strip_prefixes(line.trim(), &["///", "/**", "//", "\"\"\"", "'''"]);
+            let line = line.strip_suffix("*/").unwrap_or(line);
+            let line = line.strip_suffix("\"\"\"").unwrap_or(line);
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.trim()
+        })
+        .collect();
+
+    let start = lines.iter().position(|l| !l.is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|l| !l.is_empty()).map_or(start, |i| i + 1);
+    lines[start..end].join("\n")
+}
+
+/// Comment lines for `text` in `syntax`, without line endings
+fn render_doc(syntax: DocSyntax, indent: &str, text: &str) -> Vec<String> {
+    let text_lines: Vec<&str> = text.lines().collect();
+    match syntax {
+        DocSyntax::Rust => text_lines.iter()
+            .map(|l| if l.is_empty() { format!("{}///", indent) } else { format!("{}/// {}", indent, l) })
+            .collect(),
+        DocSyntax::JsDoc => {
+            let escaped: Vec<String> = text_lines.iter().map(|l| l.replace("*/", "*\\/")).collect();
+            if escaped.len() == 1 {
+                return vec![format!("{}/** {} */", indent, escaped[0])];
+            }
+            let mut out = vec![format!("{}/**", indent)];
+            out.extend(escaped.iter().map(|l| {
+                if l.is_empty() { format!("{} *", indent) } else { format!("{} * {}", indent, l) }
+            }));
+            out.push(format!("{} */", indent));
+            out
+        }
+        DocSyntax::Python => {
+            let escaped: Vec<String> = text_lines.iter()
+                .map(|l| l.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\""))
+                .collect();
+            if escaped.len() == 1 && !escaped[0].ends_with('"') {
+                return vec![format!("{}\"\"\"{}\"\"\"", indent, escaped[0])];
+            }
+            let mut out = vec![format!("{}\"\"\"{}", indent, escaped[0])];
+            out.extend(escaped[1..].iter().map(|l| {
+                if l.is_empty() { String::new() } else { format!("{}{}", indent, l) }
+            }));
+            out.push(format!("{}\"\"\"", indent));
+            out
+        }
+    }
+}
+
+/// Insert rendered documentation for each target. Existing lines are copied
+/// through unchanged; inserted lines use the file's line ending.
+pub fn insert_docs(content: &str, syntax: DocSyntax, docs: &[(DocTarget, String)]) -> String {
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut inserts: HashMap<usize, Vec<String>> = HashMap::new();
+    for (target, text) in docs {
+        inserts.entry(target.insert_at)
+            .or_default()
+            .extend(render_doc(syntax, &target.indent, text));
+    }
+
+    let mut output = String::with_capacity(content.len());
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        for doc_line in inserts.get(&index).into_iter().flatten() {
+            output.push_str(doc_line);
+            output.push_str(newline);
+        }
+        output.push_str(line);
+    }
+    output
+}
+
+#[derive(Deserialize)]
+struct RawDoc {
+    line: u32,
+    doc: String,
+}
+
+/// Document every undocumented item in a file and return the change as a
+/// pending suggestion for review. Items are sent to the model in batches
+/// that fit `chunk_tokens`.
+pub async fn generate_docs(
+    llm: &dyn ChatCompletion,
+    model_id: Option<&str>,
+    file: &str,
+    content: &str,
+    syntax: DocSyntax,
+    chunk_tokens: usize,
+) -> Result<CodeSuggestion, String> {
+    let targets = find_doc_targets(content, syntax);
+    if targets.is_empty() {
+        return Err(format!("No undocumented items found in {}", file));
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut batches: Vec<Vec<String>> = Vec::new();
+    let mut batch: Vec<String> = Vec::new();
+    let mut batch_tokens = 0;
+    for target in &targets {
+        let start = target.line as usize - 1;
+        let snippet: String = lines[start..(start + DOC_SNIPPET_LINES).min(lines.len())]
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("{:>5}| {}\n", start + i + 1, line))
+            .collect();
+        let item = format!("Item at line {}:\n{}", target.line, snippet);
+        let item_tokens = estimate_tokens(&item);
+
+        if !batch.is_empty() && batch_tokens + item_tokens > chunk_tokens {
+            batches.push(std::mem::take(&mut batch));
+            batch_tokens = 0;
+        }
+        batch.push(item);
+        batch_tokens += item_tokens;
+    }
+    batches.push(batch);
+
+    let system = "You write documentation for source code. Reply with only a JSON array containing, \
+                  for each item you are shown, an object with \"line\" (the item's line number) and \
+                  \"doc\" (the documentation as plain text, without comment markers or indentation). \
+                  Describe what the item does and, where useful, its parameters and return value.";
+
+    let mut docs: Vec<(DocTarget, String)> = Vec::new();
+    for batch in batches {
+        let prompt = format!("File: {}\n\n{}", file, batch.join("\n"));
+        let reply = complete(llm, model_id, system, prompt)
+            .await
+            .map_err(|e| format!("Documentation for {} failed: {}", file, e))?;
+        let json = json_array(&reply)
+            .ok_or_else(|| "Model reply did not contain a JSON array of docs".to_string())?;
+        let raw: Vec<RawDoc> = serde_json::from_str(json)
+            .map_err(|e| format!("Failed to parse model docs: {}", e))?;
+
+        for doc in raw {
+            let text = clean_doc_text(&doc.doc);
+            let target = targets.iter().find(|t| t.line == doc.line);
+            if let Some(target) = target.filter(|t| !text.is_empty() && !docs.iter().any(|(d, _)| d == *t)) {
+                docs.push((target.clone(), text));
+            }
+        }
+    }
+    if docs.is_empty() {
+        return Err(format!("Model returned no documentation for {}", file));
+    }
+
+    let modified = insert_docs(content, syntax, &docs);
+    Ok(CodeSuggestion {
+        id: Uuid::new_v4().to_string(),
+        file_path: file.to_string(),
+        original_content: Some(content.to_string()),
+        diff_hunks: compute_diff(content, &modified, DEFAULT_DIFF_CONTEXT),
+        suggested_content: modified,
+        description: format!(
+            "Add documentation to {} item{}",
+            docs.len(),
+            if docs.len() == 1 { "" } else { "s" }
+        ),
+        status: SuggestionStatus::Pending,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_service::{apply_diff, DiffLineType};
+    use crate::llm_service::{ChatChoice, ChatResponse};
+    use anyhow::Result;
+    use std::sync::Mutex;
+
+    /// Answers each prompt with `respond` and records the prompts it was sent
+    struct StubLlm {
+        respond: fn(&str) -> String,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl StubLlm {
+        fn new(respond: fn(&str) -> String) -> Self {
+            Self { respond, prompts: Mutex::new(Vec::new()) }
+        }
+    }
+
+    /// Reports every numbered line containing `unwrap()`
+    fn unwrap_findings(prompt: &str) -> String {
+        let findings: Vec<serde_json::Value> = prompt.lines()
+            .filter_map(|line| line.split_once("| "))
+            .filter(|(_, code)| code.contains("unwrap()"))
+            .map(|(number, _)| {
+                let line: u32 = number.trim().parse().unwrap();
+                serde_json::json!({
+                    "start_line": line,
+                    "end_line": line + 1,
+                    "severity": "high",
+                    "message": "Unwrap may panic",
+                    "suggested_fix": "?",
+                })
+            })
+            .collect();
+        format!("```json\n{}\n```", serde_json::Value::from(findings))
+    }
+
+    /// Documents every item it is shown, with a two-paragraph doc
+    fn document_items(prompt: &str) -> String {
+        let docs: Vec<serde_json::Value> = prompt.lines()
+            .filter_map(|line| line.strip_prefix("Item at line "))
+            .map(|rest| {
+                let line: u32 = rest.trim_end_matches(':').parse().unwrap();
+                let doc = if line.is_multiple_of(2) {
+                    format!("Item {}.\n\nMore detail.", line)
+                } else {
+                    format!("Item {}.", line)
+                };
+                serde_json::json!({ "line": line, "doc": doc })
+            })
+            .collect();
+        serde_json::Value::from(docs).to_string()
+    }
+
+    #[async_trait::async_trait]
+    impl ChatCompletion for StubLlm {
+        async fn chat(
+            &self,
+            messages: Vec<ChatMessage>,
+            _model_id: Option<&str>,
+            _temperature: Option<f64>,
+            _max_tokens: Option<i32>,
+        ) -> Result<ChatResponse> {
+            let prompt = messages.last().unwrap().content.clone();
+            let reply = (self.respond)(&prompt);
+            self.prompts.lock().unwrap().push(prompt);
+
+            Ok(ChatResponse {
+                id: "stub".to_string(),
+                model: "stub".to_string(),
+                choices: vec![ChatChoice {
+                    index: 0,
+                    message: ChatMessage {
+                        role: "assistant".to_string(),
+                        content: reply,
+                        tool_calls: None,
+                        tool_call_id: None,
+                    },
+                    finish_reason: Some("stop".to_string()),
+                }],
+                usage: None,
+            })
+        }
+
+        async fn has_api_key(&self) -> bool {
+            true
+        }
+    }
+
+    fn source(lines: usize, unwrap_at: &[usize]) -> String {
+        (1..=lines)
+            .map(|i| if unwrap_at.contains(&i) {
+                format!("let v{} = parse(input).unwrap();", i)
+            } else {
+                format!("let v{} = compute(input, {});", i, i)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_chunks_cover_file_with_overlap() {
+        let content = source(300, &[]);
+        let chunks = chunk_source(&content, MIN_CHUNK_TOKENS, CHUNK_OVERLAP_LINES);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].start_line, 1);
+        assert_eq!(chunks.last().unwrap().end_line, 300);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[1].start_line, pair[0].end_line - CHUNK_OVERLAP_LINES as u32 + 1);
+        }
+        assert!(chunks[1].text.starts_with(&format!("{:>5}| ", chunks[1].start_line)));
+        assert!(chunk_source("", MIN_CHUNK_TOKENS, CHUNK_OVERLAP_LINES).is_empty());
+
+        assert_eq!(chunk_token_budget(4_000), MIN_CHUNK_TOKENS);
+        assert_eq!(chunk_token_budget(1_000_000), MAX_CHUNK_TOKENS);
+    }
+
+    #[tokio::test]
+    async fn test_findings_map_to_file_lines_across_chunks() {
+        let llm = StubLlm::new(unwrap_findings);
+        let first_overlap = chunk_source(&source(300, &[]), MIN_CHUNK_TOKENS, CHUNK_OVERLAP_LINES)[0].end_line as usize;
+        let content = source(300, &[3, first_overlap, 290]);
+
+        let findings = analyze_source(&llm, None, AnalysisKind::Bugs, "src/lib.rs", &content, MIN_CHUNK_TOKENS)
+            .await
+            .unwrap();
+        assert!(llm.prompts.lock().unwrap().len() > 1);
+
+        // The finding on the overlapping line is reported by two chunks but kept once
+        let spans: Vec<(u32, u32)> = findings.iter().map(|f| (f.start_line, f.end_line)).collect();
+        assert_eq!(spans, vec![(3, 4), (first_overlap as u32, first_overlap as u32 + 1), (290, 291)]);
+        assert!(findings.iter().all(|f| f.file == "src/lib.rs" && f.severity == Impact::High));
+        assert_eq!(findings[0].suggested_fix.as_deref(), Some("?"));
+
+        let again = analyze_source(&llm, None, AnalysisKind::Bugs, "src/lib.rs", &content, MIN_CHUNK_TOKENS)
+            .await
+            .unwrap();
+        assert_eq!(findings, again);
+        let review = analyze_source(&llm, None, AnalysisKind::Review, "src/lib.rs", &content, MIN_CHUNK_TOKENS)
+            .await
+            .unwrap();
+        assert_ne!(findings[0].id, review[0].id);
+    }
+
+    #[test]
+    fn test_parse_findings_clamps_to_chunk() {
+        let chunk = CodeChunk { start_line: 10, end_line: 20, text: String::new() };
+        let reply = r#"Here you go: [
+            {"start_line": 12, "end_line": 40, "severity": "CRITICAL", "message": "a"},
+            {"start_line": 15, "message": "b", "suggested_fix": ""},
+            {"start_line": 5, "end_line": 11, "message": "outside"}
+        ]"#;
+        let findings = parse_findings(reply, &chunk, "f.rs").unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!((findings[0].start_line, findings[0].end_line, findings[0].severity), (12, 20, Impact::Critical));
+        assert_eq!((findings[1].start_line, findings[1].end_line, findings[1].severity), (15, 15, Impact::Medium));
+        assert!(findings[1].suggested_fix.is_none());
+        assert!(parse_findings("no findings", &chunk, "f.rs").is_err());
+    }
+
+    #[test]
+    fn test_merge_keeps_union_and_worst_severity() {
+        let finding = |start, end, severity, message: &str| CodeFinding {
+            id: String::new(),
+            file: "f.rs".to_string(),
+            start_line: start,
+            end_line: end,
+            severity,
+            message: message.to_string(),
+            suggested_fix: None,
+        };
+        let merged = merge_findings(AnalysisKind::Review, vec![
+            finding(5, 8, Impact::Low, "Slow  loop"),
+            finding(7, 12, Impact::High, "slow loop"),
+            finding(7, 9, Impact::Low, "Unrelated"),
+            finding(20, 20, Impact::Info, "x"),
+            finding(20, 20, Impact::Medium, "y"),
+        ]);
+        let summary: Vec<(u32, u32, Impact)> = merged.iter().map(|f| (f.start_line, f.end_line, f.severity)).collect();
+        assert_eq!(summary, vec![(5, 12, Impact::High), (7, 9, Impact::Low), (20, 20, Impact::Medium)]);
+        assert!(merged.iter().all(|f| f.id.len() == 16));
+    }
+
+    #[tokio::test]
+    async fn test_cache_and_suggestions_keep_stable_ids() {
+        let llm = StubLlm::new(unwrap_findings);
+        let content = source(20, &[4]);
+        let hash = content_hash(&content);
+        let mut service = AiEnhancementService::new();
+        assert!(service.cached_findings(AnalysisKind::Bugs, &hash, "a.rs").is_none());
+
+        let findings = analyze_source(&llm, None, AnalysisKind::Bugs, "a.rs", &content, MAX_CHUNK_TOKENS)
+            .await
+            .unwrap();
+        service.cache_findings(AnalysisKind::Bugs, &hash, &findings);
+        assert_eq!(service.cached_findings(AnalysisKind::Bugs, &hash, "a.rs").unwrap(), findings);
+        assert!(service.cached_findings(AnalysisKind::Review, &hash, "a.rs").is_none());
+
+        // Same content at a new path is served from cache with ids for that path
+        let moved = service.cached_findings(AnalysisKind::Bugs, &hash, "b.rs").unwrap();
+        assert_eq!(moved[0].file, "b.rs");
+        assert_ne!(moved[0].id, findings[0].id);
+
+        service.record_findings("p", AnalysisKind::Bugs, &findings);
+        service.dismiss_suggestion("p", &findings[0].id).unwrap();
+        service.record_findings("p", AnalysisKind::Bugs, &findings);
+        assert_eq!(service.suggestions["p"].len(), 1);
+        assert!(service.get_suggestions("p").is_empty());
+        assert_eq!(service.suggestions["p"][0].line_range, Some((4, 5)));
+    }
+
+    fn assert_only_adds_lines(original: &str, suggestion: &CodeSuggestion) {
+        assert_eq!(apply_diff(original, &suggestion.diff_hunks).unwrap(), suggestion.suggested_content);
+        assert!(suggestion.diff_hunks.iter()
+            .flat_map(|h| &h.lines)
+            .all(|l| l.line_type != DiffLineType::Deletion));
+    }
+
+    #[test]
+    fn test_rust_targets_skip_documented_and_tests() {
+        let content = "use std::fmt;\n\n/// Already documented\npub fn documented() {}\n\n#[derive(Debug)]\n#[serde(rename_all = \"snake_case\")]\npub struct Point {\n    x: i32,\n}\n\nimpl Point {\n    pub async fn new() -> Self {\n        Self { x: 0 }\n    }\n}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn t() {}\n}\n";
+        let targets = find_doc_targets(content, DocSyntax::Rust);
+        let summary: Vec<(u32, usize, &str)> = targets.iter().map(|t| (t.line, t.insert_at, t.indent.as_str())).collect();
+        assert_eq!(summary, vec![(8, 5, ""), (13, 12, "    ")]);
+    }
+
+    #[test]
+    fn test_js_targets_cover_declarations_not_locals() {
+        let content = "import x from 'y';\n\n/** Existing */\nexport function a() {}\n\n@Component({})\nexport class Widget {\n  method() {}\n}\n\nexport const handler = async (req: Request) => {\n  const inner = () => 1;\n};\n\nconst value = compute(1);\ntype Alias = string;\nexport interface Props {\n  name: string;\n}\n";
+        let lines: Vec<u32> = find_doc_targets(content, DocSyntax::JsDoc).iter().map(|t| t.line).collect();
+        assert_eq!(lines, vec![7, 11, 16, 17]);
+        assert_eq!(find_doc_targets(content, DocSyntax::JsDoc)[0].insert_at, 5);
+    }
+
+    #[tokio::test]
+    async fn test_python_docstrings_follow_signature_and_keep_crlf() {
+        let content = "class Service:\r\n    \"\"\"Existing.\"\"\"\r\n\r\n    def run(self,\r\n            job):\r\n        return job\r\n\r\n    def short(self): return 1\r\n\r\nasync def main():\r\n    await Service().run(1)";
+        let targets = find_doc_targets(content, DocSyntax::Python);
+        let summary: Vec<(u32, usize, &str)> = targets.iter().map(|t| (t.line, t.insert_at, t.indent.as_str())).collect();
+        assert_eq!(summary, vec![(4, 5, "        "), (10, 10, "    ")]);
+
+        let llm = StubLlm::new(document_items);
+        let suggestion = generate_docs(&llm, None, "svc.py", content, DocSyntax::Python, MAX_CHUNK_TOKENS)
+            .await
+            .unwrap();
+        assert_only_adds_lines(content, &suggestion);
+        assert_eq!(
+            suggestion.suggested_content,
+            "class Service:\r\n    \"\"\"Existing.\"\"\"\r\n\r\n    def run(self,\r\n            job):\r\n        \"\"\"Item 4.\r\n\r\n        More detail.\r\n        \"\"\"\r\n        return job\r\n\r\n    def short(self): return 1\r\n\r\nasync def main():\r\n    \"\"\"Item 10.\r\n\r\n    More detail.\r\n    \"\"\"\r\n    await Service().run(1)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generated_docs_apply_cleanly() {
+        let rust = "#[derive(Debug)]\npub struct A;\n\nimpl A {\n    fn run(&self) {}\n}\n";
+        let ts = "export function a() {}\nexport class B {}\n";
+
+        for (content, syntax) in [(rust, DocSyntax::Rust), (ts, DocSyntax::JsDoc)] {
+            let llm = StubLlm::new(document_items);
+            // A tiny budget sends one item per request
+            let suggestion = generate_docs(&llm, None, "f", content, syntax, 1).await.unwrap();
+            assert_eq!(llm.prompts.lock().unwrap().len(), 2);
+            assert_eq!(suggestion.status, SuggestionStatus::Pending);
+            assert_eq!(suggestion.original_content.as_deref(), Some(content));
+            assert_only_adds_lines(content, &suggestion);
+        }
+
+        let llm = StubLlm::new(document_items);
+        let rust_docs = generate_docs(&llm, None, "a.rs", rust, DocSyntax::Rust, MAX_CHUNK_TOKENS).await.unwrap();
+        assert_eq!(
+            rust_docs.suggested_content,
+            "/// Item 2.\n///\n/// More detail.\n#[derive(Debug)]\npub struct A;\n\nimpl A {\n    /// Item 5.\n    fn run(&self) {}\n}\n"
+        );
+        let ts_docs = generate_docs(&llm, None, "a.ts", ts, DocSyntax::JsDoc, MAX_CHUNK_TOKENS).await.unwrap();
+        assert_eq!(
+            ts_docs.suggested_content,
+            "/** Item 1. */\nexport function a() {}\n/**\n * Item 2.\n *\n * More detail.\n */\nexport class B {}\n"
+        );
+        assert!(find_doc_targets(&ts_docs.suggested_content, DocSyntax::JsDoc).is_empty());
+
+        let documented = "/// Done\nfn a() {}\n";
+        assert!(generate_docs(&llm, None, "a.rs", documented, DocSyntax::Rust, MAX_CHUNK_TOKENS).await.is_err());
+    }
+
+    #[test]
+    fn test_doc_text_is_cleaned_and_escaped() {
+        assert_eq!(clean_doc_text("/**\n * Runs it.\n *\n * Twice.\n */"), "Runs it.\n\nTwice.");
+        assert_eq!(clean_doc_text("/// Runs it."), "Runs it.");
+        assert_eq!(clean_doc_text("\"\"\"Runs it.\"\"\""), "Runs it.");
+
+        assert_eq!(render_doc(DocSyntax::JsDoc, "", "a */ b"), vec!["/** a *\\/ b */"]);
+        assert_eq!(render_doc(DocSyntax::Python, "", "say \"\"\"hi\"\"\""), vec![
+            "\"\"\"say \\\"\\\"\\\"hi\\\"\\\"\\\"",
+            "\"\"\"",
+        ]);
+    }
+}
@@ -0,0 +1,303 @@
+// API Key Service - LLM provider API keys in secure storage
+//
+// Provides:
+// - Storing, reading and deleting provider API keys
+// - The backend (OS keyring or encrypted file) holding each key
+// - Format checks, and optionally a live check with the provider, before a key is stored
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error_handling::AppError;
+use crate::keyring_fallback::{get_secure_storage, SecretBackend, SecureStorage};
+use crate::llm_service::LlmProvider;
+
+/// Providers that take an API key; `Local` needs none
+const PROVIDERS: &[LlmProvider] = &[
+    LlmProvider::OpenRouter,
+    LlmProvider::OpenAI,
+    LlmProvider::Anthropic,
+    LlmProvider::Deepseek,
+    LlmProvider::Google,
+];
+
+/// Shorter strings are never real keys for any supported provider
+const MIN_KEY_LENGTH: usize = 20;
+
+const VALIDATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ============================================
+// Types
+// ============================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredApiKey {
+    pub provider: String,
+    pub api_key: String,
+    pub backend: SecretBackend,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderKeyStatus {
+    pub provider: String,
+    pub configured: bool,
+    /// Where the key is stored; `None` when not configured
+    pub backend: Option<SecretBackend>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyValidation {
+    pub provider: String,
+    pub valid: bool,
+    pub message: Option<String>,
+}
+
+// ============================================
+// Helpers
+// ============================================
+
+fn normalize_provider(provider: &str) -> Result<LlmProvider, AppError> {
+    LlmProvider::from_str(provider.trim())
+        .filter(|p| PROVIDERS.contains(p))
+        .ok_or_else(|| AppError::InvalidInput(format!("Invalid provider: {}", provider)))
+}
+
+fn key_name(provider: &LlmProvider) -> String {
+    format!("api_key_{}", provider.as_str())
+}
+
+/// Reject values that can't be an API key, e.g. empty or pasted with spaces
+fn check_key_format(api_key: &str) -> Result<(), AppError> {
+    if api_key.trim().is_empty() {
+        return Err(AppError::InvalidFormat("API key cannot be empty".to_string()));
+    }
+    if api_key.chars().any(char::is_whitespace) {
+        return Err(AppError::InvalidFormat("API key must not contain whitespace".to_string()));
+    }
+    if api_key.len() < MIN_KEY_LENGTH {
+        return Err(AppError::InvalidFormat(format!("API key is too short ({} characters)", api_key.len())));
+    }
+    Ok(())
+}
+
+/// Make the cheapest authenticated request the provider offers. Only a 401
+/// or 403 proves the key is wrong; anything else is reported as such so the
+/// caller can retry or store without validation.
+pub async fn verify_api_key(
+    client: &reqwest::Client,
+    provider: &LlmProvider,
+    base_url: &str,
+    api_key: &str,
+) -> Result<(), AppError> {
+    let base_url = base_url.trim_end_matches('/');
+    let request = match provider {
+        LlmProvider::OpenRouter => client
+            .get(format!("{}/auth/key", base_url))
+            .bearer_auth(api_key),
+        LlmProvider::Anthropic => client
+            .get(format!("{}/models", base_url))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01"),
+        LlmProvider::Google => client
+            .get(format!("{}/models", base_url))
+            .header("x-goog-api-key", api_key),
+        _ => client
+            .get(format!("{}/models", base_url))
+            .bearer_auth(api_key),
+    };
+
+    let response = request.send().await?;
+    match response.status().as_u16() {
+        200..=299 => Ok(()),
+        401 | 403 => Err(AppError::ApiKeyInvalid(provider.as_str().to_string())),
+        // Google rejects a bad key with 400 and reason API_KEY_INVALID
+        400 if *provider == LlmProvider::Google && response.text().await?.contains("API_KEY_INVALID") => {
+            Err(AppError::ApiKeyInvalid(provider.as_str().to_string()))
+        }
+        429 => Err(AppError::ApiRateLimited(format!("{} key check", provider.as_str()))),
+        status => Err(AppError::ApiRequestFailed(format!(
+            "{} key check returned HTTP {}",
+            provider.as_str(),
+            status
+        ))),
+    }
+}
+
+fn validation_client() -> Result<reqwest::Client, AppError> {
+    reqwest::Client::builder()
+        .timeout(VALIDATION_TIMEOUT)
+        .build()
+        .map_err(AppError::from)
+}
+
+fn with_storage<T>(f: impl FnOnce(&SecureStorage) -> Result<T, String>) -> Result<T, String> {
+    let guard = get_secure_storage()?;
+    let storage = guard.as_ref().ok_or("Secure storage not initialized")?;
+    f(storage)
+}
+
+// ============================================
+// Tauri Commands
+// ============================================
+
+/// Store a provider's API key. Returns the backend it landed in.
+/// With `validate`, the key is checked with the provider first and not
+/// stored if it is rejected; without it the key is stored offline.
+#[tauri::command]
+pub async fn api_key_set(provider: String, api_key: String, validate: Option<bool>) -> Result<SecretBackend, AppError> {
+    let provider = normalize_provider(&provider)?;
+    let api_key = api_key.trim();
+    check_key_format(api_key)?;
+
+    if validate.unwrap_or(false) {
+        verify_api_key(&validation_client()?, &provider, provider.base_url(), api_key).await?;
+    }
+
+    with_storage(|storage| storage.set(&key_name(&provider), api_key)).map_err(AppError::InternalError)
+}
+
+#[tauri::command]
+pub fn api_key_get(provider: String) -> Result<Option<StoredApiKey>, String> {
+    let provider = normalize_provider(&provider)?;
+    let secret = with_storage(|storage| storage.get(&key_name(&provider)))?;
+    Ok(secret.map(|secret| StoredApiKey {
+        provider: provider.as_str().to_string(),
+        api_key: secret.value,
+        backend: secret.backend,
+    }))
+}
+
+#[tauri::command]
+pub fn api_key_delete(provider: String) -> Result<(), String> {
+    let provider = normalize_provider(&provider)?;
+    with_storage(|storage| storage.delete(&key_name(&provider)))
+}
+
+/// Every supported provider, whether a key is stored and in which backend
+#[tauri::command]
+pub fn api_key_list_providers() -> Result<Vec<ProviderKeyStatus>, String> {
+    let entries = with_storage(|storage| storage.list_entries())?;
+    Ok(PROVIDERS
+        .iter()
+        .map(|provider| {
+            let backend = entries
+                .iter()
+                .find(|entry| entry.key == key_name(provider))
+                .map(|entry| entry.backend);
+            ProviderKeyStatus {
+                provider: provider.as_str().to_string(),
+                configured: backend.is_some(),
+                backend,
+            }
+        })
+        .collect())
+}
+
+/// Check a key's format and ask the provider whether it accepts it.
+/// A rejected key is reported as invalid; an unreachable provider is an error.
+#[tauri::command]
+pub async fn api_key_validate(provider: String, api_key: String) -> Result<ApiKeyValidation, AppError> {
+    let provider = normalize_provider(&provider)?;
+    let api_key = api_key.trim();
+
+    let result = match check_key_format(api_key) {
+        Ok(()) => verify_api_key(&validation_client()?, &provider, provider.base_url(), api_key).await,
+        Err(e) => Err(e),
+    };
+    let message = match result {
+        Ok(()) => None,
+        Err(e @ (AppError::InvalidFormat(_) | AppError::ApiKeyInvalid(_))) => Some(e.to_string()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(ApiKeyValidation {
+        provider: provider.as_str().to_string(),
+        valid: message.is_none(),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Accept `good-key` via whichever auth header the request carries, reject
+    /// anything else with 401
+    async fn provider_server() -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 2048];
+                let n = socket.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..n]).to_lowercase();
+                let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                let authorized = ["authorization: bearer good-key", "x-api-key: good-key", "x-goog-api-key: good-key"]
+                    .iter()
+                    .any(|header| request.contains(header));
+                let google = request.contains("x-goog-api-key:");
+                let (status, body) = match (path.as_str(), authorized) {
+                    ("/auth/key" | "/models", true) => ("200 OK", "{}"),
+                    // Google answers a bad key with 400 rather than 401
+                    ("/models", false) if google => (
+                        "400 Bad Request",
+                        r#"{"error":{"code":400,"status":"INVALID_ARGUMENT","details":[{"reason":"API_KEY_INVALID"}]}}"#,
+                    ),
+                    ("/auth/key" | "/models", false) => ("401 Unauthorized", "{}"),
+                    ("/bad-request/models", _) => ("400 Bad Request", r#"{"error":{"status":"INVALID_ARGUMENT"}}"#),
+                    _ => ("404 Not Found", "{}"),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    fn test_provider_and_key_format_checks() {
+        assert_eq!(normalize_provider(" OpenAI ").unwrap(), LlmProvider::OpenAI);
+        assert!(normalize_provider("acme").is_err());
+        assert!(normalize_provider("local").is_err());
+
+        assert!(check_key_format("sk-or-v1-0123456789abcdef").is_ok());
+        assert!(check_key_format("").is_err());
+        assert!(check_key_format("sk-short").is_err());
+        assert!(check_key_format("sk-or-v1 0123456789abcdef").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_api_key_against_provider() {
+        let base_url = provider_server().await;
+        let client = reqwest::Client::new();
+
+        for provider in PROVIDERS {
+            verify_api_key(&client, provider, &base_url, "good-key").await.unwrap();
+            match verify_api_key(&client, provider, &base_url, "typo-key").await {
+                Err(AppError::ApiKeyInvalid(name)) => assert_eq!(name, provider.as_str()),
+                other => panic!("expected ApiKeyInvalid for {}, got {:?}", provider.as_str(), other),
+            }
+        }
+
+        // Other statuses don't prove the key wrong
+        let err = verify_api_key(&client, &LlmProvider::OpenAI, &format!("{}/missing", base_url), "good-key")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::ApiRequestFailed(_)));
+
+        // A 400 for any other reason isn't a verdict on the key either
+        let err = verify_api_key(&client, &LlmProvider::Google, &format!("{}/bad-request", base_url), "good-key")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::ApiRequestFailed(_)));
+    }
+}
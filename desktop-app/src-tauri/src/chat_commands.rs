@@ -6,19 +6,20 @@
 // - Model selection
 // - Session management
 
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 
 use crate::memory_manager::{
-    MemoryManager, MemoryStats,
+    MemoryManager, MemoryStats, SessionSummary, SummarizationConfig,
     AddShortTermMemoryRequest, AddWorkingMemoryRequest, AddLongTermMemoryRequest,
     ShortTermMemory, WorkingMemory, LongTermMemory, RetrievalQuery, RetrievedContext,
 };
 use crate::context_builder::{Skill, ChatContext};
 use crate::llm_service::{
-    LlmService, LlmServiceConfig, LlmModel, ChatServiceResponse,
-    ProviderConfig, LlmProvider,
+    LlmService, LlmServiceConfig, LlmModel, ChatService, ChatServiceResponse,
+    ChatStreamListener, FallbackPolicy, ProviderConfig, LlmProvider,
 };
 
 // ============================================
@@ -28,6 +29,9 @@ use crate::llm_service::{
 pub struct ChatState {
     pub memory_manager: Arc<MemoryManager>,
     pub llm_service: Arc<LlmService>,
+    pub chat_service: Arc<ChatService>,
+    /// Cancellation handles for replies currently streaming, by session
+    pub active_streams: HashMap<String, Arc<Notify>>,
 }
 
 // ============================================
@@ -71,6 +75,33 @@ pub async fn clear_session_memory(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn summarize_session(
+    state: State<'_, Arc<Mutex<ChatState>>>,
+    workspace_id: String,
+    session_id: String,
+    keep_recent_n: Option<usize>,
+) -> Result<Option<SessionSummary>, String> {
+    let (memory_manager, llm_service) = {
+        let state = state.lock().await;
+        (state.memory_manager.clone(), state.llm_service.clone())
+    };
+    memory_manager
+        .summarize_session(llm_service.as_ref(), &workspace_id, &session_id, keep_recent_n)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_summarization_config(
+    state: State<'_, Arc<Mutex<ChatState>>>,
+    config: SummarizationConfig,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    state.memory_manager.set_summarization_config(config);
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn add_working_memory(
     state: State<'_, Arc<Mutex<ChatState>>>,
@@ -242,8 +273,8 @@ pub async fn set_model_for_mode(
     model_id: String,
 ) -> Result<(), String> {
     let state = state.lock().await;
-    state.llm_service.set_model_for_mode(&mode, &model_id).await;
-    Ok(())
+    state.llm_service.set_model_for_mode(&mode, &model_id).await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -255,6 +286,25 @@ pub async fn get_model_for_mode(
     Ok(state.llm_service.get_model_for_mode(&mode).await)
 }
 
+/// Models `chat_send_message` moves on to after a transient failure
+#[tauri::command]
+pub async fn get_fallback_policy(
+    state: State<'_, Arc<Mutex<ChatState>>>,
+) -> Result<FallbackPolicy, String> {
+    let state = state.lock().await;
+    Ok(state.chat_service.fallback_policy().await)
+}
+
+#[tauri::command]
+pub async fn set_fallback_policy(
+    state: State<'_, Arc<Mutex<ChatState>>>,
+    policy: FallbackPolicy,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    state.chat_service.set_fallback_policy(policy).await;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn estimate_tokens(
     state: State<'_, Arc<Mutex<ChatState>>>,
@@ -325,6 +375,56 @@ pub struct ChatSession {
     pub updated_at: String,
 }
 
+/// Stream a reply, emitting `ChatStreamEvent`s on `chat://stream/<session_id>`
+#[tauri::command]
+pub async fn chat_send_message_stream(
+    app: AppHandle,
+    state: State<'_, Arc<Mutex<ChatState>>>,
+    workspace_id: String,
+    session_id: String,
+    message: String,
+    model_id: Option<String>,
+) -> Result<ChatServiceResponse, String> {
+    let cancel = Arc::new(Notify::new());
+    let chat_service = {
+        let mut state = state.lock().await;
+        if state.active_streams.contains_key(&session_id) {
+            return Err(format!("A reply is already streaming for session {}", session_id));
+        }
+        state.active_streams.insert(session_id.clone(), cancel.clone());
+        state.chat_service.clone()
+    };
+    
+    let event_name = format!("chat://stream/{}", session_id);
+    let on_event: ChatStreamListener = Arc::new(move |event| {
+        let _ = app.emit(&event_name, event);
+    });
+    
+    let result = chat_service
+        .send_message_stream(&workspace_id, &session_id, &message, model_id.as_deref(), on_event, cancel)
+        .await
+        .map_err(|e| e.to_string());
+    
+    state.lock().await.active_streams.remove(&session_id);
+    result
+}
+
+/// Stop a streaming reply; the part received so far is kept
+#[tauri::command]
+pub async fn chat_cancel_stream(
+    state: State<'_, Arc<Mutex<ChatState>>>,
+    session_id: String,
+) -> Result<bool, String> {
+    let state = state.lock().await;
+    match state.active_streams.get(&session_id) {
+        Some(cancel) => {
+            cancel.notify_one();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
 #[tauri::command]
 pub async fn create_chat_session(
     workspace_id: String,
@@ -12,8 +12,8 @@ use tokio::sync::Mutex;
 
 use crate::cli_service::{
     CliService, CliCommand, CommandResult, CommandStatus, OutputBlock, OutputBlockType,
-    CodeSuggestion, SuggestionStatus, FileNode, FileContent, SearchResult,
-    get_help_text,
+    CodeSuggestion, SuggestionStatus, FileNode, FileContent, SearchResult, DiffHunk,
+    InputSuggestion, ExecutionTarget, DEFAULT_DIFF_CONTEXT, DEFAULT_INPUT_SUGGESTION_LIMIT, get_help_text,
 };
 
 // ============================================
@@ -53,6 +53,24 @@ pub async fn cli_get_workspace(
     Ok(state.service.get_workspace().await.map(|p| p.to_string_lossy().to_string()))
 }
 
+#[tauri::command]
+pub async fn cli_set_sandbox(
+    state: State<'_, Arc<Mutex<CliState>>>,
+    container_id: Option<String>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    state.service.set_sandbox(container_id).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cli_get_execution_target(
+    state: State<'_, Arc<Mutex<CliState>>>,
+) -> Result<ExecutionTarget, String> {
+    let state = state.lock().await;
+    state.service.execution_target().await
+}
+
 // ============================================
 // Command Execution
 // ============================================
@@ -69,9 +87,13 @@ pub async fn cli_parse_command(
 pub async fn cli_execute_command(
     state: State<'_, Arc<Mutex<CliState>>>,
     input: String,
+    steps: Option<Vec<String>>,
 ) -> Result<CommandResult, String> {
     let state = state.lock().await;
     let start = std::time::Instant::now();
+    let steps = steps.unwrap_or_default();
+    let mut status = CommandStatus::Success;
+    let mut files_modified = Vec::new();
     
     // Add to history
     state.service.add_to_history(&input).await;
@@ -80,7 +102,7 @@ pub async fn cli_execute_command(
     let command = CliService::parse_command(&input)?;
     
     // Execute based on command type
-    let (output, suggestions) = match &command {
+    let (mut output, suggestions) = match &command {
         CliCommand::Help => {
             let output = vec![OutputBlock {
                 block_type: OutputBlockType::Text,
@@ -168,13 +190,35 @@ pub async fn cli_execute_command(
         }
     };
 
+    // Shell steps for commands that change code run in the sandbox when one is attached
+    let runs_steps = matches!(
+        command,
+        CliCommand::Implement { .. } | CliCommand::Debug { .. } | CliCommand::Review { .. }
+    );
+    if runs_steps && !steps.is_empty() {
+        let report = state.service.execute_steps(&steps).await?;
+        output.push(OutputBlock {
+            block_type: OutputBlockType::Info,
+            content: match &report.target {
+                ExecutionTarget::Sandbox { container_id } => format!("Running in sandbox {}", container_id),
+                ExecutionTarget::Host => "Running on host (no sandbox attached)".to_string(),
+            },
+            metadata: None,
+        });
+        output.extend(report.output);
+        files_modified = report.files_modified;
+        if !report.success {
+            status = CommandStatus::Error("A step failed".to_string());
+        }
+    }
+
     Ok(CommandResult {
         command: input,
-        status: CommandStatus::Success,
+        status,
         output,
         suggestions,
         files_read: vec![],
-        files_modified: vec![],
+        files_modified,
         execution_time_ms: start.elapsed().as_millis() as u64,
     })
 }
@@ -200,6 +244,23 @@ pub async fn cli_search_history(
     Ok(state.service.search_history(&query).await)
 }
 
+#[tauri::command]
+pub async fn cli_get_suggestions(
+    state: State<'_, Arc<Mutex<CliState>>>,
+    input: String,
+    cursor: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Vec<InputSuggestion>, String> {
+    let state = state.lock().await;
+    // Only the text before the cursor (a character offset) is completed
+    let end = cursor
+        .and_then(|c| input.char_indices().nth(c).map(|(i, _)| i))
+        .unwrap_or(input.len());
+    Ok(state.service
+        .get_input_suggestions(&input[..end], limit.unwrap_or(DEFAULT_INPUT_SUGGESTION_LIMIT))
+        .await)
+}
+
 // ============================================
 // Suggestion Commands
 // ============================================
@@ -318,9 +379,19 @@ pub async fn cli_write_file(
 pub async fn cli_list_files(
     state: State<'_, Arc<Mutex<CliState>>>,
     dir: Option<String>,
+    include_ignored: Option<bool>,
 ) -> Result<Vec<FileNode>, String> {
     let state = state.lock().await;
-    state.service.list_files(dir.as_deref()).await
+    state.service.list_files(dir.as_deref(), include_ignored.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn cli_get_file_tree(
+    state: State<'_, Arc<Mutex<CliState>>>,
+    include_ignored: Option<bool>,
+) -> Result<Vec<FileNode>, String> {
+    let state = state.lock().await;
+    state.service.list_files(None, include_ignored.unwrap_or(false)).await
 }
 
 #[tauri::command]
@@ -328,9 +399,10 @@ pub async fn cli_search_files(
     state: State<'_, Arc<Mutex<CliState>>>,
     query: String,
     file_pattern: Option<String>,
+    include_ignored: Option<bool>,
 ) -> Result<Vec<SearchResult>, String> {
     let state = state.lock().await;
-    state.service.search_files(&query, file_pattern.as_deref()).await
+    state.service.search_files(&query, file_pattern.as_deref(), include_ignored.unwrap_or(false)).await
 }
 
 // ============================================
@@ -341,10 +413,31 @@ pub async fn cli_search_files(
 pub async fn cli_generate_diff(
     original: String,
     modified: String,
-) -> Result<Vec<crate::cli_service::DiffHunk>, String> {
+) -> Result<Vec<DiffHunk>, String> {
     Ok(CliService::generate_diff(&original, &modified))
 }
 
+#[tauri::command]
+pub async fn cli_get_diff(
+    state: State<'_, Arc<Mutex<CliState>>>,
+    path: String,
+    modified: String,
+    context_lines: Option<usize>,
+) -> Result<Vec<DiffHunk>, String> {
+    let state = state.lock().await;
+    state.service.diff_file(&path, &modified, context_lines.unwrap_or(DEFAULT_DIFF_CONTEXT)).await
+}
+
+#[tauri::command]
+pub async fn cli_apply_diff(
+    state: State<'_, Arc<Mutex<CliState>>>,
+    path: String,
+    hunks: Vec<DiffHunk>,
+) -> Result<FileContent, String> {
+    let state = state.lock().await;
+    state.service.apply_diff_to_file(&path, &hunks).await
+}
+
 // ============================================
 // Helper Types
 // ============================================
@@ -13,11 +13,13 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use serde::{Deserialize, Serialize};
 
+use crate::docker_manager::{DockerManager, ExecOutput};
+
 // ============================================
 // Types
 // ============================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CliCommand {
     Spec { description: String },
     Plan { task: String },
@@ -94,9 +96,12 @@ pub struct DiffLine {
     pub content: String,
     pub old_line_no: Option<i32>,
     pub new_line_no: Option<i32>,
+    /// The line is the last one in its file and has no trailing newline
+    #[serde(default)]
+    pub no_newline: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum DiffLineType {
     Context,
     Addition,
@@ -111,6 +116,221 @@ pub enum SuggestionStatus {
     Modified,
 }
 
+/// Where shell steps and file operations run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ExecutionTarget {
+    Host,
+    Sandbox { container_id: String },
+}
+
+/// Output of shell steps run for a command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    pub target: ExecutionTarget,
+    pub output: Vec<OutputBlock>,
+    pub files_modified: Vec<String>,
+    pub success: bool,
+}
+
+/// A completion for the command input; `value` replaces the whole input line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputSuggestion {
+    pub value: String,
+    pub label: String,
+    pub kind: InputSuggestionKind,
+    pub score: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum InputSuggestionKind {
+    Command,
+    History,
+    Path,
+}
+
+// ============================================
+// Command Parsing
+// ============================================
+
+/// Slash commands accepted by `CliCommand::parse`
+const COMMAND_NAMES: &[&str] = &[
+    "/spec", "/plan", "/tasks", "/implement", "/debug", "/review", "/ask", "/help",
+];
+
+/// Commands whose arguments complete as workspace paths
+const FILE_ARG_COMMANDS: &[&str] = &["/implement", "/impl", "/debug", "/review"];
+
+/// A shell-style argument; quoted arguments are never treated as file paths
+#[derive(Debug, Clone, PartialEq)]
+struct Arg {
+    value: String,
+    quoted: bool,
+}
+
+impl CliCommand {
+    /// Parse a typed line such as `/implement "add auth" src/a.rs src/b.rs`.
+    ///
+    /// Arguments use shell-style quoting. Input without a leading slash is
+    /// treated as a question.
+    pub fn parse(input: &str) -> Result<CliCommand, String> {
+        let input = input.trim();
+
+        if input.is_empty() {
+            return Err("Empty command".to_string());
+        }
+
+        if !input.starts_with('/') {
+            return Ok(CliCommand::Ask { question: input.to_string() });
+        }
+
+        let (command, rest) = match input.split_once(char::is_whitespace) {
+            Some((command, rest)) => (command.to_lowercase(), rest),
+            None => (input.to_lowercase(), ""),
+        };
+        let args = split_args(rest)?;
+
+        match command.as_str() {
+            "/spec" => Ok(CliCommand::Spec {
+                description: required_text(&args, "/spec <description>")?,
+            }),
+            "/plan" => Ok(CliCommand::Plan {
+                task: required_text(&args, "/plan <task>")?,
+            }),
+            "/tasks" => Ok(CliCommand::Tasks {
+                filter: optional_text(&args),
+            }),
+            "/implement" | "/impl" => {
+                let (files, words): (Vec<&Arg>, Vec<&Arg>) = args.iter()
+                    .partition(|arg| looks_like_path(arg));
+                let instruction = join_args(&words);
+                if instruction.is_empty() {
+                    return Err("Missing instruction. Usage: /implement <instruction> [files...]".to_string());
+                }
+                Ok(CliCommand::Implement {
+                    instruction,
+                    files: files.iter().map(|arg| arg.value.clone()).collect(),
+                })
+            }
+            "/debug" => {
+                // `/debug <error> in <file>`; an unquoted path alone is the file
+                let split = args.iter().position(|arg| !arg.quoted && arg.value == "in");
+                let (error_args, file_args): (Vec<&Arg>, Vec<&Arg>) = match split {
+                    Some(index) => (args[..index].iter().collect(), args[index + 1..].iter().collect()),
+                    None if args.len() == 1 && looks_like_path(&args[0]) => (vec![], vec![&args[0]]),
+                    None => (args.iter().collect(), vec![]),
+                };
+                if split.is_some() && file_args.is_empty() {
+                    return Err("Missing file after 'in'. Usage: /debug [error] [in <file>]".to_string());
+                }
+                let error = join_args(&error_args);
+                let file = join_args(&file_args);
+                Ok(CliCommand::Debug {
+                    error: if error.is_empty() { None } else { Some(error) },
+                    file: if file.is_empty() { None } else { Some(file) },
+                })
+            }
+            "/review" => Ok(CliCommand::Review {
+                files: args.into_iter().map(|arg| arg.value).collect(),
+            }),
+            "/ask" => Ok(CliCommand::Ask {
+                question: required_text(&args, "/ask <question>")?,
+            }),
+            "/help" | "/?" => Ok(CliCommand::Help),
+            _ => Err(format!(
+                "Unknown command: {}. Available commands: {}",
+                command,
+                COMMAND_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Split arguments with shell-style quoting and backslash escapes
+fn split_args(input: &str) -> Result<Vec<Arg>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut quote: Option<char> = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                // Only escape quotes, spaces and backslashes so Windows paths survive
+                match chars.clone().next() {
+                    Some(next) if next == '"' || next == '\'' || next == '\\' || next.is_whitespace() => {
+                        current.push(next);
+                        chars.next();
+                    }
+                    _ => current.push('\\'),
+                }
+                in_arg = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                quoted = true;
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(Arg { value: std::mem::take(&mut current), quoted });
+                    in_arg = false;
+                    quoted = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if let Some(q) = quote {
+        return Err(format!("Unterminated {} quote in command", q));
+    }
+    if in_arg {
+        args.push(Arg { value: current, quoted });
+    }
+
+    Ok(args)
+}
+
+/// Whether an unquoted argument names a file rather than instruction text
+fn looks_like_path(arg: &Arg) -> bool {
+    if arg.quoted {
+        return false;
+    }
+    if arg.value.contains('/') || arg.value.contains('\\') {
+        return true;
+    }
+    Path::new(&arg.value)
+        .extension()
+        .map(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            LANGUAGE_BY_EXTENSION.iter().any(|(known, _)| *known == ext)
+        })
+        .unwrap_or(false)
+}
+
+fn join_args(args: &[&Arg]) -> String {
+    args.iter()
+        .map(|arg| arg.value.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn optional_text(args: &[Arg]) -> Option<String> {
+    let text = join_args(&args.iter().collect::<Vec<_>>());
+    if text.is_empty() { None } else { Some(text) }
+}
+
+fn required_text(args: &[Arg], usage: &str) -> Result<String, String> {
+    optional_text(args).ok_or_else(|| format!("Missing argument. Usage: {}", usage))
+}
+
 // ============================================
 // File System Types
 // ============================================
@@ -129,10 +349,12 @@ pub struct FileNode {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileContent {
     pub path: String,
+    /// Empty for binary files
     pub content: String,
     pub language: String,
     pub line_count: i32,
     pub size: u64,
+    pub is_binary: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,6 +366,64 @@ pub struct SearchResult {
     pub match_end: i32,
 }
 
+/// Leading bytes checked for NUL when sniffing binary files
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Language identifiers by lowercase file extension
+const LANGUAGE_BY_EXTENSION: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("mjs", "javascript"),
+    ("cjs", "javascript"),
+    ("py", "python"),
+    ("go", "go"),
+    ("java", "java"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cpp", "cpp"),
+    ("cc", "cpp"),
+    ("hpp", "cpp"),
+    ("cs", "csharp"),
+    ("rb", "ruby"),
+    ("php", "php"),
+    ("swift", "swift"),
+    ("kt", "kotlin"),
+    ("kts", "kotlin"),
+    ("scala", "scala"),
+    ("html", "html"),
+    ("htm", "html"),
+    ("css", "css"),
+    ("scss", "css"),
+    ("sass", "css"),
+    ("less", "css"),
+    ("json", "json"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("toml", "toml"),
+    ("xml", "xml"),
+    ("md", "markdown"),
+    ("markdown", "markdown"),
+    ("sql", "sql"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("dockerfile", "dockerfile"),
+];
+
+/// Directories skipped even without a `.gitignore` entry, unless ignored files are requested
+const DEFAULT_IGNORED_DIRS: &[&str] = &["node_modules", "target", "dist", "__pycache__"];
+
+/// Where sandbox containers mount the workspace (see `WorkspaceManager::create_container`)
+pub const SANDBOX_PROJECT_DIR: &str = "/workspace/project";
+
+pub const DEFAULT_INPUT_SUGGESTION_LIMIT: usize = 10;
+
+/// Upper bound on workspace entries scanned for path completion
+const MAX_PATH_CANDIDATES: usize = 5000;
+
 // ============================================
 // CLI Service
 // ============================================
@@ -152,6 +432,7 @@ pub struct CliService {
     workspace_path: Arc<Mutex<Option<PathBuf>>>,
     command_history: Arc<Mutex<Vec<String>>>,
     pending_suggestions: Arc<Mutex<HashMap<String, CodeSuggestion>>>,
+    sandbox_container: Arc<Mutex<Option<String>>>,
 }
 
 impl CliService {
@@ -160,6 +441,7 @@ impl CliService {
             workspace_path: Arc::new(Mutex::new(None)),
             command_history: Arc::new(Mutex::new(Vec::new())),
             pending_suggestions: Arc::new(Mutex::new(HashMap::new())),
+            sandbox_container: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -177,85 +459,179 @@ impl CliService {
     }
 
     // ============================================
-    // Command Parsing
+    // Sandbox Execution
     // ============================================
 
-    pub fn parse_command(input: &str) -> Result<CliCommand, String> {
-        let input = input.trim();
-        
-        if input.is_empty() {
-            return Err("Empty command".to_string());
+    /// Associate the workspace with a sandbox container that mounts it at `SANDBOX_PROJECT_DIR`
+    pub async fn set_sandbox(&self, container_id: Option<String>) {
+        *self.sandbox_container.lock().await = container_id;
+    }
+
+    pub async fn get_sandbox(&self) -> Option<String> {
+        self.sandbox_container.lock().await.clone()
+    }
+
+    /// The associated sandbox, or the host when there is none. A sandbox
+    /// that is not running is an error rather than a silent fall back to the host.
+    pub async fn execution_target(&self) -> Result<ExecutionTarget, String> {
+        let Some(container_id) = self.get_sandbox().await else {
+            return Ok(ExecutionTarget::Host);
+        };
+
+        let id = container_id.clone();
+        let running = tokio::task::spawn_blocking(move || DockerManager::is_container_running(&id))
+            .await
+            .unwrap_or(false);
+
+        if !running {
+            return Err(format!(
+                "Sandbox {} is not running. Start it or detach it from the workspace.",
+                container_id
+            ));
         }
+        Ok(ExecutionTarget::Sandbox { container_id })
+    }
 
-        // Check for slash commands
-        if input.starts_with('/') {
-            let parts: Vec<&str> = input.splitn(2, ' ').collect();
-            let command = parts[0].to_lowercase();
-            let args = parts.get(1).map(|s| s.trim()).unwrap_or("");
-
-            match command.as_str() {
-                "/spec" => Ok(CliCommand::Spec {
-                    description: args.to_string(),
-                }),
-                "/plan" => Ok(CliCommand::Plan {
-                    task: args.to_string(),
-                }),
-                "/tasks" => Ok(CliCommand::Tasks {
-                    filter: if args.is_empty() { None } else { Some(args.to_string()) },
-                }),
-                "/implement" | "/impl" => {
-                    let (instruction, files) = Self::parse_implement_args(args);
-                    Ok(CliCommand::Implement { instruction, files })
-                }
-                "/debug" => {
-                    let (error, file) = Self::parse_debug_args(args);
-                    Ok(CliCommand::Debug { error, file })
-                }
-                "/review" => {
-                    let files = Self::parse_file_list(args);
-                    Ok(CliCommand::Review { files })
-                }
-                "/ask" => Ok(CliCommand::Ask {
-                    question: args.to_string(),
-                }),
-                "/help" | "/?" => Ok(CliCommand::Help),
-                _ => Err(format!("Unknown command: {}", command)),
+    /// Map a workspace path (relative, or absolute under the workspace) into the container
+    pub fn sandbox_path(workspace: &Path, path: &str) -> Result<String, String> {
+        let requested = Path::new(path);
+        let relative = if requested.is_absolute() {
+            requested.strip_prefix(workspace)
+                .map_err(|_| format!("Path is outside the workspace: {}", path))?
+        } else {
+            requested
+        };
+
+        let mut parts = Vec::new();
+        for component in relative.components() {
+            match component {
+                std::path::Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+                std::path::Component::CurDir => {}
+                _ => return Err(format!("Path is outside the workspace: {}", path)),
             }
+        }
+
+        if parts.is_empty() {
+            Ok(SANDBOX_PROJECT_DIR.to_string())
         } else {
-            // Default to ask
-            Ok(CliCommand::Ask {
-                question: input.to_string(),
-            })
+            Ok(format!("{}/{}", SANDBOX_PROJECT_DIR, parts.join("/")))
         }
     }
 
-    fn parse_implement_args(args: &str) -> (String, Vec<String>) {
-        let mut files = Vec::new();
-        let mut instruction_parts = Vec::new();
+    async fn exec_in_sandbox(
+        container_id: &str,
+        command: Vec<String>,
+        stdin: Option<Vec<u8>>,
+    ) -> Result<ExecOutput, String> {
+        let container_id = container_id.to_string();
+        tokio::task::spawn_blocking(move || {
+            DockerManager::exec_command(&container_id, &command, Some(SANDBOX_PROJECT_DIR), stdin.as_deref())
+        })
+        .await
+        .map_err(|e| format!("Sandbox task failed: {}", e))?
+    }
 
-        for part in args.split_whitespace() {
-            if part.contains('.') && (part.contains('/') || part.ends_with(".ts") || part.ends_with(".rs") || part.ends_with(".tsx") || part.ends_with(".js")) {
-                files.push(part.to_string());
-            } else {
-                instruction_parts.push(part);
+    /// Run one shell step in the workspace root, inside the sandbox when there is one
+    pub async fn run_shell(&self, command: &str) -> Result<(ExecutionTarget, ExecOutput), String> {
+        let target = self.execution_target().await?;
+
+        let output = match &target {
+            ExecutionTarget::Sandbox { container_id } => {
+                let argv = vec!["sh".to_string(), "-c".to_string(), command.to_string()];
+                Self::exec_in_sandbox(container_id, argv, None).await?
             }
-        }
+            ExecutionTarget::Host => {
+                let workspace = self.get_workspace().await.ok_or("No workspace set")?;
+                let mut shell = if cfg!(windows) {
+                    let mut cmd = tokio::process::Command::new("cmd");
+                    cmd.args(["/C", command]);
+                    cmd
+                } else {
+                    let mut cmd = tokio::process::Command::new("sh");
+                    cmd.args(["-c", command]);
+                    cmd
+                };
+                let output = shell.current_dir(workspace)
+                    .output()
+                    .await
+                    .map_err(|e| format!("Failed to run command: {}", e))?;
+                ExecOutput {
+                    stdout: output.stdout,
+                    stderr: output.stderr,
+                    exit_code: output.status.code(),
+                }
+            }
+        };
 
-        (instruction_parts.join(" "), files)
+        Ok((target, output))
     }
 
-    fn parse_debug_args(args: &str) -> (Option<String>, Option<String>) {
-        let parts: Vec<&str> = args.splitn(2, " in ").collect();
-        let error = if parts[0].is_empty() { None } else { Some(parts[0].to_string()) };
-        let file = parts.get(1).map(|s| s.trim().to_string());
-        (error, file)
+    /// Run shell steps in order, stopping at the first failure, and report
+    /// their output and the workspace files they changed
+    pub async fn execute_steps(&self, steps: &[String]) -> Result<ExecutionReport, String> {
+        let workspace = self.get_workspace().await.ok_or("No workspace set")?;
+        // The sandbox bind-mounts the workspace, so host snapshots see its writes too
+        let before = snapshot_workspace(&workspace);
+
+        let mut target = ExecutionTarget::Host;
+        let mut output = Vec::new();
+        let mut success = true;
+
+        for step in steps {
+            let (step_target, result) = self.run_shell(step).await?;
+            target = step_target;
+
+            let exit_code = result.exit_code.map(|c| c.to_string()).unwrap_or_else(|| "none".to_string());
+            let mut metadata = HashMap::new();
+            metadata.insert("command".to_string(), step.clone());
+            metadata.insert("exit_code".to_string(), exit_code.clone());
+            if let ExecutionTarget::Sandbox { container_id } = &target {
+                metadata.insert("container_id".to_string(), container_id.clone());
+            }
+
+            let stdout = String::from_utf8_lossy(&result.stdout).to_string();
+            let stderr = String::from_utf8_lossy(&result.stderr).to_string();
+            if !stdout.is_empty() {
+                output.push(OutputBlock {
+                    block_type: OutputBlockType::Code,
+                    content: stdout,
+                    metadata: Some(metadata.clone()),
+                });
+            }
+            if !stderr.is_empty() {
+                output.push(OutputBlock {
+                    block_type: if result.success() { OutputBlockType::Warning } else { OutputBlockType::Error },
+                    content: stderr,
+                    metadata: Some(metadata.clone()),
+                });
+            }
+
+            if !result.success() {
+                output.push(OutputBlock {
+                    block_type: OutputBlockType::Error,
+                    content: format!("Step failed with exit code {}: {}", exit_code, step),
+                    metadata: Some(metadata),
+                });
+                success = false;
+                break;
+            }
+        }
+
+        let after = snapshot_workspace(&workspace);
+        Ok(ExecutionReport {
+            target,
+            output,
+            files_modified: changed_files(&before, &after),
+            success,
+        })
     }
 
-    fn parse_file_list(args: &str) -> Vec<String> {
-        args.split_whitespace()
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
-            .collect()
+    // ============================================
+    // Command Parsing
+    // ============================================
+
+    pub fn parse_command(input: &str) -> Result<CliCommand, String> {
+        CliCommand::parse(input)
     }
 
     // ============================================
@@ -287,6 +663,100 @@ impl CliService {
             .collect()
     }
 
+    // ============================================
+    // Input Suggestions
+    // ============================================
+
+    /// Rank command, history and path completions for `input` (the text before the cursor)
+    pub async fn get_input_suggestions(&self, input: &str, limit: usize) -> Vec<InputSuggestion> {
+        let mut suggestions = Vec::new();
+        let trimmed = input.trim_start();
+
+        match trimmed.split_once(char::is_whitespace) {
+            None => {
+                let query = trimmed.trim_start_matches('/');
+                for name in COMMAND_NAMES {
+                    if let Some(score) = fuzzy_score(query, &name[1..]) {
+                        suggestions.push(InputSuggestion {
+                            value: format!("{} ", name),
+                            label: name.to_string(),
+                            kind: InputSuggestionKind::Command,
+                            score: score + 50,
+                        });
+                    }
+                }
+            }
+            Some((command, _)) if FILE_ARG_COMMANDS.contains(&command.to_lowercase().as_str()) => {
+                // Complete the token under the cursor
+                let split = input.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+                let (head, partial) = input.split_at(split);
+                for (path, score) in self.match_workspace_paths(partial).await {
+                    suggestions.push(InputSuggestion {
+                        value: format!("{}{}", head, path),
+                        label: path,
+                        kind: InputSuggestionKind::Path,
+                        score,
+                    });
+                }
+            }
+            Some(_) => {}
+        }
+
+        // Recent history, newest first, gets a small recency boost
+        if !trimmed.is_empty() {
+            let history = self.command_history.lock().await;
+            for (age, entry) in history.iter().rev().enumerate() {
+                if entry == input {
+                    continue;
+                }
+                if let Some(score) = fuzzy_score(trimmed, entry) {
+                    suggestions.push(InputSuggestion {
+                        value: entry.clone(),
+                        label: entry.clone(),
+                        kind: InputSuggestionKind::History,
+                        score: score * 4 / 5 + (100 - age.min(100) as i64),
+                    });
+                }
+            }
+        }
+
+        suggestions.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.label.cmp(&b.label)));
+        let mut seen = std::collections::HashSet::new();
+        suggestions.retain(|s| seen.insert(s.value.clone()));
+        suggestions.truncate(limit);
+        suggestions
+    }
+
+    async fn match_workspace_paths(&self, partial: &str) -> Vec<(String, i64)> {
+        let Some(root) = self.get_workspace().await else {
+            return Vec::new();
+        };
+        let partial = partial.trim_start_matches(['"', '\'']).replace('\\', "/");
+
+        Self::walk_workspace(&root, false)
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.depth() > 0)
+            .take(MAX_PATH_CANDIDATES)
+            .filter_map(|entry| {
+                let relative = entry.path()
+                    .strip_prefix(&root)
+                    .ok()?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                let name = entry.file_name().to_string_lossy();
+
+                // The full path wins over a bare file-name match
+                let score = fuzzy_score(&partial, &relative)
+                    .into_iter()
+                    .chain(fuzzy_score(&partial, &name).map(|score| score - 100))
+                    .max()?;
+                let path = if is_dir { format!("{}/", relative) } else { relative };
+                Some((path, score))
+            })
+            .collect()
+    }
+
     // ============================================
     // Suggestion Management
     // ============================================
@@ -330,48 +800,80 @@ impl CliService {
     // ============================================
 
     pub async fn read_file(&self, path: &str) -> Result<FileContent, String> {
-        let workspace = self.workspace_path.lock().await;
+        let workspace = self.get_workspace().await;
         let full_path = if let Some(ws) = workspace.as_ref() {
             ws.join(path)
         } else {
             PathBuf::from(path)
         };
 
-        if !full_path.exists() {
-            return Err(format!("File not found: {}", path));
+        let bytes = match (self.execution_target().await?, workspace.as_ref()) {
+            (ExecutionTarget::Sandbox { container_id }, Some(ws)) => {
+                let argv = vec!["cat".to_string(), "--".to_string(), Self::sandbox_path(ws, path)?];
+                let exec = Self::exec_in_sandbox(&container_id, argv, None).await?;
+                if !exec.success() {
+                    return Err(format!("File not found: {}", path));
+                }
+                exec.stdout
+            }
+            _ => {
+                if !full_path.exists() {
+                    return Err(format!("File not found: {}", path));
+                }
+                std::fs::read(&full_path)
+                    .map_err(|e| format!("Failed to read file: {}", e))?
+            }
+        };
+        let size = bytes.len() as u64;
+
+        if is_binary_content(&bytes) {
+            return Ok(FileContent {
+                path: path.to_string(),
+                content: String::new(),
+                language: "binary".to_string(),
+                line_count: 0,
+                size,
+                is_binary: true,
+            });
         }
 
-        let content = std::fs::read_to_string(&full_path)
+        let content = String::from_utf8(bytes)
             .map_err(|e| format!("Failed to read file: {}", e))?;
-        
-        let metadata = std::fs::metadata(&full_path)
-            .map_err(|e| format!("Failed to get metadata: {}", e))?;
-
-        let extension = full_path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("");
-
-        let language = Self::detect_language(extension);
-        let line_count = content.lines().count() as i32;
 
         Ok(FileContent {
             path: path.to_string(),
+            language: Self::detect_language(&full_path),
+            line_count: count_lines(&content),
             content,
-            language,
-            line_count,
-            size: metadata.len(),
+            size,
+            is_binary: false,
         })
     }
 
     pub async fn write_file(&self, path: &str, content: &str) -> Result<(), String> {
-        let workspace = self.workspace_path.lock().await;
+        let workspace = self.get_workspace().await;
         let full_path = if let Some(ws) = workspace.as_ref() {
             ws.join(path)
         } else {
             PathBuf::from(path)
         };
 
+        if let (ExecutionTarget::Sandbox { container_id }, Some(ws)) = (self.execution_target().await?, workspace.as_ref()) {
+            // The path is passed as $1 so the shell never interprets it
+            let argv = vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "mkdir -p \"$(dirname \"$1\")\" && cat > \"$1\"".to_string(),
+                "sh".to_string(),
+                Self::sandbox_path(ws, path)?,
+            ];
+            let exec = Self::exec_in_sandbox(&container_id, argv, Some(content.as_bytes().to_vec())).await?;
+            if !exec.success() {
+                return Err(format!("Failed to write file: {}", String::from_utf8_lossy(&exec.stderr).trim()));
+            }
+            return Ok(());
+        }
+
         // Create parent directories if needed
         if let Some(parent) = full_path.parent() {
             std::fs::create_dir_all(parent)
@@ -384,7 +886,7 @@ impl CliService {
         Ok(())
     }
 
-    pub async fn list_files(&self, dir: Option<&str>) -> Result<Vec<FileNode>, String> {
+    pub async fn list_files(&self, dir: Option<&str>, include_ignored: bool) -> Result<Vec<FileNode>, String> {
         let workspace = self.workspace_path.lock().await;
         let base_path = workspace.as_ref().ok_or("No workspace set")?;
         
@@ -394,29 +896,49 @@ impl CliService {
             base_path.clone()
         };
 
-        Self::build_file_tree(&target_path, base_path)
+        Self::build_file_tree(&target_path, base_path, include_ignored)
     }
 
-    fn build_file_tree(path: &Path, base_path: &Path) -> Result<Vec<FileNode>, String> {
-        let mut nodes = Vec::new();
-        
-        let entries = std::fs::read_dir(path)
-            .map_err(|e| format!("Failed to read directory: {}", e))?;
+    /// Walk `root` honouring `.gitignore` files, including nested ones and
+    /// those in parent directories. Hidden files are skipped unless
+    /// `include_ignored` is set; `.git` is always skipped.
+    fn walk_workspace(root: &Path, include_ignored: bool) -> ignore::Walk {
+        ignore::WalkBuilder::new(root)
+            .hidden(!include_ignored)
+            .git_ignore(!include_ignored)
+            .git_exclude(!include_ignored)
+            .git_global(false)
+            .ignore(!include_ignored)
+            .parents(!include_ignored)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| {
+                let name = entry.file_name().to_string_lossy();
+                if name == ".git" {
+                    return false;
+                }
+                include_ignored || !DEFAULT_IGNORED_DIRS.contains(&name.as_ref())
+            })
+            .build()
+    }
 
-        for entry in entries {
-            let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-            let file_path = entry.path();
-            let file_name = entry.file_name().to_string_lossy().to_string();
+    fn build_file_tree(path: &Path, base_path: &Path, include_ignored: bool) -> Result<Vec<FileNode>, String> {
+        if !path.is_dir() {
+            return Err(format!("Failed to read directory: {}", path.display()));
+        }
 
-            // Skip hidden files and common ignore patterns
-            if file_name.starts_with('.') || 
-               file_name == "node_modules" || 
-               file_name == "target" ||
-               file_name == "dist" ||
-               file_name == "__pycache__" {
-                continue;
-            }
+        // Unreadable entries are left out rather than failing the whole tree
+        let mut entries: Vec<ignore::DirEntry> = Self::walk_workspace(path, include_ignored)
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.depth() > 0)
+            .collect();
+
+        // Deepest first, so every directory's children are complete before it is built
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.depth()));
 
+        let mut children_by_dir: HashMap<PathBuf, Vec<FileNode>> = HashMap::new();
+        for entry in entries {
+            let file_path = entry.path().to_path_buf();
             let metadata = entry.metadata()
                 .map_err(|e| format!("Failed to get metadata: {}", e))?;
 
@@ -428,7 +950,9 @@ impl CliService {
 
             let is_dir = metadata.is_dir();
             let children = if is_dir {
-                Some(Self::build_file_tree(&file_path, base_path)?)
+                let mut children = children_by_dir.remove(&file_path).unwrap_or_default();
+                Self::sort_file_nodes(&mut children);
+                Some(children)
             } else {
                 None
             };
@@ -439,18 +963,28 @@ impl CliService {
                 file_path.extension().map(|e| e.to_string_lossy().to_string())
             };
 
-            nodes.push(FileNode {
-                name: file_name,
+            let node = FileNode {
+                name: entry.file_name().to_string_lossy().to_string(),
                 path: relative_path,
                 is_dir,
                 children,
                 size: if is_dir { None } else { Some(metadata.len()) },
                 modified: None, // Could add timestamp
                 extension,
-            });
+            };
+
+            let parent = file_path.parent().map(Path::to_path_buf).unwrap_or_default();
+            children_by_dir.entry(parent).or_default().push(node);
         }
 
-        // Sort: directories first, then alphabetically
+        let mut nodes = children_by_dir.remove(path).unwrap_or_default();
+        Self::sort_file_nodes(&mut nodes);
+
+        Ok(nodes)
+    }
+
+    /// Sort: directories first, then alphabetically
+    fn sort_file_nodes(nodes: &mut [FileNode]) {
         nodes.sort_by(|a, b| {
             match (a.is_dir, b.is_dir) {
                 (true, false) => std::cmp::Ordering::Less,
@@ -458,81 +992,80 @@ impl CliService {
                 _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
             }
         });
-
-        Ok(nodes)
     }
 
-    pub async fn search_files(&self, query: &str, file_pattern: Option<&str>) -> Result<Vec<SearchResult>, String> {
+    pub async fn search_files(
+        &self,
+        query: &str,
+        file_pattern: Option<&str>,
+        include_ignored: bool,
+    ) -> Result<Vec<SearchResult>, String> {
         let workspace = self.workspace_path.lock().await;
         let base_path = workspace.as_ref().ok_or("No workspace set")?;
         
         let mut results = Vec::new();
-        Self::search_in_dir(base_path, base_path, query, file_pattern, &mut results)?;
+        Self::search_in_dir(base_path, query, file_pattern, include_ignored, &mut results);
         
         Ok(results)
     }
 
     fn search_in_dir(
-        path: &Path,
         base_path: &Path,
         query: &str,
         file_pattern: Option<&str>,
+        include_ignored: bool,
         results: &mut Vec<SearchResult>,
-    ) -> Result<(), String> {
-        let entries = std::fs::read_dir(path)
-            .map_err(|e| format!("Failed to read directory: {}", e))?;
+    ) {
+        let query_lower = query.to_lowercase();
+
+        for entry in Self::walk_workspace(base_path, include_ignored).filter_map(|e| e.ok()) {
+            if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                continue;
+            }
 
-        for entry in entries {
-            let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
             let file_path = entry.path();
             let file_name = entry.file_name().to_string_lossy().to_string();
 
-            // Skip hidden and ignored
-            if file_name.starts_with('.') || 
-               file_name == "node_modules" || 
-               file_name == "target" {
+            // Check file pattern
+            if let Some(pattern) = file_pattern {
+                if !file_name.ends_with(pattern) && !file_name.contains(pattern) {
+                    continue;
+                }
+            }
+
+            // Search in file, skipping binaries
+            let Ok(bytes) = std::fs::read(file_path) else {
+                continue;
+            };
+            if is_binary_content(&bytes) {
                 continue;
             }
 
-            if file_path.is_dir() {
-                Self::search_in_dir(&file_path, base_path, query, file_pattern, results)?;
-            } else {
-                // Check file pattern
-                if let Some(pattern) = file_pattern {
-                    if !file_name.ends_with(pattern) && !file_name.contains(pattern) {
-                        continue;
-                    }
-                }
+            if let Ok(content) = String::from_utf8(bytes) {
+                let relative_path = file_path
+                    .strip_prefix(base_path)
+                    .unwrap_or(file_path)
+                    .to_string_lossy()
+                    .to_string();
+
+                for (line_no, line) in content.lines().enumerate() {
+                    if let Some(pos) = line.to_lowercase().find(&query_lower) {
+                        results.push(SearchResult {
+                            file_path: relative_path.clone(),
+                            line_number: (line_no + 1) as i32,
+                            line_content: line.to_string(),
+                            match_start: pos as i32,
+                            match_end: (pos + query.len()) as i32,
+                        });
 
-                // Search in file
-                if let Ok(content) = std::fs::read_to_string(&file_path) {
-                    let relative_path = file_path
-                        .strip_prefix(base_path)
-                        .unwrap_or(&file_path)
-                        .to_string_lossy()
-                        .to_string();
-
-                    for (line_no, line) in content.lines().enumerate() {
-                        if let Some(pos) = line.to_lowercase().find(&query.to_lowercase()) {
-                            results.push(SearchResult {
-                                file_path: relative_path.clone(),
-                                line_number: (line_no + 1) as i32,
-                                line_content: line.to_string(),
-                                match_start: pos as i32,
-                                match_end: (pos + query.len()) as i32,
-                            });
-
-                            // Limit results per file
-                            if results.len() >= 100 {
-                                return Ok(());
-                            }
+                        // Limit total results
+                        if results.len() >= 100 {
+                            return;
                         }
                     }
                 }
             }
         }
-
-        Ok(())
     }
 
     // ============================================
@@ -540,148 +1073,392 @@ impl CliService {
     // ============================================
 
     pub fn generate_diff(original: &str, modified: &str) -> Vec<DiffHunk> {
-        let original_lines: Vec<&str> = original.lines().collect();
-        let modified_lines: Vec<&str> = modified.lines().collect();
-        
-        let mut hunks = Vec::new();
-        let mut current_hunk: Option<DiffHunk> = None;
-        let mut old_line = 1i32;
-        let mut new_line = 1i32;
+        compute_diff(original, modified, DEFAULT_DIFF_CONTEXT)
+    }
 
-        // Simple line-by-line diff (could use more sophisticated algorithm)
-        let max_lines = original_lines.len().max(modified_lines.len());
-        
-        for i in 0..max_lines {
-            let old_content = original_lines.get(i).copied();
-            let new_content = modified_lines.get(i).copied();
-
-            match (old_content, new_content) {
-                (Some(old), Some(new)) if old == new => {
-                    // Context line
-                    if let Some(ref mut hunk) = current_hunk {
-                        hunk.lines.push(DiffLine {
-                            line_type: DiffLineType::Context,
-                            content: old.to_string(),
-                            old_line_no: Some(old_line),
-                            new_line_no: Some(new_line),
-                        });
-                    }
-                    old_line += 1;
-                    new_line += 1;
-                }
-                (Some(old), Some(new)) => {
-                    // Changed line
-                    let hunk = current_hunk.get_or_insert_with(|| DiffHunk {
-                        old_start: old_line,
-                        old_lines: 0,
-                        new_start: new_line,
-                        new_lines: 0,
-                        lines: Vec::new(),
-                    });
-                    
-                    hunk.lines.push(DiffLine {
-                        line_type: DiffLineType::Deletion,
-                        content: old.to_string(),
-                        old_line_no: Some(old_line),
-                        new_line_no: None,
-                    });
-                    hunk.old_lines += 1;
-                    
-                    hunk.lines.push(DiffLine {
-                        line_type: DiffLineType::Addition,
-                        content: new.to_string(),
-                        old_line_no: None,
-                        new_line_no: Some(new_line),
-                    });
-                    hunk.new_lines += 1;
-                    
-                    old_line += 1;
-                    new_line += 1;
-                }
-                (Some(old), None) => {
-                    // Deleted line
-                    let hunk = current_hunk.get_or_insert_with(|| DiffHunk {
-                        old_start: old_line,
-                        old_lines: 0,
-                        new_start: new_line,
-                        new_lines: 0,
-                        lines: Vec::new(),
-                    });
-                    
-                    hunk.lines.push(DiffLine {
-                        line_type: DiffLineType::Deletion,
-                        content: old.to_string(),
-                        old_line_no: Some(old_line),
-                        new_line_no: None,
-                    });
-                    hunk.old_lines += 1;
-                    old_line += 1;
-                }
-                (None, Some(new)) => {
-                    // Added line
-                    let hunk = current_hunk.get_or_insert_with(|| DiffHunk {
-                        old_start: old_line,
-                        old_lines: 0,
-                        new_start: new_line,
-                        new_lines: 0,
-                        lines: Vec::new(),
-                    });
-                    
-                    hunk.lines.push(DiffLine {
-                        line_type: DiffLineType::Addition,
-                        content: new.to_string(),
-                        old_line_no: None,
-                        new_line_no: Some(new_line),
-                    });
-                    hunk.new_lines += 1;
-                    new_line += 1;
-                }
-                (None, None) => break,
+    /// Diff a workspace file against proposed content; a missing file diffs as empty
+    pub async fn diff_file(&self, path: &str, modified: &str, context: usize) -> Result<Vec<DiffHunk>, String> {
+        let original = self.read_text_or_empty(path).await?;
+        Ok(compute_diff(&original, modified, context))
+    }
+
+    /// Apply hunks to a workspace file, refusing if the file changed since the diff
+    pub async fn apply_diff_to_file(&self, path: &str, hunks: &[DiffHunk]) -> Result<FileContent, String> {
+        let original = self.read_text_or_empty(path).await?;
+        let patched = apply_diff(&original, hunks)
+            .map_err(|e| format!("Cannot apply diff to {}: {}", path, e))?;
+        self.write_file(path, &patched).await?;
+        self.read_file(path).await
+    }
+
+    async fn read_text_or_empty(&self, path: &str) -> Result<String, String> {
+        match self.read_file(path).await {
+            Ok(file) if file.is_binary => Err(format!("Cannot diff binary file: {}", path)),
+            Ok(file) => Ok(file.content),
+            Err(e) if e.starts_with("File not found") => Ok(String::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // ============================================
+    // Language Detection
+    // ============================================
+
+    fn detect_language(path: &Path) -> String {
+        // Extensionless files like `Dockerfile` are matched by name
+        let key = path
+            .extension()
+            .or_else(|| path.file_name())
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        LANGUAGE_BY_EXTENSION.iter()
+            .find(|(ext, _)| *ext == key)
+            .map(|(_, language)| *language)
+            .unwrap_or("plaintext")
+            .to_string()
+    }
+}
+
+/// Fingerprint of every non-ignored workspace file, keyed by relative path
+fn snapshot_workspace(root: &Path) -> HashMap<String, u64> {
+    use std::hash::{Hash, Hasher};
+
+    CliService::walk_workspace(root, false)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .filter_map(|entry| {
+            let bytes = std::fs::read(entry.path()).ok()?;
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            bytes.hash(&mut hasher);
+            let relative = entry.path().strip_prefix(root).ok()?.to_string_lossy().to_string();
+            Some((relative, hasher.finish()))
+        })
+        .collect()
+}
+
+/// Files added, changed or removed between two snapshots, sorted
+fn changed_files(before: &HashMap<String, u64>, after: &HashMap<String, u64>) -> Vec<String> {
+    let mut changed: Vec<String> = after.iter()
+        .filter(|(path, hash)| before.get(*path) != Some(*hash))
+        .map(|(path, _)| path.clone())
+        .chain(before.keys().filter(|path| !after.contains_key(*path)).cloned())
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// Binary if there's a NUL byte near the start or the content isn't valid UTF-8
+fn is_binary_content(bytes: &[u8]) -> bool {
+    let sniff = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+    sniff.contains(&0) || std::str::from_utf8(bytes).is_err()
+}
+
+/// Count lines, including a final line that has no trailing newline
+fn count_lines(content: &str) -> i32 {
+    if content.is_empty() {
+        return 0;
+    }
+    let newlines = content.matches('\n').count();
+    (newlines + usize::from(!content.ends_with('\n'))) as i32
+}
+
+// ============================================
+// Diff Computation
+// ============================================
+
+/// Unchanged lines shown around each change, as in `diff -u`
+pub const DEFAULT_DIFF_CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// One step of an edit script; indices are cursor positions before the step
+#[derive(Debug, Clone, Copy)]
+struct Edit {
+    kind: EditKind,
+    old_index: usize,
+    new_index: usize,
+}
+
+/// Split into lines that keep their `\n`, so a missing final newline is a difference
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+pub(crate) fn diff_line(kind: DiffLineType, raw: &str, old_line_no: Option<i32>, new_line_no: Option<i32>) -> DiffLine {
+    DiffLine {
+        line_type: kind,
+        content: raw.strip_suffix('\n').unwrap_or(raw).to_string(),
+        old_line_no,
+        new_line_no,
+        no_newline: !raw.ends_with('\n'),
+    }
+}
+
+fn raw_line(line: &DiffLine) -> String {
+    if line.no_newline {
+        line.content.clone()
+    } else {
+        format!("{}\n", line.content)
+    }
+}
+
+/// Shortest edit script between two line lists (Myers, O((N+M)D))
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; (2 * max + 3) as usize];
+    // trace[d] holds the furthest x for diagonals -d..=d after round d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+                break 'search;
             }
+            k += 2;
         }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (0..trace.len() as isize).rev() {
+        let k = x - y;
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let prev = &trace[(d - 1) as usize];
+            let at = |k: isize| prev[(k + d - 1) as usize];
+            let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+            (at(prev_k), at(prev_k) - prev_k)
+        };
 
-        if let Some(hunk) = current_hunk {
-            if !hunk.lines.is_empty() {
-                hunks.push(hunk);
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit { kind: EditKind::Equal, old_index: x as usize, new_index: y as usize });
+        }
+        if d > 0 {
+            if x == prev_x {
+                y -= 1;
+                edits.push(Edit { kind: EditKind::Insert, old_index: x as usize, new_index: y as usize });
+            } else {
+                x -= 1;
+                edits.push(Edit { kind: EditKind::Delete, old_index: x as usize, new_index: y as usize });
             }
         }
+    }
+
+    edits.reverse();
+    edits
+}
+
+/// Compute unified-diff hunks with `context` unchanged lines around each change
+pub fn compute_diff(original: &str, modified: &str, context: usize) -> Vec<DiffHunk> {
+    let old = split_lines(original);
+    let new = split_lines(modified);
+    let edits = edit_script(&old, &new);
+
+    let changes: Vec<usize> = edits.iter()
+        .enumerate()
+        .filter(|(_, edit)| edit.kind != EditKind::Equal)
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < changes.len() {
+        // Merge changes whose separating context would overlap
+        let mut last = changes[i];
+        let mut j = i + 1;
+        while j < changes.len() && changes[j] - last - 1 <= 2 * context {
+            last = changes[j];
+            j += 1;
+        }
+
+        let start = changes[i].saturating_sub(context);
+        let end = (last + context + 1).min(edits.len());
+        let span = &edits[start..end];
 
-        hunks
+        let mut hunk = DiffHunk {
+            old_start: span[0].old_index as i32,
+            old_lines: 0,
+            new_start: span[0].new_index as i32,
+            new_lines: 0,
+            lines: Vec::with_capacity(span.len()),
+        };
+        for edit in span {
+            let old_no = Some(edit.old_index as i32 + 1);
+            let new_no = Some(edit.new_index as i32 + 1);
+            let line = match edit.kind {
+                EditKind::Equal => diff_line(DiffLineType::Context, old[edit.old_index], old_no, new_no),
+                EditKind::Delete => diff_line(DiffLineType::Deletion, old[edit.old_index], old_no, None),
+                EditKind::Insert => diff_line(DiffLineType::Addition, new[edit.new_index], None, new_no),
+            };
+            if edit.kind != EditKind::Insert {
+                hunk.old_lines += 1;
+            }
+            if edit.kind != EditKind::Delete {
+                hunk.new_lines += 1;
+            }
+            hunk.lines.push(line);
+        }
+        // Unified diff numbers an empty side by the line it follows
+        if hunk.old_lines > 0 {
+            hunk.old_start += 1;
+        }
+        if hunk.new_lines > 0 {
+            hunk.new_start += 1;
+        }
+
+        hunks.push(hunk);
+        i = j;
     }
 
-    // ============================================
-    // Language Detection
-    // ============================================
+    hunks
+}
+
+/// Apply hunks produced by `compute_diff`, failing if context or deleted lines no longer match
+pub fn apply_diff(original: &str, hunks: &[DiffHunk]) -> Result<String, String> {
+    let old = split_lines(original);
+    let mut output = String::with_capacity(original.len());
+    let mut cursor = 0usize;
+
+    for (n, hunk) in hunks.iter().enumerate() {
+        let start = if hunk.old_lines == 0 { hunk.old_start } else { hunk.old_start - 1 };
+        if start < 0 || (start as usize) < cursor || start as usize > old.len() {
+            return Err(format!("hunk {} starts at invalid line {}", n + 1, hunk.old_start));
+        }
+
+        for line in &old[cursor..start as usize] {
+            output.push_str(line);
+        }
+        cursor = start as usize;
+
+        for line in &hunk.lines {
+            let raw = raw_line(line);
+            match line.line_type {
+                DiffLineType::Addition => output.push_str(&raw),
+                DiffLineType::Context | DiffLineType::Deletion => {
+                    if old.get(cursor) != Some(&raw.as_str()) {
+                        return Err(format!(
+                            "hunk {} does not match line {}; the file has changed",
+                            n + 1,
+                            cursor + 1
+                        ));
+                    }
+                    if line.line_type == DiffLineType::Context {
+                        output.push_str(&raw);
+                    }
+                    cursor += 1;
+                }
+            }
+        }
+    }
 
-    fn detect_language(extension: &str) -> String {
-        match extension.to_lowercase().as_str() {
-            "rs" => "rust",
-            "ts" | "tsx" => "typescript",
-            "js" | "jsx" => "javascript",
-            "py" => "python",
-            "go" => "go",
-            "java" => "java",
-            "c" | "h" => "c",
-            "cpp" | "cc" | "hpp" => "cpp",
-            "cs" => "csharp",
-            "rb" => "ruby",
-            "php" => "php",
-            "swift" => "swift",
-            "kt" | "kts" => "kotlin",
-            "scala" => "scala",
-            "html" | "htm" => "html",
-            "css" | "scss" | "sass" | "less" => "css",
-            "json" => "json",
-            "yaml" | "yml" => "yaml",
-            "toml" => "toml",
-            "xml" => "xml",
-            "md" | "markdown" => "markdown",
-            "sql" => "sql",
-            "sh" | "bash" | "zsh" => "shell",
-            "dockerfile" => "dockerfile",
-            _ => "plaintext",
-        }.to_string()
+    for line in &old[cursor..] {
+        output.push_str(line);
     }
+
+    Ok(output)
+}
+
+// ============================================
+// Fuzzy Matching
+// ============================================
+
+/// Case-insensitive match score, higher is better: prefix, then substring,
+/// then subsequence, then a prefix within a small edit distance
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let extra = candidate.len().saturating_sub(query.len()).min(100) as i64;
+
+    if query.is_empty() {
+        return Some(100 - extra);
+    }
+    if candidate.starts_with(&query) {
+        return Some(1000 - extra);
+    }
+    if let Some(pos) = candidate.windows(query.len()).position(|w| w == query.as_slice()) {
+        return Some(800 - pos.min(100) as i64 - extra);
+    }
+
+    // Subsequence: every query char in order, penalised by the gaps between them
+    let mut gaps = 0i64;
+    let mut last: Option<usize> = None;
+    let mut rest = candidate.iter().enumerate();
+    let is_subsequence = query.iter().all(|q| {
+        rest.by_ref().find(|(_, c)| *c == q).map(|(i, _)| {
+            gaps += last.map(|l| (i - l - 1) as i64).unwrap_or(i as i64);
+            last = Some(i);
+        }).is_some()
+    });
+    if is_subsequence {
+        return Some(600 - gaps.min(200) - extra);
+    }
+
+    // Typos: compare against the candidate prefix of the same length
+    let max_typos = match query.len() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    };
+    let prefix = &candidate[..query.len().min(candidate.len())];
+    let distance = edit_distance(&query, prefix);
+    if distance <= max_typos {
+        return Some(400 - 100 * distance as i64 - extra);
+    }
+
+    None
+}
+
+/// Optimal string alignment distance (Levenshtein plus adjacent transpositions)
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    rows[a.len()][b.len()]
 }
 
 // ============================================
@@ -721,3 +1498,438 @@ pub fn get_help_text() -> String {
 ╚═══════════════════════════════════════════════════════════════════╝
 "#.to_string()
 }
+
+// ============================================
+// Tests
+// ============================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// Workspace with a root and a nested `.gitignore`
+    fn fixture_workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".gitignore", "build/\n*.log\n");
+        write(root, ".git/HEAD", "ref: refs/heads/main needle\n");
+        write(root, "README.md", "needle in readme\n");
+        write(root, "src/main.rs", "fn main() {} // needle\n");
+        write(root, "src/debug.log", "needle in log\n");
+        write(root, "src/.gitignore", "generated.rs\n");
+        write(root, "src/generated.rs", "// needle generated\n");
+        write(root, "build/out.txt", "needle in build\n");
+        write(root, "node_modules/pkg/index.js", "// needle\n");
+        dir
+    }
+
+    fn flatten(nodes: &[FileNode], out: &mut Vec<String>) {
+        for node in nodes {
+            out.push(node.path.clone());
+            if let Some(children) = &node.children {
+                flatten(children, out);
+            }
+        }
+    }
+
+    async fn tree_paths(service: &CliService, include_ignored: bool) -> Vec<String> {
+        let mut paths = Vec::new();
+        flatten(&service.list_files(None, include_ignored).await.unwrap(), &mut paths);
+        paths
+    }
+
+    fn implement(instruction: &str, files: &[&str]) -> CliCommand {
+        CliCommand::Implement {
+            instruction: instruction.to_string(),
+            files: files.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let cases = vec![
+            ("/spec user login flow", CliCommand::Spec { description: "user login flow".to_string() }),
+            ("/SPEC  'quoted   spacing'", CliCommand::Spec { description: "quoted   spacing".to_string() }),
+            ("/plan migrate db", CliCommand::Plan { task: "migrate db".to_string() }),
+            ("/tasks", CliCommand::Tasks { filter: None }),
+            ("/tasks blocked", CliCommand::Tasks { filter: Some("blocked".to_string()) }),
+            ("/implement \"add auth\" src/a.rs src/b.rs", implement("add auth", &["src/a.rs", "src/b.rs"])),
+            ("/implement add auth to main.rs", implement("add auth to", &["main.rs"])),
+            ("/impl \"update main.rs docs\" lib.rs", implement("update main.rs docs", &["lib.rs"])),
+            ("/implement refactor \"my dir/file.ts\"", implement("refactor my dir/file.ts", &[])),
+            ("/implement fix src/a\\ b.rs", implement("fix", &["src/a b.rs"])),
+            ("/implement fix src\\app.rs", implement("fix", &["src\\app.rs"])),
+            ("/debug", CliCommand::Debug { error: None, file: None }),
+            ("/debug \"index out of bounds\" in src/lib.rs", CliCommand::Debug {
+                error: Some("index out of bounds".to_string()),
+                file: Some("src/lib.rs".to_string()),
+            }),
+            ("/debug null pointer", CliCommand::Debug { error: Some("null pointer".to_string()), file: None }),
+            ("/debug src/main.rs", CliCommand::Debug { error: None, file: Some("src/main.rs".to_string()) }),
+            ("/debug \"fails in prod\"", CliCommand::Debug { error: Some("fails in prod".to_string()), file: None }),
+            ("/review", CliCommand::Review { files: vec![] }),
+            ("/review a.rs 'b c.rs'", CliCommand::Review { files: vec!["a.rs".to_string(), "b c.rs".to_string()] }),
+            ("/ask what does this do?", CliCommand::Ask { question: "what does this do?".to_string() }),
+            ("/help", CliCommand::Help),
+            ("/?", CliCommand::Help),
+            ("how is auth wired?", CliCommand::Ask { question: "how is auth wired?".to_string() }),
+            ("  leading space question  ", CliCommand::Ask { question: "leading space question".to_string() }),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(CliCommand::parse(input).unwrap(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_command_errors() {
+        let cases = [
+            ("", "Empty command"),
+            ("   ", "Empty command"),
+            ("/deploy now", "Unknown command: /deploy"),
+            ("/spec", "Usage: /spec <description>"),
+            ("/plan   ", "Usage: /plan <task>"),
+            ("/ask", "Usage: /ask <question>"),
+            ("/implement src/a.rs", "Missing instruction"),
+            ("/debug crash in", "Missing file after 'in'"),
+            ("/spec \"unterminated", "Unterminated \" quote"),
+        ];
+
+        for (input, expected) in cases {
+            let err = CliCommand::parse(input).unwrap_err();
+            assert!(err.contains(expected), "input: {:?}, error: {}", input, err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_tree_respects_gitignore() {
+        let dir = fixture_workspace();
+        let service = CliService::new();
+        service.set_workspace(&dir.path().to_string_lossy()).await.unwrap();
+
+        let paths = tree_paths(&service, false).await;
+        assert_eq!(paths, vec!["src", "src/main.rs", "README.md"]);
+
+        let all = tree_paths(&service, true).await;
+        for expected in ["build/out.txt", "src/debug.log", "src/generated.rs", "node_modules/pkg/index.js", ".gitignore"] {
+            assert!(all.iter().any(|p| p == expected), "missing {}", expected);
+        }
+        assert!(all.iter().all(|p| !p.starts_with(".git/") && p != ".git"));
+    }
+
+    #[tokio::test]
+    async fn test_read_file_detects_binary_and_language() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "lib.rs", "fn a() {}\nfn b() {}");
+        write(root, "notes.md", "# Title\n\nbody\n");
+        write(root, "Dockerfile", "FROM rust\n");
+        std::fs::write(root.join("image.png"), [0x89, b'P', b'N', b'G', 0x00, 0x01]).unwrap();
+        std::fs::write(root.join("latin1.txt"), [b'c', b'a', b'f', 0xE9]).unwrap();
+
+        let service = CliService::new();
+        service.set_workspace(&root.to_string_lossy()).await.unwrap();
+
+        let rust = service.read_file("lib.rs").await.unwrap();
+        assert!(!rust.is_binary);
+        assert_eq!(rust.language, "rust");
+        assert_eq!(rust.line_count, 2);
+
+        let markdown = service.read_file("notes.md").await.unwrap();
+        assert_eq!(markdown.language, "markdown");
+        assert_eq!(markdown.line_count, 3);
+
+        assert_eq!(service.read_file("Dockerfile").await.unwrap().language, "dockerfile");
+
+        for binary in ["image.png", "latin1.txt"] {
+            let file = service.read_file(binary).await.unwrap();
+            assert!(file.is_binary, "{} should be binary", binary);
+            assert!(file.content.is_empty());
+            assert!(file.size > 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_skips_binary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "a.txt", "needle\n");
+        let mut binary = b"needle".to_vec();
+        binary.push(0);
+        std::fs::write(root.join("b.bin"), binary).unwrap();
+
+        let service = CliService::new();
+        service.set_workspace(&root.to_string_lossy()).await.unwrap();
+
+        let results = service.search_files("needle", None, false).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].file_path, "a.txt");
+    }
+
+    #[test]
+    fn test_count_lines() {
+        assert_eq!(count_lines(""), 0);
+        assert_eq!(count_lines("one"), 1);
+        assert_eq!(count_lines("one\n"), 1);
+        assert_eq!(count_lines("one\ntwo"), 2);
+        assert_eq!(count_lines("\n\n"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_respects_gitignore() {
+        let dir = fixture_workspace();
+        let service = CliService::new();
+        service.set_workspace(&dir.path().to_string_lossy()).await.unwrap();
+
+        let mut files: Vec<String> = service.search_files("needle", None, false).await.unwrap()
+            .into_iter()
+            .map(|r| r.file_path)
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["README.md", "src/main.rs"]);
+
+        let all: Vec<String> = service.search_files("needle", None, true).await.unwrap()
+            .into_iter()
+            .map(|r| r.file_path)
+            .collect();
+        assert!(all.iter().any(|f| f == "build/out.txt"));
+        assert!(all.iter().all(|f| !f.starts_with(".git/")));
+    }
+
+    #[test]
+    fn test_diff_round_trip() {
+        let cases = [
+            ("", ""),
+            ("", "a\nb\n"),
+            ("a\nb\n", ""),
+            ("a\nb\nc\n", "a\nb\nc\n"),
+            ("a\nb\nc\n", "a\nx\nc\n"),
+            ("a\nb\nc", "a\nb\nc\n"),
+            ("a\nb\nc\n", "a\nb\nc"),
+            ("a\r\nb\r\n", "a\r\nc\r\n"),
+            ("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n", "0\n1\n2\n3\n5\n6\n7\n8\n9\n10\n11\n"),
+            ("x\ny\nz\n", "z\ny\nx\n"),
+        ];
+
+        for (a, b) in cases {
+            for context in [0, 1, DEFAULT_DIFF_CONTEXT] {
+                let hunks = compute_diff(a, b, context);
+                assert_eq!(apply_diff(a, &hunks).unwrap(), b, "{:?} -> {:?} (context {})", a, b, context);
+            }
+        }
+    }
+
+    #[test]
+    fn test_diff_round_trip_generated() {
+        // Deterministic LCG so failures reproduce
+        let mut seed = 42u64;
+        let mut next = move |bound: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+
+        for _ in 0..200 {
+            let a: String = (0..next(30)).map(|_| format!("{}\n", next(6))).collect();
+            let b: String = (0..next(30)).map(|_| format!("{}\n", next(6))).collect();
+            let hunks = compute_diff(&a, &b, next(4) as usize);
+            assert_eq!(apply_diff(&a, &hunks).unwrap(), b);
+        }
+    }
+
+    #[test]
+    fn test_diff_hunk_positions() {
+        let original: String = (1..=20).map(|n| format!("line {}\n", n)).collect();
+        let modified = original
+            .replace("line 3\n", "line three\n")
+            .replace("line 18\n", "");
+
+        let hunks = compute_diff(&original, &modified, 2);
+        assert_eq!(hunks.len(), 2);
+
+        let first = &hunks[0];
+        assert_eq!((first.old_start, first.old_lines, first.new_start, first.new_lines), (1, 5, 1, 5));
+        let kinds: Vec<DiffLineType> = first.lines.iter().map(|l| l.line_type).collect();
+        assert_eq!(kinds, vec![
+            DiffLineType::Context,
+            DiffLineType::Context,
+            DiffLineType::Deletion,
+            DiffLineType::Addition,
+            DiffLineType::Context,
+            DiffLineType::Context,
+        ]);
+        assert_eq!(first.lines[2].content, "line 3");
+        assert_eq!(first.lines[2].old_line_no, Some(3));
+        assert_eq!(first.lines[3].new_line_no, Some(3));
+
+        let second = &hunks[1];
+        assert_eq!((second.old_start, second.old_lines, second.new_start, second.new_lines), (16, 5, 16, 4));
+        assert_eq!(second.lines[2].line_type, DiffLineType::Deletion);
+        assert_eq!(second.lines[2].old_line_no, Some(18));
+
+        // A wider radius merges both changes into one hunk
+        assert_eq!(compute_diff(&original, &modified, 7).len(), 1);
+        assert!(compute_diff(&original, &original, 3).is_empty());
+    }
+
+    #[test]
+    fn test_diff_insert_into_empty_side() {
+        let hunks = compute_diff("a\nb\n", "a\nnew\nb\n", 0);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!((hunks[0].old_start, hunks[0].old_lines, hunks[0].new_start, hunks[0].new_lines), (1, 0, 2, 1));
+
+        let hunks = compute_diff("a\n", "a", 3);
+        assert!(hunks[0].lines.iter().any(|l| l.no_newline));
+    }
+
+    #[test]
+    fn test_apply_diff_rejects_changed_original() {
+        let hunks = compute_diff("a\nb\nc\n", "a\nB\nc\n", 1);
+        let err = apply_diff("a\nchanged\nc\n", &hunks).unwrap_err();
+        assert!(err.contains("hunk 1"), "{}", err);
+        assert!(apply_diff("", &hunks).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_diff_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "src/lib.rs", "fn a() {}\nfn b() {}\n");
+
+        let service = CliService::new();
+        service.set_workspace(&dir.path().to_string_lossy()).await.unwrap();
+
+        let target = "fn a() {}\nfn b() { todo!() }\nfn c() {}\n";
+        let hunks = service.diff_file("src/lib.rs", target, DEFAULT_DIFF_CONTEXT).await.unwrap();
+        let updated = service.apply_diff_to_file("src/lib.rs", &hunks).await.unwrap();
+        assert_eq!(updated.content, target);
+
+        // The same hunks no longer apply once the file has moved on
+        let err = service.apply_diff_to_file("src/lib.rs", &hunks).await.unwrap_err();
+        assert!(err.contains("src/lib.rs"), "{}", err);
+
+        let created = service.diff_file("src/new.rs", "fn new() {}\n", 3).await.unwrap();
+        service.apply_diff_to_file("src/new.rs", &created).await.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("src/new.rs")).unwrap(), "fn new() {}\n");
+    }
+
+    #[test]
+    fn test_fuzzy_score_ordering() {
+        let prefix = fuzzy_score("imp", "implement").unwrap();
+        let substring = fuzzy_score("ple", "implement").unwrap();
+        let subsequence = fuzzy_score("impt", "implement").unwrap();
+        let typo = fuzzy_score("imlpement", "implement").unwrap();
+        assert!(prefix > substring && substring > subsequence && subsequence > typo);
+
+        assert!(fuzzy_score("IMP", "implement").is_some());
+        assert!(fuzzy_score("xyz", "implement").is_none());
+        assert!(fuzzy_score("revew", "review").is_some());
+        assert!(fuzzy_score("sp", "plan").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_input_suggestions_commands_and_history() {
+        let service = CliService::new();
+
+        let suggestions = service.get_input_suggestions("imp", DEFAULT_INPUT_SUGGESTION_LIMIT).await;
+        assert_eq!(suggestions[0].label, "/implement");
+        assert_eq!(suggestions[0].value, "/implement ");
+        assert_eq!(suggestions[0].kind, InputSuggestionKind::Command);
+
+        let typo = service.get_input_suggestions("/implment", DEFAULT_INPUT_SUGGESTION_LIMIT).await;
+        assert_eq!(typo[0].label, "/implement");
+
+        service.add_to_history("/review src/old.rs").await;
+        service.add_to_history("/review src/main.rs").await;
+        let suggestions = service.get_input_suggestions("/rev", DEFAULT_INPUT_SUGGESTION_LIMIT).await;
+        let history: Vec<&str> = suggestions.iter()
+            .filter(|s| s.kind == InputSuggestionKind::History)
+            .map(|s| s.value.as_str())
+            .collect();
+        assert_eq!(history, vec!["/review src/main.rs", "/review src/old.rs"]);
+
+        assert_eq!(service.get_input_suggestions("", 3).await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_input_suggestions_complete_paths() {
+        let dir = fixture_workspace();
+        let service = CliService::new();
+        service.set_workspace(&dir.path().to_string_lossy()).await.unwrap();
+
+        let suggestions = service.get_input_suggestions("/review src/ma", DEFAULT_INPUT_SUGGESTION_LIMIT).await;
+        assert_eq!(suggestions[0].value, "/review src/main.rs");
+        assert_eq!(suggestions[0].kind, InputSuggestionKind::Path);
+
+        // Only the token under the cursor is replaced
+        let suggestions = service.get_input_suggestions("/implement \"fix it\" READ", DEFAULT_INPUT_SUGGESTION_LIMIT).await;
+        assert_eq!(suggestions[0].value, "/implement \"fix it\" README.md");
+
+        // Ignored files are not offered
+        let suggestions = service.get_input_suggestions("/review gen", DEFAULT_INPUT_SUGGESTION_LIMIT).await;
+        assert!(suggestions.iter().all(|s| !s.value.contains("generated.rs")));
+
+        // Non-file commands take free text, not paths
+        let suggestions = service.get_input_suggestions("/ask src/ma", DEFAULT_INPUT_SUGGESTION_LIMIT).await;
+        assert!(suggestions.iter().all(|s| s.kind != InputSuggestionKind::Path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sandbox_path_mapping() {
+        let workspace = Path::new("/home/dev/project");
+        assert_eq!(CliService::sandbox_path(workspace, "src/main.rs").unwrap(), "/workspace/project/src/main.rs");
+        assert_eq!(CliService::sandbox_path(workspace, "./README.md").unwrap(), "/workspace/project/README.md");
+        assert_eq!(CliService::sandbox_path(workspace, "/home/dev/project/a/b.ts").unwrap(), "/workspace/project/a/b.ts");
+        assert_eq!(CliService::sandbox_path(workspace, ".").unwrap(), "/workspace/project");
+        assert!(CliService::sandbox_path(workspace, "../secrets").is_err());
+        assert!(CliService::sandbox_path(workspace, "/etc/passwd").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_steps_on_host_without_sandbox() {
+        let dir = fixture_workspace();
+        let service = CliService::new();
+        service.set_workspace(&dir.path().to_string_lossy()).await.unwrap();
+        assert_eq!(service.execution_target().await, Ok(ExecutionTarget::Host));
+
+        let steps = vec![
+            "echo hello".to_string(),
+            "echo warn >&2; echo changed >> README.md; echo new > src/new.rs".to_string(),
+            "echo ignored > build/skip.txt".to_string(),
+        ];
+        let report = service.execute_steps(&steps).await.unwrap();
+        assert!(report.success);
+        assert_eq!(report.target, ExecutionTarget::Host);
+        assert_eq!(report.output[0].content, "hello\n");
+        assert!(matches!(report.output[1].block_type, OutputBlockType::Warning));
+        assert_eq!(report.files_modified, vec!["README.md", "src/new.rs"]);
+
+        // A failing step stops the run and later steps never execute
+        let steps = vec!["rm src/main.rs; exit 3".to_string(), "touch after.txt".to_string()];
+        let report = service.execute_steps(&steps).await.unwrap();
+        assert!(!report.success);
+        assert!(report.output.last().unwrap().content.contains("exit code 3"));
+        assert_eq!(report.files_modified, vec!["src/main.rs"]);
+        assert!(!dir.path().join("after.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stopped_sandbox_does_not_fall_back_to_host() {
+        let dir = fixture_workspace();
+        let service = CliService::new();
+        service.set_workspace(&dir.path().to_string_lossy()).await.unwrap();
+        service.set_sandbox(Some("smartspec-missing-sandbox".to_string())).await;
+
+        let err = service.execution_target().await.unwrap_err();
+        assert!(err.contains("smartspec-missing-sandbox is not running"), "{}", err);
+        assert!(service.execute_steps(&["touch ran.txt".to_string()]).await.is_err());
+        assert!(!dir.path().join("ran.txt").exists());
+    }
+}
@@ -12,9 +12,11 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::collaboration::{
-    CollaborationService, User, Comment, Review, Notification, Activity,
+    CollaborationService, User, Comment, Review, Notification, Activity, ActivityFilter,
     UserRole, UserStatus, CommentTarget, ReviewTarget, ReviewStatus, ReviewComment,
+    ReviewPolicy, ReviewSummary,
 };
+use crate::models::{Page, PageRequest};
 
 // ============================================
 // State Types
@@ -118,12 +120,14 @@ pub async fn collab_add_comment(
     target_type: String,
     target_id: String,
     content: String,
+    parent_comment_id: Option<String>,
 ) -> Result<Comment, String> {
     let mut state = state.lock().await;
     let target_type = parse_comment_target(&target_type)?;
-    state.service.add_comment(target_type, &target_id, &content)
+    state.service.add_comment(target_type, &target_id, &content, parent_comment_id.as_deref())
 }
 
+/// Root comments oldest first, each with its replies nested under `replies`
 #[tauri::command]
 pub async fn collab_get_comments(
     state: State<'_, Arc<Mutex<CollabState>>>,
@@ -132,7 +136,7 @@ pub async fn collab_get_comments(
 ) -> Result<Vec<Comment>, String> {
     let state = state.lock().await;
     let target_type = parse_comment_target(&target_type)?;
-    Ok(state.service.get_comments(target_type, &target_id).into_iter().cloned().collect())
+    Ok(state.service.get_comments(target_type, &target_id))
 }
 
 #[tauri::command]
@@ -145,6 +149,25 @@ pub async fn collab_add_reply(
     state.service.add_reply(&comment_id, &content)
 }
 
+#[tauri::command]
+pub async fn collab_edit_comment(
+    state: State<'_, Arc<Mutex<CollabState>>>,
+    comment_id: String,
+    content: String,
+) -> Result<Comment, String> {
+    let mut state = state.lock().await;
+    state.service.edit_comment(&comment_id, &content)
+}
+
+#[tauri::command]
+pub async fn collab_delete_comment(
+    state: State<'_, Arc<Mutex<CollabState>>>,
+    comment_id: String,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.service.delete_comment(&comment_id)
+}
+
 #[tauri::command]
 pub async fn collab_add_reaction(
     state: State<'_, Arc<Mutex<CollabState>>>,
@@ -164,6 +187,15 @@ pub async fn collab_resolve_comment(
     state.service.resolve_comment(&comment_id)
 }
 
+#[tauri::command]
+pub async fn collab_unresolve_comment(
+    state: State<'_, Arc<Mutex<CollabState>>>,
+    comment_id: String,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.service.unresolve_comment(&comment_id)
+}
+
 fn parse_comment_target(s: &str) -> Result<CommentTarget, String> {
     match s.to_lowercase().as_str() {
         "task" => Ok(CommentTarget::Task),
@@ -231,6 +263,31 @@ pub async fn collab_submit_review(
     state.service.submit_review(&review_id, status, comments)
 }
 
+#[tauri::command]
+pub async fn collab_set_review_policy(
+    state: State<'_, Arc<Mutex<CollabState>>>,
+    target_type: String,
+    target_id: String,
+    policy: ReviewPolicy,
+) -> Result<ReviewSummary, String> {
+    let mut state = state.lock().await;
+    let target_type = parse_review_target(&target_type)?;
+    state.service.set_review_policy(&target_type, &target_id, policy);
+    Ok(state.service.review_summary(&target_type, &target_id))
+}
+
+/// Approval counts and whether the target's review policy is satisfied
+#[tauri::command]
+pub async fn collab_get_review_summary(
+    state: State<'_, Arc<Mutex<CollabState>>>,
+    target_type: String,
+    target_id: String,
+) -> Result<ReviewSummary, String> {
+    let state = state.lock().await;
+    let target_type = parse_review_target(&target_type)?;
+    Ok(state.service.review_summary(&target_type, &target_id))
+}
+
 #[derive(serde::Deserialize)]
 pub struct ReviewCommentInput {
     pub line_number: Option<i32>,
@@ -304,13 +361,19 @@ pub async fn collab_get_unread_count(
 // Activity Commands
 // ============================================
 
+/// Newest activity first. Page with `before_id`/`after_id` from a previous
+/// page's `next_cursor`; `limit` defaults to 50.
 #[tauri::command]
 pub async fn collab_get_activity_feed(
     state: State<'_, Arc<Mutex<CollabState>>>,
-    limit: Option<usize>,
-) -> Result<Vec<Activity>, String> {
+    filter: Option<ActivityFilter>,
+    before_id: Option<String>,
+    after_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Page<Activity>, String> {
     let state = state.lock().await;
-    Ok(state.service.get_activity_feed(limit.unwrap_or(50)).into_iter().cloned().collect())
+    let page = PageRequest { before_id, after_id, limit: Some(limit.unwrap_or(50)) };
+    state.service.get_activity_feed(&filter.unwrap_or_default(), &page)
 }
 
 #[tauri::command]
@@ -320,5 +383,5 @@ pub async fn collab_get_user_activity(
     limit: Option<usize>,
 ) -> Result<Vec<Activity>, String> {
     let state = state.lock().await;
-    Ok(state.service.get_user_activity(&user_id, limit.unwrap_or(50)).into_iter().cloned().collect())
+    Ok(state.service.get_user_activity(&user_id, limit.unwrap_or(50)))
 }
@@ -1,15 +1,17 @@
 // Collaboration Service - Team Collaboration Features
 //
 // Provides:
-// - Comments and discussions
-// - Review system
+// - Threaded comments with @mentions, edits and tombstoned deletes
+// - Reviews gated by a per-target approval policy
 // - Notifications
-// - Activity feed
+// - Activity feed with typed events, filters and keyset pagination
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::models::{Page, PageCursor, PageRequest};
+
 // ============================================
 // Types
 // ============================================
@@ -53,13 +55,25 @@ pub struct Comment {
     pub content: String,
     pub mentions: Vec<String>,
     pub reactions: Vec<Reaction>,
+    /// Filled in by `get_comments`; stored comments keep this empty
     pub replies: Vec<Comment>,
+    #[serde(default)]
+    pub parent_comment_id: Option<String>,
+    /// Only meaningful on a thread's root comment
     pub resolved: bool,
     pub created_at: i64,
     pub updated_at: i64,
+    #[serde(default)]
+    pub edited_at: Option<i64>,
+    /// Deleted but kept as a placeholder because it still has replies
+    #[serde(default)]
+    pub deleted: bool,
+    /// Creation order, for comments posted within the same second
+    #[serde(default)]
+    pub sequence: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum CommentTarget {
     Task,
@@ -87,9 +101,50 @@ pub struct Review {
     pub comments: Vec<ReviewComment>,
     pub requested_at: i64,
     pub completed_at: Option<i64>,
+    #[serde(default)]
+    pub requester_id: Option<String>,
+    #[serde(default)]
+    pub transitions: Vec<ReviewTransition>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewTransition {
+    pub from: ReviewStatus,
+    pub to: ReviewStatus,
+    pub at: i64,
+}
+
+/// Merge requirements for one review target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewPolicy {
+    pub required_approvals: u32,
+    /// Whether an outstanding "changes requested" blocks merging even with
+    /// enough approvals. Rejections always block.
+    pub changes_requested_blocks: bool,
+}
+
+impl Default for ReviewPolicy {
+    fn default() -> Self {
+        Self { required_approvals: 1, changes_requested_blocks: true }
+    }
+}
+
+/// Where the reviews for one target stand against its policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSummary {
+    pub target_type: ReviewTarget,
+    pub target_id: String,
+    pub policy: ReviewPolicy,
+    pub approvals: u32,
+    pub changes_requested: u32,
+    pub rejections: u32,
+    pub pending: u32,
+    pub can_merge: bool,
+    /// Why `can_merge` is false
+    pub blockers: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ReviewTarget {
     Spec,
@@ -97,7 +152,7 @@ pub enum ReviewTarget {
     Task,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ReviewStatus {
     Pending,
@@ -148,15 +203,169 @@ pub struct Activity {
     pub user_id: String,
     pub user_name: String,
     pub activity_type: ActivityType,
-    pub target_type: String,
+    pub target_type: EntityType,
     pub target_id: String,
     pub target_name: String,
     pub description: String,
     pub metadata: HashMap<String, serde_json::Value>,
     pub timestamp: i64,
+    pub event: ActivityEvent,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Activity {
+    /// The target plus any comment or review the event is about
+    pub fn entities(&self) -> Vec<EntityRef> {
+        let mut entities = vec![EntityRef::new(self.target_type, &self.target_id)];
+        match &self.event {
+            ActivityEvent::CommentAdded { comment_id, parent_comment_id } => {
+                entities.push(EntityRef::new(EntityType::Comment, comment_id));
+                if let Some(parent_id) = parent_comment_id {
+                    entities.push(EntityRef::new(EntityType::Comment, parent_id));
+                }
+            }
+            ActivityEvent::CommentEdited { comment_id }
+            | ActivityEvent::CommentDeleted { comment_id }
+            | ActivityEvent::ThreadResolved { comment_id }
+            | ActivityEvent::ThreadReopened { comment_id } => {
+                entities.push(EntityRef::new(EntityType::Comment, comment_id));
+            }
+            ActivityEvent::ReviewRequested { review_id, .. }
+            | ActivityEvent::ReviewSubmitted { review_id, .. } => {
+                entities.push(EntityRef::new(EntityType::Review, review_id));
+            }
+        }
+        entities
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityType {
+    Job,
+    Task,
+    Spec,
+    Document,
+    Project,
+    Line,
+    Comment,
+    Review,
+}
+
+impl From<&CommentTarget> for EntityType {
+    fn from(target: &CommentTarget) -> Self {
+        match target {
+            CommentTarget::Task => EntityType::Task,
+            CommentTarget::Spec => EntityType::Spec,
+            CommentTarget::Document => EntityType::Document,
+            CommentTarget::Project => EntityType::Project,
+            CommentTarget::Line => EntityType::Line,
+        }
+    }
+}
+
+impl From<&ReviewTarget> for EntityType {
+    fn from(target: &ReviewTarget) -> Self {
+        match target {
+            ReviewTarget::Spec => EntityType::Spec,
+            ReviewTarget::Document => EntityType::Document,
+            ReviewTarget::Task => EntityType::Task,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EntityRef {
+    pub entity_type: EntityType,
+    pub id: String,
+}
+
+impl EntityRef {
+    pub fn new(entity_type: EntityType, id: &str) -> Self {
+        Self { entity_type, id: id.to_string() }
+    }
+}
+
+/// What happened, with the ids needed to render it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ActivityEvent {
+    CommentAdded { comment_id: String, parent_comment_id: Option<String> },
+    CommentEdited { comment_id: String },
+    CommentDeleted { comment_id: String },
+    ThreadResolved { comment_id: String },
+    ThreadReopened { comment_id: String },
+    ReviewRequested { review_id: String, reviewer_id: String },
+    ReviewSubmitted { review_id: String, status: ReviewStatus },
+}
+
+impl ReviewStatus {
+    /// A reviewer's verdict, as opposed to a review still underway
+    pub fn is_decision(&self) -> bool {
+        matches!(self, ReviewStatus::Approved | ReviewStatus::RequestChanges | ReviewStatus::Rejected)
+    }
+
+    /// Pending -> InProgress -> a decision; a decision may later be revised
+    /// or the review re-requested (back to Pending)
+    fn can_transition_to(&self, next: &ReviewStatus) -> bool {
+        *next != ReviewStatus::InProgress || *self == ReviewStatus::Pending
+    }
+}
+
+impl ActivityEvent {
+    pub fn activity_type(&self) -> ActivityType {
+        match self {
+            ActivityEvent::CommentAdded { .. } => ActivityType::Commented,
+            ActivityEvent::CommentEdited { .. }
+            | ActivityEvent::ThreadResolved { .. }
+            | ActivityEvent::ThreadReopened { .. } => ActivityType::Updated,
+            ActivityEvent::CommentDeleted { .. } => ActivityType::Deleted,
+            ActivityEvent::ReviewRequested { .. } => ActivityType::Assigned,
+            ActivityEvent::ReviewSubmitted { .. } => ActivityType::Reviewed,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            ActivityEvent::CommentAdded { parent_comment_id: Some(_), .. } => "Replied to a comment".to_string(),
+            ActivityEvent::CommentAdded { .. } => "Added a comment".to_string(),
+            ActivityEvent::CommentEdited { .. } => "Edited a comment".to_string(),
+            ActivityEvent::CommentDeleted { .. } => "Deleted a comment".to_string(),
+            ActivityEvent::ThreadResolved { .. } => "Resolved a thread".to_string(),
+            ActivityEvent::ThreadReopened { .. } => "Reopened a thread".to_string(),
+            ActivityEvent::ReviewRequested { .. } => "Requested a review".to_string(),
+            ActivityEvent::ReviewSubmitted { status, .. } => format!("Completed review with status: {:?}", status),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivityFilter {
+    pub actor_id: Option<String>,
+    /// Empty means every type; an activity matches if its target or the
+    /// comment/review it concerns has one of these types
+    #[serde(default)]
+    pub entity_types: Vec<EntityType>,
+    /// Only activity concerning this entity, e.g. everything on one spec
+    pub entity: Option<EntityRef>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+impl ActivityFilter {
+    pub fn matches(&self, activity: &Activity) -> bool {
+        if self.actor_id.as_ref().is_some_and(|actor| *actor != activity.user_id)
+            || self.start_time.is_some_and(|start| activity.timestamp < start)
+            || self.end_time.is_some_and(|end| activity.timestamp > end)
+        {
+            return false;
+        }
+        let entities = activity.entities();
+        (self.entity_types.is_empty() || entities.iter().any(|e| self.entity_types.contains(&e.entity_type)))
+            && self.entity.as_ref().is_none_or(|entity| entities.contains(entity))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ActivityType {
     Created,
@@ -170,6 +379,42 @@ pub enum ActivityType {
     Shared,
 }
 
+/// `@handle` mentions in order of first appearance. Handles are letters,
+/// digits, `_`, `-` and `.`, and trailing punctuation is ignored, so
+/// "thanks @ana." mentions `ana`. Email addresses are not mentions.
+pub fn parse_mentions(content: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    let mut chars = content.char_indices().peekable();
+    let mut prev: Option<char> = None;
+    while let Some((i, c)) = chars.next() {
+        let at_word_start = prev.is_none_or(|p| !p.is_alphanumeric() && p != '_');
+        prev = Some(c);
+        if c != '@' || !at_word_start {
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        while let Some(&(j, next)) = chars.peek() {
+            if !(next.is_alphanumeric() || matches!(next, '_' | '-' | '.')) {
+                break;
+            }
+            end = j + next.len_utf8();
+            prev = Some(next);
+            chars.next();
+        }
+        let handle = content[start..end].trim_end_matches(['.', '-']);
+        if !handle.is_empty() && !mentions.iter().any(|m| m == handle) {
+            mentions.push(handle.to_string());
+        }
+    }
+    mentions
+}
+
+fn comment_link(comment: &Comment) -> String {
+    let target_type = format!("{:?}", comment.target_type).to_lowercase();
+    format!("{}/{}#comment-{}", target_type, comment.target_id, comment.id)
+}
+
 // ============================================
 // Collaboration Service
 // ============================================
@@ -178,9 +423,12 @@ pub struct CollaborationService {
     pub users: HashMap<String, User>,
     pub comments: HashMap<String, Comment>,
     pub reviews: HashMap<String, Review>,
+    /// Keyed by review target; targets without one use `ReviewPolicy::default`
+    pub review_policies: HashMap<EntityRef, ReviewPolicy>,
     pub notifications: Vec<Notification>,
     pub activities: Vec<Activity>,
     pub current_user_id: Option<String>,
+    next_comment_sequence: u64,
 }
 
 impl CollaborationService {
@@ -189,9 +437,11 @@ impl CollaborationService {
             users: HashMap::new(),
             comments: HashMap::new(),
             reviews: HashMap::new(),
+            review_policies: HashMap::new(),
             notifications: Vec::new(),
             activities: Vec::new(),
             current_user_id: None,
+            next_comment_sequence: 0,
         }
     }
 
@@ -237,108 +487,181 @@ impl CollaborationService {
     // Comment Operations
     // ============================================
 
+    /// Add a comment, or a reply when `parent_comment_id` is set. Mentioned
+    /// users are notified, and so is the author of the comment replied to.
     pub fn add_comment(
         &mut self,
         target_type: CommentTarget,
         target_id: &str,
         content: &str,
+        parent_comment_id: Option<&str>,
     ) -> Result<Comment, String> {
-        let author_id = self.current_user_id.clone()
-            .ok_or("No current user set")?;
-        let author = self.users.get(&author_id)
-            .ok_or("Current user not found")?;
-
-        // Extract mentions (@username)
-        let mentions: Vec<String> = content
-            .split_whitespace()
-            .filter(|w| w.starts_with('@'))
-            .map(|w| w[1..].to_string())
-            .collect();
+        let author = self.current_user()?.clone();
+
+        let parent = match parent_comment_id {
+            Some(parent_id) => {
+                let parent = self.comments.get(parent_id)
+                    .ok_or_else(|| format!("Comment not found: {}", parent_id))?;
+                if parent.deleted {
+                    return Err("Cannot reply to a deleted comment".to_string());
+                }
+                if parent.target_type != target_type || parent.target_id != target_id {
+                    return Err("A reply must be on the same target as its parent".to_string());
+                }
+                Some(parent.clone())
+            }
+            None => None,
+        };
 
         let now = chrono::Utc::now().timestamp();
         let comment = Comment {
             id: Uuid::new_v4().to_string(),
             target_type,
             target_id: target_id.to_string(),
-            author_id: author_id.clone(),
+            author_id: author.id.clone(),
             author_name: author.name.clone(),
             content: content.to_string(),
-            mentions: mentions.clone(),
+            mentions: parse_mentions(content),
             reactions: Vec::new(),
             replies: Vec::new(),
+            parent_comment_id: parent.as_ref().map(|p| p.id.clone()),
             resolved: false,
             created_at: now,
             updated_at: now,
+            edited_at: None,
+            deleted: false,
+            sequence: self.next_comment_sequence,
         };
+        self.next_comment_sequence += 1;
 
         self.comments.insert(comment.id.clone(), comment.clone());
 
-        // Create notifications for mentions
-        for mention in mentions {
-            if let Some(user) = self.users.values().find(|u| u.name == mention) {
+        let mentioned = self.notify_mentions(&comment, &author, &HashSet::new());
+        if let Some(parent) = &parent {
+            if parent.author_id != author.id && !mentioned.contains(&parent.author_id) {
                 self.add_notification(
-                    &user.id,
-                    NotificationType::Mention,
-                    "You were mentioned",
-                    &format!("{} mentioned you in a comment", author.name),
-                    None,
+                    &parent.author_id,
+                    NotificationType::Comment,
+                    "New reply",
+                    &format!("{} replied to your comment", author.name),
+                    Some(comment_link(&comment)),
                 );
             }
         }
 
-        // Add activity
         self.add_activity(
-            &author_id,
-            &author.name,
-            ActivityType::Commented,
-            &format!("{:?}", comment.target_type).to_lowercase(),
-            target_id,
-            target_id,
-            format!("Added a comment"),
+            &author,
+            EntityRef::new((&comment.target_type).into(), target_id),
+            ActivityEvent::CommentAdded {
+                comment_id: comment.id.clone(),
+                parent_comment_id: comment.parent_comment_id.clone(),
+            },
         );
 
         Ok(comment)
     }
 
-    pub fn get_comments(&self, target_type: CommentTarget, target_id: &str) -> Vec<&Comment> {
-        self.comments.values()
-            .filter(|c| {
-                matches!(&c.target_type, t if std::mem::discriminant(t) == std::mem::discriminant(&target_type))
-                    && c.target_id == target_id
-            })
-            .collect()
+    /// Comments on a target as threads: root comments oldest first, each with
+    /// its replies nested and also oldest first
+    pub fn get_comments(&self, target_type: CommentTarget, target_id: &str) -> Vec<Comment> {
+        let mut children: HashMap<Option<&str>, Vec<&Comment>> = HashMap::new();
+        for comment in self.comments.values() {
+            if comment.target_type == target_type && comment.target_id == target_id {
+                children.entry(comment.parent_comment_id.as_deref()).or_default().push(comment);
+            }
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|c| (c.created_at, c.sequence));
+        }
+
+        fn build(comment: &Comment, children: &HashMap<Option<&str>, Vec<&Comment>>) -> Comment {
+            let mut node = comment.clone();
+            node.replies = children.get(&Some(comment.id.as_str()))
+                .map(|replies| replies.iter().map(|r| build(r, children)).collect())
+                .unwrap_or_default();
+            node
+        }
+
+        children.get(&None)
+            .map(|roots| roots.iter().map(|c| build(c, &children)).collect())
+            .unwrap_or_default()
     }
 
     pub fn add_reply(&mut self, comment_id: &str, content: &str) -> Result<Comment, String> {
-        let author_id = self.current_user_id.clone()
-            .ok_or("No current user set")?;
-        let author = self.users.get(&author_id)
-            .ok_or("Current user not found")?;
-
-        let now = chrono::Utc::now().timestamp();
-        let reply = Comment {
-            id: Uuid::new_v4().to_string(),
-            target_type: CommentTarget::Task, // Will be overwritten
-            target_id: comment_id.to_string(),
-            author_id: author_id.clone(),
-            author_name: author.name.clone(),
-            content: content.to_string(),
-            mentions: Vec::new(),
-            reactions: Vec::new(),
-            replies: Vec::new(),
-            resolved: false,
-            created_at: now,
-            updated_at: now,
-        };
+        let parent = self.comments.get(comment_id)
+            .ok_or_else(|| format!("Comment not found: {}", comment_id))?;
+        let (target_type, target_id) = (parent.target_type.clone(), parent.target_id.clone());
+        self.add_comment(target_type, &target_id, content, Some(comment_id))
+    }
 
+    /// Change a comment's text. Only its author may edit it, and users newly
+    /// mentioned by the edit are notified.
+    pub fn edit_comment(&mut self, comment_id: &str, content: &str) -> Result<Comment, String> {
+        let author = self.current_user()?.clone();
         let comment = self.comments.get_mut(comment_id)
             .ok_or_else(|| format!("Comment not found: {}", comment_id))?;
-        
-        let reply_clone = reply.clone();
-        comment.replies.push(reply);
+        if comment.deleted {
+            return Err("Cannot edit a deleted comment".to_string());
+        }
+        if comment.author_id != author.id {
+            return Err("Only the author can edit a comment".to_string());
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let previous_mentions = std::mem::replace(&mut comment.mentions, parse_mentions(content));
+        comment.content = content.to_string();
+        comment.edited_at = Some(now);
         comment.updated_at = now;
 
-        Ok(reply_clone)
+        let comment = comment.clone();
+        let already_notified = previous_mentions.iter()
+            .filter_map(|m| self.find_mentioned_user(m))
+            .map(|u| u.id.clone())
+            .collect();
+        self.notify_mentions(&comment, &author, &already_notified);
+        self.add_activity(
+            &author,
+            EntityRef::new((&comment.target_type).into(), &comment.target_id),
+            ActivityEvent::CommentEdited { comment_id: comment.id.clone() },
+        );
+        Ok(comment)
+    }
+
+    /// Delete a comment. One that still has replies becomes a tombstone so
+    /// the thread stays intact; otherwise it is removed, along with any
+    /// tombstoned ancestors left without replies.
+    pub fn delete_comment(&mut self, comment_id: &str) -> Result<(), String> {
+        let author = self.current_user()?.clone();
+        let comment = self.comments.get(comment_id)
+            .ok_or_else(|| format!("Comment not found: {}", comment_id))?;
+        if comment.deleted {
+            return Err(format!("Comment not found: {}", comment_id));
+        }
+        if comment.author_id != author.id {
+            return Err("Only the author can delete a comment".to_string());
+        }
+
+        let target = EntityRef::new((&comment.target_type).into(), &comment.target_id);
+        self.add_activity(&author, target, ActivityEvent::CommentDeleted { comment_id: comment_id.to_string() });
+
+        if self.has_replies(comment_id) {
+            let comment = self.comments.get_mut(comment_id).expect("comment exists");
+            comment.deleted = true;
+            comment.content.clear();
+            comment.mentions.clear();
+            comment.reactions.clear();
+            comment.updated_at = chrono::Utc::now().timestamp();
+            return Ok(());
+        }
+
+        let mut next = Some(comment_id.to_string());
+        while let Some(id) = next.take() {
+            let removed = self.comments.remove(&id).expect("comment exists");
+            next = removed.parent_comment_id
+                .filter(|parent_id| self.comments.get(parent_id).is_some_and(|p| p.deleted))
+                .filter(|parent_id| !self.has_replies(parent_id));
+        }
+        Ok(())
     }
 
     pub fn add_reaction(&mut self, comment_id: &str, emoji: &str) -> Result<(), String> {
@@ -349,6 +672,9 @@ impl CollaborationService {
 
         let comment = self.comments.get_mut(comment_id)
             .ok_or_else(|| format!("Comment not found: {}", comment_id))?;
+        if comment.deleted {
+            return Err("Cannot react to a deleted comment".to_string());
+        }
 
         // Remove existing reaction from same user
         comment.reactions.retain(|r| r.user_id != user_id);
@@ -363,17 +689,93 @@ impl CollaborationService {
     }
 
     pub fn resolve_comment(&mut self, comment_id: &str) -> Result<(), String> {
-        let comment = self.comments.get_mut(comment_id)
-            .ok_or_else(|| format!("Comment not found: {}", comment_id))?;
-        comment.resolved = true;
-        comment.updated_at = chrono::Utc::now().timestamp();
+        self.set_thread_resolved(comment_id, true)
+    }
+
+    pub fn unresolve_comment(&mut self, comment_id: &str) -> Result<(), String> {
+        self.set_thread_resolved(comment_id, false)
+    }
+
+    /// Resolution applies to the whole thread, so it is stored on the root
+    /// comment whichever comment in the thread is given
+    fn set_thread_resolved(&mut self, comment_id: &str, resolved: bool) -> Result<(), String> {
+        let mut root_id = comment_id.to_string();
+        loop {
+            let comment = self.comments.get(&root_id)
+                .ok_or_else(|| format!("Comment not found: {}", root_id))?;
+            match &comment.parent_comment_id {
+                Some(parent_id) => root_id = parent_id.clone(),
+                None => break,
+            }
+        }
+
+        let root = self.comments.get_mut(&root_id).expect("comment exists");
+        root.resolved = resolved;
+        root.updated_at = chrono::Utc::now().timestamp();
+
+        let target = EntityRef::new((&root.target_type).into(), &root.target_id);
+        if let Ok(user) = self.current_user().cloned() {
+            let event = if resolved {
+                ActivityEvent::ThreadResolved { comment_id: root_id }
+            } else {
+                ActivityEvent::ThreadReopened { comment_id: root_id }
+            };
+            self.add_activity(&user, target, event);
+        }
         Ok(())
     }
 
+    fn current_user(&self) -> Result<&User, String> {
+        let user_id = self.current_user_id.as_ref().ok_or("No current user set")?;
+        self.users.get(user_id).ok_or_else(|| "Current user not found".to_string())
+    }
+
+    fn has_replies(&self, comment_id: &str) -> bool {
+        self.comments.values().any(|c| c.parent_comment_id.as_deref() == Some(comment_id))
+    }
+
+    /// Notify each user mentioned in `comment` other than the author and
+    /// those in `already_notified`. Returns the ids of the users notified.
+    fn notify_mentions(&mut self, comment: &Comment, author: &User, already_notified: &HashSet<String>) -> HashSet<String> {
+        let mut notified = HashSet::new();
+        for mention in &comment.mentions {
+            let user_id = match self.find_mentioned_user(mention) {
+                Some(user) if user.id != author.id => user.id.clone(),
+                _ => continue,
+            };
+            if !already_notified.contains(&user_id) && notified.insert(user_id.clone()) {
+                self.add_notification(
+                    &user_id,
+                    NotificationType::Mention,
+                    "You were mentioned",
+                    &format!("{} mentioned you in a comment", author.name),
+                    Some(comment_link(comment)),
+                );
+            }
+        }
+        notified
+    }
+
+    /// Match a mention against user names (spaces may be written as `_` or
+    /// `.`) or the local part of an email address, ignoring case
+    fn find_mentioned_user(&self, mention: &str) -> Option<&User> {
+        let mention = mention.to_lowercase();
+        self.users.values().find(|u| {
+            let name = u.name.to_lowercase();
+            let handle = u.email.split('@').next().unwrap_or_default().to_lowercase();
+            name == mention
+                || name.replace(' ', "_") == mention
+                || name.replace(' ', ".") == mention
+                || (!handle.is_empty() && handle == mention)
+        })
+    }
+
     // ============================================
     // Review Operations
     // ============================================
 
+    /// Ask `reviewer_id` to review a target. A reviewer has at most one
+    /// review per target, so asking again re-requests that review.
     pub fn request_review(
         &mut self,
         target_type: ReviewTarget,
@@ -382,20 +784,56 @@ impl CollaborationService {
     ) -> Result<Review, String> {
         let reviewer = self.users.get(reviewer_id)
             .ok_or_else(|| format!("Reviewer not found: {}", reviewer_id))?;
+        let reviewer_name = reviewer.name.clone();
+        let requester = self.current_user().ok().cloned();
+        let now = chrono::Utc::now().timestamp();
 
-        let review = Review {
-            id: Uuid::new_v4().to_string(),
-            target_type,
-            target_id: target_id.to_string(),
-            reviewer_id: reviewer_id.to_string(),
-            reviewer_name: reviewer.name.clone(),
-            status: ReviewStatus::Pending,
-            comments: Vec::new(),
-            requested_at: chrono::Utc::now().timestamp(),
-            completed_at: None,
+        let existing = self.reviews.values()
+            .find(|r| r.target_type == target_type && r.target_id == target_id && r.reviewer_id == reviewer_id)
+            .map(|r| r.id.clone());
+        let review = match existing {
+            Some(review_id) => {
+                let review = self.reviews.get_mut(&review_id).expect("review exists");
+                if review.status != ReviewStatus::Pending {
+                    review.transitions.push(ReviewTransition { from: review.status.clone(), to: ReviewStatus::Pending, at: now });
+                    review.status = ReviewStatus::Pending;
+                }
+                review.requested_at = now;
+                review.completed_at = None;
+                if requester.is_some() {
+                    review.requester_id = requester.as_ref().map(|u| u.id.clone());
+                }
+                review.clone()
+            }
+            None => {
+                let review = Review {
+                    id: Uuid::new_v4().to_string(),
+                    target_type,
+                    target_id: target_id.to_string(),
+                    reviewer_id: reviewer_id.to_string(),
+                    reviewer_name,
+                    status: ReviewStatus::Pending,
+                    comments: Vec::new(),
+                    requested_at: now,
+                    completed_at: None,
+                    requester_id: requester.as_ref().map(|u| u.id.clone()),
+                    transitions: Vec::new(),
+                };
+                self.reviews.insert(review.id.clone(), review.clone());
+                review
+            }
         };
 
-        self.reviews.insert(review.id.clone(), review.clone());
+        if let Some(user) = &requester {
+            self.add_activity(
+                user,
+                EntityRef::new((&review.target_type).into(), target_id),
+                ActivityEvent::ReviewRequested {
+                    review_id: review.id.clone(),
+                    reviewer_id: reviewer_id.to_string(),
+                },
+            );
+        }
 
         // Notify reviewer
         self.add_notification(
@@ -419,32 +857,62 @@ impl CollaborationService {
             .collect()
     }
 
+    /// Record the current user's review. Only the assigned reviewer may
+    /// submit, and submitting again revises their existing review rather
+    /// than adding another. The requester is notified of every submission.
     pub fn submit_review(
         &mut self,
         review_id: &str,
         status: ReviewStatus,
         comments: Vec<ReviewComment>,
     ) -> Result<Review, String> {
+        let user = self.current_user()?.clone();
         let review = self.reviews.get_mut(review_id)
             .ok_or_else(|| format!("Review not found: {}", review_id))?;
+        if review.reviewer_id != user.id {
+            return Err("Only the assigned reviewer can submit this review".to_string());
+        }
+        if status == ReviewStatus::Pending {
+            return Err("Use request_review to re-request a review".to_string());
+        }
+        if !review.status.can_transition_to(&status) {
+            return Err(format!("Cannot move a review from {:?} to {:?}", review.status, status));
+        }
 
+        let now = chrono::Utc::now().timestamp();
+        if review.status != status {
+            review.transitions.push(ReviewTransition { from: review.status.clone(), to: status.clone(), at: now });
+        }
+        review.completed_at = status.is_decision().then_some(now);
         review.status = status;
         review.comments = comments;
-        review.completed_at = Some(chrono::Utc::now().timestamp());
 
         let review_clone = review.clone();
 
         // Add activity
-        if let Some(user_id) = &self.current_user_id {
-            if let Some(user) = self.users.get(user_id) {
-                self.add_activity(
-                    user_id,
-                    &user.name,
-                    ActivityType::Reviewed,
-                    &format!("{:?}", review_clone.target_type).to_lowercase(),
-                    &review_clone.target_id,
-                    &review_clone.target_id,
-                    format!("Completed review with status: {:?}", review_clone.status),
+        self.add_activity(
+            &user,
+            EntityRef::new((&review_clone.target_type).into(), &review_clone.target_id),
+            ActivityEvent::ReviewSubmitted {
+                review_id: review_clone.id.clone(),
+                status: review_clone.status.clone(),
+            },
+        );
+
+        if review_clone.status.is_decision() {
+            if let Some(requester_id) = review_clone.requester_id.clone().filter(|id| *id != user.id) {
+                let summary = self.review_summary(&review_clone.target_type, &review_clone.target_id);
+                self.add_notification(
+                    &requester_id,
+                    NotificationType::ReviewCompleted,
+                    "Review submitted",
+                    &format!(
+                        "{} reviewed with status {:?}; {}",
+                        user.name,
+                        review_clone.status,
+                        if summary.can_merge { "ready to merge".to_string() } else { summary.blockers.join("; ") }
+                    ),
+                    None,
                 );
             }
         }
@@ -452,6 +920,56 @@ impl CollaborationService {
         Ok(review_clone)
     }
 
+    pub fn set_review_policy(&mut self, target_type: &ReviewTarget, target_id: &str, policy: ReviewPolicy) {
+        self.review_policies.insert(EntityRef::new(target_type.into(), target_id), policy);
+    }
+
+    pub fn review_policy(&self, target_type: &ReviewTarget, target_id: &str) -> ReviewPolicy {
+        self.review_policies.get(&EntityRef::new(target_type.into(), target_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Tally the target's reviews against its policy
+    pub fn review_summary(&self, target_type: &ReviewTarget, target_id: &str) -> ReviewSummary {
+        let policy = self.review_policy(target_type, target_id);
+        let mut summary = ReviewSummary {
+            target_type: target_type.clone(),
+            target_id: target_id.to_string(),
+            policy: policy.clone(),
+            approvals: 0,
+            changes_requested: 0,
+            rejections: 0,
+            pending: 0,
+            can_merge: false,
+            blockers: Vec::new(),
+        };
+
+        for review in self.reviews.values().filter(|r| r.target_type == *target_type && r.target_id == target_id) {
+            match review.status {
+                ReviewStatus::Approved => summary.approvals += 1,
+                ReviewStatus::RequestChanges => summary.changes_requested += 1,
+                ReviewStatus::Rejected => summary.rejections += 1,
+                ReviewStatus::Pending | ReviewStatus::InProgress => summary.pending += 1,
+            }
+        }
+
+        if summary.approvals < policy.required_approvals {
+            summary.blockers.push(format!(
+                "{} of {} required approvals",
+                summary.approvals, policy.required_approvals
+            ));
+        }
+        if policy.changes_requested_blocks && summary.changes_requested > 0 {
+            summary.blockers.push(format!("{} reviewer(s) requested changes", summary.changes_requested));
+        }
+        if summary.rejections > 0 {
+            summary.blockers.push(format!("{} reviewer(s) rejected", summary.rejections));
+        }
+        summary.can_merge = summary.blockers.is_empty();
+        summary
+    }
+
     // ============================================
     // Notification Operations
     // ============================================
@@ -510,42 +1028,380 @@ impl CollaborationService {
     // Activity Operations
     // ============================================
 
-    fn add_activity(
-        &mut self,
-        user_id: &str,
-        user_name: &str,
-        activity_type: ActivityType,
-        target_type: &str,
-        target_id: &str,
-        target_name: &str,
-        description: String,
-    ) {
+    fn add_activity(&mut self, actor: &User, target: EntityRef, event: ActivityEvent) {
         let activity = Activity {
             id: Uuid::new_v4().to_string(),
-            user_id: user_id.to_string(),
-            user_name: user_name.to_string(),
-            activity_type,
-            target_type: target_type.to_string(),
-            target_id: target_id.to_string(),
-            target_name: target_name.to_string(),
-            description,
+            user_id: actor.id.clone(),
+            user_name: actor.name.clone(),
+            activity_type: event.activity_type(),
+            target_type: target.entity_type,
+            target_name: target.id.clone(),
+            target_id: target.id,
+            description: event.description(),
             metadata: HashMap::new(),
             timestamp: chrono::Utc::now().timestamp(),
+            event,
         };
         self.activities.push(activity);
     }
 
-    pub fn get_activity_feed(&self, limit: usize) -> Vec<&Activity> {
-        let mut activities: Vec<_> = self.activities.iter().collect();
-        activities.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        activities.into_iter().take(limit).collect()
+    /// Matching activity newest first. Keyset on (timestamp, recording
+    /// position) so activity logged meanwhile never shifts a page; pages
+    /// fetched with `after_id` come back newest first as well.
+    pub fn get_activity_feed(&self, filter: &ActivityFilter, page: &PageRequest) -> Result<Page<Activity>, String> {
+        let key_of = |id: &str| self.activities.iter()
+            .position(|a| a.id == id)
+            .map(|i| (self.activities[i].timestamp, i))
+            .ok_or_else(|| format!("Activity cursor not found: {}", id));
+
+        let mut keys: Vec<(i64, usize)> = self.activities.iter().enumerate()
+            .filter(|(_, a)| filter.matches(a))
+            .map(|(i, a)| (a.timestamp, i))
+            .collect();
+
+        let cursor = page.cursor()?;
+        match cursor {
+            PageCursor::Latest => {}
+            PageCursor::Before(id) => {
+                let cursor_key = key_of(id)?;
+                keys.retain(|k| *k < cursor_key);
+            }
+            PageCursor::After(id) => {
+                let cursor_key = key_of(id)?;
+                keys.retain(|k| *k > cursor_key);
+            }
+        }
+
+        let newer = matches!(cursor, PageCursor::After(_));
+        if newer {
+            keys.sort();
+        } else {
+            keys.sort_by(|a, b| b.cmp(a));
+        }
+
+        let fetch = page.limit.map(|l| l.max(1) as usize + 1).unwrap_or(usize::MAX);
+        let rows = keys.into_iter().take(fetch).map(|(_, i)| self.activities[i].clone()).collect();
+        let mut page = Page::from_rows(rows, page.limit, |a| a.id.clone());
+        if newer {
+            page.items.reverse();
+        }
+        Ok(page)
     }
 
-    pub fn get_user_activity(&self, user_id: &str, limit: usize) -> Vec<&Activity> {
-        let mut activities: Vec<_> = self.activities.iter()
-            .filter(|a| a.user_id == user_id)
+    pub fn get_user_activity(&self, user_id: &str, limit: usize) -> Vec<Activity> {
+        let filter = ActivityFilter { actor_id: Some(user_id.to_string()), ..Default::default() };
+        let page = PageRequest { limit: Some(limit as i64), ..Default::default() };
+        self.get_activity_feed(&filter, &page).map(|p| p.items).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service_with_users() -> (CollaborationService, User, User) {
+        let mut service = CollaborationService::new();
+        let ana = service.add_user("Ana Lee", "ana@example.com", UserRole::Member);
+        let bo = service.add_user("bo", "bo.k@example.com", UserRole::Member);
+        service.set_current_user(&ana.id);
+        (service, ana, bo)
+    }
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(parse_mentions("cc @bo, @ana_lee. and @bo again"), vec!["bo", "ana_lee"]);
+        assert_eq!(parse_mentions("mail me at ana@example.com"), Vec::<String>::new());
+        assert_eq!(parse_mentions("@ @- (@bo.k)"), vec!["bo.k"]);
+    }
+
+    #[test]
+    fn test_replies_nest_in_order() {
+        let (mut service, ana, bo) = service_with_users();
+        let root = service.add_comment(CommentTarget::Spec, "spec-1", "First", None).unwrap();
+        let other = service.add_comment(CommentTarget::Spec, "spec-1", "Second", None).unwrap();
+        service.set_current_user(&bo.id);
+        let reply = service.add_reply(&root.id, "Reply").unwrap();
+        let nested = service.add_comment(CommentTarget::Spec, "spec-1", "Nested", Some(&reply.id)).unwrap();
+        service.set_current_user(&ana.id);
+        let later = service.add_reply(&root.id, "Later reply").unwrap();
+
+        assert!(service.add_comment(CommentTarget::Task, "spec-1", "Wrong target", Some(&root.id)).is_err());
+        service.add_comment(CommentTarget::Spec, "spec-2", "Elsewhere", None).unwrap();
+
+        let threads = service.get_comments(CommentTarget::Spec, "spec-1");
+        assert_eq!(threads.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), vec![root.id.as_str(), other.id.as_str()]);
+        let replies = &threads[0].replies;
+        assert_eq!(replies.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), vec![reply.id.as_str(), later.id.as_str()]);
+        assert_eq!(replies[0].replies[0].id, nested.id);
+        assert_eq!(replies[0].replies[0].parent_comment_id.as_deref(), Some(reply.id.as_str()));
+
+        // Resolving from any comment resolves the thread's root
+        service.resolve_comment(&nested.id).unwrap();
+        assert!(service.comments[&root.id].resolved);
+        service.unresolve_comment(&root.id).unwrap();
+        assert!(!service.comments[&root.id].resolved);
+    }
+
+    #[test]
+    fn test_mentions_and_replies_notify() {
+        let (mut service, ana, bo) = service_with_users();
+        let root = service.add_comment(CommentTarget::Task, "t1", "@bo.k please look, also @ana_lee", None).unwrap();
+
+        // The author is not notified of their own mention
+        assert!(service.get_notifications(&ana.id, false).is_empty());
+        let notifications = service.get_notifications(&bo.id, true);
+        assert_eq!(notifications.len(), 1);
+        assert!(matches!(notifications[0].notification_type, NotificationType::Mention));
+        assert_eq!(notifications[0].link.as_deref(), Some(format!("task/t1#comment-{}", root.id).as_str()));
+
+        service.set_current_user(&bo.id);
+        service.add_reply(&root.id, "On it").unwrap();
+        let replies = service.get_notifications(&ana.id, false);
+        assert_eq!(replies.len(), 1);
+        assert!(matches!(replies[0].notification_type, NotificationType::Comment));
+
+        // Editing only notifies newly mentioned users
+        let mine = service.add_comment(CommentTarget::Task, "t1", "ping @Ana_Lee", None).unwrap();
+        assert_eq!(service.get_notifications(&ana.id, false).len(), 2);
+        let edited = service.edit_comment(&mine.id, "ping @ana_lee (edited)").unwrap();
+        assert!(edited.edited_at.is_some());
+        assert_eq!(service.get_notifications(&ana.id, false).len(), 2);
+
+        service.set_current_user(&ana.id);
+        assert!(service.edit_comment(&mine.id, "not mine").is_err());
+    }
+
+    #[test]
+    fn test_delete_tombstones_parents_with_replies() {
+        let (mut service, ana, bo) = service_with_users();
+        let root = service.add_comment(CommentTarget::Document, "d1", "Root", None).unwrap();
+        service.set_current_user(&bo.id);
+        let reply = service.add_reply(&root.id, "Reply").unwrap();
+        assert!(service.delete_comment(&root.id).is_err());
+
+        service.set_current_user(&ana.id);
+        service.delete_comment(&root.id).unwrap();
+        let threads = service.get_comments(CommentTarget::Document, "d1");
+        assert_eq!(threads.len(), 1);
+        assert!(threads[0].deleted);
+        assert!(threads[0].content.is_empty());
+        assert_eq!(threads[0].replies[0].id, reply.id);
+        assert!(service.add_reply(&root.id, "To a tombstone").is_err());
+
+        // Removing the last reply also removes the tombstone above it
+        service.set_current_user(&bo.id);
+        service.delete_comment(&reply.id).unwrap();
+        assert!(service.get_comments(CommentTarget::Document, "d1").is_empty());
+        assert!(service.comments.is_empty());
+
+        let leaf = service.add_comment(CommentTarget::Document, "d1", "Leaf", None).unwrap();
+        service.delete_comment(&leaf.id).unwrap();
+        assert!(service.delete_comment(&leaf.id).is_err());
+    }
+
+    fn activity_service() -> (CollaborationService, User, User, Comment) {
+        let (mut service, ana, bo) = service_with_users();
+        let on_spec = service.add_comment(CommentTarget::Spec, "spec-1", "Spec note", None).unwrap();
+        service.add_comment(CommentTarget::Task, "task-1", "Task note", None).unwrap();
+        service.set_current_user(&bo.id);
+        service.add_reply(&on_spec.id, "Reply").unwrap();
+        service.request_review(ReviewTarget::Spec, "spec-1", &ana.id).unwrap();
+        service.resolve_comment(&on_spec.id).unwrap();
+        (service, ana, bo, on_spec)
+    }
+
+    fn kinds(page: &Page<Activity>) -> Vec<ActivityType> {
+        page.items.iter().map(|a| a.event.activity_type()).collect()
+    }
+
+    #[test]
+    fn test_activity_filter_by_actor_and_type() {
+        let (service, ana, bo, _) = activity_service();
+        let all = service.get_activity_feed(&ActivityFilter::default(), &PageRequest::default()).unwrap();
+        assert_eq!(all.items.len(), 5);
+        // Newest first
+        assert!(matches!(all.items[0].event, ActivityEvent::ThreadResolved { .. }));
+
+        let by_ana = ActivityFilter { actor_id: Some(ana.id.clone()), ..Default::default() };
+        let page = service.get_activity_feed(&by_ana, &PageRequest::default()).unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert!(page.items.iter().all(|a| a.user_id == ana.id));
+        assert_eq!(service.get_user_activity(&bo.id, 10).len(), 3);
+
+        let reviews = ActivityFilter { entity_types: vec![EntityType::Review], ..Default::default() };
+        let page = service.get_activity_feed(&reviews, &PageRequest::default()).unwrap();
+        assert_eq!(kinds(&page), vec![ActivityType::Assigned]);
+
+        let tasks = ActivityFilter { entity_types: vec![EntityType::Task], ..Default::default() };
+        let page = service.get_activity_feed(&tasks, &PageRequest::default()).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].target_id, "task-1");
+
+        let json = serde_json::to_value(&page.items[0]).unwrap();
+        assert_eq!(json["event"]["kind"], "comment_added");
+        assert_eq!(json["target_type"], "task");
+    }
+
+    #[test]
+    fn test_activity_scoped_to_entity_and_time() {
+        let (mut service, _, _, on_spec) = activity_service();
+
+        let spec = ActivityFilter { entity: Some(EntityRef::new(EntityType::Spec, "spec-1")), ..Default::default() };
+        let page = service.get_activity_feed(&spec, &PageRequest::default()).unwrap();
+        assert_eq!(page.items.len(), 4);
+        assert!(page.items.iter().all(|a| a.target_id == "spec-1"));
+
+        // A comment's scope includes replies to it
+        let thread = ActivityFilter { entity: Some(EntityRef::new(EntityType::Comment, &on_spec.id)), ..Default::default() };
+        let page = service.get_activity_feed(&thread, &PageRequest::default()).unwrap();
+        assert_eq!(kinds(&page), vec![ActivityType::Updated, ActivityType::Commented, ActivityType::Commented]);
+
+        service.activities[0].timestamp = 1_000;
+        service.activities[1].timestamp = 2_000;
+        let window = ActivityFilter { start_time: Some(1_500), end_time: Some(2_500), ..Default::default() };
+        let page = service.get_activity_feed(&window, &PageRequest::default()).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].target_id, "task-1");
+    }
+
+    #[test]
+    fn test_activity_keyset_pagination() {
+        let (service, _, _, _) = activity_service();
+        let ids: Vec<String> = service.activities.iter().rev().map(|a| a.id.clone()).collect();
+
+        let first = service.get_activity_feed(&ActivityFilter::default(), &PageRequest { limit: Some(2), ..Default::default() }).unwrap();
+        assert_eq!(first.items.iter().map(|a| &a.id).collect::<Vec<_>>(), vec![&ids[0], &ids[1]]);
+        let cursor = first.next_cursor.clone().unwrap();
+
+        let second = service.get_activity_feed(
+            &ActivityFilter::default(),
+            &PageRequest { before_id: Some(cursor.clone()), limit: Some(2), ..Default::default() },
+        ).unwrap();
+        assert_eq!(second.items.iter().map(|a| &a.id).collect::<Vec<_>>(), vec![&ids[2], &ids[3]]);
+
+        let last = service.get_activity_feed(
+            &ActivityFilter::default(),
+            &PageRequest { before_id: second.next_cursor.clone(), limit: Some(2), ..Default::default() },
+        ).unwrap();
+        assert_eq!(last.items.len(), 1);
+        assert_eq!(last.next_cursor, None);
+
+        // Paging back towards newer entries
+        let newer = service.get_activity_feed(
+            &ActivityFilter::default(),
+            &PageRequest { after_id: Some(cursor), limit: Some(5), ..Default::default() },
+        ).unwrap();
+        assert_eq!(newer.items.iter().map(|a| &a.id).collect::<Vec<_>>(), vec![&ids[0]]);
+
+        let bad = PageRequest { before_id: Some("missing".to_string()), ..Default::default() };
+        assert!(service.get_activity_feed(&ActivityFilter::default(), &bad).is_err());
+    }
+
+    #[test]
+    fn test_activity_pages_follow_timestamps() {
+        let (mut service, _, _, _) = activity_service();
+        // The first activity recorded carries the newest timestamp
+        for (activity, ts) in service.activities.iter_mut().zip([5_000, 1_000, 2_000, 3_000, 4_000]) {
+            activity.timestamp = ts;
+        }
+        let id = |i: usize| service.activities[i].id.clone();
+        let ids = |page: &Page<Activity>| page.items.iter().map(|a| a.id.clone()).collect::<Vec<_>>();
+
+        let feed = service.get_activity_feed(&ActivityFilter::default(), &PageRequest::default()).unwrap();
+        assert_eq!(ids(&feed), vec![id(0), id(4), id(3), id(2), id(1)]);
+
+        let older = service.get_activity_feed(
+            &ActivityFilter::default(),
+            &PageRequest { before_id: Some(id(4)), limit: Some(2), ..Default::default() },
+        ).unwrap();
+        assert_eq!(ids(&older), vec![id(3), id(2)]);
+
+        let newer = service.get_activity_feed(
+            &ActivityFilter::default(),
+            &PageRequest { after_id: Some(id(2)), limit: Some(2), ..Default::default() },
+        ).unwrap();
+        assert_eq!(ids(&newer), vec![id(4), id(3)]);
+        assert_eq!(newer.next_cursor, Some(id(4)));
+
+        let newest = service.get_activity_feed(
+            &ActivityFilter::default(),
+            &PageRequest { after_id: newer.next_cursor.clone(), limit: Some(2), ..Default::default() },
+        ).unwrap();
+        assert_eq!(ids(&newest), vec![id(0)]);
+        assert_eq!(newest.next_cursor, None);
+    }
+
+    fn review_setup() -> (CollaborationService, User, Vec<Review>) {
+        let (mut service, ana, bo) = service_with_users();
+        let cy = service.add_user("cy", "cy@example.com", UserRole::Member);
+        service.set_review_policy(&ReviewTarget::Spec, "spec-1", ReviewPolicy { required_approvals: 2, changes_requested_blocks: true });
+        let reviews = [&bo, &cy].iter()
+            .map(|u| service.request_review(ReviewTarget::Spec, "spec-1", &u.id).unwrap())
             .collect();
-        activities.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        activities.into_iter().take(limit).collect()
+        (service, ana, reviews)
+    }
+
+    fn submit(service: &mut CollaborationService, review: &Review, status: ReviewStatus) -> Result<Review, String> {
+        service.set_current_user(&review.reviewer_id);
+        service.submit_review(&review.id, status, Vec::new())
+    }
+
+    #[test]
+    fn test_required_approvals_gate_merge() {
+        let (mut service, ana, reviews) = review_setup();
+        let summary = service.review_summary(&ReviewTarget::Spec, "spec-1");
+        assert_eq!((summary.pending, summary.can_merge), (2, false));
+
+        submit(&mut service, &reviews[0], ReviewStatus::Approved).unwrap();
+        let summary = service.review_summary(&ReviewTarget::Spec, "spec-1");
+        assert_eq!(summary.approvals, 1);
+        assert!(!summary.can_merge);
+        assert_eq!(summary.blockers, vec!["1 of 2 required approvals"]);
+
+        // Resubmitting revises the same review instead of counting twice
+        submit(&mut service, &reviews[0], ReviewStatus::Approved).unwrap();
+        assert_eq!(service.review_summary(&ReviewTarget::Spec, "spec-1").approvals, 1);
+        assert_eq!(service.reviews.len(), 2);
+
+        submit(&mut service, &reviews[1], ReviewStatus::Approved).unwrap();
+        assert!(service.review_summary(&ReviewTarget::Spec, "spec-1").can_merge);
+
+        // The requester hears about each decision
+        let updates = service.get_notifications(&ana.id, false);
+        assert_eq!(updates.iter().filter(|n| matches!(n.notification_type, NotificationType::ReviewCompleted)).count(), 3);
+        assert!(updates.last().unwrap().message.ends_with("ready to merge"));
+
+        // Only the assigned reviewer may submit
+        service.set_current_user(&ana.id);
+        assert!(service.submit_review(&reviews[0].id, ReviewStatus::Approved, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_changes_requested_blocks_merge() {
+        let (mut service, ana, reviews) = review_setup();
+        submit(&mut service, &reviews[0], ReviewStatus::Approved).unwrap();
+        submit(&mut service, &reviews[1], ReviewStatus::InProgress).unwrap();
+        let changed = submit(&mut service, &reviews[1], ReviewStatus::RequestChanges).unwrap();
+        assert_eq!(changed.transitions.iter().map(|t| t.to.clone()).collect::<Vec<_>>(),
+            vec![ReviewStatus::InProgress, ReviewStatus::RequestChanges]);
+        assert!(submit(&mut service, &reviews[1], ReviewStatus::InProgress).is_err());
+
+        service.set_review_policy(&ReviewTarget::Spec, "spec-1", ReviewPolicy { required_approvals: 1, changes_requested_blocks: true });
+        let summary = service.review_summary(&ReviewTarget::Spec, "spec-1");
+        assert!(!summary.can_merge);
+        assert_eq!(summary.blockers, vec!["1 reviewer(s) requested changes"]);
+
+        service.set_review_policy(&ReviewTarget::Spec, "spec-1", ReviewPolicy { required_approvals: 1, changes_requested_blocks: false });
+        assert!(service.review_summary(&ReviewTarget::Spec, "spec-1").can_merge);
+
+        // Re-requesting puts the same review back to pending
+        service.set_current_user(&ana.id);
+        let again = service.request_review(ReviewTarget::Spec, "spec-1", &reviews[1].reviewer_id).unwrap();
+        assert_eq!(again.id, reviews[1].id);
+        assert_eq!(again.status, ReviewStatus::Pending);
+        assert_eq!(service.review_summary(&ReviewTarget::Spec, "spec-1").pending, 1);
+
+        submit(&mut service, &reviews[1], ReviewStatus::Rejected).unwrap();
+        assert!(!service.review_summary(&ReviewTarget::Spec, "spec-1").can_merge);
     }
 }
@@ -410,6 +410,18 @@ Available commands:
             total_tokens += tokens;
         }
         
+        // Older turns folded away by summarization lead the history
+        if let Some(summary) = self.memory_manager.get_session_summary(workspace_id, session_id)? {
+            let tokens = self.estimate_tokens(&summary.content);
+            if total_tokens + tokens <= max_tokens {
+                history.push(ConversationMessage {
+                    role: "system".to_string(),
+                    content: format!("Summary of earlier conversation:\n{}", summary.content),
+                    tokens_estimate: tokens,
+                });
+            }
+        }
+        
         // Reverse to get chronological order
         history.reverse();
         
@@ -8,6 +8,7 @@
 // - Cost analytics and reporting
 // - Data recovery after restart
 
+use chrono::Datelike;
 use rusqlite::{params, Connection, Result as SqliteResult};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -64,6 +65,27 @@ pub struct CostAnalytics {
     pub total_requests: i64,
 }
 
+/// Totals for one group of chat completions (a session, model or day)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageBreakdown {
+    pub key: String,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatUsageStats {
+    pub total_requests: i64,
+    pub total_input_tokens: i64,
+    pub total_output_tokens: i64,
+    pub total_cost_usd: f64,
+    pub by_session: Vec<UsageBreakdown>,
+    pub by_model: Vec<UsageBreakdown>,
+    pub by_day: Vec<UsageBreakdown>,
+}
+
 // ============================================
 // Cost Database Manager
 // ============================================
@@ -100,6 +122,7 @@ impl CostDatabase {
             CREATE INDEX IF NOT EXISTS idx_cost_provider ON cost_records(provider);
             CREATE INDEX IF NOT EXISTS idx_cost_timestamp ON cost_records(timestamp);
             CREATE INDEX IF NOT EXISTS idx_cost_workspace ON cost_records(workspace_id);
+            CREATE INDEX IF NOT EXISTS idx_cost_session ON cost_records(workspace_id, session_id);
             
             CREATE TABLE IF NOT EXISTS daily_summaries (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -373,6 +396,59 @@ impl CostDatabase {
         records.collect()
     }
     
+    /// Chat completion usage in a workspace, optionally narrowed to one
+    /// session, broken down by session, model and day
+    pub fn get_chat_usage_stats(&self, workspace_id: &str, session_id: Option<&str>) -> SqliteResult<ChatUsageStats> {
+        let conn = self.conn.lock().unwrap();
+        
+        let by_session = Self::chat_usage_by(&conn, "session_id", workspace_id, session_id)?;
+        let by_model = Self::chat_usage_by(&conn, "model", workspace_id, session_id)?;
+        let mut by_day = Self::chat_usage_by(&conn, "date(timestamp, 'unixepoch')", workspace_id, session_id)?;
+        by_day.sort_by(|a, b| a.key.cmp(&b.key));
+        
+        Ok(ChatUsageStats {
+            total_requests: by_session.iter().map(|b| b.requests).sum(),
+            total_input_tokens: by_session.iter().map(|b| b.input_tokens).sum(),
+            total_output_tokens: by_session.iter().map(|b| b.output_tokens).sum(),
+            total_cost_usd: by_session.iter().map(|b| b.cost_usd).sum(),
+            by_session,
+            by_model,
+            by_day,
+        })
+    }
+    
+    /// Grouped totals, costliest first. `group_expr` is always one of the
+    /// fixed expressions above, never user input.
+    fn chat_usage_by(
+        conn: &Connection,
+        group_expr: &str,
+        workspace_id: &str,
+        session_id: Option<&str>,
+    ) -> SqliteResult<Vec<UsageBreakdown>> {
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {group_expr}, COUNT(*), SUM(input_tokens), SUM(output_tokens), SUM(cost_usd)
+            FROM cost_records
+            WHERE request_type = 'chat' AND workspace_id = ?1 AND session_id IS NOT NULL
+              AND (?2 IS NULL OR session_id = ?2)
+            GROUP BY 1
+            ORDER BY 5 DESC, 1
+            "#
+        ))?;
+        
+        let rows = stmt.query_map(params![workspace_id, session_id], |row| {
+            Ok(UsageBreakdown {
+                key: row.get(0)?,
+                requests: row.get(1)?,
+                input_tokens: row.get(2)?,
+                output_tokens: row.get(3)?,
+                cost_usd: row.get(4)?,
+            })
+        })?;
+        
+        rows.collect()
+    }
+    
     /// Export cost data to CSV
     pub fn export_to_csv(&self, start_date: &str, end_date: &str) -> SqliteResult<String> {
         let conn = self.conn.lock().unwrap();
@@ -411,6 +487,12 @@ impl CostDatabase {
         Ok(csv)
     }
     
+    /// Flush the WAL into the main database file
+    pub fn checkpoint(&self) -> SqliteResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+    }
+    
     /// Cleanup old records (keep last N days)
     pub fn cleanup_old_records(&self, keep_days: i32) -> SqliteResult<usize> {
         let conn = self.conn.lock().unwrap();
@@ -458,6 +540,15 @@ pub fn get_cost_database() -> Result<std::sync::MutexGuard<'static, Option<CostD
     COST_DB.lock().map_err(|e| e.to_string())
 }
 
+/// Checkpoint and close the global cost database; a no-op if it was never opened
+pub fn flush_cost_database() -> Result<(), String> {
+    let mut guard = COST_DB.lock().map_err(|e| e.to_string())?;
+    match guard.take() {
+        Some(db) => db.checkpoint().map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
 // ============================================
 // Tauri Commands
 // ============================================
@@ -513,6 +604,13 @@ pub fn get_monthly_cost_summaries(months: i32) -> Result<Vec<MonthlyCostSummary>
     db.get_monthly_summaries(months).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn chat_get_usage_stats(workspace_id: String, session_id: Option<String>) -> Result<ChatUsageStats, String> {
+    let guard = get_cost_database()?;
+    let db = guard.as_ref().ok_or("Cost database not initialized")?;
+    db.get_chat_usage_stats(&workspace_id, session_id.as_deref()).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn export_cost_csv(start_date: String, end_date: String) -> Result<String, String> {
     let guard = get_cost_database()?;
@@ -526,3 +624,78 @@ pub fn cleanup_old_cost_records(keep_days: i32) -> Result<usize, String> {
     let db = guard.as_ref().ok_or("Cost database not initialized")?;
     db.cleanup_old_records(keep_days).map_err(|e| e.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn completion(session_id: &str, model: &str, input_tokens: i64, output_tokens: i64, cost_usd: f64, timestamp: i64) -> CostRecord {
+        CostRecord {
+            id: None,
+            provider: "openrouter".to_string(),
+            model: model.to_string(),
+            input_tokens,
+            output_tokens,
+            cost_usd,
+            request_type: "chat".to_string(),
+            timestamp,
+            workspace_id: Some("ws".to_string()),
+            session_id: Some(session_id.to_string()),
+        }
+    }
+    
+    #[test]
+    fn test_chat_usage_aggregates() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = CostDatabase::new(dir.path().join("costs.db")).unwrap();
+        let day1 = 1_760_000_000; // 2025-10-09
+        let day2 = day1 + 86_400;
+        
+        for record in [
+            completion("s1", "model-a", 100, 50, 0.25, day1),
+            completion("s1", "model-b", 200, 100, 0.10, day1),
+            completion("s1", "model-a", 300, 10, 0.07, day2),
+            completion("s2", "model-a", 40, 20, 0.50, day2),
+        ] {
+            db.record_cost(&record).unwrap();
+        }
+        // Not a chat completion, and another workspace: both left out
+        db.record_cost(&CostRecord { request_type: "embedding".to_string(), ..completion("s1", "model-a", 9, 9, 9.0, day1) }).unwrap();
+        db.record_cost(&CostRecord { workspace_id: Some("other".to_string()), ..completion("s1", "model-a", 9, 9, 9.0, day1) }).unwrap();
+        
+        let stats = db.get_chat_usage_stats("ws", Some("s1")).unwrap();
+        assert_eq!(stats.total_requests, 3);
+        assert_eq!((stats.total_input_tokens, stats.total_output_tokens), (600, 160));
+        assert!((stats.total_cost_usd - 0.42).abs() < 1e-9);
+        
+        let models: Vec<(&str, i64)> = stats.by_model.iter().map(|b| (b.key.as_str(), b.requests)).collect();
+        assert_eq!(models, vec![("model-a", 2), ("model-b", 1)]);
+        assert!((stats.by_model[0].cost_usd - 0.32).abs() < 1e-9);
+        
+        let all = db.get_chat_usage_stats("ws", None).unwrap();
+        let sessions: Vec<(&str, i64)> = all.by_session.iter().map(|b| (b.key.as_str(), b.input_tokens)).collect();
+        assert_eq!(sessions, vec![("s2", 40), ("s1", 600)]);
+        
+        let days: Vec<(&str, i64)> = all.by_day.iter().map(|b| (b.key.as_str(), b.requests)).collect();
+        assert_eq!(days, vec![("2025-10-09", 2), ("2025-10-10", 2)]);
+        assert!((all.by_day[1].cost_usd - 0.57).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_checkpoint_empties_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("costs.db");
+        let db = CostDatabase::new(path.clone()).unwrap();
+        db.record_cost(&completion("s1", "model-a", 10, 5, 0.01, 1_760_000_000)).unwrap();
+        
+        let wal = dir.path().join("costs.db-wal");
+        assert!(std::fs::metadata(&wal).unwrap().len() > 0);
+        
+        db.checkpoint().unwrap();
+        assert_eq!(std::fs::metadata(&wal).unwrap().len(), 0);
+        drop(db);
+        
+        let reopened = CostDatabase::new(path).unwrap();
+        assert_eq!(reopened.get_recent_records(10).unwrap().len(), 1);
+    }
+}
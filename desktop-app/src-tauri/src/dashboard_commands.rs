@@ -12,8 +12,12 @@ use tokio::sync::Mutex;
 
 use crate::progress_dashboard::{
     ProgressDashboard, Project, Task, Subtask, TimelineEntry,
-    ProjectMetrics, ProjectUpdate, TaskUpdate, Milestone,
+    ProjectMetrics, ProjectUpdate, TaskUpdate, Milestone, BurndownPoint,
+    TimelineBucket, TimelineGranularity, parse_timezone, TaskHistory, compute_burndown,
 };
+use crate::workspace_commands::AppState as WorkspaceAppState;
+use crate::workspace_data::compute_job_progress;
+use crate::workspace_db::GlobalDbStats;
 
 // ============================================
 // State Types
@@ -177,15 +181,47 @@ pub async fn dashboard_toggle_subtask(
 // Timeline Commands
 // ============================================
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProjectTimeline {
+    pub entries: Vec<TimelineEntry>,
+    /// Task creations and completions per period, including empty periods
+    pub buckets: Vec<TimelineBucket>,
+    pub granularity: TimelineGranularity,
+    pub timezone: String,
+}
+
+/// `granularity` is hour/day/week/month (default day) and `timezone` an IANA
+/// name (default UTC). `start`/`end` bound the buckets; otherwise they span the
+/// project's activity.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn dashboard_get_timeline(
     state: State<'_, Arc<Mutex<DashboardState>>>,
     project_id: String,
     limit: Option<usize>,
-) -> Result<Vec<TimelineEntry>, String> {
+    granularity: Option<String>,
+    timezone: Option<String>,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> Result<ProjectTimeline, String> {
+    let granularity = granularity.as_deref()
+        .map(TimelineGranularity::parse)
+        .transpose()?
+        .unwrap_or(TimelineGranularity::Day);
+    let timezone = timezone.unwrap_or_else(|| "UTC".to_string());
+    let tz = parse_timezone(&timezone)?;
+    let range = match (start, end) {
+        (Some(start), Some(end)) => Some((start, end)),
+        (None, None) => None,
+        _ => return Err("Both start and end are required to bound the timeline".to_string()),
+    };
+
     let state = state.lock().await;
-    Ok(state.dashboard.get_project_timeline(&project_id, limit.unwrap_or(50))
-        .into_iter().cloned().collect())
+    let buckets = state.dashboard.get_project_activity(&project_id, granularity, &tz, range)?;
+    let entries = state.dashboard.get_project_timeline(&project_id, limit.unwrap_or(50))
+        .into_iter().cloned().collect();
+
+    Ok(ProjectTimeline { entries, buckets, granularity, timezone })
 }
 
 // ============================================
@@ -227,6 +263,74 @@ pub async fn dashboard_get_board_data(
     })
 }
 
+/// Burndown for a workspace job, or for every job when `job_id` is omitted.
+/// Scope grows as tasks are added and shrinks as they are cancelled. The
+/// ideal line runs from the scope at `start` (default: job creation) to zero
+/// at `end`, or at the projected finish when no end is given.
+#[tauri::command]
+pub async fn dashboard_get_burndown(
+    state: State<'_, WorkspaceAppState>,
+    workspace_id: String,
+    job_id: Option<String>,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> Result<Vec<BurndownPoint>, String> {
+    let timestamp = |value: &str| chrono::DateTime::parse_from_rfc3339(value).ok().map(|d| d.timestamp());
+    
+    let (job_start, tasks) = match &job_id {
+        Some(job_id) => {
+            let job = state.data_ops.get_job(&workspace_id, job_id).map_err(|e| e.to_string())?;
+            let tasks = state.data_ops.list_tasks(&workspace_id, job_id).map_err(|e| e.to_string())?;
+            (timestamp(&job.created_at), tasks)
+        }
+        None => {
+            let mut tasks = Vec::new();
+            for job in state.data_ops.list_jobs(&workspace_id, None).map_err(|e| e.to_string())? {
+                tasks.extend(state.data_ops.list_tasks(&workspace_id, &job.id).map_err(|e| e.to_string())?);
+            }
+            (None, tasks)
+        }
+    };
+    
+    let now = chrono::Utc::now();
+    let history: Vec<TaskHistory> = tasks.iter()
+        .filter_map(|t| {
+            let updated_at = timestamp(&t.updated_at);
+            Some(TaskHistory {
+                created_at: timestamp(&t.created_at)?,
+                completed_at: (t.status == "completed")
+                    .then(|| t.completed_at.as_deref().and_then(timestamp).or(updated_at))
+                    .flatten(),
+                removed_at: (t.status == "cancelled").then_some(updated_at).flatten(),
+            })
+        })
+        .collect();
+    
+    let start = start
+        .or(job_start)
+        .or_else(|| history.iter().map(|h| h.created_at).min())
+        .unwrap_or(now.timestamp());
+    let ideal_end = end.unwrap_or_else(|| {
+        let progress = compute_job_progress(job_id.as_deref().unwrap_or_default(), &tasks, now);
+        now.timestamp() + progress.eta_minutes.unwrap_or(0) as i64 * 60
+    });
+    let end = end.unwrap_or(now.timestamp());
+    if start > end {
+        return Err("Burndown range starts after it ends".to_string());
+    }
+    
+    Ok(compute_burndown(&history, start, end, ideal_end))
+}
+
+#[tauri::command]
+pub async fn dashboard_get_workspace_totals(
+    state: State<'_, WorkspaceAppState>,
+) -> Result<GlobalDbStats, String> {
+    state.db_manager
+        .get_global_stats()
+        .map_err(|e| e.to_string())
+}
+
 #[derive(serde::Serialize)]
 pub struct BoardData {
     pub project: Project,
@@ -51,6 +51,13 @@ impl Database {
         conn.execute_batch(schema)
             .context("Failed to initialize database schema")?;
 
+        // Columns added after release; CREATE TABLE IF NOT EXISTS skips them
+        // on existing databases
+        if conn.prepare("SELECT config_snapshot FROM executions LIMIT 0").is_err() {
+            conn.execute("ALTER TABLE executions ADD COLUMN config_snapshot TEXT", [])
+                .context("Failed to add executions.config_snapshot")?;
+        }
+
         Ok(())
     }
 
@@ -4,8 +4,10 @@
 //! for local sandbox environments on PC/Mac.
 
 use serde::{Deserialize, Serialize};
-use std::process::Command;
-use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::collections::{BTreeMap, HashMap};
 
 /// Container status types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -106,6 +108,204 @@ pub struct SandboxConfig {
     pub cpu_limit: Option<f64>,
 }
 
+/// Raw output of a command run inside a container
+#[derive(Debug, Clone)]
+pub struct ExecOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub exit_code: Option<i32>,
+}
+
+impl ExecOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// One service container of a compose project, from `docker compose ps`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ComposeServiceInfo {
+    pub service: String,
+    pub container_id: String,
+    pub name: String,
+    /// Docker state such as `running` or `exited`
+    pub state: String,
+}
+
+/// Progress of an image pull or build, emitted as Docker reports it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImageProgress {
+    /// Layer id for pulls, step label for builds
+    pub id: Option<String>,
+    pub status: String,
+    /// Progress of this layer or step (0-100), when known
+    pub item_percent: Option<f64>,
+    /// Overall progress (0-100); never decreases within one operation
+    pub percent: f64,
+    pub done: bool,
+}
+
+/// One line of Docker's JSON progress stream
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JsonProgressMessage {
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default, rename = "progressDetail")]
+    pub progress_detail: Option<ProgressDetail>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProgressDetail {
+    #[serde(default)]
+    pub current: Option<u64>,
+    #[serde(default)]
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct LayerProgress {
+    download: f64,
+    extract: f64,
+    done: bool,
+}
+
+impl LayerProgress {
+    /// Downloading dominates pull time, so it carries most of the weight
+    fn fraction(&self) -> f64 {
+        if self.done {
+            1.0
+        } else {
+            0.8 * self.download + 0.2 * self.extract
+        }
+    }
+}
+
+/// Folds interleaved per-layer messages into a monotonic overall percentage
+#[derive(Debug, Default)]
+pub struct PullProgressTracker {
+    layers: BTreeMap<String, LayerProgress>,
+    percent: f64,
+}
+
+impl PullProgressTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, message: &JsonProgressMessage) -> ImageProgress {
+        let status = message.status.clone().unwrap_or_default();
+        let ratio = message.progress_detail.as_ref().and_then(|detail| {
+            match (detail.current, detail.total) {
+                (Some(current), Some(total)) if total > 0 => Some((current as f64 / total as f64).min(1.0)),
+                _ => None,
+            }
+        });
+
+        let mut item_percent = None;
+        if let Some(id) = &message.id {
+            let is_layer_status = matches!(
+                status.as_str(),
+                "Pulling fs layer" | "Waiting" | "Downloading" | "Verifying Checksum"
+                    | "Download complete" | "Extracting" | "Pull complete" | "Already exists"
+            );
+            if is_layer_status || self.layers.contains_key(id) {
+                let layer = self.layers.entry(id.clone()).or_default();
+                match status.as_str() {
+                    "Downloading" => layer.download = layer.download.max(ratio.unwrap_or(0.0)),
+                    "Verifying Checksum" | "Download complete" => layer.download = 1.0,
+                    "Extracting" => {
+                        layer.download = 1.0;
+                        layer.extract = layer.extract.max(ratio.unwrap_or(0.0));
+                    }
+                    "Pull complete" | "Already exists" => layer.done = true,
+                    _ => {}
+                }
+                item_percent = Some(layer.fraction() * 100.0);
+            }
+        }
+
+        // Final "Status: ..." line, or a digest once every layer is in
+        let done = status.starts_with("Status:");
+        let overall = if done {
+            100.0
+        } else if self.layers.is_empty() {
+            0.0
+        } else {
+            let total: f64 = self.layers.values().map(LayerProgress::fraction).sum();
+            100.0 * total / self.layers.len() as f64
+        };
+        // Layers announced late would otherwise pull the average back down
+        self.percent = self.percent.max(overall);
+
+        ImageProgress {
+            id: message.id.clone(),
+            status,
+            item_percent,
+            percent: self.percent,
+            done,
+        }
+    }
+}
+
+/// Tracks `docker build` steps from BuildKit (`#5 [2/4] RUN ...`) or the
+/// classic builder (`Step 2/4 : RUN ...`)
+#[derive(Debug, Default)]
+pub struct BuildProgressTracker {
+    percent: f64,
+}
+
+impl BuildProgressTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, line: &str) -> Option<ImageProgress> {
+        let line = line.trim();
+        let (step, total) = Self::parse_step(line)?;
+        // A step has started, so the ones before it are complete
+        self.percent = self.percent.max(100.0 * (step - 1) as f64 / total as f64);
+
+        Some(ImageProgress {
+            id: Some(format!("{}/{}", step, total)),
+            status: line.to_string(),
+            item_percent: None,
+            percent: self.percent,
+            done: false,
+        })
+    }
+
+    pub fn finish(&mut self) -> ImageProgress {
+        self.percent = 100.0;
+        ImageProgress {
+            id: None,
+            status: "Build complete".to_string(),
+            item_percent: None,
+            percent: 100.0,
+            done: true,
+        }
+    }
+
+    fn parse_step(line: &str) -> Option<(u32, u32)> {
+        let fraction = if let Some(rest) = line.strip_prefix("Step ") {
+            rest.split_whitespace().next()?
+        } else if line.starts_with('#') {
+            // `#7 [builder 3/5] RUN ...`; the stage name is optional
+            let inner = &line[line.find('[')? + 1..line.find(']')?];
+            inner.split_whitespace().last()?
+        } else {
+            return None;
+        };
+
+        let (step, total) = fraction.split_once('/')?;
+        let (step, total) = (step.parse().ok()?, total.parse().ok()?);
+        (step >= 1 && step <= total).then_some((step, total))
+    }
+}
+
 /// Docker Manager - handles all Docker operations
 pub struct DockerManager;
 
@@ -222,7 +422,7 @@ impl DockerManager {
                     name: parts.get(1).unwrap_or(&"").to_string(),
                     image: parts.get(2).unwrap_or(&"").to_string(),
                     uptime: parts.get(3).unwrap_or(&"").to_string(),
-                    status: ContainerStatus::from(parts.get(4).unwrap_or(&"")),
+                    status: ContainerStatus::from(*parts.get(4).unwrap_or(&"")),
                     state: parts.get(4).unwrap_or(&"").to_string(),
                     created: parts.get(5).unwrap_or(&"").to_string(),
                     ports: parts.get(6)
@@ -398,20 +598,348 @@ impl DockerManager {
         Ok(images)
     }
 
-    /// Pull a Docker image
+    /// Pull a Docker image, blocking until it finishes
     pub fn pull_image(image: &str) -> Result<String, String> {
-        let output = Command::new("docker")
+        Self::pull_image_streaming(image, |_| {})
+    }
+
+    /// Pull a Docker image, reporting progress as it arrives. Returns the
+    /// final status line.
+    ///
+    /// Byte-level progress comes from the Engine API's JSON stream on the
+    /// local socket. Without one (or for registries that need the CLI's
+    /// credentials), `docker pull` status lines are tracked instead.
+    pub fn pull_image_streaming<F>(image: &str, mut on_progress: F) -> Result<String, String>
+    where
+        F: FnMut(ImageProgress),
+    {
+        let mut tracker = PullProgressTracker::new();
+        let mut last_status = String::new();
+        let mut handle = |message: JsonProgressMessage| {
+            let progress = tracker.update(&message);
+            last_status = progress.status.clone();
+            on_progress(progress);
+        };
+
+        #[cfg(unix)]
+        match Self::engine_pull(image, &mut handle) {
+            Ok(true) => return Ok(last_status),
+            Ok(false) => {}
+            Err(e) if !Self::needs_cli_credentials(&e) => return Err(e),
+            Err(_) => {}
+        }
+
+        let mut child = Command::new("docker")
             .args(["pull", image])
-            .output()
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
             .map_err(|e| format!("Failed to pull image: {}", e))?;
 
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if let Some(message) = Self::parse_pull_line(&line) {
+                    handle(message);
+                }
+            }
+        }
+
+        let output = child.wait_with_output()
+            .map_err(|e| format!("Failed to pull image: {}", e))?;
         if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+            Ok(last_status)
         } else {
             Err(String::from_utf8_lossy(&output.stderr).to_string())
         }
     }
 
+    /// `docker pull` plain output: `<layer>: <status>` or a bare status line
+    fn parse_pull_line(line: &str) -> Option<JsonProgressMessage> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+
+        let (id, status) = match line.split_once(": ") {
+            Some((id, status)) if !id.contains(' ') && id != "Status" && id != "Digest" => {
+                (Some(id.to_string()), status.to_string())
+            }
+            _ => (None, line.to_string()),
+        };
+
+        Some(JsonProgressMessage {
+            status: Some(status),
+            id,
+            ..JsonProgressMessage::default()
+        })
+    }
+
+    fn needs_cli_credentials(error: &str) -> bool {
+        let error = error.to_lowercase();
+        ["unauthorized", "denied", "authentication required", "no basic auth"]
+            .iter()
+            .any(|needle| error.contains(needle))
+    }
+
+    /// `$DOCKER_CONFIG`, else `~/.docker`
+    fn docker_config_dir() -> Option<std::path::PathBuf> {
+        std::env::var_os("DOCKER_CONFIG")
+            .map(std::path::PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".docker")))
+    }
+
+    fn read_docker_config(config_dir: &Path) -> serde_json::Value {
+        std::fs::read_to_string(config_dir.join("config.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Local Engine API socket the `docker` CLI would talk to, if it's one.
+    /// `None` when Docker is reached some other way (TCP, SSH) or not at all.
+    #[cfg(unix)]
+    fn engine_socket() -> Option<std::path::PathBuf> {
+        Self::resolve_engine_socket(
+            std::env::var("DOCKER_HOST").ok(),
+            std::env::var("DOCKER_CONTEXT").ok(),
+            Self::docker_config_dir().as_deref(),
+        )
+    }
+
+    /// `DOCKER_HOST` wins, then `DOCKER_CONTEXT`, then the config's current
+    /// context, then the default socket locations
+    #[cfg(unix)]
+    fn resolve_engine_socket(
+        docker_host: Option<String>,
+        docker_context: Option<String>,
+        config_dir: Option<&Path>,
+    ) -> Option<std::path::PathBuf> {
+        let unix_socket = |host: &str| host.strip_prefix("unix://").map(std::path::PathBuf::from);
+        if let Some(host) = docker_host.filter(|h| !h.is_empty()) {
+            return unix_socket(&host);
+        }
+
+        let context = docker_context.filter(|c| !c.is_empty()).or_else(|| {
+            config_dir
+                .map(Self::read_docker_config)
+                .and_then(|config| config["currentContext"].as_str().map(String::from))
+        });
+        if let Some(context) = context.filter(|c| c != "default") {
+            use sha2::{Digest, Sha256};
+
+            // The CLI stores each context under the SHA-256 of its name
+            let meta_path = config_dir?
+                .join("contexts/meta")
+                .join(hex::encode(Sha256::digest(context.as_bytes())))
+                .join("meta.json");
+            let meta: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(meta_path).ok()?).ok()?;
+            return unix_socket(meta["Endpoints"]["docker"]["Host"].as_str()?);
+        }
+
+        let mut candidates = vec![std::path::PathBuf::from("/var/run/docker.sock")];
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(home.join(".docker/run/docker.sock"));
+        }
+        candidates.into_iter().find(|path| path.exists())
+    }
+
+    /// Registry host of an image reference, as the CLI keys its credentials
+    fn registry_of(image: &str) -> String {
+        match image.split_once('/') {
+            Some((first, _)) if first.contains('.') || first.contains(':') || first == "localhost" => first.to_string(),
+            _ => "https://index.docker.io/v1/".to_string(),
+        }
+    }
+
+    /// `X-Registry-Auth` value for pulling `image`: `Ok(None)` to pull
+    /// anonymously, `Err` when only the CLI can find the credentials
+    fn registry_auth(image: &str, config_dir: Option<&Path>) -> Result<Option<String>, String> {
+        use base64::Engine;
+
+        let Some(config) = config_dir.map(Self::read_docker_config) else {
+            return Ok(None);
+        };
+        let registry = Self::registry_of(image);
+        let encode = |auth: serde_json::Value| {
+            Some(base64::engine::general_purpose::URL_SAFE.encode(auth.to_string()))
+        };
+
+        if let Some(entry) = config["auths"].get(&registry) {
+            if let Some(token) = entry["identitytoken"].as_str() {
+                return Ok(encode(serde_json::json!({ "identitytoken": token, "serveraddress": registry })));
+            }
+            let decoded = entry["auth"].as_str()
+                .and_then(|auth| base64::engine::general_purpose::STANDARD.decode(auth).ok())
+                .and_then(|bytes| String::from_utf8(bytes).ok());
+            if let Some((username, password)) = decoded.as_deref().and_then(|d| d.split_once(':')) {
+                return Ok(encode(serde_json::json!({
+                    "username": username,
+                    "password": password,
+                    "serveraddress": registry,
+                })));
+            }
+        }
+
+        // Credentials held by a helper (e.g. the OS keychain)
+        let helper = config["credHelpers"][registry.as_str()].as_str()
+            .or_else(|| config["credsStore"].as_str());
+        match helper {
+            Some(helper) => Err(format!("Credentials for {} are held by docker-credential-{}", registry, helper)),
+            None => Ok(None),
+        }
+    }
+
+    /// Pull through the Engine API. `Ok(false)` means the API can't be used
+    /// the way the CLI would, so the caller should run `docker pull`.
+    #[cfg(unix)]
+    fn engine_pull(image: &str, on_message: &mut dyn FnMut(JsonProgressMessage)) -> Result<bool, String> {
+        let Some(socket) = Self::engine_socket() else {
+            return Ok(false);
+        };
+        let Ok(auth) = Self::registry_auth(image, Self::docker_config_dir().as_deref()) else {
+            return Ok(false);
+        };
+        let Ok(mut stream) = std::os::unix::net::UnixStream::connect(&socket) else {
+            return Ok(false);
+        };
+
+        let (name, tag) = Self::split_image_reference(image);
+        let mut path = format!("/images/create?fromImage={}", Self::encode_query(&name));
+        if let Some(tag) = tag {
+            path.push_str(&format!("&tag={}", Self::encode_query(&tag)));
+        }
+
+        // HTTP/1.0 so the daemon streams the body and closes, without chunking
+        let auth_header = auth.map(|auth| format!("X-Registry-Auth: {}\r\n", auth)).unwrap_or_default();
+        write!(stream, "POST {} HTTP/1.0\r\nHost: docker\r\n{}Content-Length: 0\r\n\r\n", path, auth_header)
+            .map_err(|e| format!("Failed to pull image: {}", e))?;
+
+        let mut reader = BufReader::new(stream);
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line)
+            .map_err(|e| format!("Failed to pull image: {}", e))?;
+        let status_code = status_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+
+        // Skip headers
+        let mut header = String::new();
+        loop {
+            header.clear();
+            let read = reader.read_line(&mut header)
+                .map_err(|e| format!("Failed to pull image: {}", e))?;
+            if read == 0 || header.trim().is_empty() {
+                break;
+            }
+        }
+
+        if status_code != "200" {
+            let mut body = String::new();
+            let _ = std::io::Read::read_to_string(&mut reader, &mut body);
+            let message = serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from))
+                .unwrap_or(body);
+            return Err(format!("Failed to pull image: {}", message.trim()));
+        }
+
+        for line in reader.lines() {
+            let line = line.map_err(|e| format!("Failed to pull image: {}", e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message: JsonProgressMessage = serde_json::from_str(&line)
+                .map_err(|e| format!("Failed to parse pull progress: {}", e))?;
+            if let Some(error) = &message.error {
+                return Err(format!("Failed to pull image: {}", error));
+            }
+            on_message(message);
+        }
+
+        Ok(true)
+    }
+
+    /// Split `repo[:tag]` or `repo@digest`; a registry port is not a tag
+    fn split_image_reference(image: &str) -> (String, Option<String>) {
+        if image.contains('@') {
+            return (image.to_string(), None);
+        }
+        let last_segment = image.rfind('/').map(|i| i + 1).unwrap_or(0);
+        match image[last_segment..].rfind(':') {
+            Some(i) => {
+                let split = last_segment + i;
+                (image[..split].to_string(), Some(image[split + 1..].to_string()))
+            }
+            None => (image.to_string(), Some("latest".to_string())),
+        }
+    }
+
+    fn encode_query(value: &str) -> String {
+        value.bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect()
+    }
+
+    /// Build an image with `docker build`, reporting step progress
+    pub fn build_image<F>(
+        context_dir: &Path,
+        tag: &str,
+        dockerfile: Option<&Path>,
+        mut on_progress: F,
+    ) -> Result<String, String>
+    where
+        F: FnMut(ImageProgress),
+    {
+        let mut args = vec!["build".to_string(), "--progress=plain".to_string(), "-t".to_string(), tag.to_string()];
+        if let Some(file) = dockerfile {
+            args.push("-f".to_string());
+            args.push(file.to_string_lossy().to_string());
+        }
+        args.push(context_dir.to_string_lossy().to_string());
+
+        // BuildKit writes progress to stderr and the classic builder to stdout
+        let mut child = Command::new("docker")
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to build image: {}", e))?;
+
+        let stdout = child.stdout.take();
+        let stdout_reader = std::thread::spawn(move || {
+            stdout.map(|out| BufReader::new(out).lines().map_while(Result::ok).collect::<Vec<_>>())
+                .unwrap_or_default()
+        });
+
+        let mut tracker = BuildProgressTracker::new();
+        let mut log = Vec::new();
+        if let Some(stderr) = child.stderr.take() {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if let Some(progress) = tracker.update(&line) {
+                    on_progress(progress);
+                }
+                log.push(line);
+            }
+        }
+        for line in stdout_reader.join().unwrap_or_default() {
+            if let Some(progress) = tracker.update(&line) {
+                on_progress(progress);
+            }
+            log.push(line);
+        }
+
+        let status = child.wait().map_err(|e| format!("Failed to build image: {}", e))?;
+        if status.success() {
+            on_progress(tracker.finish());
+            Ok(tag.to_string())
+        } else {
+            // The tail of the log holds the failing step's output
+            let tail = log.len().saturating_sub(20);
+            Err(format!("Docker build failed:\n{}", log[tail..].join("\n")))
+        }
+    }
+
     /// Remove a Docker image
     pub fn remove_image(image_id: &str, force: bool) -> Result<(), String> {
         let mut args = vec!["rmi", image_id];
@@ -495,6 +1023,151 @@ impl DockerManager {
         }
     }
 
+    /// Execute an argument vector in a container, optionally in `workdir` and fed `stdin`.
+    /// Unlike `exec_in_container`, a non-zero exit is returned as output, not an error.
+    pub fn exec_command(
+        container_id: &str,
+        command: &[String],
+        workdir: Option<&str>,
+        stdin: Option<&[u8]>,
+    ) -> Result<ExecOutput, String> {
+        if command.is_empty() {
+            return Err("No command to execute".to_string());
+        }
+
+        let mut child = Command::new("docker")
+            .args(Self::exec_args(container_id, command, workdir, stdin.is_some()))
+            .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to exec in container: {}", e))?;
+
+        if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(input)
+                .map_err(|e| format!("Failed to write to container stdin: {}", e))?;
+        }
+
+        let output = child.wait_with_output()
+            .map_err(|e| format!("Failed to exec in container: {}", e))?;
+
+        Ok(ExecOutput {
+            stdout: output.stdout,
+            stderr: output.stderr,
+            exit_code: output.status.code(),
+        })
+    }
+
+    fn exec_args(container_id: &str, command: &[String], workdir: Option<&str>, interactive: bool) -> Vec<String> {
+        let mut args = vec!["exec".to_string()];
+        if interactive {
+            args.push("-i".to_string());
+        }
+        if let Some(dir) = workdir {
+            args.push("-w".to_string());
+            args.push(dir.to_string());
+        }
+        args.push(container_id.to_string());
+        args.extend(command.iter().cloned());
+        args
+    }
+
+    /// Whether a container exists and is currently running
+    pub fn is_container_running(container_id: &str) -> bool {
+        Command::new("docker")
+            .args(["inspect", "-f", "{{.State.Running}}", container_id])
+            .output()
+            .map(|output| output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "true")
+            .unwrap_or(false)
+    }
+
+    // ========================================
+    // Compose Projects
+    // ========================================
+
+    fn compose_args(project: &str, compose_file: Option<&Path>, command: &[&str]) -> Vec<String> {
+        let mut args = vec!["compose".to_string(), "-p".to_string(), project.to_string()];
+        if let Some(file) = compose_file {
+            args.push("-f".to_string());
+            args.push(file.to_string_lossy().to_string());
+        }
+        args.extend(command.iter().map(|arg| arg.to_string()));
+        args
+    }
+
+    fn run_compose(args: &[String], action: &str) -> Result<String, String> {
+        let output = Command::new("docker")
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to {} compose project: {}", action, e))?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(format!(
+                "Docker compose {} failed: {}",
+                action,
+                String::from_utf8_lossy(&output.stderr)
+            ))
+        }
+    }
+
+    /// Create and start every service of a compose project in the background
+    pub fn compose_up(project: &str, compose_file: &Path) -> Result<(), String> {
+        let args = Self::compose_args(project, Some(compose_file), &["up", "-d", "--remove-orphans"]);
+        Self::run_compose(&args, "up").map(|_| ())
+    }
+
+    /// Stop and remove a compose project's containers and networks
+    pub fn compose_down(project: &str, remove_volumes: bool) -> Result<(), String> {
+        let mut command = vec!["down", "--remove-orphans"];
+        if remove_volumes {
+            command.push("--volumes");
+        }
+        let args = Self::compose_args(project, None, &command);
+        Self::run_compose(&args, "down").map(|_| ())
+    }
+
+    /// List a compose project's containers, including stopped ones
+    pub fn compose_ps(project: &str) -> Result<Vec<ComposeServiceInfo>, String> {
+        let args = Self::compose_args(project, None, &["ps", "-a", "--format", "json"]);
+        let output = Self::run_compose(&args, "ps")?;
+        Self::parse_compose_ps(&output)
+    }
+
+    /// Parse `docker compose ps --format json`, which is a JSON array on older
+    /// Compose releases and one object per line on newer ones
+    fn parse_compose_ps(output: &str) -> Result<Vec<ComposeServiceInfo>, String> {
+        let trimmed = output.trim();
+        if trimmed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let values: Vec<serde_json::Value> = if trimmed.starts_with('[') {
+            serde_json::from_str(trimmed)
+                .map_err(|e| format!("Failed to parse compose ps output: {}", e))?
+        } else {
+            trimmed.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to parse compose ps output: {}", e))?
+        };
+
+        let field = |value: &serde_json::Value, key: &str| {
+            value.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+        };
+
+        Ok(values.iter()
+            .map(|value| ComposeServiceInfo {
+                service: field(value, "Service"),
+                container_id: field(value, "ID"),
+                name: field(value, "Name"),
+                state: field(value, "State").to_lowercase(),
+            })
+            .collect())
+    }
+
     /// Prune unused containers
     pub fn prune_containers() -> Result<String, String> {
         let output = Command::new("docker")
@@ -557,6 +1230,198 @@ mod tests {
         assert_eq!(DockerManager::parse_size("500KB"), 500 * 1024);
     }
 
+    fn message(id: &str, status: &str, current: u64, total: u64) -> JsonProgressMessage {
+        JsonProgressMessage {
+            status: Some(status.to_string()),
+            id: Some(id.to_string()),
+            progress_detail: Some(ProgressDetail { current: Some(current), total: Some(total) }),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_pull_progress_is_monotonic_with_interleaved_layers() {
+        let mut tracker = PullProgressTracker::new();
+        let stream = [
+            message("a", "Pulling fs layer", 0, 0),
+            message("a", "Downloading", 50, 100),
+            message("a", "Downloading", 100, 100),
+            // A second layer appears late and would halve a naive average
+            message("b", "Pulling fs layer", 0, 0),
+            message("b", "Downloading", 10, 100),
+            message("a", "Downloading", 40, 100),
+            message("a", "Extracting", 100, 100),
+            message("b", "Download complete", 0, 0),
+            message("a", "Pull complete", 0, 0),
+            message("b", "Pull complete", 0, 0),
+        ];
+
+        let mut last = 0.0;
+        for msg in &stream {
+            let progress = tracker.update(msg);
+            assert!(progress.percent >= last, "{} dropped below {}", progress.percent, last);
+            assert!(progress.percent <= 100.0);
+            last = progress.percent;
+        }
+        assert_eq!(last, 100.0);
+
+        let done = tracker.update(&JsonProgressMessage {
+            status: Some("Status: Downloaded newer image for node:20".to_string()),
+            ..JsonProgressMessage::default()
+        });
+        assert!(done.done);
+    }
+
+    #[test]
+    fn test_pull_progress_from_json_lines() {
+        let line = r#"{"status":"Downloading","progressDetail":{"current":25,"total":100},"progress":"[=>  ]","id":"f1"}"#;
+        let parsed: JsonProgressMessage = serde_json::from_str(line).unwrap();
+        let progress = PullProgressTracker::new().update(&parsed);
+        assert_eq!(progress.id.as_deref(), Some("f1"));
+        assert_eq!(progress.item_percent, Some(20.0));
+
+        // "Pulling from" carries the tag as its id but is not a layer
+        let header = DockerManager::parse_pull_line("latest: Pulling from library/node").unwrap();
+        let progress = PullProgressTracker::new().update(&header);
+        assert_eq!(progress.item_percent, None);
+        assert_eq!(progress.percent, 0.0);
+    }
+
+    #[test]
+    fn test_parse_pull_line() {
+        let layer = DockerManager::parse_pull_line("a2abf6c4d29d: Pull complete").unwrap();
+        assert_eq!(layer.id.as_deref(), Some("a2abf6c4d29d"));
+        assert_eq!(layer.status.as_deref(), Some("Pull complete"));
+
+        let digest = DockerManager::parse_pull_line("Digest: sha256:abc").unwrap();
+        assert_eq!(digest.id, None);
+        assert!(DockerManager::parse_pull_line("   ").is_none());
+    }
+
+    #[test]
+    fn test_build_progress_steps() {
+        let mut tracker = BuildProgressTracker::new();
+        assert!(tracker.update("#1 [internal] load build definition").is_none());
+        assert_eq!(tracker.update("#5 [1/4] FROM node:20").unwrap().percent, 0.0);
+        assert_eq!(tracker.update("#7 [builder 3/4] RUN npm ci").unwrap().percent, 50.0);
+        // A parallel stage reporting an earlier step keeps the percentage
+        assert_eq!(tracker.update("#6 [2/4] COPY . .").unwrap().percent, 50.0);
+        assert_eq!(tracker.update("Step 4/4 : CMD [\"node\"]").unwrap().percent, 75.0);
+        assert_eq!(tracker.finish().percent, 100.0);
+    }
+
+    #[test]
+    fn test_split_image_reference() {
+        assert_eq!(DockerManager::split_image_reference("node"), ("node".to_string(), Some("latest".to_string())));
+        assert_eq!(DockerManager::split_image_reference("node:20-alpine"), ("node".to_string(), Some("20-alpine".to_string())));
+        assert_eq!(
+            DockerManager::split_image_reference("localhost:5000/app"),
+            ("localhost:5000/app".to_string(), Some("latest".to_string())),
+        );
+        assert_eq!(DockerManager::split_image_reference("app@sha256:abc").1, None);
+        assert_eq!(DockerManager::encode_query("ghcr.io/a b"), "ghcr.io%2Fa%20b");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_engine_socket_follows_the_active_context() {
+        use sha2::{Digest, Sha256};
+
+        let config_dir = tempfile::tempdir().unwrap();
+        let write_context = |name: &str, host: &str| {
+            let dir = config_dir.path().join("contexts/meta").join(hex::encode(Sha256::digest(name.as_bytes())));
+            std::fs::create_dir_all(&dir).unwrap();
+            let meta = serde_json::json!({ "Name": name, "Endpoints": { "docker": { "Host": host } } });
+            std::fs::write(dir.join("meta.json"), meta.to_string()).unwrap();
+        };
+        write_context("colima", "unix:///home/me/.colima/default/docker.sock");
+        write_context("remote", "ssh://me@build-box");
+        std::fs::write(config_dir.path().join("config.json"), r#"{"currentContext": "colima"}"#).unwrap();
+
+        let resolve = |host: Option<&str>, context: Option<&str>| {
+            DockerManager::resolve_engine_socket(host.map(String::from), context.map(String::from), Some(config_dir.path()))
+        };
+        assert_eq!(resolve(None, None), Some("/home/me/.colima/default/docker.sock".into()));
+        assert_eq!(resolve(None, Some("remote")), None);
+        assert_eq!(resolve(None, Some("missing")), None);
+        assert_eq!(resolve(Some("unix:///tmp/d.sock"), Some("remote")), Some("/tmp/d.sock".into()));
+        assert_eq!(resolve(Some("tcp://10.0.0.2:2375"), None), None);
+    }
+
+    #[test]
+    fn test_registry_auth_from_docker_config() {
+        use base64::Engine;
+
+        let config_dir = tempfile::tempdir().unwrap();
+        let basic = base64::engine::general_purpose::STANDARD.encode("octo:s3cret");
+        let config = serde_json::json!({
+            "auths": {
+                "ghcr.io": { "auth": basic },
+                "https://index.docker.io/v1/": { "identitytoken": "tok" },
+            },
+            "credHelpers": { "123.dkr.ecr.us-east-1.amazonaws.com": "ecr-login" },
+        });
+        std::fs::write(config_dir.path().join("config.json"), config.to_string()).unwrap();
+        let auth = |image: &str| DockerManager::registry_auth(image, Some(config_dir.path()));
+        let decode = |header: String| -> serde_json::Value {
+            let bytes = base64::engine::general_purpose::URL_SAFE.decode(header).unwrap();
+            serde_json::from_slice(&bytes).unwrap()
+        };
+
+        let ghcr = decode(auth("ghcr.io/org/app:1").unwrap().unwrap());
+        assert_eq!((ghcr["username"].as_str(), ghcr["password"].as_str()), (Some("octo"), Some("s3cret")));
+        assert_eq!(ghcr["serveraddress"], "ghcr.io");
+        assert_eq!(decode(auth("library/node").unwrap().unwrap())["identitytoken"], "tok");
+        assert!(auth("123.dkr.ecr.us-east-1.amazonaws.com/app").is_err());
+        assert_eq!(auth("quay.io/org/app").unwrap(), None);
+        assert_eq!(DockerManager::registry_of("localhost:5000/app"), "localhost:5000");
+        assert_eq!(DockerManager::registry_of("node"), "https://index.docker.io/v1/");
+    }
+
+    #[test]
+    fn test_compose_args() {
+        assert_eq!(
+            DockerManager::compose_args("smartspec-app-main", Some(Path::new("/ws/compose.yml")), &["up", "-d"]),
+            vec!["compose", "-p", "smartspec-app-main", "-f", "/ws/compose.yml", "up", "-d"],
+        );
+        assert_eq!(
+            DockerManager::compose_args("p", None, &["ps"]),
+            vec!["compose", "-p", "p", "ps"],
+        );
+    }
+
+    #[test]
+    fn test_parse_compose_ps_formats() {
+        let lines = r#"{"ID":"abc","Name":"p-app-1","Service":"app","State":"running"}
+{"ID":"def","Name":"p-db-1","Service":"db","State":"exited"}"#;
+        let array = r#"[{"ID":"abc","Name":"p-app-1","Service":"app","State":"Running"},
+            {"ID":"def","Name":"p-db-1","Service":"db","State":"exited"}]"#;
+
+        for output in [lines, array] {
+            let services = DockerManager::parse_compose_ps(output).unwrap();
+            assert_eq!(services.len(), 2);
+            assert_eq!(services[0].service, "app");
+            assert_eq!(services[0].state, "running");
+            assert_eq!(services[1].container_id, "def");
+        }
+
+        assert!(DockerManager::parse_compose_ps("").unwrap().is_empty());
+        assert!(DockerManager::parse_compose_ps("not json").is_err());
+    }
+
+    #[test]
+    fn test_exec_args() {
+        let command = vec!["sh".to_string(), "-c".to_string(), "cat > a.txt".to_string()];
+        assert_eq!(
+            DockerManager::exec_args("abc123", &command, Some("/workspace/project"), true),
+            vec!["exec", "-i", "-w", "/workspace/project", "abc123", "sh", "-c", "cat > a.txt"],
+        );
+        assert_eq!(
+            DockerManager::exec_args("abc123", &command[..1], None, false),
+            vec!["exec", "abc123", "sh"],
+        );
+    }
+
     #[test]
     fn test_container_status_from_str() {
         assert_eq!(ContainerStatus::from("running"), ContainerStatus::Running);
@@ -6,14 +6,18 @@
 // - Audit logging
 // - Compliance settings
 
+use serde::Serialize;
 use tauri::State;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::report_export::ReportFormat;
 use crate::enterprise::{
-    EnterpriseService, SsoConfig, SsoProvider, Role, Permission, UserRole,
+    AuditStore, EnterpriseService, SessionUser, SsoConfig, SsoProvider, SsoValidationReport, Role, Permission, UserRole,
+    EffectivePermission, PermissionAction,
     RoleScope, AuditLog, AuditAction, AuditStatus, AuditQuery, AuditQueryResult,
-    ComplianceSettings, DataExportRequest, ExportType,
+    AuditExport, AuditIntegrityReport, ComplianceReport, ReportFilter, ReportHeader, ReportKind,
+    ComplianceSettings, DataExportRequest, ExportType, backend_url, fetch_session_user, validate_sso_config,
 };
 
 // ============================================
@@ -30,23 +34,92 @@ impl EnterpriseState {
             service: EnterpriseService::new(),
         }
     }
+
+    /// State whose audit chain is loaded from and persisted to `store`
+    pub fn with_audit_store(store: AuditStore) -> Result<Self, String> {
+        Ok(Self {
+            service: EnterpriseService::with_audit_store(store)?,
+        })
+    }
+}
+
+// ============================================
+// Session Commands
+// ============================================
+
+/// Resolve the stored auth token to a user through the backend and make them
+/// the actor for every permission check that follows
+#[tauri::command]
+pub async fn ent_sign_in(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+) -> Result<SessionUser, String> {
+    let token = crate::secure_store::get_auth_token()?
+        .ok_or_else(|| "Not logged in".to_string())?;
+    let user = fetch_session_user(&backend_url(), &token).await?;
+
+    let mut state = state.lock().await;
+    state.service.sign_in(user.clone());
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn ent_sign_out(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.service.sign_out();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ent_get_session(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+) -> Result<Option<SessionUser>, String> {
+    let state = state.lock().await;
+    Ok(state.service.session.clone())
+}
+
+/// Make the signed-in backend administrator the first RBAC admin
+#[tauri::command]
+pub async fn ent_bootstrap_admin(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+) -> Result<UserRole, String> {
+    let mut state = state.lock().await;
+    state.service.bootstrap_admin()
 }
 
 // ============================================
 // SSO Commands
 // ============================================
 
+/// Validate and store an SSO config. Requires admin on `sso`. Discovery runs
+/// without the lock; the config is only stored if the report is valid.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn ent_configure_sso(
     state: State<'_, Arc<Mutex<EnterpriseState>>>,
     provider: String,
     client_id: String,
     tenant_id: Option<String>,
     domain: Option<String>,
-) -> Result<SsoConfig, String> {
-    let mut state = state.lock().await;
+    issuer: Option<String>,
+    metadata_url: Option<String>,
+    metadata_xml: Option<String>,
+) -> Result<SsoValidationReport, String> {
+    state.lock().await.service.require_session_permission(PermissionAction::Admin, "sso")?;
     let sso_provider = parse_sso_provider(&provider)?;
-    Ok(state.service.configure_sso(sso_provider, &client_id, tenant_id.as_deref(), domain.as_deref()))
+    let mut config = SsoConfig::new(sso_provider, &client_id, tenant_id.as_deref(), domain.as_deref());
+    config.issuer = issuer;
+    config.metadata_url = metadata_url;
+
+    let mut report = validate_sso_config(config, metadata_xml).await;
+    if let Some(config) = report.config.take() {
+        let mut state = state.lock().await;
+        // The session may have changed while discovery ran
+        state.service.require_session_permission(PermissionAction::Admin, "sso")?;
+        report.config = Some(state.service.configure_sso(config));
+    }
+    Ok(report)
 }
 
 #[tauri::command]
@@ -63,6 +136,7 @@ pub async fn ent_disable_sso(
     config_id: String,
 ) -> Result<(), String> {
     let mut state = state.lock().await;
+    state.service.require_session_permission(PermissionAction::Admin, "sso")?;
     state.service.disable_sso(&config_id)
 }
 
@@ -76,9 +150,22 @@ pub async fn ent_create_role(
     name: String,
     description: String,
     permissions: Vec<Permission>,
+    parent_role: Option<String>,
+) -> Result<Role, String> {
+    let mut state = state.lock().await;
+    state.service.require_session_permission(PermissionAction::Admin, "role")?;
+    state.service.create_role(&name, &description, permissions, parent_role.as_deref())
+}
+
+#[tauri::command]
+pub async fn ent_set_role_parent(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+    role_id: String,
+    parent_role: Option<String>,
 ) -> Result<Role, String> {
     let mut state = state.lock().await;
-    Ok(state.service.create_role(&name, &description, permissions))
+    state.service.require_session_permission(PermissionAction::Admin, "role")?;
+    state.service.set_parent_role(&role_id, parent_role.as_deref())
 }
 
 #[tauri::command]
@@ -96,9 +183,9 @@ pub async fn ent_assign_role(
     role_id: String,
     scope_type: String,
     scope_id: Option<String>,
-    assigned_by: String,
 ) -> Result<UserRole, String> {
     let mut state = state.lock().await;
+    let assigned_by = state.service.require_session_permission(PermissionAction::Admin, "role")?;
     let scope = parse_role_scope(&scope_type, scope_id)?;
     state.service.assign_role(&user_id, &role_id, scope, &assigned_by)
 }
@@ -110,6 +197,7 @@ pub async fn ent_revoke_role(
     role_id: String,
 ) -> Result<(), String> {
     let mut state = state.lock().await;
+    state.service.require_session_permission(PermissionAction::Admin, "role")?;
     state.service.revoke_role(&user_id, &role_id)
 }
 
@@ -134,6 +222,16 @@ pub async fn ent_check_permission(
     Ok(state.service.check_permission(&user_id, &resource, &perm_action))
 }
 
+/// Resolved permissions for a user, each with the role that granted it
+#[tauri::command]
+pub async fn ent_get_permissions(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+    user_id: String,
+) -> Result<Vec<EffectivePermission>, String> {
+    let state = state.lock().await;
+    Ok(state.service.effective_permissions(&user_id))
+}
+
 // ============================================
 // Audit Log Commands
 // ============================================
@@ -164,6 +262,26 @@ pub async fn ent_query_audit_logs(
     Ok(state.service.query_audit_logs(&query))
 }
 
+/// Export the full audit chain with its hashes for external verification
+#[tauri::command]
+pub async fn ent_export_audit_logs(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+) -> Result<AuditExport, String> {
+    let state = state.lock().await;
+    state.service.require_session_permission(PermissionAction::Read, "audit_log")?;
+    Ok(state.service.export_audit_logs())
+}
+
+/// Re-walk the audit hash chain and report the first broken link
+#[tauri::command]
+pub async fn ent_verify_audit_integrity(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+) -> Result<AuditIntegrityReport, String> {
+    let state = state.lock().await;
+    state.service.require_session_permission(PermissionAction::Read, "audit_log")?;
+    Ok(state.service.verify_audit_integrity())
+}
+
 // ============================================
 // Compliance Commands
 // ============================================
@@ -182,10 +300,73 @@ pub async fn ent_update_compliance_settings(
     settings: ComplianceSettings,
 ) -> Result<(), String> {
     let mut state = state.lock().await;
+    state.service.require_session_permission(PermissionAction::Update, "compliance")?;
     state.service.update_compliance_settings(settings);
     Ok(())
 }
 
+#[tauri::command]
+pub async fn ent_get_compliance_report(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+    filter: Option<ReportFilter>,
+    organization: Option<String>,
+    workspace: Option<String>,
+) -> Result<ComplianceReport, String> {
+    let state = state.lock().await;
+    let header = report_header(ReportKind::Compliance, filter, organization, workspace);
+    Ok(state.service.compliance_report(header))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportExportResult {
+    pub path: String,
+    /// Data rows for CSV, pages for PDF
+    pub count: usize,
+}
+
+/// Write the compliance report or the audit log to `path` as CSV or PDF.
+/// Output is streamed to a temporary file and renamed into place.
+#[tauri::command]
+pub async fn ent_export_report(
+    state: State<'_, Arc<Mutex<EnterpriseState>>>,
+    report: ReportKind,
+    format: String,
+    path: String,
+    filter: Option<ReportFilter>,
+    organization: Option<String>,
+    workspace: Option<String>,
+) -> Result<ReportExportResult, String> {
+    let format = ReportFormat::parse(&format)?;
+    let header = report_header(report, filter, organization, workspace);
+    let data = {
+        let state = state.lock().await;
+        let resource = match report {
+            ReportKind::Compliance => "compliance",
+            ReportKind::AuditLogs => "audit_log",
+        };
+        state.service.require_session_permission(PermissionAction::Read, resource)?;
+        state.service.report_data(report, &header)
+    };
+
+    // Write without holding the enterprise state lock
+    let target = std::path::PathBuf::from(&path);
+    let count = tokio::task::spawn_blocking(move || {
+        let tmp = target.with_extension("partial");
+        let file = std::fs::File::create(&tmp)
+            .map_err(|e| format!("Failed to create {}: {}", tmp.display(), e))?;
+        data.write(format, &header, std::io::BufWriter::new(file))
+            .and_then(|count| std::fs::rename(&tmp, &target).map(|_| count))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&tmp);
+                format!("Failed to export report: {}", e)
+            })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    Ok(ReportExportResult { path, count })
+}
+
 #[tauri::command]
 pub async fn ent_request_data_export(
     state: State<'_, Arc<Mutex<EnterpriseState>>>,
@@ -214,6 +395,7 @@ pub async fn ent_delete_user_data(
     user_id: String,
 ) -> Result<(), String> {
     let mut state = state.lock().await;
+    state.service.require_session_permission(PermissionAction::Delete, "user_data")?;
     state.service.delete_user_data(&user_id)
 }
 
@@ -221,6 +403,25 @@ pub async fn ent_delete_user_data(
 // Helper Functions
 // ============================================
 
+fn report_header(
+    kind: ReportKind,
+    filter: Option<ReportFilter>,
+    organization: Option<String>,
+    workspace: Option<String>,
+) -> ReportHeader {
+    let title = match kind {
+        ReportKind::Compliance => "Compliance report",
+        ReportKind::AuditLogs => "Audit log report",
+    };
+    ReportHeader {
+        title: title.to_string(),
+        organization,
+        workspace,
+        generated_at: chrono::Utc::now().timestamp(),
+        filter: filter.unwrap_or_default(),
+    }
+}
+
 fn parse_sso_provider(s: &str) -> Result<SsoProvider, String> {
     match s.to_lowercase().as_str() {
         "saml" => Ok(SsoProvider::Saml),
@@ -1,7 +1,85 @@
-use anyhow::{Context, Result};
-use git2::{Repository, Signature, IndexAddOption, BranchType};
+use anyhow::{anyhow, bail, Context, Result};
+use git2::{Repository, Signature, IndexAddOption, BranchType, Delta, DiffOptions, DiffFindOptions, ErrorCode, StatusOptions, StashFlags};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::fmt;
 use std::path::Path;
 
+use crate::cli_service::{diff_line, DiffHunk, DiffLineType};
+
+/// How a file changed between two trees (or tree and working directory)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    TypeChange,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// Previous path, for renames
+    pub old_path: Option<String>,
+    pub change: FileChangeKind,
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatusEntry {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub change: FileChangeKind,
+}
+
+/// Why a push failed. Messages never contain the access token.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PushError {
+    /// No stored token for the remote's host, or the host rejected it;
+    /// the UI should ask for a token and retry
+    AuthRequired { host: String },
+    Failed { message: String },
+}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushError::AuthRequired { host } => write!(f, "Authentication required for {}", host),
+            PushError::Failed { message } => write!(f, "Push failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PushError {}
+
+/// Where a local branch stands relative to its remote-tracking branch
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Divergence {
+    UpToDate,
+    Ahead { commits: usize },
+    Behind { commits: usize },
+    Diverged { ahead: usize, behind: usize },
+    /// The remote has no branch of that name yet
+    NoUpstream,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoStatus {
+    /// `None` when HEAD is detached
+    pub branch: Option<String>,
+    /// False until the first commit
+    pub has_commits: bool,
+    pub staged: Vec<StatusEntry>,
+    pub unstaged: Vec<StatusEntry>,
+    pub untracked: Vec<String>,
+    pub conflicted: Vec<String>,
+}
+
 pub struct GitManager {
     repo_path: String,
 }
@@ -105,17 +183,191 @@ impl GitManager {
         Ok(commit_id.to_string())
     }
 
-    /// Push branch to remote
-    pub fn push_branch(&self, branch_name: &str, remote_name: &str) -> Result<()> {
+    /// Push branch to remote. HTTPS remotes authenticate with the token
+    /// `token_for_host` returns for the remote's host, falling back to the
+    /// user's configured git credential helper; SSH remotes use the agent.
+    /// The token is handed to libgit2 in memory, never written into a URL.
+    pub fn push_branch<F>(&self, branch_name: &str, remote_name: &str, token_for_host: F) -> Result<(), PushError>
+    where
+        F: Fn(&str) -> Result<Option<String>, String>,
+    {
+        let failed = |message: String| PushError::Failed { message };
+        let repo = self.open_repo().map_err(|e| failed(e.to_string()))?;
+        let mut remote = repo.find_remote(remote_name).map_err(|e| failed(e.message().to_string()))?;
+        let url = remote.pushurl().or(remote.url()).unwrap_or_default().to_string();
+        let host = remote_host(&url);
+
+        let token = match &host {
+            Some(host) => token_for_host(host).map_err(failed)?,
+            None => None,
+        };
+        let redact = |message: &str| redact_token(message, token.as_deref());
+
+        let config = repo.config().map_err(|e| failed(e.message().to_string()))?;
+        let attempts = Cell::new(0);
+        let rejected = RefCell::new(None);
+
+        let mut callbacks = git2::RemoteCallbacks::new();
+        callbacks.credentials(remote_credentials(&config, &token, &attempts));
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(status) = status {
+                *rejected.borrow_mut() = Some(format!("{} rejected: {}", refname, status));
+            }
+            Ok(())
+        });
+
+        let mut options = git2::PushOptions::new();
+        options.remote_callbacks(callbacks);
+
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch_name);
+        if let Err(e) = remote.push(&[&refspec], Some(&mut options)) {
+            let auth_failed = e.code() == ErrorCode::Auth || attempts.get() > 1;
+            return Err(match host {
+                Some(host) if auth_failed => PushError::AuthRequired { host },
+                _ => failed(redact(e.message())),
+            });
+        }
+        drop(options);
+
+        match rejected.into_inner() {
+            Some(message) => Err(failed(redact(&message))),
+            None => Ok(()),
+        }
+    }
+
+    /// Fetch every branch of `remote_name` into its remote-tracking refs,
+    /// authenticating the same way as `push_branch`
+    pub fn fetch<F>(&self, remote_name: &str, token_for_host: F) -> Result<()>
+    where
+        F: Fn(&str) -> Result<Option<String>, String>,
+    {
         let repo = self.open_repo()?;
-        let mut remote = repo.find_remote(remote_name)?;
-        
-        let refspec = format!("refs/heads/{}", branch_name);
-        remote.push(&[&refspec], None)?;
-        
+        let mut remote = repo.find_remote(remote_name)
+            .with_context(|| format!("Remote {} not found", remote_name))?;
+        let url = remote.url().unwrap_or_default().to_string();
+        let host = remote_host(&url);
+
+        let token = match &host {
+            Some(host) => token_for_host(host).map_err(|e| anyhow!(e))?,
+            None => None,
+        };
+
+        let config = repo.config()?;
+        let attempts = Cell::new(0);
+        let mut callbacks = git2::RemoteCallbacks::new();
+        callbacks.credentials(remote_credentials(&config, &token, &attempts));
+        let mut options = git2::FetchOptions::new();
+        options.remote_callbacks(callbacks);
+
+        let refspec = format!("+refs/heads/*:refs/remotes/{}/*", remote_name);
+        if let Err(e) = remote.fetch(&[&refspec], Some(&mut options), None) {
+            let auth_failed = e.code() == ErrorCode::Auth || attempts.get() > 1;
+            if let Some(host) = host.filter(|_| auth_failed) {
+                bail!("Authentication required for {}", host);
+            }
+            let message = match &token {
+                Some(token) => e.message().replace(token.as_str(), "***"),
+                None => e.message().to_string(),
+            };
+            bail!("Fetch failed: {}", message);
+        }
         Ok(())
     }
 
+    /// Compare `branch` with `remote_name/branch` as of the last fetch
+    pub fn divergence(&self, branch: &str, remote_name: &str) -> Result<Divergence> {
+        let repo = self.open_repo()?;
+        let local = repo.refname_to_id(&format!("refs/heads/{}", branch))
+            .with_context(|| format!("Branch {} not found", branch))?;
+        let upstream = match repo.refname_to_id(&format!("refs/remotes/{}/{}", remote_name, branch)) {
+            Ok(oid) => oid,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(Divergence::NoUpstream),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(match repo.graph_ahead_behind(local, upstream)? {
+            (0, 0) => Divergence::UpToDate,
+            (ahead, 0) => Divergence::Ahead { commits: ahead },
+            (0, behind) => Divergence::Behind { commits: behind },
+            (ahead, behind) => Divergence::Diverged { ahead, behind },
+        })
+    }
+
+    /// Move `branch` to `remote_name/branch` when that is a fast-forward. A
+    /// checked-out branch updates the working tree too, and is refused when
+    /// that would overwrite local changes.
+    pub fn fast_forward(&self, branch: &str, remote_name: &str) -> Result<()> {
+        let repo = self.open_repo()?;
+        let refname = format!("refs/heads/{}", branch);
+        let local = repo.refname_to_id(&refname)
+            .with_context(|| format!("Branch {} not found", branch))?;
+        let upstream = repo.refname_to_id(&format!("refs/remotes/{}/{}", remote_name, branch))
+            .with_context(|| format!("Remote branch {}/{} not found", remote_name, branch))?;
+
+        if local == upstream {
+            return Ok(());
+        }
+        if !repo.graph_descendant_of(upstream, local)? {
+            bail!("{} has diverged from {}/{}", branch, remote_name, branch);
+        }
+
+        if Self::head_branch(&repo)?.as_deref() == Some(branch) {
+            let target = repo.find_commit(upstream)?;
+            repo.checkout_tree(target.as_object(), Some(git2::build::CheckoutBuilder::new().safe()))
+                .context("Fast-forward would overwrite local changes")?;
+        }
+        repo.reference(&refname, upstream, true, &format!("fast-forward to {}/{}", remote_name, branch))?;
+        Ok(())
+    }
+
+    /// Contents of `path` in the commit `refname` points at; `None` when the
+    /// ref or the file doesn't exist
+    pub fn read_file_at(&self, refname: &str, path: &str) -> Result<Option<Vec<u8>>> {
+        let repo = self.open_repo()?;
+        let tree = match repo.find_reference(refname) {
+            Ok(reference) => reference.peel_to_tree()?,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let entry = match tree.get_path(Path::new(path)) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let blob = entry.to_object(&repo)?.peel_to_blob()?;
+        Ok(Some(blob.content().to_vec()))
+    }
+
+    /// Commit a tree holding only `file_name` to `refname`, leaving HEAD,
+    /// the index and the working tree alone. The parent is the commit
+    /// `parent_ref` points at, if that ref exists.
+    pub fn commit_file_to_ref(
+        &self,
+        refname: &str,
+        parent_ref: &str,
+        file_name: &str,
+        content: &[u8],
+        message: &str,
+    ) -> Result<String> {
+        let repo = self.open_repo()?;
+        let parent = match repo.find_reference(parent_ref) {
+            Ok(reference) => Some(reference.peel_to_commit()?),
+            Err(e) if e.code() == ErrorCode::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let blob = repo.blob(content)?;
+        let mut builder = repo.treebuilder(None)?;
+        builder.insert(file_name, blob, git2::FileMode::Blob.into())?;
+        let tree = repo.find_tree(builder.write()?)?;
+
+        let signature = Signature::now("SmartSpec Pro", "smartspec@local")?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let commit_id = repo.commit(None, &signature, &signature, message, &tree, &parents)?;
+        repo.reference(refname, commit_id, true, message)?;
+        Ok(commit_id.to_string())
+    }
+
     /// Check if repository has uncommitted changes
     pub fn has_changes(&self) -> Result<bool> {
         let repo = self.open_repo()?;
@@ -139,19 +391,575 @@ impl GitManager {
         Ok(branch_names)
     }
 
+    /// Diff HEAD against the index (`staged`) or the index against the
+    /// working directory. Without commits, staged files diff against empty.
+    pub fn diff(&self, staged: bool) -> Result<Vec<FileDiff>> {
+        let repo = self.open_repo()?;
+        let mut opts = DiffOptions::new();
+        opts.context_lines(crate::cli_service::DEFAULT_DIFF_CONTEXT as u32);
+
+        let mut diff = if staged {
+            let head_tree = Self::head_tree(&repo)?;
+            repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))?
+        } else {
+            repo.diff_index_to_workdir(None, Some(&mut opts))?
+        };
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+        let mut files = Vec::new();
+        for (index, delta) in diff.deltas().enumerate() {
+            let Some(change) = Self::change_kind(delta.status()) else {
+                continue;
+            };
+            let path = Self::delta_path(delta.new_file().path().or(delta.old_file().path()));
+            let old_path = (change == FileChangeKind::Renamed)
+                .then(|| Self::delta_path(delta.old_file().path()));
+
+            // libgit2 yields no patch for binary files
+            let patch = git2::Patch::from_diff(&diff, index)?;
+            let binary = patch.is_none() || delta.flags().contains(git2::DiffFlags::BINARY);
+            let mut hunks = Vec::new();
+
+            if let Some(patch) = patch.filter(|_| !binary) {
+                for hunk_index in 0..patch.num_hunks() {
+                    let (hunk, line_count) = patch.hunk(hunk_index)?;
+                    let mut lines = Vec::new();
+                    for line_index in 0..line_count {
+                        let line = patch.line_in_hunk(hunk_index, line_index)?;
+                        let kind = match line.origin() {
+                            ' ' => DiffLineType::Context,
+                            '+' => DiffLineType::Addition,
+                            '-' => DiffLineType::Deletion,
+                            // "\ No newline at end of file" markers; the
+                            // preceding line already lacks its newline
+                            _ => continue,
+                        };
+                        lines.push(diff_line(
+                            kind,
+                            &String::from_utf8_lossy(line.content()),
+                            line.old_lineno().map(|n| n as i32),
+                            line.new_lineno().map(|n| n as i32),
+                        ));
+                    }
+                    hunks.push(DiffHunk {
+                        old_start: hunk.old_start() as i32,
+                        old_lines: hunk.old_lines() as i32,
+                        new_start: hunk.new_start() as i32,
+                        new_lines: hunk.new_lines() as i32,
+                        lines,
+                    });
+                }
+            }
+
+            files.push(FileDiff { path, old_path, change, binary, hunks });
+        }
+
+        Ok(files)
+    }
+
+    /// Staged, unstaged, untracked and conflicted files, with renames
+    /// detected on both sides of the index
+    pub fn status(&self) -> Result<RepoStatus> {
+        let repo = self.open_repo()?;
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true)
+            .renames_index_to_workdir(true);
+
+        let mut status = RepoStatus {
+            branch: Self::head_branch(&repo)?,
+            has_commits: Self::head_tree(&repo)?.is_some(),
+            staged: Vec::new(),
+            unstaged: Vec::new(),
+            untracked: Vec::new(),
+            conflicted: Vec::new(),
+        };
+
+        for entry in repo.statuses(Some(&mut opts))?.iter() {
+            let flags = entry.status();
+            let path = entry.path().unwrap_or_default().to_string();
+
+            if flags.is_conflicted() {
+                status.conflicted.push(path);
+                continue;
+            }
+            if flags.is_wt_new() {
+                status.untracked.push(path.clone());
+            }
+
+            let staged = if flags.is_index_renamed() {
+                Some(FileChangeKind::Renamed)
+            } else if flags.is_index_new() {
+                Some(FileChangeKind::Added)
+            } else if flags.is_index_deleted() {
+                Some(FileChangeKind::Deleted)
+            } else if flags.is_index_typechange() {
+                Some(FileChangeKind::TypeChange)
+            } else if flags.is_index_modified() {
+                Some(FileChangeKind::Modified)
+            } else {
+                None
+            };
+            if let Some(change) = staged {
+                status.staged.push(Self::status_entry(entry.head_to_index(), change, &path));
+            }
+
+            let unstaged = if flags.is_wt_renamed() {
+                Some(FileChangeKind::Renamed)
+            } else if flags.is_wt_deleted() {
+                Some(FileChangeKind::Deleted)
+            } else if flags.is_wt_typechange() {
+                Some(FileChangeKind::TypeChange)
+            } else if flags.is_wt_modified() {
+                Some(FileChangeKind::Modified)
+            } else {
+                None
+            };
+            if let Some(change) = unstaged {
+                status.unstaged.push(Self::status_entry(entry.index_to_workdir(), change, &path));
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Stash tracked and untracked changes. Returns the stash commit id, or
+    /// `None` when there was nothing to stash.
+    pub fn stash_save(&self, message: Option<&str>) -> Result<Option<String>> {
+        let mut repo = self.open_repo()?;
+        let signature = repo.signature()
+            .or_else(|_| Signature::now("SmartSpec Pro", "smartspec@local"))?;
+        let message = match message {
+            Some(message) => message.to_string(),
+            None => format!("WIP on {}", Self::head_branch(&repo)?.unwrap_or_else(|| "HEAD".to_string())),
+        };
+
+        match repo.stash_save(&signature, &message, Some(StashFlags::INCLUDE_UNTRACKED)) {
+            Ok(oid) => Ok(Some(oid.to_string())),
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e).context("Failed to stash changes"),
+        }
+    }
+
+    /// Apply and drop a stash entry (the latest by default). On conflict the
+    /// entry is kept so nothing is lost.
+    pub fn stash_pop(&self, index: Option<usize>) -> Result<()> {
+        let mut repo = self.open_repo()?;
+        let index = index.unwrap_or(0);
+
+        match repo.stash_pop(index, None) {
+            Ok(()) => Ok(()),
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                Err(anyhow::anyhow!("No stash entry at index {}", index))
+            }
+            Err(e) if e.code() == ErrorCode::Conflict || e.code() == ErrorCode::MergeConflict => {
+                Err(anyhow::anyhow!("Stash {} conflicts with local changes; it was kept", index))
+            }
+            Err(e) => Err(e).context("Failed to pop stash"),
+        }
+    }
+
+    /// HEAD's tree, or `None` in a repository with no commits yet
+    fn head_tree(repo: &Repository) -> Result<Option<git2::Tree<'_>>> {
+        match repo.head() {
+            Ok(head) => Ok(Some(head.peel_to_tree()?)),
+            Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Branch HEAD points at, which exists even before the first commit
+    fn head_branch(repo: &Repository) -> Result<Option<String>> {
+        let head = repo.find_reference("HEAD")?;
+        Ok(head.symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(String::from))
+    }
+
+    fn change_kind(delta: Delta) -> Option<FileChangeKind> {
+        match delta {
+            Delta::Added | Delta::Untracked | Delta::Copied => Some(FileChangeKind::Added),
+            Delta::Modified => Some(FileChangeKind::Modified),
+            Delta::Deleted => Some(FileChangeKind::Deleted),
+            Delta::Renamed => Some(FileChangeKind::Renamed),
+            Delta::Typechange => Some(FileChangeKind::TypeChange),
+            _ => None,
+        }
+    }
+
+    fn delta_path(path: Option<&Path>) -> String {
+        path.map(|p| p.to_string_lossy().replace('\\', "/")).unwrap_or_default()
+    }
+
+    fn status_entry(delta: Option<git2::DiffDelta<'_>>, change: FileChangeKind, path: &str) -> StatusEntry {
+        let (new_path, old_path) = match &delta {
+            Some(delta) => (
+                delta.new_file().path().map(|p| Self::delta_path(Some(p))),
+                delta.old_file().path().map(|p| Self::delta_path(Some(p))),
+            ),
+            None => (None, None),
+        };
+        StatusEntry {
+            path: new_path.unwrap_or_else(|| path.to_string()),
+            old_path: old_path.filter(|_| change == FileChangeKind::Renamed),
+            change,
+        }
+    }
+
+    /// SHA of the commit HEAD points at, or `None` before the first commit
+    pub fn head_commit(&self) -> Result<Option<String>> {
+        let repo = self.open_repo()?;
+        let head = match repo.head() {
+            Ok(head) => head,
+            Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let commit = head.peel_to_commit()?;
+        Ok(Some(commit.id().to_string()))
+    }
+
     /// Check if repository exists at path
     pub fn repo_exists(&self) -> bool {
         Repository::open(&self.repo_path).is_ok()
     }
 }
 
+/// Credentials callback for remote operations. HTTPS remotes use `token`,
+/// falling back to the git credential helper; SSH remotes use the agent.
+/// Plain HTTP remotes get no credentials at all. `attempts` counts calls so
+/// a rejected credential isn't retried forever.
+fn remote_credentials<'a>(
+    config: &'a git2::Config,
+    token: &'a Option<String>,
+    attempts: &'a Cell<u32>,
+) -> impl FnMut(&str, Option<&str>, git2::CredentialType) -> std::result::Result<git2::Cred, git2::Error> + 'a {
+    move |url, username, allowed| {
+        // libgit2 retries the callback after a rejected credential
+        attempts.set(attempts.get() + 1);
+        if attempts.get() > 1 {
+            return Err(git2::Error::from_str("credentials rejected"));
+        }
+        if url.to_lowercase().starts_with("http://") {
+            return Err(git2::Error::from_str("refusing to send credentials over plain HTTP"));
+        }
+        if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            match token {
+                Some(token) => git2::Cred::userpass_plaintext("x-access-token", token),
+                None => git2::Cred::credential_helper(config, url, username),
+            }
+        } else if allowed.contains(git2::CredentialType::SSH_KEY) {
+            git2::Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else {
+            git2::Cred::default()
+        }
+    }
+}
+
+/// Host (with port, if any) of an HTTPS remote URL; `None` for SSH and
+/// local remotes, which don't use tokens, and for plain HTTP, which must
+/// never carry one
+fn remote_host(url: &str) -> Option<String> {
+    let scheme_end = url.find("://")?;
+    if !url[..scheme_end].eq_ignore_ascii_case("https") {
+        return None;
+    }
+    let authority = url[scheme_end + 3..].split('/').next()?;
+    let host = authority.rsplit('@').next()?;
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+/// `message` with every occurrence of `token` masked
+fn redact_token(message: &str, token: Option<&str>) -> String {
+    match token {
+        Some(token) if !token.is_empty() => message.replace(token, "***"),
+        _ => message.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_git_manager_creation() {
         let manager = GitManager::new("/tmp/test-repo".to_string());
         assert_eq!(manager.repo_path, "/tmp/test-repo");
     }
+
+    #[test]
+    fn test_remote_host() {
+        assert_eq!(remote_host("https://github.com/org/repo.git").as_deref(), Some("github.com"));
+        assert_eq!(remote_host("https://user@Git.Example.com:8443/r").as_deref(), Some("git.example.com:8443"));
+        assert_eq!(remote_host("HTTPS://github.com/org/repo.git").as_deref(), Some("github.com"));
+        assert_eq!(remote_host("git@github.com:org/repo.git"), None);
+        assert_eq!(remote_host("/srv/repos/app.git"), None);
+        assert_eq!(remote_host("http://github.com/org/repo.git"), None);
+        assert_eq!(remote_host("HTTP://github.com/org/repo.git"), None);
+    }
+
+    #[test]
+    fn test_redact_token() {
+        let token = "ghp_SuperSecretToken123";
+        let message = format!("failed to push to https://x-access-token:{}@github.com/org/repo: {}", token, token);
+        let redacted = redact_token(&message, Some(token));
+        assert!(!redacted.contains(token));
+        assert_eq!(redacted.matches("***").count(), 2);
+        assert_eq!(redact_token("no secret here", Some("")), "no secret here");
+    }
+
+    /// Serve 401s on a local port, recording each request, until dropped
+    struct UnauthorizedServer {
+        port: u16,
+        requests: Arc<Mutex<Vec<u8>>>,
+        stop: Arc<AtomicBool>,
+        thread: Option<std::thread::JoinHandle<()>>,
+    }
+
+    impl UnauthorizedServer {
+        fn start() -> Self {
+            use std::io::{Read, Write};
+
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.set_nonblocking(true).unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let stop = Arc::new(AtomicBool::new(false));
+            let thread = {
+                let (requests, stop) = (requests.clone(), stop.clone());
+                std::thread::spawn(move || {
+                    while !stop.load(Ordering::SeqCst) {
+                        let Ok((mut stream, _)) = listener.accept() else {
+                            std::thread::sleep(std::time::Duration::from_millis(10));
+                            continue;
+                        };
+                        stream.set_nonblocking(false).unwrap();
+                        stream.set_read_timeout(Some(std::time::Duration::from_secs(2))).unwrap();
+                        let mut request = Vec::new();
+                        let mut buf = [0u8; 4096];
+                        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                            match stream.read(&mut buf) {
+                                Ok(0) | Err(_) => break,
+                                Ok(n) => request.extend_from_slice(&buf[..n]),
+                            }
+                        }
+                        requests.lock().unwrap().extend_from_slice(&request);
+                        let _ = stream.write_all(
+                            b"HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Basic realm=\"git\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        );
+                    }
+                })
+            };
+            Self { port, requests, stop, thread: Some(thread) }
+        }
+
+        fn requests(&self) -> String {
+            String::from_utf8_lossy(&self.requests.lock().unwrap()).to_string()
+        }
+    }
+
+    impl Drop for UnauthorizedServer {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::SeqCst);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    #[test]
+    fn test_plain_http_remotes_never_receive_credentials() {
+        use base64::Engine;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+        stage(&repo, &["a.txt"]);
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[]).unwrap();
+
+        let server = UnauthorizedServer::start();
+        repo.remote("origin", &format!("http://127.0.0.1:{}/org/repo.git", server.port)).unwrap();
+
+        let manager = GitManager::new(dir.path().to_string_lossy().to_string());
+        let branch = manager.get_current_branch().unwrap();
+        let token = "ghp_SuperSecretToken123";
+        let asked = Cell::new(false);
+        let err = manager.push_branch(&branch, "origin", |_| {
+            asked.set(true);
+            Ok(Some(token.to_string()))
+        }).unwrap_err();
+        let fetch_err = manager.fetch("origin", |_| {
+            asked.set(true);
+            Ok(Some(token.to_string()))
+        }).unwrap_err();
+
+        assert!(!asked.get(), "a token was looked up for a plain HTTP remote");
+        let requests = server.requests();
+        assert!(requests.contains("/org/repo.git"), "the remote was never contacted: {:?}", requests);
+        assert!(!requests.contains("Authorization"));
+        let basic = base64::engine::general_purpose::STANDARD.encode(format!("x-access-token:{}", token));
+        for output in [requests, err.to_string(), format!("{:?}", err), serde_json::to_string(&err).unwrap(), format!("{:#}", fetch_err)] {
+            assert!(!output.contains(token) && !output.contains(&basic), "{}", output);
+        }
+    }
+
+    #[test]
+    fn test_push_errors_never_contain_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+        stage(&repo, &["a.txt"]);
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[]).unwrap();
+        // Nothing listens on the discard port, so the push fails fast
+        repo.remote("origin", "https://127.0.0.1:9/org/repo.git").unwrap();
+
+        let manager = GitManager::new(dir.path().to_string_lossy().to_string());
+        let branch = manager.get_current_branch().unwrap();
+        let token = "ghp_SuperSecretToken123";
+
+        let hosts = RefCell::new(Vec::new());
+        let err = manager.push_branch(&branch, "origin", |host| {
+            hosts.borrow_mut().push(host.to_string());
+            Ok(Some(token.to_string()))
+        }).unwrap_err();
+        assert_eq!(hosts.into_inner(), vec!["127.0.0.1:9".to_string()]);
+        assert!(!err.to_string().contains(token));
+        assert!(!format!("{:?}", err).contains(token));
+        assert!(!serde_json::to_string(&err).unwrap().contains(token));
+
+        let err = manager.push_branch(&branch, "missing", |_| Ok(Some(token.to_string()))).unwrap_err();
+        assert!(matches!(err, PushError::Failed { .. }));
+    }
+
+    fn stage(repo: &Repository, paths: &[&str]) {
+        let mut index = repo.index().unwrap();
+        for path in paths {
+            if repo.workdir().unwrap().join(path).exists() {
+                index.add_path(Path::new(path)).unwrap();
+            } else {
+                index.remove_path(Path::new(path)).unwrap();
+            }
+        }
+        index.write().unwrap();
+    }
+
+    #[test]
+    fn test_status_and_diff_before_and_after_first_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let manager = GitManager::new(dir.path().to_string_lossy().to_string());
+
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "scratch").unwrap();
+        stage(&repo, &["a.txt"]);
+
+        let status = manager.status().unwrap();
+        assert!(!status.has_commits);
+        assert_eq!(manager.head_commit().unwrap(), None);
+        assert!(status.branch.is_some());
+        assert_eq!(status.staged, vec![StatusEntry {
+            path: "a.txt".to_string(),
+            old_path: None,
+            change: FileChangeKind::Added,
+        }]);
+        assert_eq!(status.untracked, vec!["notes.txt".to_string()]);
+
+        let staged = manager.diff(true).unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].hunks[0].new_lines, 3);
+        assert_eq!(staged[0].hunks[0].lines[0].content, "one");
+
+        // `commit` expects a parent, so create the root commit directly
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        let commit_id = repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[]).unwrap();
+        assert_eq!(manager.head_commit().unwrap(), Some(commit_id.to_string()));
+
+        // Staged rename plus an unstaged edit to the renamed file
+        std::fs::rename(dir.path().join("a.txt"), dir.path().join("b.txt")).unwrap();
+        stage(&repo, &["a.txt", "b.txt"]);
+        std::fs::write(dir.path().join("b.txt"), "one\ntwo\nthree\nfour").unwrap();
+
+        let status = manager.status().unwrap();
+        assert!(status.has_commits);
+        assert_eq!(status.staged, vec![StatusEntry {
+            path: "b.txt".to_string(),
+            old_path: Some("a.txt".to_string()),
+            change: FileChangeKind::Renamed,
+        }]);
+        assert_eq!(status.unstaged[0].change, FileChangeKind::Modified);
+
+        let unstaged = manager.diff(false).unwrap();
+        let added = unstaged[0].hunks[0].lines.last().unwrap();
+        assert_eq!(added.content, "four");
+        assert!(added.no_newline);
+    }
+
+    fn commit_file(repo: &Repository, name: &str, content: &str) {
+        std::fs::write(repo.workdir().unwrap().join(name), content).unwrap();
+        stage(repo, &[name]);
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, name, &tree, &parents).unwrap();
+    }
+
+    #[test]
+    fn test_divergence_and_fast_forward_against_remote() {
+        let no_token = |_: &str| Ok(None);
+        let remote_dir = tempfile::tempdir().unwrap();
+        Repository::init_bare(remote_dir.path()).unwrap();
+        let remote_url = remote_dir.path().to_string_lossy().to_string();
+
+        let a_dir = tempfile::tempdir().unwrap();
+        let a_repo = Repository::init(a_dir.path()).unwrap();
+        commit_file(&a_repo, "a.txt", "a");
+        a_repo.remote("origin", &remote_url).unwrap();
+        let a = GitManager::new(a_dir.path().to_string_lossy().to_string());
+        let branch = a.get_current_branch().unwrap();
+        assert_eq!(a.divergence(&branch, "origin").unwrap(), Divergence::NoUpstream);
+        a.push_branch(&branch, "origin", no_token).unwrap();
+
+        let b_dir = tempfile::tempdir().unwrap();
+        let b_repo = Repository::clone(&remote_url, b_dir.path()).unwrap();
+        let b = GitManager::new(b_dir.path().to_string_lossy().to_string());
+
+        // Behind only: fast-forward updates the checked-out tree
+        commit_file(&a_repo, "b.txt", "b");
+        a.fetch("origin", no_token).unwrap();
+        assert_eq!(a.divergence(&branch, "origin").unwrap(), Divergence::Ahead { commits: 1 });
+        a.push_branch(&branch, "origin", no_token).unwrap();
+        b.fetch("origin", no_token).unwrap();
+        assert_eq!(b.divergence(&branch, "origin").unwrap(), Divergence::Behind { commits: 1 });
+        b.fast_forward(&branch, "origin").unwrap();
+        assert_eq!(b.divergence(&branch, "origin").unwrap(), Divergence::UpToDate);
+        assert!(b_dir.path().join("b.txt").exists());
+        assert!(b.status().unwrap().staged.is_empty());
+
+        // Both sides commit: diverged, and fast-forward is refused
+        commit_file(&a_repo, "c.txt", "from a");
+        a.push_branch(&branch, "origin", no_token).unwrap();
+        commit_file(&b_repo, "d.txt", "from b");
+        b.fetch("origin", no_token).unwrap();
+        assert_eq!(b.divergence(&branch, "origin").unwrap(), Divergence::Diverged { ahead: 1, behind: 1 });
+        assert!(b.fast_forward(&branch, "origin").is_err());
+        assert!(!b_dir.path().join("c.txt").exists());
+
+        // Files committed straight to a ref travel without touching HEAD
+        let head = b.head_commit().unwrap();
+        b.commit_file_to_ref("refs/heads/data", "refs/remotes/origin/data", "records.json", b"[1]", "data").unwrap();
+        assert_eq!(b.head_commit().unwrap(), head);
+        b.push_branch("data", "origin", no_token).unwrap();
+        a.fetch("origin", no_token).unwrap();
+        assert_eq!(a.read_file_at("refs/remotes/origin/data", "records.json").unwrap(), Some(b"[1]".to_vec()));
+        assert_eq!(a.read_file_at("refs/remotes/origin/data", "missing.json").unwrap(), None);
+        assert_eq!(a.read_file_at("refs/remotes/origin/none", "records.json").unwrap(), None);
+    }
 }
@@ -0,0 +1,273 @@
+// System Health Check - Aggregated subsystem diagnostics
+//
+// Provides:
+// - Concurrent probes for Docker, git, the Python bridge, the database and LLM providers
+// - Per-probe timeouts so one hung dependency cannot stall the report
+// - A single serializable report users can paste into bug reports
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::docker_manager::DockerManager;
+use crate::llm_service::{LlmError, LlmService};
+use crate::python_bridge::PythonBridge;
+
+/// Upper bound for any single probe
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Probes that succeed but take longer than this are reported as degraded
+pub const SLOW_PROBE_THRESHOLD: Duration = Duration::from_secs(2);
+
+// ============================================
+// Types
+// ============================================
+
+/// Ordered from healthiest to worst so the overall status is the maximum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    pub latency_ms: u64,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub components: Vec<ComponentHealth>,
+    pub app_version: String,
+    pub os: String,
+    pub checked_at: String,
+}
+
+/// What a successful probe found
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeOutcome {
+    Ok(Option<String>),
+    Degraded(String),
+}
+
+// ============================================
+// Probe Runner
+// ============================================
+
+/// Run one probe under `timeout`. Errors and timeouts mark the component down.
+pub async fn probe<F>(name: &str, timeout: Duration, check: F) -> ComponentHealth
+where
+    F: Future<Output = Result<ProbeOutcome, String>>,
+{
+    let start = Instant::now();
+    let result = tokio::time::timeout(timeout, check).await;
+    let elapsed = start.elapsed();
+
+    let (status, detail) = match result {
+        Ok(Ok(ProbeOutcome::Ok(_))) if elapsed > SLOW_PROBE_THRESHOLD => (
+            HealthStatus::Degraded,
+            Some(format!("Slow response ({} ms)", elapsed.as_millis())),
+        ),
+        Ok(Ok(ProbeOutcome::Ok(detail))) => (HealthStatus::Ok, detail),
+        Ok(Ok(ProbeOutcome::Degraded(reason))) => (HealthStatus::Degraded, Some(reason)),
+        Ok(Err(e)) => (HealthStatus::Down, Some(e)),
+        Err(_) => (
+            HealthStatus::Down,
+            Some(format!("Timed out after {} s", timeout.as_secs())),
+        ),
+    };
+
+    ComponentHealth {
+        name: name.to_string(),
+        status,
+        latency_ms: elapsed.as_millis() as u64,
+        detail,
+    }
+}
+
+/// Worst status across all components
+pub fn overall_status(components: &[ComponentHealth]) -> HealthStatus {
+    components.iter()
+        .map(|c| c.status)
+        .max()
+        .unwrap_or(HealthStatus::Ok)
+}
+
+// ============================================
+// Subsystem Probes
+// ============================================
+
+async fn check_docker() -> Result<ProbeOutcome, String> {
+    let info = tokio::task::spawn_blocking(DockerManager::check_docker)
+        .await
+        .map_err(|e| e.to_string())??;
+
+    match (info.available, info.error) {
+        (false, error) => Err(error.unwrap_or_else(|| "Docker not available".to_string())),
+        (true, Some(error)) => Ok(ProbeOutcome::Degraded(error)),
+        (true, None) => Ok(ProbeOutcome::Ok(Some(format!("Docker {}", info.version)))),
+    }
+}
+
+async fn check_git() -> Result<ProbeOutcome, String> {
+    let output = tokio::process::Command::new("git")
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|e| format!("git not found: {}", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(ProbeOutcome::Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string())))
+}
+
+async fn check_python_bridge(bridge: &PythonBridge) -> Result<ProbeOutcome, String> {
+    let workflows = bridge.list_workflows().await.map_err(|e| e.to_string())?;
+    Ok(ProbeOutcome::Ok(Some(format!("{} workflows available", workflows.len()))))
+}
+
+async fn check_database(db: Arc<Database>) -> Result<ProbeOutcome, String> {
+    let healthy = tokio::task::spawn_blocking(move || db.health_check())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    if healthy {
+        Ok(ProbeOutcome::Ok(None))
+    } else {
+        Err("Unexpected result from database probe".to_string())
+    }
+}
+
+/// Probe every enabled provider concurrently. A missing key or rate limit
+/// means the provider is usable once fixed, so it counts as degraded.
+async fn check_llm_providers(llm_service: Option<Arc<LlmService>>) -> Vec<ComponentHealth> {
+    let llm_service = match llm_service {
+        Some(service) => service,
+        None => {
+            return vec![ComponentHealth {
+                name: "llm".to_string(),
+                status: HealthStatus::Degraded,
+                latency_ms: 0,
+                detail: Some("LLM service not initialized".to_string()),
+            }];
+        }
+    };
+
+    let providers = llm_service.enabled_providers().await;
+    if providers.is_empty() {
+        return vec![ComponentHealth {
+            name: "llm".to_string(),
+            status: HealthStatus::Degraded,
+            latency_ms: 0,
+            detail: Some("No LLM provider enabled".to_string()),
+        }];
+    }
+
+    let probes = providers.iter().map(|provider| {
+        let name = format!("llm:{}", provider.provider.as_str());
+        let llm_service = Arc::clone(&llm_service);
+        async move {
+            probe(&name, PROBE_TIMEOUT, async {
+                match llm_service.probe_provider(provider).await {
+                    Ok(()) => Ok(ProbeOutcome::Ok(None)),
+                    Err(e) => match LlmError::classify(&e) {
+                        Some(err @ (LlmError::MissingApiKey(_) | LlmError::RateLimited(_))) => {
+                            Ok(ProbeOutcome::Degraded(err.to_string()))
+                        }
+                        _ => Err(e.to_string()),
+                    },
+                }
+            }).await
+        }
+    });
+
+    futures::future::join_all(probes).await
+}
+
+// ============================================
+// Report
+// ============================================
+
+/// Probe all subsystems concurrently and collect the results
+pub async fn run_health_check(
+    bridge: &PythonBridge,
+    db: Arc<Database>,
+    llm_service: Option<Arc<LlmService>>,
+) -> HealthReport {
+    let (docker, git, python, database, llm) = tokio::join!(
+        probe("docker", PROBE_TIMEOUT, check_docker()),
+        probe("git", PROBE_TIMEOUT, check_git()),
+        probe("python_bridge", PROBE_TIMEOUT, check_python_bridge(bridge)),
+        probe("database", PROBE_TIMEOUT, check_database(db)),
+        check_llm_providers(llm_service),
+    );
+
+    let mut components = vec![docker, git, python, database];
+    components.extend(llm);
+
+    HealthReport {
+        status: overall_status(&components),
+        components,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_maps_outcomes_to_status() {
+        let ok = probe("a", PROBE_TIMEOUT, async { Ok(ProbeOutcome::Ok(Some("v1".to_string()))) }).await;
+        assert_eq!(ok.status, HealthStatus::Ok);
+        assert_eq!(ok.detail.as_deref(), Some("v1"));
+
+        let degraded = probe("b", PROBE_TIMEOUT, async { Ok(ProbeOutcome::Degraded("no key".to_string())) }).await;
+        assert_eq!(degraded.status, HealthStatus::Degraded);
+
+        let down = probe("c", PROBE_TIMEOUT, async { Err("refused".to_string()) }).await;
+        assert_eq!(down.status, HealthStatus::Down);
+        assert_eq!(down.detail.as_deref(), Some("refused"));
+
+        assert_eq!(overall_status(&[ok.clone(), degraded.clone()]), HealthStatus::Degraded);
+        assert_eq!(overall_status(&[ok, degraded, down]), HealthStatus::Down);
+        assert_eq!(overall_status(&[]), HealthStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn test_probe_timeout_marks_down() {
+        let hung = probe("hung", Duration::from_millis(20), async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(ProbeOutcome::Ok(None))
+        }).await;
+
+        assert_eq!(hung.status, HealthStatus::Down);
+        assert!(hung.latency_ms < 60_000);
+        assert!(hung.detail.unwrap().starts_with("Timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_probes_run_concurrently() {
+        let slow = || probe("slow", PROBE_TIMEOUT, async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            Ok(ProbeOutcome::Ok(None))
+        });
+
+        let start = Instant::now();
+        let (a, b, c) = tokio::join!(slow(), slow(), slow());
+        assert!(start.elapsed() < Duration::from_millis(900));
+        assert!([a, b, c].iter().all(|c| c.status == HealthStatus::Ok));
+    }
+}
@@ -6,6 +6,7 @@
 // - Path validation (prevent traversal)
 // - Command argument validation
 // - Container/branch name validation
+// - Workspace/project name validation
 // - General input sanitization
 
 use regex::Regex;
@@ -51,6 +52,18 @@ static STRICT_FILENAME_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^[a-zA-Z0-9][a-zA-Z0-9._-]*$").unwrap()
 });
 
+// Names that become directories: same rule the template engine uses for project names
+static RESOURCE_NAME_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[a-z][a-z0-9-]*$").unwrap()
+});
+
+// Windows device names, which cannot be used as directory names there
+const RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul",
+    "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+    "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
 // ============================================
 // Path Validation
 // ============================================
@@ -176,6 +189,41 @@ pub fn validate_filename_with_options(filename: &str, strict: bool) -> Result<()
     Ok(())
 }
 
+// ============================================
+// Name Validation
+// ============================================
+
+/// Validate a user-supplied name that is used as a directory name
+/// (workspaces, projects). Must be lowercase, start with a letter, and
+/// contain only letters, digits and hyphens, so it can never carry a path
+/// separator or traversal sequence.
+pub fn validate_resource_name(name: &str, field_name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err(format!("{} cannot be empty", field_name));
+    }
+    
+    if name.len() > 64 {
+        return Err(format!("{} too long (max 64 characters)", field_name));
+    }
+    
+    if name.contains("..") || name.contains('/') || name.contains('\\') || name.contains('\0') {
+        return Err(format!("{} cannot contain path separators or traversal sequences", field_name));
+    }
+    
+    if !RESOURCE_NAME_REGEX.is_match(name) {
+        return Err(format!(
+            "Invalid {} '{}': must be lowercase, start with a letter, and contain only letters, numbers, and hyphens",
+            field_name.to_lowercase(), name
+        ));
+    }
+    
+    if RESERVED_NAMES.contains(&name) {
+        return Err(format!("{} '{}' is reserved", field_name, name));
+    }
+    
+    Ok(())
+}
+
 // ============================================
 // Docker Validation
 // ============================================
@@ -487,6 +535,39 @@ mod tests {
         assert!(validate_filename("file\\path").is_err());
     }
     
+    #[test]
+    fn test_validate_resource_name() {
+        assert!(validate_resource_name("my-app", "Workspace name").is_ok());
+        assert!(validate_resource_name("app2", "Workspace name").is_ok());
+        
+        assert!(validate_resource_name("", "Workspace name").is_err());
+        assert!(validate_resource_name(&"a".repeat(65), "Workspace name").is_err());
+        assert!(validate_resource_name("MyApp", "Workspace name").is_err());
+        assert!(validate_resource_name("2app", "Workspace name").is_err());
+        assert!(validate_resource_name("con", "Workspace name").is_err());
+        assert!(validate_resource_name("lpt1", "Project name").is_err());
+    }
+    
+    #[test]
+    fn test_validate_resource_name_rejects_traversal_and_injection() {
+        for name in [
+            "../evil", "..", "a/../../etc", "evil/..", "a/b", "a\\b", "..\\evil",
+            "/etc/passwd", "evil\0", "app;rm -rf ~", "$(whoami)", "`id`", "app name", "app\n",
+        ] {
+            assert!(validate_resource_name(name, "Workspace name").is_err(), "accepted {:?}", name);
+        }
+        
+        let err = validate_resource_name("../evil", "Workspace name").unwrap_err();
+        assert!(err.contains("traversal"));
+    }
+    
+    #[test]
+    fn test_validate_branch_name_rejects_injection() {
+        for name in ["main;rm -rf /", "$(whoami)", "a b", "feature/../../x", "-delete", "x\n"] {
+            assert!(validate_branch_name(name).is_err(), "accepted {:?}", name);
+        }
+    }
+    
     #[test]
     fn test_validate_file_extension() {
         let allowed = &["txt", "md", "json"];
@@ -501,7 +582,7 @@ mod tests {
 }
 
 #[cfg(test)]
-mod tests {
+mod path_tests {
     use super::*;
     use std::fs;
     use tempfile::tempdir;
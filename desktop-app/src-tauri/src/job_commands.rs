@@ -6,14 +6,21 @@
 // - Branch operations
 // - Statistics
 
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 use crate::job_manager::{
     JobManager, Job, Task, Branch, JobStats, JobStatus, TaskStatus,
     CreateJobRequest, UpdateJobRequest, CreateTaskRequest,
+    JobScheduler, SchedulerConfig, SchedulerListener, TaskRunner,
 };
+use crate::python_bridge::{OutputMessage, WorkflowArgs, WorkflowStatus};
+use crate::workspace_manager::MergeOutcome;
+
+/// How often a running task's workflow is checked on
+const TASK_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 // ============================================
 // State Types
@@ -21,12 +28,111 @@ use crate::job_manager::{
 
 pub struct JobState {
     pub manager: Arc<JobManager>,
+    pub scheduler: Option<Arc<JobScheduler>>,
 }
 
 impl JobState {
     pub fn new() -> Self {
         Self {
             manager: Arc::new(JobManager::new()),
+            scheduler: None,
+        }
+    }
+
+    /// Start running queued jobs in the background with `runner`,
+    /// reporting progress as `job:progress` events
+    pub fn start_scheduler(&mut self, app: AppHandle, runner: Arc<dyn TaskRunner>, config: SchedulerConfig) {
+        let listener: SchedulerListener = Arc::new(move |event| {
+            let _ = app.emit("job:progress", event);
+        });
+        let scheduler = Arc::new(
+            JobScheduler::new(self.manager.clone(), runner, config).with_listener(listener),
+        );
+        tauri::async_runtime::spawn(scheduler.clone().run());
+        self.scheduler = Some(scheduler);
+    }
+
+    /// Run jobs in the workspace at `path`, resuming the jobs saved there.
+/// Returns how many saved jobs were loaded.
+#[tauri::command]
+pub async fn job_set_workspace(
+    state: State<'_, Arc<Mutex<JobState>>>,
+    path: String,
+) -> Result<usize, String> {
+    let state = state.lock().await;
+    state.manager.set_workspace(&path).await
+}
+
+/// Stop the scheduler, letting running tasks finish, and save job state
+    pub async fn shutdown(&mut self) -> Result<(), String> {
+        let state_file = self.manager.state_file().await;
+        match self.scheduler.take() {
+            Some(scheduler) => scheduler.shutdown(state_file.as_deref()).await,
+            None => match state_file {
+                Some(path) => self.manager.save_state(&path).await,
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// Runs each scheduled task as a Python workflow in the job manager's
+/// workspace. The job's `workflow`, `spec_id`, `category`, `mode` and
+/// `platform` metadata choose what runs; the workflow defaults to the task
+/// type.
+pub struct WorkflowTaskRunner {
+    app: AppHandle,
+    manager: Arc<JobManager>,
+}
+
+impl WorkflowTaskRunner {
+    pub fn new(app: AppHandle, manager: Arc<JobManager>) -> Self {
+        Self { app, manager }
+    }
+}
+
+#[async_trait::async_trait]
+impl TaskRunner for WorkflowTaskRunner {
+    async fn run_task(&self, job: &Job, task: &Task) -> Result<(), String> {
+        let meta = |key: &str, default: &str| {
+            job.metadata.get(key).cloned().unwrap_or_else(|| default.to_string())
+        };
+        let workflow_id = format!("job-{}-{}", job.id, task.id);
+        let workflow_name = meta("workflow", &format!("{:?}", task.task_type).to_lowercase());
+        let args = WorkflowArgs {
+            spec_id: meta("spec_id", &job.id),
+            category: meta("category", "core"),
+            mode: meta("mode", "auto"),
+            platform: meta("platform", "kilo"),
+            workspace_path: self.manager.workspace_path().await,
+        };
+
+        let state = self.app.state::<crate::AppState>();
+        state.python_bridge.lock().await
+            .spawn_workflow(workflow_id.clone(), workflow_name, args)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // Nobody else reads this workflow's output, so drain it here or the
+        // bridge would block once the channel fills
+        let mut last_error = None;
+        loop {
+            tokio::time::sleep(TASK_POLL_INTERVAL).await;
+            let bridge = state.python_bridge.lock().await;
+            while let Ok(Some(output)) = bridge.get_output(&workflow_id) {
+                match output.message {
+                    OutputMessage::Error { message, .. } => last_error = Some(message),
+                    OutputMessage::Failed { error, .. } => last_error = Some(error),
+                    _ => {}
+                }
+            }
+            match bridge.get_status(&workflow_id).map_err(|e| e.to_string())? {
+                WorkflowStatus::Running => continue,
+                WorkflowStatus::Completed => return Ok(()),
+                status => {
+                    return Err(last_error.unwrap_or_else(|| format!("Workflow ended as {:?}", status)));
+                }
+            }
         }
     }
 }
@@ -105,6 +211,15 @@ pub async fn job_pause(
     state.manager.pause_job(&job_id).await
 }
 
+#[tauri::command]
+pub async fn job_resume(
+    state: State<'_, Arc<Mutex<JobState>>>,
+    job_id: String,
+) -> Result<Job, String> {
+    let state = state.lock().await;
+    state.manager.resume_job(&job_id).await
+}
+
 #[tauri::command]
 pub async fn job_complete(
     state: State<'_, Arc<Mutex<JobState>>>,
@@ -123,6 +238,15 @@ pub async fn job_cancel(
     state.manager.cancel_job(&job_id).await
 }
 
+/// Stop the scheduler, letting running tasks finish, and save job state
+/// to the workspace
+#[tauri::command]
+pub async fn job_scheduler_shutdown(
+    state: State<'_, Arc<Mutex<JobState>>>,
+) -> Result<(), String> {
+    state.lock().await.shutdown().await
+}
+
 // ============================================
 // Task Commands
 // ============================================
@@ -158,6 +282,28 @@ pub async fn task_reorder(
     state.manager.reorder_tasks(&job_id, task_ids).await
 }
 
+#[tauri::command]
+pub async fn task_add_dependency(
+    state: State<'_, Arc<Mutex<JobState>>>,
+    job_id: String,
+    task_id: String,
+    depends_on: String,
+) -> Result<Task, String> {
+    let state = state.lock().await;
+    state.manager.add_task_dependency(&job_id, &task_id, &depends_on).await
+}
+
+#[tauri::command]
+pub async fn task_remove_dependency(
+    state: State<'_, Arc<Mutex<JobState>>>,
+    job_id: String,
+    task_id: String,
+    depends_on: String,
+) -> Result<Task, String> {
+    let state = state.lock().await;
+    state.manager.remove_task_dependency(&job_id, &task_id, &depends_on).await
+}
+
 #[tauri::command]
 pub async fn task_delete(
     state: State<'_, Arc<Mutex<JobState>>>,
@@ -204,11 +350,27 @@ pub async fn branch_merge(
     state: State<'_, Arc<Mutex<JobState>>>,
     source: String,
     target: String,
-) -> Result<(), String> {
+) -> Result<MergeOutcome, String> {
     let state = state.lock().await;
     state.manager.merge_branch(&source, &target).await
 }
 
+#[tauri::command]
+pub async fn branch_merge_abort(
+    state: State<'_, Arc<Mutex<JobState>>>,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    state.manager.abort_merge().await
+}
+
+#[tauri::command]
+pub async fn branch_merge_continue(
+    state: State<'_, Arc<Mutex<JobState>>>,
+) -> Result<MergeOutcome, String> {
+    let state = state.lock().await;
+    state.manager.continue_merge().await
+}
+
 #[tauri::command]
 pub async fn branch_delete(
     state: State<'_, Arc<Mutex<JobState>>>,
@@ -5,13 +5,20 @@
 // - Task breakdown and tracking
 // - Git branch integration
 // - Progress monitoring
+// - Background scheduling of queued jobs
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinHandle;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::workspace_manager::{self, MergeOutcome};
+
 // ============================================
 // Types
 // ============================================
@@ -48,6 +55,7 @@ pub enum JobStatus {
     Review,
     Completed,
     Cancelled,
+    Failed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -74,6 +82,8 @@ pub struct Task {
     pub line_end: Option<i32>,
     pub dependencies: Vec<String>,
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
 }
 
@@ -185,8 +195,15 @@ impl JobManager {
         }
     }
 
-    pub async fn set_workspace(&self, path: &str) {
+    /// Use `path` as the job workspace and resume the jobs saved there.
+    /// Returns how many saved jobs were loaded.
+    pub async fn set_workspace(&self, path: &str) -> Result<usize, String> {
+        if !Path::new(path).is_dir() {
+            return Err(format!("Path does not exist: {}", path));
+        }
+        let loaded = self.load_state(&state_file_in(Path::new(path))).await?;
         *self.workspace_path.lock().await = Some(path.to_string());
+        Ok(loaded)
     }
 
     // ============================================
@@ -299,16 +316,31 @@ impl JobManager {
         let mut jobs = self.jobs.lock().await;
         let job = jobs.get_mut(job_id).ok_or("Job not found")?;
 
-        if job.status != JobStatus::InProgress {
-            return Err("Only in-progress jobs can be paused".to_string());
+        if job.status != JobStatus::InProgress && job.status != JobStatus::Ready {
+            return Err("Only queued or in-progress jobs can be paused".to_string());
         }
 
+        // Running tasks finish; the scheduler starts no new ones
         job.status = JobStatus::Paused;
         job.updated_at = Utc::now();
 
         Ok(job.clone())
     }
 
+    pub async fn resume_job(&self, job_id: &str) -> Result<Job, String> {
+        let mut jobs = self.jobs.lock().await;
+        let job = jobs.get_mut(job_id).ok_or("Job not found")?;
+
+        if job.status != JobStatus::Paused {
+            return Err("Only paused jobs can be resumed".to_string());
+        }
+
+        job.status = if job.started_at.is_some() { JobStatus::InProgress } else { JobStatus::Ready };
+        job.updated_at = Utc::now();
+
+        Ok(job.clone())
+    }
+
     pub async fn complete_job(&self, job_id: &str) -> Result<Job, String> {
         let mut jobs = self.jobs.lock().await;
         let job = jobs.get_mut(job_id).ok_or("Job not found")?;
@@ -345,6 +377,11 @@ impl JobManager {
         let mut jobs = self.jobs.lock().await;
         let job = jobs.get_mut(job_id).ok_or("Job not found")?;
 
+        let dependencies = request.dependencies.unwrap_or_default();
+        if let Some(missing) = dependencies.iter().find(|dep| !job.tasks.iter().any(|t| &t.id == *dep)) {
+            return Err(format!("Dependency {} is not a task of this job", missing));
+        }
+
         let task_id = uuid::Uuid::new_v4().to_string();
         let order = job.tasks.len() as i32;
 
@@ -361,8 +398,9 @@ impl JobManager {
             file_path: request.file_path,
             line_start: request.line_start,
             line_end: request.line_end,
-            dependencies: request.dependencies.unwrap_or_default(),
+            dependencies,
             created_at: Utc::now(),
+            started_at: None,
             completed_at: None,
         };
 
@@ -417,12 +455,77 @@ impl JobManager {
             .ok_or("Task not found")?;
         
         job.tasks.remove(pos);
+        for task in job.tasks.iter_mut() {
+            task.dependencies.retain(|dep| dep != task_id);
+        }
         job.updated_at = Utc::now();
         Self::update_job_progress(job);
 
         Ok(())
     }
 
+    /// Make `task_id` wait for `depends_on` before the scheduler starts it.
+    /// Both tasks must belong to `job_id` and the edge must not close a cycle.
+    pub async fn add_task_dependency(&self, job_id: &str, task_id: &str, depends_on: &str) -> Result<Task, String> {
+        let mut jobs = self.jobs.lock().await;
+        let job = jobs.get_mut(job_id).ok_or("Job not found")?;
+
+        if !job.tasks.iter().any(|t| t.id == depends_on) {
+            return Err(format!("Dependency {} is not a task of this job", depends_on));
+        }
+        if task_id == depends_on || Self::depends_on(job, depends_on, task_id) {
+            return Err("Dependency would create a cycle".to_string());
+        }
+
+        let task = job.tasks.iter_mut()
+            .find(|t| t.id == task_id)
+            .ok_or("Task not found")?;
+        if !task.dependencies.iter().any(|dep| dep == depends_on) {
+            task.dependencies.push(depends_on.to_string());
+        }
+
+        let task_clone = task.clone();
+        job.updated_at = Utc::now();
+
+        Ok(task_clone)
+    }
+
+    pub async fn remove_task_dependency(&self, job_id: &str, task_id: &str, depends_on: &str) -> Result<Task, String> {
+        let mut jobs = self.jobs.lock().await;
+        let job = jobs.get_mut(job_id).ok_or("Job not found")?;
+
+        let task = job.tasks.iter_mut()
+            .find(|t| t.id == task_id)
+            .ok_or("Task not found")?;
+        task.dependencies.retain(|dep| dep != depends_on);
+
+        let task_clone = task.clone();
+        job.updated_at = Utc::now();
+
+        Ok(task_clone)
+    }
+
+    /// Whether `task_id` waits on `target`, directly or transitively
+    fn depends_on(job: &Job, task_id: &str, target: &str) -> bool {
+        let mut stack = vec![task_id];
+        let mut seen = std::collections::HashSet::new();
+        while let Some(id) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            let Some(task) = job.tasks.iter().find(|t| t.id == id) else {
+                continue;
+            };
+            for dep in &task.dependencies {
+                if dep == target {
+                    return true;
+                }
+                stack.push(dep);
+            }
+        }
+        false
+    }
+
     fn update_job_progress(job: &mut Job) {
         if job.tasks.is_empty() {
             job.progress_percent = 0;
@@ -491,9 +594,33 @@ impl JobManager {
         Ok(())
     }
 
-    pub async fn merge_branch(&self, source: &str, target: &str) -> Result<(), String> {
-        // TODO: Implement actual git merge
-        Ok(())
+    pub async fn merge_branch(&self, source: &str, target: &str) -> Result<MergeOutcome, String> {
+        let path = self.require_workspace().await?;
+        let (source, target) = (source.to_string(), target.to_string());
+        tokio::task::spawn_blocking(move || workspace_manager::git_merge(&path, &source, &target))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    pub async fn abort_merge(&self) -> Result<(), String> {
+        let path = self.require_workspace().await?;
+        tokio::task::spawn_blocking(move || workspace_manager::git_merge_abort(&path))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    pub async fn continue_merge(&self) -> Result<MergeOutcome, String> {
+        let path = self.require_workspace().await?;
+        tokio::task::spawn_blocking(move || workspace_manager::git_merge_continue(&path))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    async fn require_workspace(&self) -> Result<std::path::PathBuf, String> {
+        let workspace = self.workspace_path.lock().await;
+        workspace.as_ref()
+            .map(std::path::PathBuf::from)
+            .ok_or_else(|| "No workspace set".to_string())
     }
 
     pub async fn delete_branch(&self, branch_name: &str) -> Result<(), String> {
@@ -549,6 +676,63 @@ impl JobManager {
         }
     }
 
+    // ============================================
+    // Persistence
+    // ============================================
+
+    pub async fn workspace_path(&self) -> Option<String> {
+        self.workspace_path.lock().await.clone()
+    }
+
+    /// Where job state is saved for the current workspace
+    pub async fn state_file(&self) -> Option<PathBuf> {
+        self.workspace_path.lock().await
+            .as_ref()
+            .map(|path| state_file_in(Path::new(path)))
+    }
+
+    pub async fn save_state(&self, path: &Path) -> Result<(), String> {
+        let jobs = self.jobs.lock().await;
+        let mut list: Vec<&Job> = jobs.values().collect();
+        list.sort_by_key(|job| job.created_at);
+
+        let content = serde_json::to_string_pretty(&list)
+            .map_err(|e| format!("Failed to serialize jobs: {}", e))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create job state directory: {}", e))?;
+        }
+        // Write then rename so a crash mid-write keeps the previous state
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, content)
+            .map_err(|e| format!("Failed to write job state: {}", e))?;
+        std::fs::rename(&tmp, path)
+            .map_err(|e| format!("Failed to write job state: {}", e))
+    }
+
+    /// Load saved jobs. Tasks that were mid-run when the app stopped go back
+    /// to pending so the scheduler picks them up again.
+    pub async fn load_state(&self, path: &Path) -> Result<usize, String> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read job state: {}", e))?;
+        let loaded: Vec<Job> = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse job state: {}", e))?;
+
+        let mut jobs = self.jobs.lock().await;
+        let count = loaded.len();
+        for mut job in loaded {
+            for task in job.tasks.iter_mut().filter(|t| t.status == TaskStatus::InProgress) {
+                task.status = TaskStatus::Pending;
+                task.started_at = None;
+            }
+            jobs.insert(job.id.clone(), job);
+        }
+        Ok(count)
+    }
+
     // ============================================
     // Utilities
     // ============================================
@@ -567,3 +751,548 @@ impl JobManager {
             .collect()
     }
 }
+
+// ============================================
+// Job Scheduler
+// ============================================
+
+/// Executes one task on behalf of the scheduler
+#[async_trait::async_trait]
+pub trait TaskRunner: Send + Sync {
+    async fn run_task(&self, job: &Job, task: &Task) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SchedulerEvent {
+    TaskStarted {
+        job_id: String,
+        task_id: String,
+    },
+    TaskFinished {
+        job_id: String,
+        task_id: String,
+        status: TaskStatus,
+        actual_minutes: Option<i32>,
+        error: Option<String>,
+    },
+    JobProgress {
+        job_id: String,
+        status: JobStatus,
+        progress_percent: i32,
+    },
+}
+
+pub type SchedulerListener = Arc<dyn Fn(SchedulerEvent) + Send + Sync>;
+
+/// Running task id -> (job id, handle)
+type RunningTasks = Arc<Mutex<HashMap<String, (String, JoinHandle<()>)>>>;
+
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    /// Tasks running at once, across all jobs
+    pub max_concurrent: usize,
+    pub poll_interval: Duration,
+    /// How long shutdown waits for running tasks before interrupting them
+    pub shutdown_grace: Duration,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 2,
+            poll_interval: Duration::from_secs(2),
+            shutdown_grace: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Runs tasks of `Ready`/`InProgress` jobs in the background. A task starts
+/// once its dependencies are complete, and a job's tasks start only after
+/// its parent job has completed.
+pub struct JobScheduler {
+    manager: Arc<JobManager>,
+    runner: Arc<dyn TaskRunner>,
+    config: SchedulerConfig,
+    listener: Option<SchedulerListener>,
+    running: RunningTasks,
+    wake: Arc<Notify>,
+    stopping: Arc<AtomicBool>,
+}
+
+impl JobScheduler {
+    pub fn new(manager: Arc<JobManager>, runner: Arc<dyn TaskRunner>, config: SchedulerConfig) -> Self {
+        Self {
+            manager,
+            runner,
+            config: SchedulerConfig {
+                max_concurrent: config.max_concurrent.max(1),
+                ..config
+            },
+            listener: None,
+            running: Arc::new(Mutex::new(HashMap::new())),
+            wake: Arc::new(Notify::new()),
+            stopping: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_listener(mut self, listener: SchedulerListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Poll until `shutdown` is called. Finishing tasks wake the loop early.
+    pub async fn run(self: Arc<Self>) {
+        while !self.stopping.load(Ordering::SeqCst) {
+            self.tick().await;
+            tokio::select! {
+                _ = tokio::time::sleep(self.config.poll_interval) => {}
+                _ = self.wake.notified() => {}
+            }
+        }
+    }
+
+    /// Start as many runnable tasks as the concurrency limit allows.
+    /// Returns how many were started.
+    pub async fn tick(&self) -> usize {
+        if self.stopping.load(Ordering::SeqCst) {
+            return 0;
+        }
+
+        // Held while spawning so a fast task can't deregister before it's registered
+        let mut running = self.running.lock().await;
+        let slots = self.config.max_concurrent.saturating_sub(running.len());
+        if slots == 0 {
+            return 0;
+        }
+
+        let mut events = Vec::new();
+        let started: Vec<(Job, Task)> = {
+            let mut jobs = self.manager.jobs.lock().await;
+            let picks = Self::runnable_tasks(&jobs, slots);
+            let now = Utc::now();
+
+            picks.into_iter()
+                .filter_map(|(job_id, task_id)| {
+                    let job = jobs.get_mut(&job_id)?;
+                    if job.status == JobStatus::Ready {
+                        job.status = JobStatus::InProgress;
+                        job.started_at.get_or_insert(now);
+                    }
+                    job.updated_at = now;
+                    let task = job.tasks.iter_mut().find(|t| t.id == task_id)?;
+                    task.status = TaskStatus::InProgress;
+                    task.started_at = Some(now);
+                    let task = task.clone();
+                    events.push(SchedulerEvent::TaskStarted { job_id: job_id.clone(), task_id });
+                    Some((job.clone(), task))
+                })
+                .collect()
+        };
+
+        for event in events {
+            self.emit(event);
+        }
+
+        let count = started.len();
+        for (job, task) in started {
+            let task_id = task.id.clone();
+            let job_id = job.id.clone();
+            let handle = tokio::spawn(Self::execute(
+                self.manager.clone(),
+                self.runner.clone(),
+                self.listener.clone(),
+                self.running.clone(),
+                self.wake.clone(),
+                job,
+                task,
+            ));
+            running.insert(task_id, (job_id, handle));
+        }
+        count
+    }
+
+    /// Number of tasks currently executing
+    pub async fn running_count(&self) -> usize {
+        self.running.lock().await.len()
+    }
+
+    /// Stop scheduling, give running tasks `shutdown_grace` to finish, put any
+    /// still running back to pending, and save job state to `state_file`.
+    pub async fn shutdown(&self, state_file: Option<&Path>) -> Result<(), String> {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.wake.notify_one();
+
+        let deadline = tokio::time::Instant::now() + self.config.shutdown_grace;
+        while self.running_count().await > 0 && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        let interrupted: Vec<(String, String, JoinHandle<()>)> = self.running.lock().await
+            .drain()
+            .map(|(task_id, (job_id, handle))| (job_id, task_id, handle))
+            .collect();
+        if !interrupted.is_empty() {
+            let mut jobs = self.manager.jobs.lock().await;
+            for (job_id, task_id, handle) in interrupted {
+                handle.abort();
+                let task = jobs.get_mut(&job_id)
+                    .and_then(|job| job.tasks.iter_mut().find(|t| t.id == task_id));
+                if let Some(task) = task {
+                    task.status = TaskStatus::Pending;
+                    task.started_at = None;
+                }
+            }
+        }
+
+        match state_file {
+            Some(path) => self.manager.save_state(path).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Pending tasks whose job, parent job and dependencies allow them to
+    /// start, in job priority then task order
+    fn runnable_tasks(jobs: &HashMap<String, Job>, limit: usize) -> Vec<(String, String)> {
+        let mut candidates: Vec<&Job> = jobs.values()
+            .filter(|job| matches!(job.status, JobStatus::Ready | JobStatus::InProgress))
+            .filter(|job| {
+                job.parent_job_id.as_ref()
+                    .and_then(|parent| jobs.get(parent))
+                    .is_none_or(|parent| parent.status == JobStatus::Completed)
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            Self::priority_rank(&b.priority).cmp(&Self::priority_rank(&a.priority))
+                .then(a.created_at.cmp(&b.created_at))
+        });
+
+        let mut picks = Vec::new();
+        for job in candidates {
+            // A failed task stops the rest of its job
+            if job.tasks.iter().any(|t| t.status == TaskStatus::Failed) {
+                continue;
+            }
+            let mut tasks: Vec<&Task> = job.tasks.iter()
+                .filter(|t| t.status == TaskStatus::Pending)
+                .filter(|t| t.dependencies.iter().all(|dep| {
+                    job.tasks.iter()
+                        .find(|other| &other.id == dep)
+                        .is_none_or(|other| matches!(other.status, TaskStatus::Completed | TaskStatus::Skipped))
+                }))
+                .collect();
+            tasks.sort_by_key(|t| t.order);
+
+            for task in tasks {
+                if picks.len() == limit {
+                    return picks;
+                }
+                picks.push((job.id.clone(), task.id.clone()));
+            }
+        }
+        picks
+    }
+
+    fn priority_rank(priority: &JobPriority) -> u8 {
+        match priority {
+            JobPriority::Low => 0,
+            JobPriority::Medium => 1,
+            JobPriority::High => 2,
+            JobPriority::Critical => 3,
+        }
+    }
+
+    async fn execute(
+        manager: Arc<JobManager>,
+        runner: Arc<dyn TaskRunner>,
+        listener: Option<SchedulerListener>,
+        running: RunningTasks,
+        wake: Arc<Notify>,
+        job: Job,
+        task: Task,
+    ) {
+        let result = runner.run_task(&job, &task).await;
+        let mut events = Vec::new();
+
+        {
+            let mut jobs = manager.jobs.lock().await;
+            if let Some(job) = jobs.get_mut(&job.id) {
+                let now = Utc::now();
+                if let Some(stored) = job.tasks.iter_mut().find(|t| t.id == task.id) {
+                    stored.status = if result.is_ok() { TaskStatus::Completed } else { TaskStatus::Failed };
+                    stored.completed_at = Some(now);
+                    stored.actual_minutes = stored.started_at.map(|start| elapsed_minutes(start, now));
+                    events.push(SchedulerEvent::TaskFinished {
+                        job_id: job.id.clone(),
+                        task_id: stored.id.clone(),
+                        status: stored.status.clone(),
+                        actual_minutes: stored.actual_minutes,
+                        error: result.as_ref().err().cloned(),
+                    });
+                }
+
+                JobManager::update_job_progress(job);
+                job.updated_at = now;
+                if result.is_err() {
+                    job.status = JobStatus::Failed;
+                } else if job.status != JobStatus::Paused && job.tasks.iter()
+                    .all(|t| matches!(t.status, TaskStatus::Completed | TaskStatus::Skipped))
+                {
+                    job.status = JobStatus::Completed;
+                    job.completed_at = Some(now);
+                    job.progress_percent = 100;
+                    if let Some(started) = job.started_at {
+                        job.actual_hours = Some((now - started).num_minutes() as f64 / 60.0);
+                    }
+                }
+                events.push(SchedulerEvent::JobProgress {
+                    job_id: job.id.clone(),
+                    status: job.status.clone(),
+                    progress_percent: job.progress_percent,
+                });
+            }
+        }
+
+        if let Some(listener) = &listener {
+            for event in events {
+                listener(event);
+            }
+        }
+        running.lock().await.remove(&task.id);
+        wake.notify_one();
+    }
+
+    fn emit(&self, event: SchedulerEvent) {
+        if let Some(listener) = &self.listener {
+            listener(event);
+        }
+    }
+}
+
+/// Where job state is saved in `workspace`
+fn state_file_in(workspace: &Path) -> PathBuf {
+    workspace.join(".smartspec").join("jobs.json")
+}
+
+/// Whole minutes between two instants, rounding a partial minute up
+fn elapsed_minutes(start: DateTime<Utc>, end: DateTime<Utc>) -> i32 {
+    let millis = (end - start).num_milliseconds().max(0);
+    ((millis + 59_999) / 60_000) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Records start/finish order and the peak number of concurrent tasks
+    #[derive(Default)]
+    struct RecordingRunner {
+        log: std::sync::Mutex<Vec<String>>,
+        active: AtomicUsize,
+        peak: AtomicUsize,
+        fail: Option<String>,
+    }
+
+    #[async_trait::async_trait]
+    impl TaskRunner for RecordingRunner {
+        async fn run_task(&self, _job: &Job, task: &Task) -> Result<(), String> {
+            let now = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            self.log.lock().unwrap().push(format!("start {}", task.title));
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.log.lock().unwrap().push(format!("end {}", task.title));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+
+            match &self.fail {
+                Some(title) if title == &task.title => Err("boom".to_string()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    async fn ready_job(manager: &JobManager, title: &str, parent: Option<&str>, tasks: &[&str]) -> String {
+        let job = manager.create_job("ws", CreateJobRequest {
+            title: title.to_string(),
+            description: String::new(),
+            priority: None,
+            parent_job_id: parent.map(String::from),
+            tags: None,
+            estimated_hours: None,
+            create_branch: false,
+            branch_prefix: None,
+        }).await.unwrap();
+        for task in tasks {
+            manager.add_task(&job.id, CreateTaskRequest {
+                title: task.to_string(),
+                description: None,
+                task_type: TaskType::Implement,
+                estimated_minutes: None,
+                file_path: None,
+                line_start: None,
+                line_end: None,
+                dependencies: None,
+            }).await.unwrap();
+        }
+        manager.update_job(&job.id, UpdateJobRequest {
+            title: None,
+            description: None,
+            status: Some(JobStatus::Ready),
+            priority: None,
+            tags: None,
+            estimated_hours: None,
+        }).await.unwrap();
+        job.id
+    }
+
+    /// Tick until nothing is running and nothing new can start
+    async fn drain(scheduler: &JobScheduler) {
+        for _ in 0..500 {
+            let started = scheduler.tick().await;
+            if started == 0 && scheduler.running_count().await == 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("scheduler did not go idle");
+    }
+
+    fn position(log: &[String], entry: &str) -> usize {
+        log.iter().position(|e| e == entry).unwrap_or_else(|| panic!("missing {}", entry))
+    }
+
+    #[tokio::test]
+    async fn test_child_job_waits_for_parent_and_task_dependencies() {
+        let manager = Arc::new(JobManager::new());
+        let parent = ready_job(&manager, "parent", None, &["p1"]).await;
+        let child = ready_job(&manager, "child", Some(&parent), &["c1", "c2"]).await;
+
+        // c2 depends on c1 even though nothing else orders them
+        {
+            let mut jobs = manager.jobs.lock().await;
+            let tasks = &mut jobs.get_mut(&child).unwrap().tasks;
+            let c1 = tasks[0].id.clone();
+            tasks[1].dependencies = vec![c1];
+        }
+
+        let runner = Arc::new(RecordingRunner::default());
+        let scheduler = JobScheduler::new(manager.clone(), runner.clone(), SchedulerConfig {
+            max_concurrent: 4,
+            ..SchedulerConfig::default()
+        });
+        drain(&scheduler).await;
+
+        let log = runner.log.lock().unwrap().clone();
+        assert!(position(&log, "end p1") < position(&log, "start c1"));
+        assert!(position(&log, "end c1") < position(&log, "start c2"));
+
+        for id in [&parent, &child] {
+            let job = manager.get_job(id).await.unwrap();
+            assert_eq!(job.status, JobStatus::Completed);
+            assert!(job.tasks.iter().all(|t| t.actual_minutes == Some(1)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_dependency_edges_order_tasks_and_reject_cycles() {
+        let manager = Arc::new(JobManager::new());
+        let job = ready_job(&manager, "job", None, &["a", "b", "c"]).await;
+        let ids: Vec<String> = manager.get_job(&job).await.unwrap().tasks.iter().map(|t| t.id.clone()).collect();
+
+        // a waits on c, which reverses the list order
+        manager.add_task_dependency(&job, &ids[0], &ids[2]).await.unwrap();
+        manager.add_task_dependency(&job, &ids[2], &ids[1]).await.unwrap();
+        assert!(manager.add_task_dependency(&job, &ids[1], &ids[0]).await.is_err());
+        assert!(manager.add_task_dependency(&job, &ids[1], &ids[1]).await.is_err());
+        let other = ready_job(&manager, "other", None, &["x"]).await;
+        let foreign = manager.get_job(&other).await.unwrap().tasks[0].id.clone();
+        assert!(manager.add_task_dependency(&job, &ids[1], &foreign).await.is_err());
+
+        let runner = Arc::new(RecordingRunner::default());
+        let scheduler = JobScheduler::new(manager.clone(), runner.clone(), SchedulerConfig {
+            max_concurrent: 4,
+            ..SchedulerConfig::default()
+        });
+        drain(&scheduler).await;
+
+        let log = runner.log.lock().unwrap().clone();
+        assert!(position(&log, "end b") < position(&log, "start c"));
+        assert!(position(&log, "end c") < position(&log, "start a"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_is_capped() {
+        let manager = Arc::new(JobManager::new());
+        for i in 0..5 {
+            ready_job(&manager, &format!("job{}", i), None, &["a", "b"]).await;
+        }
+
+        let runner = Arc::new(RecordingRunner::default());
+        let scheduler = JobScheduler::new(manager.clone(), runner.clone(), SchedulerConfig {
+            max_concurrent: 3,
+            ..SchedulerConfig::default()
+        });
+        drain(&scheduler).await;
+
+        assert_eq!(runner.peak.load(Ordering::SeqCst), 3);
+        assert_eq!(runner.log.lock().unwrap().len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_failure_pause_and_shutdown_persistence() {
+        let manager = Arc::new(JobManager::new());
+        let failing = ready_job(&manager, "failing", None, &["bad", "after"]).await;
+        let paused = ready_job(&manager, "paused", None, &["x"]).await;
+        manager.pause_job(&paused).await.unwrap();
+
+        let runner = Arc::new(RecordingRunner { fail: Some("bad".to_string()), ..RecordingRunner::default() });
+        let scheduler = JobScheduler::new(manager.clone(), runner.clone(), SchedulerConfig {
+            max_concurrent: 1,
+            ..SchedulerConfig::default()
+        });
+        drain(&scheduler).await;
+
+        let job = manager.get_job(&failing).await.unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.tasks[1].status, TaskStatus::Pending);
+        assert_eq!(manager.get_job(&paused).await.unwrap().tasks[0].status, TaskStatus::Pending);
+
+        manager.resume_job(&paused).await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let state = dir.path().join("jobs.json");
+        scheduler.tick().await;
+        scheduler.shutdown(Some(&state)).await.unwrap();
+        assert_eq!(scheduler.tick().await, 0);
+
+        let restored = JobManager::new();
+        assert_eq!(restored.load_state(&state).await.unwrap(), 2);
+        assert_eq!(restored.get_job(&paused).await.unwrap().status, JobStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_set_workspace_resumes_saved_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().to_string_lossy().to_string();
+        let manager = JobManager::new();
+        assert_eq!(manager.set_workspace(&workspace).await.unwrap(), 0);
+        let job_id = ready_job(&manager, "interrupted", None, &["a"]).await;
+        let task_id = manager.get_job(&job_id).await.unwrap().tasks[0].id.clone();
+        manager.update_task_status(&job_id, &task_id, TaskStatus::InProgress).await.unwrap();
+        manager.save_state(&manager.state_file().await.unwrap()).await.unwrap();
+
+        let restarted = JobManager::new();
+        assert!(restarted.set_workspace(&dir.path().join("missing").to_string_lossy()).await.is_err());
+        assert_eq!(restarted.set_workspace(&workspace).await.unwrap(), 1);
+        assert_eq!(restarted.workspace_path().await.as_deref(), Some(workspace.as_str()));
+        let job = restarted.get_job(&job_id).await.unwrap();
+        assert_eq!(job.tasks[0].status, TaskStatus::Pending);
+    }
+
+    #[test]
+    fn test_elapsed_minutes_rounds_up() {
+        let start = Utc::now();
+        assert_eq!(elapsed_minutes(start, start), 0);
+        assert_eq!(elapsed_minutes(start, start + chrono::Duration::milliseconds(20)), 1);
+        assert_eq!(elapsed_minutes(start, start + chrono::Duration::seconds(120)), 2);
+    }
+}
@@ -2,18 +2,19 @@
 //
 // RISK-012 FIX: Provides fallback storage when OS keyring is unavailable
 //
-// Storage priority:
+// Every request walks the same chain:
 // 1. OS Keyring (most secure)
-// 2. Encrypted file storage (fallback)
+// 2. Encrypted file storage (fallback, only when the keyring is unavailable)
+// 3. Error
 //
 // The fallback uses AES-256-GCM encryption with a key derived from
-// machine-specific identifiers.
+// machine-specific identifiers. Each request logs which backend served it,
+// never the value.
 
 use aes_gcm::{
     aead::{Aead, KeyInit},
     Aes256Gcm, Nonce,
 };
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use keyring::Entry;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -22,6 +23,8 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::secure_store::{clear_git_tokens, git_token_keys, AUDIT_ANCHOR_KEY, WORKSPACE_DB_KEY};
+
 const SERVICE: &str = "smartspecpro";
 const FALLBACK_FILE: &str = "secure_store.enc";
 const NONCE_SIZE: usize = 12;
@@ -50,56 +53,135 @@ impl Default for FallbackStore {
     }
 }
 
-#[derive(Debug)]
-pub enum StorageBackend {
+/// Where a secret was read from or written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
     Keyring,
     EncryptedFile,
 }
 
+impl SecretBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretBackend::Keyring => "keyring",
+            SecretBackend::EncryptedFile => "encrypted_file",
+        }
+    }
+}
+
+/// A secret and the backend that served it
+#[derive(Debug, Clone)]
+pub struct StoredSecret {
+    pub value: String,
+    pub backend: SecretBackend,
+}
+
+/// A stored key and its backend, without the value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretLocation {
+    pub key: String,
+    pub backend: SecretBackend,
+}
+
+/// Keys the keyring is checked for; it can't enumerate its entries. Git
+/// tokens are found through the host list `secure_store` keeps.
+const KNOWN_KEYS: &[&str] = &[
+    "auth_token",
+    "refresh_token",
+    "user_data",
+    "proxy_token",
+    "api_key_openrouter",
+    "api_key_openai",
+    "api_key_anthropic",
+    "api_key_deepseek",
+    "api_key_google",
+    WORKSPACE_DB_KEY,
+    AUDIT_ANCHOR_KEY,
+];
+
+/// Kept by `clear_all`: encrypted workspaces are unreadable without the
+/// database key, and the audit chain can't be checked for truncation
+/// without its anchor
+const KEPT_ON_CLEAR: &[&str] = &[WORKSPACE_DB_KEY, AUDIT_ANCHOR_KEY];
+
+// ============================================
+// Keyring Access
+// ============================================
+
+/// OS keyring operations, behind a trait so tests can simulate an
+/// unavailable keyring
+pub trait KeyringAccess: Send {
+    fn set(&self, key: &str, value: &str) -> keyring::Result<()>;
+    fn get(&self, key: &str) -> keyring::Result<String>;
+    fn delete(&self, key: &str) -> keyring::Result<()>;
+}
+
+pub struct OsKeyring;
+
+impl KeyringAccess for OsKeyring {
+    fn set(&self, key: &str, value: &str) -> keyring::Result<()> {
+        Entry::new(SERVICE, key)?.set_password(value)
+    }
+
+    fn get(&self, key: &str) -> keyring::Result<String> {
+        Entry::new(SERVICE, key)?.get_password()
+    }
+
+    fn delete(&self, key: &str) -> keyring::Result<()> {
+        Entry::new(SERVICE, key)?.delete_password()
+    }
+}
+
+/// Errors that mean the keyring itself can't be used, as opposed to a
+/// problem with this particular entry
+fn keyring_unavailable(error: &keyring::Error) -> bool {
+    matches!(error, keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_))
+}
+
+fn log_served(operation: &str, key: &str, backend: SecretBackend) {
+    eprintln!("Secure storage {} '{}' served by {}", operation, key, backend.as_str());
+}
+
 // ============================================
 // Secure Storage with Fallback
 // ============================================
 
 pub struct SecureStorage {
-    backend: StorageBackend,
+    keyring: Box<dyn KeyringAccess>,
+    /// Result of the startup probe, for reporting only
+    keyring_available: bool,
     fallback_path: PathBuf,
     encryption_key: [u8; 32],
 }
 
 impl SecureStorage {
-    /// Create a new SecureStorage instance
-    /// Automatically detects available backend
+    /// Create a new SecureStorage instance backed by the OS keyring
     pub fn new() -> Result<Self, String> {
-        let fallback_path = Self::get_fallback_path()?;
+        Self::with_keyring(Box::new(OsKeyring), Self::get_fallback_path()?)
+    }
+    
+    fn with_keyring(keyring: Box<dyn KeyringAccess>, fallback_path: PathBuf) -> Result<Self, String> {
         let encryption_key = Self::derive_encryption_key()?;
-        
-        // Test if keyring is available
-        let backend = if Self::test_keyring() {
-            StorageBackend::Keyring
-        } else {
-            log::warn!("OS keyring unavailable, using encrypted file fallback");
-            StorageBackend::EncryptedFile
-        };
+        let keyring_available = Self::test_keyring(keyring.as_ref());
+        if !keyring_available {
+            eprintln!("OS keyring unavailable, secrets will use the encrypted file fallback");
+        }
         
         Ok(Self {
-            backend,
+            keyring,
+            keyring_available,
             fallback_path,
             encryption_key,
         })
     }
     
     /// Test if keyring is available
-    fn test_keyring() -> bool {
-        let test_entry = Entry::new(SERVICE, "__keyring_test__");
-        match test_entry {
-            Ok(entry) => {
-                // Try to set and delete a test value
-                let test_result = entry.set_password("test")
-                    .and_then(|_| entry.delete_password());
-                test_result.is_ok()
-            }
-            Err(_) => false,
-        }
+    fn test_keyring(keyring: &dyn KeyringAccess) -> bool {
+        // Try to set and delete a test value
+        keyring.set("__keyring_test__", "test")
+            .and_then(|_| keyring.delete("__keyring_test__"))
+            .is_ok()
     }
     
     /// Get the fallback file path
@@ -144,64 +226,84 @@ impl SecureStorage {
         Ok(key)
     }
     
-    /// Store a value securely
-    pub fn set(&self, key: &str, value: &str) -> Result<(), String> {
-        match &self.backend {
-            StorageBackend::Keyring => self.set_keyring(key, value),
-            StorageBackend::EncryptedFile => self.set_file(key, value),
-        }
+    /// Store a value in the keyring, or in the encrypted file if the
+    /// keyring is unavailable
+    pub fn set(&self, key: &str, value: &str) -> Result<SecretBackend, String> {
+        let backend = match self.keyring.set(key, value) {
+            Ok(()) => {
+                // Don't leave an older copy behind in the fallback file
+                if let Err(e) = self.delete_file(key) {
+                    eprintln!("Failed to remove fallback copy of '{}': {}", key, e);
+                }
+                SecretBackend::Keyring
+            }
+            Err(e) if keyring_unavailable(&e) => {
+                self.set_file(key, value)
+                    .map_err(|file_err| format!("No secure storage available (keyring: {}; encrypted file: {})", e, file_err))?;
+                SecretBackend::EncryptedFile
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        
+        log_served("set", key, backend);
+        Ok(backend)
     }
     
-    /// Retrieve a value
-    pub fn get(&self, key: &str) -> Result<Option<String>, String> {
-        match &self.backend {
-            StorageBackend::Keyring => self.get_keyring(key),
-            StorageBackend::EncryptedFile => self.get_file(key),
+    /// Retrieve a value and the backend holding it. The keyring is checked
+    /// first; the encrypted file covers values written while it was unavailable.
+    pub fn get(&self, key: &str) -> Result<Option<StoredSecret>, String> {
+        let secret = self.find(key)?;
+        if let Some(secret) = &secret {
+            log_served("get", key, secret.backend);
         }
+        Ok(secret)
     }
     
-    /// Delete a value
-    pub fn delete(&self, key: &str) -> Result<(), String> {
-        match &self.backend {
-            StorageBackend::Keyring => self.delete_keyring(key),
-            StorageBackend::EncryptedFile => self.delete_file(key),
+    fn find(&self, key: &str) -> Result<Option<StoredSecret>, String> {
+        let keyring_error = match self.keyring.get(key) {
+            Ok(value) => return Ok(Some(StoredSecret { value, backend: SecretBackend::Keyring })),
+            Err(keyring::Error::NoEntry) => None,
+            Err(e) if keyring_unavailable(&e) => Some(e),
+            Err(e) => return Err(e.to_string()),
+        };
+        
+        match self.get_file(key) {
+            Ok(value) => Ok(value.map(|value| StoredSecret { value, backend: SecretBackend::EncryptedFile })),
+            Err(file_err) => Err(match keyring_error {
+                Some(e) => format!("No secure storage available (keyring: {}; encrypted file: {})", e, file_err),
+                None => file_err,
+            }),
         }
     }
     
-    /// Check if keyring is being used
-    pub fn is_keyring_available(&self) -> bool {
-        matches!(self.backend, StorageBackend::Keyring)
-    }
-    
-    /// Get current backend type
-    pub fn get_backend(&self) -> &StorageBackend {
-        &self.backend
-    }
-    
-    // ============================================
-    // Keyring Operations
-    // ============================================
-    
-    fn set_keyring(&self, key: &str, value: &str) -> Result<(), String> {
-        let entry = Entry::new(SERVICE, key).map_err(|e| e.to_string())?;
-        entry.set_password(value).map_err(|e| e.to_string())
+    /// Delete a value from every backend
+    pub fn delete(&self, key: &str) -> Result<(), String> {
+        self.delete_keyring(key)?;
+        self.delete_file(key)
     }
     
-    fn get_keyring(&self, key: &str) -> Result<Option<String>, String> {
-        let entry = Entry::new(SERVICE, key).map_err(|e| e.to_string())?;
-        match entry.get_password() {
-            Ok(v) => Ok(Some(v)),
-            Err(keyring::Error::NoEntry) => Ok(None),
+    fn delete_keyring(&self, key: &str) -> Result<(), String> {
+        match self.keyring.delete(key) {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) if keyring_unavailable(&e) => {
+                eprintln!("OS keyring unavailable, '{}' could only be deleted from the encrypted file: {}", key, e);
+                Ok(())
+            }
             Err(e) => Err(e.to_string()),
         }
     }
     
-    fn delete_keyring(&self, key: &str) -> Result<(), String> {
-        let entry = Entry::new(SERVICE, key).map_err(|e| e.to_string())?;
-        match entry.delete_password() {
-            Ok(_) => Ok(()),
-            Err(keyring::Error::NoEntry) => Ok(()),
-            Err(e) => Err(e.to_string()),
+    /// Whether the keyring passed the startup probe
+    pub fn is_keyring_available(&self) -> bool {
+        self.keyring_available
+    }
+    
+    /// Backend new secrets are expected to land in
+    pub fn get_backend(&self) -> SecretBackend {
+        if self.keyring_available {
+            SecretBackend::Keyring
+        } else {
+            SecretBackend::EncryptedFile
         }
     }
     
@@ -222,8 +324,13 @@ impl SecureStorage {
     }
     
     fn delete_file(&self, key: &str) -> Result<(), String> {
+        if !self.fallback_path.exists() {
+            return Ok(());
+        }
         let mut store = self.load_store()?;
-        store.entries.remove(key);
+        if store.entries.remove(key).is_none() {
+            return Ok(());
+        }
         store.updated_at = chrono::Utc::now().timestamp();
         self.save_store(&store)
     }
@@ -282,69 +389,61 @@ impl SecureStorage {
             .map_err(|e| format!("Failed to write store file: {}", e))
     }
     
-    /// List all stored keys
-    pub fn list_keys(&self) -> Result<Vec<String>, String> {
-        match &self.backend {
-            StorageBackend::Keyring => {
-                // Keyring doesn't support listing, return known keys
-                Ok(vec![
-                    "auth_token".to_string(),
-                    "refresh_token".to_string(),
-                    "proxy_token".to_string(),
-                    "api_key_openrouter".to_string(),
-                    "api_key_openai".to_string(),
-                    "api_key_anthropic".to_string(),
-                    "api_key_deepseek".to_string(),
-                    "api_key_google".to_string(),
-                ])
+    /// Stored keys and the backend each one is read from
+    pub fn list_entries(&self) -> Result<Vec<SecretLocation>, String> {
+        let store = if self.fallback_path.exists() {
+            self.load_store()?
+        } else {
+            FallbackStore::default()
+        };
+        
+        let mut keys: Vec<String> = KNOWN_KEYS.iter().map(|key| key.to_string()).collect();
+        keys.extend(git_token_keys(self)?);
+        
+        let mut entries = Vec::new();
+        for key in keys {
+            if self.keyring.get(&key).is_ok() {
+                entries.push(SecretLocation { key, backend: SecretBackend::Keyring });
             }
-            StorageBackend::EncryptedFile => {
-                let store = self.load_store()?;
-                Ok(store.entries.keys().cloned().collect())
+        }
+        for key in store.entries.keys() {
+            if !entries.iter().any(|e| &e.key == key) {
+                entries.push(SecretLocation { key: key.clone(), backend: SecretBackend::EncryptedFile });
             }
         }
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        
+        Ok(entries)
+    }
+    
+    /// List all stored keys
+    pub fn list_keys(&self) -> Result<Vec<String>, String> {
+        Ok(self.list_entries()?.into_iter().map(|e| e.key).collect())
     }
     
     /// Get count of stored credentials
     pub fn count(&self) -> Result<usize, String> {
-        match &self.backend {
-            StorageBackend::Keyring => {
-                // Count by checking each known key
-                let keys = self.list_keys()?;
-                let mut count = 0;
-                for key in keys {
-                    if self.get(&key)?.is_some() {
-                        count += 1;
-                    }
-                }
-                Ok(count)
-            }
-            StorageBackend::EncryptedFile => {
-                let store = self.load_store()?;
-                Ok(store.entries.len())
-            }
-        }
+        Ok(self.list_entries()?.len())
     }
     
-    /// Clear all stored credentials
+    /// Clear all stored credentials, git tokens included, except the
+    /// `KEPT_ON_CLEAR` keys
     pub fn clear_all(&self) -> Result<(), String> {
-        match &self.backend {
-            StorageBackend::Keyring => {
-                let keys = self.list_keys()?;
-                for key in keys {
-                    let _ = self.delete(&key);
-                }
-                Ok(())
-            }
-            StorageBackend::EncryptedFile => {
-                let store = FallbackStore::default();
-                self.save_store(&store)
-            }
+        clear_git_tokens(self)?;
+        for key in KNOWN_KEYS.iter().filter(|key| !KEPT_ON_CLEAR.contains(key)) {
+            self.delete_keyring(key)?;
+        }
+        if self.fallback_path.exists() {
+            let mut store = self.load_store()?;
+            store.entries.retain(|key, _| KEPT_ON_CLEAR.contains(&key.as_str()));
+            store.updated_at = chrono::Utc::now().timestamp();
+            self.save_store(&store)?;
         }
+        Ok(())
     }
     
     /// Migrate from keyring to file or vice versa
-    pub fn migrate_to(&self, target: StorageBackend) -> Result<(), String> {
+    pub fn migrate_to(&self, _target: SecretBackend) -> Result<(), String> {
         // This would require creating a new SecureStorage with the target backend
         // and copying all values. For now, just return an error.
         Err("Migration between backends not yet implemented".to_string())
@@ -368,9 +467,13 @@ pub fn init_secure_storage() -> Result<(), String> {
     Ok(())
 }
 
-/// Get the global secure storage instance
+/// Get the global secure storage instance, initializing it on first use
 pub fn get_secure_storage() -> Result<std::sync::MutexGuard<'static, Option<SecureStorage>>, String> {
-    SECURE_STORAGE.lock().map_err(|e| e.to_string())
+    let mut guard = SECURE_STORAGE.lock().map_err(|e| e.to_string())?;
+    if guard.is_none() {
+        *guard = Some(SecureStorage::new()?);
+    }
+    Ok(guard)
 }
 
 // ============================================
@@ -378,17 +481,20 @@ pub fn get_secure_storage() -> Result<std::sync::MutexGuard<'static, Option<Secu
 // ============================================
 
 #[tauri::command]
-pub fn secure_store_set(key: String, value: String) -> Result<(), String> {
+pub fn secure_store_set(key: String, value: String) -> Result<SecretBackend, String> {
     let guard = get_secure_storage()?;
     let storage = guard.as_ref().ok_or("Secure storage not initialized")?;
     storage.set(&key, &value)
 }
 
 #[tauri::command]
-pub fn secure_store_get(key: String) -> Result<Option<String>, String> {
+pub fn secure_store_get(key: String) -> Result<Option<SecretValue>, String> {
     let guard = get_secure_storage()?;
     let storage = guard.as_ref().ok_or("Secure storage not initialized")?;
-    storage.get(&key)
+    Ok(storage.get(&key)?.map(|secret| SecretValue {
+        value: secret.value,
+        backend: secret.backend,
+    }))
 }
 
 #[tauri::command]
@@ -406,10 +512,8 @@ pub fn get_security_info() -> Result<SecurityInfo, String> {
     Ok(SecurityInfo {
         keyring_available: storage.is_keyring_available(),
         credentials_count: storage.count()?,
-        backend: match storage.get_backend() {
-            StorageBackend::Keyring => "keyring".to_string(),
-            StorageBackend::EncryptedFile => "encrypted_file".to_string(),
-        },
+        backend: storage.get_backend().as_str().to_string(),
+        entries: storage.list_entries()?,
     })
 }
 
@@ -425,6 +529,13 @@ pub struct SecurityInfo {
     pub keyring_available: bool,
     pub credentials_count: usize,
     pub backend: String,
+    pub entries: Vec<SecretLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretValue {
+    pub value: String,
+    pub backend: SecretBackend,
 }
 
 // ============================================
@@ -434,6 +545,8 @@ pub struct SecurityInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
     
     #[test]
     fn test_derive_encryption_key() {
@@ -453,4 +566,121 @@ mod tests {
         assert_eq!(store.version, 1);
         assert!(store.entries.is_empty());
     }
+    
+    /// In-memory keyring that can be switched off
+    #[derive(Clone, Default)]
+    struct FakeKeyring {
+        entries: Arc<Mutex<HashMap<String, String>>>,
+        unavailable: Arc<AtomicBool>,
+    }
+    
+    impl FakeKeyring {
+        fn check(&self) -> keyring::Result<()> {
+            if self.unavailable.load(Ordering::SeqCst) {
+                Err(keyring::Error::NoStorageAccess("keyring locked".into()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+    
+    impl KeyringAccess for FakeKeyring {
+        fn set(&self, key: &str, value: &str) -> keyring::Result<()> {
+            self.check()?;
+            self.entries.lock().unwrap().insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+        
+        fn get(&self, key: &str) -> keyring::Result<String> {
+            self.check()?;
+            self.entries.lock().unwrap().get(key).cloned().ok_or(keyring::Error::NoEntry)
+        }
+        
+        fn delete(&self, key: &str) -> keyring::Result<()> {
+            self.check()?;
+            self.entries.lock().unwrap().remove(key).map(|_| ()).ok_or(keyring::Error::NoEntry)
+        }
+    }
+    
+    #[test]
+    fn test_unavailable_keyring_falls_back_to_encrypted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = FakeKeyring::default();
+        keyring.unavailable.store(true, Ordering::SeqCst);
+        let storage = SecureStorage::with_keyring(Box::new(keyring.clone()), dir.path().join(FALLBACK_FILE)).unwrap();
+        assert!(!storage.is_keyring_available());
+        assert_eq!(storage.get_backend(), SecretBackend::EncryptedFile);
+        
+        assert_eq!(storage.set("api_key_openai", "sk-secret").unwrap(), SecretBackend::EncryptedFile);
+        let secret = storage.get("api_key_openai").unwrap().unwrap();
+        assert_eq!(secret.value, "sk-secret");
+        assert_eq!(secret.backend, SecretBackend::EncryptedFile);
+        
+        // The file is encrypted, not plaintext
+        let raw = fs::read(dir.path().join(FALLBACK_FILE)).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("sk-secret"));
+        
+        // Once the keyring is back it is preferred, and file-only values stay readable
+        keyring.unavailable.store(false, Ordering::SeqCst);
+        assert_eq!(storage.set("api_key_anthropic", "sk-ant").unwrap(), SecretBackend::Keyring);
+        assert_eq!(storage.get("api_key_openai").unwrap().unwrap().backend, SecretBackend::EncryptedFile);
+        assert_eq!(
+            storage.list_entries().unwrap(),
+            vec![
+                SecretLocation { key: "api_key_anthropic".to_string(), backend: SecretBackend::Keyring },
+                SecretLocation { key: "api_key_openai".to_string(), backend: SecretBackend::EncryptedFile },
+            ]
+        );
+        
+        // Re-storing moves the value into the keyring
+        assert_eq!(storage.set("api_key_openai", "sk-new").unwrap(), SecretBackend::Keyring);
+        assert!(storage.get_file("api_key_openai").unwrap().is_none());
+        
+        storage.delete("api_key_openai").unwrap();
+        assert!(storage.get("api_key_openai").unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_no_backend_available_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = FakeKeyring::default();
+        keyring.unavailable.store(true, Ordering::SeqCst);
+        
+        // A directory where the store file should be makes the fallback unwritable
+        let fallback_path = dir.path().join(FALLBACK_FILE);
+        fs::create_dir(&fallback_path).unwrap();
+        let storage = SecureStorage::with_keyring(Box::new(keyring), fallback_path).unwrap();
+        
+        let err = storage.set("auth_token", "token").unwrap_err();
+        assert!(err.contains("No secure storage available"));
+        assert!(storage.get("auth_token").is_err());
+    }
+    
+    #[test]
+    fn test_clear_all_removes_git_tokens_and_keeps_workspace_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = FakeKeyring::default();
+        let storage = SecureStorage::with_keyring(Box::new(keyring.clone()), dir.path().join(FALLBACK_FILE)).unwrap();
+        
+        storage.set("auth_token", "token").unwrap();
+        storage.set(WORKSPACE_DB_KEY, "db-key").unwrap();
+        storage.set("git_token_github.com", "ghp_secret").unwrap();
+        storage.set("git_token_hosts", r#"["github.com"]"#).unwrap();
+        
+        // Written while the keyring was away, so only the file holds these
+        keyring.unavailable.store(true, Ordering::SeqCst);
+        storage.set("api_key_openai", "sk-secret").unwrap();
+        storage.set("custom_secret", "value").unwrap();
+        keyring.unavailable.store(false, Ordering::SeqCst);
+        
+        let listed = storage.list_keys().unwrap();
+        assert!(listed.contains(&"git_token_github.com".to_string()), "{:?}", listed);
+        assert!(listed.contains(&WORKSPACE_DB_KEY.to_string()), "{:?}", listed);
+        
+        storage.clear_all().unwrap();
+        assert_eq!(storage.list_keys().unwrap(), vec![WORKSPACE_DB_KEY.to_string()]);
+        assert!(storage.get("git_token_github.com").unwrap().is_none());
+        assert_eq!(storage.get(WORKSPACE_DB_KEY).unwrap().unwrap().value, "db-key");
+        assert_eq!(keyring.entries.lock().unwrap().len(), 1);
+    }
 }
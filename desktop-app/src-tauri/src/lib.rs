@@ -4,6 +4,7 @@
 
 // Core modules
 mod python_bridge;
+mod workflow_artifacts;
 mod database;
 mod models;
 mod repository;
@@ -15,6 +16,8 @@ mod workspace_manager;
 mod workspace_db;
 mod workspace_data;
 mod workspace_commands;
+mod workspace_registry;
+mod maintenance_scheduler;
 
 // Security modules
 mod secure_store;
@@ -49,6 +52,7 @@ mod performance_commands;
 mod memory_monitor;
 mod cost_persistence;
 mod platform_tests;
+mod health_check;
 
 // Phase 2: Non-Dev Friendly
 mod template_engine;
@@ -69,8 +73,11 @@ mod ai_enhancement;
 mod ai_commands;
 mod multi_workspace;
 mod multiworkspace_commands;
+mod workspace_sync;
+mod workspace_access;
 mod enterprise;
 mod enterprise_commands;
+mod report_export;
 mod workflow_commands;
 
 // OpenCode Integration (Phase 2)
@@ -80,10 +87,11 @@ mod opencode_commands;
 // Imports
 // ========================================
 
-use python_bridge::{OutputMessage, PythonBridge, WorkflowArgs};
+use python_bridge::{PythonBridge, WorkflowArgs, WorkflowOutput, WorkflowStatus, WorkflowSummary};
+use workflow_artifacts::StoredArtifact;
 use workflow_commands::WorkflowState;
 use tokio::sync::Mutex;
-use tauri::{Manager, State};
+use tauri::{AppHandle, Emitter, Manager, RunEvent, State};
 use std::sync::Arc;
 
 use database::Database;
@@ -92,6 +100,7 @@ use repository::*;
 use git_manager::GitManager;
 use docker_manager::{DockerManager, ContainerInfo, ContainerStats, ImageInfo, DockerInfo, ContainerLogs, SandboxConfig};
 use workspace_commands::AppState as WorkspaceAppState;
+use workspace_db::WorkspaceDbManager;
 
 // ========================================
 // App State
@@ -101,6 +110,60 @@ struct AppState {
     python_bridge: Mutex<PythonBridge>,
     db: Arc<Database>,
     git_manager: Arc<Mutex<Option<GitManager>>>,
+    /// Sandbox containers started this session
+    sandbox_containers: Mutex<Vec<String>>,
+}
+
+/// App setting that opts in to stopping this session's sandbox containers on exit
+const STOP_SANDBOXES_ON_EXIT_SETTING: &str = "shutdown.stop_sandbox_containers";
+
+/// What `AppState::shutdown` cleaned up, and anything that failed along the way
+#[derive(Debug, Default)]
+struct ShutdownReport {
+    workflows_stopped: usize,
+    containers_stopped: usize,
+    errors: Vec<String>,
+}
+
+impl AppState {
+    /// Stop running workflows, optionally stop sandbox containers, and flush
+    /// databases. Every step runs even if an earlier one fails.
+    async fn shutdown(&self, workspace_db: Option<&WorkspaceDbManager>) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
+
+        let bridge = self.python_bridge.lock().await;
+        for workflow_id in bridge.running_workflows() {
+            match bridge.stop_workflow(&workflow_id) {
+                Ok(()) => report.workflows_stopped += 1,
+                Err(e) => report.errors.push(format!("workflow {}: {}", workflow_id, e)),
+            }
+        }
+        drop(bridge);
+
+        let stop_containers = workspace_db
+            .and_then(|db| db.get_app_setting(STOP_SANDBOXES_ON_EXIT_SETTING).ok().flatten())
+            .is_some_and(|value| value == "true");
+        if stop_containers {
+            let containers = std::mem::take(&mut *self.sandbox_containers.lock().await);
+            for container_id in containers {
+                match DockerManager::stop_container(&container_id) {
+                    Ok(()) => report.containers_stopped += 1,
+                    Err(e) => report.errors.push(format!("container {}: {}", container_id, e)),
+                }
+            }
+        }
+
+        if let Err(e) = cost_persistence::flush_cost_database() {
+            report.errors.push(format!("cost database: {}", e));
+        }
+        if let Some(workspace_db) = workspace_db {
+            if let Err(e) = workspace_db.close_all() {
+                report.errors.push(format!("workspace databases: {}", e));
+            }
+        }
+
+        report
+    }
 }
 
 // ========================================
@@ -118,7 +181,7 @@ async fn run_workflow(
     workflow_id: String,
     workflow_name: String,
     args: WorkflowArgs,
-) -> Result<(), String> {
+) -> Result<String, String> {
     let bridge = state.python_bridge.lock().await;
 
     bridge
@@ -131,7 +194,7 @@ async fn run_workflow(
 async fn get_workflow_output(
     state: State<'_, AppState>,
     workflow_id: String,
-) -> Result<Option<OutputMessage>, String> {
+) -> Result<Option<WorkflowOutput>, String> {
     let bridge = state.python_bridge.lock().await;
 
     bridge.get_output(&workflow_id).map_err(|e| e.to_string())
@@ -145,16 +208,30 @@ async fn stop_workflow(state: State<'_, AppState>, workflow_id: String) -> Resul
 }
 
 #[tauri::command]
-async fn get_workflow_status(state: State<'_, AppState>, workflow_id: String) -> Result<String, String> {
+async fn get_workflow_status(
+    state: State<'_, AppState>,
+    workflow_id: String,
+) -> Result<WorkflowStatus, String> {
     let bridge = state.python_bridge.lock().await;
 
     bridge.get_status(&workflow_id).map_err(|e| e.to_string())
 }
 
+/// Files a workflow produced, with sizes and content types
+#[tauri::command]
+async fn get_workflow_artifacts(
+    state: State<'_, AppState>,
+    workflow_id: String,
+) -> Result<Vec<StoredArtifact>, String> {
+    let bridge = state.python_bridge.lock().await;
+
+    bridge.get_artifacts(&workflow_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn list_workflows(
     state: State<'_, AppState>,
-) -> Result<Vec<serde_json::Value>, String> {
+) -> Result<Vec<WorkflowSummary>, String> {
     let bridge = state.python_bridge.lock().await;
 
     bridge.list_workflows().await.map_err(|e| e.to_string())
@@ -170,6 +247,21 @@ async fn validate_spec(
     bridge.validate_spec(spec_path).await.map_err(|e| e.to_string())
 }
 
+/// Probe Docker, git, the Python bridge, the database and LLM providers in one pass
+#[tauri::command]
+async fn system_health_check(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<health_check::HealthReport, String> {
+    let llm_service = match app.try_state::<Arc<Mutex<chat_commands::ChatState>>>() {
+        Some(chat) => Some(Arc::clone(&chat.lock().await.llm_service)),
+        None => None,
+    };
+    let bridge = state.python_bridge.lock().await;
+
+    Ok(health_check::run_health_check(&bridge, Arc::clone(&state.db), llm_service).await)
+}
+
 // ========================================
 // Main Entry Point
 // ========================================
@@ -186,10 +278,6 @@ pub fn run() {
                 .join("python")
                 .join("bridge.py");
             
-            // Initialize Python bridge with resource path
-            let python_bridge = PythonBridge::with_path(bridge_path)
-                .expect("Failed to initialize Python bridge");
-            
             // Use app data directory for database
             let app_data_dir = app.path().app_data_dir()
                 .expect("Failed to get app data dir");
@@ -198,6 +286,11 @@ pub fn run() {
             std::fs::create_dir_all(&app_data_dir)
                 .expect("Failed to create app data directory");
             
+            // Initialize Python bridge with resource path
+            let python_bridge = PythonBridge::with_path(bridge_path)
+                .expect("Failed to initialize Python bridge")
+                .with_artifacts_root(app_data_dir.join("workflow-artifacts"));
+            
             let db_path = app_data_dir.join("smartspecpro.db");
             let db = Database::new(db_path).expect("Failed to initialize database");
             
@@ -210,9 +303,51 @@ pub fn run() {
                 python_bridge: Mutex::new(python_bridge),
                 db: Arc::new(db),
                 git_manager: Arc::new(Mutex::new(None)),
+                sandbox_containers: Mutex::new(Vec::new()),
+            });
+            
+            // Shrink caches when the app's memory crosses a watermark
+            let ai_state = Arc::new(Mutex::new(ai_commands::AiEnhancementState::new()));
+            let monitor = memory_monitor::get_memory_monitor();
+            monitor.register_evictor(performance::shared_render_cache());
+            monitor.register_evictor(workspace_state.db_manager.clone());
+            monitor.register_evictor(ai_state.clone());
+            let app_handle = app.handle().clone();
+            monitor.set_alert_handler(move |alert| {
+                let _ = app_handle.emit("memory:pressure", alert);
             });
+            memory_monitor::init_memory_monitoring();
+            
+            // Run workspace maintenance in the background when it falls due
+            let maintenance = Arc::new(maintenance_scheduler::MaintenanceScheduler::new(
+                workspace_state.db_manager.clone(),
+            ));
+            tauri::async_runtime::spawn(maintenance.clone().run());
             
+            // Run queued job tasks in the background as Python workflows
+            let mut job_state = job_commands::JobState::new();
+            let runner = Arc::new(job_commands::WorkflowTaskRunner::new(
+                app.handle().clone(),
+                job_state.manager.clone(),
+            ));
+            job_state.start_scheduler(app.handle().clone(), runner, job_manager::SchedulerConfig::default());
+            
+            // Keep the audit chain under app data, anchored in secure storage
+            let audit_store = enterprise::AuditStore::new(
+                app_data_dir.join("audit").join("audit.jsonl"),
+                Box::new(enterprise::SecureStoreAnchor),
+            );
+            let enterprise_state = enterprise_commands::EnterpriseState::with_audit_store(audit_store)
+                .expect("Failed to load audit log");
+            
+            app.manage(Arc::new(Mutex::new(marketplace_commands::MarketplaceState::new(
+                workspace_state.db_manager.clone(),
+            ))));
             app.manage(workspace_state);
+            app.manage(maintenance);
+            app.manage(Arc::new(Mutex::new(job_state)));
+            app.manage(ai_state);
+            app.manage(Arc::new(Mutex::new(enterprise_state)));
             
             // Initialize workflow state for Chat-to-Workflow Bridge
             app.manage(Arc::new(Mutex::new(WorkflowState::new())));
@@ -231,18 +366,25 @@ pub fn run() {
             get_workflow_output,
             stop_workflow,
             get_workflow_status,
+            get_workflow_artifacts,
             list_workflows,
             validate_spec,
+            system_health_check,
             
             // ========================================
             // Chat-to-Workflow Bridge Commands
             // ========================================
             workflow_commands::workflow_detect_intent,
+            workflow_commands::workflow_get_intent_patterns,
+            workflow_commands::workflow_set_intent_patterns,
             workflow_commands::workflow_execute,
             workflow_commands::workflow_stop,
             workflow_commands::workflow_list_running,
             workflow_commands::workflow_approve,
             workflow_commands::workflow_reject,
+            workflow_commands::workflow_set_approval_policy,
+            workflow_commands::workflow_get_approval_policy,
+            workflow_commands::workflow_list_pending_approvals,
             
             // ========================================
             // Workflow Management
@@ -263,6 +405,7 @@ pub fn run() {
             update_execution_status_db,
             delete_execution_db,
             delete_old_executions_db,
+            diff_execution_configs,
             
             // ========================================
             // Config Management
@@ -272,6 +415,15 @@ pub fn run() {
             list_configs_by_workflow_db,
             delete_config_db,
             
+            // ========================================
+            // Config Templates
+            // ========================================
+            create_config_template_db,
+            update_config_template_db,
+            list_config_templates_db,
+            delete_config_template_db,
+            apply_config_template,
+            
             // ========================================
             // Database Stats
             // ========================================
@@ -290,6 +442,10 @@ pub fn run() {
             git_push_branch,
             git_has_changes,
             git_list_branches,
+            git_diff,
+            git_status,
+            git_stash,
+            git_stash_pop,
             
             // ========================================
             // Secure Store
@@ -297,6 +453,12 @@ pub fn run() {
             secure_store::set_proxy_token,
             secure_store::get_proxy_token,
             secure_store::delete_proxy_token,
+            secure_store::set_workspace_db_key,
+            secure_store::has_workspace_db_key,
+            secure_store::set_git_token,
+            secure_store::has_git_token,
+            secure_store::delete_git_token,
+            keyring_fallback::get_security_info,
             
             // ========================================
             // Docker Management
@@ -316,6 +478,13 @@ pub fn run() {
             docker_exec_command,
             docker_prune_containers,
             docker_prune_images,
+            docker_reconcile_workspaces,
+            docker_reconcile_workspace,
+            docker_compose_up,
+            docker_compose_down,
+            docker_compose_ps,
+            docker_pull_image_streaming,
+            docker_build_image_streaming,
             
             // ========================================
             // Workspace Management
@@ -326,18 +495,40 @@ pub fn run() {
             workspace_commands::get_recent_workspaces,
             workspace_commands::update_workspace,
             workspace_commands::delete_workspace,
+            workspace_commands::restore_deleted_workspace,
+            workspace_commands::list_trashed_workspaces,
+            workspace_commands::purge_trash,
             workspace_commands::open_workspace,
             workspace_commands::close_workspace,
             workspace_commands::get_workspace_stats,
+            workspace_commands::create_workspace_full,
+            workspace_commands::delete_workspace_full,
+            workspace_commands::get_workspace_full,
+            workspace_commands::reconcile_workspaces,
             
             // ========================================
             // Workspace Maintenance
             // ========================================
             workspace_commands::backup_workspace,
             workspace_commands::restore_workspace,
+            workspace_commands::backup_workspace_incremental,
+            workspace_commands::list_backup_generations,
+            workspace_commands::restore_backup_generation,
+            workspace_commands::export_workspace_archive,
+            workspace_commands::import_workspace_archive,
             workspace_commands::vacuum_workspace,
             workspace_commands::cleanup_expired_memory,
             workspace_commands::optimize_workspace,
+            workspace_commands::get_busy_retry_config,
+            workspace_commands::set_busy_retry_config,
+            workspace_commands::get_max_open_workspaces,
+            workspace_commands::set_max_open_workspaces,
+            workspace_commands::workspace_rebuild_search_index,
+            workspace_commands::encrypt_workspace,
+            maintenance_scheduler::maintenance_get_config,
+            maintenance_scheduler::maintenance_set_config,
+            maintenance_scheduler::maintenance_set_enabled,
+            maintenance_scheduler::maintenance_run_now,
             
             // ========================================
             // App Settings
@@ -350,6 +541,7 @@ pub fn run() {
             // ========================================
             workspace_commands::create_job,
             workspace_commands::get_job,
+            workspace_commands::job_get_progress,
             workspace_commands::list_jobs,
             workspace_commands::update_job_status,
             workspace_commands::delete_job,
@@ -360,6 +552,10 @@ pub fn run() {
             workspace_commands::create_task,
             workspace_commands::list_tasks,
             workspace_commands::update_task_status,
+            workspace_commands::reorder_tasks,
+            workspace_commands::add_task_dependency,
+            workspace_commands::remove_task_dependency,
+            workspace_commands::get_task_graph,
             
             // ========================================
             // Chat Sessions
@@ -368,6 +564,7 @@ pub fn run() {
             workspace_commands::list_chat_sessions,
             workspace_commands::add_chat_message,
             workspace_commands::get_chat_messages,
+            workspace_commands::chat_search_messages,
             
             // ========================================
             // Knowledge
@@ -375,6 +572,13 @@ pub fn run() {
             workspace_commands::create_knowledge,
             workspace_commands::search_knowledge,
             workspace_commands::list_knowledge,
+            workspace_commands::update_knowledge,
+            workspace_commands::get_knowledge_history,
+            workspace_commands::revert_knowledge,
+            workspace_commands::get_tag_facets,
+            workspace_commands::list_knowledge_by_tag,
+            workspace_commands::find_duplicate_knowledge,
+            workspace_commands::merge_knowledge,
             
             // ========================================
             // Memory
@@ -382,6 +586,7 @@ pub fn run() {
             workspace_commands::create_memory_long,
             workspace_commands::get_relevant_memories,
             workspace_commands::increment_memory_access,
+            workspace_commands::list_memory_long_by_tag,
             
             // ========================================
             // Chat Commands (Phase 1.2)
@@ -401,12 +606,14 @@ pub fn run() {
             chat_commands::chat_set_model,
             chat_commands::chat_get_current_model,
             chat_commands::chat_estimate_tokens,
-            chat_commands::chat_get_usage_stats,
+            chat_commands::get_fallback_policy,
+            chat_commands::set_fallback_policy,
+            cost_persistence::chat_get_usage_stats,
             
             // ========================================
             // CLI Commands (Phase 1.3)
             // ========================================
-            cli_commands::cli_execute,
+            cli_commands::cli_execute_command,
             cli_commands::cli_get_history,
             cli_commands::cli_clear_history,
             cli_commands::cli_get_suggestions,
@@ -418,6 +625,8 @@ pub fn run() {
             cli_commands::cli_apply_diff,
             cli_commands::cli_search_files,
             cli_commands::cli_get_file_tree,
+            cli_commands::cli_set_sandbox,
+            cli_commands::cli_get_execution_target,
             
             // ========================================
             // Job Commands (Phase 1.4)
@@ -432,8 +641,16 @@ pub fn run() {
             job_commands::job_get_tasks,
             job_commands::job_create_branch,
             job_commands::job_get_branches,
-            job_commands::job_merge_branch,
+            job_commands::branch_merge,
+            job_commands::branch_merge_abort,
+            job_commands::branch_merge_continue,
             job_commands::job_get_stats,
+            job_commands::job_pause,
+            job_commands::job_resume,
+            job_commands::job_set_workspace,
+            job_commands::job_scheduler_shutdown,
+            job_commands::task_add_dependency,
+            job_commands::task_remove_dependency,
             
             // ========================================
             // Performance Commands (Phase 1.5)
@@ -443,6 +660,9 @@ pub fn run() {
             performance_commands::perf_clear_cache,
             performance_commands::perf_optimize,
             performance_commands::perf_get_recommendations,
+            performance_commands::perf_resize_render_cache,
+            memory_monitor::set_memory_thresholds,
+            memory_monitor::relieve_memory_pressure,
             
             // ========================================
             // Template Commands (Phase 2.1)
@@ -451,6 +671,11 @@ pub fn run() {
             template_commands::template_get,
             template_commands::template_create_project,
             template_commands::template_validate,
+            template_commands::template_validate_config,
+            template_commands::template_refresh,
+            template_commands::template_preview_project,
+            template_commands::template_lint,
+            template_commands::template_generate_project_streaming,
             template_commands::template_get_categories,
             
             // ========================================
@@ -463,7 +688,18 @@ pub fn run() {
             spec_commands::spec_add_component,
             spec_commands::spec_remove_component,
             spec_commands::spec_connect_components,
+            spec_commands::spec_group_components,
+            spec_commands::spec_ungroup,
+            spec_commands::spec_move_group,
+            spec_commands::spec_update_group,
             spec_commands::spec_export,
+            spec_commands::spec_undo,
+            spec_commands::spec_redo,
+            spec_commands::spec_get_history_state,
+            spec_commands::spec_auto_layout,
+            spec_commands::spec_align_components,
+            spec_commands::spec_distribute_components,
+            spec_commands::spec_validate,
             spec_commands::spec_import,
             spec_commands::spec_get_component_library,
             
@@ -475,15 +711,23 @@ pub fn run() {
             dashboard_commands::dashboard_get_timeline,
             dashboard_commands::dashboard_get_burndown,
             dashboard_commands::dashboard_export_report,
+            dashboard_commands::dashboard_get_workspace_totals,
             
             // ========================================
             // Collaboration Commands (Phase 2.4)
             // ========================================
             collab_commands::collab_add_comment,
             collab_commands::collab_get_comments,
+            collab_commands::collab_add_reply,
+            collab_commands::collab_edit_comment,
+            collab_commands::collab_delete_comment,
+            collab_commands::collab_resolve_comment,
+            collab_commands::collab_unresolve_comment,
             collab_commands::collab_add_reaction,
             collab_commands::collab_request_review,
             collab_commands::collab_submit_review,
+            collab_commands::collab_set_review_policy,
+            collab_commands::collab_get_review_summary,
             collab_commands::collab_get_notifications,
             collab_commands::collab_mark_notification_read,
             collab_commands::collab_get_activity_feed,
@@ -493,11 +737,19 @@ pub fn run() {
             // ========================================
             plugin_commands::plugin_list,
             plugin_commands::plugin_install,
+            plugin_commands::plugin_preview_install,
+            plugin_commands::plugin_install_from_dir,
+            plugin_commands::plugin_grant_permissions,
             plugin_commands::plugin_uninstall,
             plugin_commands::plugin_enable,
             plugin_commands::plugin_disable,
             plugin_commands::plugin_get_config,
             plugin_commands::plugin_set_config,
+            plugin_commands::plugin_invoke,
+            plugin_commands::plugin_get_limits,
+            plugin_commands::plugin_set_limits,
+            plugin_commands::plugin_get_permission_allowlist,
+            plugin_commands::plugin_set_permission_allowlist,
             
             // ========================================
             // Marketplace Commands (Phase 3.2)
@@ -507,6 +759,11 @@ pub fn run() {
             marketplace_commands::marketplace_install,
             marketplace_commands::marketplace_get_categories,
             marketplace_commands::marketplace_get_featured,
+            marketplace_commands::marketplace_set_index_url,
+            marketplace_commands::marketplace_set_allow_unverified,
+            marketplace_commands::marketplace_list_trusted_keys,
+            marketplace_commands::marketplace_add_trusted_key,
+            marketplace_commands::marketplace_remove_trusted_key,
             
             // ========================================
             // AI Commands (Phase 3.3)
@@ -515,7 +772,9 @@ pub fn run() {
             ai_commands::ai_analyze_code,
             ai_commands::ai_predict_bugs,
             ai_commands::ai_generate_docs,
+            ai_commands::ai_analyze_quality,
             ai_commands::ai_get_quality_report,
+            ai_commands::ai_get_quality_trend,
             
             // ========================================
             // Multi-workspace Commands (Phase 3.4)
@@ -525,18 +784,33 @@ pub fn run() {
             multiworkspace_commands::mw_sync_workspace,
             multiworkspace_commands::mw_create_team_workspace,
             multiworkspace_commands::mw_share_workspace,
+            multiworkspace_commands::mw_authorize_invite,
+            multiworkspace_commands::mw_list_members,
+            multiworkspace_commands::mw_add_member,
+            multiworkspace_commands::mw_remove_member,
+            multiworkspace_commands::mw_update_member_role,
+            multiworkspace_commands::mw_search_all,
+            multiworkspace_commands::mw_list_sync_conflicts,
+            multiworkspace_commands::mw_resolve_sync_conflict,
             
             // ========================================
             // Enterprise Commands (Phase 3.5)
             // ========================================
+            enterprise_commands::ent_sign_in,
+            enterprise_commands::ent_sign_out,
+            enterprise_commands::ent_get_session,
+            enterprise_commands::ent_bootstrap_admin,
             enterprise_commands::ent_configure_sso,
             enterprise_commands::ent_get_sso_config,
             enterprise_commands::ent_create_role,
+            enterprise_commands::ent_set_role_parent,
             enterprise_commands::ent_assign_role,
             enterprise_commands::ent_get_permissions,
             enterprise_commands::ent_get_audit_logs,
             enterprise_commands::ent_export_audit_logs,
+            enterprise_commands::ent_verify_audit_integrity,
             enterprise_commands::ent_get_compliance_report,
+            enterprise_commands::ent_export_report,
             
             // ========================================
             // Security Commands
@@ -551,6 +825,7 @@ pub fn run() {
             // Rate Limiter Commands
             // ========================================
             rate_limiter::rate_limit_check,
+            rate_limiter::rate_limit_set_limit,
             rate_limiter::rate_limit_get_status,
             rate_limiter::rate_limit_reset,
             
@@ -586,8 +861,23 @@ pub fn run() {
             opencode_commands::opencode_generate_cli_config,
             opencode_commands::opencode_get_connection_info,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                if let Some(jobs) = app.try_state::<Arc<Mutex<job_commands::JobState>>>() {
+                    if let Err(e) = tauri::async_runtime::block_on(async { jobs.lock().await.shutdown().await }) {
+                        eprintln!("Shutdown: failed to save job state: {}", e);
+                    }
+                }
+                let workspace_state = app.try_state::<WorkspaceAppState>();
+                let workspace_db = workspace_state.as_ref().map(|state| state.db_manager.as_ref());
+                let report = tauri::async_runtime::block_on(app.state::<AppState>().shutdown(workspace_db));
+                for error in &report.errors {
+                    eprintln!("Shutdown: {}", error);
+                }
+            }
+        });
 }
 
 // ========================================
@@ -661,9 +951,10 @@ async fn delete_workflow_db(
 async fn create_execution_db(
     state: State<'_, AppState>,
     workflow_id: String,
+    workflow_name: String,
     config_snapshot: Option<serde_json::Value>,
 ) -> Result<Execution, String> {
-    let execution = Execution::new(workflow_id, config_snapshot);
+    let execution = Execution::new(workflow_id, workflow_name).with_config_snapshot(config_snapshot);
     let repo = ExecutionRepository::new(state.db.get_connection());
     
     repo.create(&execution).map_err(|e| e.to_string())?;
@@ -684,9 +975,9 @@ async fn get_execution_db(
 async fn list_executions_db(
     state: State<'_, AppState>,
     filter: ExecutionFilter,
-) -> Result<Vec<Execution>, String> {
+) -> Result<Page<Execution>, String> {
     let repo = ExecutionRepository::new(state.db.get_connection());
-    repo.list(&filter).map_err(|e| e.to_string())
+    repo.list_page(&filter).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -710,13 +1001,32 @@ async fn delete_execution_db(
     repo.delete(&id).map_err(|e| e.to_string())
 }
 
+/// What changed in the workflow config between two executions
+#[tauri::command]
+async fn diff_execution_configs(
+    state: State<'_, AppState>,
+    id_a: String,
+    id_b: String,
+) -> Result<ConfigDiff, String> {
+    let repo = ExecutionRepository::new(state.db.get_connection());
+    repo.diff_configs(&id_a, &id_b).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn delete_old_executions_db(
     state: State<'_, AppState>,
     days: i64,
 ) -> Result<usize, String> {
     let repo = ExecutionRepository::new(state.db.get_connection());
-    repo.delete_older_than(days).map_err(|e| e.to_string())
+    let deleted = repo.delete_old(days).map_err(|e| e.to_string())?;
+
+    // Artifacts follow the same retention as the executions that made them
+    let bridge = state.python_bridge.lock().await;
+    if let Err(e) = bridge.delete_artifacts_older_than(days) {
+        eprintln!("Failed to delete old workflow artifacts: {}", e);
+    }
+
+    Ok(deleted)
 }
 
 // ========================================
@@ -767,6 +1077,69 @@ async fn delete_config_db(
     repo.delete(&workflow_id, &key).map_err(|e| e.to_string())
 }
 
+// ========================================
+// Config Template Commands
+// ========================================
+
+#[tauri::command]
+async fn create_config_template_db(
+    state: State<'_, AppState>,
+    name: String,
+    description: Option<String>,
+    variables: Vec<ConfigTemplateVariable>,
+    entries: Vec<ConfigTemplateEntry>,
+) -> Result<ConfigTemplate, String> {
+    let template = ConfigTemplate::new(name, description, variables, entries);
+    let repo = ConfigTemplateRepository::new(state.db.get_connection());
+
+    repo.save(&template).map_err(|e| e.to_string())?;
+
+    Ok(template)
+}
+
+#[tauri::command]
+async fn update_config_template_db(
+    state: State<'_, AppState>,
+    mut template: ConfigTemplate,
+) -> Result<ConfigTemplate, String> {
+    template.updated_at = chrono::Utc::now().timestamp();
+    let repo = ConfigTemplateRepository::new(state.db.get_connection());
+
+    repo.save(&template).map_err(|e| e.to_string())?;
+
+    Ok(template)
+}
+
+#[tauri::command]
+async fn list_config_templates_db(
+    state: State<'_, AppState>,
+) -> Result<Vec<ConfigTemplate>, String> {
+    let repo = ConfigTemplateRepository::new(state.db.get_connection());
+    repo.list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_config_template_db(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    let repo = ConfigTemplateRepository::new(state.db.get_connection());
+    repo.delete(&id).map_err(|e| e.to_string())
+}
+
+/// Resolve a template's placeholders with `vars` and upsert the resulting
+/// configs into the workflow
+#[tauri::command]
+async fn apply_config_template(
+    state: State<'_, AppState>,
+    workflow_id: String,
+    template_id: String,
+    vars: std::collections::HashMap<String, String>,
+) -> Result<Vec<Config>, String> {
+    let repo = ConfigTemplateRepository::new(state.db.get_connection());
+    repo.apply(&workflow_id, &template_id, &vars).map_err(|e| format!("{:#}", e))
+}
+
 // ========================================
 // Database Stats Commands
 // ========================================
@@ -864,11 +1237,13 @@ async fn git_push_branch(
     state: State<'_, AppState>,
     branch_name: String,
     remote: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), git_manager::PushError> {
     let git = state.git_manager.lock().await;
-    let manager = git.as_ref().ok_or("Git not initialized")?;
+    let manager = git.as_ref().ok_or(git_manager::PushError::Failed {
+        message: "Git not initialized".to_string(),
+    })?;
     let remote_name = remote.unwrap_or_else(|| "origin".to_string());
-    manager.push_branch(&branch_name, &remote_name).map_err(|e| e.to_string())
+    manager.push_branch(&branch_name, &remote_name, secure_store::load_git_token)
 }
 
 #[tauri::command]
@@ -889,6 +1264,45 @@ async fn git_list_branches(
     manager.list_branches().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn git_diff(
+    state: State<'_, AppState>,
+    staged: bool,
+) -> Result<Vec<git_manager::FileDiff>, String> {
+    let git = state.git_manager.lock().await;
+    let manager = git.as_ref().ok_or("Git not initialized")?;
+    manager.diff(staged).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn git_status(
+    state: State<'_, AppState>,
+) -> Result<git_manager::RepoStatus, String> {
+    let git = state.git_manager.lock().await;
+    let manager = git.as_ref().ok_or("Git not initialized")?;
+    manager.status().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn git_stash(
+    state: State<'_, AppState>,
+    message: Option<String>,
+) -> Result<Option<String>, String> {
+    let git = state.git_manager.lock().await;
+    let manager = git.as_ref().ok_or("Git not initialized")?;
+    manager.stash_save(message.as_deref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn git_stash_pop(
+    state: State<'_, AppState>,
+    index: Option<usize>,
+) -> Result<(), String> {
+    let git = state.git_manager.lock().await;
+    let manager = git.as_ref().ok_or("Git not initialized")?;
+    manager.stash_pop(index).map_err(|e| e.to_string())
+}
+
 // ========================================
 // Docker Commands
 // ========================================
@@ -949,13 +1363,22 @@ async fn docker_remove_image(image_id: String, force: bool) -> Result<(), String
 }
 
 #[tauri::command]
-async fn docker_create_sandbox(config: SandboxConfig) -> Result<String, String> {
-    DockerManager::create_sandbox(config).await.map_err(|e| e.to_string())
+async fn docker_create_sandbox(state: State<'_, AppState>, config: SandboxConfig) -> Result<String, String> {
+    let container_id = tokio::task::spawn_blocking(move || DockerManager::create_sandbox(&config))
+        .await
+        .map_err(|e| e.to_string())??;
+    state.sandbox_containers.lock().await.push(container_id.clone());
+    Ok(container_id)
 }
 
 #[tauri::command]
 async fn docker_exec_command(container_id: String, command: Vec<String>) -> Result<String, String> {
-    DockerManager::exec_command(&container_id, command).await.map_err(|e| e.to_string())
+    let output = DockerManager::exec_command(&container_id, &command, None, None)?;
+    if output.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
 }
 
 #[tauri::command]
@@ -967,3 +1390,131 @@ async fn docker_prune_containers() -> Result<u64, String> {
 async fn docker_prune_images() -> Result<u64, String> {
     DockerManager::prune_images().await.map_err(|e| e.to_string())
 }
+
+/// Reconcile branch container statuses with Docker; the frontend polls this
+#[tauri::command]
+async fn docker_reconcile_workspaces() -> Result<workspace_manager::WorkspaceList, String> {
+    tokio::task::spawn_blocking(|| workspace_manager::WorkspaceManager::new()?.list_workspaces())
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn docker_reconcile_workspace(workspace: String) -> Result<workspace_manager::Workspace, String> {
+    tokio::task::spawn_blocking(move || workspace_manager::WorkspaceManager::new()?.reconcile_status(&workspace))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn docker_compose_up(workspace: String, branch: String) -> Result<workspace_manager::BranchConfig, String> {
+    tokio::task::spawn_blocking(move || workspace_manager::WorkspaceManager::new()?.compose_up(&workspace, &branch))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn docker_compose_down(workspace: String, branch: String) -> Result<workspace_manager::BranchConfig, String> {
+    tokio::task::spawn_blocking(move || workspace_manager::WorkspaceManager::new()?.compose_down(&workspace, &branch))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn docker_compose_ps(
+    workspace: String,
+    branch: String,
+) -> Result<Vec<workspace_manager::ComposeServiceState>, String> {
+    tokio::task::spawn_blocking(move || workspace_manager::WorkspaceManager::new()?.compose_ps(&workspace, &branch))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Payload of `docker:progress` events; `request_id` lets the frontend route
+/// updates when several pulls or builds run at once
+#[derive(Clone, serde::Serialize)]
+struct DockerProgressEvent {
+    request_id: String,
+    progress: docker_manager::ImageProgress,
+}
+
+#[tauri::command]
+async fn docker_pull_image_streaming(
+    app: AppHandle,
+    image: String,
+    request_id: String,
+) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        DockerManager::pull_image_streaming(&image, |progress| {
+            let _ = app.emit("docker:progress", DockerProgressEvent { request_id: request_id.clone(), progress });
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn docker_build_image_streaming(
+    app: AppHandle,
+    context_dir: String,
+    tag: String,
+    dockerfile: Option<String>,
+    request_id: String,
+) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        DockerManager::build_image(
+            std::path::Path::new(&context_dir),
+            &tag,
+            dockerfile.as_deref().map(std::path::Path::new),
+            |progress| {
+                let _ = app.emit("docker:progress", DockerProgressEvent { request_id: request_id.clone(), progress });
+            },
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_state(dir: &std::path::Path) -> AppState {
+        let script = dir.join("bridge.py");
+        std::fs::write(&script, "import time\ntime.sleep(30)\n").unwrap();
+
+        AppState {
+            python_bridge: Mutex::new(PythonBridge::with_path(script).unwrap()),
+            db: Arc::new(Database::new(dir.join("app.db")).unwrap()),
+            git_manager: Arc::new(Mutex::new(None)),
+            sandbox_containers: Mutex::new(Vec::new()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_workflows_and_keeps_containers_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = test_state(dir.path());
+        let args = WorkflowArgs {
+            spec_id: "spec".to_string(),
+            category: "core".to_string(),
+            mode: "normal".to_string(),
+            platform: "claude".to_string(),
+            workspace_path: None,
+        };
+        {
+            let bridge = state.python_bridge.lock().await;
+            bridge.spawn_workflow("wf-1".to_string(), "plan".to_string(), args.clone()).await.unwrap();
+            bridge.spawn_workflow("wf-2".to_string(), "plan".to_string(), args).await.unwrap();
+        }
+        state.sandbox_containers.lock().await.push("sandbox-1".to_string());
+
+        let report = state.shutdown(None).await;
+
+        assert_eq!(report.workflows_stopped, 2);
+        assert_eq!(report.containers_stopped, 0);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert!(state.python_bridge.lock().await.running_workflows().is_empty());
+        assert_eq!(*state.sandbox_containers.lock().await, vec!["sandbox-1".to_string()]);
+    }
+}
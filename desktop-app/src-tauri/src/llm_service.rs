@@ -10,8 +10,18 @@
 use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock};
+
+use crate::workspace_db::WorkspaceDbManager;
+
+/// Upper bound on a single non-streaming completion request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// App setting key prefix for per-mode model selections
+const MODE_MODEL_SETTING_PREFIX: &str = "model.mode.";
 
 // ============================================
 // LLM Provider Types
@@ -197,6 +207,87 @@ impl LlmModel {
     }
 }
 
+/// What a streamed completion produced
+struct StreamedReply {
+    /// Usage the provider reported, if it sent any
+    usage: Option<TokenUsage>,
+    completion: String,
+}
+
+/// Read a streaming completion's server-sent events, passing each chunk to
+/// `on_chunk`, until `[DONE]` or the end of the body. Returns `None` once
+/// `cancel` is notified.
+async fn read_sse_stream<S, B, E>(
+    stream: S,
+    on_chunk: &impl Fn(StreamChunk),
+    cancel: &Notify,
+) -> Result<Option<StreamedReply>>
+where
+    S: futures::Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    use futures::StreamExt;
+    
+    let mut reply = StreamedReply { usage: None, completion: String::new() };
+    // Raw bytes: events, and UTF-8 characters, can be split across network chunks
+    let mut pending: Vec<u8> = Vec::new();
+    futures::pin_mut!(stream);
+    
+    loop {
+        let chunk = tokio::select! {
+            chunk = stream.next() => chunk,
+            _ = cancel.notified() => return Ok(None),
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        pending.extend_from_slice(chunk.context("Failed to read stream chunk")?.as_ref());
+        
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim_end().strip_prefix("data: ") else {
+                continue;
+            };
+            if data == "[DONE]" {
+                return Ok(Some(reply));
+            }
+            
+            if let Ok(stream_chunk) = serde_json::from_str::<StreamChunk>(data) {
+                for choice in &stream_chunk.choices {
+                    reply.completion.push_str(choice.delta.content.as_deref().unwrap_or_default());
+                }
+                if stream_chunk.usage.is_some() {
+                    reply.usage = stream_chunk.usage.clone();
+                }
+                on_chunk(stream_chunk);
+            }
+        }
+    }
+    
+    Ok(Some(reply))
+}
+
+/// Final usage for a streamed reply. Estimates stand in for whatever the
+/// provider left out, so a stream never records zero prompt tokens.
+fn settle_stream_usage(
+    reported: Option<TokenUsage>,
+    prompt_estimate: i32,
+    completion_estimate: i32,
+) -> TokenUsage {
+    let mut usage = reported.unwrap_or(TokenUsage {
+        prompt_tokens: 0,
+        completion_tokens: completion_estimate,
+        total_tokens: completion_estimate,
+    });
+    if usage.prompt_tokens <= 0 {
+        usage.prompt_tokens = prompt_estimate;
+        usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+    }
+    usage
+}
+
 // ============================================
 // API Request/Response Types
 // ============================================
@@ -213,6 +304,14 @@ pub struct ChatRequest {
     pub stream: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Tool>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+}
+
+/// Asks the provider to report token usage on the final stream chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamOptions {
+    pub include_usage: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -280,6 +379,9 @@ pub struct StreamChunk {
     pub id: String,
     pub model: String,
     pub choices: Vec<StreamChoice>,
+    /// Sent by the provider on the final chunk only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -350,6 +452,70 @@ impl Default for LlmServiceConfig {
     }
 }
 
+// ============================================
+// LLM Errors
+// ============================================
+
+/// Provider failures that callers can act on. Carried inside `anyhow::Error`;
+/// use `LlmError::classify` to get it back out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LlmError {
+    MissingApiKey(String),
+    RateLimited(String),
+    ProviderError { status: u16, message: String },
+    Timeout(String),
+}
+
+impl LlmError {
+    pub fn from_status(status: u16, message: String) -> Self {
+        if status == 429 {
+            LlmError::RateLimited(message)
+        } else {
+            LlmError::ProviderError { status, message }
+        }
+    }
+    
+    /// Whether another attempt, possibly on a different model, may succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            LlmError::RateLimited(_) | LlmError::Timeout(_) => true,
+            LlmError::ProviderError { status, .. } => *status >= 500,
+            LlmError::MissingApiKey(_) => false,
+        }
+    }
+    
+    pub fn classify(error: &anyhow::Error) -> Option<&LlmError> {
+        error.chain().find_map(|e| e.downcast_ref::<LlmError>())
+    }
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmError::MissingApiKey(provider) => write!(f, "No API key configured for {}", provider),
+            LlmError::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
+            LlmError::ProviderError { status, message } => write!(f, "Provider error ({}): {}", status, message),
+            LlmError::Timeout(msg) => write!(f, "Request timed out: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+fn send_error(provider: &str, error: reqwest::Error) -> anyhow::Error {
+    if error.is_timeout() {
+        LlmError::Timeout(format!("{}: {}", provider, error)).into()
+    } else {
+        anyhow::Error::new(error).context(format!("Failed to send request to {}", provider))
+    }
+}
+
+async fn status_error(provider: &str, response: reqwest::Response) -> anyhow::Error {
+    let status = response.status().as_u16();
+    let error_text = response.text().await.unwrap_or_default();
+    LlmError::from_status(status, format!("{} API error: {}", provider, error_text)).into()
+}
+
 // ============================================
 // LLM Service
 // ============================================
@@ -358,14 +524,28 @@ pub struct LlmService {
     config: Arc<RwLock<LlmServiceConfig>>,
     http_client: reqwest::Client,
     selected_models: Arc<RwLock<HashMap<String, String>>>, // mode -> model_id
+    db_manager: Arc<WorkspaceDbManager>,
 }
 
 impl LlmService {
-    pub fn new(config: LlmServiceConfig) -> Self {
+    /// Create the service, restoring per-mode model selections saved in the
+    /// app database
+    pub fn new(config: LlmServiceConfig, db_manager: Arc<WorkspaceDbManager>) -> Self {
+        let selected_models = match db_manager.list_app_settings(MODE_MODEL_SETTING_PREFIX) {
+            Ok(settings) => settings.into_iter()
+                .map(|(key, model_id)| (key[MODE_MODEL_SETTING_PREFIX.len()..].to_string(), model_id))
+                .collect(),
+            Err(e) => {
+                eprintln!("Failed to load saved model selections: {}", e);
+                HashMap::new()
+            }
+        };
+        
         Self {
             config: Arc::new(RwLock::new(config)),
             http_client: reqwest::Client::new(),
-            selected_models: Arc::new(RwLock::new(HashMap::new())),
+            selected_models: Arc::new(RwLock::new(selected_models)),
+            db_manager,
         }
     }
     
@@ -382,19 +562,33 @@ impl LlmService {
     // Model Selection
     // ========================================
     
-    pub async fn set_model_for_mode(&self, mode: &str, model_id: &str) {
+    pub async fn set_model_for_mode(&self, mode: &str, model_id: &str) -> Result<()> {
+        self.db_manager.set_app_setting(&format!("{}{}", MODE_MODEL_SETTING_PREFIX, mode), model_id)
+            .context("Failed to save model selection")?;
+        
         let mut models = self.selected_models.write().await;
         models.insert(mode.to_string(), model_id.to_string());
+        Ok(())
     }
     
     pub async fn get_model_for_mode(&self, mode: &str) -> String {
-        let models = self.selected_models.read().await;
-        models.get(mode)
-            .cloned()
-            .unwrap_or_else(|| {
-                let config = futures::executor::block_on(self.config.read());
-                config.default_model.clone()
-            })
+        let selected = self.selected_models.read().await.get(mode).cloned();
+        if let Some(model_id) = selected {
+            return model_id;
+        }
+        
+        // Picked up here if another service instance saved it after we loaded
+        match self.db_manager.get_app_setting(&format!("{}{}", MODE_MODEL_SETTING_PREFIX, mode)) {
+            Ok(Some(model_id)) => {
+                self.selected_models.write().await.insert(mode.to_string(), model_id.clone());
+                model_id
+            }
+            Ok(None) => self.config.read().await.default_model.clone(),
+            Err(e) => {
+                eprintln!("Failed to read saved model selection: {}", e);
+                self.config.read().await.default_model.clone()
+            }
+        }
     }
     
     pub fn get_available_models(&self) -> Vec<LlmModel> {
@@ -418,12 +612,14 @@ impl LlmService {
         // Try OpenRouter first
         let openrouter = config.providers.iter()
             .find(|p| p.provider == LlmProvider::OpenRouter && p.enabled);
+        let mut last_error = None;
         
         if let Some(provider) = openrouter {
             match self.call_openrouter(&provider.api_key, &model, messages.clone(), temperature, max_tokens, &config.openrouter_settings).await {
                 Ok(response) => return Ok(response),
                 Err(e) if config.fallback_enabled => {
                     eprintln!("OpenRouter failed, trying fallback: {}", e);
+                    last_error = Some(e);
                 }
                 Err(e) => return Err(e),
             }
@@ -442,7 +638,7 @@ impl LlmService {
             }
         }
         
-        Err(anyhow!("No available LLM provider"))
+        Err(last_error.unwrap_or_else(|| anyhow!("No available LLM provider")))
     }
     
     async fn call_openrouter(
@@ -454,6 +650,10 @@ impl LlmService {
         max_tokens: Option<i32>,
         settings: &OpenRouterSettings,
     ) -> Result<ChatResponse> {
+        if api_key.trim().is_empty() {
+            return Err(LlmError::MissingApiKey(LlmProvider::OpenRouter.as_str().to_string()).into());
+        }
+        
         let request = ChatRequest {
             model: model.to_string(),
             messages,
@@ -461,6 +661,7 @@ impl LlmService {
             max_tokens,
             stream: Some(false),
             tools: None,
+            stream_options: None,
         };
         
         let response = self.http_client
@@ -468,14 +669,14 @@ impl LlmService {
             .header("Authorization", format!("Bearer {}", api_key))
             .header("HTTP-Referer", &settings.app_url)
             .header("X-Title", &settings.app_name)
+            .timeout(REQUEST_TIMEOUT)
             .json(&request)
             .send()
             .await
-            .context("Failed to send request to OpenRouter")?;
+            .map_err(|e| send_error("OpenRouter", e))?;
         
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow!("OpenRouter API error: {}", error_text));
+            return Err(status_error("OpenRouter", response).await);
         }
         
         let chat_response: ChatResponse = response.json().await
@@ -492,6 +693,10 @@ impl LlmService {
         temperature: Option<f64>,
         max_tokens: Option<i32>,
     ) -> Result<ChatResponse> {
+        if provider.api_key.trim().is_empty() {
+            return Err(LlmError::MissingApiKey(provider.provider.as_str().to_string()).into());
+        }
+        
         let request = ChatRequest {
             model: model.split('/').last().unwrap_or(model).to_string(),
             messages,
@@ -499,11 +704,13 @@ impl LlmService {
             max_tokens,
             stream: Some(false),
             tools: None,
+            stream_options: None,
         };
         
         let mut req_builder = self.http_client
             .post(format!("{}/chat/completions", provider.provider.base_url()))
             .header("Authorization", format!("Bearer {}", provider.api_key))
+            .timeout(REQUEST_TIMEOUT)
             .json(&request);
         
         // Anthropic uses different header
@@ -513,11 +720,10 @@ impl LlmService {
         }
         
         let response = req_builder.send().await
-            .context("Failed to send request to provider")?;
+            .map_err(|e| send_error(provider.provider.as_str(), e))?;
         
         if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Provider API error: {}", error_text));
+            return Err(status_error(provider.provider.as_str(), response).await);
         }
         
         let chat_response: ChatResponse = response.json().await
@@ -530,6 +736,8 @@ impl LlmService {
     // Streaming Chat
     // ========================================
     
+    /// Stream a completion, passing each chunk to `on_chunk`. Returns `None`
+    /// if `cancel` is notified before the provider finishes.
     pub async fn chat_stream(
         &self,
         messages: Vec<ChatMessage>,
@@ -537,7 +745,8 @@ impl LlmService {
         temperature: Option<f64>,
         max_tokens: Option<i32>,
         on_chunk: impl Fn(StreamChunk) + Send + 'static,
-    ) -> Result<TokenUsage> {
+        cancel: &Notify,
+    ) -> Result<Option<TokenUsage>> {
         let config = self.config.read().await;
         let model = model_id.unwrap_or(&config.default_model).to_string();
         
@@ -553,6 +762,7 @@ impl LlmService {
                 max_tokens,
                 &config.openrouter_settings,
                 on_chunk,
+                cancel,
             ).await;
         }
         
@@ -568,7 +778,11 @@ impl LlmService {
         max_tokens: Option<i32>,
         settings: &OpenRouterSettings,
         on_chunk: impl Fn(StreamChunk) + Send + 'static,
-    ) -> Result<TokenUsage> {
+        cancel: &Notify,
+    ) -> Result<Option<TokenUsage>> {
+        let prompt_tokens = messages.iter()
+            .map(|m| self.estimate_tokens(&m.content))
+            .sum();
         let request = ChatRequest {
             model: model.to_string(),
             messages,
@@ -576,51 +790,30 @@ impl LlmService {
             max_tokens,
             stream: Some(true),
             tools: None,
+            stream_options: Some(StreamOptions { include_usage: true }),
         };
         
-        let response = self.http_client
+        let send = self.http_client
             .post(format!("{}/chat/completions", LlmProvider::OpenRouter.base_url()))
             .header("Authorization", format!("Bearer {}", api_key))
             .header("HTTP-Referer", &settings.app_url)
             .header("X-Title", &settings.app_name)
             .json(&request)
-            .send()
-            .await
-            .context("Failed to send streaming request")?;
+            .send();
+        let response = tokio::select! {
+            response = send => response.context("Failed to send streaming request")?,
+            _ = cancel.notified() => return Ok(None),
+        };
         
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
             return Err(anyhow!("OpenRouter streaming error: {}", error_text));
         }
         
-        let mut total_tokens = 0;
-        let mut stream = response.bytes_stream();
-        
-        use futures::StreamExt;
-        while let Some(chunk_result) = stream.next().await {
-            let chunk = chunk_result.context("Failed to read stream chunk")?;
-            let text = String::from_utf8_lossy(&chunk);
-            
-            for line in text.lines() {
-                if line.starts_with("data: ") {
-                    let data = &line[6..];
-                    if data == "[DONE]" {
-                        break;
-                    }
-                    
-                    if let Ok(stream_chunk) = serde_json::from_str::<StreamChunk>(data) {
-                        on_chunk(stream_chunk);
-                    }
-                }
-            }
-        }
-        
-        // Return estimated token usage (actual usage comes in final chunk)
-        Ok(TokenUsage {
-            prompt_tokens: 0,
-            completion_tokens: total_tokens,
-            total_tokens,
-        })
+        let Some(reply) = read_sse_stream(response.bytes_stream(), &on_chunk, cancel).await? else {
+            return Ok(None);
+        };
+        Ok(Some(settle_stream_usage(reply.usage, prompt_tokens, self.estimate_tokens(&reply.completion))))
     }
     
     // ========================================
@@ -651,6 +844,151 @@ impl LlmService {
             0.0
         }
     }
+    
+    /// Whether any enabled provider has an API key
+    pub async fn has_api_key(&self) -> bool {
+        let config = self.config.read().await;
+        config.providers.iter().any(|p| p.enabled && !p.api_key.trim().is_empty())
+    }
+    
+    /// Enabled providers, highest priority first
+    pub async fn enabled_providers(&self) -> Vec<ProviderConfig> {
+        let config = self.config.read().await;
+        let mut providers: Vec<ProviderConfig> = config.providers.iter()
+            .filter(|p| p.enabled)
+            .cloned()
+            .collect();
+        providers.sort_by_key(|p| p.priority);
+        providers
+    }
+    
+    /// Reachability and credential check that spends no tokens: lists the
+    /// provider's models instead of requesting a completion
+    pub async fn probe_provider(&self, provider: &ProviderConfig) -> Result<()> {
+        let name = provider.provider.as_str();
+        if provider.api_key.trim().is_empty() && provider.provider != LlmProvider::Local {
+            return Err(LlmError::MissingApiKey(name.to_string()).into());
+        }
+        
+        let mut req_builder = self.http_client
+            .get(format!("{}/models", provider.provider.base_url()));
+        req_builder = match provider.provider {
+            LlmProvider::Anthropic => req_builder
+                .header("x-api-key", &provider.api_key)
+                .header("anthropic-version", "2023-06-01"),
+            LlmProvider::Google => req_builder.header("x-goog-api-key", &provider.api_key),
+            LlmProvider::Local => req_builder,
+            _ => req_builder.header("Authorization", format!("Bearer {}", provider.api_key)),
+        };
+        
+        let response = req_builder.send().await
+            .map_err(|e| send_error(name, e))?;
+        
+        if !response.status().is_success() {
+            return Err(status_error(name, response).await);
+        }
+        
+        Ok(())
+    }
+}
+
+// ============================================
+// Completion Backend
+// ============================================
+
+/// The completion call other modules depend on, so they can be driven by a
+/// stub in tests
+#[async_trait::async_trait]
+pub trait ChatCompletion: Send + Sync {
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        model_id: Option<&str>,
+        temperature: Option<f64>,
+        max_tokens: Option<i32>,
+    ) -> Result<ChatResponse>;
+    
+    async fn has_api_key(&self) -> bool;
+}
+
+#[async_trait::async_trait]
+impl ChatCompletion for LlmService {
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        model_id: Option<&str>,
+        temperature: Option<f64>,
+        max_tokens: Option<i32>,
+    ) -> Result<ChatResponse> {
+        LlmService::chat(self, messages, model_id, temperature, max_tokens).await
+    }
+    
+    async fn has_api_key(&self) -> bool {
+        LlmService::has_api_key(self).await
+    }
+}
+
+// ============================================
+// Model Fallback
+// ============================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FallbackPolicy {
+    /// Models tried in order once the primary model fails with a transient error
+    pub fallback_models: Vec<String>,
+    /// Cap on completion calls per message, the primary model included
+    pub max_attempts: usize,
+}
+
+impl Default for FallbackPolicy {
+    fn default() -> Self {
+        Self {
+            fallback_models: Vec::new(),
+            max_attempts: 3,
+        }
+    }
+}
+
+impl FallbackPolicy {
+    /// The primary model followed by the fallbacks, without repeats and cut
+    /// to `max_attempts` (at least one)
+    pub fn chain(&self, primary: &str) -> Vec<String> {
+        let mut chain = vec![primary.to_string()];
+        for model in &self.fallback_models {
+            if !chain.contains(model) {
+                chain.push(model.clone());
+            }
+        }
+        chain.truncate(self.max_attempts.max(1));
+        chain
+    }
+}
+
+/// Run the completion down the policy's model chain, moving on only after
+/// rate limits, 5xx responses and timeouts. Returns the response together
+/// with the model that produced it.
+pub async fn chat_with_fallback(
+    llm: &dyn ChatCompletion,
+    policy: &FallbackPolicy,
+    primary_model: &str,
+    messages: Vec<ChatMessage>,
+    temperature: Option<f64>,
+    max_tokens: Option<i32>,
+) -> Result<(ChatResponse, String)> {
+    let mut last_error = None;
+    
+    for model in policy.chain(primary_model) {
+        match llm.chat(messages.clone(), Some(&model), temperature, max_tokens).await {
+            Ok(response) => return Ok((response, model)),
+            Err(e) if LlmError::classify(&e).is_some_and(LlmError::is_transient) => {
+                eprintln!("Model {} failed, trying next in chain: {}", model, e);
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    
+    Err(last_error.unwrap_or_else(|| anyhow!("No model available to answer")))
 }
 
 // ============================================
@@ -659,11 +997,13 @@ impl LlmService {
 
 use crate::context_builder::{ContextBuilder, Skill, ApiMessage};
 use crate::memory_manager::{MemoryManager, AddShortTermMemoryRequest};
+use crate::cost_persistence::{get_cost_database, CostRecord};
 
 pub struct ChatService {
     llm_service: Arc<LlmService>,
     memory_manager: Arc<MemoryManager>,
     context_builder: Arc<ContextBuilder>,
+    fallback_policy: RwLock<FallbackPolicy>,
 }
 
 impl ChatService {
@@ -676,9 +1016,21 @@ impl ChatService {
             llm_service,
             memory_manager,
             context_builder,
+            fallback_policy: RwLock::new(FallbackPolicy::default()),
         }
     }
     
+    pub async fn fallback_policy(&self) -> FallbackPolicy {
+        self.fallback_policy.read().await.clone()
+    }
+    
+    pub async fn set_fallback_policy(&self, policy: FallbackPolicy) {
+        *self.fallback_policy.write().await = policy;
+    }
+    
+    /// Sends through the policy's fallback chain. The first model is
+    /// `model_id` when given, otherwise the one selected for the detected
+    /// skill's mode (or "chat") via `set_model_for_mode`.
     pub async fn send_message(
         &self,
         workspace_id: &str,
@@ -686,6 +1038,98 @@ impl ChatService {
         user_message: &str,
         model_id: Option<&str>,
     ) -> Result<ChatServiceResponse> {
+        let turn = self.prepare_turn(workspace_id, session_id, user_message, model_id).await?;
+        
+        // 5. Call LLM
+        let policy = self.fallback_policy().await;
+        let (response, model_used) = chat_with_fallback(
+            self.llm_service.as_ref(),
+            &policy,
+            &turn.primary_model,
+            turn.messages.clone(),
+            Some(0.7),
+            Some(4096),
+        ).await?;
+        
+        // 6. Extract response
+        let assistant_message = response.choices.first()
+            .map(|c| c.message.content.clone())
+            .unwrap_or_default();
+        
+        // 7. Save assistant message and usage
+        self.finish_turn(workspace_id, session_id, turn, assistant_message, response.usage, model_used, false)
+    }
+    
+    /// Like `send_message`, but streams the reply through `chat_stream`,
+    /// passing each piece of content to `on_event` as it arrives. Context is
+    /// built and the user message saved before the stream starts. If `cancel`
+    /// is notified mid-stream, the partial reply is saved and returned with
+    /// `cancelled` set. Streaming uses the first model only, without fallbacks.
+    pub async fn send_message_stream(
+        &self,
+        workspace_id: &str,
+        session_id: &str,
+        user_message: &str,
+        model_id: Option<&str>,
+        on_event: ChatStreamListener,
+        cancel: Arc<Notify>,
+    ) -> Result<ChatServiceResponse> {
+        let turn = self.prepare_turn(workspace_id, session_id, user_message, model_id).await?;
+        
+        let assembled = Arc::new(std::sync::Mutex::new(String::new()));
+        let on_chunk = {
+            let assembled = Arc::clone(&assembled);
+            let on_event = Arc::clone(&on_event);
+            move |chunk: StreamChunk| {
+                for content in chunk.choices.into_iter().filter_map(|c| c.delta.content) {
+                    if content.is_empty() {
+                        continue;
+                    }
+                    if let Ok(mut assembled) = assembled.lock() {
+                        assembled.push_str(&content);
+                    }
+                    on_event(ChatStreamEvent::Delta { content });
+                }
+            }
+        };
+        
+        let usage = self.llm_service.chat_stream(
+            turn.messages.clone(),
+            Some(&turn.primary_model),
+            Some(0.7),
+            Some(4096),
+            on_chunk,
+            &cancel,
+        ).await?;
+        
+        let assistant_message = assembled.lock()
+            .map(|m| m.clone())
+            .map_err(|_| anyhow!("Failed to read streamed message"))?;
+        let cancelled = usage.is_none();
+        
+        let model_used = turn.primary_model.clone();
+        let response = self.finish_turn(
+            workspace_id,
+            session_id,
+            turn,
+            assistant_message,
+            usage,
+            model_used,
+            cancelled,
+        )?;
+        
+        on_event(ChatStreamEvent::Done { cancelled });
+        Ok(response)
+    }
+    
+    /// Steps shared by both send paths up to the LLM call
+    async fn prepare_turn(
+        &self,
+        workspace_id: &str,
+        session_id: &str,
+        user_message: &str,
+        model_id: Option<&str>,
+    ) -> Result<PreparedTurn> {
         // 1. Detect skill from message
         let skill = Skill::detect_skill(user_message);
         
@@ -700,6 +1144,14 @@ impl ChatService {
         // 3. Format for API
         let api_messages = self.context_builder.format_for_api(&context, user_message);
         
+        let primary_model = match model_id {
+            Some(model_id) => model_id.to_string(),
+            None => {
+                let mode = skill.as_ref().map(|s| s.name.as_str()).unwrap_or("chat");
+                self.llm_service.get_model_for_mode(mode).await
+            }
+        };
+        
         // 4. Save user message to short-term memory
         self.memory_manager.add_short_term_memory(
             workspace_id,
@@ -710,13 +1162,12 @@ impl ChatService {
                 tool_calls_json: None,
                 tool_results_json: None,
                 tokens_used: Some(self.llm_service.estimate_tokens(user_message)),
-                model_id: model_id.map(|s| s.to_string()),
+                model_id: Some(primary_model.clone()),
                 ttl_minutes: None,
             },
         )?;
         
-        // 5. Call LLM
-        let chat_messages: Vec<ChatMessage> = api_messages.into_iter()
+        let messages = api_messages.into_iter()
             .map(|m| ChatMessage {
                 role: m.role,
                 content: m.content,
@@ -725,45 +1176,106 @@ impl ChatService {
             })
             .collect();
         
-        let response = self.llm_service.chat(
-            chat_messages,
-            model_id,
-            Some(0.7),
-            Some(4096),
-        ).await?;
-        
-        // 6. Extract response
-        let assistant_message = response.choices.first()
-            .map(|c| c.message.content.clone())
-            .unwrap_or_default();
-        
-        let tokens_used = response.usage.as_ref()
-            .map(|u| u.total_tokens)
-            .unwrap_or(0);
+        Ok(PreparedTurn {
+            skill_used: skill.map(|s| s.name),
+            context_tokens: context.total_tokens_estimate,
+            retrieved_context_count: context.retrieved_memories.len() as i32,
+            messages,
+            primary_model,
+        })
+    }
+    
+    /// Save the reply to short-term memory, record its cost in the usage
+    /// ledger and build the response. Token counts are estimated when the
+    /// provider reported none.
+    #[allow(clippy::too_many_arguments)]
+    fn finish_turn(
+        &self,
+        workspace_id: &str,
+        session_id: &str,
+        turn: PreparedTurn,
+        assistant_message: String,
+        usage: Option<TokenUsage>,
+        model_used: String,
+        cancelled: bool,
+    ) -> Result<ChatServiceResponse> {
+        let usage = usage.unwrap_or_else(|| {
+            let completion_tokens = self.llm_service.estimate_tokens(&assistant_message);
+            TokenUsage {
+                prompt_tokens: turn.context_tokens,
+                completion_tokens,
+                total_tokens: turn.context_tokens + completion_tokens,
+            }
+        });
+        let tokens_used = usage.total_tokens;
+        self.record_usage(workspace_id, session_id, &model_used, &usage);
         
-        // 7. Save assistant message to short-term memory
-        self.memory_manager.add_short_term_memory(
-            workspace_id,
-            AddShortTermMemoryRequest {
-                session_id: session_id.to_string(),
-                role: "assistant".to_string(),
-                content: assistant_message.clone(),
-                tool_calls_json: None,
-                tool_results_json: None,
-                tokens_used: Some(tokens_used),
-                model_id: model_id.map(|s| s.to_string()),
-                ttl_minutes: None,
-            },
-        )?;
+        // A reply cancelled before any content arrived leaves nothing to keep
+        if !(cancelled && assistant_message.is_empty()) {
+            self.memory_manager.add_short_term_memory(
+                workspace_id,
+                AddShortTermMemoryRequest {
+                    session_id: session_id.to_string(),
+                    role: "assistant".to_string(),
+                    content: assistant_message.clone(),
+                    tool_calls_json: None,
+                    tool_results_json: None,
+                    tokens_used: Some(tokens_used),
+                    model_id: Some(model_used.clone()),
+                    ttl_minutes: None,
+                },
+            )?;
+        }
         
         Ok(ChatServiceResponse {
             message: assistant_message,
-            skill_used: skill.map(|s| s.name),
+            skill_used: turn.skill_used,
             tokens_used,
-            context_tokens: context.total_tokens_estimate,
-            retrieved_context_count: context.retrieved_memories.len() as i32,
+            context_tokens: turn.context_tokens,
+            retrieved_context_count: turn.retrieved_context_count,
+            model_used,
+            cancelled,
         })
     }
+    
+    /// Add the completion to the cost ledger. Skipped when the cost database
+    /// isn't initialized; a failed write is logged rather than failing the chat.
+    fn record_usage(&self, workspace_id: &str, session_id: &str, model_id: &str, usage: &TokenUsage) {
+        let provider = LlmModel::get_model_by_id(model_id)
+            .map(|m| m.provider.as_str().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let record = CostRecord {
+            id: None,
+            provider,
+            model: model_id.to_string(),
+            input_tokens: usage.prompt_tokens as i64,
+            output_tokens: usage.completion_tokens as i64,
+            cost_usd: self.llm_service.estimate_cost(model_id, usage.prompt_tokens, usage.completion_tokens),
+            request_type: "chat".to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            workspace_id: Some(workspace_id.to_string()),
+            session_id: Some(session_id.to_string()),
+        };
+        
+        match get_cost_database() {
+            Ok(guard) => {
+                if let Some(db) = guard.as_ref() {
+                    if let Err(e) = db.record_cost(&record) {
+                        eprintln!("Failed to record chat usage: {}", e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("Failed to record chat usage: {}", e),
+        }
+    }
+}
+
+struct PreparedTurn {
+    skill_used: Option<String>,
+    context_tokens: i32,
+    retrieved_context_count: i32,
+    messages: Vec<ChatMessage>,
+    primary_model: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -773,4 +1285,243 @@ pub struct ChatServiceResponse {
     pub tokens_used: i32,
     pub context_tokens: i32,
     pub retrieved_context_count: i32,
+    /// Model that produced the reply, after any fallbacks
+    pub model_used: String,
+    /// The reply was cut short and `message` holds what had arrived
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+/// Progress of a streamed reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatStreamEvent {
+    Delta { content: String },
+    Done { cancelled: bool },
+}
+
+pub type ChatStreamListener = Arc<dyn Fn(ChatStreamEvent) + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    
+    /// Fails the models it was given errors for and answers everything else
+    struct MockLlm {
+        failures: HashMap<String, LlmError>,
+        calls: Mutex<Vec<String>>,
+    }
+    
+    impl MockLlm {
+        fn new(failures: &[(&str, LlmError)]) -> Self {
+            Self {
+                failures: failures.iter().map(|(m, e)| (m.to_string(), e.clone())).collect(),
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+        
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+    
+    #[async_trait::async_trait]
+    impl ChatCompletion for MockLlm {
+        async fn chat(
+            &self,
+            _messages: Vec<ChatMessage>,
+            model_id: Option<&str>,
+            _temperature: Option<f64>,
+            _max_tokens: Option<i32>,
+        ) -> Result<ChatResponse> {
+            let model = model_id.unwrap_or_default().to_string();
+            self.calls.lock().unwrap().push(model.clone());
+            if let Some(error) = self.failures.get(&model) {
+                return Err(anyhow::Error::new(error.clone()).context("mock call failed"));
+            }
+            Ok(ChatResponse {
+                id: "mock".to_string(),
+                model: model.clone(),
+                choices: vec![ChatChoice {
+                    index: 0,
+                    message: ChatMessage {
+                        role: "assistant".to_string(),
+                        content: format!("answer from {}", model),
+                        tool_calls: None,
+                        tool_call_id: None,
+                    },
+                    finish_reason: Some("stop".to_string()),
+                }],
+                usage: None,
+            })
+        }
+        
+        async fn has_api_key(&self) -> bool {
+            true
+        }
+    }
+    
+    fn policy(fallbacks: &[&str], max_attempts: usize) -> FallbackPolicy {
+        FallbackPolicy {
+            fallback_models: fallbacks.iter().map(|m| m.to_string()).collect(),
+            max_attempts,
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_mode_model_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_manager = || Arc::new(WorkspaceDbManager::with_base_dir(dir.path()).unwrap());
+        let service = LlmService::new(LlmServiceConfig::default(), db_manager());
+        assert_eq!(service.get_model_for_mode("plan").await, "anthropic/claude-3.5-sonnet");
+        
+        service.set_model_for_mode("plan", "openai/gpt-4o").await.unwrap();
+        drop(service);
+        
+        let restarted = LlmService::new(LlmServiceConfig::default(), db_manager());
+        assert_eq!(restarted.get_model_for_mode("plan").await, "openai/gpt-4o");
+    }
+    
+    #[tokio::test]
+    async fn test_falls_back_to_next_model() {
+        let llm = MockLlm::new(&[("primary", LlmError::RateLimited("slow down".to_string()))]);
+        
+        let (response, model) = chat_with_fallback(&llm, &policy(&["backup"], 3), "primary", Vec::new(), None, None)
+            .await.unwrap();
+        
+        assert_eq!(model, "backup");
+        assert_eq!(response.choices[0].message.content, "answer from backup");
+        assert_eq!(llm.calls(), vec!["primary", "backup"]);
+    }
+    
+    #[tokio::test]
+    async fn test_only_transient_errors_fall_back() {
+        let overloaded = LlmError::from_status(503, "overloaded".to_string());
+        let llm = MockLlm::new(&[("primary", overloaded), ("timeout", LlmError::Timeout("slow".to_string()))]);
+        let (_, model) = chat_with_fallback(&llm, &policy(&["timeout", "backup"], 3), "primary", Vec::new(), None, None)
+            .await.unwrap();
+        assert_eq!(model, "backup");
+        
+        for error in [LlmError::MissingApiKey("openrouter".to_string()), LlmError::from_status(400, "bad request".to_string())] {
+            let llm = MockLlm::new(&[("primary", error.clone())]);
+            let result = chat_with_fallback(&llm, &policy(&["backup"], 3), "primary", Vec::new(), None, None).await;
+            
+            assert_eq!(LlmError::classify(&result.unwrap_err()), Some(&error));
+            assert_eq!(llm.calls(), vec!["primary"]);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_fallback_respects_max_attempts() {
+        let limited = LlmError::from_status(429, "limited".to_string());
+        let llm = MockLlm::new(&[("a", limited.clone()), ("b", limited.clone())]);
+        
+        let result = chat_with_fallback(&llm, &policy(&["a", "b", "c"], 2), "a", Vec::new(), None, None).await;
+        
+        assert_eq!(LlmError::classify(&result.unwrap_err()), Some(&limited));
+        assert_eq!(llm.calls(), vec!["a", "b"]);
+        assert_eq!(policy(&["a", "b"], 0).chain("b"), vec!["b"]);
+    }
+    
+    #[test]
+    fn test_stream_usage_never_records_zero_prompt_tokens() {
+        let estimated = settle_stream_usage(None, 120, 30);
+        assert_eq!((estimated.prompt_tokens, estimated.completion_tokens, estimated.total_tokens), (120, 30, 150));
+        
+        let reported = TokenUsage { prompt_tokens: 200, completion_tokens: 40, total_tokens: 240 };
+        let kept = settle_stream_usage(Some(reported), 120, 30);
+        assert_eq!((kept.prompt_tokens, kept.total_tokens), (200, 240));
+        
+        let partial = TokenUsage { prompt_tokens: 0, completion_tokens: 40, total_tokens: 40 };
+        let filled = settle_stream_usage(Some(partial), 120, 30);
+        assert_eq!((filled.prompt_tokens, filled.completion_tokens, filled.total_tokens), (120, 40, 160));
+    }
+    
+    fn sse_event(content: &str) -> String {
+        format!(
+            "data: {{\"id\":\"c1\",\"model\":\"m\",\"choices\":[{{\"index\":0,\"delta\":{{\"content\":{}}}}}]}}\n\n",
+            serde_json::to_string(content).unwrap(),
+        )
+    }
+    
+    fn collect_content(chunks: &Mutex<Vec<String>>) -> impl Fn(StreamChunk) + '_ {
+        move |chunk: StreamChunk| {
+            for content in chunk.choices.into_iter().filter_map(|c| c.delta.content) {
+                chunks.lock().unwrap().push(content);
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_sse_events_split_across_network_chunks() {
+        let body = [
+            sse_event("Hello"),
+            ": keep-alive\n\n".to_string(),
+            sse_event("สวัสดี"),
+            "data: {\"id\":\"c1\",\"model\":\"m\",\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":3,\"total_tokens\":15}}\r\n\r\n".to_string(),
+            "data: [DONE]\n\n".to_string(),
+            sse_event("ignored after done"),
+        ].concat().into_bytes();
+        
+        // Cut mid-event and mid-character (Thai letters are three bytes each)
+        let thai = body.windows(3).position(|w| w == "ส".as_bytes()).unwrap();
+        let cuts = [7, thai + 1, thai + 2, body.len() - 10];
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        for cut in cuts.into_iter().chain([body.len()]) {
+            pieces.push(Ok::<_, std::io::Error>(body[start..cut].to_vec()));
+            start = cut;
+        }
+        
+        let chunks = Mutex::new(Vec::new());
+        let cancel = Notify::new();
+        let reply = read_sse_stream(futures::stream::iter(pieces), &collect_content(&chunks), &cancel)
+            .await
+            .unwrap()
+            .unwrap();
+        
+        assert_eq!(*chunks.lock().unwrap(), vec!["Hello", "สวัสดี"]);
+        assert_eq!(reply.completion, "Helloสวัสดี");
+        assert_eq!(reply.usage.map(|u| u.total_tokens), Some(15));
+    }
+    
+    #[tokio::test]
+    async fn test_sse_stream_stops_when_cancelled() {
+        use futures::StreamExt;
+        
+        // The provider sends one piece and then goes quiet
+        let first = futures::stream::iter([Ok::<_, std::io::Error>(sse_event("partial").into_bytes())]);
+        let stream = first.chain(futures::stream::pending());
+        
+        let chunks = Mutex::new(Vec::new());
+        let cancel = Notify::new();
+        let collect = collect_content(&chunks);
+        let on_chunk = |chunk: StreamChunk| {
+            collect(chunk);
+            cancel.notify_one();
+        };
+        
+        let reply = tokio::time::timeout(Duration::from_secs(5), read_sse_stream(stream, &on_chunk, &cancel))
+            .await
+            .expect("cancel should end the stream")
+            .unwrap();
+        assert!(reply.is_none());
+        assert_eq!(*chunks.lock().unwrap(), vec!["partial"]);
+    }
+    
+    #[test]
+    fn test_stream_request_asks_for_usage() {
+        let request = ChatRequest {
+            model: "m".to_string(),
+            messages: vec![],
+            temperature: None,
+            max_tokens: None,
+            stream: Some(true),
+            tools: None,
+            stream_options: Some(StreamOptions { include_usage: true }),
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["stream_options"]["include_usage"], true);
+    }
 }
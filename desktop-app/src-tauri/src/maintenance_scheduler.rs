@@ -0,0 +1,461 @@
+// Maintenance Scheduler - Background upkeep for workspace databases
+//
+// Provides:
+// - Periodic vacuum, analyze, expired-memory cleanup and FTS rebuild per workspace
+// - Runs only on idle workspaces, on a jittered schedule
+// - Last-run records in app settings, so a restart doesn't re-run early
+// - Enable/disable and run-now commands
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tauri::State;
+use tokio::sync::Notify;
+
+use crate::memory_manager::MemoryManager;
+use crate::workspace_db::{WorkspaceDbManager, WorkspaceMetadata};
+
+const CONFIG_SETTING: &str = "maintenance.config";
+const LAST_RUN_SETTING_PREFIX: &str = "maintenance.last_run.";
+
+// ============================================
+// Types
+// ============================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceOp {
+    Vacuum,
+    Analyze,
+    CleanupExpired,
+    CleanupExpiredMemories,
+    RebuildFts,
+}
+
+impl MaintenanceOp {
+    pub const ALL: [MaintenanceOp; 5] = [
+        MaintenanceOp::CleanupExpired,
+        MaintenanceOp::CleanupExpiredMemories,
+        MaintenanceOp::RebuildFts,
+        MaintenanceOp::Vacuum,
+        MaintenanceOp::Analyze,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaintenanceOp::Vacuum => "vacuum",
+            MaintenanceOp::Analyze => "analyze",
+            MaintenanceOp::CleanupExpired => "cleanup_expired",
+            MaintenanceOp::CleanupExpiredMemories => "cleanup_expired_memories",
+            MaintenanceOp::RebuildFts => "rebuild_fts",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    pub enabled: bool,
+    pub vacuum_interval_hours: u64,
+    pub analyze_interval_hours: u64,
+    pub cleanup_interval_hours: u64,
+    pub fts_rebuild_interval_hours: u64,
+    /// A workspace is idle once it hasn't been opened for this long
+    pub idle_minutes: u64,
+    /// Each due time is pushed back by up to this fraction of its interval
+    pub jitter_ratio: f64,
+    pub poll_interval_secs: u64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            vacuum_interval_hours: 24 * 7,
+            analyze_interval_hours: 24,
+            cleanup_interval_hours: 6,
+            fts_rebuild_interval_hours: 24 * 7,
+            idle_minutes: 15,
+            jitter_ratio: 0.1,
+            poll_interval_secs: 300,
+        }
+    }
+}
+
+impl MaintenanceConfig {
+    pub fn interval(&self, op: MaintenanceOp) -> chrono::Duration {
+        let hours = match op {
+            MaintenanceOp::Vacuum => self.vacuum_interval_hours,
+            MaintenanceOp::Analyze => self.analyze_interval_hours,
+            MaintenanceOp::CleanupExpired | MaintenanceOp::CleanupExpiredMemories => self.cleanup_interval_hours,
+            MaintenanceOp::RebuildFts => self.fts_rebuild_interval_hours,
+        };
+        chrono::Duration::hours(hours as i64)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let intervals = [
+            self.vacuum_interval_hours,
+            self.analyze_interval_hours,
+            self.cleanup_interval_hours,
+            self.fts_rebuild_interval_hours,
+        ];
+        if intervals.contains(&0) {
+            return Err("Maintenance intervals must be at least one hour".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.jitter_ratio) {
+            return Err("Jitter ratio must be between 0 and 1".to_string());
+        }
+        if self.poll_interval_secs == 0 {
+            return Err("Poll interval must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceRun {
+    pub workspace_id: String,
+    pub op: MaintenanceOp,
+    pub success: bool,
+    /// Rows removed or reindexed, or the error
+    pub detail: Option<String>,
+    pub duration_ms: u64,
+    pub ran_at: String,
+}
+
+// ============================================
+// Scheduler
+// ============================================
+
+/// Runs maintenance on each workspace when it is due and the workspace is
+/// idle. Due times are measured from the last recorded run (or workspace
+/// creation) plus the interval and a per-workspace jitter, so workspaces
+/// created together don't all fall due at once.
+pub struct MaintenanceScheduler {
+    db_manager: Arc<WorkspaceDbManager>,
+    memory_manager: MemoryManager,
+    config: RwLock<MaintenanceConfig>,
+    wake: Notify,
+}
+
+impl MaintenanceScheduler {
+    /// Create the scheduler with the config saved in app settings, if any
+    pub fn new(db_manager: Arc<WorkspaceDbManager>) -> Self {
+        let config = db_manager.get_app_setting(CONFIG_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            memory_manager: MemoryManager::new(Arc::clone(&db_manager)),
+            db_manager,
+            config: RwLock::new(config),
+            wake: Notify::new(),
+        }
+    }
+
+    pub fn config(&self) -> MaintenanceConfig {
+        self.config.read().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// Validate, save and apply a new config
+    pub fn set_config(&self, config: MaintenanceConfig) -> Result<(), String> {
+        config.validate()?;
+        let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+        self.db_manager.set_app_setting(CONFIG_SETTING, &json).map_err(|e| e.to_string())?;
+        *self.config.write().map_err(|_| "Failed to acquire config lock")? = config;
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    pub fn set_enabled(&self, enabled: bool) -> Result<MaintenanceConfig, String> {
+        let config = MaintenanceConfig { enabled, ..self.config() };
+        self.set_config(config.clone())?;
+        Ok(config)
+    }
+
+    /// Poll forever, running whatever is due
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            let scheduler = Arc::clone(&self);
+            match tokio::task::spawn_blocking(move || scheduler.tick_at(Utc::now())).await {
+                Ok(runs) => {
+                    for run in runs.iter().filter(|r| !r.success) {
+                        eprintln!(
+                            "Maintenance {} failed for workspace {}: {}",
+                            run.op.as_str(),
+                            run.workspace_id,
+                            run.detail.as_deref().unwrap_or("unknown error")
+                        );
+                    }
+                }
+                Err(e) => eprintln!("Maintenance tick failed: {}", e),
+            }
+
+            let poll_interval = Duration::from_secs(self.config().poll_interval_secs);
+            tokio::select! {
+                _ = tokio::time::sleep(poll_interval) => {}
+                _ = self.wake.notified() => {}
+            }
+        }
+    }
+
+    /// Run due maintenance on the first idle workspace that has any.
+    /// One workspace per tick keeps the background load bounded.
+    pub fn tick_at(&self, now: DateTime<Utc>) -> Vec<MaintenanceRun> {
+        if !self.config().enabled {
+            return Vec::new();
+        }
+        let workspaces = match self.db_manager.list_workspaces() {
+            Ok(workspaces) => workspaces,
+            Err(e) => {
+                eprintln!("Maintenance could not list workspaces: {}", e);
+                return Vec::new();
+            }
+        };
+
+        for workspace in workspaces.iter().filter(|w| w.deleted_at.is_none()) {
+            let runs = self.run_due(workspace, now);
+            if !runs.is_empty() {
+                return runs;
+            }
+        }
+        Vec::new()
+    }
+
+    /// Run every op that is due for `workspace`, if it is idle at `now`
+    pub fn run_due(&self, workspace: &WorkspaceMetadata, now: DateTime<Utc>) -> Vec<MaintenanceRun> {
+        if !self.is_idle(workspace, now) {
+            return Vec::new();
+        }
+        MaintenanceOp::ALL.iter()
+            .filter(|op| self.next_due(workspace, **op) <= now)
+            .map(|op| self.run_op(&workspace.id, *op, now))
+            .collect()
+    }
+
+    /// Run `ops` (default all) on a workspace now, regardless of schedule
+    pub fn run_now(&self, workspace_id: &str, ops: &[MaintenanceOp]) -> Vec<MaintenanceRun> {
+        let ops = if ops.is_empty() { &MaintenanceOp::ALL[..] } else { ops };
+        ops.iter().map(|op| self.run_op(workspace_id, *op, Utc::now())).collect()
+    }
+
+    /// Unused for `idle_minutes` and with no connection held or locked
+    fn is_idle(&self, workspace: &WorkspaceMetadata, now: DateTime<Utc>) -> bool {
+        let idle_for = chrono::Duration::minutes(self.config().idle_minutes as i64);
+        let last_accessed = parse_timestamp(&workspace.last_accessed_at).unwrap_or(DateTime::UNIX_EPOCH);
+        now - last_accessed >= idle_for && !self.db_manager.is_workspace_in_use(&workspace.id)
+    }
+
+    /// When `op` next falls due for `workspace`
+    pub fn next_due(&self, workspace: &WorkspaceMetadata, op: MaintenanceOp) -> DateTime<Utc> {
+        let config = self.config();
+        let baseline = self.last_run(&workspace.id, op)
+            .or_else(|| parse_timestamp(&workspace.created_at))
+            .unwrap_or(DateTime::UNIX_EPOCH);
+        let interval = config.interval(op);
+        baseline + interval + jitter(&workspace.id, op, interval, config.jitter_ratio)
+    }
+
+    fn last_run(&self, workspace_id: &str, op: MaintenanceOp) -> Option<DateTime<Utc>> {
+        self.db_manager.get_app_setting(&last_run_key(workspace_id, op))
+            .ok()
+            .flatten()
+            .and_then(|value| parse_timestamp(&value))
+    }
+
+    /// Run one op and record it, failed or not, so a broken workspace is
+    /// retried on the next interval rather than every poll
+    fn run_op(&self, workspace_id: &str, op: MaintenanceOp, now: DateTime<Utc>) -> MaintenanceRun {
+        let started = Instant::now();
+        let result = match op {
+            MaintenanceOp::Vacuum => self.db_manager.vacuum_workspace(workspace_id).map(|_| None),
+            MaintenanceOp::Analyze => self.db_manager.optimize_workspace(workspace_id).map(|_| None),
+            MaintenanceOp::CleanupExpired => self.db_manager.cleanup_expired_memory(workspace_id)
+                .map(|deleted| Some(format!("{} expired rows deleted", deleted))),
+            MaintenanceOp::CleanupExpiredMemories => self.memory_manager.cleanup_expired_memories(workspace_id)
+                .map(|deleted| Some(format!("{} expired memories deleted", deleted))),
+            MaintenanceOp::RebuildFts => self.db_manager.rebuild_fts(workspace_id)
+                .map(|stats| Some(format!(
+                    "{} rows reindexed",
+                    stats.knowledge_rows + stats.memory_long_rows + stats.chat_messages_rows
+                ))),
+        };
+
+        let ran_at = now.to_rfc3339();
+        if let Err(e) = self.db_manager.set_app_setting(&last_run_key(workspace_id, op), &ran_at) {
+            eprintln!("Failed to record maintenance run for {}: {}", workspace_id, e);
+        }
+
+        MaintenanceRun {
+            workspace_id: workspace_id.to_string(),
+            op,
+            success: result.is_ok(),
+            detail: result.unwrap_or_else(|e| Some(e.to_string())),
+            duration_ms: started.elapsed().as_millis() as u64,
+            ran_at,
+        }
+    }
+}
+
+/// Prefix of every last-run record of a workspace, removed when it is purged
+pub fn last_run_prefix(workspace_id: &str) -> String {
+    format!("{}{}.", LAST_RUN_SETTING_PREFIX, workspace_id)
+}
+
+fn last_run_key(workspace_id: &str, op: MaintenanceOp) -> String {
+    format!("{}{}", last_run_prefix(workspace_id), op.as_str())
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// A stable offset in `[0, interval * ratio)` for this workspace and op.
+/// Derived from a hash rather than randomness so it survives restarts.
+fn jitter(workspace_id: &str, op: MaintenanceOp, interval: chrono::Duration, ratio: f64) -> chrono::Duration {
+    let digest = Sha256::digest(format!("{}:{}", workspace_id, op.as_str()).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    let fraction = u64::from_le_bytes(bytes) as f64 / u64::MAX as f64;
+    chrono::Duration::seconds((interval.num_seconds() as f64 * ratio * fraction) as i64)
+}
+
+// ============================================
+// Tauri Commands
+// ============================================
+
+#[tauri::command]
+pub fn maintenance_get_config(
+    state: State<'_, Arc<MaintenanceScheduler>>,
+) -> Result<MaintenanceConfig, String> {
+    Ok(state.config())
+}
+
+#[tauri::command]
+pub fn maintenance_set_config(
+    state: State<'_, Arc<MaintenanceScheduler>>,
+    config: MaintenanceConfig,
+) -> Result<(), String> {
+    state.set_config(config)
+}
+
+#[tauri::command]
+pub fn maintenance_set_enabled(
+    state: State<'_, Arc<MaintenanceScheduler>>,
+    enabled: bool,
+) -> Result<MaintenanceConfig, String> {
+    state.set_enabled(enabled)
+}
+
+/// Run maintenance on a workspace immediately; `ops` defaults to all of them
+#[tauri::command]
+pub async fn maintenance_run_now(
+    state: State<'_, Arc<MaintenanceScheduler>>,
+    workspace_id: String,
+    ops: Option<Vec<MaintenanceOp>>,
+) -> Result<Vec<MaintenanceRun>, String> {
+    let scheduler = Arc::clone(&state);
+    tokio::task::spawn_blocking(move || scheduler.run_now(&workspace_id, &ops.unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily_config() -> MaintenanceConfig {
+        MaintenanceConfig {
+            vacuum_interval_hours: 24,
+            analyze_interval_hours: 24,
+            cleanup_interval_hours: 24,
+            fts_rebuild_interval_hours: 24,
+            ..MaintenanceConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_jitter_is_stable_and_bounded() {
+        let interval = chrono::Duration::hours(24);
+        let a = jitter("ws-a", MaintenanceOp::Vacuum, interval, 0.1);
+        assert_eq!(a, jitter("ws-a", MaintenanceOp::Vacuum, interval, 0.1));
+        assert!(a >= chrono::Duration::zero() && a < chrono::Duration::minutes(144));
+        assert_ne!(a, jitter("ws-b", MaintenanceOp::Vacuum, interval, 0.1));
+        assert_eq!(jitter("ws-a", MaintenanceOp::Vacuum, interval, 0.0), chrono::Duration::zero());
+    }
+
+    #[test]
+    fn test_scheduler_respects_interval() {
+        let base = tempfile::tempdir().unwrap();
+        let manager = Arc::new(WorkspaceDbManager::with_base_dir(base.path()).unwrap());
+        let workspace = manager.create_workspace("test-maintenance-interval", None).unwrap();
+        let scheduler = MaintenanceScheduler {
+            db_manager: manager.clone(),
+            memory_manager: MemoryManager::new(manager.clone()),
+            config: RwLock::new(daily_config()),
+            wake: Notify::new(),
+        };
+        let created = parse_timestamp(&workspace.created_at).unwrap();
+
+        // Idle, but nothing is due before the first interval has passed
+        assert!(scheduler.run_due(&workspace, created + chrono::Duration::hours(12)).is_empty());
+
+        let first = created + chrono::Duration::hours(48);
+        let runs = scheduler.run_due(&workspace, first);
+        assert_eq!(runs.len(), MaintenanceOp::ALL.len());
+        assert!(runs.iter().all(|r| r.success), "{:?}", runs);
+
+        // Recorded runs push the next one a full interval out, even for a
+        // fresh scheduler as after a restart
+        let restarted = MaintenanceScheduler {
+            db_manager: manager.clone(),
+            memory_manager: MemoryManager::new(manager.clone()),
+            config: RwLock::new(daily_config()),
+            wake: Notify::new(),
+        };
+        assert!(restarted.run_due(&workspace, first).is_empty());
+        assert!(restarted.run_due(&workspace, first + chrono::Duration::hours(23)).is_empty());
+        assert_eq!(restarted.run_due(&workspace, first + chrono::Duration::hours(27)).len(), MaintenanceOp::ALL.len());
+
+        // Last-run records go with the workspace once it is purged
+        manager.delete_workspace(&workspace.id).unwrap();
+        assert!(!manager.list_app_settings(&last_run_prefix(&workspace.id)).unwrap().is_empty());
+        manager.purge_trash(0).unwrap();
+        assert!(manager.list_app_settings(&last_run_prefix(&workspace.id)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scheduler_skips_busy_or_recent_workspaces() {
+        let base = tempfile::tempdir().unwrap();
+        let manager = Arc::new(WorkspaceDbManager::with_base_dir(base.path()).unwrap());
+        let workspace = manager.create_workspace("test-maintenance-idle", None).unwrap();
+        let scheduler = MaintenanceScheduler {
+            db_manager: manager.clone(),
+            memory_manager: MemoryManager::new(manager.clone()),
+            config: RwLock::new(daily_config()),
+            wake: Notify::new(),
+        };
+        let created = parse_timestamp(&workspace.created_at).unwrap();
+        let due = created + chrono::Duration::hours(48);
+
+        // Used moments ago
+        let recent = WorkspaceMetadata { last_accessed_at: (due - chrono::Duration::minutes(1)).to_rfc3339(), ..workspace.clone() };
+        assert!(scheduler.run_due(&recent, due).is_empty());
+
+        // A held connection means someone is working in it
+        let held = manager.open_workspace(&workspace.id).unwrap();
+        assert!(scheduler.run_due(&workspace, due).is_empty());
+        drop(held);
+
+        // Disabled scheduler never ticks
+        scheduler.config.write().unwrap().enabled = false;
+        assert!(scheduler.tick_at(due).is_empty());
+
+        assert_eq!(scheduler.run_due(&workspace, due).len(), MaintenanceOp::ALL.len());
+
+        manager.delete_workspace(&workspace.id).unwrap();
+    }
+}
@@ -2,12 +2,20 @@
 //
 // Provides:
 // - Browse and search marketplace items
+// - Offline cache of the remote index with a TTL
 // - Download and install items
+// - Checksum and signature verification before install
 // - Publish items to marketplace
 // - Reviews and ratings
 
 use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ring::signature::{UnparsedPublicKey, ED25519};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 // ============================================
@@ -42,6 +50,21 @@ pub struct MarketplaceItem {
     pub updated_at: i64,
     pub verified: bool,
     pub featured: bool,
+    /// Downloadable package; items without one cannot be verified
+    #[serde(default)]
+    pub package: Option<PackageInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageInfo {
+    /// `https://` URL of the package archive; see `fetch_package`
+    pub download_url: String,
+    /// SHA-256 of the package, hex encoded
+    pub checksum: Option<String>,
+    /// Detached Ed25519 signature over the package bytes, base64 encoded
+    pub signature: Option<String>,
+    /// Trusted key the signature was made with; all trusted keys are tried if absent
+    pub signing_key_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,6 +156,10 @@ pub struct SearchResult {
     pub page: u32,
     pub per_page: u32,
     pub total_pages: u32,
+    /// Served from the cached index rather than a fresh fetch; set by the
+    /// caller from `refresh_index`
+    #[serde(default)]
+    pub from_cache: bool,
 }
 
 // ============================================
@@ -172,6 +199,268 @@ pub enum PublishStatus {
     Published,
 }
 
+// ============================================
+// Marketplace Errors
+// ============================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketplaceError {
+    NotFound(String),
+    /// Checksum or signature missing, unknown or wrong
+    UnverifiedItem { item_id: String, reason: String },
+    Download(String),
+    Io(String),
+}
+
+impl fmt::Display for MarketplaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarketplaceError::NotFound(id) => write!(f, "Item not found: {}", id),
+            MarketplaceError::UnverifiedItem { item_id, reason } => {
+                write!(f, "Refusing to install unverified item {}: {}", item_id, reason)
+            }
+            MarketplaceError::Download(msg) => write!(f, "Download failed: {}", msg),
+            MarketplaceError::Io(msg) => write!(f, "I/O error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MarketplaceError {}
+
+// ============================================
+// Package Verification
+// ============================================
+
+/// How a package checked out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verification {
+    /// Checksum matched and a trusted key signed it
+    Verified,
+    /// Installed without a valid signature because unverified installs are allowed
+    Unverified,
+}
+
+/// Trusted Ed25519 public keys (raw 32 bytes) by key id
+#[derive(Debug, Clone, Default)]
+pub struct TrustedKeys {
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl TrustedKeys {
+    /// Add a base64-encoded raw Ed25519 public key
+    pub fn add(&mut self, key_id: &str, public_key_base64: &str) -> Result<(), String> {
+        let key = BASE64.decode(public_key_base64.trim())
+            .map_err(|e| format!("Invalid public key {}: {}", key_id, e))?;
+        if key.len() != 32 {
+            return Err(format!("Public key {} must be 32 bytes, got {}", key_id, key.len()));
+        }
+        self.keys.insert(key_id.to_string(), key);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key_id: &str) -> bool {
+        self.keys.remove(key_id).is_some()
+    }
+
+    pub fn key_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.keys.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Keys as a JSON object of base64 public keys by key id, the form they are saved in
+    pub fn to_json(&self) -> String {
+        let encoded: std::collections::BTreeMap<&str, String> = self.keys.iter()
+            .map(|(id, key)| (id.as_str(), BASE64.encode(key)))
+            .collect();
+        serde_json::to_string(&encoded).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Load keys saved by `to_json`. Keys that no longer parse are skipped.
+    pub fn from_json(json: &str) -> Self {
+        let mut trusted = Self::default();
+        let encoded: HashMap<String, String> = match serde_json::from_str(json) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                eprintln!("Ignoring saved trusted keys: {}", e);
+                return trusted;
+            }
+        };
+        for (key_id, public_key) in encoded {
+            if let Err(e) = trusted.add(&key_id, &public_key) {
+                eprintln!("Ignoring saved trusted key: {}", e);
+            }
+        }
+        trusted
+    }
+
+    fn verify(&self, key_id: Option<&str>, message: &[u8], signature: &[u8]) -> bool {
+        let check = |key: &Vec<u8>| UnparsedPublicKey::new(&ED25519, key).verify(message, signature).is_ok();
+        match key_id {
+            Some(id) => self.keys.get(id).is_some_and(check),
+            None => self.keys.values().any(check),
+        }
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Check a downloaded package against the item's checksum and signature.
+///
+/// With `allow_unverified` a missing checksum, missing signature or unknown
+/// key is tolerated, but a checksum mismatch or a signature that a trusted key
+/// rejects is always refused because it means the payload was altered.
+pub fn verify_package(
+    item_id: &str,
+    package: &PackageInfo,
+    bytes: &[u8],
+    trusted_keys: &TrustedKeys,
+    allow_unverified: bool,
+) -> Result<Verification, MarketplaceError> {
+    let unverified = |reason: &str| MarketplaceError::UnverifiedItem {
+        item_id: item_id.to_string(),
+        reason: reason.to_string(),
+    };
+    let tolerate = |reason: &str| {
+        if allow_unverified {
+            Ok(Verification::Unverified)
+        } else {
+            Err(unverified(reason))
+        }
+    };
+
+    let checksum = match &package.checksum {
+        Some(checksum) => checksum,
+        None => return tolerate("no checksum"),
+    };
+    if !sha256_hex(bytes).eq_ignore_ascii_case(checksum.trim()) {
+        return Err(unverified("checksum mismatch"));
+    }
+
+    let signature = match &package.signature {
+        Some(signature) => BASE64.decode(signature.trim())
+            .map_err(|_| unverified("malformed signature"))?,
+        None => return tolerate("no signature"),
+    };
+
+    let key_id = package.signing_key_id.as_deref();
+    let key_known = match key_id {
+        Some(id) => trusted_keys.keys.contains_key(id),
+        None => !trusted_keys.keys.is_empty(),
+    };
+    if !key_known {
+        return tolerate("signing key is not trusted");
+    }
+
+    if trusted_keys.verify(key_id, bytes, &signature) {
+        Ok(Verification::Verified)
+    } else {
+        Err(unverified("signature does not match"))
+    }
+}
+
+/// Longest a package or index download may take
+const FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Redirects followed before a download is abandoned
+const MAX_REDIRECTS: usize = 10;
+
+/// Whether packages and indexes may be fetched from `url`: HTTPS, or plain
+/// HTTP to this machine, which browsers treat as secure too
+fn is_fetchable_url(url: &reqwest::Url) -> bool {
+    match url.scheme() {
+        "https" => true,
+        "http" => match url.host_str() {
+            Some("localhost") => true,
+            Some(host) => host.trim_start_matches('[').trim_end_matches(']')
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback()),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Download a package over HTTPS. Other schemes, including `file://`, are
+/// refused, and so are redirects to them.
+pub async fn fetch_package(url: &str) -> Result<Vec<u8>, MarketplaceError> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| MarketplaceError::Download(format!("{}: {}", url, e)))?;
+    if !is_fetchable_url(&parsed) {
+        return Err(MarketplaceError::Download(format!("{}: only https URLs are allowed", url)));
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if is_fetchable_url(attempt.url()) {
+                attempt.follow()
+            } else {
+                attempt.error("redirected to a URL that is not https")
+            }
+        }))
+        .build()
+        .map_err(|e| MarketplaceError::Download(e.to_string()))?;
+    let response = client.get(parsed).send().await
+        .map_err(|e| MarketplaceError::Download(format!("{}: {}", url, e)))?
+        .error_for_status()
+        .map_err(|e| MarketplaceError::Download(format!("{}: {}", url, e)))?;
+    let bytes = response.bytes().await
+        .map_err(|e| MarketplaceError::Download(format!("{}: {}", url, e)))?;
+    Ok(bytes.to_vec())
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), MarketplaceError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| MarketplaceError::Io(e.to_string()))?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes).map_err(|e| MarketplaceError::Io(e.to_string()))?;
+    std::fs::rename(&tmp, path).map_err(|e| MarketplaceError::Io(e.to_string()))
+}
+
+// ============================================
+// Index Cache
+// ============================================
+
+/// How long a fetched index is used before it is fetched again
+pub const DEFAULT_INDEX_TTL: Duration = Duration::from_secs(60 * 60);
+
+const INDEX_CACHE_FILE: &str = "index.json";
+
+/// Last index fetched from the remote, as persisted in the cache directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedIndex {
+    /// Index the items were fetched from
+    #[serde(default)]
+    pub url: Option<String>,
+    pub fetched_at: i64,
+    pub items: Vec<MarketplaceItem>,
+}
+
+/// Fetch the item index, a JSON array of items
+pub async fn fetch_index(url: &str) -> Result<Vec<MarketplaceItem>, MarketplaceError> {
+    let bytes = fetch_package(url).await?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| MarketplaceError::Download(format!("{}: invalid index: {}", url, e)))
+}
+
+/// Next step of an install as decided by `MarketplaceService::plan_install`
+#[derive(Debug, Clone)]
+pub enum InstallPlan {
+    /// A verified copy is already cached
+    Cached(PathBuf),
+    /// Download, then verify with `store_package`
+    Download(PackageInfo),
+    /// No package and unverified installs are allowed
+    NoPackage,
+}
+
 // ============================================
 // Marketplace Service
 // ============================================
@@ -181,6 +470,16 @@ pub struct MarketplaceService {
     pub reviews: HashMap<String, Vec<Review>>,
     pub installed: HashMap<String, InstalledItem>,
     pub favorites: Vec<String>,
+    pub trusted_keys: TrustedKeys,
+    /// Development escape hatch; see `verify_package`
+    pub allow_unverified: bool,
+    /// Verified packages, stored as `<checksum>.pkg`, and the cached index
+    pub cache_dir: PathBuf,
+    /// Remote index; without one only the bundled items are listed
+    pub index_url: Option<String>,
+    pub index_ttl: Duration,
+    /// When `items` was last fetched from `index_url`
+    pub index_fetched_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -189,17 +488,36 @@ pub struct InstalledItem {
     pub version: String,
     pub installed_at: i64,
     pub auto_update: bool,
+    #[serde(default)]
+    pub package_path: Option<String>,
+    #[serde(default)]
+    pub verification: Option<Verification>,
 }
 
 impl MarketplaceService {
     pub fn new() -> Self {
+        let cache_dir = dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("smartspecpro")
+            .join("marketplace");
+        Self::with_cache_dir(cache_dir)
+    }
+
+    pub fn with_cache_dir(cache_dir: PathBuf) -> Self {
         let mut service = Self {
             items: HashMap::new(),
             reviews: HashMap::new(),
             installed: HashMap::new(),
             favorites: Vec::new(),
+            trusted_keys: TrustedKeys::default(),
+            allow_unverified: false,
+            cache_dir,
+            index_url: None,
+            index_ttl: DEFAULT_INDEX_TTL,
+            index_fetched_at: None,
         };
         service.load_sample_items();
+        service.load_cached_index();
         service
     }
 
@@ -238,6 +556,7 @@ impl MarketplaceService {
                 updated_at: chrono::Utc::now().timestamp() - 86400 * 7,
                 verified: true,
                 featured: true,
+                package: None,
             },
             MarketplaceItem {
                 id: "plugin-jira".to_string(),
@@ -271,6 +590,7 @@ impl MarketplaceService {
                 updated_at: chrono::Utc::now().timestamp() - 86400 * 14,
                 verified: true,
                 featured: true,
+                package: None,
             },
             MarketplaceItem {
                 id: "template-saas".to_string(),
@@ -304,6 +624,7 @@ impl MarketplaceService {
                 updated_at: chrono::Utc::now().timestamp() - 86400 * 10,
                 verified: false,
                 featured: false,
+                package: None,
             },
             MarketplaceItem {
                 id: "theme-dark-pro".to_string(),
@@ -337,6 +658,7 @@ impl MarketplaceService {
                 updated_at: chrono::Utc::now().timestamp() - 86400 * 5,
                 verified: false,
                 featured: true,
+                package: None,
             },
         ];
 
@@ -345,6 +667,103 @@ impl MarketplaceService {
         }
     }
 
+    // ============================================
+    // Index
+    // ============================================
+
+    /// Replace the bundled items with the last index saved to disk, if it
+    /// came from `index_url`. Without an index URL the cached index's own URL
+    /// is adopted, so the marketplace works offline from the start.
+    fn load_cached_index(&mut self) {
+        let Ok(bytes) = std::fs::read(self.cache_dir.join(INDEX_CACHE_FILE)) else {
+            return;
+        };
+        let index = match serde_json::from_slice::<CachedIndex>(&bytes) {
+            Ok(index) => index,
+            Err(e) => {
+                eprintln!("Ignoring corrupt marketplace index cache: {}", e);
+                return;
+            }
+        };
+        // A cache that doesn't say where it came from can't be matched
+        let Some(url) = index.url else {
+            return;
+        };
+        match &self.index_url {
+            Some(current) if *current != url => return,
+            Some(_) => {}
+            None => self.index_url = Some(url),
+        }
+        self.set_items(index.items, index.fetched_at);
+    }
+
+    /// Point the marketplace at a remote index, or back at the bundled items.
+    /// Items cached from another index are dropped rather than listed under
+    /// the new one.
+    pub fn set_index_url(&mut self, url: Option<String>) {
+        self.index_url = url;
+        self.index_fetched_at = None;
+        self.items.clear();
+        self.load_sample_items();
+        if self.index_url.is_some() {
+            self.load_cached_index();
+        }
+    }
+
+    fn set_items(&mut self, items: Vec<MarketplaceItem>, fetched_at: i64) {
+        self.items = items.into_iter().map(|item| (item.id.clone(), item)).collect();
+        self.index_fetched_at = Some(fetched_at);
+    }
+
+    /// URL to fetch when the index is missing or older than the TTL
+    pub fn index_refresh_url(&self) -> Option<String> {
+        let url = self.index_url.as_ref()?;
+        let fresh = self.index_fetched_at.is_some_and(|fetched_at| {
+            let age = chrono::Utc::now().timestamp() - fetched_at;
+            age >= 0 && (age as u64) < self.index_ttl.as_secs()
+        });
+        (!fresh).then(|| url.clone())
+    }
+
+    /// Use a freshly fetched index and persist it for offline use
+    pub fn apply_index(&mut self, items: Vec<MarketplaceItem>) -> Result<(), MarketplaceError> {
+        let index = CachedIndex {
+            url: self.index_url.clone(),
+            fetched_at: chrono::Utc::now().timestamp(),
+            items,
+        };
+        let bytes = serde_json::to_vec(&index).map_err(|e| MarketplaceError::Io(e.to_string()))?;
+        write_atomically(&self.cache_dir.join(INDEX_CACHE_FILE), &bytes)?;
+        self.set_items(index.items, index.fetched_at);
+        Ok(())
+    }
+
+    /// Whether listings currently come from a cached index
+    pub fn is_serving_cache(&self) -> bool {
+        self.index_fetched_at.is_some()
+    }
+
+    /// Fetch the index if stale. Falls back to the cached index when the
+    /// fetch fails. Returns true when results will come from the cache.
+    pub async fn refresh_index(&mut self) -> bool {
+        let Some(url) = self.index_refresh_url() else {
+            return self.is_serving_cache();
+        };
+        match fetch_index(&url).await {
+            Ok(items) => match self.apply_index(items) {
+                Ok(()) => false,
+                Err(e) => {
+                    eprintln!("Failed to cache marketplace index: {}", e);
+                    false
+                }
+            },
+            Err(e) => {
+                eprintln!("Marketplace index unavailable, using cache: {}", e);
+                self.is_serving_cache()
+            }
+        }
+    }
+
     // ============================================
     // Search and Browse
     // ============================================
@@ -382,13 +801,17 @@ impl MarketplaceService {
             items.retain(|item| std::mem::discriminant(&item.price.price_type) == std::mem::discriminant(price_type));
         }
 
-        // Sort
+        // Sort, ties broken by id so pages are stable across requests
+        items.sort_by(|a, b| a.id.cmp(&b.id));
         match query.sort_by {
-            SortBy::Downloads => items.sort_by(|a, b| b.downloads.cmp(&a.downloads)),
+            SortBy::Downloads => items.sort_by_key(|item| std::cmp::Reverse(item.downloads)),
             SortBy::Rating => items.sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap_or(std::cmp::Ordering::Equal)),
-            SortBy::Newest => items.sort_by(|a, b| b.published_at.cmp(&a.published_at)),
-            SortBy::Updated => items.sort_by(|a, b| b.updated_at.cmp(&a.updated_at)),
-            SortBy::Relevance => {} // Keep original order
+            SortBy::Newest => items.sort_by_key(|item| std::cmp::Reverse(item.published_at)),
+            SortBy::Updated => items.sort_by_key(|item| std::cmp::Reverse(item.updated_at)),
+            SortBy::Relevance => {
+                let q_lower = query.query.as_deref().unwrap_or("").to_lowercase();
+                items.sort_by_key(|item| std::cmp::Reverse((relevance_score(item, &q_lower), item.downloads)));
+            }
         }
 
         let total = items.len() as u64;
@@ -404,6 +827,7 @@ impl MarketplaceService {
             page,
             per_page,
             total_pages,
+            from_cache: false,
         }
     }
 
@@ -412,7 +836,9 @@ impl MarketplaceService {
     }
 
     pub fn get_featured(&self) -> Vec<&MarketplaceItem> {
-        self.items.values().filter(|item| item.featured).collect()
+        let mut featured: Vec<_> = self.items.values().filter(|item| item.featured).collect();
+        featured.sort_by_key(|item| (std::cmp::Reverse(item.downloads), &item.id));
+        featured
     }
 
     pub fn get_categories(&self) -> Vec<CategoryInfo> {
@@ -429,15 +855,112 @@ impl MarketplaceService {
     // Installation
     // ============================================
 
-    pub fn install(&mut self, item_id: &str) -> Result<InstalledItem, String> {
+    /// Cached package for a checksum, if an earlier install verified it and
+    /// the file still matches. A cached file that no longer does is removed.
+    pub fn cached_package(&self, checksum: &str) -> Option<PathBuf> {
+        let checksum = checksum.trim().to_lowercase();
+        if checksum.len() != 64 || !checksum.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let path = self.cache_dir.join(format!("{}.pkg", checksum));
+        let bytes = std::fs::read(&path).ok()?;
+        if sha256_hex(&bytes) != checksum {
+            eprintln!("Discarding cached package that fails its checksum: {}", path.display());
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        Some(path)
+    }
+
+    /// Verify downloaded bytes and store them. Verified packages go into the
+    /// checksum-keyed cache so a re-install can skip download and verification.
+    pub fn store_package(
+        &self,
+        item_id: &str,
+        package: &PackageInfo,
+        bytes: &[u8],
+    ) -> Result<(PathBuf, Verification), MarketplaceError> {
+        let verification = verify_package(item_id, package, bytes, &self.trusted_keys, self.allow_unverified)?;
+
+        // A verified package's checksum is the hash of its bytes
+        let file_name = match verification {
+            Verification::Verified => format!("{}.pkg", sha256_hex(bytes)),
+            Verification::Unverified => format!("unverified-{}.pkg", sha256_hex(bytes)),
+        };
+        let path = self.cache_dir.join(file_name);
+        write_atomically(&path, bytes)?;
+
+        Ok((path, verification))
+    }
+
+    /// Decide how an install proceeds: from the cache, by downloading, or
+    /// without a package
+    pub fn plan_install(&self, item_id: &str) -> Result<InstallPlan, MarketplaceError> {
         let item = self.items.get(item_id)
-            .ok_or_else(|| format!("Item not found: {}", item_id))?;
+            .ok_or_else(|| MarketplaceError::NotFound(item_id.to_string()))?;
+
+        match &item.package {
+            Some(package) => {
+                let cached = package.checksum.as_deref().and_then(|c| self.cached_package(c));
+                Ok(match cached {
+                    Some(path) => InstallPlan::Cached(path),
+                    None => InstallPlan::Download(package.clone()),
+                })
+            }
+            None if self.allow_unverified => Ok(InstallPlan::NoPackage),
+            None => Err(MarketplaceError::UnverifiedItem {
+                item_id: item_id.to_string(),
+                reason: "no package checksum or signature".to_string(),
+            }),
+        }
+    }
 
+    /// Install an item, downloading and verifying its package unless a
+    /// verified copy is already cached
+    pub async fn install_verified(&mut self, item_id: &str) -> Result<InstalledItem, MarketplaceError> {
+        let (package_path, verification) = match self.plan_install(item_id)? {
+            InstallPlan::Cached(path) => (Some(path), Verification::Verified),
+            InstallPlan::Download(package) => {
+                let bytes = fetch_package(&package.download_url).await?;
+                let (path, verification) = self.store_package(item_id, &package, &bytes)?;
+                (Some(path), verification)
+            }
+            InstallPlan::NoPackage => (None, Verification::Unverified),
+        };
+
+        Ok(self.record_install(item_id, package_path, verification))
+    }
+
+    /// Drop cached packages, e.g. after a trusted key is revoked
+    pub fn clear_package_cache(&self) -> Result<(), MarketplaceError> {
+        let entries = match std::fs::read_dir(&self.cache_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(MarketplaceError::Io(e.to_string())),
+        };
+        for entry in entries {
+            let path = entry.map_err(|e| MarketplaceError::Io(e.to_string()))?.path();
+            if path.extension().is_some_and(|ext| ext == "pkg") {
+                std::fs::remove_file(&path).map_err(|e| MarketplaceError::Io(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record_install(
+        &mut self,
+        item_id: &str,
+        package_path: Option<PathBuf>,
+        verification: Verification,
+    ) -> InstalledItem {
+        let version = self.items.get(item_id).map(|item| item.version.clone()).unwrap_or_default();
         let installed = InstalledItem {
             item_id: item_id.to_string(),
-            version: item.version.clone(),
+            version,
             installed_at: chrono::Utc::now().timestamp(),
             auto_update: true,
+            package_path: package_path.map(|p| p.to_string_lossy().into_owned()),
+            verification: Some(verification),
         };
 
         self.installed.insert(item_id.to_string(), installed.clone());
@@ -447,7 +970,7 @@ impl MarketplaceService {
             item.downloads += 1;
         }
 
-        Ok(installed)
+        installed
     }
 
     pub fn uninstall(&mut self, item_id: &str) -> Result<(), String> {
@@ -545,3 +1068,299 @@ pub struct CategoryInfo {
     pub name: String,
     pub count: u32,
 }
+
+/// Name matches rank above tag matches, which rank above description matches
+fn relevance_score(item: &MarketplaceItem, q_lower: &str) -> u32 {
+    if q_lower.is_empty() {
+        return 0;
+    }
+    let name = item.name.to_lowercase();
+    let mut score = 0;
+    if name == q_lower {
+        score += 8;
+    } else if name.contains(q_lower) {
+        score += 4;
+    }
+    if item.tags.iter().any(|t| t.to_lowercase().contains(q_lower)) {
+        score += 2;
+    }
+    if item.description.to_lowercase().contains(q_lower) {
+        score += 1;
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    struct Publisher {
+        key_pair: Ed25519KeyPair,
+    }
+
+    impl Publisher {
+        fn new() -> Self {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+            Self { key_pair: Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap() }
+        }
+
+        fn public_key(&self) -> String {
+            BASE64.encode(self.key_pair.public_key().as_ref())
+        }
+
+        /// A package served from `url` whose published bytes are `bytes`
+        fn package(&self, url: &str, bytes: &[u8]) -> PackageInfo {
+            PackageInfo {
+                download_url: url.to_string(),
+                checksum: Some(sha256_hex(bytes)),
+                signature: Some(BASE64.encode(self.key_pair.sign(bytes).as_ref())),
+                signing_key_id: Some("official".to_string()),
+            }
+        }
+    }
+
+    fn service(dir: &Path, publisher: &Publisher) -> MarketplaceService {
+        let mut service = MarketplaceService::with_cache_dir(dir.join("cache"));
+        service.trusted_keys.add("official", &publisher.public_key()).unwrap();
+        service
+    }
+
+    #[tokio::test]
+    async fn test_verified_install_is_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let publisher = Publisher::new();
+        let mut service = service(dir.path(), &publisher);
+        let (url, server) = serve(b"plugin payload".to_vec()).await;
+        service.items.get_mut("plugin-github").unwrap().package =
+            Some(publisher.package(&url, b"plugin payload"));
+
+        let installed = service.install_verified("plugin-github").await.unwrap();
+        assert_eq!(installed.verification, Some(Verification::Verified));
+        let cached = PathBuf::from(installed.package_path.unwrap());
+        assert_eq!(std::fs::read(&cached).unwrap(), b"plugin payload");
+
+        // Re-install comes from the cache without downloading again
+        server.abort();
+        let _ = server.await;
+        assert!(matches!(service.plan_install("plugin-github").unwrap(), InstallPlan::Cached(_)));
+        let reinstalled = service.install_verified("plugin-github").await.unwrap();
+        assert_eq!(reinstalled.package_path, Some(cached.to_string_lossy().into_owned()));
+
+        // A cached file altered on disk is discarded, not installed
+        std::fs::write(&cached, b"plugin payload with a backdoor").unwrap();
+        assert!(matches!(service.plan_install("plugin-github").unwrap(), InstallPlan::Download(_)));
+        assert!(!cached.exists());
+        assert!(service.install_verified("plugin-github").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_packages_are_only_fetched_over_https() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = dir.path().join("secret");
+        std::fs::write(&secret, b"secret").unwrap();
+
+        for url in [format!("file://{}", secret.display()), "ftp://example.com/plugin".to_string()] {
+            assert!(matches!(fetch_package(&url).await, Err(MarketplaceError::Download(_))), "{}", url);
+        }
+        let remote_http = reqwest::Url::parse("http://example.com/plugin").unwrap();
+        assert!(!is_fetchable_url(&remote_http));
+        for url in ["https://example.com/plugin", "http://localhost:8080/x", "http://127.0.0.1/x", "http://[::1]/x"] {
+            assert!(is_fetchable_url(&reqwest::Url::parse(url).unwrap()), "{}", url);
+        }
+
+        // Not even an index can point a package at a local file
+        let publisher = Publisher::new();
+        let mut service = service(dir.path(), &publisher);
+        service.allow_unverified = true;
+        service.items.get_mut("plugin-github").unwrap().package =
+            Some(publisher.package(&format!("file://{}", secret.display()), b"secret"));
+        assert!(matches!(service.install_verified("plugin-github").await, Err(MarketplaceError::Download(_))));
+    }
+
+    #[tokio::test]
+    async fn test_tampered_payload_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let publisher = Publisher::new();
+        let mut service = service(dir.path(), &publisher);
+        service.allow_unverified = true;
+
+        let (url, _server) = serve(b"plugin payload with a backdoor".to_vec()).await;
+        let mut package = publisher.package(&url, b"plugin payload");
+        service.items.get_mut("plugin-github").unwrap().package = Some(package.clone());
+
+        // Even the development override does not accept a modified payload
+        let err = service.install_verified("plugin-github").await.unwrap_err();
+        assert_eq!(err, MarketplaceError::UnverifiedItem {
+            item_id: "plugin-github".to_string(),
+            reason: "checksum mismatch".to_string(),
+        });
+
+        // Recomputing the checksum does not help without the publisher's key
+        package.checksum = Some(sha256_hex(b"plugin payload with a backdoor"));
+        service.items.get_mut("plugin-github").unwrap().package = Some(package);
+        let err = service.install_verified("plugin-github").await.unwrap_err();
+        assert!(matches!(err, MarketplaceError::UnverifiedItem { ref reason, .. } if reason == "signature does not match"));
+
+        assert!(!service.is_installed("plugin-github"));
+        assert!(!service.cache_dir.exists() || std::fs::read_dir(&service.cache_dir).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_unsigned_or_untrusted_items_need_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let publisher = Publisher::new();
+        let stranger = Publisher::new();
+        let service_with_keys = service(dir.path(), &publisher);
+
+        let unsigned = PackageInfo { signature: None, ..publisher.package("https://example.com/a", b"a") };
+        let foreign = PackageInfo {
+            signing_key_id: Some("stranger".to_string()),
+            ..stranger.package("https://example.com/b", b"b")
+        };
+
+        for (package, bytes) in [(&unsigned, &b"a"[..]), (&foreign, &b"b"[..])] {
+            assert!(matches!(
+                verify_package("item", package, bytes, &service_with_keys.trusted_keys, false),
+                Err(MarketplaceError::UnverifiedItem { .. })
+            ));
+            assert_eq!(
+                verify_package("item", package, bytes, &service_with_keys.trusted_keys, true),
+                Ok(Verification::Unverified)
+            );
+        }
+
+        // Sample items ship no package at all
+        assert!(matches!(
+            service_with_keys.plan_install("plugin-github"),
+            Err(MarketplaceError::UnverifiedItem { .. })
+        ));
+    }
+
+    /// Serve `body` to each request on a loopback port until the task is aborted
+    async fn serve(body: Vec<u8>) -> (String, tokio::task::JoinHandle<()>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/index.json", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await;
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len(),
+                );
+                let _ = socket.write_all(header.as_bytes()).await;
+                let _ = socket.write_all(&body).await;
+            }
+        });
+        (url, handle)
+    }
+
+    fn remote_items(count: usize) -> Vec<MarketplaceItem> {
+        let template = MarketplaceService::with_cache_dir(PathBuf::new()).items["plugin-github"].clone();
+        (0..count)
+            .map(|i| MarketplaceItem {
+                id: format!("remote-{:02}", i),
+                name: format!("Remote {}", i),
+                downloads: i as u64 * 10,
+                updated_at: 1_000 - i as i64,
+                featured: i == 3,
+                ..template.clone()
+            })
+            .collect()
+    }
+
+    fn query(sort_by: SortBy, page: u32, per_page: u32) -> SearchQuery {
+        SearchQuery {
+            query: None,
+            item_type: None,
+            category: None,
+            tags: vec![],
+            price_type: None,
+            sort_by,
+            page,
+            per_page,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_is_cached_for_offline_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, server) = serve(serde_json::to_vec(&remote_items(25)).unwrap()).await;
+
+        let mut service = MarketplaceService::with_cache_dir(dir.path().to_path_buf());
+        service.set_index_url(Some(url.clone()));
+        assert!(!service.refresh_index().await);
+        assert_eq!(service.items.len(), 25);
+
+        // Within the TTL no request is made
+        assert_eq!(service.index_refresh_url(), None);
+        assert!(service.refresh_index().await);
+
+        // Server gone and TTL expired: the cached index is still served
+        server.abort();
+        let _ = server.await;
+        service.index_ttl = Duration::ZERO;
+        assert!(service.refresh_index().await);
+        assert_eq!(service.items.len(), 25);
+
+        // A fresh start reads the index from disk while offline
+        let mut offline = MarketplaceService::with_cache_dir(dir.path().to_path_buf());
+        assert_eq!(offline.index_url.as_deref(), Some(url.as_str()));
+        assert!(offline.refresh_index().await);
+        assert_eq!(offline.search(query(SortBy::Relevance, 1, 100)).total, 25);
+        assert_eq!(offline.get_featured().len(), 1);
+        assert_eq!(offline.get_categories().iter().map(|c| c.count).sum::<u32>(), 25);
+
+        // Clearing packages keeps the index
+        offline.clear_package_cache().unwrap();
+        assert!(dir.path().join(INDEX_CACHE_FILE).exists());
+
+        // Another index never serves this one's cached items
+        let bundled = MarketplaceService::with_cache_dir(PathBuf::new()).items.len();
+        offline.set_index_url(Some("http://127.0.0.1:9/other.json".to_string()));
+        assert_eq!(offline.items.len(), bundled);
+        assert_eq!(offline.index_refresh_url().as_deref(), Some("http://127.0.0.1:9/other.json"));
+        assert!(!offline.refresh_index().await);
+        assert!(offline.get_item("remote-00").is_none());
+
+        // Switching back finds the cache again
+        offline.set_index_url(Some(url));
+        assert_eq!(offline.items.len(), 25);
+        assert!(offline.refresh_index().await);
+    }
+
+    #[tokio::test]
+    async fn test_search_pages_and_sorts_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let (url, server) = serve(serde_json::to_vec(&remote_items(25)).unwrap()).await;
+        let mut service = MarketplaceService::with_cache_dir(dir.path().to_path_buf());
+        service.set_index_url(Some(url));
+        service.refresh_index().await;
+        server.abort();
+
+        let first = service.search(query(SortBy::Downloads, 1, 10));
+        assert_eq!((first.total, first.total_pages, first.items.len()), (25, 3, 10));
+        assert_eq!(first.items[0].id, "remote-24");
+
+        let last = service.search(query(SortBy::Downloads, 3, 10));
+        assert_eq!(last.items.len(), 5);
+        assert_eq!(last.items[4].id, "remote-00");
+
+        // Out of range pages clamp to the last page
+        assert_eq!(service.search(query(SortBy::Downloads, 9, 10)).page, 3);
+
+        let updated = service.search(query(SortBy::Updated, 1, 2));
+        assert_eq!(updated.items[0].id, "remote-00");
+
+        let mut relevant = query(SortBy::Relevance, 1, 5);
+        relevant.query = Some("remote 7".to_string());
+        let result = service.search(relevant);
+        assert_eq!(result.total, 1);
+        assert_eq!(result.items[0].id, "remote-07");
+    }
+}
@@ -12,8 +12,15 @@ use tokio::sync::Mutex;
 
 use crate::marketplace::{
     MarketplaceService, MarketplaceItem, MarketplaceItemType, SearchQuery,
-    SearchResult, InstalledItem, Review, CategoryInfo, SortBy, PriceType,
+    SearchResult, InstalledItem, Review, CategoryInfo, SortBy, PriceType, TrustedKeys,
 };
+use crate::workspace_db::WorkspaceDbManager;
+
+/// App setting holding the trusted package signing keys
+const TRUSTED_KEYS_SETTING: &str = "marketplace.trusted_keys";
+
+/// App setting that allows unverified installs (development only)
+const ALLOW_UNVERIFIED_SETTING: &str = "marketplace.allow_unverified";
 
 // ============================================
 // State Types
@@ -21,14 +28,73 @@ use crate::marketplace::{
 
 pub struct MarketplaceState {
     pub service: MarketplaceService,
+    db_manager: Arc<WorkspaceDbManager>,
 }
 
 impl MarketplaceState {
-    pub fn new() -> Self {
-        Self {
-            service: MarketplaceService::new(),
+    /// Marketplace with the trusted keys and unverified-install override saved in app settings
+    pub fn new(db_manager: Arc<WorkspaceDbManager>) -> Self {
+        Self::with_service(MarketplaceService::new(), db_manager)
+    }
+
+    fn with_service(mut service: MarketplaceService, db_manager: Arc<WorkspaceDbManager>) -> Self {
+        let setting = |key: &str| db_manager.get_app_setting(key).ok().flatten();
+        if let Some(json) = setting(TRUSTED_KEYS_SETTING) {
+            service.trusted_keys = TrustedKeys::from_json(&json);
         }
+        service.allow_unverified = setting(ALLOW_UNVERIFIED_SETTING).is_some_and(|value| value == "true");
+        Self { service, db_manager }
+    }
+
+    pub fn set_allow_unverified(&mut self, allow: bool) -> Result<(), String> {
+        self.db_manager.set_app_setting(ALLOW_UNVERIFIED_SETTING, if allow { "true" } else { "false" })
+            .map_err(|e| e.to_string())?;
+        self.service.allow_unverified = allow;
+        Ok(())
+    }
+
+    pub fn add_trusted_key(&mut self, key_id: &str, public_key: &str) -> Result<(), String> {
+        let mut keys = self.service.trusted_keys.clone();
+        keys.add(key_id, public_key)?;
+        self.save_trusted_keys(keys)
+    }
+
+    /// Revoke a trusted key. Cached packages are dropped so they are verified again.
+    pub fn remove_trusted_key(&mut self, key_id: &str) -> Result<(), String> {
+        let mut keys = self.service.trusted_keys.clone();
+        if !keys.remove(key_id) {
+            return Err(format!("Unknown key: {}", key_id));
+        }
+        self.save_trusted_keys(keys)?;
+        self.service.clear_package_cache().map_err(|e| e.to_string())
+    }
+
+    fn save_trusted_keys(&mut self, keys: TrustedKeys) -> Result<(), String> {
+        self.db_manager.set_app_setting(TRUSTED_KEYS_SETTING, &keys.to_json())
+            .map_err(|e| e.to_string())?;
+        self.service.trusted_keys = keys;
+        Ok(())
+    }
+}
+
+// ============================================
+// Index Commands
+// ============================================
+
+/// Point the marketplace at a remote index. The next listing fetches it
+/// unless a fresh copy from the same URL is cached.
+#[tauri::command]
+pub async fn marketplace_set_index_url(
+    state: State<'_, Arc<Mutex<MarketplaceState>>>,
+    url: Option<String>,
+    ttl_secs: Option<u64>,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.service.set_index_url(url);
+    if let Some(ttl_secs) = ttl_secs {
+        state.service.index_ttl = std::time::Duration::from_secs(ttl_secs);
     }
+    Ok(())
 }
 
 // ============================================
@@ -47,7 +113,8 @@ pub async fn marketplace_search(
     page: Option<u32>,
     per_page: Option<u32>,
 ) -> Result<SearchResult, String> {
-    let state = state.lock().await;
+    let mut state = state.lock().await;
+    let from_cache = state.service.refresh_index().await;
     
     let search_query = SearchQuery {
         query,
@@ -60,7 +127,9 @@ pub async fn marketplace_search(
         per_page: per_page.unwrap_or(20),
     };
     
-    Ok(state.service.search(search_query))
+    let mut result = state.service.search(search_query);
+    result.from_cache = from_cache;
+    Ok(result)
 }
 
 #[tauri::command]
@@ -78,7 +147,8 @@ pub async fn marketplace_get_item(
 pub async fn marketplace_get_featured(
     state: State<'_, Arc<Mutex<MarketplaceState>>>,
 ) -> Result<Vec<MarketplaceItem>, String> {
-    let state = state.lock().await;
+    let mut state = state.lock().await;
+    state.service.refresh_index().await;
     Ok(state.service.get_featured().into_iter().cloned().collect())
 }
 
@@ -86,7 +156,8 @@ pub async fn marketplace_get_featured(
 pub async fn marketplace_get_categories(
     state: State<'_, Arc<Mutex<MarketplaceState>>>,
 ) -> Result<Vec<CategoryInfo>, String> {
-    let state = state.lock().await;
+    let mut state = state.lock().await;
+    state.service.refresh_index().await;
     Ok(state.service.get_categories())
 }
 
@@ -94,13 +165,53 @@ pub async fn marketplace_get_categories(
 // Installation Commands
 // ============================================
 
+/// Install an item after verifying its package checksum and signature
 #[tauri::command]
 pub async fn marketplace_install(
     state: State<'_, Arc<Mutex<MarketplaceState>>>,
     item_id: String,
 ) -> Result<InstalledItem, String> {
     let mut state = state.lock().await;
-    state.service.install(&item_id)
+    state.service.install_verified(&item_id).await.map_err(|e| e.to_string())
+}
+
+/// Allow installing items without a valid signature (development only)
+#[tauri::command]
+pub async fn marketplace_set_allow_unverified(
+    state: State<'_, Arc<Mutex<MarketplaceState>>>,
+    allow: bool,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.set_allow_unverified(allow)
+}
+
+#[tauri::command]
+pub async fn marketplace_list_trusted_keys(
+    state: State<'_, Arc<Mutex<MarketplaceState>>>,
+) -> Result<Vec<String>, String> {
+    let state = state.lock().await;
+    Ok(state.service.trusted_keys.key_ids())
+}
+
+/// Trust a base64-encoded Ed25519 public key for package signatures
+#[tauri::command]
+pub async fn marketplace_add_trusted_key(
+    state: State<'_, Arc<Mutex<MarketplaceState>>>,
+    key_id: String,
+    public_key: String,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.add_trusted_key(&key_id, &public_key)
+}
+
+/// Revoke a trusted key. Cached packages are dropped so they are verified again.
+#[tauri::command]
+pub async fn marketplace_remove_trusted_key(
+    state: State<'_, Arc<Mutex<MarketplaceState>>>,
+    key_id: String,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.remove_trusted_key(&key_id)
 }
 
 #[tauri::command]
@@ -220,3 +331,36 @@ fn parse_sort_by(s: &str) -> Option<SortBy> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+    #[test]
+    fn test_trusted_keys_and_override_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_manager = Arc::new(WorkspaceDbManager::with_base_dir(dir.path()).unwrap());
+        let start = || MarketplaceState::with_service(
+            MarketplaceService::with_cache_dir(dir.path().join("cache")),
+            Arc::clone(&db_manager),
+        );
+
+        let mut state = start();
+        assert!(state.service.trusted_keys.key_ids().is_empty());
+        state.add_trusted_key("official", &BASE64.encode([7u8; 32])).unwrap();
+        state.add_trusted_key("partner", &BASE64.encode([9u8; 32])).unwrap();
+        assert!(state.add_trusted_key("short", &BASE64.encode([1u8; 8])).is_err());
+        state.set_allow_unverified(true).unwrap();
+
+        let mut restarted = start();
+        assert_eq!(restarted.service.trusted_keys.key_ids(), vec!["official", "partner"]);
+        assert!(restarted.service.allow_unverified);
+
+        restarted.remove_trusted_key("partner").unwrap();
+        restarted.set_allow_unverified(false).unwrap();
+        let restarted = start();
+        assert_eq!(restarted.service.trusted_keys.key_ids(), vec!["official"]);
+        assert!(!restarted.service.allow_unverified);
+    }
+}
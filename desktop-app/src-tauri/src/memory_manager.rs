@@ -9,9 +9,10 @@
 use anyhow::{Context, Result, anyhow};
 use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
-use crate::workspace_db::WorkspaceDbManager;
+use crate::llm_service::{ChatCompletion, ChatMessage};
+use crate::workspace_db::{sanitize_fts_query, WorkspaceDbManager};
 
 // ============================================
 // Memory Types
@@ -67,7 +68,14 @@ pub struct RetrievedContext {
     pub id: i64,
     pub title: String,
     pub content: String,
+    /// Combined score on 0..1, comparable across memory types
     pub relevance_score: f64,
+    /// Query match on 0..1 before blending; see "Retrieval Scoring"
+    #[serde(default)]
+    pub text_score: f64,
+    /// Stored confidence blended into long-term scores
+    #[serde(default)]
+    pub confidence: Option<f64>,
     pub source: String,
 }
 
@@ -160,17 +168,95 @@ pub struct RetrievalQuery {
     pub min_relevance: Option<f64>,
 }
 
+// ============================================
+// Session Summarization
+// ============================================
+
+/// Working memory category holding a session's rolling summary
+pub const SESSION_SUMMARY_CATEGORY: &str = "conversation_summary";
+
+const DEFAULT_SUMMARY_PROMPT: &str = "You maintain the running summary of a conversation between a user and a coding assistant. \
+Combine any existing summary with the new messages into one concise summary. Keep decisions, requirements, \
+file and function names, open questions and unfinished work; drop greetings and repetition. \
+Reply with the summary only.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizationConfig {
+    /// Most recent short-term messages left as they are
+    pub keep_recent_n: usize,
+    /// System prompt given to the summarizing model
+    pub prompt: String,
+    /// None uses the service's default model
+    pub model_id: Option<String>,
+    pub max_tokens: i32,
+}
+
+impl Default for SummarizationConfig {
+    fn default() -> Self {
+        Self {
+            keep_recent_n: 20,
+            prompt: DEFAULT_SUMMARY_PROMPT.to_string(),
+            model_id: None,
+            max_tokens: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub summary: WorkingMemory,
+    /// Short-term messages folded into the summary and deleted
+    pub summarized_count: usize,
+}
+
+// ============================================
+// Retrieval Scoring
+// ============================================
+//
+// Every source is mapped onto 0..1 before results are merged:
+//
+//   long-term: relevance = 0.6 * text + 0.4 * confidence
+//              text      = bm25 / max(bm25 over the batch), bm25 = -(FTS5 rank)
+//   working:   relevance = text
+//              text      = share of query words found in the content
+//
+// Scaling by the batch maximum keeps the best FTS hit at 1.0 while preserving
+// how much weaker the others are; raw BM25 values are unbounded and depend on
+// corpus size, so they cannot be compared with keyword overlap directly.
+
+const LONG_TERM_TEXT_WEIGHT: f64 = 0.6;
+const LONG_TERM_CONFIDENCE_WEIGHT: f64 = 0.4;
+
+/// Scale FTS5 `rank` values (negated BM25, lower is better) to 0..1
+fn normalize_fts_ranks(ranks: &[f64]) -> Vec<f64> {
+    let scores: Vec<f64> = ranks.iter().map(|rank| (-rank).max(0.0)).collect();
+    let max = scores.iter().copied().fold(0.0, f64::max);
+    if max <= 0.0 {
+        // Every hit matched equally well
+        return vec![1.0; scores.len()];
+    }
+    scores.iter().map(|score| score / max).collect()
+}
+
+fn long_term_relevance(text_score: f64, confidence: f64) -> f64 {
+    LONG_TERM_TEXT_WEIGHT * text_score + LONG_TERM_CONFIDENCE_WEIGHT * confidence.clamp(0.0, 1.0)
+}
+
 // ============================================
 // Memory Manager
 // ============================================
 
 pub struct MemoryManager {
     db_manager: Arc<WorkspaceDbManager>,
+    summarization: RwLock<SummarizationConfig>,
 }
 
 impl MemoryManager {
     pub fn new(db_manager: Arc<WorkspaceDbManager>) -> Self {
-        Self { db_manager }
+        Self {
+            db_manager,
+            summarization: RwLock::new(SummarizationConfig::default()),
+        }
     }
     
     // ========================================
@@ -192,7 +278,7 @@ impl MemoryManager {
             (now + chrono::Duration::minutes(ttl as i64)).to_rfc3339()
         });
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "INSERT INTO memory_short (session_id, role, content, tool_calls_json, tool_results_json, tokens_used, model_id, created_at, expires_at)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
@@ -206,7 +292,7 @@ impl MemoryManager {
                 created_at,
                 expires_at,
             ],
-        ).context("Failed to add short-term memory")?;
+        )).context("Failed to add short-term memory")?;
         
         let id = db.conn.last_insert_rowid();
         
@@ -240,7 +326,7 @@ impl MemoryManager {
             "SELECT id, session_id, role, content, tool_calls_json, tool_results_json, tokens_used, model_id, created_at, expires_at
              FROM memory_short
              WHERE session_id = ? AND (expires_at IS NULL OR expires_at > datetime('now'))
-             ORDER BY created_at DESC
+             ORDER BY created_at DESC, id DESC
              LIMIT ?"
         ).context("Failed to prepare query")?;
         
@@ -275,10 +361,10 @@ impl MemoryManager {
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
         
-        let deleted = db.conn.execute(
+        let deleted = self.db_manager.with_write_retry(|| db.conn.execute(
             "DELETE FROM memory_short WHERE session_id = ?",
             params![session_id],
-        ).context("Failed to clear session memory")?;
+        )).context("Failed to clear session memory")?;
         
         Ok(deleted)
     }
@@ -309,7 +395,7 @@ impl MemoryManager {
             0
         };
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "INSERT INTO memory_working (session_id, category, title, content, is_pinned, pin_order, source, created_at, updated_at)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
@@ -323,7 +409,7 @@ impl MemoryManager {
                 now,
                 now,
             ],
-        ).context("Failed to add working memory")?;
+        )).context("Failed to add working memory")?;
         
         let id = db.conn.last_insert_rowid();
         
@@ -390,15 +476,15 @@ impl MemoryManager {
                 |row| row.get(0),
             ).unwrap_or(1);
             
-            db.conn.execute(
+            self.db_manager.with_write_retry(|| db.conn.execute(
                 "UPDATE memory_working SET is_pinned = 1, pin_order = ?, updated_at = ? WHERE id = ?",
                 params![pin_order, now, memory_id],
-            ).context("Failed to pin memory")?;
+            )).context("Failed to pin memory")?;
         } else {
-            db.conn.execute(
+            self.db_manager.with_write_retry(|| db.conn.execute(
                 "UPDATE memory_working SET is_pinned = 0, pin_order = 0, updated_at = ? WHERE id = ?",
                 params![now, memory_id],
-            ).context("Failed to unpin memory")?;
+            )).context("Failed to unpin memory")?;
         }
         
         Ok(())
@@ -412,10 +498,10 @@ impl MemoryManager {
         let now = chrono::Utc::now().to_rfc3339();
         
         for (index, id) in memory_ids.iter().enumerate() {
-            db.conn.execute(
+            self.db_manager.with_write_retry(|| db.conn.execute(
                 "UPDATE memory_working SET pin_order = ?, updated_at = ? WHERE id = ? AND is_pinned = 1",
                 params![index as i32 + 1, now, id],
-            ).context("Failed to reorder pinned memory")?;
+            )).context("Failed to reorder pinned memory")?;
         }
         
         Ok(())
@@ -438,7 +524,7 @@ impl MemoryManager {
         let tags_json = request.tags.map(|t| serde_json::to_string(&t).unwrap_or_default());
         let confidence = request.confidence.unwrap_or(1.0);
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "INSERT INTO memory_long (category, title, content, tags_json, source, confidence, access_count, created_at, updated_at)
              VALUES (?, ?, ?, ?, ?, ?, 0, ?, ?)",
             params![
@@ -451,7 +537,7 @@ impl MemoryManager {
                 now,
                 now,
             ],
-        ).context("Failed to add long-term memory")?;
+        )).context("Failed to add long-term memory")?;
         
         let id = db.conn.last_insert_rowid();
         
@@ -486,25 +572,25 @@ impl MemoryManager {
         let now = chrono::Utc::now().to_rfc3339();
         
         if let Some(t) = title {
-            db.conn.execute(
+            self.db_manager.with_write_retry(|| db.conn.execute(
                 "UPDATE memory_long SET title = ?, updated_at = ? WHERE id = ?",
                 params![t, now, memory_id],
-            ).context("Failed to update title")?;
+            )).context("Failed to update title")?;
         }
         
         if let Some(c) = content {
-            db.conn.execute(
+            self.db_manager.with_write_retry(|| db.conn.execute(
                 "UPDATE memory_long SET content = ?, updated_at = ? WHERE id = ?",
                 params![c, now, memory_id],
-            ).context("Failed to update content")?;
+            )).context("Failed to update content")?;
         }
         
         if let Some(t) = tags {
             let tags_json = serde_json::to_string(&t).unwrap_or_default();
-            db.conn.execute(
+            self.db_manager.with_write_retry(|| db.conn.execute(
                 "UPDATE memory_long SET tags_json = ?, updated_at = ? WHERE id = ?",
                 params![tags_json, now, memory_id],
-            ).context("Failed to update tags")?;
+            )).context("Failed to update tags")?;
         }
         
         Ok(())
@@ -547,7 +633,7 @@ impl MemoryManager {
                     created_at: row.get(10)?,
                     updated_at: row.get(11)?,
                 })
-            }).context("Failed to query long-term memory")?
+            }).context("Failed to query long-term memory")?.collect::<Vec<_>>()
         } else {
             stmt.query_map(params![limit], |row| {
                 Ok(LongTermMemory {
@@ -564,7 +650,7 @@ impl MemoryManager {
                     created_at: row.get(10)?,
                     updated_at: row.get(11)?,
                 })
-            }).context("Failed to query long-term memory")?
+            }).context("Failed to query long-term memory")?.collect::<Vec<_>>()
         };
         
         let mut result = Vec::new();
@@ -585,6 +671,7 @@ impl MemoryManager {
         query: RetrievalQuery,
     ) -> Result<Vec<RetrievedContext>> {
         let mut results = Vec::new();
+        let min_relevance = query.min_relevance.unwrap_or(0.0);
         
         // 1. Search long-term memory using FTS
         if query.include_long_term {
@@ -595,15 +682,23 @@ impl MemoryManager {
                 query.limit,
             )?;
             
-            for memory in long_term {
-                results.push(RetrievedContext {
-                    memory_type: "long_term".to_string(),
-                    id: memory.id,
-                    title: memory.title,
-                    content: memory.content,
-                    relevance_score: memory.confidence,
-                    source: memory.source,
-                });
+            let ranks: Vec<f64> = long_term.iter().map(|(_, rank)| *rank).collect();
+            let text_scores = normalize_fts_ranks(&ranks);
+            
+            for ((memory, _), text_score) in long_term.into_iter().zip(text_scores) {
+                let relevance = long_term_relevance(text_score, memory.confidence);
+                if relevance > min_relevance {
+                    results.push(RetrievedContext {
+                        memory_type: "long_term".to_string(),
+                        id: memory.id,
+                        title: memory.title,
+                        content: memory.content,
+                        relevance_score: relevance,
+                        text_score,
+                        confidence: Some(memory.confidence),
+                        source: memory.source,
+                    });
+                }
             }
         }
         
@@ -615,21 +710,23 @@ impl MemoryManager {
                 // Simple keyword matching for relevance
                 let relevance = self.calculate_keyword_relevance(&query.query, &memory.content);
                 
-                if relevance > query.min_relevance.unwrap_or(0.0) {
+                if relevance > min_relevance {
                     results.push(RetrievedContext {
                         memory_type: "working".to_string(),
                         id: memory.id,
                         title: memory.title,
                         content: memory.content,
                         relevance_score: relevance,
+                        text_score: relevance,
+                        confidence: None,
                         source: memory.source,
                     });
                 }
             }
         }
         
-        // 3. Sort by relevance
-        results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
+        // 3. Sort by relevance; scores share one scale, so sources interleave
+        results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
         
         // 4. Limit results
         let limit = query.limit.unwrap_or(10) as usize;
@@ -651,7 +748,12 @@ impl MemoryManager {
         query: &str,
         categories: Option<&[String]>,
         limit: Option<i32>,
-    ) -> Result<Vec<LongTermMemory>> {
+    ) -> Result<Vec<(LongTermMemory, f64)>> {
+        let Some(query) = sanitize_fts_query(query, false) else {
+            return Ok(Vec::new());
+        };
+        let query = query.as_str();
+        
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
@@ -661,7 +763,7 @@ impl MemoryManager {
         // Use FTS5 for full-text search
         let sql = if categories.is_some() && !categories.unwrap().is_empty() {
             format!(
-                "SELECT m.id, m.category, m.title, m.content, m.tags_json, m.source, m.confidence, m.access_count, m.last_accessed_at, m.embedding_json, m.created_at, m.updated_at
+                "SELECT m.id, m.category, m.title, m.content, m.tags_json, m.source, m.confidence, m.access_count, m.last_accessed_at, m.embedding_json, m.created_at, m.updated_at, fts.rank
                  FROM memory_long m
                  JOIN memory_long_fts fts ON m.id = fts.rowid
                  WHERE memory_long_fts MATCH ? AND m.category IN ({})
@@ -670,7 +772,7 @@ impl MemoryManager {
                 categories.unwrap().iter().map(|_| "?").collect::<Vec<_>>().join(",")
             )
         } else {
-            "SELECT m.id, m.category, m.title, m.content, m.tags_json, m.source, m.confidence, m.access_count, m.last_accessed_at, m.embedding_json, m.created_at, m.updated_at
+            "SELECT m.id, m.category, m.title, m.content, m.tags_json, m.source, m.confidence, m.access_count, m.last_accessed_at, m.embedding_json, m.created_at, m.updated_at, fts.rank
              FROM memory_long m
              JOIN memory_long_fts fts ON m.id = fts.rowid
              WHERE memory_long_fts MATCH ?
@@ -688,7 +790,7 @@ impl MemoryManager {
             params_vec.push(Box::new(limit));
             
             stmt.query_map(rusqlite::params_from_iter(params_vec.iter().map(|p| p.as_ref())), |row| {
-                Ok(LongTermMemory {
+                Ok((LongTermMemory {
                     id: row.get(0)?,
                     category: row.get(1)?,
                     title: row.get(2)?,
@@ -701,11 +803,11 @@ impl MemoryManager {
                     embedding_json: row.get(9)?,
                     created_at: row.get(10)?,
                     updated_at: row.get(11)?,
-                })
-            }).context("Failed to search long-term memory")?
+                }, row.get(12)?))
+            }).context("Failed to search long-term memory")?.collect::<Vec<_>>()
         } else {
             stmt.query_map(params![query, limit], |row| {
-                Ok(LongTermMemory {
+                Ok((LongTermMemory {
                     id: row.get(0)?,
                     category: row.get(1)?,
                     title: row.get(2)?,
@@ -718,8 +820,8 @@ impl MemoryManager {
                     embedding_json: row.get(9)?,
                     created_at: row.get(10)?,
                     updated_at: row.get(11)?,
-                })
-            }).context("Failed to search long-term memory")?
+                }, row.get(12)?))
+            }).context("Failed to search long-term memory")?.collect::<Vec<_>>()
         };
         
         let mut result = Vec::new();
@@ -731,7 +833,8 @@ impl MemoryManager {
     }
     
     fn calculate_keyword_relevance(&self, query: &str, content: &str) -> f64 {
-        let query_words: Vec<&str> = query.to_lowercase().split_whitespace().collect();
+        let query_lower = query.to_lowercase();
+        let query_words: Vec<&str> = query_lower.split_whitespace().collect();
         let content_lower = content.to_lowercase();
         
         let mut matches = 0;
@@ -755,10 +858,10 @@ impl MemoryManager {
         
         let now = chrono::Utc::now().to_rfc3339();
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "UPDATE memory_long SET access_count = access_count + 1, last_accessed_at = ? WHERE id = ?",
             params![now, memory_id],
-        ).context("Failed to increment memory access")?;
+        )).context("Failed to increment memory access")?;
         
         Ok(())
     }
@@ -803,20 +906,195 @@ impl MemoryManager {
     }
     
     // ========================================
-    // Cleanup Operations
+    // Session Summarization
     // ========================================
     
-    pub fn cleanup_expired_memories(&self, workspace_id: &str) -> Result<usize> {
+    pub fn summarization_config(&self) -> SummarizationConfig {
+        self.summarization.read()
+            .map(|c| c.clone())
+            .unwrap_or_default()
+    }
+    
+    pub fn set_summarization_config(&self, config: SummarizationConfig) {
+        if let Ok(mut current) = self.summarization.write() {
+            *current = config;
+        }
+    }
+    
+    /// The session's rolling summary, if it has been summarized before
+    pub fn get_session_summary(&self, workspace_id: &str, session_id: &str) -> Result<Option<WorkingMemory>> {
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
         
-        let deleted = db.conn.execute(
-            "DELETE FROM memory_short WHERE expires_at IS NOT NULL AND expires_at < datetime('now')",
-            [],
-        ).context("Failed to cleanup expired memories")?;
+        let summary = db.conn.query_row(
+            "SELECT id, session_id, category, title, content, is_pinned, pin_order, source, created_at, updated_at
+             FROM memory_working
+             WHERE session_id = ? AND category = ?
+             ORDER BY id DESC
+             LIMIT 1",
+            params![session_id, SESSION_SUMMARY_CATEGORY],
+            |row| {
+                Ok(WorkingMemory {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    category: row.get(2)?,
+                    title: row.get(3)?,
+                    content: row.get(4)?,
+                    is_pinned: row.get(5)?,
+                    pin_order: row.get(6)?,
+                    source: row.get(7)?,
+                    created_at: row.get(8)?,
+                    updated_at: row.get(9)?,
+                })
+            },
+        );
         
-        Ok(deleted)
+        match summary {
+            Ok(memory) => Ok(Some(memory)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to query session summary"),
+        }
+    }
+    
+    /// Fold all but the last `keep_recent_n` short-term messages (default
+    /// from the summarization config) into the session's summary entry in
+    /// working memory, then delete them. Returns None when there is nothing
+    /// to fold or no API key is configured.
+    pub async fn summarize_session(
+        &self,
+        llm: &dyn ChatCompletion,
+        workspace_id: &str,
+        session_id: &str,
+        keep_recent_n: Option<usize>,
+    ) -> Result<Option<SessionSummary>> {
+        let config = self.summarization_config();
+        let keep = keep_recent_n.unwrap_or(config.keep_recent_n);
+        
+        let history = self.get_session_memory(workspace_id, session_id, Some(i32::MAX))?;
+        if history.len() <= keep || !llm.has_api_key().await {
+            return Ok(None);
+        }
+        let older = &history[..history.len() - keep];
+        let previous = self.get_session_summary(workspace_id, session_id)?;
+        
+        let mut transcript = String::new();
+        if let Some(previous) = &previous {
+            transcript.push_str(&format!("Existing summary:\n{}\n\nNew messages:\n", previous.content));
+        }
+        for memory in older {
+            transcript.push_str(&format!("{}: {}\n", memory.role, memory.content));
+        }
+        
+        let message = |role: &str, content: String| ChatMessage {
+            role: role.to_string(),
+            content,
+            tool_calls: None,
+            tool_call_id: None,
+        };
+        let response = llm.chat(
+            vec![message("system", config.prompt.clone()), message("user", transcript)],
+            config.model_id.as_deref(),
+            Some(0.2),
+            Some(config.max_tokens),
+        ).await.context("Failed to summarize session")?;
+        
+        let content = response.choices.first()
+            .map(|c| c.message.content.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| anyhow!("Summarization returned no content"))?;
+        
+        let last_summarized = older.last().map(|m| m.id).unwrap_or_default();
+        let summary = self.store_session_summary(
+            workspace_id,
+            session_id,
+            previous.map(|p| p.id),
+            &content,
+            last_summarized,
+        )?;
+        
+        Ok(Some(SessionSummary {
+            summary,
+            summarized_count: older.len(),
+        }))
+    }
+    
+    /// Write the summary and drop the messages it covers in one transaction
+    fn store_session_summary(
+        &self,
+        workspace_id: &str,
+        session_id: &str,
+        existing_id: Option<i64>,
+        content: &str,
+        last_summarized: i64,
+    ) -> Result<WorkingMemory> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let now = chrono::Utc::now().to_rfc3339();
+        let title = "Conversation summary";
+        let source = "session_summary";
+        
+        let (id, created_at) = self.db_manager.with_write_retry(|| {
+            let tx = db.conn.unchecked_transaction()?;
+            
+            let saved = match existing_id {
+                Some(id) => {
+                    tx.execute(
+                        "UPDATE memory_working SET content = ?, updated_at = ? WHERE id = ?",
+                        params![content, now, id],
+                    )?;
+                    tx.query_row("SELECT id, created_at FROM memory_working WHERE id = ?", params![id], |row| {
+                        Ok((row.get(0)?, row.get(1)?))
+                    })?
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO memory_working (session_id, category, title, content, is_pinned, pin_order, source, created_at, updated_at)
+                         VALUES (?, ?, ?, ?, 0, 0, ?, ?, ?)",
+                        params![session_id, SESSION_SUMMARY_CATEGORY, title, content, source, now, now],
+                    )?;
+                    (tx.last_insert_rowid(), now.clone())
+                }
+            };
+            
+            tx.execute(
+                "DELETE FROM memory_short WHERE session_id = ? AND id <= ?",
+                params![session_id, last_summarized],
+            )?;
+            tx.commit()?;
+            Ok(saved)
+        }).context("Failed to store session summary")?;
+        
+        Ok(WorkingMemory {
+            id,
+            session_id: Some(session_id.to_string()),
+            category: SESSION_SUMMARY_CATEGORY.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            is_pinned: false,
+            pin_order: 0,
+            source: source.to_string(),
+            created_at,
+            updated_at: now,
+        })
+    }
+    
+    // ========================================
+    // Cleanup Operations
+    // ========================================
+    
+    /// Runs on a background connection so scheduled cleanup doesn't mark
+    /// the workspace as recently used
+    pub fn cleanup_expired_memories(&self, workspace_id: &str) -> Result<usize> {
+        self.db_manager.with_background_connection(workspace_id, |db| {
+            let deleted = self.db_manager.with_write_retry(|| db.conn.execute(
+                "DELETE FROM memory_short WHERE expires_at IS NOT NULL AND expires_at < datetime('now')",
+                [],
+            )).context("Failed to cleanup expired memories")?;
+            Ok(deleted)
+        })
     }
     
     pub fn get_memory_stats(&self, workspace_id: &str) -> Result<MemoryStats> {
@@ -872,3 +1150,176 @@ pub struct MemoryStats {
     pub long_term_count: i64,
     pub total_tokens: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_service::{ChatChoice, ChatResponse};
+    use std::sync::Mutex;
+    
+    /// Returns a canned summary and records the transcript it was given
+    struct StubLlm {
+        has_key: bool,
+        reply: String,
+        prompts: Mutex<Vec<String>>,
+    }
+    
+    impl StubLlm {
+        fn new(has_key: bool, reply: &str) -> Self {
+            Self { has_key, reply: reply.to_string(), prompts: Mutex::new(Vec::new()) }
+        }
+    }
+    
+    #[async_trait::async_trait]
+    impl ChatCompletion for StubLlm {
+        async fn chat(
+            &self,
+            messages: Vec<ChatMessage>,
+            _model_id: Option<&str>,
+            _temperature: Option<f64>,
+            _max_tokens: Option<i32>,
+        ) -> Result<ChatResponse> {
+            self.prompts.lock().unwrap().push(messages.last().unwrap().content.clone());
+            Ok(ChatResponse {
+                id: "stub".to_string(),
+                model: "stub".to_string(),
+                choices: vec![ChatChoice {
+                    index: 0,
+                    message: ChatMessage {
+                        role: "assistant".to_string(),
+                        content: self.reply.clone(),
+                        tool_calls: None,
+                        tool_call_id: None,
+                    },
+                    finish_reason: Some("stop".to_string()),
+                }],
+                usage: None,
+            })
+        }
+        
+        async fn has_api_key(&self) -> bool {
+            self.has_key
+        }
+    }
+    
+    fn setup(name: &str, turns: usize) -> (tempfile::TempDir, MemoryManager, String) {
+        let base_dir = tempfile::tempdir().unwrap();
+        let db_manager = Arc::new(WorkspaceDbManager::with_base_dir(base_dir.path()).unwrap());
+        let manager = MemoryManager::new(Arc::clone(&db_manager));
+        let workspace = db_manager.create_workspace(name, None).unwrap();
+        for i in 0..turns {
+            manager.add_short_term_memory(&workspace.id, AddShortTermMemoryRequest {
+                session_id: "session".to_string(),
+                role: if i % 2 == 0 { "user" } else { "assistant" }.to_string(),
+                content: format!("turn {}", i),
+                tool_calls_json: None,
+                tool_results_json: None,
+                tokens_used: None,
+                model_id: None,
+                ttl_minutes: None,
+            }).unwrap();
+        }
+        (base_dir, manager, workspace.id)
+    }
+    
+    fn contents(manager: &MemoryManager, workspace_id: &str) -> Vec<String> {
+        manager.get_session_memory(workspace_id, "session", None).unwrap()
+            .into_iter().map(|m| m.content).collect()
+    }
+    
+    #[tokio::test]
+    async fn test_summary_replaces_old_turns() {
+        let (_base_dir, manager, workspace_id) = setup("test-summarize", 6);
+        let llm = StubLlm::new(true, "User and assistant covered turns 0-3.");
+        
+        let result = manager.summarize_session(&llm, &workspace_id, "session", Some(2)).await.unwrap().unwrap();
+        assert_eq!(result.summarized_count, 4);
+        assert_eq!(result.summary.category, SESSION_SUMMARY_CATEGORY);
+        assert_eq!(contents(&manager, &workspace_id), vec!["turn 4", "turn 5"]);
+        
+        let prompt = llm.prompts.lock().unwrap()[0].clone();
+        assert!(prompt.contains("user: turn 0") && prompt.contains("assistant: turn 3"));
+        assert!(!prompt.contains("turn 4"));
+        
+        // A second pass folds into the same entry, building on the old summary
+        let llm = StubLlm::new(true, "Turns 0-4 summarized.");
+        let again = manager.summarize_session(&llm, &workspace_id, "session", Some(1)).await.unwrap().unwrap();
+        assert_eq!(again.summary.id, result.summary.id);
+        assert!(llm.prompts.lock().unwrap()[0].contains("User and assistant covered turns 0-3."));
+        assert_eq!(contents(&manager, &workspace_id), vec!["turn 5"]);
+        assert_eq!(
+            manager.get_session_summary(&workspace_id, "session").unwrap().unwrap().content,
+            "Turns 0-4 summarized.",
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_summarize_is_a_noop_without_api_key_or_old_turns() {
+        let (_base_dir, manager, workspace_id) = setup("test-summarize-noop", 4);
+        
+        let llm = StubLlm::new(false, "unused");
+        assert!(manager.summarize_session(&llm, &workspace_id, "session", Some(1)).await.unwrap().is_none());
+        assert!(llm.prompts.lock().unwrap().is_empty());
+        
+        manager.set_summarization_config(SummarizationConfig { keep_recent_n: 10, ..SummarizationConfig::default() });
+        let llm = StubLlm::new(true, "unused");
+        assert!(manager.summarize_session(&llm, &workspace_id, "session", None).await.unwrap().is_none());
+        
+        assert_eq!(contents(&manager, &workspace_id).len(), 4);
+        assert!(manager.get_session_summary(&workspace_id, "session").unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_normalize_fts_ranks() {
+        assert_eq!(normalize_fts_ranks(&[-4.0, -2.0, -1.0]), vec![1.0, 0.5, 0.25]);
+        assert_eq!(normalize_fts_ranks(&[0.0, 0.0]), vec![1.0, 1.0]);
+        assert!(normalize_fts_ranks(&[]).is_empty());
+    }
+    
+    #[test]
+    fn test_working_memory_can_outrank_low_confidence_long_term() {
+        let (_base_dir, manager, workspace_id) = setup("test-hybrid-scoring", 0);
+        manager.add_long_term_memory(&workspace_id, AddLongTermMemoryRequest {
+            category: "decision".to_string(),
+            title: "Caching".to_string(),
+            content: "We keep a preview cache in memory".to_string(),
+            tags: None,
+            source: "user".to_string(),
+            confidence: Some(0.1),
+        }).unwrap();
+        for content in ["Preview cache invalidates on template mtime", "Cache warming notes"] {
+            manager.add_working_memory(&workspace_id, AddWorkingMemoryRequest {
+                session_id: None,
+                category: "note".to_string(),
+                title: content.to_string(),
+                content: content.to_string(),
+                is_pinned: true,
+                source: "user".to_string(),
+            }).unwrap();
+        }
+        
+        let results = manager.retrieve_context(&workspace_id, RetrievalQuery {
+            query: "preview cache".to_string(),
+            categories: None,
+            limit: Some(10),
+            include_short_term: false,
+            include_working: true,
+            include_long_term: true,
+            min_relevance: None,
+        }).unwrap();
+        
+        let order: Vec<(&str, f64)> = results.iter()
+            .map(|r| (r.memory_type.as_str(), r.relevance_score))
+            .collect();
+        assert_eq!(order.len(), 3);
+        assert_eq!(order[0], ("working", 1.0));
+        assert_eq!(order[1].0, "long_term");
+        assert!((order[1].1 - long_term_relevance(1.0, 0.1)).abs() < 1e-9);
+        assert_eq!(order[2], ("working", 0.5));
+        
+        // Component scores are exposed for debugging
+        assert_eq!(results[1].text_score, 1.0);
+        assert_eq!(results[1].confidence, Some(0.1));
+        assert_eq!(results[0].confidence, None);
+    }
+}
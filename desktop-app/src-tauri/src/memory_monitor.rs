@@ -3,8 +3,8 @@
 // RISK-005 FIX: Monitor memory usage and cleanup when needed
 //
 // Provides:
-// - Memory usage tracking
-// - Automatic cleanup when threshold exceeded
+// - Memory usage tracking, including the app's own resident set size
+// - High/critical watermarks that run registered cache evictors
 // - Memory leak detection
 // - Performance metrics
 
@@ -22,6 +22,8 @@ const DEFAULT_WARNING_THRESHOLD_MB: u64 = 512;
 const DEFAULT_CRITICAL_THRESHOLD_MB: u64 = 1024;
 const SAMPLE_INTERVAL_SECS: u64 = 30;
 const MAX_SAMPLES: usize = 120; // 1 hour of samples at 30s intervals
+/// Minimum time between automatic relief runs at the same pressure level
+const RELIEF_COOLDOWN_SECS: u64 = 300;
 
 // ============================================
 // Types
@@ -29,6 +31,8 @@ const MAX_SAMPLES: usize = 120; // 1 hour of samples at 30s intervals
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryStats {
+    /// Resident set size of this process
+    pub rss_mb: u64,
     pub used_mb: u64,
     pub available_mb: u64,
     pub total_mb: u64,
@@ -55,6 +59,64 @@ pub struct MemoryAlert {
     pub timestamp: i64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryPressure {
+    Normal,
+    High,
+    Critical,
+}
+
+impl MemoryPressure {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "normal" => Ok(MemoryPressure::Normal),
+            "high" => Ok(MemoryPressure::High),
+            "critical" => Ok(MemoryPressure::Critical),
+            _ => Err(format!("Invalid memory pressure level: {}", s)),
+        }
+    }
+}
+
+/// A cache the monitor can shrink when memory runs high
+pub trait CacheEvictor: Send + Sync {
+    fn name(&self) -> &str;
+    /// Entries currently held
+    fn entries(&self) -> usize;
+    /// Free what `level` calls for; returns the number of entries dropped
+    fn evict(&self, level: MemoryPressure) -> usize;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheSize {
+    pub name: String,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvictionResult {
+    pub name: String,
+    pub evicted: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PressureRelief {
+    pub level: MemoryPressure,
+    pub rss_before_mb: u64,
+    pub rss_after_mb: u64,
+    pub evictions: Vec<EvictionResult>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryMetrics {
+    pub stats: MemoryStats,
+    pub pressure: MemoryPressure,
+    pub high_watermark_mb: u64,
+    pub critical_watermark_mb: u64,
+    pub caches: Vec<CacheSize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryReport {
     pub stats: MemoryStats,
@@ -67,13 +129,17 @@ pub struct MemoryReport {
 // Memory Monitor
 // ============================================
 
+type AlertHandler = Box<dyn Fn(&MemoryAlert) + Send + Sync>;
+
 pub struct MemoryMonitor {
     samples: Mutex<VecDeque<MemoryStats>>,
     warning_threshold_mb: AtomicU64,
     critical_threshold_mb: AtomicU64,
     monitoring_active: AtomicBool,
-    last_cleanup: Mutex<Option<Instant>>,
-    cleanup_callbacks: Mutex<Vec<Box<dyn Fn() + Send + Sync>>>,
+    /// When relief last ran automatically, and at which level
+    last_relief: Mutex<Option<(Instant, MemoryPressure)>>,
+    evictors: Mutex<Vec<Arc<dyn CacheEvictor>>>,
+    alert_handler: Mutex<Option<AlertHandler>>,
 }
 
 impl MemoryMonitor {
@@ -83,8 +149,9 @@ impl MemoryMonitor {
             warning_threshold_mb: AtomicU64::new(DEFAULT_WARNING_THRESHOLD_MB),
             critical_threshold_mb: AtomicU64::new(DEFAULT_CRITICAL_THRESHOLD_MB),
             monitoring_active: AtomicBool::new(false),
-            last_cleanup: Mutex::new(None),
-            cleanup_callbacks: Mutex::new(Vec::new()),
+            last_relief: Mutex::new(None),
+            evictors: Mutex::new(Vec::new()),
+            alert_handler: Mutex::new(None),
         }
     }
     
@@ -98,13 +165,115 @@ impl MemoryMonitor {
         self.critical_threshold_mb.store(mb, Ordering::SeqCst);
     }
     
-    /// Register a cleanup callback
-    pub fn register_cleanup_callback<F>(&self, callback: F)
+    /// Register a cache to shrink when a watermark is crossed
+    pub fn register_evictor(&self, evictor: Arc<dyn CacheEvictor>) {
+        let mut evictors = self.evictors.lock().unwrap();
+        evictors.push(evictor);
+    }
+    
+    /// Called with each alert raised when a watermark is crossed, e.g. to
+    /// forward it to the UI
+    pub fn set_alert_handler<F>(&self, handler: F)
     where
-        F: Fn() + Send + Sync + 'static,
+        F: Fn(&MemoryAlert) + Send + Sync + 'static,
     {
-        let mut callbacks = self.cleanup_callbacks.lock().unwrap();
-        callbacks.push(Box::new(callback));
+        *self.alert_handler.lock().unwrap() = Some(Box::new(handler));
+    }
+    
+    /// Pressure level for a resident set size
+    pub fn pressure_for(&self, rss_mb: u64) -> MemoryPressure {
+        if rss_mb >= self.critical_threshold_mb.load(Ordering::SeqCst) {
+            MemoryPressure::Critical
+        } else if rss_mb >= self.warning_threshold_mb.load(Ordering::SeqCst) {
+            MemoryPressure::High
+        } else {
+            MemoryPressure::Normal
+        }
+    }
+    
+    /// Entries held by each registered cache
+    pub fn cache_sizes(&self) -> Vec<CacheSize> {
+        let evictors = self.evictors.lock().unwrap().clone();
+        evictors.iter()
+            .map(|e| CacheSize { name: e.name().to_string(), entries: e.entries() })
+            .collect()
+    }
+    
+    /// Current stats, pressure level and cache sizes
+    pub fn get_metrics(&self) -> MemoryMetrics {
+        let stats = self.get_current_stats();
+        MemoryMetrics {
+            pressure: self.pressure_for(stats.rss_mb),
+            high_watermark_mb: self.warning_threshold_mb.load(Ordering::SeqCst),
+            critical_watermark_mb: self.critical_threshold_mb.load(Ordering::SeqCst),
+            caches: self.cache_sizes(),
+            stats,
+        }
+    }
+    
+    /// Run every evictor at `level`, regardless of current usage
+    pub fn relieve_pressure(&self, level: MemoryPressure) -> PressureRelief {
+        let rss_before_mb = Self::process_rss() / (1024 * 1024);
+        // Clone the list so an evictor may register others without deadlocking
+        let evictors = self.evictors.lock().unwrap().clone();
+        let evictions = evictors.iter()
+            .map(|e| EvictionResult { name: e.name().to_string(), evicted: e.evict(level) })
+            .collect();
+        
+        PressureRelief {
+            level,
+            rss_before_mb,
+            rss_after_mb: Self::process_rss() / (1024 * 1024),
+            evictions,
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+    
+    /// React to a resident set size: above a watermark, raise an alert and
+    /// run the evictors. Relief at the same level is skipped during the
+    /// cooldown; escalating from high to critical is not.
+    pub fn handle_rss(&self, rss_mb: u64) -> Option<PressureRelief> {
+        let level = self.pressure_for(rss_mb);
+        if level == MemoryPressure::Normal {
+            return None;
+        }
+        
+        {
+            let mut last_relief = self.last_relief.lock().unwrap();
+            if let Some((at, last_level)) = *last_relief {
+                if last_level >= level && at.elapsed() < Duration::from_secs(RELIEF_COOLDOWN_SECS) {
+                    return None;
+                }
+            }
+            *last_relief = Some((Instant::now(), level));
+        }
+        
+        let threshold_mb = if level == MemoryPressure::Critical {
+            self.critical_threshold_mb.load(Ordering::SeqCst)
+        } else {
+            self.warning_threshold_mb.load(Ordering::SeqCst)
+        };
+        let level_name = if level == MemoryPressure::Critical { "critical" } else { "warning" };
+        let alert = MemoryAlert {
+            level: level_name.to_string(),
+            message: format!(
+                "Memory usage {}: {}MB exceeds {}MB threshold, freeing caches",
+                level_name, rss_mb, threshold_mb
+            ),
+            current_mb: rss_mb,
+            threshold_mb,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        if let Some(handler) = self.alert_handler.lock().unwrap().as_ref() {
+            handler(&alert);
+        }
+        
+        Some(self.relieve_pressure(level))
+    }
+    
+    /// Check the process's memory against the watermarks
+    pub fn check_pressure(&self) -> Option<PressureRelief> {
+        self.handle_rss(Self::process_rss() / (1024 * 1024))
     }
     
     /// Get current memory stats
@@ -117,6 +286,7 @@ impl MemoryMonitor {
         };
         
         MemoryStats {
+            rss_mb: Self::process_rss() / (1024 * 1024),
             used_mb: used / (1024 * 1024),
             available_mb: available / (1024 * 1024),
             total_mb: total / (1024 * 1024),
@@ -195,25 +365,25 @@ impl MemoryMonitor {
         
         let mut alerts = Vec::new();
         
-        if stats.used_mb >= critical_threshold {
+        if stats.rss_mb >= critical_threshold {
             alerts.push(MemoryAlert {
                 level: "critical".to_string(),
                 message: format!(
                     "Memory usage critical: {}MB exceeds {}MB threshold",
-                    stats.used_mb, critical_threshold
+                    stats.rss_mb, critical_threshold
                 ),
-                current_mb: stats.used_mb,
+                current_mb: stats.rss_mb,
                 threshold_mb: critical_threshold,
                 timestamp: stats.timestamp,
             });
-        } else if stats.used_mb >= warning_threshold {
+        } else if stats.rss_mb >= warning_threshold {
             alerts.push(MemoryAlert {
                 level: "warning".to_string(),
                 message: format!(
                     "Memory usage warning: {}MB exceeds {}MB threshold",
-                    stats.used_mb, warning_threshold
+                    stats.rss_mb, warning_threshold
                 ),
-                current_mb: stats.used_mb,
+                current_mb: stats.rss_mb,
                 threshold_mb: warning_threshold,
                 timestamp: stats.timestamp,
             });
@@ -225,7 +395,7 @@ impl MemoryMonitor {
             alerts.push(MemoryAlert {
                 level: "warning".to_string(),
                 message: "Potential memory leak detected: memory usage continuously increasing".to_string(),
-                current_mb: stats.used_mb,
+                current_mb: stats.rss_mb,
                 threshold_mb: warning_threshold,
                 timestamp: stats.timestamp,
             });
@@ -241,7 +411,7 @@ impl MemoryMonitor {
         let alerts = self.check_alerts();
         
         let critical_threshold = self.critical_threshold_mb.load(Ordering::SeqCst);
-        let cleanup_recommended = stats.used_mb >= critical_threshold * 80 / 100;
+        let cleanup_recommended = stats.rss_mb >= critical_threshold * 80 / 100;
         
         MemoryReport {
             stats,
@@ -253,33 +423,17 @@ impl MemoryMonitor {
     
     /// Trigger cleanup if needed
     pub fn trigger_cleanup_if_needed(&self) -> bool {
-        let stats = self.get_current_stats();
-        let critical_threshold = self.critical_threshold_mb.load(Ordering::SeqCst);
-        
-        if stats.used_mb >= critical_threshold {
-            // Check if we cleaned up recently (within 5 minutes)
-            let mut last_cleanup = self.last_cleanup.lock().unwrap();
-            if let Some(last) = *last_cleanup {
-                if last.elapsed() < Duration::from_secs(300) {
-                    return false;
-                }
-            }
-            
-            // Run cleanup callbacks
-            let callbacks = self.cleanup_callbacks.lock().unwrap();
-            for callback in callbacks.iter() {
-                callback();
-            }
-            
-            *last_cleanup = Some(Instant::now());
-            
-            // Force garbage collection hint
-            // Note: Rust doesn't have explicit GC, but we can drop unused allocations
-            
-            true
-        } else {
-            false
-        }
+        self.check_pressure().is_some()
+    }
+    
+    /// Resident set size of this process in bytes, 0 if unavailable
+    fn process_rss() -> u64 {
+        let Ok(pid) = sysinfo::get_current_pid() else {
+            return 0;
+        };
+        let mut system = sysinfo::System::new();
+        system.refresh_process(pid);
+        system.process(pid).map(|p| p.memory()).unwrap_or(0)
     }
     
     /// Get system memory info (platform-specific)
@@ -464,6 +618,16 @@ pub fn trigger_memory_cleanup() -> bool {
     get_memory_monitor().trigger_cleanup_if_needed()
 }
 
+/// Run the pressure-relief path now, at `level` (default "high")
+#[tauri::command]
+pub fn relieve_memory_pressure(level: Option<String>) -> Result<PressureRelief, String> {
+    let level = match level {
+        Some(level) => MemoryPressure::parse(&level)?,
+        None => MemoryPressure::High,
+    };
+    Ok(get_memory_monitor().relieve_pressure(level))
+}
+
 // ============================================
 // Tests
 // ============================================
@@ -504,4 +668,53 @@ mod tests {
         assert_eq!(monitor.warning_threshold_mb.load(Ordering::SeqCst), 256);
         assert_eq!(monitor.critical_threshold_mb.load(Ordering::SeqCst), 512);
     }
+    
+    struct FakeEvictor {
+        entries: AtomicU64,
+        calls: Mutex<Vec<MemoryPressure>>,
+    }
+    
+    impl CacheEvictor for FakeEvictor {
+        fn name(&self) -> &str {
+            "fake"
+        }
+        
+        fn entries(&self) -> usize {
+            self.entries.load(Ordering::SeqCst) as usize
+        }
+        
+        fn evict(&self, level: MemoryPressure) -> usize {
+            self.calls.lock().unwrap().push(level);
+            self.entries.swap(0, Ordering::SeqCst) as usize
+        }
+    }
+    
+    #[test]
+    fn test_high_memory_runs_evictors() {
+        let monitor = MemoryMonitor::new();
+        monitor.set_warning_threshold(256);
+        monitor.set_critical_threshold(512);
+        
+        let evictor = Arc::new(FakeEvictor { entries: AtomicU64::new(7), calls: Mutex::new(Vec::new()) });
+        monitor.register_evictor(evictor.clone());
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let sink = alerts.clone();
+        monitor.set_alert_handler(move |alert| sink.lock().unwrap().push(alert.level.clone()));
+        
+        assert_eq!(monitor.cache_sizes()[0].entries, 7);
+        assert!(monitor.handle_rss(100).is_none());
+        
+        let relief = monitor.handle_rss(300).unwrap();
+        assert_eq!(relief.level, MemoryPressure::High);
+        assert_eq!(relief.evictions[0].evicted, 7);
+        assert_eq!(monitor.cache_sizes()[0].entries, 0);
+        
+        // Same level within the cooldown is skipped; escalating is not
+        assert!(monitor.handle_rss(300).is_none());
+        let relief = monitor.handle_rss(600).unwrap();
+        assert_eq!(relief.level, MemoryPressure::Critical);
+        
+        assert_eq!(*evictor.calls.lock().unwrap(), vec![MemoryPressure::High, MemoryPressure::Critical]);
+        assert_eq!(*alerts.lock().unwrap(), vec!["warning".to_string(), "critical".to_string()]);
+    }
 }
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 /// Workflow model
@@ -104,6 +105,9 @@ pub struct Execution {
     pub error: Option<String>,
     pub started_at: i64,
     pub completed_at: Option<i64>,
+    /// Workflow configuration as it was when the execution started
+    #[serde(default)]
+    pub config_snapshot: Option<serde_json::Value>,
 }
 
 impl Execution {
@@ -118,9 +122,16 @@ impl Execution {
             error: None,
             started_at: Utc::now().timestamp(),
             completed_at: None,
+            config_snapshot: None,
         }
     }
 
+    /// Attach the configuration the execution runs with
+    pub fn with_config_snapshot(mut self, config_snapshot: Option<serde_json::Value>) -> Self {
+        self.config_snapshot = config_snapshot;
+        self
+    }
+
     /// Mark execution as completed
     pub fn complete(&mut self, output: Option<serde_json::Value>) {
         self.status = ExecutionStatus::Completed;
@@ -147,6 +158,130 @@ impl Execution {
     }
 }
 
+/// One difference between two config snapshots. `path` is dotted for
+/// object keys and bracketed for array items, e.g. `llm.stops[1]`, or
+/// `steps[id=lint]` when array items are matched by their `id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<serde_json::Value>,
+}
+
+/// What changed in the config snapshot from execution `a` to execution `b`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigDiff {
+    pub execution_a: String,
+    pub execution_b: String,
+    pub added: Vec<ConfigChange>,
+    pub removed: Vec<ConfigChange>,
+    pub changed: Vec<ConfigChange>,
+}
+
+impl ConfigDiff {
+    /// Diff two executions' snapshots. A missing snapshot counts as empty.
+    pub fn between(a: &Execution, b: &Execution) -> Self {
+        let empty = serde_json::Value::Object(Default::default());
+        let mut diff = ConfigDiff {
+            execution_a: a.id.clone(),
+            execution_b: b.id.clone(),
+            ..Default::default()
+        };
+        diff.compare(
+            "",
+            a.config_snapshot.as_ref().unwrap_or(&empty),
+            b.config_snapshot.as_ref().unwrap_or(&empty),
+        );
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    fn compare(&mut self, path: &str, old: &serde_json::Value, new: &serde_json::Value) {
+        use serde_json::Value;
+
+        match (old, new) {
+            (Value::Object(old_map), Value::Object(new_map)) => {
+                for (key, old_value) in old_map {
+                    let child = object_path(path, key);
+                    match new_map.get(key) {
+                        Some(new_value) => self.compare(&child, old_value, new_value),
+                        None => self.removed.push(ConfigChange { path: child, old: Some(old_value.clone()), new: None }),
+                    }
+                }
+                for (key, new_value) in new_map {
+                    if !old_map.contains_key(key) {
+                        let child = object_path(path, key);
+                        self.added.push(ConfigChange { path: child, old: None, new: Some(new_value.clone()) });
+                    }
+                }
+            }
+            (Value::Array(old_items), Value::Array(new_items)) => {
+                match (item_keys(old_items), item_keys(new_items)) {
+                    (Some(old_keys), Some(new_keys)) => {
+                        for (key, old_item) in old_keys.iter().zip(old_items) {
+                            let child = format!("{}[id={}]", path, key);
+                            match new_keys.iter().position(|k| k == key) {
+                                Some(i) => self.compare(&child, old_item, &new_items[i]),
+                                None => self.removed.push(ConfigChange { path: child, old: Some(old_item.clone()), new: None }),
+                            }
+                        }
+                        for (key, new_item) in new_keys.iter().zip(new_items) {
+                            if !old_keys.contains(key) {
+                                let child = format!("{}[id={}]", path, key);
+                                self.added.push(ConfigChange { path: child, old: None, new: Some(new_item.clone()) });
+                            }
+                        }
+                    }
+                    _ => {
+                        for i in 0..old_items.len().max(new_items.len()) {
+                            let child = format!("{}[{}]", path, i);
+                            match (old_items.get(i), new_items.get(i)) {
+                                (Some(o), Some(n)) => self.compare(&child, o, n),
+                                (Some(o), None) => self.removed.push(ConfigChange { path: child, old: Some(o.clone()), new: None }),
+                                (None, Some(n)) => self.added.push(ConfigChange { path: child, old: None, new: Some(n.clone()) }),
+                                (None, None) => {}
+                            }
+                        }
+                    }
+                }
+            }
+            _ if old != new => self.changed.push(ConfigChange {
+                path: path.to_string(),
+                old: Some(old.clone()),
+                new: Some(new.clone()),
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn object_path(parent: &str, key: &str) -> String {
+    if key.contains(['.', '[', ']']) {
+        format!("{}[{:?}]", parent, key)
+    } else if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+/// The `id` of every item, when all items are objects with distinct string
+/// ids, so reordering an array of steps isn't reported as edits to each
+fn item_keys(items: &[serde_json::Value]) -> Option<Vec<String>> {
+    let keys: Vec<String> = items.iter()
+        .map(|item| item.get("id").and_then(|id| id.as_str()).map(str::to_string))
+        .collect::<Option<_>>()?;
+    let mut unique = keys.clone();
+    unique.sort();
+    unique.dedup();
+    (!keys.is_empty() && unique.len() == keys.len()).then_some(keys)
+}
+
 /// Config value type enum
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -221,6 +356,130 @@ impl Config {
     }
 }
 
+/// A named placeholder in a config template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigTemplateVariable {
+    pub name: String,
+    pub description: Option<String>,
+    /// Used when the variable isn't provided; a variable with a default is
+    /// never missing
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default = "default_true")]
+    pub required: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One config a template produces. `value` may contain `{{variable}}`
+/// placeholders and template helpers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigTemplateEntry {
+    pub key: String,
+    pub value: String,
+    pub value_type: ConfigValueType,
+    pub description: Option<String>,
+}
+
+/// Config template model
+/// A reusable parameter set shared by all workflows and workspaces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub variables: Vec<ConfigTemplateVariable>,
+    pub entries: Vec<ConfigTemplateEntry>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl ConfigTemplate {
+    /// Create a new config template
+    pub fn new(
+        name: String,
+        description: Option<String>,
+        variables: Vec<ConfigTemplateVariable>,
+        entries: Vec<ConfigTemplateEntry>,
+    ) -> Self {
+        let now = Utc::now().timestamp();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            description,
+            variables,
+            entries,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Required variables that are neither in `vars` nor defaulted
+    pub fn missing_variables(&self, vars: &HashMap<String, String>) -> Vec<String> {
+        self.variables
+            .iter()
+            .filter(|v| v.required && v.default.is_none() && !vars.contains_key(&v.name))
+            .map(|v| v.name.clone())
+            .collect()
+    }
+
+    /// Resolve the placeholders into configs for `workflow_id`. Fails if a
+    /// required variable is missing, a value references an undeclared
+    /// variable, or a resolved value doesn't match its type.
+    pub fn resolve(&self, workflow_id: &str, vars: &HashMap<String, String>) -> anyhow::Result<Vec<Config>> {
+        let missing = self.missing_variables(vars);
+        if !missing.is_empty() {
+            anyhow::bail!("Missing required variables for template '{}': {}", self.name, missing.join(", "));
+        }
+
+        let mut context = serde_json::Map::new();
+        for variable in &self.variables {
+            if let Some(value) = vars.get(&variable.name).or(variable.default.as_ref()) {
+                context.insert(variable.name.clone(), serde_json::Value::String(value.clone()));
+            }
+        }
+        let context = serde_json::Value::Object(context);
+
+        // Config values aren't HTML, and a typo'd placeholder shouldn't
+        // silently resolve to an empty string
+        let mut handlebars = crate::template_engine::TemplateEngine::new_handlebars();
+        handlebars.set_strict_mode(true);
+        handlebars.register_escape_fn(handlebars::no_escape);
+
+        self.entries
+            .iter()
+            .map(|entry| {
+                let value = handlebars
+                    .render_template(&entry.value, &context)
+                    .map_err(|e| anyhow::anyhow!("Failed to resolve config '{}': {}", entry.key, e))?;
+                check_value_type(&entry.key, &value, &entry.value_type)?;
+                Ok(Config::new(
+                    workflow_id.to_string(),
+                    entry.key.clone(),
+                    value,
+                    entry.value_type.clone(),
+                    entry.description.clone(),
+                ))
+            })
+            .collect()
+    }
+}
+
+fn check_value_type(key: &str, value: &str, value_type: &ConfigValueType) -> anyhow::Result<()> {
+    let valid = match value_type {
+        ConfigValueType::String => true,
+        ConfigValueType::Number => value.trim().parse::<f64>().is_ok(),
+        ConfigValueType::Boolean => matches!(value.trim(), "true" | "false"),
+        ConfigValueType::Json => serde_json::from_str::<serde_json::Value>(value).is_ok(),
+    };
+    if !valid {
+        anyhow::bail!("Config '{}' resolved to '{}', which is not a valid {}", key, value, value_type.as_str());
+    }
+    Ok(())
+}
+
 /// Execution filter for queries
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionFilter {
@@ -228,6 +487,10 @@ pub struct ExecutionFilter {
     pub status: Option<ExecutionStatus>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    #[serde(default)]
+    pub before_id: Option<String>,
+    #[serde(default)]
+    pub after_id: Option<String>,
 }
 
 impl Default for ExecutionFilter {
@@ -237,7 +500,66 @@ impl Default for ExecutionFilter {
             status: None,
             limit: Some(50),
             offset: Some(0),
+            before_id: None,
+            after_id: None,
+        }
+    }
+}
+
+/// Keyset pagination request. `before_id` pages towards older rows and
+/// `after_id` towards newer ones; with neither, the most recent page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageRequest {
+    pub before_id: Option<String>,
+    pub after_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Where a page starts relative to its cursor row
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageCursor<'a> {
+    Latest,
+    Before(&'a str),
+    After(&'a str),
+}
+
+impl PageRequest {
+    pub fn cursor(&self) -> Result<PageCursor<'_>, String> {
+        match (&self.before_id, &self.after_id) {
+            (Some(_), Some(_)) => Err("Use either before_id or after_id, not both".to_string()),
+            (Some(id), None) => Ok(PageCursor::Before(id)),
+            (None, Some(id)) => Ok(PageCursor::After(id)),
+            (None, None) => Ok(PageCursor::Latest),
+        }
+    }
+
+    /// SQL LIMIT to query with: one extra row tells whether another page
+    /// exists, and -1 means no limit
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit.map(|l| l.max(1) + 1).unwrap_or(-1)
+    }
+}
+
+/// One page of a keyset-paginated listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass back as the same cursor (`before_id` or `after_id`) to continue
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Build a page from rows in fetch order, i.e. moving away from the
+    /// cursor, queried with `PageRequest::fetch_limit`
+    pub fn from_rows(mut rows: Vec<T>, limit: Option<i64>, cursor_of: impl Fn(&T) -> String) -> Self {
+        let limit = limit.map(|l| l.max(1) as usize);
+        let has_more = limit.is_some_and(|l| rows.len() > l);
+        if let Some(l) = limit {
+            rows.truncate(l);
         }
+
+        let next_cursor = if has_more { rows.last().map(cursor_of) } else { None };
+        Self { items: rows, next_cursor }
     }
 }
 
@@ -314,6 +636,48 @@ mod tests {
         assert!(execution.duration().is_some());
     }
 
+    #[test]
+    fn test_config_diff_nested_changes() {
+        let snapshot = |config: serde_json::Value| {
+            Execution::new("workflow-id".to_string(), "plan".to_string()).with_config_snapshot(Some(config))
+        };
+        let a = snapshot(serde_json::json!({
+            "llm": { "model": "gpt-4o", "temperature": 0.2, "stop": ["END"] },
+            "steps": [
+                { "id": "lint", "enabled": true },
+                { "id": "test", "retries": 1 }
+            ],
+            "tags": ["a", "b"],
+            "legacy": true
+        }));
+        let b = snapshot(serde_json::json!({
+            "llm": { "model": "claude-sonnet", "temperature": 0.2, "stop": ["END", "STOP"] },
+            "steps": [
+                { "id": "test", "retries": 3 },
+                { "id": "lint", "enabled": true },
+                { "id": "build" }
+            ],
+            "tags": "a",
+            "paths.root": "/srv"
+        }));
+
+        let diff = ConfigDiff::between(&a, &b);
+        let paths = |changes: &[ConfigChange]| changes.iter().map(|c| c.path.clone()).collect::<Vec<_>>();
+
+        assert_eq!(paths(&diff.changed), vec!["llm.model", "steps[id=test].retries", "tags"]);
+        assert_eq!(diff.changed[0].old, Some(serde_json::json!("gpt-4o")));
+        assert_eq!(diff.changed[0].new, Some(serde_json::json!("claude-sonnet")));
+        assert_eq!(paths(&diff.added), vec!["llm.stop[1]", "steps[id=build]", "[\"paths.root\"]"]);
+        assert_eq!(paths(&diff.removed), vec!["legacy"]);
+        assert!(diff.removed[0].new.is_none());
+
+        // Identical snapshots, and two missing ones, have no differences
+        assert!(ConfigDiff::between(&a, &a).is_empty());
+        let bare = Execution::new("workflow-id".to_string(), "plan".to_string());
+        assert!(ConfigDiff::between(&bare, &bare).is_empty());
+        assert_eq!(paths(&ConfigDiff::between(&bare, &a).added).len(), 4);
+    }
+
     #[test]
     fn test_execution_status_conversion() {
         assert_eq!(ExecutionStatus::Running.as_str(), "running");
@@ -321,6 +685,25 @@ mod tests {
         assert_eq!(ExecutionStatus::from_str("invalid"), None);
     }
 
+    #[test]
+    fn test_page_from_rows() {
+        let page = Page::from_rows(vec![5, 4, 3], Some(2), |n| n.to_string());
+        assert_eq!(page.items, vec![5, 4]);
+        assert_eq!(page.next_cursor.as_deref(), Some("4"));
+
+        let page = Page::from_rows(vec![5, 4], Some(2), |n| n.to_string());
+        assert_eq!(page.next_cursor, None);
+
+        let page = Page::from_rows(vec![1, 2, 3], None, |n| n.to_string());
+        assert_eq!(page.items.len(), 3);
+        assert_eq!(page.next_cursor, None);
+
+        let request = PageRequest { before_id: Some("a".into()), after_id: Some("b".into()), limit: None };
+        assert!(request.cursor().is_err());
+        assert_eq!(PageRequest::default().cursor(), Ok(PageCursor::Latest));
+        assert_eq!(PageRequest::default().fetch_limit(), -1);
+    }
+
     #[test]
     fn test_config_creation() {
         let config = Config::new(
@@ -335,4 +718,64 @@ mod tests {
         assert_eq!(config.value, "secret123");
         assert_eq!(config.value_type, ConfigValueType::String);
     }
+
+    #[test]
+    fn test_config_template_resolve() {
+        let template = ConfigTemplate::new(
+            "review".to_string(),
+            None,
+            vec![
+                ConfigTemplateVariable {
+                    name: "project".to_string(),
+                    description: None,
+                    default: None,
+                    required: true,
+                },
+                ConfigTemplateVariable {
+                    name: "strict".to_string(),
+                    description: None,
+                    default: Some("false".to_string()),
+                    required: true,
+                },
+            ],
+            vec![
+                ConfigTemplateEntry {
+                    key: "output_dir".to_string(),
+                    value: "specs/{{snakeCase project}} & co".to_string(),
+                    value_type: ConfigValueType::String,
+                    description: None,
+                },
+                ConfigTemplateEntry {
+                    key: "strict".to_string(),
+                    value: "{{strict}}".to_string(),
+                    value_type: ConfigValueType::Boolean,
+                    description: None,
+                },
+            ],
+        );
+
+        let vars = HashMap::from([("project".to_string(), "MyApp".to_string())]);
+        let configs = template.resolve("workflow-id", &vars).unwrap();
+        assert_eq!(configs[0].value, "specs/my_app & co");
+        assert_eq!(configs[0].workflow_id, "workflow-id");
+        assert_eq!(configs[1].value, "false");
+
+        // Every missing variable is reported at once
+        let err = template.resolve("workflow-id", &HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains("project"));
+        assert_eq!(template.missing_variables(&HashMap::new()), vec!["project".to_string()]);
+
+        // Resolved values must match their type
+        let vars = HashMap::from([
+            ("project".to_string(), "app".to_string()),
+            ("strict".to_string(), "yes".to_string()),
+        ]);
+        assert!(template.resolve("workflow-id", &vars).is_err());
+
+        // Placeholders must reference declared variables
+        let mut typo = template.clone();
+        typo.entries[0].value = "{{projcet}}".to_string();
+        let vars = HashMap::from([("project".to_string(), "app".to_string())]);
+        assert!(typo.resolve("workflow-id", &vars).is_err());
+    }
 }
@@ -53,7 +53,7 @@ pub struct WorkspaceMember {
     pub last_active_at: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum WorkspaceRole {
     Owner,
@@ -62,6 +62,37 @@ pub enum WorkspaceRole {
     Viewer,
 }
 
+impl WorkspaceRole {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "owner" => Ok(WorkspaceRole::Owner),
+            "admin" => Ok(WorkspaceRole::Admin),
+            "editor" => Ok(WorkspaceRole::Editor),
+            "viewer" => Ok(WorkspaceRole::Viewer),
+            _ => Err(format!("Invalid workspace role: {}", s)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkspaceRole::Owner => "owner",
+            WorkspaceRole::Admin => "admin",
+            WorkspaceRole::Editor => "editor",
+            WorkspaceRole::Viewer => "viewer",
+        }
+    }
+
+    /// Higher ranks include everything lower ranks may do
+    pub fn rank(&self) -> u8 {
+        match self {
+            WorkspaceRole::Owner => 3,
+            WorkspaceRole::Admin => 2,
+            WorkspaceRole::Editor => 1,
+            WorkspaceRole::Viewer => 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkspaceSettings {
     pub default_template: Option<String>,
@@ -339,10 +370,10 @@ impl MultiWorkspaceService {
         workspace.last_accessed_at = chrono::Utc::now().timestamp();
         self.active_workspace_id = Some(workspace_id.to_string());
 
-        let workspace = self.workspaces.get(workspace_id).unwrap();
-        self.update_recent(workspace);
+        let snapshot = workspace.clone();
+        self.update_recent(&snapshot);
 
-        Ok(workspace)
+        Ok(&self.workspaces[workspace_id])
     }
 
     pub fn get_active_workspace(&self) -> Option<&Workspace> {
@@ -371,62 +402,6 @@ impl MultiWorkspaceService {
         self.recent_workspaces.truncate(10);
     }
 
-    // ============================================
-    // Members
-    // ============================================
-
-    pub fn add_member(&mut self, workspace_id: &str, user_id: &str, name: &str, email: &str, role: WorkspaceRole) -> Result<WorkspaceMember, String> {
-        let workspace = self.workspaces.get_mut(workspace_id)
-            .ok_or_else(|| format!("Workspace not found: {}", workspace_id))?;
-
-        if workspace.members.iter().any(|m| m.user_id == user_id) {
-            return Err(format!("User {} is already a member", user_id));
-        }
-
-        let now = chrono::Utc::now().timestamp();
-        let member = WorkspaceMember {
-            user_id: user_id.to_string(),
-            name: name.to_string(),
-            email: email.to_string(),
-            role,
-            joined_at: now,
-            last_active_at: now,
-        };
-
-        workspace.members.push(member.clone());
-        workspace.updated_at = now;
-
-        Ok(member)
-    }
-
-    pub fn remove_member(&mut self, workspace_id: &str, user_id: &str) -> Result<(), String> {
-        let workspace = self.workspaces.get_mut(workspace_id)
-            .ok_or_else(|| format!("Workspace not found: {}", workspace_id))?;
-
-        if workspace.owner_id == user_id {
-            return Err("Cannot remove workspace owner".to_string());
-        }
-
-        workspace.members.retain(|m| m.user_id != user_id);
-        workspace.updated_at = chrono::Utc::now().timestamp();
-
-        Ok(())
-    }
-
-    pub fn update_member_role(&mut self, workspace_id: &str, user_id: &str, role: WorkspaceRole) -> Result<(), String> {
-        let workspace = self.workspaces.get_mut(workspace_id)
-            .ok_or_else(|| format!("Workspace not found: {}", workspace_id))?;
-
-        let member = workspace.members.iter_mut()
-            .find(|m| m.user_id == user_id)
-            .ok_or_else(|| format!("Member not found: {}", user_id))?;
-
-        member.role = role;
-        workspace.updated_at = chrono::Utc::now().timestamp();
-
-        Ok(())
-    }
-
     // ============================================
     // Sync
     // ============================================
@@ -3,8 +3,9 @@
 // Provides commands for:
 // - Workspace CRUD
 // - Workspace switching
-// - Member management
-// - Sync operations
+// - Team workspaces: members, roles and invite tokens
+// - Sync operations, including conflict-aware sync with the git remote
+// - Search across all workspaces
 
 use tauri::State;
 use std::sync::Arc;
@@ -15,6 +16,11 @@ use crate::multi_workspace::{
     WorkspaceMember, WorkspaceSettings, WorkspaceTemplate, RecentWorkspace,
     SyncStatus,
 };
+use crate::enterprise_commands::EnterpriseState;
+use crate::workspace_access::{self, NewMember, TeamWorkspace, WorkspaceInvite, DEFAULT_INVITE_TTL_HOURS};
+use crate::workspace_commands::AppState as WorkspaceAppState;
+use crate::workspace_data::{GlobalSearchResults, GlobalSearchSources, DEFAULT_GLOBAL_SEARCH_LIMIT};
+use crate::workspace_sync::{self, SyncConflict, SyncReport, SyncSide};
 
 // ============================================
 // State Types
@@ -131,40 +137,105 @@ pub async fn mw_get_recent_workspaces(
 // Member Commands
 // ============================================
 
+/// Create a workspace database owned by the signed-in user
+#[tauri::command]
+pub async fn mw_create_team_workspace(
+    workspace_state: State<'_, WorkspaceAppState>,
+    enterprise_state: State<'_, Arc<Mutex<EnterpriseState>>>,
+    name: String,
+    git_remote: Option<String>,
+) -> Result<TeamWorkspace, String> {
+    let enterprise = enterprise_state.lock().await;
+    workspace_access::create_team_workspace(&workspace_state.db_manager, &enterprise.service, &name, git_remote.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn mw_list_members(
+    workspace_state: State<'_, WorkspaceAppState>,
+    workspace_id: String,
+) -> Result<Vec<WorkspaceMember>, String> {
+    workspace_access::list_members(&workspace_state.db_manager, &workspace_id)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn mw_add_member(
-    state: State<'_, Arc<Mutex<MultiWorkspaceState>>>,
+    workspace_state: State<'_, WorkspaceAppState>,
+    enterprise_state: State<'_, Arc<Mutex<EnterpriseState>>>,
     workspace_id: String,
     user_id: String,
     name: String,
     email: String,
     role: String,
 ) -> Result<WorkspaceMember, String> {
-    let mut state = state.lock().await;
-    let ws_role = parse_workspace_role(&role)?;
-    state.service.add_member(&workspace_id, &user_id, &name, &email, ws_role)
+    let member = NewMember { user_id, name, email, role: WorkspaceRole::parse(&role)? };
+    let enterprise = enterprise_state.lock().await;
+    workspace_access::add_member(&workspace_state.db_manager, &enterprise.service, &workspace_id, &member)
+        .map_err(|e| e.to_string())
 }
 
+/// Remove a member; any invite tokens issued to them are revoked
 #[tauri::command]
 pub async fn mw_remove_member(
-    state: State<'_, Arc<Mutex<MultiWorkspaceState>>>,
+    workspace_state: State<'_, WorkspaceAppState>,
+    enterprise_state: State<'_, Arc<Mutex<EnterpriseState>>>,
     workspace_id: String,
     user_id: String,
-) -> Result<(), String> {
-    let mut state = state.lock().await;
-    state.service.remove_member(&workspace_id, &user_id)
+) -> Result<usize, String> {
+    let enterprise = enterprise_state.lock().await;
+    workspace_access::remove_member(&workspace_state.db_manager, &enterprise.service, &workspace_id, &user_id)
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn mw_update_member_role(
-    state: State<'_, Arc<Mutex<MultiWorkspaceState>>>,
+    workspace_state: State<'_, WorkspaceAppState>,
+    enterprise_state: State<'_, Arc<Mutex<EnterpriseState>>>,
     workspace_id: String,
     user_id: String,
     role: String,
-) -> Result<(), String> {
-    let mut state = state.lock().await;
-    let ws_role = parse_workspace_role(&role)?;
-    state.service.update_member_role(&workspace_id, &user_id, ws_role)
+) -> Result<WorkspaceMember, String> {
+    let role = WorkspaceRole::parse(&role)?;
+    let enterprise = enterprise_state.lock().await;
+    workspace_access::update_member_role(&workspace_state.db_manager, &enterprise.service, &workspace_id, &user_id, role)
+        .map_err(|e| e.to_string())
+}
+
+/// Share a workspace and return an invite token for the recipient
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn mw_share_workspace(
+    workspace_state: State<'_, WorkspaceAppState>,
+    enterprise_state: State<'_, Arc<Mutex<EnterpriseState>>>,
+    workspace_id: String,
+    user_id: String,
+    name: String,
+    email: String,
+    role: String,
+    expires_in_hours: Option<i64>,
+) -> Result<WorkspaceInvite, String> {
+    let member = NewMember { user_id, name, email, role: WorkspaceRole::parse(&role)? };
+    let enterprise = enterprise_state.lock().await;
+    workspace_access::share_workspace(
+        &workspace_state.db_manager,
+        &enterprise.service,
+        &workspace_id,
+        &member,
+        expires_in_hours.unwrap_or(DEFAULT_INVITE_TTL_HOURS),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Check an invite token and return the member it belongs to
+#[tauri::command]
+pub async fn mw_authorize_invite(
+    workspace_state: State<'_, WorkspaceAppState>,
+    workspace_id: String,
+    token: String,
+) -> Result<WorkspaceMember, String> {
+    workspace_access::authorize_invite(&workspace_state.db_manager, &workspace_id, &token)
+        .map_err(|e| e.to_string())
 }
 
 // ============================================
@@ -198,6 +269,46 @@ pub async fn mw_trigger_sync(
     state.service.trigger_sync(&workspace_id)
 }
 
+/// Sync a workspace's project branch and data with its git remote
+#[tauri::command]
+pub async fn mw_sync_workspace(
+    workspace_state: State<'_, WorkspaceAppState>,
+    workspace_id: String,
+    remote: Option<String>,
+) -> Result<SyncReport, String> {
+    let db_manager = Arc::clone(&workspace_state.db_manager);
+    let remote_name = remote.unwrap_or_else(|| "origin".to_string());
+    
+    tokio::task::spawn_blocking(move || {
+        workspace_sync::sync_workspace(&db_manager, &workspace_id, &remote_name, crate::secure_store::load_git_token)
+            .map_err(|e| format!("{:#}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn mw_list_sync_conflicts(
+    workspace_state: State<'_, WorkspaceAppState>,
+    workspace_id: String,
+) -> Result<Vec<SyncConflict>, String> {
+    workspace_sync::list_sync_conflicts(&workspace_state.db_manager, &workspace_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Settle a sync conflict by keeping the `local` or `remote` version
+#[tauri::command]
+pub async fn mw_resolve_sync_conflict(
+    workspace_state: State<'_, WorkspaceAppState>,
+    workspace_id: String,
+    conflict_id: i64,
+    keep: String,
+) -> Result<(), String> {
+    let keep = SyncSide::parse(&keep)?;
+    workspace_sync::resolve_sync_conflict(&workspace_state.db_manager, &workspace_id, conflict_id, keep)
+        .map_err(|e| e.to_string())
+}
+
 // ============================================
 // Template Commands
 // ============================================
@@ -235,6 +346,38 @@ pub async fn mw_update_workspace_settings(
     state.service.update_workspace_settings(&workspace_id, settings)
 }
 
+// ============================================
+// Global Search Commands
+// ============================================
+
+/// Search knowledge, long-term memory and chat history in every registered
+/// workspace. Each source is included unless its toggle is `false`.
+#[tauri::command]
+pub async fn mw_search_all(
+    workspace_state: State<'_, WorkspaceAppState>,
+    query: String,
+    limit: Option<usize>,
+    include_knowledge: Option<bool>,
+    include_memory: Option<bool>,
+    include_chat: Option<bool>,
+) -> Result<GlobalSearchResults, String> {
+    let sources = GlobalSearchSources {
+        knowledge: include_knowledge.unwrap_or(true),
+        memory: include_memory.unwrap_or(true),
+        chat: include_chat.unwrap_or(true),
+    };
+    let limit = limit.unwrap_or(DEFAULT_GLOBAL_SEARCH_LIMIT);
+    let db_manager = Arc::clone(&workspace_state.db_manager);
+    let data_ops = Arc::clone(&workspace_state.data_ops);
+    
+    tokio::task::spawn_blocking(move || {
+        let workspaces = db_manager.list_workspaces().map_err(|e| e.to_string())?;
+        Ok(data_ops.search_all_workspaces(&workspaces, &query, sources, limit))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 // ============================================
 // Helper Functions
 // ============================================
@@ -248,13 +391,3 @@ fn parse_workspace_type(s: &str) -> Result<WorkspaceType, String> {
         _ => Err(format!("Invalid workspace type: {}", s)),
     }
 }
-
-fn parse_workspace_role(s: &str) -> Result<WorkspaceRole, String> {
-    match s.to_lowercase().as_str() {
-        "owner" => Ok(WorkspaceRole::Owner),
-        "admin" => Ok(WorkspaceRole::Admin),
-        "editor" => Ok(WorkspaceRole::Editor),
-        "viewer" => Ok(WorkspaceRole::Viewer),
-        _ => Err(format!("Invalid workspace role: {}", s)),
-    }
-}
@@ -5,13 +5,34 @@
 // - CPU monitoring
 // - Database optimization
 // - Cache management
+// - Content-addressed render cache
 // - Lazy loading utilities
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::Mutex;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::memory_monitor::{get_memory_monitor, CacheEvictor, MemoryPressure};
+use crate::workspace_db::WorkspaceMaintenanceStats;
+
+// ============================================
+// Recommendation Thresholds
+// ============================================
+
+/// Lookups needed before a cache's hit rate is meaningful
+const MIN_CACHE_LOOKUPS: i64 = 100;
+const LOW_CACHE_HIT_RATE: f64 = 0.5;
+/// A low hit rate only points at cache size when the cache is this full
+const CACHE_FULL_RATIO: f64 = 0.9;
+/// Smaller workspace databases aren't worth vacuuming
+const VACUUM_MIN_SIZE_BYTES: u64 = 10 * 1024 * 1024;
+const VACUUM_MIN_FREE_RATIO: f64 = 0.2;
+const EXPIRED_ROWS_THRESHOLD: i64 = 100;
 
 // ============================================
 // Types
@@ -43,7 +64,49 @@ pub struct Recommendation {
     pub level: RecommendationLevel,
     pub category: String,
     pub message: String,
+    /// Command the UI can invoke to apply this, e.g. `vacuum_workspace`
     pub action: Option<String>,
+    /// Arguments for `action`, keyed as the command expects
+    #[serde(default)]
+    pub action_args: serde_json::Value,
+}
+
+impl Recommendation {
+    fn new(level: RecommendationLevel, category: &str, message: String, action: &str, action_args: serde_json::Value) -> Self {
+        Self {
+            level,
+            category: category.to_string(),
+            message,
+            action: Some(action.to_string()),
+            action_args,
+        }
+    }
+}
+
+/// Measurements that recommendations are computed from
+#[derive(Debug, Clone)]
+pub struct RecommendationSignals {
+    pub memory_pressure: MemoryPressure,
+    pub rss_mb: u64,
+    pub render_cache: CacheStats,
+    pub render_cache_max_size_mb: f64,
+    pub render_cache_max_entries: usize,
+    pub workspaces: Vec<WorkspaceMaintenanceStats>,
+}
+
+impl RecommendationSignals {
+    pub fn collect(render_cache: &RenderCache, workspaces: Vec<WorkspaceMaintenanceStats>) -> Self {
+        let memory = get_memory_monitor().get_metrics();
+        let (render_cache_max_size_mb, render_cache_max_entries) = render_cache.limits();
+        Self {
+            memory_pressure: memory.pressure,
+            rss_mb: memory.stats.rss_mb,
+            render_cache: render_cache.stats(),
+            render_cache_max_size_mb,
+            render_cache_max_entries,
+            workspaces,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +135,10 @@ pub struct CacheStats {
     pub miss_count: i64,
     pub eviction_count: i64,
     pub oldest_entry_age_secs: i64,
+    #[serde(default)]
+    pub render_hit_count: i64,
+    #[serde(default)]
+    pub render_miss_count: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,10 +194,15 @@ impl<K: std::hash::Hash + Eq + Clone, V: Clone> LruCache<K, V> {
     }
 
     pub fn get(&mut self, key: &K) -> Option<V> {
+        self.get_if(key, |_| true)
+    }
+
+    /// Like `get`, but an entry rejected by `is_fresh` is dropped and counted as a miss.
+    pub fn get_if(&mut self, key: &K, is_fresh: impl FnOnce(&V) -> bool) -> Option<V> {
         if let Some(entry) = self.entries.get_mut(key) {
             // Check TTL
-            if entry.created_at.elapsed() > self.ttl {
-                self.entries.remove(key);
+            if entry.created_at.elapsed() > self.ttl || !is_fresh(&entry.value) {
+                self.remove(key);
                 self.misses += 1;
                 return None;
             }
@@ -184,6 +256,19 @@ impl<K: std::hash::Hash + Eq + Clone, V: Clone> LruCache<K, V> {
         self.current_size_bytes = 0;
     }
 
+    pub fn limits(&self) -> (f64, usize) {
+        (self.max_size_bytes as f64 / (1024.0 * 1024.0), self.max_entries)
+    }
+
+    /// Change the limits, evicting least-recently-used entries to fit
+    pub fn resize(&mut self, max_size_mb: f64, max_entries: usize) {
+        self.max_size_bytes = (max_size_mb * 1024.0 * 1024.0) as usize;
+        self.max_entries = max_entries;
+        while (self.current_size_bytes > self.max_size_bytes || self.entries.len() > self.max_entries)
+            && self.evict_lru()
+        {}
+    }
+
     fn evict_lru(&mut self) -> bool {
         if self.entries.is_empty() {
             return false;
@@ -219,6 +304,8 @@ impl<K: std::hash::Hash + Eq + Clone, V: Clone> LruCache<K, V> {
             miss_count: self.misses as i64,
             eviction_count: self.evictions as i64,
             oldest_entry_age_secs: oldest_age,
+            render_hit_count: 0,
+            render_miss_count: 0,
         }
     }
 
@@ -232,6 +319,133 @@ impl<K: std::hash::Hash + Eq + Clone, V: Clone> LruCache<K, V> {
     }
 }
 
+// ============================================
+// Render Cache
+// ============================================
+
+/// Modification time of a file or directory a cached render was produced from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceStamp {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl SourceStamp {
+    pub fn capture(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: std::fs::metadata(path).and_then(|m| m.modified()).ok(),
+        }
+    }
+
+    fn is_current(&self) -> bool {
+        *self == Self::capture(&self.path)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedRender {
+    content: String,
+    sources: Vec<SourceStamp>,
+}
+
+/// Content-addressed cache for rendered output (template previews, spec exports).
+///
+/// Entries are keyed by a hash of their inputs; any on-disk sources recorded
+/// with an entry are re-checked on lookup so edited templates are never served stale.
+pub struct RenderCache {
+    entries: std::sync::Mutex<LruCache<String, CachedRender>>,
+}
+
+impl RenderCache {
+    pub fn new(max_size_mb: f64, max_entries: usize, ttl_secs: u64) -> Self {
+        Self {
+            entries: std::sync::Mutex::new(LruCache::new(max_size_mb, max_entries, ttl_secs)),
+        }
+    }
+
+    /// Hash the given inputs into a cache key. Parts are length-prefixed so
+    /// `["ab", "c"]` and `["a", "bc"]` never collide.
+    pub fn key(parts: &[&[u8]]) -> String {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get_if(&key.to_string(), |cached| cached.sources.iter().all(SourceStamp::is_current))
+            .map(|cached| cached.content)
+    }
+
+    pub fn insert(&self, key: &str, content: String, sources: Vec<SourceStamp>) {
+        let size = content.len();
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(key.to_string(), CachedRender { content, sources }, size);
+    }
+
+    /// Return the cached render for `key`, producing and storing it on a miss.
+    pub fn get_or_insert_with<E>(
+        &self,
+        key: &str,
+        render: impl FnOnce() -> Result<(String, Vec<SourceStamp>), E>,
+    ) -> Result<String, E> {
+        if let Some(content) = self.get(key) {
+            return Ok(content);
+        }
+        let (content, sources) = render()?;
+        self.insert(key, content.clone(), sources);
+        Ok(content)
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).stats()
+    }
+
+    /// Maximum size in MB and maximum entry count
+    pub fn limits(&self) -> (f64, usize) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).limits()
+    }
+
+    pub fn resize(&self, max_size_mb: f64, max_entries: usize) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).resize(max_size_mb, max_entries);
+    }
+}
+
+impl CacheEvictor for RenderCache {
+    fn name(&self) -> &str {
+        "render_cache"
+    }
+
+    fn entries(&self) -> usize {
+        self.stats().total_entries as usize
+    }
+
+    /// Rendered output is cheap to rebuild, so any pressure clears it
+    fn evict(&self, _level: MemoryPressure) -> usize {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let count = entries.stats().total_entries as usize;
+        entries.clear();
+        count
+    }
+}
+
+static SHARED_RENDER_CACHE: Lazy<Arc<RenderCache>> =
+    Lazy::new(|| Arc::new(RenderCache::new(50.0, 500, 3600))); // 50MB, 500 entries, 1h TTL
+
+/// Process-wide render cache shared by the template engine and spec builder.
+pub fn shared_render_cache() -> Arc<RenderCache> {
+    SHARED_RENDER_CACHE.clone()
+}
+
 // ============================================
 // Performance Monitor
 // ============================================
@@ -241,6 +455,7 @@ pub struct PerformanceMonitor {
     max_history: usize,
     query_cache: Arc<Mutex<LruCache<String, String>>>,
     data_cache: Arc<Mutex<LruCache<String, Vec<u8>>>>,
+    render_cache: Arc<RenderCache>,
 }
 
 impl PerformanceMonitor {
@@ -250,6 +465,7 @@ impl PerformanceMonitor {
             max_history: 100,
             query_cache: Arc::new(Mutex::new(LruCache::new(50.0, 1000, 300))), // 50MB, 1000 entries, 5min TTL
             data_cache: Arc::new(Mutex::new(LruCache::new(100.0, 500, 600))),  // 100MB, 500 entries, 10min TTL
+            render_cache: shared_render_cache(),
         }
     }
 
@@ -347,10 +563,16 @@ impl PerformanceMonitor {
     pub async fn clear_caches(&self) {
         self.query_cache.lock().await.clear();
         self.data_cache.lock().await.clear();
+        self.render_cache.clear();
     }
 
     pub async fn get_cache_stats(&self) -> CacheStats {
-        self.query_cache.lock().await.stats()
+        let render = self.render_cache.stats();
+        CacheStats {
+            render_hit_count: render.hit_count,
+            render_miss_count: render.miss_count,
+            ..self.query_cache.lock().await.stats()
+        }
     }
 
     // ============================================
@@ -444,44 +666,7 @@ impl PerformanceMonitor {
     // ============================================
 
     pub async fn get_recommendations(&self) -> Vec<Recommendation> {
-        let mut recommendations = Vec::new();
-        let metrics = self.collect_metrics().await;
-        let cache_stats = self.get_cache_stats().await;
-
-        // Memory recommendations
-        if metrics.memory_percent > 80.0 {
-            recommendations.push(Recommendation {
-                level: RecommendationLevel::Warning,
-                category: "Memory".to_string(),
-                message: format!("Memory usage is high ({:.1}%)", metrics.memory_percent),
-                action: Some("Consider closing unused workspaces or clearing cache".to_string()),
-            });
-        }
-
-        // Cache recommendations
-        if cache_stats.hit_count + cache_stats.miss_count > 100 {
-            let hit_rate = cache_stats.hit_count as f64 / (cache_stats.hit_count + cache_stats.miss_count) as f64;
-            if hit_rate < 0.5 {
-                recommendations.push(Recommendation {
-                    level: RecommendationLevel::Info,
-                    category: "Cache".to_string(),
-                    message: format!("Cache hit rate is low ({:.1}%)", hit_rate * 100.0),
-                    action: Some("Consider increasing cache size or TTL".to_string()),
-                });
-            }
-        }
-
-        // Cache size recommendation
-        if cache_stats.size_mb > 80.0 {
-            recommendations.push(Recommendation {
-                level: RecommendationLevel::Info,
-                category: "Cache".to_string(),
-                message: format!("Cache is using {:.1} MB", cache_stats.size_mb),
-                action: Some("Consider clearing old cache entries".to_string()),
-            });
-        }
-
-        recommendations
+        recommend(&RecommendationSignals::collect(&self.render_cache, Vec::new()))
     }
 
     // ============================================
@@ -518,6 +703,103 @@ impl PerformanceMonitor {
     }
 }
 
+// ============================================
+// Recommendations
+// ============================================
+
+/// Turn measured signals into recommendations, each naming the command that
+/// applies it
+pub fn recommend(signals: &RecommendationSignals) -> Vec<Recommendation> {
+    let mut recommendations = Vec::new();
+
+    if signals.memory_pressure != MemoryPressure::Normal {
+        let (level, pressure) = match signals.memory_pressure {
+            MemoryPressure::Critical => (RecommendationLevel::Critical, "critical"),
+            _ => (RecommendationLevel::Warning, "high"),
+        };
+        recommendations.push(Recommendation::new(
+            level,
+            "Memory",
+            format!("Memory usage is {} ({} MB); free caches and idle workspaces", pressure, signals.rss_mb),
+            "relieve_memory_pressure",
+            serde_json::json!({ "level": pressure }),
+        ));
+    }
+
+    let cache = &signals.render_cache;
+    let lookups = cache.hit_count + cache.miss_count;
+    let cache_full = cache.total_entries as f64 >= signals.render_cache_max_entries as f64 * CACHE_FULL_RATIO
+        || cache.size_mb >= signals.render_cache_max_size_mb * CACHE_FULL_RATIO;
+    if lookups >= MIN_CACHE_LOOKUPS && cache_full {
+        let hit_rate = cache.hit_count as f64 / lookups as f64;
+        if hit_rate < LOW_CACHE_HIT_RATE {
+            recommendations.push(Recommendation::new(
+                RecommendationLevel::Info,
+                "Cache",
+                format!(
+                    "Render cache hit rate is low ({:.1}%) and the cache is full; doubling its size should help",
+                    hit_rate * 100.0
+                ),
+                "perf_resize_render_cache",
+                serde_json::json!({
+                    "maxSizeMb": signals.render_cache_max_size_mb * 2.0,
+                    "maxEntries": signals.render_cache_max_entries * 2,
+                }),
+            ));
+        }
+    }
+
+    for workspace in &signals.workspaces {
+        let args = serde_json::json!({ "workspaceId": workspace.workspace_id });
+
+        if workspace.db_size_bytes >= VACUUM_MIN_SIZE_BYTES
+            && workspace.free_bytes as f64 >= workspace.db_size_bytes as f64 * VACUUM_MIN_FREE_RATIO
+        {
+            recommendations.push(Recommendation::new(
+                RecommendationLevel::Info,
+                "Database",
+                format!(
+                    "Workspace {} database has {:.1} MB of free pages ({:.0}% of the file); vacuum to reclaim it",
+                    workspace.workspace_id,
+                    workspace.free_bytes as f64 / (1024.0 * 1024.0),
+                    workspace.free_bytes as f64 / workspace.db_size_bytes as f64 * 100.0
+                ),
+                "vacuum_workspace",
+                args.clone(),
+            ));
+        }
+
+        if workspace.expired_short_term >= EXPIRED_ROWS_THRESHOLD {
+            recommendations.push(Recommendation::new(
+                RecommendationLevel::Info,
+                "Memory",
+                format!(
+                    "Workspace {} holds {} expired short-term memory rows",
+                    workspace.workspace_id, workspace.expired_short_term
+                ),
+                "cleanup_expired_memory",
+                args.clone(),
+            ));
+        }
+
+        if !workspace.stale_fts_tables.is_empty() {
+            recommendations.push(Recommendation::new(
+                RecommendationLevel::Warning,
+                "Search",
+                format!(
+                    "Workspace {} search index is out of date ({}); search may miss results",
+                    workspace.workspace_id,
+                    workspace.stale_fts_tables.join(", ")
+                ),
+                "workspace_rebuild_search_index",
+                args,
+            ));
+        }
+    }
+
+    recommendations
+}
+
 // ============================================
 // Lazy Loader
 // ============================================
@@ -622,3 +904,124 @@ impl Throttler {
         Some(f())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_cache_hit_and_miss() {
+        let cache = RenderCache::new(1.0, 10, 60);
+        let key = RenderCache::key(&[b"svg", b"{\"id\":\"doc\"}"]);
+        let mut renders = 0;
+
+        for _ in 0..3 {
+            let out = cache.get_or_insert_with::<String>(&key, || {
+                renders += 1;
+                Ok(("<svg/>".to_string(), Vec::new()))
+            }).unwrap();
+            assert_eq!(out, "<svg/>");
+        }
+
+        assert_eq!(renders, 1);
+        let stats = cache.stats();
+        assert_eq!((stats.hit_count, stats.miss_count), (2, 1));
+        assert_ne!(key, RenderCache::key(&[b"png", b"{\"id\":\"doc\"}"]));
+        assert_ne!(RenderCache::key(&[b"ab", b"c"]), RenderCache::key(&[b"a", b"bc"]));
+    }
+
+    #[test]
+    fn test_render_cache_invalidated_by_source_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let template = dir.path().join("README.md.hbs");
+        std::fs::write(&template, "# {{project_name}}").unwrap();
+
+        let cache = RenderCache::new(1.0, 10, 60);
+        cache.insert("preview", "# demo".to_string(), vec![SourceStamp::capture(&template)]);
+        assert_eq!(cache.get("preview").as_deref(), Some("# demo"));
+
+        let file = std::fs::File::options().write(true).open(&template).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+
+        assert_eq!(cache.get("preview"), None);
+        let stats = cache.stats();
+        assert_eq!((stats.total_entries, stats.hit_count, stats.miss_count), (0, 1, 1));
+    }
+
+    fn quiet_signals() -> RecommendationSignals {
+        RecommendationSignals {
+            memory_pressure: MemoryPressure::Normal,
+            rss_mb: 200,
+            render_cache: RenderCache::new(1.0, 10, 60).stats(),
+            render_cache_max_size_mb: 50.0,
+            render_cache_max_entries: 500,
+            workspaces: vec![WorkspaceMaintenanceStats {
+                workspace_id: "ws-1".to_string(),
+                db_size_bytes: 64 * 1024 * 1024,
+                free_bytes: 1024 * 1024,
+                expired_short_term: 3,
+                stale_fts_tables: Vec::new(),
+            }],
+        }
+    }
+
+    fn actions(recommendations: &[Recommendation]) -> Vec<&str> {
+        recommendations.iter().filter_map(|r| r.action.as_deref()).collect()
+    }
+
+    #[test]
+    fn test_recommend_nothing_for_healthy_signals() {
+        assert!(recommend(&quiet_signals()).is_empty());
+    }
+
+    #[test]
+    fn test_recommend_larger_cache_only_when_full_and_missing() {
+        let mut signals = quiet_signals();
+        signals.render_cache.hit_count = 20;
+        signals.render_cache.miss_count = 180;
+        signals.render_cache.total_entries = 100;
+        // A mostly empty cache wouldn't benefit from more room
+        assert!(recommend(&signals).is_empty());
+
+        signals.render_cache.total_entries = 480;
+        let recommendations = recommend(&signals);
+        assert_eq!(actions(&recommendations), vec!["perf_resize_render_cache"]);
+        assert_eq!(recommendations[0].action_args["maxEntries"], 1000);
+        assert_eq!(recommendations[0].action_args["maxSizeMb"], 100.0);
+    }
+
+    #[test]
+    fn test_recommend_workspace_maintenance() {
+        let mut signals = quiet_signals();
+        signals.workspaces[0].free_bytes = 32 * 1024 * 1024;
+        signals.workspaces[0].expired_short_term = 250;
+        signals.workspaces[0].stale_fts_tables = vec!["knowledge_fts".to_string()];
+        // Small databases aren't worth vacuuming even when mostly free
+        signals.workspaces.push(WorkspaceMaintenanceStats {
+            workspace_id: "ws-small".to_string(),
+            db_size_bytes: 1024 * 1024,
+            free_bytes: 900 * 1024,
+            ..Default::default()
+        });
+
+        let recommendations = recommend(&signals);
+        assert_eq!(
+            actions(&recommendations),
+            vec!["vacuum_workspace", "cleanup_expired_memory", "workspace_rebuild_search_index"]
+        );
+        assert!(recommendations.iter().all(|r| r.action_args["workspaceId"] == "ws-1"));
+        assert!(recommendations[2].message.contains("knowledge_fts"));
+    }
+
+    #[test]
+    fn test_recommend_relief_under_memory_pressure() {
+        let mut signals = quiet_signals();
+        signals.memory_pressure = MemoryPressure::Critical;
+        signals.rss_mb = 1500;
+
+        let recommendations = recommend(&signals);
+        assert_eq!(actions(&recommendations), vec!["relieve_memory_pressure"]);
+        assert!(matches!(recommendations[0].level, RecommendationLevel::Critical));
+        assert_eq!(recommendations[0].action_args["level"], "critical");
+    }
+}
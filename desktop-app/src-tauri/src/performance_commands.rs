@@ -5,15 +5,19 @@
 // - Cache management
 // - Database optimization
 // - Performance reports
+// - Process memory and evictable cache sizes
 
 use tauri::State;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::performance::{
-    PerformanceMonitor, SystemMetrics, PerformanceReport,
+    self, PerformanceMonitor, SystemMetrics, PerformanceReport,
     CacheStats, DatabaseStats, OptimizationResult,
+    Recommendation, RecommendationSignals,
 };
+use crate::memory_monitor::{get_memory_monitor, MemoryMetrics};
+use crate::workspace_commands::AppState as WorkspaceAppState;
 
 // ============================================
 // State Types
@@ -52,6 +56,38 @@ pub async fn perf_get_report(
     state.monitor.generate_report(db_path.as_deref()).await
 }
 
+/// Resident set size, pressure level against the watermarks, and the size of
+/// every cache the memory monitor can evict
+#[tauri::command]
+pub async fn perf_get_metrics() -> Result<MemoryMetrics, String> {
+    Ok(get_memory_monitor().get_metrics())
+}
+
+/// Recommendations computed from memory pressure, render cache hit rate and
+/// each workspace database. Every recommendation names the command that
+/// applies it, with its arguments.
+#[tauri::command]
+pub async fn perf_get_recommendations(
+    state: State<'_, WorkspaceAppState>,
+) -> Result<Vec<Recommendation>, String> {
+    let db_manager = Arc::clone(&state.db_manager);
+    let workspaces = tokio::task::spawn_blocking(move || -> Result<_, String> {
+        let mut stats = Vec::new();
+        for workspace in db_manager.list_workspaces().map_err(|e| e.to_string())? {
+            match db_manager.maintenance_stats(&workspace.id) {
+                Ok(s) => stats.push(s),
+                Err(e) => eprintln!("Skipping maintenance check for workspace {}: {}", workspace.id, e),
+            }
+        }
+        Ok(stats)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let signals = RecommendationSignals::collect(&performance::shared_render_cache(), workspaces);
+    Ok(performance::recommend(&signals))
+}
+
 // ============================================
 // Cache Commands
 // ============================================
@@ -105,6 +141,17 @@ pub async fn perf_clear_caches(
     Ok(())
 }
 
+/// Change the shared render cache's limits; shrinking evicts LRU entries
+#[tauri::command]
+pub async fn perf_resize_render_cache(max_size_mb: f64, max_entries: usize) -> Result<CacheStats, String> {
+    if max_size_mb <= 0.0 || max_entries == 0 {
+        return Err("Cache limits must be greater than zero".to_string());
+    }
+    let cache = performance::shared_render_cache();
+    cache.resize(max_size_mb, max_entries);
+    Ok(cache.stats())
+}
+
 #[tauri::command]
 pub async fn perf_get_cache_stats(
     state: State<'_, Arc<Mutex<PerformanceState>>>,
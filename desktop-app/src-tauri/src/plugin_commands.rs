@@ -5,6 +5,7 @@
 // - Plugin settings
 // - Hook and event handling
 // - Permission management
+// - Sandboxed plugin invocation
 
 use tauri::State;
 use std::sync::Arc;
@@ -13,8 +14,9 @@ use tokio::sync::Mutex;
 use std::path::PathBuf;
 
 use crate::plugin_system::{
-    PluginManager, Plugin, PluginManifest, PluginState, Permission,
-    PluginApi, PluginContext, get_plugin_template,
+    PluginManager, Plugin, PluginManifest, PluginState, Permission, PermissionRequest,
+    PluginApi, PluginContext, PluginLimits, PluginOutput, PluginConfigHost, PluginError,
+    get_plugin_template, run_plugin,
 };
 
 // ============================================
@@ -33,6 +35,18 @@ impl PluginSystemState {
     }
 }
 
+/// Config host calls from a running plugin take the lock for the call alone
+#[async_trait::async_trait]
+impl PluginConfigHost for Mutex<PluginSystemState> {
+    async fn get_config(&self, plugin_id: &str, key: Option<&str>) -> Result<serde_json::Value, PluginError> {
+        self.lock().await.manager.get_config(plugin_id, key)
+    }
+
+    async fn set_config(&self, plugin_id: &str, key: &str, value: serde_json::Value) -> Result<(), PluginError> {
+        self.lock().await.manager.set_config(plugin_id, key, value)
+    }
+}
+
 // ============================================
 // Plugin Management Commands
 // ============================================
@@ -47,6 +61,48 @@ pub async fn plugin_install(
     state.manager.install_plugin(manifest, &wasm_path)
 }
 
+/// Manifest and requested permissions for the install prompt
+#[derive(serde::Serialize)]
+pub struct PluginInstallPreview {
+    pub manifest: PluginManifest,
+    pub permissions: Vec<PermissionRequest>,
+}
+
+#[tauri::command]
+pub async fn plugin_preview_install(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+    plugin_dir: String,
+) -> Result<PluginInstallPreview, String> {
+    let manifest = PluginManifest::load(&PathBuf::from(&plugin_dir)).map_err(|e| e.to_string())?;
+    let state = state.lock().await;
+    let permissions = state.manager.permission_requests(&manifest);
+    Ok(PluginInstallPreview { manifest, permissions })
+}
+
+/// Install from a directory containing `manifest.json`, granting the
+/// permissions the user approved in the install prompt
+#[tauri::command]
+pub async fn plugin_install_from_dir(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+    plugin_dir: String,
+    approved_permissions: Vec<Permission>,
+) -> Result<Plugin, String> {
+    let plugin_dir = PathBuf::from(plugin_dir);
+    let manifest = PluginManifest::load(&plugin_dir).map_err(|e| e.to_string())?;
+    let entry_path = manifest.entry_path(&plugin_dir).map_err(|e| e.to_string())?;
+
+    let mut state = state.lock().await;
+    let plugin = state.manager.install_plugin(manifest, &entry_path.to_string_lossy())?;
+    if let Err(e) = state.manager.grant_permissions(&plugin.id, &approved_permissions) {
+        let _ = state.manager.uninstall_plugin(&plugin.id);
+        return Err(e.to_string());
+    }
+
+    state.manager.get_plugin(&plugin.id)
+        .cloned()
+        .ok_or_else(|| format!("Plugin not found: {}", plugin.id))
+}
+
 #[tauri::command]
 pub async fn plugin_uninstall(
     state: State<'_, Arc<Mutex<PluginSystemState>>>,
@@ -62,7 +118,7 @@ pub async fn plugin_enable(
     plugin_id: String,
 ) -> Result<(), String> {
     let mut state = state.lock().await;
-    state.manager.enable_plugin(&plugin_id)
+    state.manager.enable_plugin(&plugin_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -124,6 +180,29 @@ pub async fn plugin_update_settings(
     state.manager.update_plugin_settings(&plugin_id, settings)
 }
 
+/// Read a plugin's configuration on its behalf. Requires `read_settings`.
+#[tauri::command]
+pub async fn plugin_get_config(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+    plugin_id: String,
+    key: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let state = state.lock().await;
+    state.manager.get_config(&plugin_id, key.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Change a plugin's configuration on its behalf. Requires `write_settings`.
+#[tauri::command]
+pub async fn plugin_set_config(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+    plugin_id: String,
+    key: String,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.manager.set_config(&plugin_id, &key, value).map_err(|e| e.to_string())
+}
+
 // ============================================
 // Hook Commands
 // ============================================
@@ -202,11 +281,42 @@ pub async fn plugin_request_permission(
     state.manager.request_permission(&plugin_id, permission)
 }
 
+#[tauri::command]
+pub async fn plugin_grant_permissions(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+    plugin_id: String,
+    permissions: Vec<Permission>,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.manager.grant_permissions(&plugin_id, &permissions).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plugin_get_permission_allowlist(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+) -> Result<Vec<Permission>, String> {
+    let state = state.lock().await;
+    Ok(state.manager.permission_allowlist.clone())
+}
+
+#[tauri::command]
+pub async fn plugin_set_permission_allowlist(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+    permissions: Vec<String>,
+) -> Result<(), String> {
+    let permissions = permissions.iter()
+        .map(|p| parse_permission(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut state = state.lock().await;
+    state.manager.permission_allowlist = permissions;
+    Ok(())
+}
+
 fn parse_permission(s: &str) -> Result<Permission, String> {
     match s.to_lowercase().as_str() {
-        "read_files" => Ok(Permission::ReadFiles),
-        "write_files" => Ok(Permission::WriteFiles),
-        "network_access" => Ok(Permission::NetworkAccess),
+        "read_files" | "fs_read" => Ok(Permission::ReadFiles),
+        "write_files" | "fs_write" => Ok(Permission::WriteFiles),
+        "network_access" | "network" => Ok(Permission::NetworkAccess),
         "read_workspace" => Ok(Permission::ReadWorkspace),
         "write_workspace" => Ok(Permission::WriteWorkspace),
         "create_ui" => Ok(Permission::CreateUi),
@@ -215,12 +325,53 @@ fn parse_permission(s: &str) -> Result<Permission, String> {
         "notifications" => Ok(Permission::Notifications),
         "read_settings" => Ok(Permission::ReadSettings),
         "write_settings" => Ok(Permission::WriteSettings),
-        "read_database" => Ok(Permission::ReadDatabase),
-        "write_database" => Ok(Permission::WriteDatabase),
+        "read_database" | "db_read" => Ok(Permission::ReadDatabase),
+        "write_database" | "db_write" => Ok(Permission::WriteDatabase),
         _ => Err(format!("Invalid permission: {}", s)),
     }
 }
 
+// ============================================
+// Execution Commands
+// ============================================
+
+#[tauri::command]
+pub async fn plugin_invoke(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+    plugin_id: String,
+    handler: String,
+    input: serde_json::Value,
+) -> Result<PluginOutput, String> {
+    // Release the lock before running so a slow plugin does not block other commands
+    let invocation = {
+        let state = state.lock().await;
+        state.manager.prepare_invocation(&plugin_id).map_err(|e| e.to_string())?
+    };
+    // Host calls the plugin makes while running are served for this plugin only
+    run_plugin(&invocation, &handler, input, &**state).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn plugin_get_limits(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+) -> Result<PluginLimits, String> {
+    let state = state.lock().await;
+    Ok(state.manager.limits.clone())
+}
+
+#[tauri::command]
+pub async fn plugin_set_limits(
+    state: State<'_, Arc<Mutex<PluginSystemState>>>,
+    limits: PluginLimits,
+) -> Result<(), String> {
+    if limits.timeout_ms == 0 || limits.max_output_bytes == 0 {
+        return Err("Plugin timeout and output cap must be greater than zero".to_string());
+    }
+    let mut state = state.lock().await;
+    state.manager.limits = limits;
+    Ok(())
+}
+
 // ============================================
 // API Commands
 // ============================================
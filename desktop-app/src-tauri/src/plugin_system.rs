@@ -3,12 +3,17 @@
 // Provides:
 // - Plugin registry and lifecycle management
 // - WASM sandbox execution
+// - Sandboxed subprocess execution with timeout, output and resource limits
 // - Plugin API and hooks
-// - Permission system
+// - Permission system with capability checks at the host call boundary
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
 use uuid::Uuid;
 
 // ============================================
@@ -21,7 +26,11 @@ pub struct Plugin {
     pub manifest: PluginManifest,
     pub state: PluginState,
     pub settings: HashMap<String, serde_json::Value>,
+    /// Permissions the user granted; always a subset of `manifest.permissions`
     pub permissions: Vec<Permission>,
+    /// Executable or WASM module the plugin runs from
+    #[serde(default)]
+    pub entry_path: String,
     pub installed_at: i64,
     pub updated_at: i64,
 }
@@ -37,16 +46,76 @@ pub struct PluginManifest {
     pub license: Option<String>,
     pub icon: Option<String>,
     pub category: PluginCategory,
+    #[serde(default)]
     pub tags: Vec<String>,
     pub min_app_version: String,
     pub entry_point: String,
+    /// Capabilities the plugin requests; the user approves them at install
+    #[serde(default)]
     pub permissions: Vec<Permission>,
     pub settings_schema: Option<SettingsSchema>,
+    #[serde(default)]
     pub hooks: Vec<HookRegistration>,
+    #[serde(default)]
     pub commands: Vec<CommandRegistration>,
+    #[serde(default)]
     pub ui_contributions: Vec<UiContribution>,
 }
 
+/// File every plugin directory ships its manifest in
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+impl PluginManifest {
+    pub fn parse(json: &str) -> Result<Self, PluginError> {
+        let manifest: PluginManifest = serde_json::from_str(json)
+            .map_err(|e| PluginError::InvalidManifest(e.to_string()))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Read `manifest.json` from a plugin directory
+    pub fn load(plugin_dir: &Path) -> Result<Self, PluginError> {
+        let path = plugin_dir.join(MANIFEST_FILE);
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| PluginError::InvalidManifest(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&json)
+    }
+
+    pub fn validate(&self) -> Result<(), PluginError> {
+        if self.name.is_empty() {
+            return Err(PluginError::InvalidManifest("Plugin name is required".to_string()));
+        }
+        if self.version.is_empty() {
+            return Err(PluginError::InvalidManifest("Plugin version is required".to_string()));
+        }
+        if self.entry_point.is_empty() {
+            return Err(PluginError::InvalidManifest("Plugin entry point is required".to_string()));
+        }
+        if !Path::new(&self.entry_point).components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(PluginError::InvalidManifest(format!(
+                "Plugin entry point must be a relative path inside the plugin directory: {}",
+                self.entry_point
+            )));
+        }
+        Ok(())
+    }
+
+    /// Resolve the entry point inside `plugin_dir`. Symlinks are followed, so
+    /// one pointing out of the directory is rejected too.
+    pub fn entry_path(&self, plugin_dir: &Path) -> Result<PathBuf, PluginError> {
+        let invalid = |e: std::io::Error| PluginError::InvalidManifest(format!("{}: {}", self.entry_point, e));
+        let plugin_dir = std::fs::canonicalize(plugin_dir).map_err(invalid)?;
+        let entry_path = std::fs::canonicalize(plugin_dir.join(&self.entry_point)).map_err(invalid)?;
+        if !entry_path.starts_with(&plugin_dir) {
+            return Err(PluginError::InvalidManifest(format!(
+                "Plugin entry point resolves outside the plugin directory: {}",
+                self.entry_point
+            )));
+        }
+        Ok(entry_path)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PluginCategory {
@@ -69,14 +138,19 @@ pub enum PluginState {
     Updating,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Capabilities a plugin can request. Manifests may use the short
+/// capability names (`fs_read`, `fs_write`, `network`, `db_read`, `db_write`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Permission {
     // File system
+    #[serde(alias = "fs_read")]
     ReadFiles,
+    #[serde(alias = "fs_write")]
     WriteFiles,
     
     // Network
+    #[serde(alias = "network")]
     NetworkAccess,
     
     // Workspace
@@ -94,10 +168,42 @@ pub enum Permission {
     // Data
     ReadSettings,
     WriteSettings,
+    #[serde(alias = "db_read")]
     ReadDatabase,
+    #[serde(alias = "db_write")]
     WriteDatabase,
 }
 
+impl Permission {
+    /// What granting the permission allows, for the install prompt
+    pub fn description(&self) -> &'static str {
+        match self {
+            Permission::ReadFiles => "Read files in its data directory",
+            Permission::WriteFiles => "Create and modify files in its data directory",
+            Permission::NetworkAccess => "Make HTTP requests",
+            Permission::ReadWorkspace => "Read workspace contents",
+            Permission::WriteWorkspace => "Modify workspace contents",
+            Permission::CreateUi => "Add panels and toolbar items",
+            Permission::ModifyUi => "Change existing UI",
+            Permission::SystemInfo => "Read system information",
+            Permission::Notifications => "Show notifications",
+            Permission::ReadSettings => "Read its configuration",
+            Permission::WriteSettings => "Change its configuration",
+            Permission::ReadDatabase => "Read the database",
+            Permission::WriteDatabase => "Modify the database",
+        }
+    }
+}
+
+/// A requested permission as shown to the user before install
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionRequest {
+    pub permission: Permission,
+    pub description: String,
+    /// Whether the current allowlist lets the plugin be enabled with it
+    pub allowed: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SettingsSchema {
     pub properties: HashMap<String, SettingProperty>,
@@ -158,6 +264,90 @@ pub struct PluginEvent {
     pub timestamp: i64,
 }
 
+// ============================================
+// Plugin Errors
+// ============================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PluginError {
+    NotFound(String),
+    NotEnabled(String),
+    InvalidManifest(String),
+    PermissionDenied { plugin_id: String, permissions: Vec<Permission> },
+    /// A host call needed a permission the plugin was not granted
+    CapabilityDenied { plugin_id: String, permission: Permission },
+    InvalidPath(String),
+    Timeout { plugin_id: String, limit_ms: u64 },
+    OutputTooLarge { plugin_id: String, limit_bytes: usize },
+    Unsupported(String),
+    Execution(String),
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::NotFound(id) => write!(f, "Plugin not found: {}", id),
+            PluginError::NotEnabled(id) => write!(f, "Plugin is not enabled: {}", id),
+            PluginError::InvalidManifest(msg) => write!(f, "Invalid plugin manifest: {}", msg),
+            PluginError::PermissionDenied { plugin_id, permissions } => {
+                write!(f, "Plugin {} requests permissions that are not allowed: {:?}", plugin_id, permissions)
+            }
+            PluginError::CapabilityDenied { plugin_id, permission } => {
+                write!(f, "Plugin {} does not have the {:?} permission", plugin_id, permission)
+            }
+            PluginError::InvalidPath(path) => write!(f, "Path is outside the plugin data directory: {}", path),
+            PluginError::Timeout { plugin_id, limit_ms } => {
+                write!(f, "Plugin {} timed out after {} ms", plugin_id, limit_ms)
+            }
+            PluginError::OutputTooLarge { plugin_id, limit_bytes } => {
+                write!(f, "Plugin {} produced more than {} bytes of output", plugin_id, limit_bytes)
+            }
+            PluginError::Unsupported(msg) => write!(f, "Unsupported plugin: {}", msg),
+            PluginError::Execution(msg) => write!(f, "Plugin execution failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+// ============================================
+// Execution Limits
+// ============================================
+
+/// Bounds applied to every plugin invocation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginLimits {
+    /// Wall-clock limit per invocation
+    pub timeout_ms: u64,
+    /// Stdout cap; the plugin is killed as soon as it writes more
+    pub max_output_bytes: usize,
+    /// Virtual memory cap for subprocess plugins (unix only)
+    pub max_memory_bytes: Option<u64>,
+    /// CPU time cap for subprocess plugins (unix only)
+    pub max_cpu_seconds: Option<u64>,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 30_000,
+            max_output_bytes: 1024 * 1024,
+            max_memory_bytes: Some(512 * 1024 * 1024),
+            max_cpu_seconds: Some(30),
+        }
+    }
+}
+
+/// Permissions a plugin may hold without the user widening the allowlist
+pub const DEFAULT_PERMISSION_ALLOWLIST: &[Permission] = &[
+    Permission::ReadFiles,
+    Permission::ReadWorkspace,
+    Permission::CreateUi,
+    Permission::SystemInfo,
+    Permission::Notifications,
+    Permission::ReadSettings,
+];
+
 // ============================================
 // Plugin Manager
 // ============================================
@@ -167,6 +357,8 @@ pub struct PluginManager {
     pub hooks: HashMap<String, Vec<HookHandler>>,
     pub event_listeners: HashMap<String, Vec<EventListener>>,
     pub plugins_dir: PathBuf,
+    pub limits: PluginLimits,
+    pub permission_allowlist: Vec<Permission>,
 }
 
 #[derive(Debug, Clone)]
@@ -189,6 +381,8 @@ impl PluginManager {
             hooks: HashMap::new(),
             event_listeners: HashMap::new(),
             plugins_dir,
+            limits: PluginLimits::default(),
+            permission_allowlist: DEFAULT_PERMISSION_ALLOWLIST.to_vec(),
         }
     }
 
@@ -198,7 +392,7 @@ impl PluginManager {
 
     pub fn install_plugin(&mut self, manifest: PluginManifest, wasm_path: &str) -> Result<Plugin, String> {
         // Validate manifest
-        self.validate_manifest(&manifest)?;
+        manifest.validate().map_err(|e| e.to_string())?;
 
         let now = chrono::Utc::now().timestamp();
         let plugin = Plugin {
@@ -206,7 +400,9 @@ impl PluginManager {
             manifest: manifest.clone(),
             state: PluginState::Installed,
             settings: self.get_default_settings(&manifest),
-            permissions: manifest.permissions.clone(),
+            // Nothing is granted until the user approves the requested permissions
+            permissions: Vec::new(),
+            entry_path: wasm_path.to_string(),
             installed_at: now,
             updated_at: now,
         };
@@ -237,10 +433,21 @@ impl PluginManager {
         Ok(())
     }
 
-    pub fn enable_plugin(&mut self, plugin_id: &str) -> Result<(), String> {
-        let plugin = self.plugins.get_mut(plugin_id)
-            .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
+    /// Enable a plugin once every permission it holds is on the allowlist
+    pub fn enable_plugin(&mut self, plugin_id: &str) -> Result<(), PluginError> {
+        let plugin = self.plugins.get(plugin_id)
+            .ok_or_else(|| PluginError::NotFound(plugin_id.to_string()))?;
 
+        let denied = self.denied_permissions(plugin);
+        if !denied.is_empty() {
+            return Err(PluginError::PermissionDenied {
+                plugin_id: plugin_id.to_string(),
+                permissions: denied,
+            });
+        }
+
+        let plugin = self.plugins.get_mut(plugin_id)
+            .ok_or_else(|| PluginError::NotFound(plugin_id.to_string()))?;
         plugin.state = PluginState::Enabled;
         plugin.updated_at = chrono::Utc::now().timestamp();
         Ok(())
@@ -364,22 +571,74 @@ impl PluginManager {
     // Validation
     // ============================================
 
-    fn validate_manifest(&self, manifest: &PluginManifest) -> Result<(), String> {
-        if manifest.name.is_empty() {
-            return Err("Plugin name is required".to_string());
+    /// Requested permissions annotated for the install prompt
+    pub fn permission_requests(&self, manifest: &PluginManifest) -> Vec<PermissionRequest> {
+        manifest.permissions.iter()
+            .map(|permission| PermissionRequest {
+                permission: permission.clone(),
+                description: permission.description().to_string(),
+                allowed: self.permission_allowlist.contains(permission),
+            })
+            .collect()
+    }
+
+    // ============================================
+    // Permission Checking
+    // ============================================
+
+    /// Permissions the plugin declares or was granted that the allowlist does not cover
+    pub fn denied_permissions(&self, plugin: &Plugin) -> Vec<Permission> {
+        let mut denied: Vec<Permission> = Vec::new();
+        for permission in plugin.manifest.permissions.iter().chain(&plugin.permissions) {
+            if !self.permission_allowlist.contains(permission) && !denied.contains(permission) {
+                denied.push(permission.clone());
+            }
         }
-        if manifest.version.is_empty() {
-            return Err("Plugin version is required".to_string());
+        denied
+    }
+
+    /// Grant permissions the user approved. Only permissions the manifest
+    /// requested can be granted this way.
+    pub fn grant_permissions(&mut self, plugin_id: &str, permissions: &[Permission]) -> Result<(), PluginError> {
+        let plugin = self.plugins.get_mut(plugin_id)
+            .ok_or_else(|| PluginError::NotFound(plugin_id.to_string()))?;
+
+        let unrequested: Vec<Permission> = permissions.iter()
+            .filter(|p| !plugin.manifest.permissions.contains(p))
+            .cloned()
+            .collect();
+        if !unrequested.is_empty() {
+            return Err(PluginError::PermissionDenied {
+                plugin_id: plugin_id.to_string(),
+                permissions: unrequested,
+            });
         }
-        if manifest.entry_point.is_empty() {
-            return Err("Plugin entry point is required".to_string());
+
+        for permission in permissions {
+            if !plugin.permissions.contains(permission) {
+                plugin.permissions.push(permission.clone());
+            }
         }
+        plugin.updated_at = chrono::Utc::now().timestamp();
         Ok(())
     }
 
-    // ============================================
-    // Permission Checking
-    // ============================================
+    /// Fail unless the plugin is enabled and holds `permission`
+    pub fn require_permission(&self, plugin_id: &str, permission: &Permission) -> Result<(), PluginError> {
+        let plugin = self.plugins.get(plugin_id)
+            .ok_or_else(|| PluginError::NotFound(plugin_id.to_string()))?;
+
+        if !matches!(plugin.state, PluginState::Enabled) {
+            return Err(PluginError::NotEnabled(plugin_id.to_string()));
+        }
+        if !plugin.permissions.contains(permission) {
+            return Err(PluginError::CapabilityDenied {
+                plugin_id: plugin_id.to_string(),
+                permission: permission.clone(),
+            });
+        }
+        Ok(())
+    }
 
     pub fn check_permission(&self, plugin_id: &str, permission: &Permission) -> bool {
         self.plugins.get(plugin_id)
@@ -391,11 +650,433 @@ impl PluginManager {
         let plugin = self.plugins.get_mut(plugin_id)
             .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
 
+        // Runtime requests can't reach beyond what the user reviewed at install
+        if !plugin.manifest.permissions.contains(&permission) {
+            return Err(format!("Permission not declared in manifest: {:?}", permission));
+        }
+
+        // An enabled plugin must not gain permissions it could not be enabled with
+        if matches!(plugin.state, PluginState::Enabled) && !self.permission_allowlist.contains(&permission) {
+            return Err(format!("Permission not allowed: {:?}", permission));
+        }
+
         if !plugin.permissions.contains(&permission) {
             plugin.permissions.push(permission);
         }
         Ok(())
     }
+
+    // ============================================
+    // Execution
+    // ============================================
+
+    /// Snapshot what is needed to run a plugin so the manager lock can be
+    /// released while it executes
+    pub fn prepare_invocation(&self, plugin_id: &str) -> Result<PluginInvocation, PluginError> {
+        let plugin = self.plugins.get(plugin_id)
+            .ok_or_else(|| PluginError::NotFound(plugin_id.to_string()))?;
+
+        if !matches!(plugin.state, PluginState::Enabled) {
+            return Err(PluginError::NotEnabled(plugin_id.to_string()));
+        }
+
+        Ok(PluginInvocation {
+            plugin_id: plugin.id.clone(),
+            entry_path: PathBuf::from(&plugin.entry_path),
+            data_dir: self.plugin_data_dir(plugin_id),
+            settings: plugin.settings.clone(),
+            permissions: plugin.permissions.clone(),
+            limits: self.limits.clone(),
+        })
+    }
+
+    // ============================================
+    // Host Calls
+    // ============================================
+
+    /// Directory a plugin's file calls are confined to
+    pub fn plugin_data_dir(&self, plugin_id: &str) -> PathBuf {
+        self.plugins_dir.join(plugin_id).join("data")
+    }
+
+    /// Read the plugin's own configuration. Requires `read_settings`.
+    pub fn get_config(&self, plugin_id: &str, key: Option<&str>) -> Result<serde_json::Value, PluginError> {
+        self.require_permission(plugin_id, &Permission::ReadSettings)?;
+        let plugin = self.plugins.get(plugin_id)
+            .ok_or_else(|| PluginError::NotFound(plugin_id.to_string()))?;
+
+        Ok(match key {
+            Some(key) => plugin.settings.get(key).cloned().unwrap_or(serde_json::Value::Null),
+            None => serde_json::to_value(&plugin.settings).unwrap_or(serde_json::Value::Null),
+        })
+    }
+
+    /// Change one key of the plugin's own configuration. Requires `write_settings`.
+    pub fn set_config(&mut self, plugin_id: &str, key: &str, value: serde_json::Value) -> Result<(), PluginError> {
+        self.require_permission(plugin_id, &Permission::WriteSettings)?;
+        let plugin = self.plugins.get_mut(plugin_id)
+            .ok_or_else(|| PluginError::NotFound(plugin_id.to_string()))?;
+
+        plugin.settings.insert(key.to_string(), value);
+        plugin.updated_at = chrono::Utc::now().timestamp();
+        Ok(())
+    }
+}
+
+// ============================================
+// Host Call Boundary
+// ============================================
+
+/// Services a plugin can ask the host for. Each call is gated by one permission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HostCall {
+    ReadFile { path: String },
+    WriteFile { path: String, contents: String },
+    HttpRequest { method: String, url: String, body: Option<String> },
+    GetConfig { key: Option<String> },
+    SetConfig { key: String, value: serde_json::Value },
+}
+
+impl HostCall {
+    pub fn required_permission(&self) -> Permission {
+        match self {
+            HostCall::ReadFile { .. } => Permission::ReadFiles,
+            HostCall::WriteFile { .. } => Permission::WriteFiles,
+            HostCall::HttpRequest { .. } => Permission::NetworkAccess,
+            HostCall::GetConfig { .. } => Permission::ReadSettings,
+            HostCall::SetConfig { .. } => Permission::WriteSettings,
+        }
+    }
+}
+
+/// Resolve a plugin-supplied path inside its data directory. Absolute paths
+/// and `..` are rejected so a plugin cannot reach other files.
+pub fn resolve_plugin_path(data_dir: &Path, path: &str) -> Result<PathBuf, PluginError> {
+    let relative = Path::new(path);
+    let escapes = relative.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if path.is_empty() || escapes {
+        return Err(PluginError::InvalidPath(path.to_string()));
+    }
+    Ok(data_dir.join(relative))
+}
+
+/// Perform a file or network host call that was already authorized. Config
+/// calls go through the `PluginConfigHost` instead.
+pub async fn execute_host_call(data_dir: &Path, call: HostCall) -> Result<serde_json::Value, PluginError> {
+    match call {
+        HostCall::ReadFile { path } => {
+            let path = resolve_plugin_path(data_dir, &path)?;
+            let contents = tokio::fs::read_to_string(&path).await
+                .map_err(|e| PluginError::Execution(format!("Failed to read {}: {}", path.display(), e)))?;
+            Ok(serde_json::Value::String(contents))
+        }
+        HostCall::WriteFile { path, contents } => {
+            let path = resolve_plugin_path(data_dir, &path)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await
+                    .map_err(|e| PluginError::Execution(e.to_string()))?;
+            }
+            tokio::fs::write(&path, contents).await
+                .map_err(|e| PluginError::Execution(format!("Failed to write {}: {}", path.display(), e)))?;
+            Ok(serde_json::Value::Null)
+        }
+        HostCall::HttpRequest { method, url, body } => {
+            let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+                .map_err(|e| PluginError::Execution(e.to_string()))?;
+            let mut request = reqwest::Client::new().request(method, &url).timeout(Duration::from_secs(30));
+            if let Some(body) = body {
+                request = request.body(body);
+            }
+            let response = request.send().await
+                .map_err(|e| PluginError::Execution(format!("Request to {} failed: {}", url, e)))?;
+            let status = response.status().as_u16();
+            let body = response.text().await
+                .map_err(|e| PluginError::Execution(e.to_string()))?;
+            Ok(serde_json::json!({ "status": status, "body": body }))
+        }
+        HostCall::GetConfig { .. } | HostCall::SetConfig { .. } => Err(PluginError::Unsupported(
+            "Config calls are handled by the plugin manager".to_string(),
+        )),
+    }
+}
+
+/// Config storage a running plugin reaches through its host calls. The
+/// manager lock is not held while a plugin runs, so whoever owns the manager
+/// takes it for each call.
+#[async_trait::async_trait]
+pub trait PluginConfigHost: Send + Sync {
+    async fn get_config(&self, plugin_id: &str, key: Option<&str>) -> Result<serde_json::Value, PluginError>;
+    async fn set_config(&self, plugin_id: &str, key: &str, value: serde_json::Value) -> Result<(), PluginError>;
+}
+
+/// Stdout line a plugin writes to ask for a host service
+#[derive(Debug, Deserialize)]
+struct HostCallMessage {
+    host_call: HostCall,
+}
+
+/// Serve a host call for the plugin that made it, checked against its own grants
+async fn serve_host_call<H: PluginConfigHost + ?Sized>(
+    invocation: &PluginInvocation,
+    host: &H,
+    call: HostCall,
+) -> Result<serde_json::Value, PluginError> {
+    invocation.authorize(&call)?;
+    match call {
+        HostCall::GetConfig { key } => host.get_config(&invocation.plugin_id, key.as_deref()).await,
+        HostCall::SetConfig { key, value } => {
+            host.set_config(&invocation.plugin_id, &key, value).await?;
+            Ok(serde_json::Value::Null)
+        }
+        call => execute_host_call(&invocation.data_dir, call).await,
+    }
+}
+
+// ============================================
+// Sandboxed Execution
+// ============================================
+
+#[derive(Debug, Clone)]
+pub struct PluginInvocation {
+    pub plugin_id: String,
+    pub entry_path: PathBuf,
+    /// Directory the plugin's file host calls are confined to
+    pub data_dir: PathBuf,
+    pub settings: HashMap<String, serde_json::Value>,
+    pub permissions: Vec<Permission>,
+    pub limits: PluginLimits,
+}
+
+impl PluginInvocation {
+    /// Check a host call against the permissions granted when the invocation started
+    pub fn authorize(&self, call: &HostCall) -> Result<(), PluginError> {
+        let permission = call.required_permission();
+        if !self.permissions.contains(&permission) {
+            return Err(PluginError::CapabilityDenied {
+                plugin_id: self.plugin_id.clone(),
+                permission,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginOutput {
+    /// Parsed stdout, or the raw text when it is not JSON
+    pub output: serde_json::Value,
+    pub duration_ms: u64,
+}
+
+/// Run a subprocess plugin in its sandbox and under its limits. Host and
+/// plugin exchange one JSON message per line over stdio:
+/// - the host writes the request (`handler`, `input`, `settings`, `permissions`)
+/// - a line `{"host_call": ...}` asks for a host service. It is checked
+///   against this plugin's grants and answered with `{"ok": ...}` or
+///   `{"error": "..."}`.
+/// - any other line is the result, parsed as JSON or kept as text. Stdin is
+///   then closed and the plugin is expected to exit.
+pub async fn run_plugin<H: PluginConfigHost + ?Sized>(
+    invocation: &PluginInvocation,
+    handler: &str,
+    input: serde_json::Value,
+    host: &H,
+) -> Result<PluginOutput, PluginError> {
+    if invocation.entry_path.extension().is_some_and(|ext| ext == "wasm") {
+        return Err(PluginError::Unsupported(
+            "WASM plugins cannot be executed by this build".to_string(),
+        ));
+    }
+
+    let limits = &invocation.limits;
+    let request = serde_json::json!({
+        "handler": handler,
+        "input": input,
+        "settings": invocation.settings,
+        "permissions": invocation.permissions,
+    });
+
+    let entry_path = std::fs::canonicalize(&invocation.entry_path).map_err(|e| {
+        PluginError::Execution(format!("Plugin entry point {}: {}", invocation.entry_path.display(), e))
+    })?;
+
+    let mut child = plugin_command(&entry_path, limits)?
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| PluginError::Execution(format!("Failed to start plugin: {}", e)))?;
+
+    let mut stdin = child.stdin.take()
+        .ok_or_else(|| PluginError::Execution("Failed to open plugin stdin".to_string()))?;
+    let stdout = child.stdout.take()
+        .ok_or_else(|| PluginError::Execution("Failed to capture plugin stdout".to_string()))?;
+    let stderr = child.stderr.take()
+        .ok_or_else(|| PluginError::Execution("Failed to capture plugin stderr".to_string()))?;
+
+    let start = Instant::now();
+    let timeout = Duration::from_millis(limits.timeout_ms);
+    let max_output = limits.max_output_bytes;
+    let too_large = || PluginError::OutputTooLarge {
+        plugin_id: invocation.plugin_id.clone(),
+        limit_bytes: max_output,
+    };
+    let read_failed = |e: std::io::Error| PluginError::Execution(format!("Failed to read plugin output: {}", e));
+
+    let result = tokio::time::timeout(timeout, async {
+        // Stderr is drained past the cap so a chatty plugin cannot stall on a full pipe
+        let stderr_task = tokio::spawn(read_capped(stderr, max_output, true));
+
+        // A plugin that never reads stdin must not block the invocation
+        let _ = write_message(&mut stdin, &request).await;
+
+        let mut stdout = BufReader::new(stdout);
+        let mut output_bytes = 0;
+        let result = loop {
+            let mut line = Vec::new();
+            let read = (&mut stdout).take((max_output - output_bytes) as u64 + 1)
+                .read_until(b'\n', &mut line).await
+                .map_err(read_failed)?;
+            output_bytes += read;
+            if output_bytes > max_output {
+                return Err(too_large());
+            }
+            if read == 0 {
+                break line;
+            }
+
+            match serde_json::from_slice::<HostCallMessage>(&line) {
+                Ok(message) => {
+                    let reply = match serve_host_call(invocation, host, message.host_call).await {
+                        Ok(value) => serde_json::json!({ "ok": value }),
+                        Err(e) => serde_json::json!({ "error": e.to_string() }),
+                    };
+                    let _ = write_message(&mut stdin, &reply).await;
+                }
+                Err(_) => break line,
+            }
+        };
+        drop(stdin);
+
+        // Output after the result still counts toward the cap
+        let (_, overflowed) = read_capped(stdout, max_output - output_bytes, false).await
+            .map_err(read_failed)?;
+        if overflowed {
+            return Err(too_large());
+        }
+
+        let status = child.wait().await
+            .map_err(|e| PluginError::Execution(format!("Failed to wait for plugin: {}", e)))?;
+        let (stderr, _) = stderr_task.await
+            .map_err(|e| PluginError::Execution(e.to_string()))?
+            .unwrap_or_default();
+
+        Ok((status, result, stderr))
+    }).await;
+
+    let (status, stdout, stderr) = match result {
+        Ok(Ok(finished)) => finished,
+        Ok(Err(e)) => {
+            let _ = child.kill().await;
+            return Err(e);
+        }
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(PluginError::Timeout {
+                plugin_id: invocation.plugin_id.clone(),
+                limit_ms: limits.timeout_ms,
+            });
+        }
+    };
+
+    if !status.success() {
+        return Err(PluginError::Execution(format!(
+            "Plugin exited with {}: {}",
+            status,
+            String::from_utf8_lossy(&stderr).trim(),
+        )));
+    }
+
+    let text = String::from_utf8_lossy(&stdout);
+    let output = serde_json::from_str(text.trim())
+        .unwrap_or_else(|_| serde_json::Value::String(text.into_owned()));
+
+    Ok(PluginOutput {
+        output,
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &serde_json::Value) -> std::io::Result<()> {
+    writer.write_all(format!("{}\n", message).as_bytes()).await?;
+    writer.flush().await
+}
+
+/// Run the entry point in new user, network and mount namespaces with every
+/// mount remounted read-only, so the plugin reaches the network and writes
+/// files only through host calls. If the sandbox cannot be set up the plugin
+/// does not run. Rlimits the platform refuses are skipped.
+#[cfg(target_os = "linux")]
+fn plugin_command(entry_path: &Path, limits: &PluginLimits) -> Result<tokio::process::Command, PluginError> {
+    let mut script = String::from(
+        "while read -r _ mount _; do mount -o remount,bind,ro \"$mount\" || exit 126; done < /proc/self/mounts; ",
+    );
+    if let Some(bytes) = limits.max_memory_bytes {
+        script.push_str(&format!("ulimit -v {} 2>/dev/null; ", bytes / 1024));
+    }
+    if let Some(seconds) = limits.max_cpu_seconds {
+        script.push_str(&format!("ulimit -t {} 2>/dev/null; ", seconds));
+    }
+    script.push_str("exec \"$0\"");
+
+    let mut command = tokio::process::Command::new("unshare");
+    command
+        .args(["--user", "--map-root-user", "--net", "--mount", "sh", "-c"])
+        .arg(script)
+        .arg(entry_path);
+    if let Some(dir) = entry_path.parent() {
+        command.current_dir(dir);
+    }
+    Ok(command)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn plugin_command(_entry_path: &Path, _limits: &PluginLimits) -> Result<tokio::process::Command, PluginError> {
+    Err(PluginError::Unsupported(
+        "Subprocess plugins only run sandboxed, which this platform does not support yet".to_string(),
+    ))
+}
+
+/// Read up to `cap` bytes. Returns whether the stream went past the cap; with
+/// `drain` the rest is read and discarded, otherwise reading stops there.
+async fn read_capped<R: AsyncRead + Unpin>(
+    mut reader: R,
+    cap: usize,
+    drain: bool,
+) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let mut overflowed = false;
+
+    loop {
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+
+        let room = cap.saturating_sub(buffer.len());
+        buffer.extend_from_slice(&chunk[..read.min(room)]);
+        if read > room {
+            overflowed = true;
+            if !drain {
+                break;
+            }
+        }
+    }
+
+    Ok((buffer, overflowed))
 }
 
 // ============================================
@@ -478,3 +1159,371 @@ pub fn get_plugin_template() -> String {
   "ui_contributions": []
 }"#.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::Mutex;
+
+    #[async_trait::async_trait]
+    impl PluginConfigHost for Mutex<PluginManager> {
+        async fn get_config(&self, plugin_id: &str, key: Option<&str>) -> Result<serde_json::Value, PluginError> {
+            self.lock().await.get_config(plugin_id, key)
+        }
+
+        async fn set_config(&self, plugin_id: &str, key: &str, value: serde_json::Value) -> Result<(), PluginError> {
+            self.lock().await.set_config(plugin_id, key, value)
+        }
+    }
+
+    fn manifest(permissions: Vec<Permission>) -> PluginManifest {
+        serde_json::from_str::<PluginManifest>(&get_plugin_template())
+            .map(|manifest| PluginManifest { permissions, ..manifest })
+            .unwrap()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn mock_plugin(dir: &Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("plugin.sh");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(target_os = "linux")]
+    fn enabled_plugin(entry: &Path, limits: PluginLimits) -> (PluginManager, String) {
+        let mut manager = PluginManager::new(entry.parent().unwrap().to_path_buf());
+        manager.limits = limits;
+        let plugin = manager.install_plugin(manifest(vec![]), entry.to_str().unwrap()).unwrap();
+        manager.enable_plugin(&plugin.id).unwrap();
+        (manager, plugin.id)
+    }
+
+    #[test]
+    fn test_enable_requires_allowlisted_permissions() {
+        let mut manager = PluginManager::new(PathBuf::from("plugins"));
+        let plugin = manager.install_plugin(
+            manifest(vec![Permission::ReadWorkspace, Permission::WriteDatabase]),
+            "main.wasm",
+        ).unwrap();
+
+        let err = manager.enable_plugin(&plugin.id).unwrap_err();
+        assert_eq!(err, PluginError::PermissionDenied {
+            plugin_id: plugin.id.clone(),
+            permissions: vec![Permission::WriteDatabase],
+        });
+        assert!(matches!(manager.get_plugin(&plugin.id).unwrap().state, PluginState::Installed));
+
+        manager.permission_allowlist.push(Permission::WriteDatabase);
+        manager.enable_plugin(&plugin.id).unwrap();
+
+        // Requests are limited to what the manifest declares, even when allowlisted
+        assert_eq!(
+            manager.request_permission(&plugin.id, Permission::ReadFiles),
+            Err("Permission not declared in manifest: ReadFiles".to_string())
+        );
+        assert!(manager.request_permission(&plugin.id, Permission::ReadWorkspace).is_ok());
+        assert!(manager.check_permission(&plugin.id, &Permission::ReadWorkspace));
+
+        // Enabled plugins cannot pick up permissions outside the allowlist
+        manager.permission_allowlist.retain(|p| p != &Permission::WriteDatabase);
+        assert_eq!(
+            manager.request_permission(&plugin.id, Permission::WriteDatabase),
+            Err("Permission not allowed: WriteDatabase".to_string())
+        );
+    }
+
+    #[test]
+    fn test_manifest_accepts_capability_names() {
+        let manifest = PluginManifest::parse(r#"{
+            "name": "fetcher",
+            "version": "0.1.0",
+            "description": "Fetches things",
+            "author": "someone",
+            "category": "integrations",
+            "min_app_version": "1.0.0",
+            "entry_point": "main.sh",
+            "permissions": ["network", "fs_write", "read_settings"],
+            "settings_schema": null
+        }"#).unwrap();
+
+        assert_eq!(manifest.permissions, vec![
+            Permission::NetworkAccess,
+            Permission::WriteFiles,
+            Permission::ReadSettings,
+        ]);
+        assert!(manifest.hooks.is_empty());
+
+        let manager = PluginManager::new(PathBuf::from("plugins"));
+        let requests = manager.permission_requests(&manifest);
+        assert!(!requests[0].allowed);
+        assert!(requests[2].allowed);
+
+        // An otherwise valid manifest is rejected for the unknown permission alone
+        let mut unknown: serde_json::Value = serde_json::from_str(&get_plugin_template()).unwrap();
+        unknown["permissions"] = serde_json::json!(["read_files", "teleport"]);
+        assert!(PluginManifest::parse(&get_plugin_template()).is_ok());
+        match PluginManifest::parse(&unknown.to_string()) {
+            Err(PluginError::InvalidManifest(msg)) => assert!(msg.contains("teleport"), "{}", msg),
+            other => panic!("expected an invalid manifest, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_entry_point_stays_in_plugin_dir() {
+        for entry_point in ["/usr/bin/env", "../../bin/sh", "bin/../../sh", "./main.sh"] {
+            let manifest = PluginManifest { entry_point: entry_point.to_string(), ..manifest(vec![]) };
+            assert!(
+                matches!(manifest.validate(), Err(PluginError::InvalidManifest(_))),
+                "{} was accepted",
+                entry_point
+            );
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let plugin_dir = dir.path().join("plugin");
+        std::fs::create_dir(&plugin_dir).unwrap();
+        std::fs::write(plugin_dir.join("main.wasm"), b"").unwrap();
+        std::fs::write(dir.path().join("outside.wasm"), b"").unwrap();
+
+        let entry_path = manifest(vec![]).entry_path(&plugin_dir).unwrap();
+        assert_eq!(entry_path, std::fs::canonicalize(plugin_dir.join("main.wasm")).unwrap());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path().join("outside.wasm"), plugin_dir.join("link.wasm")).unwrap();
+            let linked = PluginManifest { entry_point: "link.wasm".to_string(), ..manifest(vec![]) };
+            assert!(matches!(linked.entry_path(&plugin_dir), Err(PluginError::InvalidManifest(_))));
+        }
+    }
+
+    #[test]
+    fn test_install_grants_only_approved_requested_permissions() {
+        let mut manager = PluginManager::new(PathBuf::from("plugins"));
+        let plugin = manager.install_plugin(
+            manifest(vec![Permission::ReadFiles, Permission::ReadSettings]),
+            "main.wasm",
+        ).unwrap();
+        assert!(plugin.permissions.is_empty());
+
+        manager.grant_permissions(&plugin.id, &[Permission::ReadSettings]).unwrap();
+        assert!(manager.check_permission(&plugin.id, &Permission::ReadSettings));
+        assert!(!manager.check_permission(&plugin.id, &Permission::ReadFiles));
+
+        assert!(matches!(
+            manager.grant_permissions(&plugin.id, &[Permission::NetworkAccess]),
+            Err(PluginError::PermissionDenied { .. })
+        ));
+    }
+
+    #[test]
+    fn test_host_calls_without_capability_are_denied() {
+        let mut manager = PluginManager::new(PathBuf::from("plugins"));
+        manager.permission_allowlist.push(Permission::WriteFiles);
+        let plugin = manager.install_plugin(
+            manifest(vec![Permission::ReadFiles, Permission::WriteFiles]),
+            "main.wasm",
+        ).unwrap();
+        manager.grant_permissions(&plugin.id, &[Permission::ReadFiles]).unwrap();
+
+        let http = HostCall::HttpRequest {
+            method: "GET".to_string(),
+            url: "https://example.com".to_string(),
+            body: None,
+        };
+        let write = HostCall::WriteFile { path: "out.txt".to_string(), contents: "x".to_string() };
+        let read = HostCall::ReadFile { path: "out.txt".to_string() };
+
+        // Nothing is callable until the plugin is enabled
+        assert_eq!(manager.prepare_invocation(&plugin.id).unwrap_err(), PluginError::NotEnabled(plugin.id.clone()));
+
+        manager.enable_plugin(&plugin.id).unwrap();
+        let invocation = manager.prepare_invocation(&plugin.id).unwrap();
+        assert_eq!(invocation.authorize(&http), Err(PluginError::CapabilityDenied {
+            plugin_id: plugin.id.clone(),
+            permission: Permission::NetworkAccess,
+        }));
+        assert_eq!(invocation.authorize(&write), Err(PluginError::CapabilityDenied {
+            plugin_id: plugin.id.clone(),
+            permission: Permission::WriteFiles,
+        }));
+        assert!(invocation.authorize(&read).is_ok());
+
+        manager.grant_permissions(&plugin.id, &[Permission::WriteFiles]).unwrap();
+        assert!(manager.prepare_invocation(&plugin.id).unwrap().authorize(&write).is_ok());
+    }
+
+    #[test]
+    fn test_config_access_is_permission_scoped() {
+        let mut manager = PluginManager::new(PathBuf::from("plugins"));
+        let plugin = manager.install_plugin(manifest(vec![Permission::ReadSettings]), "main.wasm").unwrap();
+        manager.grant_permissions(&plugin.id, &[Permission::ReadSettings]).unwrap();
+        manager.enable_plugin(&plugin.id).unwrap();
+
+        assert_eq!(manager.get_config(&plugin.id, Some("missing")).unwrap(), serde_json::Value::Null);
+        assert!(matches!(
+            manager.set_config(&plugin.id, "theme", serde_json::json!("dark")),
+            Err(PluginError::CapabilityDenied { permission: Permission::WriteSettings, .. })
+        ));
+        assert!(manager.get_plugin_settings(&plugin.id).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_host_calls_stay_in_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+
+        execute_host_call(dir.path(), HostCall::WriteFile {
+            path: "notes/out.txt".to_string(),
+            contents: "hello".to_string(),
+        }).await.unwrap();
+        let contents = execute_host_call(dir.path(), HostCall::ReadFile {
+            path: "notes/out.txt".to_string(),
+        }).await.unwrap();
+        assert_eq!(contents, "hello");
+
+        for path in ["../escape.txt", "/etc/passwd", ""] {
+            let result = execute_host_call(dir.path(), HostCall::WriteFile {
+                path: path.to_string(),
+                contents: "x".to_string(),
+            }).await;
+            assert_eq!(result, Err(PluginError::InvalidPath(path.to_string())));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_plugin_receives_request_and_returns_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = mock_plugin(dir.path(), "cat");
+        let (manager, plugin_id) = enabled_plugin(&entry, PluginLimits::default());
+
+        let invocation = manager.prepare_invocation(&plugin_id).unwrap();
+        let result = run_plugin(&invocation, "onExport", serde_json::json!({ "doc": 1 }), &Mutex::new(manager)).await.unwrap();
+
+        assert_eq!(result.output["handler"], "onExport");
+        assert_eq!(result.output["input"]["doc"], 1);
+        assert_eq!(result.output["permissions"], serde_json::json!([]));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_host_calls_are_served_over_stdio_with_the_plugins_grants() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = mock_plugin(dir.path(), r#"
+read -r request
+echo '{"host_call":{"type":"write_file","path":"notes/out.txt","contents":"hello"}}'
+read -r written
+echo '{"host_call":{"type":"http_request","method":"GET","url":"https://example.com"}}'
+read -r fetched
+echo '{"host_call":{"type":"set_config","key":"theme","value":"dark"}}'
+read -r configured
+echo "{\"written\":$written,\"fetched\":$fetched,\"configured\":$configured}""#);
+        let mut manager = PluginManager::new(dir.path().to_path_buf());
+        manager.permission_allowlist.extend([Permission::WriteFiles, Permission::NetworkAccess, Permission::WriteSettings]);
+        let plugin = manager.install_plugin(
+            manifest(vec![Permission::WriteFiles, Permission::NetworkAccess, Permission::WriteSettings]),
+            entry.to_str().unwrap(),
+        ).unwrap();
+        manager.grant_permissions(&plugin.id, &[Permission::WriteFiles, Permission::WriteSettings]).unwrap();
+        manager.enable_plugin(&plugin.id).unwrap();
+
+        let invocation = manager.prepare_invocation(&plugin.id).unwrap();
+        let host = Mutex::new(manager);
+        let result = run_plugin(&invocation, "onInit", serde_json::Value::Null, &host).await.unwrap();
+
+        assert_eq!(result.output["written"], serde_json::json!({ "ok": null }));
+        assert_eq!(
+            std::fs::read_to_string(invocation.data_dir.join("notes/out.txt")).unwrap(),
+            "hello"
+        );
+        assert_eq!(result.output["fetched"]["error"], PluginError::CapabilityDenied {
+            plugin_id: plugin.id.clone(),
+            permission: Permission::NetworkAccess,
+        }.to_string());
+        assert_eq!(result.output["configured"], serde_json::json!({ "ok": null }));
+        assert_eq!(host.lock().await.get_plugin_settings(&plugin.id).unwrap()["theme"], "dark");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_plugin_cannot_write_files_or_reach_the_network_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = mock_plugin(dir.path(), r#"
+read -r request
+if touch escaped.txt 2>/dev/null; then wrote=true; else wrote=false; fi
+echo "{\"wrote\":$wrote,\"interfaces\":$(grep -c : /proc/net/dev)}""#);
+        let (manager, plugin_id) = enabled_plugin(&entry, PluginLimits::default());
+        let invocation = manager.prepare_invocation(&plugin_id).unwrap();
+
+        let result = run_plugin(&invocation, "onInit", serde_json::Value::Null, &Mutex::new(manager)).await.unwrap();
+
+        assert_eq!(result.output["wrote"], false);
+        assert!(!dir.path().join("escaped.txt").exists());
+        // Only the loopback interface exists in the plugin's network namespace
+        assert_eq!(result.output["interfaces"], 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_slow_plugin_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = mock_plugin(dir.path(), "sleep 30");
+        let limits = PluginLimits { timeout_ms: 200, ..PluginLimits::default() };
+        let (manager, plugin_id) = enabled_plugin(&entry, limits);
+
+        let invocation = manager.prepare_invocation(&plugin_id).unwrap();
+        let start = Instant::now();
+        let err = run_plugin(&invocation, "onInit", serde_json::Value::Null, &Mutex::new(manager)).await.unwrap_err();
+
+        assert_eq!(err, PluginError::Timeout { plugin_id, limit_ms: 200 });
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_verbose_plugin_output_is_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = mock_plugin(dir.path(), "while :; do echo spam spam spam spam; done");
+        let limits = PluginLimits { max_output_bytes: 4096, ..PluginLimits::default() };
+        let (manager, plugin_id) = enabled_plugin(&entry, limits);
+
+        let invocation = manager.prepare_invocation(&plugin_id).unwrap();
+        let err = run_plugin(&invocation, "onInit", serde_json::Value::Null, &Mutex::new(manager)).await.unwrap_err();
+
+        assert_eq!(err, PluginError::OutputTooLarge { plugin_id, limit_bytes: 4096 });
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_cpu_limit_kills_busy_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = mock_plugin(dir.path(), "while :; do :; done");
+        let limits = PluginLimits { max_cpu_seconds: Some(1), ..PluginLimits::default() };
+        let (manager, plugin_id) = enabled_plugin(&entry, limits);
+
+        let invocation = manager.prepare_invocation(&plugin_id).unwrap();
+        let err = run_plugin(&invocation, "onInit", serde_json::Value::Null, &Mutex::new(manager)).await.unwrap_err();
+
+        assert!(matches!(err, PluginError::Execution(_)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_plugin_is_not_invoked() {
+        let mut manager = PluginManager::new(PathBuf::from("plugins"));
+        let plugin = manager.install_plugin(manifest(vec![]), "main.wasm").unwrap();
+
+        assert_eq!(
+            manager.prepare_invocation(&plugin.id).unwrap_err(),
+            PluginError::NotEnabled(plugin.id.clone())
+        );
+
+        manager.enable_plugin(&plugin.id).unwrap();
+        let invocation = manager.prepare_invocation(&plugin.id).unwrap();
+        assert!(matches!(
+            run_plugin(&invocation, "onInit", serde_json::Value::Null, &Mutex::new(manager)).await,
+            Err(PluginError::Unsupported(_))
+        ));
+    }
+}
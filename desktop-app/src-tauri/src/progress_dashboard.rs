@@ -2,11 +2,13 @@
 //
 // Provides:
 // - Kanban board state management
-// - Timeline tracking
+// - Timeline tracking, bucketed by hour/day/week/month in the user's timezone
 // - Progress metrics and charts
 // - Reports generation
 
 use std::collections::HashMap;
+use chrono::{DateTime, Datelike, Days, Duration, LocalResult, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -196,6 +198,250 @@ pub struct BurndownPoint {
     pub remaining: i32,
     pub completed: i32,
     pub ideal: f64,
+    /// Tasks in scope at `date`; rises when work is added mid-sprint
+    #[serde(default)]
+    pub scope: i32,
+}
+
+// ============================================
+// Burndown
+// ============================================
+
+/// When a task entered scope, was finished, and left scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskHistory {
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    /// Set for cancelled tasks; they stop counting towards scope from here
+    pub removed_at: Option<i64>,
+}
+
+/// Replay task history between `start` and `end`, with one point at each
+/// moment scope or completion changed. The ideal line runs from the scope
+/// at `start` to zero at `ideal_end`, so added work widens the gap to the
+/// ideal instead of showing up as negative progress.
+pub fn compute_burndown(history: &[TaskHistory], start: i64, end: i64, ideal_end: i64) -> Vec<BurndownPoint> {
+    // (time, scope delta, completed delta)
+    let mut events: Vec<(i64, i32, i32)> = Vec::new();
+    for task in history {
+        let removed = task.removed_at.map(|r| r.max(task.created_at));
+        if removed == Some(task.created_at) {
+            continue;
+        }
+        events.push((task.created_at, 1, 0));
+        if let Some(removed) = removed {
+            events.push((removed, -1, 0));
+        }
+        if let Some(completed) = task.completed_at.map(|c| c.max(task.created_at)) {
+            if removed.is_none_or(|r| completed < r) {
+                events.push((completed, 0, 1));
+                if let Some(removed) = removed {
+                    events.push((removed, 0, -1));
+                }
+            }
+        }
+    }
+    events.sort_unstable_by_key(|e| e.0);
+
+    let (mut scope, mut completed) = (0, 0);
+    let mut pending = events.iter().peekable();
+    while let Some(&&(time, d_scope, d_completed)) = pending.peek() {
+        if time > start {
+            break;
+        }
+        scope += d_scope;
+        completed += d_completed;
+        pending.next();
+    }
+
+    let original_scope = scope as f64;
+    let ideal = |date: i64| {
+        if date <= start {
+            original_scope
+        } else if ideal_end <= start {
+            0.0
+        } else {
+            (original_scope * (1.0 - (date - start) as f64 / (ideal_end - start) as f64)).max(0.0)
+        }
+    };
+    let point = |date: i64, scope: i32, completed: i32| BurndownPoint {
+        date,
+        remaining: scope - completed,
+        completed,
+        ideal: ideal(date),
+        scope,
+    };
+
+    let mut points = vec![point(start, scope, completed)];
+    while let Some(&&(time, _, _)) = pending.peek() {
+        if time > end {
+            break;
+        }
+        while let Some((_, d_scope, d_completed)) = pending.next_if(|e| e.0 == time) {
+            scope += d_scope;
+            completed += d_completed;
+        }
+        points.push(point(time, scope, completed));
+    }
+    if points.last().is_some_and(|p| p.date < end) {
+        points.push(point(end, scope, completed));
+    }
+    points
+}
+
+// ============================================
+// Timeline Buckets
+// ============================================
+
+/// Upper bound on buckets per request, so an hourly view over years fails fast
+pub const MAX_TIMELINE_BUCKETS: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineGranularity {
+    Hour,
+    Day,
+    /// ISO weeks, starting Monday
+    Week,
+    Month,
+}
+
+impl TimelineGranularity {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "hour" => Ok(Self::Hour),
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            _ => Err(format!("Invalid granularity: {}", s)),
+        }
+    }
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>().map_err(|_| format!("Unknown timezone: {}", name))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineBucket {
+    /// Unix timestamp of the bucket's local start
+    pub start: i64,
+    /// Exclusive end; buckets differ in length across DST changes
+    pub end: i64,
+    /// Local start time with its UTC offset, e.g. 2024-03-10T00:00-05:00
+    pub label: String,
+    pub created: u32,
+    pub completed: u32,
+}
+
+/// Resolve a local wall-clock time to an instant. Ambiguous times (clocks
+/// going back) take the earlier instant; skipped times (clocks going forward)
+/// move to the first valid minute after the gap.
+fn resolve_local(tz: &Tz, mut local: NaiveDateTime) -> DateTime<Tz> {
+    loop {
+        match tz.from_local_datetime(&local) {
+            LocalResult::Single(dt) => return dt,
+            LocalResult::Ambiguous(earliest, _) => return earliest,
+            LocalResult::None => local += Duration::minutes(1),
+        }
+    }
+}
+
+/// Start of the bucket containing `ts`, or `None` if it lies outside the
+/// dates chrono can represent
+fn bucket_floor(ts: i64, granularity: TimelineGranularity, tz: &Tz) -> Option<DateTime<Tz>> {
+    let local = tz.timestamp_opt(ts, 0).single()?;
+    let midnight = |date: NaiveDate| resolve_local(tz, date.and_time(NaiveTime::MIN));
+    match granularity {
+        // Truncate the zoned time so repeated local hours stay separate
+        TimelineGranularity::Hour => {
+            let into_hour = local.minute() as i64 * 60 + local.second() as i64;
+            tz.timestamp_opt(ts - into_hour, 0).single()
+        }
+        TimelineGranularity::Day => Some(midnight(local.date_naive())),
+        TimelineGranularity::Week => {
+            let days_into_week = local.weekday().num_days_from_monday() as u64;
+            local.date_naive().checked_sub_days(Days::new(days_into_week)).map(midnight)
+        }
+        TimelineGranularity::Month => local.date_naive().with_day(1).map(midnight),
+    }
+}
+
+/// Start of the bucket after `start`, or `None` past the last representable date
+fn bucket_next(start: &DateTime<Tz>, granularity: TimelineGranularity, tz: &Tz) -> Option<DateTime<Tz>> {
+    let midnight = |date: NaiveDate| resolve_local(tz, date.and_time(NaiveTime::MIN));
+    match granularity {
+        TimelineGranularity::Hour => start.checked_add_signed(Duration::hours(1)),
+        TimelineGranularity::Day => start.date_naive().checked_add_days(Days::new(1)).map(midnight),
+        TimelineGranularity::Week => start.date_naive().checked_add_days(Days::new(7)).map(midnight),
+        TimelineGranularity::Month => start.date_naive().checked_add_months(Months::new(1)).map(midnight),
+    }
+}
+
+/// Whether chrono can place `ts` on a calendar at all
+fn is_representable(ts: i64) -> bool {
+    DateTime::from_timestamp(ts, 0).is_some()
+}
+
+/// Count creations and completions per bucket between `range` (or the span of
+/// the events), including empty buckets so charts do not skip periods
+pub fn bucket_activity(
+    created: &[i64],
+    completed: &[i64],
+    granularity: TimelineGranularity,
+    tz: &Tz,
+    range: Option<(i64, i64)>,
+) -> Result<Vec<TimelineBucket>, String> {
+    let (from, to) = match range {
+        Some(range) => range,
+        // Out-of-range timestamps can't be bucketed; leave them out of the span
+        None => {
+            let all = created.iter().chain(completed).filter(|&&ts| is_representable(ts));
+            match (all.clone().min(), all.max()) {
+                (Some(&min), Some(&max)) => (min, max),
+                _ => return Ok(Vec::new()),
+            }
+        }
+    };
+    if from > to {
+        return Err("Timeline range starts after it ends".to_string());
+    }
+
+    let mut buckets = Vec::new();
+    let mut start = bucket_floor(from, granularity, tz)
+        .ok_or_else(|| format!("Timeline range start is out of range: {}", from))?;
+    while start.timestamp() <= to {
+        if buckets.len() == MAX_TIMELINE_BUCKETS {
+            return Err(format!("Range needs more than {} buckets; use a coarser granularity", MAX_TIMELINE_BUCKETS));
+        }
+        let Some(end) = bucket_next(&start, granularity, tz) else {
+            break;
+        };
+        buckets.push(TimelineBucket {
+            start: start.timestamp(),
+            end: end.timestamp(),
+            label: start.format("%Y-%m-%dT%H:%M%:z").to_string(),
+            created: 0,
+            completed: 0,
+        });
+        start = end;
+    }
+
+    let find = |buckets: &[TimelineBucket], ts: i64| -> Option<usize> {
+        let index = buckets.partition_point(|b| b.end <= ts);
+        (index < buckets.len() && buckets[index].start <= ts).then_some(index)
+    };
+    for &ts in created {
+        if let Some(i) = find(&buckets, ts) {
+            buckets[i].created += 1;
+        }
+    }
+    for &ts in completed {
+        if let Some(i) = find(&buckets, ts) {
+            buckets[i].completed += 1;
+        }
+    }
+    Ok(buckets)
 }
 
 // ============================================
@@ -364,11 +610,11 @@ impl ProgressDashboard {
         }
         task.updated_at = chrono::Utc::now().timestamp();
 
-        let project_id = task.project_id.clone();
-        self.add_timeline_entry(&project_id, Some(task_id), TimelineEventType::TaskUpdated,
+        let task = task.clone();
+        self.add_timeline_entry(&task.project_id, Some(task_id), TimelineEventType::TaskUpdated,
             format!("Task '{}' updated", task.title));
 
-        Ok(task.clone())
+        Ok(task)
     }
 
     pub fn move_task(&mut self, task_id: &str, column_id: &str, position: i32) -> Result<Task, String> {
@@ -381,24 +627,24 @@ impl ProgressDashboard {
         task.updated_at = chrono::Utc::now().timestamp();
 
         // Mark as completed if moved to done column
-        if column_id == "done" && task.completed_at.is_none() {
+        let completed_now = column_id == "done" && task.completed_at.is_none();
+        if completed_now {
             task.completed_at = Some(chrono::Utc::now().timestamp());
-            let project_id = task.project_id.clone();
-            self.add_timeline_entry(&project_id, Some(task_id), TimelineEventType::TaskCompleted,
-                format!("Task '{}' completed", task.title));
         } else if column_id != "done" {
             task.completed_at = None;
         }
 
-        let project_id = task.project_id.clone();
-        let title = task.title.clone();
-        
+        let task = task.clone();
+        if completed_now {
+            self.add_timeline_entry(&task.project_id, Some(task_id), TimelineEventType::TaskCompleted,
+                format!("Task '{}' completed", task.title));
+        }
         if old_column != column_id {
-            self.add_timeline_entry(&project_id, Some(task_id), TimelineEventType::TaskMoved,
-                format!("Task '{}' moved from {} to {}", title, old_column, column_id));
+            self.add_timeline_entry(&task.project_id, Some(task_id), TimelineEventType::TaskMoved,
+                format!("Task '{}' moved from {} to {}", task.title, old_column, column_id));
         }
 
-        Ok(task.clone())
+        Ok(task)
     }
 
     pub fn delete_task(&mut self, task_id: &str) -> Result<(), String> {
@@ -477,6 +723,22 @@ impl ProgressDashboard {
         entries.into_iter().take(limit).collect()
     }
 
+    /// Task creations and completions for a project, bucketed in `tz`
+    pub fn get_project_activity(
+        &self,
+        project_id: &str,
+        granularity: TimelineGranularity,
+        tz: &Tz,
+        range: Option<(i64, i64)>,
+    ) -> Result<Vec<TimelineBucket>, String> {
+        let tasks: Vec<_> = self.tasks.values()
+            .filter(|t| t.project_id == project_id)
+            .collect();
+        let created: Vec<i64> = tasks.iter().map(|t| t.created_at).collect();
+        let completed: Vec<i64> = tasks.iter().filter_map(|t| t.completed_at).collect();
+        bucket_activity(&created, &completed, granularity, tz, range)
+    }
+
     // ============================================
     // Metrics Operations
     // ============================================
@@ -530,11 +792,11 @@ impl ProgressDashboard {
             trend: 0.0,
         };
 
-        // Burndown (simplified)
-        let burndown = vec![
-            BurndownPoint { date: now - 7 * 86400, remaining: total_tasks, completed: 0, ideal: total_tasks as f64 },
-            BurndownPoint { date: now, remaining: total_tasks - completed_tasks, completed: completed_tasks, ideal: 0.0 },
-        ];
+        // Burndown over the last week
+        let history: Vec<TaskHistory> = tasks.iter()
+            .map(|t| TaskHistory { created_at: t.created_at, completed_at: t.completed_at, removed_at: None })
+            .collect();
+        let burndown = compute_burndown(&history, now - 7 * 86400, now, now);
 
         ProjectMetrics {
             total_tasks,
@@ -575,3 +837,144 @@ pub struct TaskUpdate {
     pub estimated_hours: Option<Option<f64>>,
     pub actual_hours: Option<Option<f64>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(tz: &Tz, y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+        tz.with_ymd_and_hms(y, m, d, h, min, 0).earliest().unwrap().timestamp()
+    }
+
+    fn task(created_at: i64, completed_at: Option<i64>, removed_at: Option<i64>) -> TaskHistory {
+        TaskHistory { created_at, completed_at, removed_at }
+    }
+
+    #[test]
+    fn test_burndown_tracks_added_scope_separately() {
+        let day = 86400;
+        let history = [
+            task(0, Some(2 * day), None),
+            task(0, Some(4 * day), None),
+            task(0, None, None),
+            task(0, None, None),
+            // Added mid-sprint, one finished before the end
+            task(3 * day, Some(5 * day), None),
+            task(3 * day, None, None),
+        ];
+        let points = compute_burndown(&history, 0, 10 * day, 10 * day);
+
+        let series: Vec<_> = points.iter().map(|p| (p.date / day, p.scope, p.completed, p.remaining)).collect();
+        assert_eq!(series, vec![
+            (0, 4, 0, 4),
+            (2, 4, 1, 3),
+            (3, 6, 1, 5),
+            (4, 6, 2, 4),
+            (5, 6, 3, 3),
+            (10, 6, 3, 3),
+        ]);
+        // The ideal line only reflects the original four tasks
+        assert_eq!(points[0].ideal, 4.0);
+        assert_eq!(points[3].ideal, 4.0 * 0.6);
+        assert_eq!(points.last().unwrap().ideal, 0.0);
+    }
+
+    #[test]
+    fn test_burndown_range_and_removed_scope() {
+        let day = 86400;
+        let history = [
+            task(0, Some(day), None),
+            task(0, None, Some(2 * day)),
+            // Finished, then cancelled: leaves both scope and completed
+            task(0, Some(day), Some(3 * day)),
+            task(0, None, None),
+            // Outside the range
+            task(20 * day, None, None),
+        ];
+        let points = compute_burndown(&history, day, 5 * day, 5 * day);
+
+        let series: Vec<_> = points.iter().map(|p| (p.date / day, p.scope, p.completed, p.remaining)).collect();
+        assert_eq!(series, vec![(1, 4, 2, 2), (2, 3, 2, 1), (3, 2, 1, 1), (5, 2, 1, 1)]);
+        assert_eq!(points[0].ideal, 4.0);
+
+        assert_eq!(compute_burndown(&[], 0, day, day).len(), 2);
+    }
+
+    #[test]
+    fn test_day_buckets_follow_local_midnight() {
+        let tokyo = parse_timezone("Asia/Tokyo").unwrap();
+        // 23:30 and 00:30 Tokyo are the same UTC day but different local days
+        let created = [ts(&tokyo, 2024, 5, 1, 23, 30), ts(&tokyo, 2024, 5, 2, 0, 30)];
+        let buckets = bucket_activity(&created, &[], TimelineGranularity::Day, &tokyo, None).unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].label, "2024-05-01T00:00+09:00");
+        assert_eq!(buckets.iter().map(|b| b.created).collect::<Vec<_>>(), vec![1, 1]);
+
+        let utc = bucket_activity(&created, &[], TimelineGranularity::Day, &chrono_tz::UTC, None).unwrap();
+        assert_eq!(utc.len(), 1);
+        assert_eq!(utc[0].created, 2);
+    }
+
+    #[test]
+    fn test_buckets_across_dst_transitions() {
+        let ny = parse_timezone("America/New_York").unwrap();
+
+        // Spring forward on 2024-03-10: that day is 23 hours long
+        let range = (ts(&ny, 2024, 3, 9, 12, 0), ts(&ny, 2024, 3, 11, 12, 0));
+        let completed = [ts(&ny, 2024, 3, 10, 23, 59)];
+        let days = bucket_activity(&[], &completed, TimelineGranularity::Day, &ny, Some(range)).unwrap();
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[1].end - days[1].start, 23 * 3600);
+        assert_eq!(days[1].label, "2024-03-10T00:00-05:00");
+        assert_eq!(days[2].label, "2024-03-11T00:00-04:00");
+        assert_eq!(days[1].completed, 1);
+
+        // Fall back on 2024-11-03: 01:00-02:00 happens twice and gets two hourly buckets
+        let first = ny.with_ymd_and_hms(2024, 11, 3, 1, 15, 0).earliest().unwrap().timestamp();
+        let second = ny.with_ymd_and_hms(2024, 11, 3, 1, 15, 0).latest().unwrap().timestamp();
+        let hours = bucket_activity(&[first, second], &[], TimelineGranularity::Hour, &ny, None).unwrap();
+        assert_eq!(hours.len(), 2);
+        assert_eq!(hours[0].label, "2024-11-03T01:00-04:00");
+        assert_eq!(hours[1].label, "2024-11-03T01:00-05:00");
+        assert!(hours.iter().all(|h| h.created == 1));
+
+        let days = bucket_activity(&[first], &[], TimelineGranularity::Day, &ny, None).unwrap();
+        assert_eq!(days[0].end - days[0].start, 25 * 3600);
+    }
+
+    #[test]
+    fn test_gaps_are_filled_with_empty_buckets() {
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        let created = [ts(&berlin, 2024, 1, 15, 9, 0), ts(&berlin, 2024, 4, 2, 9, 0)];
+        let months = bucket_activity(&created, &[], TimelineGranularity::Month, &berlin, None).unwrap();
+        assert_eq!(months.iter().map(|b| b.created).collect::<Vec<_>>(), vec![1, 0, 0, 1]);
+        // Each bucket ends where the next starts
+        assert!(months.windows(2).all(|w| w[0].end == w[1].start));
+
+        // 2024-01-15 is a Monday
+        let weeks = bucket_activity(&created[..1], &[], TimelineGranularity::Week, &berlin, None).unwrap();
+        assert_eq!(weeks[0].label, "2024-01-15T00:00+01:00");
+
+        let year = (created[0], ts(&berlin, 2025, 1, 15, 9, 0));
+        assert!(bucket_activity(&created, &[], TimelineGranularity::Hour, &berlin, Some(year)).is_err());
+        assert!(parse_timezone("Mars/Olympus").is_err());
+        assert!(bucket_activity(&[], &[], TimelineGranularity::Day, &berlin, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_out_of_range_timestamps_are_skipped() {
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        let created = [i64::MIN, ts(&berlin, 2024, 1, 15, 9, 0), i64::MAX];
+        for granularity in [TimelineGranularity::Hour, TimelineGranularity::Day, TimelineGranularity::Week, TimelineGranularity::Month] {
+            let buckets = bucket_activity(&created, &[i64::MAX], granularity, &berlin, None).unwrap();
+            assert_eq!(buckets.len(), 1);
+            assert_eq!(buckets[0].created, 1);
+            assert_eq!(buckets[0].completed, 0);
+        }
+
+        // Only unrepresentable events leave nothing to bucket
+        assert!(bucket_activity(&[i64::MAX], &[], TimelineGranularity::Day, &berlin, None).unwrap().is_empty());
+        // An explicit range has to start somewhere representable
+        assert!(bucket_activity(&[], &[], TimelineGranularity::Day, &berlin, Some((i64::MIN, 0))).is_err());
+    }
+}
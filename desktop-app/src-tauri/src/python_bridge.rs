@@ -1,23 +1,46 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::{Arc, Mutex};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::mpsc;
 
+use crate::workflow_artifacts::{is_spec, ArtifactStore, StoredArtifact};
+
+/// Version of the JSON messages exchanged with `bridge.py`. Bump together
+/// with `SCHEMA_VERSION` in the script whenever a message shape changes.
+pub const BRIDGE_SCHEMA_VERSION: u64 = 1;
+
+/// Code of the `Error` message reported for each non-JSON stderr line
+pub const STDERR_ERROR_CODE: &str = "BRIDGE_STDERR";
+
+/// Code of the `Error` message reported when a declared artifact can't be collected
+pub const ARTIFACT_ERROR_CODE: &str = "ARTIFACT_COLLECTION_FAILED";
+
+/// Raw stderr lines kept to explain a process that exits without a result
+const STDERR_TAIL_LINES: usize = 20;
+
+/// Shortest interval at which the watchdog checks a workflow's timeouts
+const WATCHDOG_MIN_TICK: Duration = Duration::from_millis(50);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowArgs {
     pub spec_id: String,
     pub category: String,
     pub mode: String,
     pub platform: String,
+    /// Run the workflow in this workspace and keep its artifacts there
+    #[serde(default)]
+    pub workspace_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum OutputMessage {
     Started {
         workflow_id: String,
@@ -26,7 +49,7 @@ pub enum OutputMessage {
     },
     Log {
         workflow_id: String,
-        level: String,
+        level: LogLevel,
         message: String,
         timestamp: String,
     },
@@ -48,9 +71,17 @@ pub enum OutputMessage {
         message: String,
         timestamp: String,
     },
+    /// A file the workflow produced, collected as soon as it is declared
+    Artifact {
+        workflow_id: String,
+        path: String,
+        #[serde(default)]
+        kind: Option<String>,
+        timestamp: String,
+    },
     Completed {
         workflow_id: String,
-        result: serde_json::Value,
+        result: WorkflowResult,
         timestamp: String,
     },
     Failed {
@@ -58,23 +89,416 @@ pub enum OutputMessage {
         error: String,
         timestamp: String,
     },
-    WorkflowsList {
-        workflows: Vec<serde_json::Value>,
-        count: usize,
-        timestamp: String,
-    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    /// A level this app doesn't know yet
+    #[serde(other)]
+    Other,
+}
+
+/// What a completed workflow reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowResult {
+    #[serde(default)]
+    pub success: bool,
+    #[serde(default)]
+    pub spec_id: Option<String>,
+    /// Autopilot output; its shape depends on the workflow
+    #[serde(default)]
+    pub output: serde_json::Value,
+    #[serde(default)]
+    pub artifacts: Vec<WorkflowArtifact>,
+    /// True when the CLI was missing and the run was simulated
+    #[serde(default)]
+    pub simulated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowArtifact {
+    pub path: String,
+    #[serde(default)]
+    pub kind: Option<String>,
+}
+
+/// One message from a running workflow, with the line it was parsed from
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowOutput {
+    pub schema_version: u64,
+    #[serde(flatten)]
+    pub message: OutputMessage,
+    /// The message exactly as the bridge sent it, including fields this
+    /// app doesn't model yet
+    pub raw: serde_json::Value,
+}
+
+impl WorkflowOutput {
+    /// Wrap a message produced by the app rather than the bridge
+    fn synthesized(message: OutputMessage) -> Self {
+        Self {
+            schema_version: BRIDGE_SCHEMA_VERSION,
+            raw: serde_json::to_value(&message).unwrap_or_default(),
+            message,
+        }
+    }
+}
+
+/// A workflow the bridge can run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowSummary {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    /// The entry exactly as the bridge listed it
+    #[serde(skip_deserializing)]
+    pub raw: serde_json::Value,
+}
+
+/// Fail with a clear message unless `value` uses this app's schema version.
+/// Bridges from before versioning send none and count as version 0.
+fn check_schema_version(value: &serde_json::Value) -> Result<()> {
+    let version = value.get("schema_version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version != BRIDGE_SCHEMA_VERSION {
+        anyhow::bail!(
+            "Python bridge speaks message schema v{} but this app expects v{}; \
+             the bundled bridge.py does not match this build",
+            version,
+            BRIDGE_SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkflowStatus {
+    Running,
+    Completed,
+    Failed,
+    Stopped,
+    /// Ran longer than the workflow timeout and was killed
+    TimedOut,
+    /// Stopped sending heartbeats and was killed
+    Unresponsive,
+    #[serde(rename = "Not found")]
+    NotFound,
+}
+
+/// Limits applied to every spawned workflow
+#[derive(Debug, Clone, Copy)]
+pub struct BridgeTimeouts {
+    /// Overall wall-clock limit for a workflow
+    pub workflow: Duration,
+    /// How often the bridge script is asked to send a heartbeat
+    pub heartbeat_interval: Duration,
+    /// Silence after which the process is considered hung
+    pub heartbeat_timeout: Duration,
+}
+
+impl Default for BridgeTimeouts {
+    fn default() -> Self {
+        Self {
+            workflow: Duration::from_secs(30 * 60),
+            heartbeat_interval: Duration::from_secs(5),
+            heartbeat_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// What the output readers have seen from a workflow process
+struct Liveness {
+    last_seen: Instant,
+    /// Set once the process reports `completed` or `failed`, or its output
+    /// closes without doing so
+    outcome: Option<WorkflowStatus>,
+    stderr_tail: VecDeque<String>,
 }
 
 pub struct ProcessHandle {
     pub workflow_id: String,
     pub child: Option<Child>,
-    pub output_rx: mpsc::Receiver<OutputMessage>,
+    pub output_rx: mpsc::Receiver<WorkflowOutput>,
+    started_at: Instant,
+    liveness: Arc<Mutex<Liveness>>,
+    /// Set when the process was killed by us
+    final_status: Option<WorkflowStatus>,
+    /// Reports why we killed the process; dropped on reaping so the output
+    /// channel closes once the readers finish
+    tx: Option<mpsc::Sender<WorkflowOutput>>,
+}
+
+impl ProcessHandle {
+    /// Kill the process, recording why. Tokio reaps the dropped child.
+    /// `error` is sent to the output as the workflow's `Failed` message.
+    fn reap(&mut self, status: WorkflowStatus, error: Option<String>) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.start_kill();
+        }
+        self.final_status = Some(status);
+        // The process is settled; its closing streams are not a crash
+        self.liveness.lock().unwrap().outcome.get_or_insert(status);
+
+        let tx = self.tx.take();
+        if let (Some(tx), Some(error)) = (tx, error) {
+            let failed = WorkflowOutput::synthesized(OutputMessage::Failed {
+                workflow_id: self.workflow_id.clone(),
+                error,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            });
+            if let Err(mpsc::error::TrySendError::Full(failed)) = tx.try_send(failed) {
+                tokio::spawn(async move {
+                    let _ = tx.send(failed).await;
+                });
+            }
+        }
+    }
+
+    /// Whether the workflow has finished and all of its output was read
+    fn is_drained(&self) -> bool {
+        self.final_status.is_some() && self.output_rx.is_closed() && self.output_rx.is_empty()
+    }
+
+    /// Current status, killing the process if it has timed out or gone silent
+    fn check(&mut self, timeouts: &BridgeTimeouts) -> WorkflowStatus {
+        if let Some(status) = self.final_status {
+            return status;
+        }
+        let (outcome, last_seen) = {
+            let liveness = self.liveness.lock().unwrap();
+            (liveness.outcome, liveness.last_seen)
+        };
+        if let Some(outcome) = outcome {
+            self.reap(outcome, None);
+            return outcome;
+        }
+
+        if self.started_at.elapsed() >= timeouts.workflow {
+            self.reap(
+                WorkflowStatus::TimedOut,
+                Some(format!("Workflow timed out after {}s", timeouts.workflow.as_secs())),
+            );
+        } else if last_seen.elapsed() >= timeouts.heartbeat_timeout {
+            self.reap(
+                WorkflowStatus::Unresponsive,
+                Some(format!("Python bridge sent no heartbeat for {}s", timeouts.heartbeat_timeout.as_secs())),
+            );
+        }
+        self.final_status.unwrap_or(WorkflowStatus::Running)
+    }
+}
+
+/// A line of bridge output, checked against the workflow it should belong to
+enum BridgeLine {
+    Heartbeat,
+    Message(WorkflowOutput),
+    /// JSON for another workflow or request, e.g. from a previous run
+    Foreign,
+    /// JSON from a bridge using another schema version
+    Mismatch(String),
+    Raw(String),
+}
+
+fn parse_bridge_line(line: &str, workflow_id: &str, request_id: &str) -> BridgeLine {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+        return BridgeLine::Raw(line.to_string());
+    };
+    let field = |name: &str| value.get(name).and_then(|v| v.as_str()).map(str::to_string);
+
+    // Messages without ids are accepted for compatibility with older scripts
+    if field("request_id").is_some_and(|id| id != request_id)
+        || field("workflow_id").is_some_and(|id| id != workflow_id)
+    {
+        return BridgeLine::Foreign;
+    }
+    if let Err(e) = check_schema_version(&value) {
+        return BridgeLine::Mismatch(e.to_string());
+    }
+    if field("type").as_deref() == Some("heartbeat") {
+        return BridgeLine::Heartbeat;
+    }
+    match serde_json::from_value::<OutputMessage>(value.clone()) {
+        Ok(message) => BridgeLine::Message(WorkflowOutput {
+            schema_version: BRIDGE_SCHEMA_VERSION,
+            message,
+            raw: value,
+        }),
+        Err(_) => BridgeLine::Raw(line.to_string()),
+    }
+}
+
+/// Collects the artifacts a workflow declares, validating specs on the way
+struct ArtifactCollector {
+    store: Arc<ArtifactStore>,
+    workflow_id: String,
+    working_dir: PathBuf,
+    python_path: PathBuf,
+    bridge_script: PathBuf,
+}
+
+impl ArtifactCollector {
+    /// Collect the artifacts declared by `message`, returning an error
+    /// message for each one that couldn't be collected
+    async fn collect(&self, message: &OutputMessage) -> Vec<OutputMessage> {
+        let declared = match message {
+            OutputMessage::Artifact { path, kind, .. } => vec![WorkflowArtifact {
+                path: path.clone(),
+                kind: kind.clone(),
+            }],
+            OutputMessage::Completed { result, .. } => result.artifacts.clone(),
+            _ => return Vec::new(),
+        };
+
+        let mut errors = Vec::new();
+        for artifact in declared {
+            let result = match self.store.collect(&self.workflow_id, &self.working_dir, &artifact) {
+                Ok(stored) => self.validate(&stored).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                errors.push(OutputMessage::Error {
+                    workflow_id: self.workflow_id.clone(),
+                    code: ARTIFACT_ERROR_CODE.to_string(),
+                    message: format!("{}: {:#}", artifact.path, e),
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                });
+            }
+        }
+        errors
+    }
+
+    /// Record `validate-spec` output on spec artifacts so produced specs
+    /// show up, checked, in `get_workflow_artifacts`
+    async fn validate(&self, artifact: &StoredArtifact) -> Result<()> {
+        if !is_spec(artifact) || artifact.validation.is_some() {
+            return Ok(());
+        }
+        let validation = run_validate_spec(&self.python_path, &self.bridge_script, &artifact.path)
+            .await
+            .unwrap_or_else(|e| serde_json::json!({ "valid": false, "error": e.to_string() }));
+        self.store.set_validation(&self.workflow_id, &artifact.name, validation)
+    }
+}
+
+/// Forward one output stream of a workflow process. Every line counts as a
+/// sign of life; non-JSON stderr becomes a structured `Error` message.
+async fn read_bridge_output(
+    stream: impl AsyncRead + Unpin,
+    is_stderr: bool,
+    workflow_id: String,
+    request_id: String,
+    liveness: Arc<Mutex<Liveness>>,
+    collector: Arc<ArtifactCollector>,
+    tx: mpsc::Sender<WorkflowOutput>,
+) {
+    let mut lines = BufReader::new(stream).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        liveness.lock().unwrap().last_seen = Instant::now();
+
+        let output = match parse_bridge_line(&line, &workflow_id, &request_id) {
+            BridgeLine::Heartbeat => continue,
+            BridgeLine::Foreign => {
+                eprintln!("Ignoring bridge output for another request: {}", line);
+                continue;
+            }
+            // Nothing else from this bridge can be trusted; fail the workflow
+            // so the process is killed on the next status check
+            BridgeLine::Mismatch(error) => {
+                liveness.lock().unwrap().outcome = Some(WorkflowStatus::Failed);
+                let _ = tx.send(WorkflowOutput::synthesized(OutputMessage::Failed {
+                    workflow_id: workflow_id.clone(),
+                    error,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                })).await;
+                break;
+            }
+            BridgeLine::Raw(raw) if is_stderr => {
+                let mut liveness = liveness.lock().unwrap();
+                if liveness.stderr_tail.len() == STDERR_TAIL_LINES {
+                    liveness.stderr_tail.pop_front();
+                }
+                liveness.stderr_tail.push_back(raw.clone());
+                drop(liveness);
+
+                WorkflowOutput::synthesized(OutputMessage::Error {
+                    workflow_id: workflow_id.clone(),
+                    code: STDERR_ERROR_CODE.to_string(),
+                    message: raw,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                })
+            }
+            BridgeLine::Raw(_) => continue,
+            BridgeLine::Message(output) => output,
+        };
+
+        let outcome = match &output.message {
+            OutputMessage::Completed { .. } => Some(WorkflowStatus::Completed),
+            OutputMessage::Failed { .. } => Some(WorkflowStatus::Failed),
+            _ => None,
+        };
+
+        // Collect before forwarding, so artifacts are in place by the time
+        // the frontend sees the workflow complete
+        for error in collector.collect(&output.message).await {
+            let _ = tx.send(WorkflowOutput::synthesized(error)).await;
+        }
+        if outcome.is_some() {
+            liveness.lock().unwrap().outcome = outcome;
+        }
+        let _ = tx.send(output).await;
+    }
+}
+
+/// Kill a workflow once it runs past its timeouts, even if nobody polls it
+async fn watch_workflow(
+    processes: Arc<Mutex<HashMap<String, ProcessHandle>>>,
+    workflow_id: String,
+    timeouts: BridgeTimeouts,
+) {
+    let tick = (timeouts.heartbeat_timeout.min(timeouts.workflow) / 4).max(WATCHDOG_MIN_TICK);
+    loop {
+        tokio::time::sleep(tick).await;
+        let mut processes = processes.lock().unwrap();
+        let status = processes.get_mut(&workflow_id).map(|handle| handle.check(&timeouts));
+        if status != Some(WorkflowStatus::Running) {
+            return;
+        }
+    }
+}
+
+/// Parse `list-workflows` output into summaries, keeping each raw entry
+fn parse_workflow_list(stdout: &str) -> Result<Vec<WorkflowSummary>> {
+    let result: serde_json::Value =
+        serde_json::from_str(stdout).context("Failed to parse workflows list")?;
+    check_schema_version(&result)?;
+
+    let Some(workflows) = result.get("workflows").and_then(|w| w.as_array()) else {
+        return Ok(vec![]);
+    };
+    workflows
+        .iter()
+        .map(|entry| {
+            let mut summary: WorkflowSummary = serde_json::from_value(entry.clone())
+                .with_context(|| format!("Unexpected workflow entry: {}", entry))?;
+            summary.raw = entry.clone();
+            Ok(summary)
+        })
+        .collect()
 }
 
 pub struct PythonBridge {
     python_path: PathBuf,
     bridge_script: PathBuf,
     processes: Arc<Mutex<HashMap<String, ProcessHandle>>>,
+    timeouts: BridgeTimeouts,
+    artifacts: Arc<ArtifactStore>,
 }
 
 impl PythonBridge {
@@ -124,6 +548,8 @@ impl PythonBridge {
             python_path,
             bridge_script,
             processes: Arc::new(Mutex::new(HashMap::new())),
+            timeouts: BridgeTimeouts::default(),
+            artifacts: Arc::new(ArtifactStore::new(std::env::temp_dir().join("smartspecpro-artifacts"))),
         })
     }
 
@@ -142,21 +568,41 @@ impl PythonBridge {
             python_path,
             bridge_script,
             processes: Arc::new(Mutex::new(HashMap::new())),
+            timeouts: BridgeTimeouts::default(),
+            artifacts: Arc::new(ArtifactStore::new(std::env::temp_dir().join("smartspecpro-artifacts"))),
         })
     }
 
+    pub fn with_timeouts(mut self, timeouts: BridgeTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Keep artifacts of workflows run outside a workspace under `root`
+    pub fn with_artifacts_root(mut self, root: PathBuf) -> Self {
+        self.artifacts = Arc::new(ArtifactStore::new(root));
+        self
+    }
+
+    /// Start a workflow and return the correlation id its output must carry
     pub async fn spawn_workflow(
         &self,
         workflow_id: String,
         workflow_name: String,
         args: WorkflowArgs,
-    ) -> Result<()> {
+    ) -> Result<String> {
+        let request_id = uuid::Uuid::new_v4().to_string();
+
         // Build command
         let mut cmd = Command::new(&self.python_path);
         cmd.arg(&self.bridge_script)
             .arg("run-workflow")
             .arg("--workflow-id")
             .arg(&workflow_id)
+            .arg("--request-id")
+            .arg(&request_id)
+            .arg("--heartbeat-interval")
+            .arg(self.timeouts.heartbeat_interval.as_secs_f64().to_string())
             .arg("--workflow-name")
             .arg(&workflow_name)
             .arg("--spec-id")
@@ -170,41 +616,68 @@ impl PythonBridge {
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
+        let workspace_path = args.workspace_path.as_deref().map(Path::new);
+        let working_dir = match workspace_path {
+            Some(workspace) => {
+                cmd.current_dir(workspace);
+                workspace.to_path_buf()
+            }
+            None => std::env::current_dir().context("Failed to get current directory")?,
+        };
+        self.artifacts.register(&workflow_id, workspace_path)?;
+        let collector = Arc::new(ArtifactCollector {
+            store: self.artifacts.clone(),
+            workflow_id: workflow_id.clone(),
+            working_dir,
+            python_path: self.python_path.clone(),
+            bridge_script: self.bridge_script.clone(),
+        });
+
         // Spawn process
         let mut child = cmd.spawn().context("Failed to spawn Python process")?;
 
         // Create channel for output
         let (tx, rx) = mpsc::channel(100);
+        let liveness = Arc::new(Mutex::new(Liveness {
+            last_seen: Instant::now(),
+            outcome: None,
+            stderr_tail: VecDeque::new(),
+        }));
 
-        // Spawn task to read stdout
-        if let Some(stdout) = child.stdout.take() {
-            let tx_clone = tx.clone();
-            tokio::spawn(async move {
-                let reader = BufReader::new(stdout);
-                let mut lines = reader.lines();
+        let stdout = child.stdout.take().map(|stdout| tokio::spawn(read_bridge_output(
+            stdout, false, workflow_id.clone(), request_id.clone(), liveness.clone(), collector.clone(), tx.clone(),
+        )));
+        let stderr = child.stderr.take().map(|stderr| tokio::spawn(read_bridge_output(
+            stderr, true, workflow_id.clone(), request_id.clone(), liveness.clone(), collector, tx.clone(),
+        )));
 
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if let Ok(msg) = serde_json::from_str::<OutputMessage>(&line) {
-                        let _ = tx_clone.send(msg).await;
-                    }
-                }
-            });
-        }
-
-        // Spawn task to read stderr
-        if let Some(stderr) = child.stderr.take() {
+        // Once both streams close, a process that never reported a result has
+        // died; report it as failed with whatever it left on stderr
+        {
+            let workflow_id = workflow_id.clone();
+            let liveness = liveness.clone();
+            let tx = tx.clone();
             tokio::spawn(async move {
-                let reader = BufReader::new(stderr);
-                let mut lines = reader.lines();
-
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if let Ok(msg) = serde_json::from_str::<OutputMessage>(&line) {
-                        let _ = tx.send(msg).await;
+                for reader in [stdout, stderr].into_iter().flatten() {
+                    let _ = reader.await;
+                }
+                let error = {
+                    let mut liveness = liveness.lock().unwrap();
+                    if liveness.outcome.is_some() {
+                        return;
+                    }
+                    liveness.outcome = Some(WorkflowStatus::Failed);
+                    if liveness.stderr_tail.is_empty() {
+                        "Python bridge exited without reporting a result".to_string()
                     } else {
-                        // Log raw stderr
-                        eprintln!("stderr: {}", line);
+                        Vec::from(liveness.stderr_tail.clone()).join("\n")
                     }
-                }
+                };
+                let _ = tx.send(WorkflowOutput::synthesized(OutputMessage::Failed {
+                    workflow_id,
+                    error,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                })).await;
             });
         }
 
@@ -213,14 +686,19 @@ impl PythonBridge {
             workflow_id: workflow_id.clone(),
             child: Some(child),
             output_rx: rx,
+            started_at: Instant::now(),
+            liveness,
+            final_status: None,
+            tx: Some(tx),
         };
 
-        self.processes.lock().unwrap().insert(workflow_id, handle);
+        self.processes.lock().unwrap().insert(workflow_id.clone(), handle);
+        tokio::spawn(watch_workflow(self.processes.clone(), workflow_id, self.timeouts));
 
-        Ok(())
+        Ok(request_id)
     }
 
-    pub fn get_output(&self, workflow_id: &str) -> Result<Option<OutputMessage>> {
+    pub fn get_output(&self, workflow_id: &str) -> Result<Option<WorkflowOutput>> {
         let mut processes = self.processes.lock().unwrap();
 
         if let Some(handle) = processes.get_mut(workflow_id) {
@@ -240,30 +718,41 @@ impl PythonBridge {
         let mut processes = self.processes.lock().unwrap();
 
         if let Some(mut handle) = processes.remove(workflow_id) {
-            if let Some(mut child) = handle.child.take() {
-                let _ = child.start_kill();
-            }
+            handle.reap(WorkflowStatus::Stopped, None);
             Ok(())
         } else {
             anyhow::bail!("Workflow not found: {}", workflow_id)
         }
     }
 
-    pub fn get_status(&self, workflow_id: &str) -> Result<String> {
-        let processes = self.processes.lock().unwrap();
+    /// IDs of workflows whose process is still running
+    pub fn running_workflows(&self) -> Vec<String> {
+        let mut processes = self.processes.lock().unwrap();
+        processes.values_mut()
+            .filter_map(|handle| {
+                (handle.check(&self.timeouts) == WorkflowStatus::Running).then(|| handle.workflow_id.clone())
+            })
+            .collect()
+    }
 
-        if let Some(handle) = processes.get(workflow_id) {
-            if handle.child.is_some() {
-                Ok("Running".to_string())
-            } else {
-                Ok("Stopped".to_string())
-            }
-        } else {
-            Ok("Not found".to_string())
+    /// Status of a workflow. A process past the workflow timeout, or silent
+    /// for longer than the heartbeat timeout, is killed here. Once a finished
+    /// workflow's output has been read, this is the last status reported for
+    /// it; later calls return `NotFound`.
+    pub fn get_status(&self, workflow_id: &str) -> Result<WorkflowStatus> {
+        let mut processes = self.processes.lock().unwrap();
+
+        let Some(handle) = processes.get_mut(workflow_id) else {
+            return Ok(WorkflowStatus::NotFound);
+        };
+        let status = handle.check(&self.timeouts);
+        if handle.is_drained() {
+            processes.remove(workflow_id);
         }
+        Ok(status)
     }
 
-    pub async fn list_workflows(&self) -> Result<Vec<serde_json::Value>> {
+    pub async fn list_workflows(&self) -> Result<Vec<WorkflowSummary>> {
         // Build command
         let mut cmd = Command::new(&self.python_path);
         cmd.arg(&self.bridge_script)
@@ -282,44 +771,316 @@ impl PythonBridge {
             anyhow::bail!("list-workflows failed: {}", stderr);
         }
 
-        // Parse output
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let result: serde_json::Value =
-            serde_json::from_str(&stdout).context("Failed to parse workflows list")?;
+        parse_workflow_list(&String::from_utf8_lossy(&output.stdout))
+    }
 
-        if let Some(workflows) = result.get("workflows").and_then(|w| w.as_array()) {
-            Ok(workflows.clone())
-        } else {
-            Ok(vec![])
+    pub async fn validate_spec(&self, spec_path: String) -> Result<serde_json::Value> {
+        run_validate_spec(&self.python_path, &self.bridge_script, &spec_path).await
+    }
+
+    /// Artifacts collected from a workflow, with sizes and content types
+    pub fn get_artifacts(&self, workflow_id: &str) -> Result<Vec<StoredArtifact>> {
+        self.artifacts.list(workflow_id)
+    }
+
+    /// Apply the execution retention policy to collected artifacts
+    pub fn delete_artifacts_older_than(&self, days: i64) -> Result<usize> {
+        self.artifacts.delete_older_than(days)
+    }
+}
+
+async fn run_validate_spec(python_path: &Path, bridge_script: &Path, spec_path: &str) -> Result<serde_json::Value> {
+    // Build command
+    let mut cmd = Command::new(python_path);
+    cmd.arg(bridge_script)
+        .arg("validate-spec")
+        .arg("--spec-path")
+        .arg(spec_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // Spawn process
+    let output = cmd
+        .output()
+        .await
+        .context("Failed to execute validate-spec")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("validate-spec failed: {}", stderr);
+    }
+
+    // Parse output
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let result: serde_json::Value =
+        serde_json::from_str(&stdout).context("Failed to parse validation result")?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A bridge whose `run-workflow` runs `body` with `request_id` and
+    /// `workflow_id` taken from the command line
+    fn mock_bridge(dir: &std::path::Path, body: &str, timeouts: BridgeTimeouts) -> PythonBridge {
+        let script = dir.join("bridge.py");
+        let prelude = "import json, sys, time\n\
+            argv = sys.argv\n\
+            if argv[1] == 'validate-spec':\n    \
+                print(json.dumps(dict(type='validation_result', valid=True, \
+                spec_path=argv[argv.index('--spec-path') + 1])))\n    \
+                sys.exit(0)\n\
+            request_id = argv[argv.index('--request-id') + 1]\n\
+            workflow_id = argv[argv.index('--workflow-id') + 1]\n\
+            def send(message_type, request_id=request_id, **fields):\n    \
+                print(json.dumps(dict(type=message_type, workflow_id=workflow_id, request_id=request_id, \
+                schema_version=1, timestamp='now', **fields)), flush=True)\n";
+        std::fs::write(&script, format!("{}{}\n", prelude, body)).unwrap();
+        PythonBridge::with_path(script).unwrap().with_timeouts(timeouts)
+    }
+
+    fn args() -> WorkflowArgs {
+        WorkflowArgs {
+            spec_id: "spec".to_string(),
+            category: "core".to_string(),
+            mode: "normal".to_string(),
+            platform: "claude".to_string(),
+            workspace_path: None,
         }
     }
 
-    pub async fn validate_spec(&self, spec_path: String) -> Result<serde_json::Value> {
-        // Build command
-        let mut cmd = Command::new(&self.python_path);
-        cmd.arg(&self.bridge_script)
-            .arg("validate-spec")
-            .arg("--spec-path")
-            .arg(&spec_path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+    async fn drain(bridge: &PythonBridge, workflow_id: &str) -> Vec<OutputMessage> {
+        let mut messages = Vec::new();
+        for _ in 0..100 {
+            match bridge.get_output(workflow_id) {
+                Ok(Some(output)) => messages.push(output.message),
+                Ok(None) => tokio::time::sleep(Duration::from_millis(20)).await,
+                Err(_) => break,
+            }
+        }
+        messages
+    }
 
-        // Spawn process
-        let output = cmd
-            .output()
-            .await
-            .context("Failed to execute validate-spec")?;
+    #[tokio::test]
+    async fn test_silent_bridge_is_reaped_as_unresponsive() {
+        let dir = tempfile::tempdir().unwrap();
+        let bridge = mock_bridge(
+            dir.path(),
+            "send('heartbeat')\ntime.sleep(30)",
+            BridgeTimeouts {
+                heartbeat_timeout: Duration::from_millis(300),
+                ..BridgeTimeouts::default()
+            },
+        );
+        bridge.spawn_workflow("wf".to_string(), "plan".to_string(), args()).await.unwrap();
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("validate-spec failed: {}", stderr);
+        assert_eq!(bridge.get_status("wf").unwrap(), WorkflowStatus::Running);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(bridge.get_status("wf").unwrap(), WorkflowStatus::Unresponsive);
+        assert!(bridge.running_workflows().is_empty());
+        assert_eq!(bridge.get_status("missing").unwrap(), WorkflowStatus::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_workflow_timeout_applies_despite_heartbeats() {
+        let dir = tempfile::tempdir().unwrap();
+        let bridge = mock_bridge(
+            dir.path(),
+            "while True:\n    send('heartbeat')\n    time.sleep(0.05)",
+            BridgeTimeouts {
+                workflow: Duration::from_millis(400),
+                heartbeat_timeout: Duration::from_millis(300),
+                ..BridgeTimeouts::default()
+            },
+        );
+        bridge.spawn_workflow("wf".to_string(), "plan".to_string(), args()).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(bridge.get_status("wf").unwrap(), WorkflowStatus::Running);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(bridge.get_status("wf").unwrap(), WorkflowStatus::TimedOut);
+        assert!(bridge.running_workflows().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_workflow_is_killed_and_reported_without_polling() {
+        let dir = tempfile::tempdir().unwrap();
+        let bridge = mock_bridge(
+            dir.path(),
+            "while True:\n    send('heartbeat')\n    time.sleep(0.05)",
+            BridgeTimeouts {
+                workflow: Duration::from_millis(300),
+                heartbeat_timeout: Duration::from_secs(5),
+                ..BridgeTimeouts::default()
+            },
+        );
+        bridge.spawn_workflow("wf".to_string(), "plan".to_string(), args()).await.unwrap();
+
+        // Nothing asks for the status; the watchdog still enforces the limit
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        let messages = drain(&bridge, "wf").await;
+        assert_eq!(messages.len(), 1, "{:?}", messages);
+        match &messages[0] {
+            OutputMessage::Failed { error, .. } => assert!(error.contains("timed out"), "{}", error),
+            other => panic!("expected Failed, got {:?}", other),
+        }
+
+        // The final status is reported once, then the drained handle is gone
+        assert_eq!(bridge.get_status("wf").unwrap(), WorkflowStatus::TimedOut);
+        assert_eq!(bridge.get_status("wf").unwrap(), WorkflowStatus::NotFound);
+        assert!(bridge.processes.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_crash_surfaces_stderr_as_structured_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let bridge = mock_bridge(
+            dir.path(),
+            "send('started', workflow_name='plan')\n\
+             send('log', request_id='stale-request', level='info', message='from an older run')\n\
+             print('Traceback: boom', file=sys.stderr, flush=True)\n\
+             sys.exit(1)",
+            BridgeTimeouts::default(),
+        );
+        bridge.spawn_workflow("wf".to_string(), "plan".to_string(), args()).await.unwrap();
+
+        let messages = drain(&bridge, "wf").await;
+        assert!(matches!(messages.first(), Some(OutputMessage::Started { .. })), "{:?}", messages);
+        assert!(!messages.iter().any(|m| matches!(m, OutputMessage::Log { .. })));
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            OutputMessage::Error { code, message, .. } if code == STDERR_ERROR_CODE && message == "Traceback: boom"
+        )));
+        match messages.last() {
+            Some(OutputMessage::Failed { error, .. }) => assert_eq!(error, "Traceback: boom"),
+            other => panic!("expected Failed, got {:?}", other),
         }
+        assert_eq!(bridge.get_status("wf").unwrap(), WorkflowStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_completed_workflow_reports_completed() {
+        let dir = tempfile::tempdir().unwrap();
+        let bridge = mock_bridge(dir.path(), "send('completed', result={'ok': True})", BridgeTimeouts::default());
+        bridge.spawn_workflow("wf".to_string(), "plan".to_string(), args()).await.unwrap();
+
+        let messages = drain(&bridge, "wf").await;
+        assert_eq!(messages.len(), 1, "{:?}", messages);
+        assert_eq!(bridge.get_status("wf").unwrap(), WorkflowStatus::Completed);
+    }
+
+    fn parse(line: &str) -> WorkflowOutput {
+        match parse_bridge_line(line, "wf", "req") {
+            BridgeLine::Message(output) => output,
+            _ => panic!("not a workflow message: {}", line),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_bridge_messages() {
+        let progress = parse(r#"{"type": "progress", "workflow_id": "wf", "request_id": "req", "schema_version": 1,
+            "step": "simulate", "progress": 0.25, "message": "Step 2/5", "timestamp": "t"}"#);
+        assert!(matches!(progress.message, OutputMessage::Progress { ref step, progress, .. } if step == "simulate" && progress == 0.25));
+
+        // Extra fields are kept in `raw`; unknown levels don't fail parsing
+        let log = parse(r#"{"type": "log", "workflow_id": "wf", "schema_version": 1, "level": "trace",
+            "message": "hi", "timestamp": "t", "module": "planner"}"#);
+        assert!(matches!(log.message, OutputMessage::Log { level: LogLevel::Other, .. }));
+        assert_eq!(log.raw["module"], "planner");
+
+        let completed = parse(r#"{"type": "completed", "workflow_id": "wf", "request_id": "req", "schema_version": 1,
+            "result": {"success": true, "spec_id": "spec-1", "output": "done",
+                       "artifacts": [{"path": "specs/spec-1/plan.md", "kind": "plan"}]},
+            "timestamp": "t"}"#);
+        match &completed.message {
+            OutputMessage::Completed { result, .. } => {
+                assert!(result.success && !result.simulated);
+                assert_eq!(result.spec_id.as_deref(), Some("spec-1"));
+                assert_eq!(result.artifacts[0].path, "specs/spec-1/plan.md");
+                assert_eq!(result.artifacts[0].kind.as_deref(), Some("plan"));
+            }
+            other => panic!("expected Completed, got {:?}", other),
+        }
+
+        // Serialized for the frontend with the message fields at the top level
+        let json = serde_json::to_value(&completed).unwrap();
+        assert_eq!(json["type"], "completed");
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["result"]["artifacts"][0]["kind"], "plan");
+    }
+
+    #[test]
+    fn test_schema_version_mismatch_is_reported() {
+        for line in [
+            r#"{"type": "started", "workflow_id": "wf", "workflow_name": "plan", "timestamp": "t"}"#,
+            r#"{"type": "started", "workflow_id": "wf", "schema_version": 2, "workflow_name": "plan", "timestamp": "t"}"#,
+        ] {
+            match parse_bridge_line(line, "wf", "req") {
+                BridgeLine::Mismatch(error) => assert!(error.contains("expects v1"), "{}", error),
+                _ => panic!("expected a schema mismatch for {}", line),
+            }
+        }
+
+        let err = parse_workflow_list(r#"{"type": "workflows_list", "workflows": [], "count": 0}"#).unwrap_err();
+        assert!(err.to_string().contains("schema v0"));
+    }
+
+    #[test]
+    fn test_parse_workflow_list() {
+        let workflows = parse_workflow_list(r#"{"type": "workflows_list", "schema_version": 1, "count": 2, "workflows": [
+            {"name": "smartspec_generate_spec", "description": "Generate specification", "category": "core"},
+            {"name": "smartspec_generate_plan", "owner": "team"}]}"#).unwrap();
+
+        assert_eq!(workflows.len(), 2);
+        assert_eq!(workflows[0].category.as_deref(), Some("core"));
+        assert_eq!(workflows[1].description, "");
+        assert_eq!(workflows[1].raw["owner"], "team");
+        assert!(parse_workflow_list(r#"{"schema_version": 1, "workflows": [{"description": "no name"}]}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_workflow_artifacts_are_collected_into_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        let bridge = mock_bridge(
+            dir.path(),
+            "import os\n\
+             os.makedirs('specs/spec-1', exist_ok=True)\n\
+             open('specs/spec-1/spec.md', 'w').write('# Spec')\n\
+             open('report.json', 'w').write('{\"ok\": true}')\n\
+             send('artifact', path='specs/spec-1/spec.md', kind='spec')\n\
+             send('completed', result={'success': True, 'artifacts': [\n    \
+                 {'path': 'report.json', 'kind': 'report'}, {'path': 'missing.txt'}]})",
+            BridgeTimeouts::default(),
+        )
+        .with_artifacts_root(dir.path().join("artifacts"));
+        let args = WorkflowArgs {
+            workspace_path: Some(workspace.path().to_string_lossy().to_string()),
+            ..args()
+        };
+        bridge.spawn_workflow("wf".to_string(), "plan".to_string(), args).await.unwrap();
+
+        let messages = drain(&bridge, "wf").await;
+        assert!(matches!(messages.last(), Some(OutputMessage::Completed { .. })), "{:?}", messages);
+        assert!(messages.iter().any(|m| matches!(
+            m,
+            OutputMessage::Error { code, message, .. } if code == ARTIFACT_ERROR_CODE && message.starts_with("missing.txt")
+        )));
 
-        // Parse output
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let result: serde_json::Value =
-            serde_json::from_str(&stdout).context("Failed to parse validation result")?;
+        let artifacts = bridge.get_artifacts("wf").unwrap();
+        assert_eq!(artifacts.len(), 2, "{:?}", artifacts);
+        let spec = artifacts.iter().find(|a| a.name == "spec.md").unwrap();
+        assert!(Path::new(&spec.path).starts_with(workspace.path().join(".smartspec/artifacts/wf")));
+        assert_eq!((spec.size_bytes, spec.content_type.as_str()), (6, "text/markdown"));
+        assert_eq!(spec.validation.as_ref().unwrap()["valid"], true);
+        let report = artifacts.iter().find(|a| a.name == "report.json").unwrap();
+        assert_eq!(report.content_type, "application/json");
+        assert!(report.validation.is_none());
 
-        Ok(result)
+        // Nothing is old enough to be pruned yet
+        assert_eq!(bridge.delete_artifacts_older_than(30).unwrap(), 0);
+        assert_eq!(bridge.get_artifacts("wf").unwrap().len(), 2);
     }
 }
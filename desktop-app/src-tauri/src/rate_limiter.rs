@@ -5,13 +5,14 @@
 // Provides:
 // - Token bucket rate limiting
 // - Per-provider rate limits
+// - Per-key buckets for arbitrary keys (provider, user, command)
 // - Cost tracking
 // - Burst handling
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
 
 // ============================================
@@ -142,6 +143,172 @@ impl TokenBucket {
             Duration::from_secs_f64(needed / self.refill_rate)
         }
     }
+    
+    /// Whole tokens left
+    fn remaining(&self) -> u32 {
+        self.tokens.floor() as u32
+    }
+    
+    /// Whether the bucket has refilled to capacity, so a fresh one would
+    /// behave the same
+    fn is_full(&self) -> bool {
+        let elapsed = self.last_refill.elapsed().as_secs_f64();
+        self.tokens + elapsed * self.refill_rate >= self.max_tokens
+    }
+}
+
+// ============================================
+// Keyed Rate Limiter
+// ============================================
+
+/// Capacity and refill rate of one key's bucket
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BucketLimit {
+    /// Requests allowed in a burst
+    pub capacity: u32,
+    /// Requests added back per minute
+    pub refill_per_minute: u32,
+}
+
+impl Default for BucketLimit {
+    fn default() -> Self {
+        Self {
+            capacity: 10,
+            refill_per_minute: 60,
+        }
+    }
+}
+
+/// Bucket key for the `ai_analyze_code` command
+pub const AI_ANALYZE_CODE_KEY: &str = "command:ai_analyze_code";
+
+/// Bucket key for the `ai_predict_bugs` command
+pub const AI_PREDICT_BUGS_KEY: &str = "command:ai_predict_bugs";
+
+/// Keys throttled out of the box
+const DEFAULT_KEY_LIMITS: &[(&str, BucketLimit)] = &[
+    (AI_ANALYZE_CODE_KEY, BucketLimit { capacity: 5, refill_per_minute: 10 }),
+    (AI_PREDICT_BUGS_KEY, BucketLimit { capacity: 5, refill_per_minute: 10 }),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitDecision {
+    pub key: String,
+    pub allowed: bool,
+    /// Requests left in the bucket after this one
+    pub remaining: u32,
+    /// How long until this request would be allowed; 0 when allowed
+    pub retry_after_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BucketStatus {
+    pub key: String,
+    pub limit: BucketLimit,
+    pub remaining: u32,
+    /// How long until one more request is allowed
+    pub retry_after_ms: u64,
+}
+
+/// Token buckets keyed by an arbitrary string. Check-and-consume happens
+/// under one lock, so concurrent callers can never overdraw a bucket.
+pub struct KeyedRateLimiter {
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+    limits: RwLock<HashMap<String, BucketLimit>>,
+    default_limit: BucketLimit,
+}
+
+impl KeyedRateLimiter {
+    pub fn new(default_limit: BucketLimit) -> Self {
+        Self::with_limits(default_limit, HashMap::new())
+    }
+    
+    /// Limiter with some keys configured up front
+    pub fn with_limits(default_limit: BucketLimit, limits: HashMap<String, BucketLimit>) -> Self {
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+            limits: RwLock::new(limits),
+            default_limit,
+        }
+    }
+    
+    fn validate(limit: &BucketLimit) -> Result<(), String> {
+        if limit.capacity == 0 || limit.refill_per_minute == 0 {
+            return Err("Capacity and refill rate must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+    
+    pub async fn limit_for(&self, key: &str) -> BucketLimit {
+        self.limits.read().await.get(key).copied().unwrap_or(self.default_limit)
+    }
+    
+    /// Configure a key's bucket. An existing bucket keeps its tokens, capped
+    /// at the new capacity.
+    pub async fn set_limit(&self, key: &str, limit: BucketLimit) -> Result<(), String> {
+        Self::validate(&limit)?;
+        self.limits.write().await.insert(key.to_string(), limit);
+        
+        if let Some(bucket) = self.buckets.lock().await.get_mut(key) {
+            bucket.refill();
+            let mut replacement = TokenBucket::new(limit.capacity, limit.refill_per_minute);
+            replacement.tokens = bucket.tokens.min(replacement.max_tokens);
+            *bucket = replacement;
+        }
+        Ok(())
+    }
+    
+    /// Take `cost` tokens from `key`'s bucket if it has them
+    pub async fn check(&self, key: &str, cost: u32) -> RateLimitDecision {
+        let limit = self.limit_for(key).await;
+        let cost = cost.max(1) as f64;
+        
+        let mut buckets = self.buckets.lock().await;
+        // Drop idle buckets rather than keep one for every key ever seen
+        buckets.retain(|k, bucket| k == key || !bucket.is_full());
+        let bucket = buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new(limit.capacity, limit.refill_per_minute));
+        
+        let allowed = bucket.try_consume(cost);
+        RateLimitDecision {
+            key: key.to_string(),
+            allowed,
+            remaining: bucket.remaining(),
+            retry_after_ms: if allowed { 0 } else { bucket.time_until_available(cost).as_millis() as u64 },
+        }
+    }
+    
+    /// Every bucket used since its last reset that hasn't refilled yet
+    pub async fn status(&self) -> Vec<BucketStatus> {
+        let limits = self.limits.read().await;
+        let mut buckets = self.buckets.lock().await;
+        
+        let mut statuses: Vec<BucketStatus> = buckets.iter_mut()
+            .map(|(key, bucket)| {
+                bucket.refill();
+                BucketStatus {
+                    key: key.clone(),
+                    limit: limits.get(key).copied().unwrap_or(self.default_limit),
+                    remaining: bucket.remaining(),
+                    retry_after_ms: bucket.time_until_available(1.0).as_millis() as u64,
+                }
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.key.cmp(&b.key));
+        statuses
+    }
+    
+    /// Refill one key's bucket, or all of them. Configured limits are kept.
+    pub async fn reset(&self, key: Option<&str>) {
+        let mut buckets = self.buckets.lock().await;
+        match key {
+            Some(key) => {
+                buckets.remove(key);
+            }
+            None => buckets.clear(),
+        }
+    }
 }
 
 // ============================================
@@ -431,6 +598,13 @@ pub static RATE_LIMITER: Lazy<RateLimiter> = Lazy::new(|| {
     RateLimiter::new(ProviderLimits::default())
 });
 
+pub static KEYED_RATE_LIMITER: Lazy<KeyedRateLimiter> = Lazy::new(|| {
+    let limits = DEFAULT_KEY_LIMITS.iter()
+        .map(|(key, limit)| (key.to_string(), *limit))
+        .collect();
+    KeyedRateLimiter::with_limits(BucketLimit::default(), limits)
+});
+
 // ============================================
 // Tauri Commands
 // ============================================
@@ -463,6 +637,30 @@ pub async fn update_rate_limits(limits: ProviderLimits) -> Result<(), String> {
     Ok(())
 }
 
+/// Take `cost` (default 1) from the bucket for `key`
+#[tauri::command]
+pub async fn rate_limit_check(key: String, cost: Option<u32>) -> Result<RateLimitDecision, String> {
+    Ok(KEYED_RATE_LIMITER.check(&key, cost.unwrap_or(1)).await)
+}
+
+#[tauri::command]
+pub async fn rate_limit_set_limit(key: String, capacity: u32, refill_per_minute: u32) -> Result<(), String> {
+    KEYED_RATE_LIMITER.set_limit(&key, BucketLimit { capacity, refill_per_minute }).await
+}
+
+/// All active buckets
+#[tauri::command]
+pub async fn rate_limit_get_status() -> Result<Vec<BucketStatus>, String> {
+    Ok(KEYED_RATE_LIMITER.status().await)
+}
+
+/// Reset one key, or every key when none is given
+#[tauri::command]
+pub async fn rate_limit_reset(key: Option<String>) -> Result<(), String> {
+    KEYED_RATE_LIMITER.reset(key.as_deref()).await;
+    Ok(())
+}
+
 // ============================================
 // Tests
 // ============================================
@@ -522,4 +720,106 @@ mod tests {
         assert!(!status.allowed);
         assert!(status.reason.unwrap().contains("Daily cost limit"));
     }
+    
+    #[tokio::test]
+    async fn test_keyed_limiter_concurrent_callers_never_overdraw() {
+        let limiter = Arc::new(KeyedRateLimiter::new(BucketLimit::default()));
+        limiter.set_limit("provider:openai", BucketLimit { capacity: 25, refill_per_minute: 1 }).await.unwrap();
+        
+        let tasks: Vec<_> = (0..100)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                tokio::spawn(async move { limiter.check("provider:openai", 1).await })
+            })
+            .collect();
+        
+        let mut allowed = 0;
+        for task in tasks {
+            let decision = task.await.unwrap();
+            if decision.allowed {
+                allowed += 1;
+            } else {
+                assert!(decision.retry_after_ms > 0);
+            }
+        }
+        assert_eq!(allowed, 25);
+        
+        // Other keys have their own buckets
+        let other = limiter.check("user:alice", 1).await;
+        assert!(other.allowed);
+        assert_eq!(other.remaining, BucketLimit::default().capacity - 1);
+        
+        let status = limiter.status().await;
+        assert_eq!(status.iter().map(|s| s.key.as_str()).collect::<Vec<_>>(), ["provider:openai", "user:alice"]);
+        assert_eq!(status[0].remaining, 0);
+        assert_eq!(status[0].limit.capacity, 25);
+        
+        limiter.reset(Some("provider:openai")).await;
+        assert_eq!(limiter.check("provider:openai", 1).await.remaining, 24);
+    }
+    
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_keyed_limiter_multi_thread_hammering() {
+        let limiter = Arc::new(KeyedRateLimiter::new(BucketLimit { capacity: 40, refill_per_minute: 1 }));
+        
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let limiter = Arc::clone(&limiter);
+                tokio::spawn(async move {
+                    let mut allowed = 0;
+                    for _ in 0..50 {
+                        if limiter.check(AI_ANALYZE_CODE_KEY, 1).await.allowed {
+                            allowed += 1;
+                        }
+                    }
+                    allowed
+                })
+            })
+            .collect();
+        
+        let mut allowed = 0;
+        for task in tasks {
+            allowed += task.await.unwrap();
+        }
+        assert_eq!(allowed, 40);
+    }
+    
+    #[tokio::test]
+    async fn test_keyed_limiter_limits_and_cost() {
+        let limiter = KeyedRateLimiter::new(BucketLimit::default());
+        assert!(limiter.set_limit("k", BucketLimit { capacity: 0, refill_per_minute: 1 }).await.is_err());
+        
+        limiter.set_limit("k", BucketLimit { capacity: 5, refill_per_minute: 60 }).await.unwrap();
+        let decision = limiter.check("k", 4).await;
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 1);
+        
+        let denied = limiter.check("k", 3).await;
+        assert!(!denied.allowed);
+        assert_eq!(denied.remaining, 1);
+        // Two more tokens at one per second
+        assert!(denied.retry_after_ms > 1_000 && denied.retry_after_ms <= 2_000);
+        
+        // Shrinking the limit caps the tokens already in the bucket
+        limiter.set_limit("k", BucketLimit { capacity: 1, refill_per_minute: 60 }).await.unwrap();
+        assert_eq!(limiter.status().await[0].remaining, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_keyed_limiter_expires_refilled_buckets() {
+        let limiter = KeyedRateLimiter::new(BucketLimit::default());
+        limiter.set_limit("fast", BucketLimit { capacity: 1, refill_per_minute: 60_000 }).await.unwrap();
+        assert!(limiter.check("fast", 1).await.allowed);
+        assert!(limiter.check("slow", 1).await.allowed);
+        
+        // "fast" refills within milliseconds and is dropped on the next check
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(limiter.check("slow", 1).await.allowed);
+        let status = limiter.status().await;
+        assert_eq!(status.iter().map(|s| s.key.as_str()).collect::<Vec<_>>(), ["slow"]);
+        assert_eq!(status[0].remaining, BucketLimit::default().capacity - 2);
+        
+        // A dropped key starts again from a full bucket
+        assert_eq!(limiter.check("fast", 1).await.remaining, 0);
+    }
 }
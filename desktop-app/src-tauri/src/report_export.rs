@@ -0,0 +1,319 @@
+// Report Export - Streaming CSV and PDF writers
+//
+// Provides:
+// - CSV output with `#` metadata lines ahead of the column header
+// - Plain-text PDF output using the built-in Helvetica fonts
+// - Both write row by row to any `Write`, so large reports never sit in memory
+
+use std::io::{self, Write};
+
+// ============================================
+// Types
+// ============================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "pdf" => Ok(ReportFormat::Pdf),
+            _ => Err(format!("Invalid report format: {}", s)),
+        }
+    }
+}
+
+// ============================================
+// CSV
+// ============================================
+
+pub struct CsvWriter<W: Write> {
+    out: W,
+    columns: usize,
+    rows: usize,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Write `metadata` as `# key: value` lines, then the column header
+    pub fn new(mut out: W, metadata: &[(&str, String)], columns: &[&str]) -> io::Result<Self> {
+        for (key, value) in metadata {
+            writeln!(out, "# {}: {}", key, value.replace(['\r', '\n'], " "))?;
+        }
+        let mut writer = Self { out, columns: columns.len(), rows: 0 };
+        writer.write_record(columns.iter().copied())?;
+        Ok(writer)
+    }
+
+    pub fn row<S: AsRef<str>>(&mut self, fields: &[S]) -> io::Result<()> {
+        if fields.len() != self.columns {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Expected {} fields, got {}", self.columns, fields.len()),
+            ));
+        }
+        self.write_record(fields.iter().map(|f| f.as_ref()))?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn write_record<'a>(&mut self, fields: impl Iterator<Item = &'a str>) -> io::Result<()> {
+        for (i, field) in fields.enumerate() {
+            if i > 0 {
+                self.out.write_all(b",")?;
+            }
+            self.out.write_all(csv_field(field).as_bytes())?;
+        }
+        self.out.write_all(b"\n")
+    }
+
+    /// Flush and return the number of data rows written
+    pub fn finish(mut self) -> io::Result<usize> {
+        self.out.flush()?;
+        Ok(self.rows)
+    }
+}
+
+/// Spreadsheet apps evaluate cells starting with these as formulas
+const FORMULA_PREFIXES: [char; 4] = ['=', '+', '-', '@'];
+
+fn csv_field(field: &str) -> String {
+    // A leading quote makes spreadsheets treat the cell as text
+    let field = if field.starts_with(FORMULA_PREFIXES) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+// ============================================
+// PDF
+// ============================================
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const BODY_SIZE: f32 = 9.0;
+const HEADING_SIZE: f32 = 13.0;
+const LEADING: f32 = 1.4;
+/// Helvetica averages about half an em per character
+const MAX_LINE_CHARS: usize = ((PAGE_WIDTH - 2.0 * MARGIN) / (BODY_SIZE * 0.5)) as usize;
+
+const CATALOG_ID: usize = 1;
+const PAGES_ID: usize = 2;
+const BODY_FONT_ID: usize = 3;
+const HEADING_FONT_ID: usize = 4;
+
+struct CountingWriter<W: Write> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Minimal PDF writer for text reports. Each page is written as soon as it
+/// fills, so only the current page is held in memory.
+pub struct PdfTextWriter<W: Write> {
+    out: CountingWriter<W>,
+    /// Byte offset of each object, indexed by object id - 1
+    offsets: Vec<u64>,
+    page_ids: Vec<usize>,
+    content: String,
+    cursor_y: f32,
+}
+
+impl<W: Write> PdfTextWriter<W> {
+    pub fn new(out: W) -> io::Result<Self> {
+        let mut writer = Self {
+            out: CountingWriter { inner: out, written: 0 },
+            offsets: vec![0; HEADING_FONT_ID],
+            page_ids: Vec::new(),
+            content: String::new(),
+            cursor_y: PAGE_HEIGHT - MARGIN,
+        };
+        writer.out.write_all(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+        writer.write_object(BODY_FONT_ID, "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>")?;
+        writer.write_object(HEADING_FONT_ID, "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>")?;
+        Ok(writer)
+    }
+
+    pub fn heading(&mut self, text: &str) -> io::Result<()> {
+        self.text("F2", HEADING_SIZE, text)
+    }
+
+    /// Body text; long lines wrap
+    pub fn line(&mut self, text: &str) -> io::Result<()> {
+        let chars: Vec<char> = text.chars().collect();
+        if chars.is_empty() {
+            return self.text("F1", BODY_SIZE, "");
+        }
+        for chunk in chars.chunks(MAX_LINE_CHARS) {
+            self.text("F1", BODY_SIZE, &chunk.iter().collect::<String>())?;
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, font: &str, size: f32, text: &str) -> io::Result<()> {
+        let height = size * LEADING;
+        if self.cursor_y - height < MARGIN {
+            self.flush_page()?;
+        }
+        self.cursor_y -= height;
+        self.content.push_str(&format!(
+            "BT /{} {} Tf {} {} Td ({}) Tj ET\n",
+            font, size, MARGIN, self.cursor_y, pdf_string(text)
+        ));
+        Ok(())
+    }
+
+    fn flush_page(&mut self) -> io::Result<()> {
+        let content_id = self.offsets.len() + 1;
+        let page_id = content_id + 1;
+        let content = std::mem::take(&mut self.content);
+
+        self.offsets.push(0);
+        self.write_object(content_id, &format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content))?;
+        self.offsets.push(0);
+        self.write_object(page_id, &format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Contents {} 0 R /Resources << /Font << /F1 {} 0 R /F2 {} 0 R >> >> >>",
+            PAGES_ID, PAGE_WIDTH, PAGE_HEIGHT, content_id, BODY_FONT_ID, HEADING_FONT_ID
+        ))?;
+
+        self.page_ids.push(page_id);
+        self.cursor_y = PAGE_HEIGHT - MARGIN;
+        Ok(())
+    }
+
+    fn write_object(&mut self, id: usize, body: &str) -> io::Result<()> {
+        self.offsets[id - 1] = self.out.written;
+        write!(self.out, "{} 0 obj\n{}\nendobj\n", id, body)
+    }
+
+    /// Write the page tree, cross-reference table and trailer.
+    /// Returns the number of pages.
+    pub fn finish(mut self) -> io::Result<usize> {
+        if !self.content.is_empty() || self.page_ids.is_empty() {
+            self.flush_page()?;
+        }
+
+        let kids: Vec<String> = self.page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        self.write_object(PAGES_ID, &format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            self.page_ids.len()
+        ))?;
+        self.write_object(CATALOG_ID, &format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES_ID))?;
+
+        let xref_offset = self.out.written;
+        write!(self.out, "xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1)?;
+        for offset in &self.offsets {
+            writeln!(self.out, "{:010} 00000 n ", offset)?;
+        }
+        write!(
+            self.out,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            CATALOG_ID,
+            xref_offset
+        )?;
+        self.out.flush()?;
+        Ok(self.page_ids.len())
+    }
+}
+
+/// Escape a string literal. The standard fonts only cover Latin-1 here, so
+/// anything else is replaced.
+fn pdf_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_quotes_and_counts_rows() {
+        let mut out = Vec::new();
+        let mut csv = CsvWriter::new(&mut out, &[("Title", "Report".to_string())], &["a", "b"]).unwrap();
+        csv.row(&["plain", "has,comma"]).unwrap();
+        csv.row(&["say \"hi\"", "two\nlines"]).unwrap();
+        assert!(csv.row(&["too few"]).is_err());
+        assert_eq!(csv.finish().unwrap(), 2);
+
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(
+            text,
+            "# Title: Report\na,b\nplain,\"has,comma\"\n\"say \"\"hi\"\"\",\"two\nlines\"\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_neutralizes_formula_cells() {
+        let mut out = Vec::new();
+        let mut csv = CsvWriter::new(&mut out, &[], &["a", "b", "c", "d"]).unwrap();
+        csv.row(&["=HYPERLINK(\"http://evil\")", "+1", "-2,3", "@SUM(A1)"]).unwrap();
+        csv.row(&["safe", "a=b", "2026-01-01", ""]).unwrap();
+        csv.finish().unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(
+            text,
+            "a,b,c,d\n\"'=HYPERLINK(\"\"http://evil\"\")\",'+1,\"'-2,3\",'@SUM(A1)\nsafe,a=b,2026-01-01,\n"
+        );
+    }
+
+    #[test]
+    fn test_pdf_structure_and_pagination() {
+        let mut out = Vec::new();
+        let mut pdf = PdfTextWriter::new(&mut out).unwrap();
+        pdf.heading("Report (draft)").unwrap();
+        for i in 0..200 {
+            pdf.line(&format!("Row {}", i)).unwrap();
+        }
+        pdf.line(&"x".repeat(MAX_LINE_CHARS * 2 + 1)).unwrap();
+        let pages = pdf.finish().unwrap();
+        assert!(pages > 1);
+
+        let text = String::from_utf8_lossy(&out);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains(&format!("/Count {}", pages)));
+        assert!(text.contains("(Report \\(draft\\)) Tj"));
+
+        // Every xref entry points at the start of its object
+        let xref = text.rfind("xref\n").unwrap();
+        for (i, entry) in text[xref..].lines().skip(3).take_while(|l| l.ends_with(" n ")).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(out[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use crate::models::*;
@@ -209,10 +210,12 @@ impl ExecutionRepository {
 
         let output_json = execution.output.as_ref()
             .map(|o| serde_json::to_string(o).unwrap_or_default());
+        let snapshot_json = execution.config_snapshot.as_ref()
+            .map(|c| serde_json::to_string(c).unwrap_or_default());
 
         conn.execute(
-            "INSERT INTO executions (id, workflow_id, workflow_name, status, output, error, started_at, completed_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO executions (id, workflow_id, workflow_name, status, output, error, started_at, completed_at, config_snapshot) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 execution.id,
                 execution.workflow_id,
@@ -222,6 +225,7 @@ impl ExecutionRepository {
                 execution.error,
                 execution.started_at,
                 execution.completed_at,
+                snapshot_json,
             ],
         ).context("Failed to create execution")?;
 
@@ -233,37 +237,32 @@ impl ExecutionRepository {
         let conn = self.conn.lock().unwrap();
 
         let mut stmt = conn.prepare(
-            "SELECT id, workflow_id, workflow_name, status, output, error, started_at, completed_at 
+            "SELECT id, workflow_id, workflow_name, status, output, error, started_at, completed_at, config_snapshot 
              FROM executions WHERE id = ?1"
         )?;
 
-        let execution = stmt.query_row(params![id], |row| {
-            let status_str: String = row.get(3)?;
-            let status = ExecutionStatus::from_str(&status_str).unwrap_or(ExecutionStatus::Failed);
-
-            let output_str: Option<String> = row.get(4)?;
-            let output = output_str.and_then(|s| serde_json::from_str(&s).ok());
-
-            Ok(Execution {
-                id: row.get(0)?,
-                workflow_id: row.get(1)?,
-                workflow_name: row.get(2)?,
-                status,
-                output,
-                error: row.get(5)?,
-                started_at: row.get(6)?,
-                completed_at: row.get(7)?,
-            })
-        }).optional()?;
+        let execution = stmt.query_row(params![id], execution_from_row).optional()?;
 
         Ok(execution)
     }
 
     /// List executions with optional filter
     pub fn list(&self, filter: &ExecutionFilter) -> Result<Vec<Execution>> {
+        Ok(self.list_page(filter)?.items)
+    }
+
+    /// List executions newest first, continuing from `before_id`/`after_id`
+    pub fn list_page(&self, filter: &ExecutionFilter) -> Result<Page<Execution>> {
         let conn = self.conn.lock().unwrap();
 
-        let mut sql = "SELECT id, workflow_id, workflow_name, status, output, error, started_at, completed_at 
+        let page = PageRequest {
+            before_id: filter.before_id.clone(),
+            after_id: filter.after_id.clone(),
+            limit: filter.limit,
+        };
+        let cursor = page.cursor().map_err(anyhow::Error::msg)?;
+
+        let mut sql = "SELECT id, workflow_id, workflow_name, status, output, error, started_at, completed_at, config_snapshot 
                        FROM executions WHERE 1=1".to_string();
         let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
@@ -277,12 +276,25 @@ impl ExecutionRepository {
             params_vec.push(Box::new(status.as_str().to_string()));
         }
 
-        sql.push_str(" ORDER BY started_at DESC");
-
-        if let Some(limit) = filter.limit {
-            sql.push_str(&format!(" LIMIT {}", limit));
+        // Keyset on (started_at, id) so rows inserted meanwhile never shift a page
+        if let PageCursor::Before(id) | PageCursor::After(id) = cursor {
+            let started_at: i64 = conn.query_row(
+                "SELECT started_at FROM executions WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            ).optional()?.context("Execution cursor not found")?;
+
+            let op = if matches!(cursor, PageCursor::Before(_)) { "<" } else { ">" };
+            sql.push_str(&format!(" AND (started_at {0} ? OR (started_at = ? AND id {0} ?))", op));
+            params_vec.push(Box::new(started_at));
+            params_vec.push(Box::new(started_at));
+            params_vec.push(Box::new(id.to_string()));
         }
 
+        let newer = matches!(cursor, PageCursor::After(_));
+        sql.push_str(if newer { " ORDER BY started_at ASC, id ASC" } else { " ORDER BY started_at DESC, id DESC" });
+        sql.push_str(&format!(" LIMIT {}", page.fetch_limit()));
+
         if let Some(offset) = filter.offset {
             sql.push_str(&format!(" OFFSET {}", offset));
         }
@@ -291,31 +303,19 @@ impl ExecutionRepository {
 
         let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
-        let executions = stmt.query_map(params_refs.as_slice(), |row| {
-            let status_str: String = row.get(3)?;
-            let status = ExecutionStatus::from_str(&status_str).unwrap_or(ExecutionStatus::Failed);
-
-            let output_str: Option<String> = row.get(4)?;
-            let output = output_str.and_then(|s| serde_json::from_str(&s).ok());
-
-            Ok(Execution {
-                id: row.get(0)?,
-                workflow_id: row.get(1)?,
-                workflow_name: row.get(2)?,
-                status,
-                output,
-                error: row.get(5)?,
-                started_at: row.get(6)?,
-                completed_at: row.get(7)?,
-            })
-        })?;
+        let executions = stmt.query_map(params_refs.as_slice(), execution_from_row)?;
 
         let mut result = Vec::new();
         for execution in executions {
             result.push(execution?);
         }
 
-        Ok(result)
+        let mut page = Page::from_rows(result, page.limit, |e| e.id.clone());
+        if newer {
+            page.items.reverse();
+        }
+
+        Ok(page)
     }
 
     /// Update execution status
@@ -376,6 +376,36 @@ impl ExecutionRepository {
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM executions", [], |row| row.get(0))?;
         Ok(count)
     }
+
+    /// Compare the config snapshots of two executions
+    pub fn diff_configs(&self, id_a: &str, id_b: &str) -> Result<ConfigDiff> {
+        let a = self.get_by_id(id_a)?.with_context(|| format!("Execution not found: {}", id_a))?;
+        let b = self.get_by_id(id_b)?.with_context(|| format!("Execution not found: {}", id_b))?;
+        Ok(ConfigDiff::between(&a, &b))
+    }
+}
+
+/// Map a row selected with the columns of `Execution`, in field order
+fn execution_from_row(row: &rusqlite::Row) -> rusqlite::Result<Execution> {
+    let status_str: String = row.get(3)?;
+    let status = ExecutionStatus::from_str(&status_str).unwrap_or(ExecutionStatus::Failed);
+
+    let output_str: Option<String> = row.get(4)?;
+    let output = output_str.and_then(|s| serde_json::from_str(&s).ok());
+    let snapshot_str: Option<String> = row.get(8)?;
+    let config_snapshot = snapshot_str.and_then(|s| serde_json::from_str(&s).ok());
+
+    Ok(Execution {
+        id: row.get(0)?,
+        workflow_id: row.get(1)?,
+        workflow_name: row.get(2)?,
+        status,
+        output,
+        error: row.get(5)?,
+        started_at: row.get(6)?,
+        completed_at: row.get(7)?,
+        config_snapshot,
+    })
 }
 
 /// Repository for config operations
@@ -391,28 +421,7 @@ impl ConfigRepository {
     /// Create or update a config
     pub fn upsert(&self, config: &Config) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-
-        conn.execute(
-            "INSERT INTO configs (id, workflow_id, key, value, value_type, description, created_at, updated_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
-             ON CONFLICT(workflow_id, key) DO UPDATE SET 
-                value = excluded.value,
-                value_type = excluded.value_type,
-                description = excluded.description,
-                updated_at = excluded.updated_at",
-            params![
-                config.id,
-                config.workflow_id,
-                config.key,
-                config.value,
-                config.value_type.as_str(),
-                config.description,
-                config.created_at,
-                config.updated_at,
-            ],
-        ).context("Failed to upsert config")?;
-
-        Ok(())
+        upsert_config(&conn, config)
     }
 
     /// Get config by workflow ID and key
@@ -501,6 +510,151 @@ impl ConfigRepository {
     }
 }
 
+fn upsert_config(conn: &Connection, config: &Config) -> Result<()> {
+    conn.execute(
+        "INSERT INTO configs (id, workflow_id, key, value, value_type, description, created_at, updated_at) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(workflow_id, key) DO UPDATE SET 
+            value = excluded.value,
+            value_type = excluded.value_type,
+            description = excluded.description,
+            updated_at = excluded.updated_at",
+        params![
+            config.id,
+            config.workflow_id,
+            config.key,
+            config.value,
+            config.value_type.as_str(),
+            config.description,
+            config.created_at,
+            config.updated_at,
+        ],
+    ).context("Failed to upsert config")?;
+
+    Ok(())
+}
+
+/// Repository for config templates
+pub struct ConfigTemplateRepository {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl ConfigTemplateRepository {
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    /// Create or update a template, matched by ID
+    pub fn save(&self, template: &ConfigTemplate) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO config_templates (id, name, description, variables, entries, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                description = excluded.description,
+                variables = excluded.variables,
+                entries = excluded.entries,
+                updated_at = excluded.updated_at",
+            params![
+                template.id,
+                template.name,
+                template.description,
+                serde_json::to_string(&template.variables)?,
+                serde_json::to_string(&template.entries)?,
+                template.created_at,
+                template.updated_at,
+            ],
+        ).context("Failed to save config template")?;
+
+        Ok(())
+    }
+
+    /// Get template by ID
+    pub fn get_by_id(&self, id: &str) -> Result<Option<ConfigTemplate>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, variables, entries, created_at, updated_at
+             FROM config_templates WHERE id = ?1"
+        )?;
+
+        let template = stmt.query_row(params![id], config_template_from_row).optional()?;
+
+        Ok(template)
+    }
+
+    /// List all templates
+    pub fn list(&self) -> Result<Vec<ConfigTemplate>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, variables, entries, created_at, updated_at
+             FROM config_templates ORDER BY name"
+        )?;
+
+        let templates = stmt.query_map([], config_template_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(templates)
+    }
+
+    /// Delete template
+    pub fn delete(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "DELETE FROM config_templates WHERE id = ?1",
+            params![id],
+        ).context("Failed to delete config template")?;
+
+        Ok(())
+    }
+
+    /// Resolve a template with `vars` and upsert the resulting configs into
+    /// the workflow. Nothing is written unless every config resolves.
+    pub fn apply(
+        &self,
+        workflow_id: &str,
+        template_id: &str,
+        vars: &HashMap<String, String>,
+    ) -> Result<Vec<Config>> {
+        let template = self.get_by_id(template_id)?
+            .with_context(|| format!("Config template not found: {}", template_id))?;
+        let configs = template.resolve(workflow_id, vars)?;
+
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()
+            .context("Failed to begin config template transaction")?;
+        for config in &configs {
+            upsert_config(&tx, config)?;
+        }
+        tx.commit().context("Failed to apply config template")?;
+
+        Ok(configs)
+    }
+}
+
+fn config_template_from_row(row: &rusqlite::Row) -> rusqlite::Result<ConfigTemplate> {
+    fn json_column<T: serde::de::DeserializeOwned>(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<T> {
+        let text: String = row.get(idx)?;
+        serde_json::from_str(&text).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+        })
+    }
+
+    Ok(ConfigTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        variables: json_column(row, 3)?,
+        entries: json_column(row, 4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -601,4 +755,149 @@ mod tests {
 
         let _ = fs::remove_file(&db_path);
     }
+
+    #[test]
+    fn test_diff_execution_configs() {
+        let db_path = std::path::PathBuf::from("/tmp/test_execution_config_diff.db");
+        let _ = fs::remove_file(&db_path);
+
+        let db = Database::new(db_path.clone()).unwrap();
+        let workflow = Workflow::new("plan".to_string(), None, None);
+        WorkflowRepository::new(db.get_connection()).create(&workflow).unwrap();
+        let repo = ExecutionRepository::new(db.get_connection());
+
+        let first = Execution::new(workflow.id.clone(), "plan".to_string())
+            .with_config_snapshot(Some(serde_json::json!({ "llm": { "model": "gpt-4o", "max_tokens": 4000 } })));
+        let second = Execution::new(workflow.id.clone(), "plan".to_string())
+            .with_config_snapshot(Some(serde_json::json!({ "llm": { "model": "gpt-4o", "max_tokens": 8000 }, "mode": "strict" })));
+        repo.create(&first).unwrap();
+        repo.create(&second).unwrap();
+
+        // Snapshots round-trip through the database
+        let stored = repo.get_by_id(&first.id).unwrap().unwrap();
+        assert_eq!(stored.config_snapshot, first.config_snapshot);
+
+        let diff = repo.diff_configs(&first.id, &second.id).unwrap();
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].path, "llm.max_tokens");
+        assert_eq!(diff.added[0].path, "mode");
+        assert!(diff.removed.is_empty());
+
+        assert!(repo.diff_configs(&first.id, "missing").is_err());
+
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_apply_config_template() {
+        let db_path = std::path::PathBuf::from("/tmp/test_config_templates.db");
+        let _ = fs::remove_file(&db_path);
+
+        let db = Database::new(db_path.clone()).unwrap();
+        let workflow = Workflow::new("plan".to_string(), None, None);
+        WorkflowRepository::new(db.get_connection()).create(&workflow).unwrap();
+        let configs = ConfigRepository::new(db.get_connection());
+        let repo = ConfigTemplateRepository::new(db.get_connection());
+
+        let variable = |name: &str| ConfigTemplateVariable {
+            name: name.to_string(),
+            description: None,
+            default: None,
+            required: true,
+        };
+        let entry = |key: &str, value: &str, value_type: ConfigValueType| ConfigTemplateEntry {
+            key: key.to_string(),
+            value: value.to_string(),
+            value_type,
+            description: None,
+        };
+        let template = ConfigTemplate::new(
+            "llm-defaults".to_string(),
+            None,
+            vec![variable("model"), variable("max_tokens")],
+            vec![
+                entry("llm.model", "{{model}}", ConfigValueType::String),
+                entry("llm.max_tokens", "{{max_tokens}}", ConfigValueType::Number),
+            ],
+        );
+        repo.save(&template).unwrap();
+        assert_eq!(repo.list().unwrap().len(), 1);
+
+        // An existing key is updated in place
+        configs.upsert(&Config::new(
+            workflow.id.clone(),
+            "llm.model".to_string(),
+            "gpt-4o-mini".to_string(),
+            ConfigValueType::String,
+            None,
+        )).unwrap();
+
+        let vars = HashMap::from([
+            ("model".to_string(), "gpt-4o".to_string()),
+            ("max_tokens".to_string(), "8000".to_string()),
+        ]);
+        let applied = repo.apply(&workflow.id, &template.id, &vars).unwrap();
+        assert_eq!(applied.len(), 2);
+        let stored = configs.list_by_workflow(&workflow.id).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(configs.get(&workflow.id, "llm.model").unwrap().unwrap().value, "gpt-4o");
+
+        // Missing variables write nothing
+        let partial = HashMap::from([("model".to_string(), "o1".to_string())]);
+        let err = repo.apply(&workflow.id, &template.id, &partial).unwrap_err();
+        assert!(err.to_string().contains("max_tokens"));
+        assert_eq!(configs.get(&workflow.id, "llm.model").unwrap().unwrap().value, "gpt-4o");
+
+        assert!(repo.apply(&workflow.id, "missing", &vars).is_err());
+
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_execution_keyset_pagination() {
+        let db_path = std::path::PathBuf::from("/tmp/test_execution_pages.db");
+        let _ = fs::remove_file(&db_path);
+
+        let db = Database::new(db_path.clone()).unwrap();
+        let repo = ExecutionRepository::new(db.get_connection());
+
+        let insert = |name: &str, started_at: i64| {
+            let mut execution = Execution::new("workflow-id".to_string(), name.to_string());
+            execution.started_at = started_at;
+            repo.create(&execution).unwrap();
+        };
+        // Two share a timestamp so the id tiebreak is exercised
+        for (i, started_at) in [100, 200, 200, 300, 400].into_iter().enumerate() {
+            insert(&format!("run-{}", i), started_at);
+        }
+
+        let mut filter = ExecutionFilter { limit: Some(2), ..ExecutionFilter::default() };
+        let first = repo.list_page(&filter).unwrap();
+        assert_eq!(first.items.iter().map(|e| e.started_at).collect::<Vec<_>>(), vec![400, 300]);
+
+        // Newer rows arriving between requests don't shift older pages
+        insert("late", 500);
+
+        let mut seen: Vec<String> = first.items.iter().map(|e| e.workflow_name.clone()).collect();
+        let mut cursor = first.next_cursor;
+        while let Some(before_id) = cursor {
+            filter.before_id = Some(before_id);
+            let page = repo.list_page(&filter).unwrap();
+            seen.extend(page.items.iter().map(|e| e.workflow_name.clone()));
+            cursor = page.next_cursor;
+        }
+        assert_eq!(seen.len(), 5);
+        assert_eq!(seen.first().map(String::as_str), Some("run-4"));
+        assert_eq!(seen.last().map(String::as_str), Some("run-0"));
+        assert!(!seen.contains(&"late".to_string()));
+
+        // Paging forward from the newest original row finds only the late one
+        let newest = repo.list(&ExecutionFilter::default()).unwrap()[1].id.clone();
+        let newer = repo.list_page(&ExecutionFilter { after_id: Some(newest), ..ExecutionFilter::default() }).unwrap();
+        assert_eq!(newer.items.len(), 1);
+        assert_eq!(newer.items[0].workflow_name, "late");
+        assert!(newer.next_cursor.is_none());
+
+        let _ = fs::remove_file(&db_path);
+    }
 }
@@ -6,11 +6,10 @@
 // - API key management
 // - Encrypted local storage fallback
 
-use keyring::Entry;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-const SERVICE: &str = "smartspecpro";
+use crate::keyring_fallback::{get_secure_storage, SecureStorage};
 
 // ============================================
 // Token Types
@@ -24,34 +23,43 @@ pub struct StoredCredential {
     pub expires_at: Option<i64>,
 }
 
+// ============================================
+// Storage Access
+// ============================================
+
+/// Run `f` against the shared secure storage, which falls back to an
+/// encrypted file when the OS keyring is unavailable
+fn with_storage<T>(f: impl FnOnce(&SecureStorage) -> Result<T, String>) -> Result<T, String> {
+    let guard = get_secure_storage()?;
+    let storage = guard.as_ref().ok_or("Secure storage not initialized")?;
+    f(storage)
+}
+
+fn load(storage: &SecureStorage, key: &str) -> Result<Option<String>, String> {
+    Ok(storage.get(key)?.map(|secret| secret.value))
+}
+
+fn store(key: &str, value: &str) -> Result<(), String> {
+    with_storage(|storage| storage.set(key, value).map(|_| ()))
+}
+
 // ============================================
 // Proxy Token Commands (existing)
 // ============================================
 
 #[tauri::command]
 pub fn set_proxy_token(token: String) -> Result<(), String> {
-    let entry = Entry::new(SERVICE, "proxy_token").map_err(|e| e.to_string())?;
-    entry.set_password(&token).map_err(|e| e.to_string())
+    store("proxy_token", &token)
 }
 
 #[tauri::command]
 pub fn get_proxy_token() -> Result<Option<String>, String> {
-    let entry = Entry::new(SERVICE, "proxy_token").map_err(|e| e.to_string())?;
-    match entry.get_password() {
-        Ok(v) => Ok(Some(v)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| load(storage, "proxy_token"))
 }
 
 #[tauri::command]
 pub fn delete_proxy_token() -> Result<(), String> {
-    let entry = Entry::new(SERVICE, "proxy_token").map_err(|e| e.to_string())?;
-    match entry.delete_password() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| storage.delete("proxy_token"))
 }
 
 // ============================================
@@ -63,28 +71,17 @@ pub fn set_auth_token(token: String) -> Result<(), String> {
     if token.is_empty() {
         return Err("Token cannot be empty".to_string());
     }
-    let entry = Entry::new(SERVICE, "auth_token").map_err(|e| e.to_string())?;
-    entry.set_password(&token).map_err(|e| e.to_string())
+    store("auth_token", &token)
 }
 
 #[tauri::command]
 pub fn get_auth_token() -> Result<Option<String>, String> {
-    let entry = Entry::new(SERVICE, "auth_token").map_err(|e| e.to_string())?;
-    match entry.get_password() {
-        Ok(v) => Ok(Some(v)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| load(storage, "auth_token"))
 }
 
 #[tauri::command]
 pub fn delete_auth_token() -> Result<(), String> {
-    let entry = Entry::new(SERVICE, "auth_token").map_err(|e| e.to_string())?;
-    match entry.delete_password() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| storage.delete("auth_token"))
 }
 
 #[tauri::command]
@@ -92,28 +89,17 @@ pub fn set_refresh_token(token: String) -> Result<(), String> {
     if token.is_empty() {
         return Err("Token cannot be empty".to_string());
     }
-    let entry = Entry::new(SERVICE, "refresh_token").map_err(|e| e.to_string())?;
-    entry.set_password(&token).map_err(|e| e.to_string())
+    store("refresh_token", &token)
 }
 
 #[tauri::command]
 pub fn get_refresh_token() -> Result<Option<String>, String> {
-    let entry = Entry::new(SERVICE, "refresh_token").map_err(|e| e.to_string())?;
-    match entry.get_password() {
-        Ok(v) => Ok(Some(v)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| load(storage, "refresh_token"))
 }
 
 #[tauri::command]
 pub fn delete_refresh_token() -> Result<(), String> {
-    let entry = Entry::new(SERVICE, "refresh_token").map_err(|e| e.to_string())?;
-    match entry.delete_password() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| storage.delete("refresh_token"))
 }
 
 // ============================================
@@ -133,46 +119,35 @@ pub fn set_api_key(provider: String, api_key: String) -> Result<(), String> {
     }
     
     let key_name = format!("api_key_{}", provider.to_lowercase());
-    let entry = Entry::new(SERVICE, &key_name).map_err(|e| e.to_string())?;
-    entry.set_password(&api_key).map_err(|e| e.to_string())
+    store(&key_name, &api_key)
 }
 
 #[tauri::command]
 pub fn get_api_key(provider: String) -> Result<Option<String>, String> {
     let key_name = format!("api_key_{}", provider.to_lowercase());
-    let entry = Entry::new(SERVICE, &key_name).map_err(|e| e.to_string())?;
-    match entry.get_password() {
-        Ok(v) => Ok(Some(v)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| load(storage, &key_name))
 }
 
 #[tauri::command]
 pub fn delete_api_key(provider: String) -> Result<(), String> {
     let key_name = format!("api_key_{}", provider.to_lowercase());
-    let entry = Entry::new(SERVICE, &key_name).map_err(|e| e.to_string())?;
-    match entry.delete_password() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| storage.delete(&key_name))
 }
 
 #[tauri::command]
 pub fn list_stored_api_keys() -> Result<Vec<String>, String> {
     let providers = ["openrouter", "openai", "anthropic", "deepseek", "google"];
-    let mut stored = Vec::new();
     
-    for provider in providers {
-        let key_name = format!("api_key_{}", provider);
-        let entry = Entry::new(SERVICE, &key_name).map_err(|e| e.to_string())?;
-        if entry.get_password().is_ok() {
-            stored.push(provider.to_string());
+    with_storage(|storage| {
+        let mut stored = Vec::new();
+        for provider in providers {
+            let key_name = format!("api_key_{}", provider);
+            if matches!(load(storage, &key_name), Ok(Some(_))) {
+                stored.push(provider.to_string());
+            }
         }
-    }
-    
-    Ok(stored)
+        Ok(stored)
+    })
 }
 
 // ============================================
@@ -181,54 +156,149 @@ pub fn list_stored_api_keys() -> Result<Vec<String>, String> {
 
 #[tauri::command]
 pub fn set_user_data(user_json: String) -> Result<(), String> {
-    let entry = Entry::new(SERVICE, "user_data").map_err(|e| e.to_string())?;
-    entry.set_password(&user_json).map_err(|e| e.to_string())
+    store("user_data", &user_json)
 }
 
 #[tauri::command]
 pub fn get_user_data() -> Result<Option<String>, String> {
-    let entry = Entry::new(SERVICE, "user_data").map_err(|e| e.to_string())?;
-    match entry.get_password() {
-        Ok(v) => Ok(Some(v)),
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| load(storage, "user_data"))
 }
 
 #[tauri::command]
 pub fn delete_user_data() -> Result<(), String> {
-    let entry = Entry::new(SERVICE, "user_data").map_err(|e| e.to_string())?;
-    match entry.delete_password() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
+    with_storage(|storage| storage.delete("user_data"))
+}
+
+// ============================================
+// Workspace Database Key
+// ============================================
+
+/// Secure storage entry holding the SQLCipher key for workspace databases
+pub const WORKSPACE_DB_KEY: &str = "workspace_db_key";
+
+/// Load the workspace database encryption key, if one is configured
+pub fn load_workspace_db_key() -> Result<Option<String>, String> {
+    with_storage(|storage| Ok(load(storage, WORKSPACE_DB_KEY)?.filter(|key| !key.is_empty())))
+}
+
+#[tauri::command]
+pub fn set_workspace_db_key(key: String) -> Result<(), String> {
+    if key.is_empty() {
+        return Err("Key cannot be empty".to_string());
     }
+    store(WORKSPACE_DB_KEY, &key)
+}
+
+#[tauri::command]
+pub fn has_workspace_db_key() -> Result<bool, String> {
+    load_workspace_db_key().map(|key| key.is_some())
+}
+
+// ============================================
+// Audit Chain Anchor
+// ============================================
+
+/// Secure storage entry holding the audit chain's head, kept apart from the
+/// chain file so truncating that file can be detected
+pub const AUDIT_ANCHOR_KEY: &str = "audit_chain_anchor";
+
+pub fn load_audit_anchor() -> Result<Option<String>, String> {
+    with_storage(|storage| load(storage, AUDIT_ANCHOR_KEY))
+}
+
+pub fn store_audit_anchor(anchor: &str) -> Result<(), String> {
+    store(AUDIT_ANCHOR_KEY, anchor)
+}
+
+// ============================================
+// Git Credentials
+// ============================================
+
+/// Secure storage entry for a git host's personal access token
+fn git_token_key(host: &str) -> String {
+    format!("git_token_{}", host.trim().to_lowercase())
+}
+
+/// Entry listing the hosts with a stored token, since the keyring can't
+/// enumerate its entries
+const GIT_TOKEN_HOSTS: &str = "git_token_hosts";
+
+fn git_token_hosts(storage: &SecureStorage) -> Result<Vec<String>, String> {
+    Ok(load(storage, GIT_TOKEN_HOSTS)?
+        .map(|json| serde_json::from_str(&json).unwrap_or_default())
+        .unwrap_or_default())
+}
+
+fn save_git_token_hosts(storage: &SecureStorage, hosts: &[String]) -> Result<(), String> {
+    if hosts.is_empty() {
+        return storage.delete(GIT_TOKEN_HOSTS);
+    }
+    let json = serde_json::to_string(hosts).map_err(|e| e.to_string())?;
+    storage.set(GIT_TOKEN_HOSTS, &json).map(|_| ())
+}
+
+/// Entries of every stored git token
+pub fn git_token_keys(storage: &SecureStorage) -> Result<Vec<String>, String> {
+    Ok(git_token_hosts(storage)?.iter().map(|host| git_token_key(host)).collect())
+}
+
+/// Load the access token stored for a git host (e.g. `github.com`).
+/// Not exposed as a command so tokens never travel back to the webview.
+pub fn load_git_token(host: &str) -> Result<Option<String>, String> {
+    with_storage(|storage| Ok(load(storage, &git_token_key(host))?.filter(|token| !token.is_empty())))
+}
+
+#[tauri::command]
+pub fn set_git_token(host: String, token: String) -> Result<(), String> {
+    if host.trim().is_empty() || token.is_empty() {
+        return Err("Host and token cannot be empty".to_string());
+    }
+    with_storage(|storage| {
+        storage.set(&git_token_key(&host), &token)?;
+
+        let host = host.trim().to_lowercase();
+        let mut hosts = git_token_hosts(storage)?;
+        if !hosts.contains(&host) {
+            hosts.push(host);
+            save_git_token_hosts(storage, &hosts)?;
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn has_git_token(host: String) -> Result<bool, String> {
+    load_git_token(&host).map(|token| token.is_some())
+}
+
+#[tauri::command]
+pub fn delete_git_token(host: String) -> Result<(), String> {
+    with_storage(|storage| {
+        storage.delete(&git_token_key(&host))?;
+
+        let host = host.trim().to_lowercase();
+        let mut hosts = git_token_hosts(storage)?;
+        hosts.retain(|h| h != &host);
+        save_git_token_hosts(storage, &hosts)
+    })
+}
+
+/// Remove the token of every host one was stored for
+pub fn clear_git_tokens(storage: &SecureStorage) -> Result<(), String> {
+    for key in git_token_keys(storage)? {
+        storage.delete(&key)?;
+    }
+    save_git_token_hosts(storage, &[])
 }
 
 // ============================================
 // Clear All Credentials (Logout)
 // ============================================
 
+/// Remove every credential except `WORKSPACE_DB_KEY`; see `SecureStorage::clear_all`
 #[tauri::command]
 pub fn clear_all_credentials() -> Result<(), String> {
-    let keys = [
-        "auth_token",
-        "refresh_token",
-        "user_data",
-        "proxy_token",
-        "api_key_openrouter",
-        "api_key_openai",
-        "api_key_anthropic",
-        "api_key_deepseek",
-        "api_key_google",
-    ];
-    
-    for key in keys {
-        let entry = Entry::new(SERVICE, key).map_err(|e| e.to_string())?;
-        let _ = entry.delete_password(); // Ignore errors for non-existent keys
-    }
-    
-    Ok(())
+    with_storage(|storage| storage.clear_all())
 }
 
 // ============================================
@@ -237,10 +307,5 @@ pub fn clear_all_credentials() -> Result<(), String> {
 
 #[tauri::command]
 pub fn is_authenticated() -> Result<bool, String> {
-    let entry = Entry::new(SERVICE, "auth_token").map_err(|e| e.to_string())?;
-    match entry.get_password() {
-        Ok(token) => Ok(!token.is_empty()),
-        Err(keyring::Error::NoEntry) => Ok(false),
-        Err(e) => Err(e.to_string()),
-    }
+    with_storage(|storage| Ok(load(storage, "auth_token")?.is_some_and(|token| !token.is_empty())))
 }
@@ -6,10 +6,14 @@
 // - Spec document generation
 // - Export to various formats
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::performance::{shared_render_cache, RenderCache};
+
 // ============================================
 // Types
 // ============================================
@@ -55,6 +59,8 @@ pub struct Canvas {
     pub grid_size: f64,
     pub components: Vec<CanvasComponent>,
     pub connections: Vec<Connection>,
+    #[serde(default)]
+    pub groups: Vec<ComponentGroup>,
 }
 
 impl Default for Canvas {
@@ -70,10 +76,28 @@ impl Default for Canvas {
             grid_size: 20.0,
             components: Vec::new(),
             connections: Vec::new(),
+            groups: Vec::new(),
         }
     }
 }
 
+impl Canvas {
+    /// The group `component_id` belongs to; a component is in at most one
+    pub fn group_of(&self, component_id: &str) -> Option<&ComponentGroup> {
+        self.groups.iter().find(|g| g.component_ids.iter().any(|id| id == component_id))
+    }
+
+    /// Hidden if the component or its group is
+    pub fn is_visible(&self, component: &CanvasComponent) -> bool {
+        component.visible && self.group_of(&component.id).is_none_or(|g| g.visible)
+    }
+
+    /// Locked if the component or its group is
+    pub fn is_locked(&self, component: &CanvasComponent) -> bool {
+        component.locked || self.group_of(&component.id).is_some_and(|g| g.locked)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanvasComponent {
     pub id: String,
@@ -254,6 +278,19 @@ impl Default for ConnectionStyle {
     }
 }
 
+/// Components that move, lock and hide together. Locking or hiding a group
+/// leaves its members' own flags alone, so ungrouping restores them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentGroup {
+    pub id: String,
+    pub name: String,
+    pub component_ids: Vec<String>,
+    /// Frames are drawn as a titled outline around their members
+    pub frame: bool,
+    pub locked: bool,
+    pub visible: bool,
+}
+
 // ============================================
 // Component Library
 // ============================================
@@ -558,15 +595,24 @@ impl ComponentLibrary {
 
 pub struct SpecBuilder {
     pub library: ComponentLibrary,
+    export_cache: Arc<RenderCache>,
 }
 
 impl SpecBuilder {
     pub fn new() -> Self {
         Self {
             library: ComponentLibrary::default_library(),
+            export_cache: shared_render_cache(),
         }
     }
 
+    /// Use a dedicated cache for exports instead of the shared one.
+    #[cfg(test)]
+    pub fn with_export_cache(mut self, cache: Arc<RenderCache>) -> Self {
+        self.export_cache = cache;
+        self
+    }
+
     // ============================================
     // Document Operations
     // ============================================
@@ -704,6 +750,112 @@ impl SpecBuilder {
         canvas.connections.retain(|c| {
             c.from_component != component_id && c.to_component != component_id
         });
+        remove_from_groups(canvas, component_id);
+
+        Ok(())
+    }
+
+    // ============================================
+    // Group Operations
+    // ============================================
+
+    /// Group components so they move, lock and hide together. Returns the
+    /// group id. A component can only be in one group.
+    pub fn group_components(
+        &self,
+        canvas: &mut Canvas,
+        component_ids: &[String],
+        name: Option<&str>,
+        frame: bool,
+    ) -> Result<String, String> {
+        if component_ids.is_empty() {
+            return Err("No components to group".to_string());
+        }
+
+        let mut members: Vec<String> = Vec::new();
+        for id in component_ids {
+            if !canvas.components.iter().any(|c| &c.id == id) {
+                return Err(format!("Component not found: {}", id));
+            }
+            if let Some(group) = canvas.group_of(id) {
+                return Err(format!("Component {} is already in group {}", id, group.name));
+            }
+            if !members.contains(id) {
+                members.push(id.clone());
+            }
+        }
+
+        let kind = if frame { "Frame" } else { "Group" };
+        let group = ComponentGroup {
+            id: Uuid::new_v4().to_string(),
+            name: name
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| format!("{} {}", kind, canvas.groups.len() + 1)),
+            component_ids: members,
+            frame,
+            locked: false,
+            visible: true,
+        };
+
+        let id = group.id.clone();
+        canvas.groups.push(group);
+        Ok(id)
+    }
+
+    /// Dissolve a group, returning its members
+    pub fn ungroup(&self, canvas: &mut Canvas, group_id: &str) -> Result<Vec<String>, String> {
+        let index = canvas.groups.iter()
+            .position(|g| g.id == group_id)
+            .ok_or_else(|| format!("Group not found: {}", group_id))?;
+
+        Ok(canvas.groups.remove(index).component_ids)
+    }
+
+    /// Translate every member of a group by (dx, dy), snapping the offset to
+    /// the grid so members stay aligned with it. Members locked on their own
+    /// stay put.
+    pub fn move_group(&self, canvas: &mut Canvas, group_id: &str, dx: f64, dy: f64) -> Result<(), String> {
+        let group = canvas.groups.iter()
+            .find(|g| g.id == group_id)
+            .ok_or_else(|| format!("Group not found: {}", group_id))?;
+        if group.locked {
+            return Err(format!("Group is locked: {}", group.name));
+        }
+
+        let (dx, dy) = if canvas.snap_to_grid {
+            (
+                (dx / canvas.grid_size).round() * canvas.grid_size,
+                (dy / canvas.grid_size).round() * canvas.grid_size,
+            )
+        } else {
+            (dx, dy)
+        };
+
+        let members = group.component_ids.clone();
+        for component in canvas.components.iter_mut().filter(|c| members.contains(&c.id) && !c.locked) {
+            component.x += dx;
+            component.y += dy;
+        }
+        Ok(())
+    }
+
+    pub fn update_group(&self, canvas: &mut Canvas, group_id: &str, updates: GroupUpdate) -> Result<(), String> {
+        let group = canvas.groups.iter_mut()
+            .find(|g| g.id == group_id)
+            .ok_or_else(|| format!("Group not found: {}", group_id))?;
+
+        if let Some(name) = updates.name {
+            group.name = name;
+        }
+        if let Some(frame) = updates.frame {
+            group.frame = frame;
+        }
+        if let Some(locked) = updates.locked {
+            group.locked = locked;
+        }
+        if let Some(visible) = updates.visible {
+            group.visible = visible;
+        }
 
         Ok(())
     }
@@ -758,6 +910,36 @@ impl SpecBuilder {
     // Export Operations
     // ============================================
 
+    /// Export `doc` as markdown, json, svg or base64-encoded png, reusing the
+    /// previous output while the document is unchanged.
+    pub fn export(&self, doc: &SpecDocument, format: &str) -> Result<String, String> {
+        let format = match format.to_lowercase().as_str() {
+            "markdown" | "md" => "markdown",
+            "json" => "json",
+            "svg" => "svg",
+            "png" => "png",
+            "mermaid" | "mmd" => "mermaid",
+            "drawio" | "draw.io" => "drawio",
+            _ => return Err(format!("Invalid export format: {}", format)),
+        };
+        let doc_json = serde_json::to_vec(doc)
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+        let key = RenderCache::key(&[format.as_bytes(), &doc_json]);
+
+        self.export_cache.get_or_insert_with(&key, || {
+            let content = match format {
+                "markdown" => self.export_to_markdown(doc),
+                "json" => self.export_to_json(doc)?,
+                "svg" => self.export_to_svg(doc),
+                "mermaid" => self.export_to_mermaid(doc),
+                "drawio" => self.export_to_drawio(doc),
+                // PNG is returned base64-encoded so it can cross the IPC boundary as a string
+                _ => BASE64.encode(self.export_to_png(doc, 2.0)?),
+            };
+            Ok((content, Vec::new()))
+        })
+    }
+
     pub fn export_to_markdown(&self, doc: &SpecDocument) -> String {
         let mut md = String::new();
 
@@ -767,20 +949,34 @@ impl SpecBuilder {
             md.push_str(&format!("{}\n\n", desc));
         }
 
-        // Group components by type
-        let mut sections: HashMap<String, Vec<&CanvasComponent>> = HashMap::new();
+        // Group components by type, in reading order within each section
+        let mut sections: Vec<(usize, &'static str, Vec<&CanvasComponent>)> = Vec::new();
         for component in &doc.canvas.components {
-            let type_name = format!("{:?}", component.component_type);
-            sections.entry(type_name).or_default().push(component);
+            let (rank, heading) = section_for(&component.component_type);
+            match sections.iter_mut().find(|(r, _, _)| *r == rank) {
+                Some((_, _, components)) => components.push(component),
+                None => sections.push((rank, heading, vec![component])),
+            }
         }
+        sections.sort_by_key(|(rank, _, _)| *rank);
+
+        for (_, heading, mut components) in sections {
+            components.sort_by(|a, b| {
+                a.y.partial_cmp(&b.y)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal))
+            });
 
-        // Output each section
-        for (type_name, components) in sections {
-            md.push_str(&format!("## {}\n\n", type_name));
+            md.push_str(&format!("## {}\n\n", heading));
             for component in components {
                 if let Some(title) = &component.properties.title {
                     md.push_str(&format!("### {}\n\n", title));
                 }
+                if matches!(component.component_type, ComponentType::UserStory | ComponentType::Requirement) {
+                    if let Some(meta) = metadata_line(&component.properties) {
+                        md.push_str(&format!("> {}\n\n", meta));
+                    }
+                }
                 if let Some(content) = &component.properties.content {
                     md.push_str(&format!("{}\n\n", content));
                 }
@@ -793,6 +989,41 @@ impl SpecBuilder {
             }
         }
 
+        // Relationships
+        let relationships: Vec<String> = doc.canvas.connections.iter()
+            .filter_map(|connection| {
+                let from = doc.canvas.components.iter().find(|c| c.id == connection.from_component)?;
+                let to = doc.canvas.components.iter().find(|c| c.id == connection.to_component)?;
+                let kind = connection_type_name(&connection.connection_type);
+                Some(match &connection.label {
+                    Some(label) => format!("- {} → {} ({}: {})", display_name(from), display_name(to), kind, label),
+                    None => format!("- {} → {} ({})", display_name(from), display_name(to), kind),
+                })
+            })
+            .collect();
+        if !relationships.is_empty() {
+            md.push_str(&format!("## {}\n\n", RELATIONSHIPS_HEADING));
+            for line in relationships {
+                md.push_str(&line);
+                md.push('\n');
+            }
+            md.push('\n');
+        }
+
+        // Groups, with members named like relationship endpoints
+        if !doc.canvas.groups.is_empty() {
+            md.push_str(&format!("## {}\n\n", GROUPS_HEADING));
+            for group in &doc.canvas.groups {
+                let members: Vec<String> = group.component_ids.iter()
+                    .filter_map(|id| doc.canvas.components.iter().find(|c| &c.id == id))
+                    .map(display_name)
+                    .collect();
+                let kind = if group.frame { "frame" } else { "group" };
+                md.push_str(&format!("- {} ({}): {}\n", group.name, kind, members.join(", ")));
+            }
+            md.push('\n');
+        }
+
         md
     }
 
@@ -800,17 +1031,2635 @@ impl SpecBuilder {
         serde_json::to_string_pretty(doc)
             .map_err(|e| format!("Failed to serialize: {}", e))
     }
+
+    /// Rebuild a spec document from Markdown produced by `export_to_markdown`.
+    ///
+    /// `##` headings select the component type and `###` headings start a new
+    /// component. Sections that don't name a known type become `Note`s so no
+    /// content is lost. Components are placed with the grid auto-layout.
+    pub fn import_from_markdown(&self, md: &str) -> SpecDocument {
+        let mut name: Option<String> = None;
+        let mut description_lines: Vec<String> = Vec::new();
+        let mut drafts: Vec<ImportedComponent> = Vec::new();
+        let mut section: Option<(ComponentType, Option<String>)> = None;
+        let mut in_relationships = false;
+        let mut relationships: Vec<(String, String, ConnectionType, Option<String>)> = Vec::new();
+        let mut in_groups = false;
+        let mut groups: Vec<(String, bool, Vec<String>)> = Vec::new();
+
+        for line in md.lines() {
+            let trimmed = line.trim_end();
+
+            if in_relationships && !trimmed.starts_with('#') {
+                if let Some(relationship) = trimmed.strip_prefix("- ").and_then(parse_relationship) {
+                    relationships.push(relationship);
+                }
+                continue;
+            }
+            if in_groups && !trimmed.starts_with('#') {
+                if let Some(group) = trimmed.strip_prefix("- ").and_then(parse_group) {
+                    groups.push(group);
+                }
+                continue;
+            }
+
+            if let Some(title) = trimmed.strip_prefix("### ") {
+                let (component_type, unknown) = section.clone()
+                    .unwrap_or((ComponentType::Note, None));
+                drafts.push(ImportedComponent::new(component_type, unknown, Some(title.trim().to_string())));
+            } else if let Some(heading) = trimmed.strip_prefix("## ") {
+                // A new section always ends the current component
+                if let Some(draft) = drafts.last_mut() {
+                    draft.closed = true;
+                }
+                let heading = heading.trim();
+                in_relationships = heading == RELATIONSHIPS_HEADING;
+                in_groups = heading == GROUPS_HEADING;
+                if in_relationships || in_groups {
+                    continue;
+                }
+                section = Some(match component_type_from_name(heading) {
+                    Some(t) => (t, None),
+                    None => (ComponentType::Note, Some(heading.to_string())),
+                });
+            } else if let Some(title) = trimmed.strip_prefix("# ") {
+                if name.is_none() {
+                    name = Some(title.trim().to_string());
+                }
+            } else if section.is_none() {
+                description_lines.push(trimmed.to_string());
+            } else if let Some(meta) = trimmed.strip_prefix("> ") {
+                if let Some(draft) = drafts.last_mut() {
+                    draft.metadata = parse_metadata_line(meta);
+                }
+            } else {
+                let accepted = drafts.last().map(|d| d.accepts(trimmed)).unwrap_or(false);
+                if !accepted {
+                    if trimmed.trim().is_empty() {
+                        continue;
+                    }
+                    // Content outside a ### block starts an untitled component for the section
+                    let (component_type, unknown) = section.clone().unwrap();
+                    drafts.push(ImportedComponent::new(component_type, unknown, None));
+                }
+                if let Some(draft) = drafts.last_mut() {
+                    draft.push_line(trimmed);
+                }
+            }
+        }
+
+        let description = description_lines.join("\n").trim().to_string();
+        let mut doc = self.create_document(
+            name.as_deref().unwrap_or("Imported Spec"),
+            if description.is_empty() { None } else { Some(description.as_str()) },
+        );
+
+        for draft in drafts {
+            let component = self.build_imported_component(draft, doc.canvas.components.len() as i32);
+            doc.canvas.components.push(component);
+        }
+
+        for (from, to, connection_type, label) in relationships {
+            let find = |name: &str| doc.canvas.components.iter()
+                .find(|c| display_name(c) == name)
+                .map(|c| c.id.clone());
+            if let (Some(from), Some(to)) = (find(&from), find(&to)) {
+                if let Ok(id) = self.add_connection(&mut doc.canvas, &from, Anchor::Right, &to, Anchor::Left, connection_type) {
+                    if let Some(connection) = doc.canvas.connections.iter_mut().find(|c| c.id == id) {
+                        connection.label = label;
+                    }
+                }
+            }
+        }
+        for (name, frame, members) in groups {
+            let ids: Vec<String> = members.iter()
+                .filter_map(|member| doc.canvas.components.iter().find(|c| &display_name(c) == member))
+                .map(|c| c.id.clone())
+                .collect();
+            // Members that are missing or already grouped are dropped with the group
+            let _ = self.group_components(&mut doc.canvas, &ids, Some(&name), frame);
+        }
+        self.auto_layout(&mut doc.canvas, LayoutStrategy::Grid);
+
+        doc
+    }
+
+    fn build_imported_component(&self, draft: ImportedComponent, z_index: i32) -> CanvasComponent {
+        let template = self.find_template_by_type(&draft.component_type);
+
+        let mut properties = template
+            .map(|t| t.default_properties.clone())
+            .unwrap_or_default();
+        properties.title = draft.title;
+        let content = draft.content.join("\n").trim().to_string();
+        properties.content = if content.is_empty() { None } else { Some(content) };
+        properties.items = if draft.items.is_empty() { None } else { Some(draft.items) };
+        properties.priority = draft.metadata.priority;
+        properties.status = draft.metadata.status;
+        properties.assignee = draft.metadata.assignee;
+        properties.due_date = draft.metadata.due_date;
+        if let Some(section) = draft.unknown_section {
+            properties.custom.insert("section".to_string(), serde_json::Value::String(section));
+        }
+
+        CanvasComponent {
+            id: Uuid::new_v4().to_string(),
+            component_type: draft.component_type,
+            x: 0.0,
+            y: 0.0,
+            width: template.map(|t| t.default_width).unwrap_or(300.0),
+            height: template.map(|t| t.default_height).unwrap_or(150.0),
+            rotation: 0.0,
+            z_index,
+            locked: false,
+            visible: true,
+            properties,
+            style: template.map(|t| t.default_style.clone()).unwrap_or_default(),
+        }
+    }
+
+    fn find_template_by_type(&self, component_type: &ComponentType) -> Option<&ComponentTemplate> {
+        self.library.categories.iter()
+            .flat_map(|c| c.components.iter())
+            .find(|t| std::mem::discriminant(&t.component_type) == std::mem::discriminant(component_type))
+    }
+
+    // ============================================
+    // Validation
+    // ============================================
+
+    /// Check a document's integrity. Errors make the spec unusable (broken
+    /// references, missing requirement titles); warnings flag likely mistakes.
+    pub fn validate(&self, doc: &SpecDocument) -> Vec<SpecIssue> {
+        let canvas = &doc.canvas;
+        let mut issues = Vec::new();
+
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for component in &canvas.components {
+            *seen.entry(component.id.as_str()).or_default() += 1;
+        }
+        let mut duplicates: Vec<&str> = seen.iter()
+            .filter(|(_, count)| **count > 1)
+            .map(|(id, _)| *id)
+            .collect();
+        duplicates.sort_unstable();
+        for id in duplicates {
+            issues.push(SpecIssue::error(
+                "duplicate_component_id",
+                format!("Component id {} is used {} times", id, seen[id]),
+                Some(id.to_string()),
+                None,
+            ));
+        }
+
+        for component in &canvas.components {
+            let is_requirement = matches!(
+                component.component_type,
+                ComponentType::UserStory | ComponentType::Requirement
+            );
+            if !is_requirement {
+                continue;
+            }
+
+            let label = format!("{:?}", component.component_type);
+            if is_blank(&component.properties.title) {
+                issues.push(SpecIssue::error(
+                    "missing_title",
+                    format!("{} is missing a title", label),
+                    Some(component.id.clone()),
+                    None,
+                ));
+            }
+            if is_blank(&component.properties.content) {
+                issues.push(SpecIssue::error(
+                    "missing_content",
+                    format!("{} is missing a description", label),
+                    Some(component.id.clone()),
+                    None,
+                ));
+            }
+        }
+
+        for connection in &canvas.connections {
+            let from = canvas.components.iter().find(|c| c.id == connection.from_component);
+            let to = canvas.components.iter().find(|c| c.id == connection.to_component);
+
+            if from.is_none() {
+                issues.push(SpecIssue::error(
+                    "dangling_connection",
+                    format!("Connection source {} does not exist", connection.from_component),
+                    None,
+                    Some(connection.id.clone()),
+                ));
+            }
+            if to.is_none() {
+                issues.push(SpecIssue::error(
+                    "dangling_connection",
+                    format!("Connection target {} does not exist", connection.to_component),
+                    None,
+                    Some(connection.id.clone()),
+                ));
+            }
+
+            // Anchors are only drawn on visible, anchorable components
+            for (end, component, anchor) in [
+                ("source", from, &connection.from_anchor),
+                ("target", to, &connection.to_anchor),
+            ] {
+                let component = match component {
+                    Some(c) => c,
+                    None => continue,
+                };
+                if !has_anchors(&component.component_type) {
+                    issues.push(SpecIssue::error(
+                        "invalid_anchor",
+                        format!("Connection {} {:?} anchor is on a {:?}, which has no anchors", end, anchor, component.component_type),
+                        Some(component.id.clone()),
+                        Some(connection.id.clone()),
+                    ));
+                } else if !component.visible {
+                    issues.push(SpecIssue::warning(
+                        "hidden_anchor",
+                        format!("Connection {} {:?} anchor is on a hidden component", end, anchor),
+                        Some(component.id.clone()),
+                        Some(connection.id.clone()),
+                    ));
+                }
+            }
+
+            if connection.from_component == connection.to_component {
+                issues.push(SpecIssue::warning(
+                    "self_connection",
+                    "Connection starts and ends on the same component".to_string(),
+                    Some(connection.from_component.clone()),
+                    Some(connection.id.clone()),
+                ));
+            }
+        }
+
+        issues
+    }
+
+    // ============================================
+    // Layout Operations
+    // ============================================
+
+    /// Reposition unlocked components so nothing overlaps, returning the bounds of
+    /// the whole canvas content so the UI can fit it to the view.
+    ///
+    /// Locked components keep their position and are treated as obstacles.
+    pub fn auto_layout(&self, canvas: &mut Canvas, strategy: LayoutStrategy) -> BoundingBox {
+        let gap = canvas.grid_size.max(1.0);
+        let snap = canvas.snap_to_grid;
+        let grid_size = canvas.grid_size;
+
+        let mut placed: Vec<BoundingBox> = canvas.components.iter()
+            .filter(|c| canvas.is_locked(c))
+            .map(BoundingBox::of)
+            .collect();
+
+        match strategy {
+            LayoutStrategy::Grid => {
+                let movable: Vec<usize> = (0..canvas.components.len())
+                    .filter(|&i| !canvas.is_locked(&canvas.components[i]))
+                    .collect();
+                let cell_width = movable.iter()
+                    .map(|&i| canvas.components[i].width)
+                    .fold(0.0, f64::max) + gap;
+                let columns = (((canvas.width - gap) / cell_width).floor() as usize).max(1);
+
+                let mut y = gap;
+                for row in movable.chunks(columns) {
+                    let row_height = row.iter()
+                        .map(|&i| canvas.components[i].height)
+                        .fold(0.0, f64::max);
+                    for (col, &i) in row.iter().enumerate() {
+                        let component = &mut canvas.components[i];
+                        let x = gap + col as f64 * cell_width;
+                        let (x, y) = find_free_position(component, x, y, &placed, gap, snap, grid_size, true);
+                        component.x = x;
+                        component.y = y;
+                        placed.push(BoundingBox::of(component));
+                    }
+                    y += row_height + gap;
+                }
+            }
+            LayoutStrategy::LayeredByConnections => {
+                let layers = layer_components(canvas);
+
+                let mut x = gap;
+                for layer in &layers {
+                    let layer_width = layer.iter()
+                        .map(|&i| canvas.components[i].width)
+                        .fold(0.0, f64::max);
+                    let mut y = gap;
+                    for &i in layer {
+                        let component = &mut canvas.components[i];
+                        let (cx, cy) = find_free_position(component, x, y, &placed, gap, snap, grid_size, false);
+                        component.x = cx;
+                        component.y = cy;
+                        placed.push(BoundingBox::of(component));
+                        y = cy + component.height + gap;
+                    }
+                    x += layer_width + gap * 3.0;
+                }
+            }
+        }
+
+        BoundingBox::enclosing(canvas.components.iter().map(BoundingBox::of))
+    }
+
+    /// Line up components on one edge or center. Locked components (their own
+    /// lock or their group's) stay put and, when selected, are the line the
+    /// others align to; otherwise the outermost edge is used, snapped to the
+    /// grid if enabled. Returns the selected components as updated.
+    pub fn align(
+        &self,
+        canvas: &mut Canvas,
+        component_ids: &[String],
+        edge: AlignEdge,
+    ) -> Result<Vec<CanvasComponent>, String> {
+        let indices = component_indices(canvas, component_ids)?;
+        let (locked, movable): (Vec<usize>, Vec<usize>) = indices.iter()
+            .partition(|&&i| canvas.is_locked(&canvas.components[i]));
+
+        let reference = if locked.is_empty() { &movable } else { &locked };
+        let edges = reference.iter().map(|&i| edge.position(&canvas.components[i]));
+        let target = match edge {
+            AlignEdge::Left | AlignEdge::Top => edges.fold(f64::INFINITY, f64::min),
+            AlignEdge::Right | AlignEdge::Bottom => edges.fold(f64::NEG_INFINITY, f64::max),
+            // Center of the span the reference components cover
+            AlignEdge::CenterX | AlignEdge::CenterY => {
+                let (low, high) = reference.iter()
+                    .map(|&i| edge.span(&canvas.components[i]))
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), (a, b)| (low.min(a), high.max(b)));
+                (low + high) / 2.0
+            }
+        };
+        let target = if locked.is_empty() { snap(canvas, target) } else { target };
+
+        for &i in &movable {
+            edge.move_to(&mut canvas.components[i], target);
+        }
+        Ok(indices.iter().map(|&i| canvas.components[i].clone()).collect())
+    }
+
+    /// Space components evenly along an axis. The outermost components and
+    /// any locked ones stay put; the rest are spread so the gaps between
+    /// neighbours are equal. Returns the selected components as updated.
+    pub fn distribute(
+        &self,
+        canvas: &mut Canvas,
+        component_ids: &[String],
+        axis: Axis,
+    ) -> Result<Vec<CanvasComponent>, String> {
+        let indices = component_indices(canvas, component_ids)?;
+
+        let mut ordered = indices.clone();
+        ordered.sort_by(|&a, &b| {
+            axis.start(&canvas.components[a])
+                .partial_cmp(&axis.start(&canvas.components[b]))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        // Fixed points: both ends plus every locked component in between
+        let anchors: Vec<usize> = (0..ordered.len())
+            .filter(|&k| k == 0 || k + 1 == ordered.len() || canvas.is_locked(&canvas.components[ordered[k]]))
+            .collect();
+
+        for pair in anchors.windows(2) {
+            let (first, last) = (pair[0], pair[1]);
+            if last - first < 2 {
+                continue;
+            }
+            let start = axis.start(&canvas.components[ordered[first]]) + axis.size(&canvas.components[ordered[first]]);
+            let end = axis.start(&canvas.components[ordered[last]]);
+            let between = &ordered[first + 1..last];
+            let occupied: f64 = between.iter().map(|&i| axis.size(&canvas.components[i])).sum();
+            let gap = (end - start - occupied) / (between.len() + 1) as f64;
+
+            let mut position = start + gap;
+            for &i in between {
+                let size = axis.size(&canvas.components[i]);
+                let snapped = snap(canvas, position);
+                axis.set_start(&mut canvas.components[i], snapped);
+                position += size + gap;
+            }
+        }
+
+        Ok(indices.iter().map(|&i| canvas.components[i].clone()).collect())
+    }
+
+    /// Render the canvas as a standalone SVG document.
+    ///
+    /// Hidden components (and connections touching them) are skipped, and
+    /// components are painted in `z_index` order. Group members are painted
+    /// together inside a `<g>`, at the depth of their lowest member, and
+    /// frames get a titled outline behind them.
+    pub fn export_to_svg(&self, doc: &SpecDocument) -> String {
+        let canvas = &doc.canvas;
+        let mut svg = String::new();
+
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"Inter, Helvetica, Arial, sans-serif\">\n",
+            w = canvas.width,
+            h = canvas.height,
+        ));
+        svg.push_str(&format!("  <title>{}</title>\n", escape_xml(&doc.name)));
+
+        let components = paint_order(canvas);
+        let connections = visible_connections(canvas, &components);
+
+        // Shared definitions: drop shadow, per-component clip paths, per-connection arrowheads
+        svg.push_str("  <defs>\n");
+        svg.push_str("    <filter id=\"shadow\" x=\"-10%\" y=\"-10%\" width=\"130%\" height=\"130%\">\n");
+        svg.push_str("      <feDropShadow dx=\"0\" dy=\"2\" stdDeviation=\"3\" flood-opacity=\"0.15\"/>\n");
+        svg.push_str("    </filter>\n");
+        for (i, component) in components.iter().enumerate() {
+            svg.push_str(&format!(
+                "    <clipPath id=\"clip-{}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>\n",
+                i, component.x, component.y, component.width, component.height,
+            ));
+        }
+        for (i, connection) in connections.iter().enumerate() {
+            if has_arrowhead(&connection.connection_type) {
+                let size = connection.style.arrow_size;
+                svg.push_str(&format!(
+                    "    <marker id=\"arrow-{}\" markerWidth=\"{s}\" markerHeight=\"{s}\" refX=\"{s}\" refY=\"{half}\" orient=\"auto\" markerUnits=\"userSpaceOnUse\"><path d=\"M0,0 L{s},{half} L0,{s} z\" fill=\"{}\"/></marker>\n",
+                    i,
+                    escape_xml(&connection.style.color),
+                    s = size,
+                    half = size / 2.0,
+                ));
+            }
+        }
+        svg.push_str("  </defs>\n");
+
+        svg.push_str(&format!("  <rect width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>\n", canvas.width, canvas.height));
+
+        let mut open_group: Option<&str> = None;
+        for (i, component) in components.iter().enumerate() {
+            let group = canvas.group_of(&component.id);
+            if open_group != group.map(|g| g.id.as_str()) {
+                if open_group.take().is_some() {
+                    svg.push_str("  </g>\n");
+                }
+                if let Some(group) = group {
+                    let members = components.iter()
+                        .filter(|c| group.component_ids.contains(&c.id))
+                        .map(|&c| BoundingBox::of(c));
+                    svg.push_str(&render_group_open_svg(group, BoundingBox::enclosing(members)));
+                    open_group = Some(group.id.as_str());
+                }
+            }
+            svg.push_str(&render_component_svg(component, i));
+        }
+        if open_group.is_some() {
+            svg.push_str("  </g>\n");
+        }
+
+        for (i, connection) in connections.iter().enumerate() {
+            let from = components.iter().find(|c| c.id == connection.from_component);
+            let to = components.iter().find(|c| c.id == connection.to_component);
+            if let (Some(from), Some(to)) = (from, to) {
+                svg.push_str(&render_connection_svg(connection, from, to, i));
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Render the canvas as a Mermaid flowchart.
+    ///
+    /// Components become nodes shaped by their type, groups become subgraphs
+    /// and connections become edges. Mermaid has no anchors, so the chart runs
+    /// left-to-right when most connections leave a side and top-down otherwise.
+    pub fn export_to_mermaid(&self, doc: &SpecDocument) -> String {
+        let canvas = &doc.canvas;
+        let components = paint_order(canvas);
+        let connections = visible_connections(canvas, &components);
+
+        // Component ids are UUIDs; short positional ids keep the source readable
+        let node_id = |id: &str| {
+            components.iter()
+                .position(|c| c.id == id)
+                .map(|i| format!("n{}", i))
+                .unwrap_or_default()
+        };
+
+        let sideways = connections.iter()
+            .filter(|conn| matches!(conn.from_anchor, Anchor::Left | Anchor::Right))
+            .count();
+        let direction = if sideways * 2 > connections.len() { "LR" } else { "TD" };
+
+        let mut out = format!("---\ntitle: {}\n---\nflowchart {}\n", yaml_string(&doc.name), direction);
+
+        let mut open_group: Option<&str> = None;
+        for (i, component) in components.iter().enumerate() {
+            let group = canvas.group_of(&component.id);
+            if open_group != group.map(|g| g.id.as_str()) {
+                if open_group.take().is_some() {
+                    out.push_str("    end\n");
+                }
+                if let Some(group) = group {
+                    let index = canvas.groups.iter().position(|g| g.id == group.id).unwrap_or_default();
+                    out.push_str(&format!("    subgraph g{}[\"{}\"]\n", index, mermaid_text(&group.name)));
+                    open_group = Some(group.id.as_str());
+                }
+            }
+            let indent = if open_group.is_some() { "        " } else { "    " };
+            let (open, close) = mermaid_shape(&component.component_type);
+            out.push_str(&format!(
+                "{}n{}{}\"{}\"{}\n",
+                indent, i, open, mermaid_text(&node_label(component)), close,
+            ));
+        }
+        if open_group.is_some() {
+            out.push_str("    end\n");
+        }
+
+        for connection in &connections {
+            let link = match connection.connection_type {
+                ConnectionType::Arrow => "-->",
+                ConnectionType::Line => "---",
+                ConnectionType::Dashed => "-.-",
+                ConnectionType::Dependency => "-.->",
+                ConnectionType::Flow => "==>",
+            };
+            let label = connection.label.as_deref()
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| format!("|\"{}\"|", mermaid_text(l)))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "    {} {}{} {}\n",
+                node_id(&connection.from_component), link, label, node_id(&connection.to_component),
+            ));
+        }
+
+        out
+    }
+
+    /// Render the canvas as an uncompressed draw.io (mxGraph) file.
+    ///
+    /// Components keep their geometry and colours and get a shape for their
+    /// type; anchors become the edges' exit and entry points. Groups are
+    /// emitted as group cells (frames as dashed containers) holding their
+    /// members, whose geometry is then relative to the group.
+    pub fn export_to_drawio(&self, doc: &SpecDocument) -> String {
+        let canvas = &doc.canvas;
+        let components = paint_order(canvas);
+        let connections = visible_connections(canvas, &components);
+
+        let mut out = String::new();
+        out.push_str("<mxfile host=\"SmartSpec\">\n");
+        out.push_str(&format!(
+            "  <diagram id=\"{}\" name=\"{}\">\n",
+            escape_xml(&doc.id),
+            escape_xml(&doc.name),
+        ));
+        out.push_str(&format!(
+            "    <mxGraphModel grid=\"{}\" gridSize=\"{}\" guides=\"1\" tooltips=\"1\" connect=\"1\" arrows=\"1\" fold=\"1\" page=\"1\" pageScale=\"1\" pageWidth=\"{}\" pageHeight=\"{}\" math=\"0\" shadow=\"0\">\n",
+            u8::from(canvas.snap_to_grid),
+            canvas.grid_size,
+            canvas.width,
+            canvas.height,
+        ));
+        out.push_str("      <root>\n");
+        out.push_str("        <mxCell id=\"0\"/>\n");
+        out.push_str("        <mxCell id=\"1\" parent=\"0\"/>\n");
+
+        let mut open_group: Option<(&str, BoundingBox)> = None;
+        for component in &components {
+            let group = canvas.group_of(&component.id);
+            if open_group.map(|(id, _)| id) != group.map(|g| g.id.as_str()) {
+                open_group = group.map(|group| {
+                    let members = components.iter()
+                        .filter(|c| group.component_ids.contains(&c.id))
+                        .map(|&c| BoundingBox::of(c));
+                    let bounds = BoundingBox::enclosing(members);
+                    let bounds = if group.frame { bounds.padded(FRAME_PADDING) } else { bounds };
+                    out.push_str(&render_group_drawio(group, bounds));
+                    (group.id.as_str(), bounds)
+                });
+            }
+            out.push_str(&render_component_drawio(component, open_group));
+        }
+
+        for connection in &connections {
+            out.push_str(&render_connection_drawio(connection));
+        }
+
+        out.push_str("      </root>\n");
+        out.push_str("    </mxGraphModel>\n");
+        out.push_str("  </diagram>\n");
+        out.push_str("</mxfile>\n");
+        out
+    }
+
+    /// Rasterize the SVG export to PNG bytes.
+    pub fn export_to_png(&self, doc: &SpecDocument, scale: f32) -> Result<Vec<u8>, String> {
+        use resvg::{tiny_skia, usvg};
+
+        let svg = self.export_to_svg(doc);
+
+        let mut options = usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+        let tree = usvg::Tree::from_str(&svg, &options)
+            .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+
+        let size = tree.size().to_int_size().scale_by(scale)
+            .ok_or_else(|| "Invalid export size".to_string())?;
+        let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+            .ok_or_else(|| "Failed to allocate image".to_string())?;
+
+        resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+        pixmap.encode_png()
+            .map_err(|e| format!("Failed to encode PNG: {}", e))
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComponentUpdate {
-    pub x: Option<f64>,
-    pub y: Option<f64>,
-    pub width: Option<f64>,
-    pub height: Option<f64>,
-    pub rotation: Option<f64>,
-    pub locked: Option<bool>,
-    pub visible: Option<bool>,
-    pub properties: Option<ComponentProperties>,
-    pub style: Option<ComponentStyle>,
+// ============================================
+// Markdown Import Helpers
+// ============================================
+
+struct ImportedComponent {
+    component_type: ComponentType,
+    unknown_section: Option<String>,
+    title: Option<String>,
+    content: Vec<String>,
+    items: Vec<String>,
+    metadata: RequirementMetadata,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct RequirementMetadata {
+    priority: Option<Priority>,
+    status: Option<ItemStatus>,
+    assignee: Option<String>,
+    due_date: Option<String>,
+}
+
+impl ImportedComponent {
+    fn new(component_type: ComponentType, unknown_section: Option<String>, title: Option<String>) -> Self {
+        Self {
+            component_type,
+            unknown_section,
+            title,
+            content: Vec::new(),
+            items: Vec::new(),
+            metadata: RequirementMetadata::default(),
+            closed: false,
+        }
+    }
+
+    /// Whether `line` continues this component. Text after a list starts the next one.
+    fn accepts(&self, line: &str) -> bool {
+        !self.closed
+            && (self.items.is_empty() || line.starts_with("- ") || line.trim().is_empty())
+    }
+
+    fn push_line(&mut self, line: &str) {
+        if let Some(item) = line.strip_prefix("- ") {
+            self.items.push(item.trim().to_string());
+        } else if self.items.is_empty() {
+            self.content.push(line.to_string());
+        }
+    }
+}
+
+const RELATIONSHIPS_HEADING: &str = "Relationships";
+const GROUPS_HEADING: &str = "Groups";
+
+/// Export order and heading for each component type: requirements first,
+/// then content, technical, UI, and finally annotations.
+fn section_for(component_type: &ComponentType) -> (usize, &'static str) {
+    match component_type {
+        ComponentType::UserStory => (0, "User Stories"),
+        ComponentType::Requirement => (1, "Requirements"),
+        ComponentType::AcceptanceCriteria => (2, "Acceptance Criteria"),
+        ComponentType::Section => (3, "Sections"),
+        ComponentType::Container => (4, "Containers"),
+        ComponentType::Card => (5, "Cards"),
+        ComponentType::Heading => (6, "Headings"),
+        ComponentType::Paragraph => (7, "Paragraphs"),
+        ComponentType::List => (8, "Lists"),
+        ComponentType::Table => (9, "Tables"),
+        ComponentType::Image => (10, "Images"),
+        ComponentType::ApiEndpoint => (11, "API Endpoints"),
+        ComponentType::DataModel => (12, "Data Models"),
+        ComponentType::FlowChart => (13, "Flow Charts"),
+        ComponentType::Sequence => (14, "Sequences"),
+        ComponentType::Button => (15, "Buttons"),
+        ComponentType::Input => (16, "Inputs"),
+        ComponentType::Form => (17, "Forms"),
+        ComponentType::Navigation => (18, "Navigation"),
+        ComponentType::Note => (19, "Notes"),
+        ComponentType::Comment => (20, "Comments"),
+        ComponentType::Arrow => (21, "Arrows"),
+        ComponentType::Connector => (22, "Connectors"),
+    }
+}
+
+/// Name used to refer to a component in the Relationships section.
+fn display_name(component: &CanvasComponent) -> String {
+    component.properties.title.clone()
+        .filter(|t| !t.trim().is_empty())
+        .or_else(|| component.properties.content.as_ref()
+            .and_then(|c| c.lines().next())
+            .map(|l| l.trim().to_string()))
+        .unwrap_or_else(|| format!("{:?}", component.component_type))
+}
+
+fn metadata_line(properties: &ComponentProperties) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(priority) = &properties.priority {
+        parts.push(format!("Priority: {:?}", priority));
+    }
+    if let Some(status) = &properties.status {
+        parts.push(format!("Status: {}", status_name(status)));
+    }
+    if let Some(assignee) = &properties.assignee {
+        parts.push(format!("Assignee: {}", assignee));
+    }
+    if let Some(due_date) = &properties.due_date {
+        parts.push(format!("Due: {}", due_date));
+    }
+
+    if parts.is_empty() { None } else { Some(parts.join(" | ")) }
+}
+
+fn parse_metadata_line(line: &str) -> RequirementMetadata {
+    let mut metadata = RequirementMetadata::default();
+
+    for part in line.split(" | ") {
+        let (key, value) = match part.split_once(": ") {
+            Some((k, v)) => (k.trim(), v.trim()),
+            None => continue,
+        };
+        match key {
+            "Priority" => {
+                metadata.priority = match value.to_lowercase().as_str() {
+                    "low" => Some(Priority::Low),
+                    "medium" => Some(Priority::Medium),
+                    "high" => Some(Priority::High),
+                    "critical" => Some(Priority::Critical),
+                    _ => None,
+                };
+            }
+            "Status" => {
+                metadata.status = match value.to_lowercase().as_str() {
+                    "todo" => Some(ItemStatus::Todo),
+                    "in progress" => Some(ItemStatus::InProgress),
+                    "done" => Some(ItemStatus::Done),
+                    "blocked" => Some(ItemStatus::Blocked),
+                    _ => None,
+                };
+            }
+            "Assignee" => metadata.assignee = Some(value.to_string()),
+            "Due" => metadata.due_date = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    metadata
+}
+
+fn status_name(status: &ItemStatus) -> &'static str {
+    match status {
+        ItemStatus::Todo => "Todo",
+        ItemStatus::InProgress => "In Progress",
+        ItemStatus::Done => "Done",
+        ItemStatus::Blocked => "Blocked",
+    }
+}
+
+fn connection_type_name(connection_type: &ConnectionType) -> &'static str {
+    match connection_type {
+        ConnectionType::Arrow => "arrow",
+        ConnectionType::Line => "line",
+        ConnectionType::Dashed => "dashed",
+        ConnectionType::Dependency => "dependency",
+        ConnectionType::Flow => "flow",
+    }
+}
+
+/// Parse "A → B (type: label)" or "A → B (type)".
+fn parse_relationship(line: &str) -> Option<(String, String, ConnectionType, Option<String>)> {
+    let (from, rest) = line.split_once(" → ")?;
+    let open = rest.rfind(" (")?;
+    let to = &rest[..open];
+    let inner = rest[open + 2..].strip_suffix(')')?;
+
+    let (kind, label) = match inner.split_once(": ") {
+        Some((k, l)) => (k, Some(l.to_string())),
+        None => (inner, None),
+    };
+    let connection_type = match kind {
+        "arrow" => ConnectionType::Arrow,
+        "line" => ConnectionType::Line,
+        "dashed" => ConnectionType::Dashed,
+        "dependency" => ConnectionType::Dependency,
+        "flow" => ConnectionType::Flow,
+        _ => return None,
+    };
+
+    Some((from.trim().to_string(), to.trim().to_string(), connection_type, label))
+}
+
+/// Parse `Name (group|frame): Member A, Member B`
+fn parse_group(line: &str) -> Option<(String, bool, Vec<String>)> {
+    let (head, members) = line.split_once("): ")?;
+    let (name, kind) = head.rsplit_once(" (")?;
+    let frame = match kind {
+        "frame" => true,
+        "group" => false,
+        _ => return None,
+    };
+    let members = members.split(", ").map(|m| m.trim().to_string()).collect();
+    Some((name.trim().to_string(), frame, members))
+}
+
+/// Parse a section heading back into a component type. Accepts the `Debug`
+/// names used by the Markdown export as well as snake_case and spaced forms.
+fn component_type_from_name(name: &str) -> Option<ComponentType> {
+    let normalized: String = name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+
+    let component_type = match normalized.as_str() {
+        "section" | "sections" => ComponentType::Section,
+        "container" | "containers" => ComponentType::Container,
+        "card" | "cards" => ComponentType::Card,
+        "heading" | "headings" => ComponentType::Heading,
+        "paragraph" | "paragraphs" => ComponentType::Paragraph,
+        "list" | "lists" => ComponentType::List,
+        "table" | "tables" => ComponentType::Table,
+        "image" | "images" => ComponentType::Image,
+        "userstory" | "userstories" => ComponentType::UserStory,
+        "requirement" | "requirements" => ComponentType::Requirement,
+        "acceptancecriteria" => ComponentType::AcceptanceCriteria,
+        "apiendpoint" | "apiendpoints" => ComponentType::ApiEndpoint,
+        "datamodel" | "datamodels" => ComponentType::DataModel,
+        "flowchart" | "flowcharts" => ComponentType::FlowChart,
+        "sequence" | "sequences" => ComponentType::Sequence,
+        "button" | "buttons" => ComponentType::Button,
+        "input" | "inputs" => ComponentType::Input,
+        "form" | "forms" => ComponentType::Form,
+        "navigation" => ComponentType::Navigation,
+        "note" | "notes" => ComponentType::Note,
+        "comment" | "comments" => ComponentType::Comment,
+        "arrow" | "arrows" => ComponentType::Arrow,
+        "connector" | "connectors" => ComponentType::Connector,
+        _ => return None,
+    };
+    Some(component_type)
+}
+
+// ============================================
+// Validation Types
+// ============================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecIssue {
+    pub severity: IssueSeverity,
+    pub code: String,
+    pub message: String,
+    pub component_id: Option<String>,
+    pub connection_id: Option<String>,
+}
+
+impl SpecIssue {
+    fn error(code: &str, message: String, component_id: Option<String>, connection_id: Option<String>) -> Self {
+        Self { severity: IssueSeverity::Error, code: code.to_string(), message, component_id, connection_id }
+    }
+
+    fn warning(code: &str, message: String, component_id: Option<String>, connection_id: Option<String>) -> Self {
+        Self { severity: IssueSeverity::Warning, code: code.to_string(), message, component_id, connection_id }
+    }
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().map(|v| v.trim().is_empty()).unwrap_or(true)
+}
+
+/// Arrows and connectors are themselves lines, so nothing can attach to them.
+fn has_anchors(component_type: &ComponentType) -> bool {
+    !matches!(component_type, ComponentType::Arrow | ComponentType::Connector)
+}
+
+// ============================================
+// Layout Helpers
+// ============================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutStrategy {
+    Grid,
+    LayeredByConnections,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl BoundingBox {
+    fn of(component: &CanvasComponent) -> Self {
+        Self {
+            x: component.x,
+            y: component.y,
+            width: component.width,
+            height: component.height,
+        }
+    }
+
+    fn enclosing(boxes: impl Iterator<Item = BoundingBox>) -> Self {
+        let mut bounds: Option<(f64, f64, f64, f64)> = None;
+        for b in boxes {
+            bounds = Some(match bounds {
+                None => (b.x, b.y, b.x + b.width, b.y + b.height),
+                Some((x1, y1, x2, y2)) => (
+                    x1.min(b.x),
+                    y1.min(b.y),
+                    x2.max(b.x + b.width),
+                    y2.max(b.y + b.height),
+                ),
+            });
+        }
+
+        match bounds {
+            Some((x1, y1, x2, y2)) => Self { x: x1, y: y1, width: x2 - x1, height: y2 - y1 },
+            None => Self { x: 0.0, y: 0.0, width: 0.0, height: 0.0 },
+        }
+    }
+
+    fn padded(self, padding: f64) -> Self {
+        Self {
+            x: self.x - padding,
+            y: self.y - padding,
+            width: self.width + padding * 2.0,
+            height: self.height + padding * 2.0,
+        }
+    }
+
+    fn overlaps(&self, other: &BoundingBox) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// Edge or center line for `SpecBuilder::align`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlignEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    CenterX,
+    CenterY,
+}
+
+impl AlignEdge {
+    fn axis(self) -> Axis {
+        match self {
+            Self::Left | Self::Right | Self::CenterX => Axis::Horizontal,
+            Self::Top | Self::Bottom | Self::CenterY => Axis::Vertical,
+        }
+    }
+
+    fn span(self, component: &CanvasComponent) -> (f64, f64) {
+        let axis = self.axis();
+        let start = axis.start(component);
+        (start, start + axis.size(component))
+    }
+
+    /// Where this edge of `component` currently is
+    fn position(self, component: &CanvasComponent) -> f64 {
+        let (start, end) = self.span(component);
+        match self {
+            Self::Left | Self::Top => start,
+            Self::Right | Self::Bottom => end,
+            Self::CenterX | Self::CenterY => (start + end) / 2.0,
+        }
+    }
+
+    /// Move `component` so this edge lies on `target`
+    fn move_to(self, component: &mut CanvasComponent, target: f64) {
+        let offset = self.position(component) - self.axis().start(component);
+        self.axis().set_start(component, target - offset);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    fn start(self, component: &CanvasComponent) -> f64 {
+        match self {
+            Self::Horizontal => component.x,
+            Self::Vertical => component.y,
+        }
+    }
+
+    fn size(self, component: &CanvasComponent) -> f64 {
+        match self {
+            Self::Horizontal => component.width,
+            Self::Vertical => component.height,
+        }
+    }
+
+    fn set_start(self, component: &mut CanvasComponent, value: f64) {
+        match self {
+            Self::Horizontal => component.x = value,
+            Self::Vertical => component.y = value,
+        }
+    }
+}
+
+/// Indices of `component_ids` in the canvas, in the order given
+fn component_indices(canvas: &Canvas, component_ids: &[String]) -> Result<Vec<usize>, String> {
+    let mut indices: Vec<usize> = Vec::new();
+    for id in component_ids {
+        let index = canvas.components.iter()
+            .position(|c| &c.id == id)
+            .ok_or_else(|| format!("Component not found: {}", id))?;
+        if !indices.contains(&index) {
+            indices.push(index);
+        }
+    }
+    Ok(indices)
+}
+
+/// Round a coordinate to the nearest grid line when snapping is on
+fn snap(canvas: &Canvas, value: f64) -> f64 {
+    if canvas.snap_to_grid && canvas.grid_size > 0.0 {
+        (value / canvas.grid_size).round() * canvas.grid_size
+    } else {
+        value
+    }
+}
+
+/// Starting from (x, y), step past any already-placed box the component would
+/// overlap, moving right (`horizontal`) or down, snapping to the grid if enabled.
+#[allow(clippy::too_many_arguments)]
+fn find_free_position(
+    component: &CanvasComponent,
+    x: f64,
+    y: f64,
+    placed: &[BoundingBox],
+    gap: f64,
+    snap: bool,
+    grid_size: f64,
+    horizontal: bool,
+) -> (f64, f64) {
+    let snap_up = |v: f64| if snap && grid_size > 0.0 { (v / grid_size).ceil() * grid_size } else { v };
+
+    let mut candidate = BoundingBox {
+        x: snap_up(x),
+        y: snap_up(y),
+        width: component.width,
+        height: component.height,
+    };
+
+    // Each step moves past one obstacle, so this terminates after at most placed.len() steps
+    for _ in 0..=placed.len() {
+        match placed.iter().find(|p| p.overlaps(&candidate)) {
+            Some(obstacle) if horizontal => candidate.x = snap_up(obstacle.x + obstacle.width + gap),
+            Some(obstacle) => candidate.y = snap_up(obstacle.y + obstacle.height + gap),
+            None => break,
+        }
+    }
+
+    (candidate.x, candidate.y)
+}
+
+/// Assign unlocked components to layers along the connection graph (longest
+/// path from the sources) and order each layer by the average position of its
+/// predecessors. Cycles are broken by forcing the node with the fewest
+/// unresolved incoming edges into the next layer.
+fn layer_components(canvas: &Canvas) -> Vec<Vec<usize>> {
+    let index_of: HashMap<&str, usize> = canvas.components.iter()
+        .enumerate()
+        .filter(|(_, c)| !canvas.is_locked(c))
+        .map(|(i, c)| (c.id.as_str(), i))
+        .collect();
+
+    let mut predecessors: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut successors: HashMap<usize, Vec<usize>> = HashMap::new();
+    for connection in &canvas.connections {
+        let from = index_of.get(connection.from_component.as_str());
+        let to = index_of.get(connection.to_component.as_str());
+        if let (Some(&from), Some(&to)) = (from, to) {
+            if from != to {
+                predecessors.entry(to).or_default().push(from);
+                successors.entry(from).or_default().push(to);
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = index_of.values().copied().collect();
+    order.sort_unstable();
+
+    let mut remaining_in: HashMap<usize, usize> = order.iter()
+        .map(|&i| (i, predecessors.get(&i).map(|p| p.len()).unwrap_or(0)))
+        .collect();
+    let mut layer_of: HashMap<usize, usize> = HashMap::new();
+
+    while layer_of.len() < order.len() {
+        let ready: Vec<usize> = order.iter()
+            .copied()
+            .filter(|i| !layer_of.contains_key(i) && remaining_in[i] == 0)
+            .collect();
+
+        // Cycle guard: nothing is ready, so force the least-blocked node
+        let ready = if ready.is_empty() {
+            let forced = order.iter()
+                .copied()
+                .filter(|i| !layer_of.contains_key(i))
+                .min_by_key(|i| remaining_in[i])
+                .unwrap();
+            vec![forced]
+        } else {
+            ready
+        };
+
+        for node in ready {
+            let layer = predecessors.get(&node)
+                .map(|preds| preds.iter()
+                    .filter_map(|p| layer_of.get(p))
+                    .map(|l| l + 1)
+                    .max()
+                    .unwrap_or(0))
+                .unwrap_or(0);
+            layer_of.insert(node, layer);
+
+            for next in successors.get(&node).into_iter().flatten() {
+                if let Some(count) = remaining_in.get_mut(next) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    let layer_count = layer_of.values().copied().max().map(|m| m + 1).unwrap_or(0);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for &node in &order {
+        layers[layer_of[&node]].push(node);
+    }
+
+    // Barycenter ordering: keep nodes near the predecessors they connect to
+    for l in 1..layers.len() {
+        let (before, after) = layers.split_at_mut(l);
+        let previous = &before[l - 1];
+        let rank = |node: &usize| -> f64 {
+            let positions: Vec<f64> = predecessors.get(node)
+                .into_iter()
+                .flatten()
+                .filter_map(|p| previous.iter().position(|n| n == p))
+                .map(|p| p as f64)
+                .collect();
+            if positions.is_empty() {
+                f64::MAX
+            } else {
+                positions.iter().sum::<f64>() / positions.len() as f64
+            }
+        };
+        after[0].sort_by(|a, b| rank(a).partial_cmp(&rank(b)).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    layers
+}
+
+// ============================================
+// Undo / Redo History
+// ============================================
+
+pub const DEFAULT_HISTORY_DEPTH: usize = 100;
+
+/// A reversible canvas mutation, holding enough state to apply it in either direction.
+#[derive(Debug, Clone)]
+enum CanvasOp {
+    AddComponent {
+        component: CanvasComponent,
+    },
+    DeleteComponent {
+        index: usize,
+        component: CanvasComponent,
+        connections: Vec<(usize, Connection)>,
+        /// The group it belonged to, as it was, and the group's index
+        group: Option<(usize, ComponentGroup)>,
+    },
+    UpdateComponent {
+        before: CanvasComponent,
+        after: CanvasComponent,
+    },
+    AddConnection {
+        connection: Connection,
+    },
+    DeleteConnection {
+        index: usize,
+        connection: Connection,
+    },
+}
+
+impl CanvasOp {
+    fn apply(&self, canvas: &mut Canvas) {
+        match self {
+            Self::AddComponent { component } => {
+                canvas.components.push(component.clone());
+            }
+            Self::DeleteComponent { component, .. } => {
+                canvas.components.retain(|c| c.id != component.id);
+                canvas.connections.retain(|c| {
+                    c.from_component != component.id && c.to_component != component.id
+                });
+                remove_from_groups(canvas, &component.id);
+            }
+            Self::UpdateComponent { after, .. } => {
+                replace_component(canvas, after);
+            }
+            Self::AddConnection { connection } => {
+                canvas.connections.push(connection.clone());
+            }
+            Self::DeleteConnection { connection, .. } => {
+                canvas.connections.retain(|c| c.id != connection.id);
+            }
+        }
+    }
+
+    fn revert(&self, canvas: &mut Canvas) {
+        match self {
+            Self::AddComponent { component } => {
+                canvas.components.retain(|c| c.id != component.id);
+                remove_from_groups(canvas, &component.id);
+            }
+            Self::DeleteComponent { index, component, connections, group } => {
+                let index = (*index).min(canvas.components.len());
+                canvas.components.insert(index, component.clone());
+                // Indices were captured in ascending order, so re-inserting in order restores positions
+                for (conn_index, connection) in connections {
+                    let conn_index = (*conn_index).min(canvas.connections.len());
+                    canvas.connections.insert(conn_index, connection.clone());
+                }
+                if let Some((group_index, group)) = group {
+                    match canvas.groups.iter_mut().find(|g| g.id == group.id) {
+                        Some(existing) if !existing.component_ids.contains(&component.id) => {
+                            let position = group.component_ids.iter()
+                                .position(|id| id == &component.id)
+                                .unwrap_or(existing.component_ids.len())
+                                .min(existing.component_ids.len());
+                            existing.component_ids.insert(position, component.id.clone());
+                        }
+                        Some(_) => {}
+                        None => {
+                            let group_index = (*group_index).min(canvas.groups.len());
+                            canvas.groups.insert(group_index, group.clone());
+                        }
+                    }
+                }
+            }
+            Self::UpdateComponent { before, .. } => {
+                replace_component(canvas, before);
+            }
+            Self::AddConnection { connection } => {
+                canvas.connections.retain(|c| c.id != connection.id);
+            }
+            Self::DeleteConnection { index, connection } => {
+                let index = (*index).min(canvas.connections.len());
+                canvas.connections.insert(index, connection.clone());
+            }
+        }
+    }
+}
+
+/// Take a removed component out of its group, dropping the group once empty
+fn remove_from_groups(canvas: &mut Canvas, component_id: &str) {
+    for group in canvas.groups.iter_mut() {
+        group.component_ids.retain(|id| id != component_id);
+    }
+    canvas.groups.retain(|g| !g.component_ids.is_empty());
+}
+
+fn replace_component(canvas: &mut Canvas, component: &CanvasComponent) {
+    if let Some(existing) = canvas.components.iter_mut().find(|c| c.id == component.id) {
+        *existing = component.clone();
+    }
+}
+
+/// Bounded undo/redo history for one document's canvas.
+///
+/// Mutations go through the history so each one is recorded with its inverse;
+/// any new mutation clears the redo stack.
+#[derive(Debug, Clone)]
+pub struct CanvasHistory {
+    undo_stack: VecDeque<CanvasOp>,
+    redo_stack: Vec<CanvasOp>,
+    max_depth: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryState {
+    pub can_undo: bool,
+    pub can_redo: bool,
+}
+
+impl Default for CanvasHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_DEPTH)
+    }
+}
+
+impl CanvasHistory {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            max_depth: max_depth.max(1),
+        }
+    }
+
+    fn record(&mut self, op: CanvasOp) {
+        if self.undo_stack.len() == self.max_depth {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(op);
+        self.redo_stack.clear();
+    }
+
+    pub fn add_component(
+        &mut self,
+        builder: &SpecBuilder,
+        canvas: &mut Canvas,
+        template_id: &str,
+        x: f64,
+        y: f64,
+    ) -> Result<String, String> {
+        let id = builder.add_component(canvas, template_id, x, y)?;
+        if let Some(component) = canvas.components.iter().find(|c| c.id == id) {
+            self.record(CanvasOp::AddComponent { component: component.clone() });
+        }
+        Ok(id)
+    }
+
+    pub fn update_component(
+        &mut self,
+        builder: &SpecBuilder,
+        canvas: &mut Canvas,
+        component_id: &str,
+        updates: ComponentUpdate,
+    ) -> Result<(), String> {
+        let before = canvas.components.iter()
+            .find(|c| c.id == component_id)
+            .cloned()
+            .ok_or_else(|| format!("Component not found: {}", component_id))?;
+
+        builder.update_component(canvas, component_id, updates)?;
+
+        if let Some(after) = canvas.components.iter().find(|c| c.id == component_id) {
+            self.record(CanvasOp::UpdateComponent { before, after: after.clone() });
+        }
+        Ok(())
+    }
+
+    pub fn delete_component(
+        &mut self,
+        builder: &SpecBuilder,
+        canvas: &mut Canvas,
+        component_id: &str,
+    ) -> Result<(), String> {
+        let index = canvas.components.iter()
+            .position(|c| c.id == component_id)
+            .ok_or_else(|| format!("Component not found: {}", component_id))?;
+        let component = canvas.components[index].clone();
+        let connections: Vec<(usize, Connection)> = canvas.connections.iter()
+            .enumerate()
+            .filter(|(_, c)| c.from_component == component_id || c.to_component == component_id)
+            .map(|(i, c)| (i, c.clone()))
+            .collect();
+        let group = canvas.groups.iter()
+            .position(|g| g.component_ids.iter().any(|id| id == component_id))
+            .map(|i| (i, canvas.groups[i].clone()));
+
+        builder.delete_component(canvas, component_id)?;
+
+        self.record(CanvasOp::DeleteComponent { index, component, connections, group });
+        Ok(())
+    }
+
+    pub fn add_connection(
+        &mut self,
+        builder: &SpecBuilder,
+        canvas: &mut Canvas,
+        from_component: &str,
+        from_anchor: Anchor,
+        to_component: &str,
+        to_anchor: Anchor,
+        connection_type: ConnectionType,
+    ) -> Result<String, String> {
+        let id = builder.add_connection(
+            canvas,
+            from_component,
+            from_anchor,
+            to_component,
+            to_anchor,
+            connection_type,
+        )?;
+        if let Some(connection) = canvas.connections.iter().find(|c| c.id == id) {
+            self.record(CanvasOp::AddConnection { connection: connection.clone() });
+        }
+        Ok(id)
+    }
+
+    pub fn delete_connection(
+        &mut self,
+        builder: &SpecBuilder,
+        canvas: &mut Canvas,
+        connection_id: &str,
+    ) -> Result<(), String> {
+        let index = canvas.connections.iter()
+            .position(|c| c.id == connection_id)
+            .ok_or_else(|| format!("Connection not found: {}", connection_id))?;
+        let connection = canvas.connections[index].clone();
+
+        builder.delete_connection(canvas, connection_id)?;
+
+        self.record(CanvasOp::DeleteConnection { index, connection });
+        Ok(())
+    }
+
+    /// Revert the most recent mutation. Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self, canvas: &mut Canvas) -> bool {
+        match self.undo_stack.pop_back() {
+            Some(op) => {
+                op.revert(canvas);
+                self.redo_stack.push(op);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-apply the most recently undone mutation. Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self, canvas: &mut Canvas) -> bool {
+        match self.redo_stack.pop() {
+            Some(op) => {
+                op.apply(canvas);
+                self.undo_stack.push_back(op);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn state(&self) -> HistoryState {
+        HistoryState {
+            can_undo: self.can_undo(),
+            can_redo: self.can_redo(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+// ============================================
+// SVG Rendering Helpers
+// ============================================
+
+/// Visible components in the order they're drawn: by `z_index`, with each
+/// group's members pulled up to its lowest member so they're contiguous.
+fn paint_order(canvas: &Canvas) -> Vec<&CanvasComponent> {
+    let mut components: Vec<&CanvasComponent> = canvas.components.iter()
+        .filter(|c| canvas.is_visible(c))
+        .collect();
+    components.sort_by_key(|c| c.z_index);
+
+    let group_depth = |component: &CanvasComponent| {
+        canvas.group_of(&component.id).map(|group| {
+            let depth = components.iter()
+                .filter(|c| group.component_ids.contains(&c.id))
+                .map(|c| c.z_index)
+                .min()
+                .unwrap_or(component.z_index);
+            (depth, Some(group.id.as_str()))
+        })
+    };
+    let mut keyed: Vec<((i32, Option<&str>), &CanvasComponent)> = components.iter()
+        .map(|&c| (group_depth(c).unwrap_or((c.z_index, None)), c))
+        .collect();
+    keyed.sort_by_key(|(key, _)| *key);
+    keyed.into_iter().map(|(_, c)| c).collect()
+}
+
+/// Connections whose ends are both among `components`
+fn visible_connections<'a>(canvas: &'a Canvas, components: &[&CanvasComponent]) -> Vec<&'a Connection> {
+    canvas.connections.iter()
+        .filter(|conn| {
+            components.iter().any(|c| c.id == conn.from_component)
+                && components.iter().any(|c| c.id == conn.to_component)
+        })
+        .collect()
+}
+
+fn render_component_svg(component: &CanvasComponent, index: usize) -> String {
+    let style = &component.style;
+    let mut out = String::new();
+
+    let cx = component.x + component.width / 2.0;
+    let cy = component.y + component.height / 2.0;
+    out.push_str(&format!(
+        "  <g id=\"{}\" opacity=\"{}\" transform=\"rotate({} {} {})\">\n",
+        escape_xml(&component.id),
+        style.opacity.unwrap_or(1.0),
+        component.rotation,
+        cx,
+        cy,
+    ));
+
+    out.push_str(&format!(
+        "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{}/>\n",
+        component.x,
+        component.y,
+        component.width,
+        component.height,
+        style.border_radius.unwrap_or(0.0),
+        escape_xml(style.background_color.as_deref().unwrap_or("none")),
+        escape_xml(style.border_color.as_deref().unwrap_or("none")),
+        style.border_width.unwrap_or(0.0),
+        if style.shadow.unwrap_or(false) { " filter=\"url(#shadow)\"" } else { "" },
+    ));
+
+    let padding = style.padding.unwrap_or(0.0);
+    let font_size = style.font_size.unwrap_or(14.0);
+    let line_height = font_size * 1.4;
+    let text_color = escape_xml(style.text_color.as_deref().unwrap_or("#1f2937"));
+    let text_x = component.x + padding;
+    let mut text_y = component.y + padding + font_size;
+
+    out.push_str(&format!("    <g clip-path=\"url(#clip-{})\" fill=\"{}\" font-size=\"{}\">\n", index, text_color, font_size));
+
+    if let Some(title) = &component.properties.title {
+        out.push_str(&format!(
+            "      <text x=\"{}\" y=\"{}\" font-weight=\"bold\">{}</text>\n",
+            text_x, text_y, escape_xml(title),
+        ));
+        text_y += line_height;
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    if let Some(content) = &component.properties.content {
+        lines.extend(content.lines().map(|l| l.to_string()));
+    }
+    if let Some(items) = &component.properties.items {
+        lines.extend(items.iter().map(|i| format!("• {}", i)));
+    }
+    if !lines.is_empty() {
+        let weight = style.font_weight.as_deref().unwrap_or("normal");
+        out.push_str(&format!("      <text x=\"{}\" y=\"{}\" font-weight=\"{}\">", text_x, text_y, escape_xml(weight)));
+        for (i, line) in lines.iter().enumerate() {
+            let dy = if i == 0 { 0.0 } else { line_height };
+            out.push_str(&format!("<tspan x=\"{}\" dy=\"{}\">{}</tspan>", text_x, dy, escape_xml(line)));
+        }
+        out.push_str("</text>\n");
+    }
+
+    out.push_str("    </g>\n");
+    out.push_str("  </g>\n");
+    out
+}
+
+/// Open a group's `<g>`; frames also draw their outline and title
+fn render_group_open_svg(group: &ComponentGroup, bounds: BoundingBox) -> String {
+    let mut out = format!(
+        "  <g id=\"group-{}\" data-name=\"{}\">\n",
+        escape_xml(&group.id),
+        escape_xml(&group.name),
+    );
+    if group.frame {
+        let padding = FRAME_PADDING;
+        out.push_str(&format!(
+            "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"8\" fill=\"none\" stroke=\"#9ca3af\" stroke-width=\"1\" stroke-dasharray=\"6 4\"/>\n",
+            bounds.x - padding,
+            bounds.y - padding,
+            bounds.width + padding * 2.0,
+            bounds.height + padding * 2.0,
+        ));
+        out.push_str(&format!(
+            "    <text x=\"{}\" y=\"{}\" font-size=\"12\" fill=\"#6b7280\">{}</text>\n",
+            bounds.x - padding,
+            bounds.y - padding - 6.0,
+            escape_xml(&group.name),
+        ));
+    }
+    out
+}
+
+/// Space between a frame's outline and its members
+const FRAME_PADDING: f64 = 16.0;
+
+fn render_connection_svg(
+    connection: &Connection,
+    from: &CanvasComponent,
+    to: &CanvasComponent,
+    index: usize,
+) -> String {
+    let (x1, y1) = anchor_point(from, &connection.from_anchor);
+    let (x2, y2) = anchor_point(to, &connection.to_anchor);
+
+    // Bend the curve out along each anchor's direction so lines leave edges cleanly
+    let bend = ((x2 - x1).abs().max((y2 - y1).abs()) / 2.0).max(20.0);
+    let (dx1, dy1) = anchor_direction(&connection.from_anchor);
+    let (dx2, dy2) = anchor_direction(&connection.to_anchor);
+    let path = format!(
+        "M {} {} C {} {}, {} {}, {} {}",
+        x1, y1,
+        x1 + dx1 * bend, y1 + dy1 * bend,
+        x2 + dx2 * bend, y2 + dy2 * bend,
+        x2, y2,
+    );
+
+    let dash = match connection.connection_type {
+        ConnectionType::Dashed => " stroke-dasharray=\"8 4\"",
+        ConnectionType::Dependency => " stroke-dasharray=\"2 4\"",
+        _ => "",
+    };
+    let marker = if has_arrowhead(&connection.connection_type) {
+        format!(" marker-end=\"url(#arrow-{})\"", index)
+    } else {
+        String::new()
+    };
+
+    let mut out = format!(
+        "  <path id=\"{}\" d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"{}{}/>\n",
+        escape_xml(&connection.id),
+        path,
+        escape_xml(&connection.style.color),
+        connection.style.width,
+        dash,
+        marker,
+    );
+
+    if let Some(label) = &connection.label {
+        out.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"12\" fill=\"{}\">{}</text>\n",
+            (x1 + x2) / 2.0,
+            (y1 + y2) / 2.0 - 4.0,
+            escape_xml(&connection.style.color),
+            escape_xml(label),
+        ));
+    }
+
+    out
+}
+
+fn anchor_point(component: &CanvasComponent, anchor: &Anchor) -> (f64, f64) {
+    let (x, y, w, h) = (component.x, component.y, component.width, component.height);
+    match anchor {
+        Anchor::Top => (x + w / 2.0, y),
+        Anchor::Right => (x + w, y + h / 2.0),
+        Anchor::Bottom => (x + w / 2.0, y + h),
+        Anchor::Left => (x, y + h / 2.0),
+        Anchor::Center => (x + w / 2.0, y + h / 2.0),
+    }
+}
+
+fn anchor_direction(anchor: &Anchor) -> (f64, f64) {
+    match anchor {
+        Anchor::Top => (0.0, -1.0),
+        Anchor::Right => (1.0, 0.0),
+        Anchor::Bottom => (0.0, 1.0),
+        Anchor::Left => (-1.0, 0.0),
+        Anchor::Center => (0.0, 0.0),
+    }
+}
+
+fn has_arrowhead(connection_type: &ConnectionType) -> bool {
+    matches!(connection_type, ConnectionType::Arrow | ConnectionType::Dependency | ConnectionType::Flow)
+}
+
+// ============================================
+// Mermaid / draw.io Helpers
+// ============================================
+
+/// Title plus first content line, or the type name for untitled components
+fn node_label(component: &CanvasComponent) -> String {
+    match (&component.properties.title, &component.properties.content) {
+        (Some(title), Some(content)) if !title.trim().is_empty() => match content.lines().next() {
+            Some(line) if !line.trim().is_empty() => format!("{}\n{}", title.trim(), line.trim()),
+            _ => title.trim().to_string(),
+        },
+        _ => display_name(component),
+    }
+}
+
+/// Mermaid label text: quotes and angle brackets as entity codes (labels are
+/// rendered as HTML), line breaks as `<br/>`
+fn mermaid_text(s: &str) -> String {
+    s.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace('\n', "<br/>")
+}
+
+/// YAML double-quoted scalar for the front matter, with line breaks turned
+/// into spaces so the title stays on one line
+fn yaml_string(s: &str) -> String {
+    let escaped: String = s.chars()
+        .filter(|&c| c != '\r')
+        .map(|c| match c {
+            '\\' => "\\\\".to_string(),
+            '"' => "\\\"".to_string(),
+            '\n' => " ".to_string(),
+            c => c.to_string(),
+        })
+        .collect();
+    format!("\"{}\"", escaped)
+}
+
+/// Opening and closing brackets of the Mermaid node shape for a type
+fn mermaid_shape(component_type: &ComponentType) -> (&'static str, &'static str) {
+    match component_type {
+        ComponentType::UserStory => ("([", "])"),
+        ComponentType::ApiEndpoint => ("{{", "}}"),
+        ComponentType::DataModel => ("[(", ")]"),
+        ComponentType::FlowChart | ComponentType::Sequence => ("[[", "]]"),
+        ComponentType::Button | ComponentType::Input | ComponentType::Form | ComponentType::Navigation => ("(", ")"),
+        ComponentType::Note | ComponentType::Comment => (">", "]"),
+        ComponentType::Arrow | ComponentType::Connector => ("((", "))"),
+        _ => ("[", "]"),
+    }
+}
+
+/// Base draw.io style for a type, before colours are appended
+fn drawio_shape(component_type: &ComponentType) -> &'static str {
+    match component_type {
+        ComponentType::UserStory => "rounded=1;arcSize=50;",
+        ComponentType::ApiEndpoint => "shape=hexagon;perimeter=hexagonPerimeter2;size=0.1;",
+        ComponentType::DataModel => "shape=cylinder3;boundedLbl=1;backgroundOutline=1;size=12;",
+        ComponentType::FlowChart | ComponentType::Sequence => "shape=process;backgroundOutline=1;",
+        ComponentType::Note | ComponentType::Comment => "shape=note;size=16;",
+        ComponentType::Arrow | ComponentType::Connector => "ellipse;",
+        ComponentType::Section | ComponentType::Container | ComponentType::Heading
+        | ComponentType::Paragraph | ComponentType::Table | ComponentType::Image => "rounded=0;",
+        _ => "rounded=1;",
+    }
+}
+
+/// Style values can't contain the `;` separator
+fn drawio_value(s: &str) -> String {
+    s.replace(';', "")
+}
+
+/// Escape an attribute value, keeping line breaks as `&#xa;`
+fn escape_drawio(s: &str) -> String {
+    escape_xml(s).replace('\n', "&#xa;")
+}
+
+fn render_group_drawio(group: &ComponentGroup, bounds: BoundingBox) -> String {
+    let style = if group.frame {
+        "rounded=1;dashed=1;fillColor=none;strokeColor=#9ca3af;container=1;collapsible=0;verticalAlign=top;align=left;spacingLeft=8;fontColor=#6b7280;"
+    } else {
+        "group;"
+    };
+    format!(
+        "        <mxCell id=\"group-{}\" value=\"{}\" style=\"{}\" vertex=\"1\" connectable=\"0\" parent=\"1\">\n          <mxGeometry x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" as=\"geometry\"/>\n        </mxCell>\n",
+        escape_xml(&group.id),
+        if group.frame { escape_drawio(&group.name) } else { String::new() },
+        style,
+        bounds.x,
+        bounds.y,
+        bounds.width,
+        bounds.height,
+    )
+}
+
+/// A component cell; inside a group its geometry is relative to the group
+fn render_component_drawio(component: &CanvasComponent, group: Option<(&str, BoundingBox)>) -> String {
+    let style = &component.style;
+    let mut css = format!("{}whiteSpace=wrap;html=0;", drawio_shape(&component.component_type));
+    let colors = [
+        ("fillColor", &style.background_color),
+        ("strokeColor", &style.border_color),
+        ("fontColor", &style.text_color),
+    ];
+    for (key, value) in colors {
+        if let Some(value) = value {
+            css.push_str(&format!("{}={};", key, drawio_value(value)));
+        }
+    }
+    if let Some(width) = style.border_width {
+        css.push_str(&format!("strokeWidth={};", width));
+    }
+    if let Some(size) = style.font_size {
+        css.push_str(&format!("fontSize={};", size));
+    }
+    if style.font_weight.as_deref() == Some("bold") {
+        css.push_str("fontStyle=1;");
+    }
+    if let Some(opacity) = style.opacity.filter(|o| *o < 1.0) {
+        css.push_str(&format!("opacity={};", (opacity * 100.0).round()));
+    }
+    if style.shadow.unwrap_or(false) {
+        css.push_str("shadow=1;");
+    }
+    if component.rotation != 0.0 {
+        css.push_str(&format!("rotation={};", component.rotation));
+    }
+    if component.locked {
+        css.push_str("movable=0;resizable=0;");
+    }
+
+    let (parent, x, y) = match group {
+        Some((id, bounds)) => (format!("group-{}", id), component.x - bounds.x, component.y - bounds.y),
+        None => ("1".to_string(), component.x, component.y),
+    };
+    format!(
+        "        <mxCell id=\"{}\" value=\"{}\" style=\"{}\" vertex=\"1\" parent=\"{}\">\n          <mxGeometry x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" as=\"geometry\"/>\n        </mxCell>\n",
+        escape_xml(&component.id),
+        escape_drawio(&node_label(component)),
+        escape_xml(&css),
+        escape_xml(&parent),
+        x,
+        y,
+        component.width,
+        component.height,
+    )
+}
+
+fn render_connection_drawio(connection: &Connection) -> String {
+    let mut css = String::from("edgeStyle=orthogonalEdgeStyle;rounded=1;html=0;");
+    css.push_str(match connection.connection_type {
+        ConnectionType::Arrow => "endArrow=classic;",
+        ConnectionType::Line => "endArrow=none;",
+        ConnectionType::Dashed => "dashed=1;endArrow=none;",
+        ConnectionType::Dependency => "dashed=1;dashPattern=2 4;endArrow=open;",
+        ConnectionType::Flow => "endArrow=block;endFill=1;",
+    });
+    css.push_str(&format!(
+        "strokeColor={};strokeWidth={};",
+        drawio_value(&connection.style.color),
+        connection.style.width,
+    ));
+    // Center anchors are left floating so draw.io picks the nearest side
+    if let Some((x, y)) = anchor_ratio(&connection.from_anchor) {
+        css.push_str(&format!("exitX={};exitY={};exitDx=0;exitDy=0;", x, y));
+    }
+    if let Some((x, y)) = anchor_ratio(&connection.to_anchor) {
+        css.push_str(&format!("entryX={};entryY={};entryDx=0;entryDy=0;", x, y));
+    }
+
+    format!(
+        "        <mxCell id=\"{}\" value=\"{}\" style=\"{}\" edge=\"1\" parent=\"1\" source=\"{}\" target=\"{}\">\n          <mxGeometry relative=\"1\" as=\"geometry\"/>\n        </mxCell>\n",
+        escape_xml(&connection.id),
+        escape_drawio(connection.label.as_deref().unwrap_or("")),
+        escape_xml(&css),
+        escape_xml(&connection.from_component),
+        escape_xml(&connection.to_component),
+    )
+}
+
+/// Anchor position as a fraction of the component's width and height
+fn anchor_ratio(anchor: &Anchor) -> Option<(f64, f64)> {
+    match anchor {
+        Anchor::Top => Some((0.5, 0.0)),
+        Anchor::Right => Some((1.0, 0.5)),
+        Anchor::Bottom => Some((0.5, 1.0)),
+        Anchor::Left => Some((0.0, 0.5)),
+        Anchor::Center => None,
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentUpdate {
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub rotation: Option<f64>,
+    pub locked: Option<bool>,
+    pub visible: Option<bool>,
+    pub properties: Option<ComponentProperties>,
+    pub style: Option<ComponentStyle>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupUpdate {
+    pub name: Option<String>,
+    pub frame: Option<bool>,
+    pub locked: Option<bool>,
+    pub visible: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canvas_with_connected_components(builder: &SpecBuilder) -> (Canvas, String, String, String) {
+        let mut canvas = Canvas::default();
+        let story = builder.add_component(&mut canvas, "user_story", 0.0, 0.0).unwrap();
+        let req = builder.add_component(&mut canvas, "requirement", 500.0, 0.0).unwrap();
+        let conn = builder.add_connection(
+            &mut canvas, &story, Anchor::Right, &req, Anchor::Left, ConnectionType::Arrow,
+        ).unwrap();
+        (canvas, story, req, conn)
+    }
+
+    #[test]
+    fn test_export_reuses_cached_output_until_document_changes() {
+        let cache = Arc::new(RenderCache::new(1.0, 10, 60));
+        let builder = SpecBuilder::new().with_export_cache(cache.clone());
+        let mut doc = builder.create_document("Checkout", None);
+        builder.add_component(&mut doc.canvas, "user_story", 0.0, 0.0).unwrap();
+
+        let first = builder.export(&doc, "svg").unwrap();
+        assert_eq!(builder.export(&doc, "SVG").unwrap(), first);
+        assert!(builder.export(&doc, "md").unwrap().contains("Checkout"));
+        let stats = cache.stats();
+        assert_eq!((stats.hit_count, stats.miss_count), (1, 2));
+
+        builder.add_component(&mut doc.canvas, "note", 200.0, 0.0).unwrap();
+        assert_ne!(builder.export(&doc, "svg").unwrap(), first);
+        assert_eq!(cache.stats().miss_count, 3);
+        assert!(builder.export(&doc, "pdf").is_err());
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let builder = SpecBuilder::new();
+        let mut doc = builder.create_document("Checkout", Some("Checkout flow spec"));
+        let story = builder.add_component(&mut doc.canvas, "user_story", 0.0, 0.0).unwrap();
+        let model = builder.add_component(&mut doc.canvas, "data_model", 0.0, 0.0).unwrap();
+        builder.add_component(&mut doc.canvas, "note", 0.0, 0.0).unwrap();
+        doc.canvas.components.iter_mut().find(|c| c.id == story).unwrap().properties.title =
+            Some("Pay with card".to_string());
+        doc.canvas.components.iter_mut().find(|c| c.id == model).unwrap().properties.title =
+            Some("Order".to_string());
+
+        let imported = builder.import_from_markdown(&builder.export_to_markdown(&doc));
+        assert_eq!(imported.name, "Checkout");
+        assert_eq!(imported.description.as_deref(), Some("Checkout flow spec"));
+        assert_eq!(imported.canvas.components.len(), doc.canvas.components.len());
+
+        for original in &doc.canvas.components {
+            let restored = imported.canvas.components.iter()
+                .find(|c| c.properties.title == original.properties.title
+                    && c.properties.content == original.properties.content)
+                .expect("component should survive the round trip");
+            assert_eq!(restored.properties.items, original.properties.items);
+            assert_eq!(
+                std::mem::discriminant(&restored.component_type),
+                std::mem::discriminant(&original.component_type)
+            );
+        }
+    }
+
+    #[test]
+    fn test_markdown_export_snapshot() {
+        let builder = SpecBuilder::new();
+        let mut doc = builder.create_document("Checkout", Some("Checkout flow spec"));
+        let note = builder.add_component(&mut doc.canvas, "note", 0.0, 400.0).unwrap();
+        let endpoint = builder.add_component(&mut doc.canvas, "api_endpoint", 0.0, 200.0).unwrap();
+        let req = builder.add_component(&mut doc.canvas, "requirement", 0.0, 0.0).unwrap();
+        let story = builder.add_component(&mut doc.canvas, "user_story", 400.0, 0.0).unwrap();
+        builder.add_connection(&mut doc.canvas, &story, Anchor::Bottom, &req, Anchor::Top, ConnectionType::Dependency).unwrap();
+        let conn = builder.add_connection(&mut doc.canvas, &req, Anchor::Bottom, &endpoint, Anchor::Top, ConnectionType::Flow).unwrap();
+        doc.canvas.connections.iter_mut().find(|c| c.id == conn).unwrap().label = Some("implemented by".to_string());
+
+        for component in doc.canvas.components.iter_mut() {
+            if component.id == req {
+                component.properties.title = Some("REQ-001 Card payments".to_string());
+                component.properties.content = Some("Accept Visa and Mastercard".to_string());
+                component.properties.status = Some(ItemStatus::InProgress);
+                component.properties.assignee = Some("alice".to_string());
+            } else if component.id == story {
+                component.properties.title = Some("Pay at checkout".to_string());
+                component.properties.content = Some("As a shopper,\nI want to pay by card".to_string());
+                component.properties.priority = Some(Priority::High);
+            } else if component.id == note {
+                component.properties.content = Some("Ask legal about PSD2".to_string());
+            }
+        }
+
+        let expected = "\
+# Checkout
+
+Checkout flow spec
+
+## User Stories
+
+### Pay at checkout
+
+> Priority: High
+
+As a shopper,
+I want to pay by card
+
+## Requirements
+
+### REQ-001 Card payments
+
+> Priority: High | Status: In Progress | Assignee: alice
+
+Accept Visa and Mastercard
+
+## API Endpoints
+
+### GET /api/resource
+
+Endpoint description...
+
+## Notes
+
+Ask legal about PSD2
+
+## Relationships
+
+- Pay at checkout → REQ-001 Card payments (dependency)
+- REQ-001 Card payments → GET /api/resource (flow: implemented by)
+
+";
+        assert_eq!(builder.export_to_markdown(&doc), expected);
+
+        // And the relationships and metadata survive an import
+        let imported = builder.import_from_markdown(expected);
+        assert_eq!(imported.canvas.connections.len(), 2);
+        let imported_req = imported.canvas.components.iter()
+            .find(|c| c.properties.title.as_deref() == Some("REQ-001 Card payments"))
+            .unwrap();
+        assert!(matches!(imported_req.properties.status, Some(ItemStatus::InProgress)));
+        assert_eq!(imported_req.properties.assignee.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_import_unknown_section_becomes_note() {
+        let builder = SpecBuilder::new();
+        let doc = builder.import_from_markdown("# Spec\n\n## Glossary\n\n### SKU\n\nStock keeping unit\n");
+
+        let component = &doc.canvas.components[0];
+        assert!(matches!(component.component_type, ComponentType::Note));
+        assert_eq!(component.properties.title.as_deref(), Some("SKU"));
+        assert_eq!(component.properties.content.as_deref(), Some("Stock keeping unit"));
+        assert_eq!(component.properties.custom.get("section"), Some(&serde_json::json!("Glossary")));
+    }
+
+    #[test]
+    fn test_import_layout_does_not_overlap() {
+        let builder = SpecBuilder::new();
+        let md = "# Spec\n\n## Requirement\n\n### A\n\n### B\n\n### C\n\n### D\n\n### E\n\n### F\n";
+        let doc = builder.import_from_markdown(md);
+        let components = &doc.canvas.components;
+
+        for (i, a) in components.iter().enumerate() {
+            assert_eq!(a.x % doc.canvas.grid_size, 0.0);
+            for b in components.iter().skip(i + 1) {
+                let overlaps = a.x < b.x + b.width && b.x < a.x + a.width
+                    && a.y < b.y + b.height && b.y < a.y + a.height;
+                assert!(!overlaps);
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_dangling_and_missing_fields() {
+        let builder = SpecBuilder::new();
+        let (mut canvas, story, req, conn) = canvas_with_connected_components(&builder);
+        // Remove the target behind the builder's back to leave a dangling connection
+        canvas.components.retain(|c| c.id != req);
+        canvas.components.push(canvas.components[0].clone());
+
+        let mut doc = builder.create_document("Spec", None);
+        doc.canvas = canvas;
+        let issues = builder.validate(&doc);
+
+        assert!(issues.iter().any(|i| i.code == "dangling_connection"
+            && i.connection_id.as_deref() == Some(conn.as_str())
+            && i.severity == IssueSeverity::Error));
+        assert!(issues.iter().any(|i| i.code == "duplicate_component_id"
+            && i.component_id.as_deref() == Some(story.as_str())));
+    }
+
+    #[test]
+    fn test_validate_requirement_without_title() {
+        let builder = SpecBuilder::new();
+        let mut doc = builder.create_document("Spec", None);
+        let req = builder.add_component(&mut doc.canvas, "requirement", 0.0, 0.0).unwrap();
+        doc.canvas.components[0].properties.title = Some("  ".to_string());
+
+        let issues = builder.validate(&doc);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].code, "missing_title");
+        assert_eq!(issues[0].component_id.as_deref(), Some(req.as_str()));
+    }
+
+    #[test]
+    fn test_validate_clean_document() {
+        let builder = SpecBuilder::new();
+        let (canvas, _, _, _) = canvas_with_connected_components(&builder);
+        let mut doc = builder.create_document("Spec", None);
+        doc.canvas = canvas;
+
+        assert!(builder.validate(&doc).is_empty());
+    }
+
+    fn assert_no_overlap(canvas: &Canvas) {
+        for (i, a) in canvas.components.iter().enumerate() {
+            for b in canvas.components.iter().skip(i + 1) {
+                assert!(!BoundingBox::of(a).overlaps(&BoundingBox::of(b)), "{} overlaps {}", a.id, b.id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_auto_layout_grid_respects_locked() {
+        let builder = SpecBuilder::new();
+        let mut canvas = Canvas::default();
+        for _ in 0..6 {
+            builder.add_component(&mut canvas, "requirement", 0.0, 0.0).unwrap();
+        }
+        canvas.components[2].x = 40.0;
+        canvas.components[2].y = 20.0;
+        canvas.components[2].locked = true;
+
+        let bounds = builder.auto_layout(&mut canvas, LayoutStrategy::Grid);
+
+        assert_eq!((canvas.components[2].x, canvas.components[2].y), (40.0, 20.0));
+        assert_no_overlap(&canvas);
+        for component in &canvas.components {
+            assert_eq!(component.x % canvas.grid_size, 0.0);
+            assert!(component.x + component.width <= bounds.x + bounds.width);
+        }
+    }
+
+    #[test]
+    fn test_auto_layout_layered_orders_by_connections() {
+        let builder = SpecBuilder::new();
+        let (mut canvas, story, req, _) = canvas_with_connected_components(&builder);
+        let model = builder.add_component(&mut canvas, "data_model", 0.0, 0.0).unwrap();
+        builder.add_connection(&mut canvas, &req, Anchor::Right, &model, Anchor::Left, ConnectionType::Flow).unwrap();
+        // Cycle back to the start must not hang the layering
+        builder.add_connection(&mut canvas, &model, Anchor::Bottom, &story, Anchor::Top, ConnectionType::Line).unwrap();
+
+        builder.auto_layout(&mut canvas, LayoutStrategy::LayeredByConnections);
+
+        let x_of = |id: &str| canvas.components.iter().find(|c| c.id == id).unwrap().x;
+        assert!(x_of(&story) < x_of(&req));
+        assert!(x_of(&req) < x_of(&model));
+        assert_no_overlap(&canvas);
+    }
+
+    #[test]
+    fn test_undo_delete_restores_component_and_connections() {
+        let builder = SpecBuilder::new();
+        let (mut canvas, story, _req, conn) = canvas_with_connected_components(&builder);
+        let mut history = CanvasHistory::default();
+
+        history.delete_component(&builder, &mut canvas, &story).unwrap();
+        assert_eq!(canvas.components.len(), 1);
+        assert!(canvas.connections.is_empty());
+
+        assert!(history.undo(&mut canvas));
+        assert_eq!(canvas.components[0].id, story);
+        assert_eq!(canvas.connections.len(), 1);
+        assert_eq!(canvas.connections[0].id, conn);
+        assert!(history.can_redo());
+
+        assert!(history.redo(&mut canvas));
+        assert_eq!(canvas.components.len(), 1);
+        assert!(canvas.connections.is_empty());
+    }
+
+    #[test]
+    fn test_undo_update_and_add() {
+        let builder = SpecBuilder::new();
+        let mut canvas = Canvas::default();
+        let mut history = CanvasHistory::default();
+
+        let id = history.add_component(&builder, &mut canvas, "note", 0.0, 0.0).unwrap();
+        history.update_component(&builder, &mut canvas, &id, ComponentUpdate {
+            x: Some(200.0),
+            y: None,
+            width: None,
+            height: None,
+            rotation: None,
+            locked: None,
+            visible: None,
+            properties: None,
+            style: None,
+        }).unwrap();
+        assert_eq!(canvas.components[0].x, 200.0);
+
+        assert!(history.undo(&mut canvas));
+        assert_eq!(canvas.components[0].x, 0.0);
+        assert!(history.undo(&mut canvas));
+        assert!(canvas.components.is_empty());
+        assert!(!history.can_undo());
+        assert!(!history.undo(&mut canvas));
+    }
+
+    #[test]
+    fn test_history_depth_is_bounded() {
+        let builder = SpecBuilder::new();
+        let mut canvas = Canvas::default();
+        let mut history = CanvasHistory::new(2);
+
+        for _ in 0..3 {
+            history.add_component(&builder, &mut canvas, "note", 0.0, 0.0).unwrap();
+        }
+
+        assert!(history.undo(&mut canvas));
+        assert!(history.undo(&mut canvas));
+        assert!(!history.can_undo());
+        assert_eq!(canvas.components.len(), 1);
+    }
+
+    #[test]
+    fn test_new_mutation_clears_redo() {
+        let builder = SpecBuilder::new();
+        let mut canvas = Canvas::default();
+        let mut history = CanvasHistory::default();
+
+        history.add_component(&builder, &mut canvas, "note", 0.0, 0.0).unwrap();
+        history.undo(&mut canvas);
+        assert!(history.can_redo());
+
+        history.add_component(&builder, &mut canvas, "comment", 0.0, 0.0).unwrap();
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_move_group_translates_children() {
+        let builder = SpecBuilder::new();
+        let (mut canvas, story, req, _conn) = canvas_with_connected_components(&builder);
+        let note = builder.add_component(&mut canvas, "note", 0.0, 400.0).unwrap();
+
+        let group = builder.group_components(&mut canvas, &[story.clone(), req.clone()], None, true).unwrap();
+        assert_eq!(canvas.groups[0].name, "Frame 1");
+        assert!(builder.group_components(&mut canvas, &[req.clone(), note.clone()], None, false).is_err());
+
+        let position = |canvas: &Canvas, id: &str| {
+            let c = canvas.components.iter().find(|c| c.id == id).unwrap();
+            (c.x, c.y)
+        };
+        // Offsets snap to the grid so members stay aligned with it
+        builder.move_group(&mut canvas, &group, 100.0, 38.0).unwrap();
+        assert_eq!(position(&canvas, &story), (100.0, 40.0));
+        assert_eq!(position(&canvas, &req), (600.0, 40.0));
+        assert_eq!(position(&canvas, &note), (0.0, 400.0));
+
+        // A member locked on its own stays put while the rest move
+        canvas.components.iter_mut().find(|c| c.id == req).unwrap().locked = true;
+        builder.move_group(&mut canvas, &group, 20.0, 0.0).unwrap();
+        assert_eq!(position(&canvas, &story), (120.0, 40.0));
+        assert_eq!(position(&canvas, &req), (600.0, 40.0));
+        canvas.components.iter_mut().find(|c| c.id == req).unwrap().locked = false;
+
+        builder.update_group(&mut canvas, &group, GroupUpdate { locked: Some(true), ..Default::default() }).unwrap();
+        assert!(builder.move_group(&mut canvas, &group, 20.0, 0.0).is_err());
+        assert!(canvas.components.iter().filter(|c| c.id != note).all(|c| canvas.is_locked(c)));
+
+        builder.update_group(&mut canvas, &group, GroupUpdate { visible: Some(false), ..Default::default() }).unwrap();
+        let doc = SpecDocument { canvas, ..builder.create_document("Frames", None) };
+        let svg = builder.export_to_svg(&doc);
+        assert!(!svg.contains(&story) && !svg.contains(&req));
+        assert!(svg.contains(&note));
+    }
+
+    #[test]
+    fn test_ungroup_restores_independence() {
+        let builder = SpecBuilder::new();
+        let (mut canvas, story, req, _conn) = canvas_with_connected_components(&builder);
+        canvas.components[1].visible = false;
+
+        let group = builder.group_components(&mut canvas, &[story.clone(), req.clone()], Some("Checkout"), false).unwrap();
+        builder.update_group(&mut canvas, &group, GroupUpdate {
+            locked: Some(true),
+            visible: Some(false),
+            ..Default::default()
+        }).unwrap();
+        assert!(!canvas.is_visible(&canvas.components[0]));
+
+        assert_eq!(builder.ungroup(&mut canvas, &group).unwrap(), vec![story.clone(), req.clone()]);
+        assert!(canvas.groups.is_empty());
+        assert!(builder.move_group(&mut canvas, &group, 20.0, 0.0).is_err());
+
+        // Members get their own lock and visibility back, and move alone
+        assert!(!canvas.is_locked(&canvas.components[0]));
+        assert!(canvas.is_visible(&canvas.components[0]));
+        assert!(!canvas.is_visible(&canvas.components[1]));
+        builder.update_component(&mut canvas, &story, ComponentUpdate {
+            x: Some(200.0),
+            y: None,
+            width: None,
+            height: None,
+            rotation: None,
+            locked: None,
+            visible: None,
+            properties: None,
+            style: None,
+        }).unwrap();
+        assert_eq!((canvas.components[0].x, canvas.components[1].x), (200.0, 500.0));
+    }
+
+    #[test]
+    fn test_delete_grouped_component_updates_group_and_undo_restores_it() {
+        let builder = SpecBuilder::new();
+        let (mut canvas, story, req, _conn) = canvas_with_connected_components(&builder);
+        let group = builder.group_components(&mut canvas, &[story.clone(), req.clone()], None, false).unwrap();
+        let mut history = CanvasHistory::default();
+
+        history.delete_component(&builder, &mut canvas, &story).unwrap();
+        assert_eq!(canvas.groups[0].component_ids, vec![req.clone()]);
+        history.delete_component(&builder, &mut canvas, &req).unwrap();
+        assert!(canvas.groups.is_empty());
+
+        assert!(history.undo(&mut canvas));
+        assert!(history.undo(&mut canvas));
+        assert_eq!(canvas.groups[0].id, group);
+        assert_eq!(canvas.groups[0].component_ids, vec![story, req]);
+    }
+
+    #[test]
+    fn test_undo_add_takes_component_out_of_its_group() {
+        let builder = SpecBuilder::new();
+        let (mut canvas, story, req, _conn) = canvas_with_connected_components(&builder);
+        let mut history = CanvasHistory::default();
+
+        let note = history.add_component(&builder, &mut canvas, "note", 0.0, 400.0).unwrap();
+        builder.group_components(&mut canvas, &[story.clone(), note.clone()], None, false).unwrap();
+        let solo = builder.group_components(&mut canvas, &[req], None, false).unwrap();
+        let only_note = history.add_component(&builder, &mut canvas, "note", 0.0, 600.0).unwrap();
+        builder.group_components(&mut canvas, &[only_note], None, false).unwrap();
+
+        assert!(history.undo(&mut canvas));
+        assert!(history.undo(&mut canvas));
+        assert_eq!(canvas.groups.len(), 2);
+        assert_eq!(canvas.groups[0].component_ids, vec![story]);
+        assert_eq!(canvas.groups[1].id, solo);
+    }
+
+    #[test]
+    fn test_groups_survive_markdown_round_trip() {
+        let builder = SpecBuilder::new();
+        let mut doc = builder.create_document("Groups", None);
+        let (canvas, story, req, _conn) = canvas_with_connected_components(&builder);
+        doc.canvas = canvas;
+        for component in doc.canvas.components.iter_mut() {
+            component.properties.title = Some(if component.id == story { "Pay".to_string() } else { "REQ-1".to_string() });
+        }
+        builder.group_components(&mut doc.canvas, &[story, req], Some("Checkout"), true).unwrap();
+
+        let md = builder.export_to_markdown(&doc);
+        assert!(md.ends_with("## Groups\n\n- Checkout (frame): Pay, REQ-1\n\n"), "{}", md);
+
+        let imported = builder.import_from_markdown(&md);
+        assert_eq!(imported.canvas.groups.len(), 1);
+        let group = &imported.canvas.groups[0];
+        assert_eq!((group.name.as_str(), group.frame, group.component_ids.len()), ("Checkout", true, 2));
+        assert_eq!(imported.canvas.components.len(), 2);
+    }
+
+    #[test]
+    fn test_align_left_uses_leftmost_edge_and_skips_locked() {
+        let builder = SpecBuilder::new();
+        let mut canvas = Canvas::default();
+        let ids: Vec<String> = [(140.0, 0.0), (60.0, 100.0), (300.0, 200.0)].iter()
+            .map(|&(x, y)| builder.add_component(&mut canvas, "note", x, y).unwrap())
+            .collect();
+
+        let updated = builder.align(&mut canvas, &ids, AlignEdge::Left).unwrap();
+        assert_eq!(updated.iter().map(|c| c.id.clone()).collect::<Vec<_>>(), ids);
+        assert!(updated.iter().all(|c| c.x == 60.0));
+
+        // A locked component is the line the others align to and never moves
+        canvas.components[2].x = 300.0;
+        canvas.components[2].locked = true;
+        let updated = builder.align(&mut canvas, &ids, AlignEdge::Right).unwrap();
+        let right = 300.0 + canvas.components[2].width;
+        assert!(updated.iter().all(|c| c.x + c.width == right));
+        assert_eq!(canvas.components[2].x, 300.0);
+
+        assert!(builder.align(&mut canvas, &["missing".to_string()], AlignEdge::Top).is_err());
+    }
+
+    #[test]
+    fn test_distribute_produces_even_gaps() {
+        let builder = SpecBuilder::new();
+        let mut canvas = Canvas::default();
+        let ids: Vec<String> = [0.0, 100.0, 180.0, 600.0].iter()
+            .map(|&x| builder.add_component(&mut canvas, "note", x, 0.0).unwrap())
+            .collect();
+        for component in &mut canvas.components {
+            component.width = 100.0;
+        }
+
+        builder.distribute(&mut canvas, &ids, Axis::Horizontal).unwrap();
+        let xs: Vec<f64> = canvas.components.iter().map(|c| c.x).collect();
+        assert_eq!(xs, vec![0.0, 200.0, 400.0, 600.0]);
+        assert!(canvas.components.iter().all(|c| c.y == 0.0));
+
+        // Locked components split the run; the ones between them still space evenly
+        canvas.components[1].locked = true;
+        canvas.components[1].x = 120.0;
+        builder.distribute(&mut canvas, &ids, Axis::Horizontal).unwrap();
+        let xs: Vec<f64> = canvas.components.iter().map(|c| c.x).collect();
+        assert_eq!(xs, vec![0.0, 120.0, 360.0, 600.0]);
+    }
+
+    /// Two requirements behind a story, the pair framed, with fixed ids so
+    /// exports are stable
+    fn small_graph(builder: &SpecBuilder) -> SpecDocument {
+        let mut doc = builder.create_document("Checkout", None);
+        doc.id = "doc-1".to_string();
+        let canvas = &mut doc.canvas;
+        for (id, component_type, x, y, title) in [
+            ("story", "user_story", 0.0, 0.0, "Buyer pays"),
+            ("card", "requirement", 400.0, 0.0, "Card \"3-D\" check"),
+            ("orders", "data_model", 400.0, 200.0, "Orders"),
+        ] {
+            let created = builder.add_component(canvas, component_type, x, y).unwrap();
+            let component = canvas.components.iter_mut().find(|c| c.id == created).unwrap();
+            component.id = id.to_string();
+            component.properties.title = Some(title.to_string());
+            component.properties.content = None;
+        }
+        canvas.components[1].properties.content = Some("Uses <3DS>".to_string());
+
+        builder.add_connection(canvas, "story", Anchor::Right, "card", Anchor::Left, ConnectionType::Arrow).unwrap();
+        builder.add_connection(canvas, "card", Anchor::Bottom, "orders", Anchor::Center, ConnectionType::Dependency).unwrap();
+        canvas.connections[0].id = "e1".to_string();
+        canvas.connections[0].label = Some("needs".to_string());
+        canvas.connections[1].id = "e2".to_string();
+
+        builder.group_components(canvas, &["card".to_string(), "orders".to_string()], Some("Payments"), true).unwrap();
+        canvas.groups[0].id = "pay".to_string();
+        doc
+    }
+
+    #[test]
+    fn test_export_to_svg_paints_visible_components_in_z_order() {
+        let builder = SpecBuilder::new();
+        let mut doc = small_graph(&builder);
+        doc.canvas.components[0].z_index = 10;
+
+        let svg = builder.export(&doc, "svg").unwrap();
+        resvg::usvg::Tree::from_str(&svg, &resvg::usvg::Options::default()).unwrap();
+        assert!(svg.contains("<title>Checkout</title>"));
+        assert!(svg.contains("Card &quot;3-D&quot; check"));
+        assert!(svg.contains("Uses &lt;3DS&gt;"));
+        assert!(svg.contains("<marker id=\"arrow-0\""));
+        // The story is raised above the grouped components
+        assert!(svg.find("id=\"story\"").unwrap() > svg.find("id=\"orders\"").unwrap());
+
+        // Hidden components take their connections with them
+        doc.canvas.components[2].visible = false;
+        let svg = builder.export(&doc, "svg").unwrap();
+        assert!(!svg.contains("id=\"orders\""));
+        assert!(svg.contains("<marker id=\"arrow-0\""));
+        assert!(!svg.contains("arrow-1"));
+    }
+
+    #[test]
+    fn test_export_to_png_rasterizes_the_svg() {
+        let builder = SpecBuilder::new();
+        let mut doc = small_graph(&builder);
+        doc.canvas.width = 1000.0;
+        doc.canvas.height = 600.0;
+
+        let png = builder.export_to_png(&doc, 2.0).unwrap();
+        assert!(png.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]));
+        let dimension = |offset: usize| u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap());
+        assert_eq!((dimension(16), dimension(20)), (2000, 1200));
+
+        // Inside the story card, clear of its title
+        let pixmap = resvg::tiny_skia::Pixmap::decode_png(&png).unwrap();
+        let pixel = pixmap.pixel(400, 340).unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (0xfe, 0xf3, 0xc7));
+
+        // The export command hands it over base64-encoded
+        assert_eq!(BASE64.decode(builder.export(&doc, "png").unwrap()).unwrap(), png);
+    }
+
+    #[test]
+    fn test_export_to_mermaid_snapshot() {
+        let builder = SpecBuilder::new();
+        let doc = small_graph(&builder);
+
+        let expected = r#"---
+title: "Checkout"
+---
+flowchart TD
+    n0(["Buyer pays"])
+    subgraph g0["Payments"]
+        n1["Card #quot;3-D#quot; check<br/>Uses #lt;3DS#gt;"]
+        n2[("Orders")]
+    end
+    n0 -->|"needs"| n1
+    n1 -.-> n2
+"#;
+        assert_eq!(builder.export(&doc, "mermaid").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_export_to_mermaid_quotes_title() {
+        let builder = SpecBuilder::new();
+        let mut doc = small_graph(&builder);
+
+        doc.name = "Checkout: v2".to_string();
+        let expected = r#"---
+title: "Checkout: v2"
+---
+flowchart TD
+    n0(["Buyer pays"])
+    subgraph g0["Payments"]
+        n1["Card #quot;3-D#quot; check<br/>Uses #lt;3DS#gt;"]
+        n2[("Orders")]
+    end
+    n0 -->|"needs"| n1
+    n1 -.-> n2
+"#;
+        assert_eq!(builder.export(&doc, "mermaid").unwrap(), expected);
+
+        // Quotes and backslashes are escaped; line breaks can't end the front matter
+
+        doc.name = "Say \"hi\" \\ bye\r\n---\nflowchart LR".to_string();
+        let mermaid = builder.export(&doc, "mermaid").unwrap();
+        assert!(mermaid.starts_with("---\ntitle: \"Say \\\"hi\\\" \\\\ bye --- flowchart LR\"\n---\nflowchart TD\n"), "{}", mermaid);
+    }
+
+    #[test]
+    fn test_export_to_drawio_snapshot() {
+        let builder = SpecBuilder::new();
+        let doc = small_graph(&builder);
+
+        let expected = r##"<mxfile host="SmartSpec">
+  <diagram id="doc-1" name="Checkout">
+    <mxGraphModel grid="1" gridSize="20" guides="1" tooltips="1" connect="1" arrows="1" fold="1" page="1" pageScale="1" pageWidth="1920" pageHeight="1080" math="0" shadow="0">
+      <root>
+        <mxCell id="0"/>
+        <mxCell id="1" parent="0"/>
+        <mxCell id="story" value="Buyer pays" style="rounded=1;arcSize=50;whiteSpace=wrap;html=0;fillColor=#fef3c7;strokeColor=#f59e0b;fontColor=#1f2937;strokeWidth=1;fontSize=14;" vertex="1" parent="1">
+          <mxGeometry x="0" y="0" width="400" height="180" as="geometry"/>
+        </mxCell>
+        <mxCell id="group-pay" value="Payments" style="rounded=1;dashed=1;fillColor=none;strokeColor=#9ca3af;container=1;collapsible=0;verticalAlign=top;align=left;spacingLeft=8;fontColor=#6b7280;" vertex="1" connectable="0" parent="1">
+          <mxGeometry x="384" y="-16" width="382" height="482" as="geometry"/>
+        </mxCell>
+        <mxCell id="card" value="Card &quot;3-D&quot; check&#xa;Uses &lt;3DS&gt;" style="rounded=1;whiteSpace=wrap;html=0;fillColor=#dbeafe;strokeColor=#3b82f6;fontColor=#1f2937;strokeWidth=1;fontSize=14;" vertex="1" parent="group-pay">
+          <mxGeometry x="16" y="16" width="350" height="120" as="geometry"/>
+        </mxCell>
+        <mxCell id="orders" value="Orders" style="shape=cylinder3;boundedLbl=1;backgroundOutline=1;size=12;whiteSpace=wrap;html=0;fillColor=#fce7f3;strokeColor=#ec4899;fontColor=#1f2937;strokeWidth=1;fontSize=14;" vertex="1" parent="group-pay">
+          <mxGeometry x="16" y="216" width="300" height="250" as="geometry"/>
+        </mxCell>
+        <mxCell id="e1" value="needs" style="edgeStyle=orthogonalEdgeStyle;rounded=1;html=0;endArrow=classic;strokeColor=#6b7280;strokeWidth=2;exitX=1;exitY=0.5;exitDx=0;exitDy=0;entryX=0;entryY=0.5;entryDx=0;entryDy=0;" edge="1" parent="1" source="story" target="card">
+          <mxGeometry relative="1" as="geometry"/>
+        </mxCell>
+        <mxCell id="e2" value="" style="edgeStyle=orthogonalEdgeStyle;rounded=1;html=0;dashed=1;dashPattern=2 4;endArrow=open;strokeColor=#6b7280;strokeWidth=2;exitX=0.5;exitY=1;exitDx=0;exitDy=0;" edge="1" parent="1" source="card" target="orders">
+          <mxGeometry relative="1" as="geometry"/>
+        </mxCell>
+      </root>
+    </mxGraphModel>
+  </diagram>
+</mxfile>
+"##;
+        assert_eq!(builder.export(&doc, "drawio").unwrap(), expected);
+        assert!(builder.export(&doc, "visio").is_err());
+    }
 }
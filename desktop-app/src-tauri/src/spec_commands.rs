@@ -13,8 +13,9 @@ use tokio::sync::Mutex;
 
 use crate::spec_builder::{
     SpecBuilder, SpecDocument, Canvas, CanvasComponent, Connection,
-    ComponentLibrary, ComponentCategory, ComponentUpdate,
-    Anchor, ConnectionType, ConnectionStyle,
+    ComponentLibrary, ComponentCategory, ComponentUpdate, GroupUpdate,
+    Anchor, ConnectionType, ConnectionStyle, CanvasHistory, HistoryState,
+    LayoutStrategy, BoundingBox, AlignEdge, Axis, SpecIssue, IssueSeverity, SpecStatus,
 };
 
 // ============================================
@@ -24,6 +25,7 @@ use crate::spec_builder::{
 pub struct SpecBuilderState {
     pub builder: SpecBuilder,
     pub documents: HashMap<String, SpecDocument>,
+    pub histories: HashMap<String, CanvasHistory>,
 }
 
 impl SpecBuilderState {
@@ -31,6 +33,7 @@ impl SpecBuilderState {
         Self {
             builder: SpecBuilder::new(),
             documents: HashMap::new(),
+            histories: HashMap::new(),
         }
     }
 }
@@ -117,7 +120,23 @@ pub async fn spec_save_document(
 ) -> Result<(), String> {
     let mut state = state.lock().await;
     let mut doc = document;
+
+    // Promotion to Approved requires an error-free spec
+    let was_approved = state.documents.get(&doc.id)
+        .map(|d| matches!(d.metadata.status, SpecStatus::Approved))
+        .unwrap_or(false);
+    if matches!(doc.metadata.status, SpecStatus::Approved) && !was_approved {
+        let errors = state.builder.validate(&doc).into_iter()
+            .filter(|i| i.severity == IssueSeverity::Error)
+            .count();
+        if errors > 0 {
+            return Err(format!("Cannot approve spec with {} validation error(s)", errors));
+        }
+    }
+
     doc.updated_at = chrono::Utc::now().timestamp();
+    // The saved canvas replaces whatever the history was tracking
+    state.histories.remove(&doc.id);
     state.documents.insert(doc.id.clone(), doc);
     Ok(())
 }
@@ -130,6 +149,7 @@ pub async fn spec_delete_document(
     let mut state = state.lock().await;
     state.documents.remove(&document_id)
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
+    state.histories.remove(&document_id);
     Ok(())
 }
 
@@ -148,9 +168,64 @@ pub async fn spec_update_canvas(
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
     doc.canvas = canvas;
     doc.updated_at = chrono::Utc::now().timestamp();
+    state.histories.remove(&document_id);
     Ok(())
 }
 
+#[tauri::command]
+pub async fn spec_auto_layout(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+    strategy: LayoutStrategy,
+) -> Result<BoundingBox, String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    let bounds = state.builder.auto_layout(&mut doc.canvas, strategy);
+    doc.updated_at = chrono::Utc::now().timestamp();
+    // Layout moves many components at once; it isn't tracked as a single undo step
+    state.histories.remove(&document_id);
+    Ok(bounds)
+}
+
+#[tauri::command]
+pub async fn spec_align_components(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+    component_ids: Vec<String>,
+    edge: AlignEdge,
+) -> Result<Vec<CanvasComponent>, String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    let updated = state.builder.align(&mut doc.canvas, &component_ids, edge)?;
+    doc.updated_at = chrono::Utc::now().timestamp();
+    state.histories.remove(&document_id);
+    Ok(updated)
+}
+
+#[tauri::command]
+pub async fn spec_distribute_components(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+    component_ids: Vec<String>,
+    axis: Axis,
+) -> Result<Vec<CanvasComponent>, String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    let updated = state.builder.distribute(&mut doc.canvas, &component_ids, axis)?;
+    doc.updated_at = chrono::Utc::now().timestamp();
+    state.histories.remove(&document_id);
+    Ok(updated)
+}
+
 // ============================================
 // Component Commands
 // ============================================
@@ -163,11 +238,13 @@ pub async fn spec_add_component(
     x: f64,
     y: f64,
 ) -> Result<String, String> {
-    let mut state = state.lock().await;
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
     let doc = state.documents.get_mut(&document_id)
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
-    
-    let component_id = state.builder.add_component(&mut doc.canvas, &template_id, x, y)?;
+    let history = state.histories.entry(document_id.clone()).or_default();
+
+    let component_id = history.add_component(&state.builder, &mut doc.canvas, &template_id, x, y)?;
     doc.updated_at = chrono::Utc::now().timestamp();
     Ok(component_id)
 }
@@ -179,11 +256,13 @@ pub async fn spec_update_component(
     component_id: String,
     updates: ComponentUpdate,
 ) -> Result<(), String> {
-    let mut state = state.lock().await;
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
     let doc = state.documents.get_mut(&document_id)
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
-    
-    state.builder.update_component(&mut doc.canvas, &component_id, updates)?;
+    let history = state.histories.entry(document_id.clone()).or_default();
+
+    history.update_component(&state.builder, &mut doc.canvas, &component_id, updates)?;
     doc.updated_at = chrono::Utc::now().timestamp();
     Ok(())
 }
@@ -194,11 +273,13 @@ pub async fn spec_delete_component(
     document_id: String,
     component_id: String,
 ) -> Result<(), String> {
-    let mut state = state.lock().await;
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
     let doc = state.documents.get_mut(&document_id)
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
-    
-    state.builder.delete_component(&mut doc.canvas, &component_id)?;
+    let history = state.histories.entry(document_id.clone()).or_default();
+
+    history.delete_component(&state.builder, &mut doc.canvas, &component_id)?;
     doc.updated_at = chrono::Utc::now().timestamp();
     Ok(())
 }
@@ -279,6 +360,86 @@ pub async fn spec_move_component_to_back(
     Ok(())
 }
 
+// ============================================
+// Group Commands
+// ============================================
+
+#[tauri::command]
+pub async fn spec_group_components(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+    component_ids: Vec<String>,
+    name: Option<String>,
+    frame: Option<bool>,
+) -> Result<String, String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    let group_id = state.builder.group_components(
+        &mut doc.canvas,
+        &component_ids,
+        name.as_deref(),
+        frame.unwrap_or(false),
+    )?;
+    doc.updated_at = chrono::Utc::now().timestamp();
+    Ok(group_id)
+}
+
+#[tauri::command]
+pub async fn spec_ungroup(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+    group_id: String,
+) -> Result<Vec<String>, String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    let component_ids = state.builder.ungroup(&mut doc.canvas, &group_id)?;
+    doc.updated_at = chrono::Utc::now().timestamp();
+    Ok(component_ids)
+}
+
+#[tauri::command]
+pub async fn spec_move_group(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+    group_id: String,
+    dx: f64,
+    dy: f64,
+) -> Result<(), String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    state.builder.move_group(&mut doc.canvas, &group_id, dx, dy)?;
+    doc.updated_at = chrono::Utc::now().timestamp();
+    // Like auto layout, this moves several components and isn't an undo step
+    state.histories.remove(&document_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn spec_update_group(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+    group_id: String,
+    updates: GroupUpdate,
+) -> Result<(), String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    state.builder.update_group(&mut doc.canvas, &group_id, updates)?;
+    doc.updated_at = chrono::Utc::now().timestamp();
+    Ok(())
+}
+
 // ============================================
 // Connection Commands
 // ============================================
@@ -293,15 +454,18 @@ pub async fn spec_add_connection(
     to_anchor: String,
     connection_type: String,
 ) -> Result<String, String> {
-    let mut state = state.lock().await;
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
     let doc = state.documents.get_mut(&document_id)
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
-    
+    let history = state.histories.entry(document_id.clone()).or_default();
+
     let from_anchor = parse_anchor(&from_anchor)?;
     let to_anchor = parse_anchor(&to_anchor)?;
     let connection_type = parse_connection_type(&connection_type)?;
-    
-    let connection_id = state.builder.add_connection(
+
+    let connection_id = history.add_connection(
+        &state.builder,
         &mut doc.canvas,
         &from_component,
         from_anchor,
@@ -320,15 +484,69 @@ pub async fn spec_delete_connection(
     document_id: String,
     connection_id: String,
 ) -> Result<(), String> {
-    let mut state = state.lock().await;
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
     let doc = state.documents.get_mut(&document_id)
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
-    
-    state.builder.delete_connection(&mut doc.canvas, &connection_id)?;
+    let history = state.histories.entry(document_id.clone()).or_default();
+
+    history.delete_connection(&state.builder, &mut doc.canvas, &connection_id)?;
     doc.updated_at = chrono::Utc::now().timestamp();
     Ok(())
 }
 
+// ============================================
+// History Commands
+// ============================================
+
+#[tauri::command]
+pub async fn spec_undo(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+) -> Result<HistoryState, String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+    let history = state.histories.entry(document_id.clone()).or_default();
+
+    if history.undo(&mut doc.canvas) {
+        doc.updated_at = chrono::Utc::now().timestamp();
+    }
+    Ok(history.state())
+}
+
+#[tauri::command]
+pub async fn spec_redo(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+) -> Result<HistoryState, String> {
+    let mut guard = state.lock().await;
+    let state = &mut *guard;
+    let doc = state.documents.get_mut(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+    let history = state.histories.entry(document_id.clone()).or_default();
+
+    if history.redo(&mut doc.canvas) {
+        doc.updated_at = chrono::Utc::now().timestamp();
+    }
+    Ok(history.state())
+}
+
+#[tauri::command]
+pub async fn spec_get_history_state(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+) -> Result<HistoryState, String> {
+    let state = state.lock().await;
+    if !state.documents.contains_key(&document_id) {
+        return Err(format!("Document not found: {}", document_id));
+    }
+    Ok(state.histories.get(&document_id)
+        .map(|h| h.state())
+        .unwrap_or(HistoryState { can_undo: false, can_redo: false }))
+}
+
 fn parse_anchor(s: &str) -> Result<Anchor, String> {
     match s.to_lowercase().as_str() {
         "top" => Ok(Anchor::Top),
@@ -351,6 +569,22 @@ fn parse_connection_type(s: &str) -> Result<ConnectionType, String> {
     }
 }
 
+// ============================================
+// Validation Commands
+// ============================================
+
+#[tauri::command]
+pub async fn spec_validate(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+) -> Result<Vec<SpecIssue>, String> {
+    let state = state.lock().await;
+    let doc = state.documents.get(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    Ok(state.builder.validate(doc))
+}
+
 // ============================================
 // Export Commands
 // ============================================
@@ -364,7 +598,7 @@ pub async fn spec_export_markdown(
     let doc = state.documents.get(&document_id)
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
     
-    Ok(state.builder.export_to_markdown(doc))
+    state.builder.export(doc, "markdown")
 }
 
 #[tauri::command]
@@ -376,5 +610,37 @@ pub async fn spec_export_json(
     let doc = state.documents.get(&document_id)
         .ok_or_else(|| format!("Document not found: {}", document_id))?;
     
-    state.builder.export_to_json(doc)
+    state.builder.export(doc, "json")
+}
+
+#[tauri::command]
+pub async fn spec_export(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    document_id: String,
+    format: String,
+) -> Result<String, String> {
+    let state = state.lock().await;
+    let doc = state.documents.get(&document_id)
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+    state.builder.export(doc, &format)
+}
+
+#[tauri::command]
+pub async fn spec_import(
+    state: State<'_, Arc<Mutex<SpecBuilderState>>>,
+    content: String,
+    format: String,
+) -> Result<SpecDocument, String> {
+    let mut state = state.lock().await;
+
+    let doc = match format.to_lowercase().as_str() {
+        "markdown" | "md" => state.builder.import_from_markdown(&content),
+        "json" => serde_json::from_str::<SpecDocument>(&content)
+            .map_err(|e| format!("Failed to parse spec: {}", e))?,
+        _ => return Err(format!("Invalid import format: {}", format)),
+    };
+
+    state.documents.insert(doc.id.clone(), doc.clone());
+    Ok(doc)
 }
@@ -5,14 +5,15 @@
 // - Template metadata retrieval
 // - Project generation
 
-use tauri::{State, Window};
+use tauri::{AppHandle, Emitter, State, Window};
 use std::sync::Arc;
 use std::path::PathBuf;
 use tokio::sync::Mutex;
 
 use crate::template_engine::{
     TemplateEngine, TemplateEntry, TemplateMetadata, TemplateCategory,
-    ConfigSchema, ProjectConfig, GenerationResult, GenerationProgress,
+    ConfigSchema, ProjectConfig, GenerationResult, GenerationProgress, ProjectPreview,
+    TemplateLintError,
 };
 
 // ============================================
@@ -124,6 +125,16 @@ pub async fn template_get_config_schema(
     engine.get_config_schema(&template_id).await
 }
 
+#[tauri::command]
+pub async fn template_refresh(
+    state: State<'_, Arc<Mutex<TemplateState>>>,
+    template_id: String,
+) -> Result<(), String> {
+    let state = state.lock().await;
+    let engine = state.engine.lock().await;
+    engine.refresh_remote_template(&template_id).await
+}
+
 // ============================================
 // Generation Commands
 // ============================================
@@ -145,8 +156,74 @@ pub async fn template_generate_project(
     engine.generate_project(config, progress_callback).await
 }
 
+/// Event `template_generate_project_streaming` emits on unless told otherwise
+pub const GENERATION_PROGRESS_EVENT: &str = "template:progress";
+
+/// Payload of streamed generation progress; `generation_id` lets the
+/// frontend route updates when several generations run at once
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GenerationProgressEvent {
+    pub generation_id: String,
+    pub progress: GenerationProgress,
+}
+
+/// Tag each progress update with `generation_id` and hand it to `emit`
+fn forward_progress(
+    generation_id: String,
+    emit: impl Fn(GenerationProgressEvent) + Send + 'static,
+) -> impl Fn(GenerationProgress) + Send + 'static {
+    move |progress| emit(GenerationProgressEvent {
+        generation_id: generation_id.clone(),
+        progress,
+    })
+}
+
+/// Generate a project, emitting every progress update as `event` (by
+/// default `template:progress`) keyed by `generation_id`
+#[tauri::command]
+pub async fn template_generate_project_streaming(
+    state: State<'_, Arc<Mutex<TemplateState>>>,
+    app: AppHandle,
+    config: ProjectConfig,
+    generation_id: String,
+    event: Option<String>,
+) -> Result<GenerationResult, String> {
+    let state = state.lock().await;
+    let engine = state.engine.lock().await;
+
+    let event = event.unwrap_or_else(|| GENERATION_PROGRESS_EVENT.to_string());
+    let progress_callback = forward_progress(generation_id, move |payload| {
+        let _ = app.emit(&event, payload);
+    });
+
+    engine.generate_project(config, progress_callback).await
+}
+
+#[tauri::command]
+pub async fn template_preview_project(
+    state: State<'_, Arc<Mutex<TemplateState>>>,
+    config: ProjectConfig,
+) -> Result<ProjectPreview, String> {
+    let state = state.lock().await;
+    let engine = state.engine.lock().await;
+    engine.preview_project(config).await
+}
+
+/// Report template variables that don't resolve for this config
+#[tauri::command]
+pub async fn template_lint(
+    state: State<'_, Arc<Mutex<TemplateState>>>,
+    template_id: String,
+    config: ProjectConfig,
+) -> Result<Vec<TemplateLintError>, String> {
+    let state = state.lock().await;
+    let engine = state.engine.lock().await;
+    engine.lint_template(ProjectConfig { template_id, ..config }).await
+}
+
 #[tauri::command]
 pub async fn template_validate_config(
+    state: State<'_, Arc<Mutex<TemplateState>>>,
     config: ProjectConfig,
 ) -> Result<ValidationResult, String> {
     let mut errors = Vec::new();
@@ -159,11 +236,10 @@ pub async fn template_validate_config(
             message: "Project name is required".to_string(),
         });
     } else {
-        let name_regex = regex::Regex::new(r"^[a-z][a-z0-9-]*$").unwrap();
-        if !name_regex.is_match(&config.project_name) {
+        if let Err(message) = crate::input_validation::validate_resource_name(&config.project_name, "Project name") {
             errors.push(ValidationError {
                 field: "project_name".to_string(),
-                message: "Project name must be lowercase, start with a letter, and contain only letters, numbers, and hyphens".to_string(),
+                message,
             });
         }
     }
@@ -192,6 +268,22 @@ pub async fn template_validate_config(
         }
     }
 
+    // Validate template variables against the config schema
+    {
+        let state = state.lock().await;
+        let engine = state.engine.lock().await;
+        for error in engine.validate_variables(&config).await? {
+            // project_name is already reported above with a friendlier message
+            if error.field == "project_name" && errors.iter().any(|e| e.field == "project_name") {
+                continue;
+            }
+            errors.push(ValidationError {
+                field: error.field,
+                message: error.message,
+            });
+        }
+    }
+
     Ok(ValidationResult {
         valid: errors.is_empty(),
         errors,
@@ -301,3 +393,49 @@ pub struct QuickStartResource {
     pub url: String,
     pub icon: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_streamed_progress_is_ordered_and_completes() {
+        let dir = tempfile::tempdir().unwrap();
+        let files_dir = dir.path().join("demo").join("files");
+        std::fs::create_dir_all(&files_dir).unwrap();
+        for i in 0..50 {
+            std::fs::write(files_dir.join(format!("file{:02}.txt.hbs", i)), "{{project_name}}").unwrap();
+        }
+
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        engine.set_generation_concurrency(8);
+        let config = ProjectConfig {
+            template_id: "demo".to_string(),
+            project_name: "shop".to_string(),
+            project_description: None,
+            output_path: dir.path().join("out").to_string_lossy().to_string(),
+            features: vec![],
+            variables: HashMap::new(),
+            keep_partial_on_error: false,
+            strict_variables: false,
+            approved_hooks: vec![],
+        };
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let callback = forward_progress("gen-1".to_string(), move |event| sink.lock().unwrap().push(event));
+        let result = engine.generate_project(config, callback).await.unwrap();
+        assert_eq!(result.files_created.len(), 50);
+
+        let events = events.lock().unwrap();
+        assert!(events.iter().all(|e| e.generation_id == "gen-1"));
+        let percents: Vec<u8> = events.iter().map(|e| e.progress.percent).collect();
+        assert!(percents.windows(2).all(|w| w[0] <= w[1]), "{:?}", percents);
+        assert_eq!(events.iter().filter(|e| e.progress.stage == "generate").count(), 50);
+
+        let last = events.last().unwrap();
+        assert_eq!((last.progress.stage.as_str(), last.progress.percent), ("complete", 100));
+        assert_eq!(events.first().unwrap().progress.stage, "validate");
+    }
+}
@@ -6,10 +6,14 @@
 // - Project generation from templates
 // - Post-generation hooks
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
-use handlebars::Handlebars;
+use handlebars::{handlebars_helper, Handlebars, RenderErrorReason, Renderable};
+
+use crate::performance::{shared_render_cache, RenderCache, SourceStamp};
+use crate::template_sanitizer::resolve_output_path;
 
 // ============================================
 // Types
@@ -27,6 +31,27 @@ pub struct TemplateEntry {
     pub name: String,
     pub category: TemplateCategory,
     pub path: String,
+    #[serde(default)]
+    pub source: TemplateSource,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TemplateSource {
+    #[default]
+    Local,
+    Git {
+        url: String,
+        #[serde(default)]
+        reference: Option<String>,
+    },
+    Http {
+        /// Fetched under the marketplace rules: `https://`, or plain `http://` only to this machine
+        url: String,
+        /// Hex-encoded SHA-256 of the tarball, optionally prefixed with `sha256:`
+        #[serde(default)]
+        checksum: Option<String>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +98,18 @@ pub struct TemplateMetadata {
     pub estimated_time: String,
     pub preview_image: Option<String>,
     pub author: Option<String>,
+    #[serde(default)]
+    pub hooks: Vec<PostHook>,
+}
+
+/// A command run in the generated project, e.g. `npm install`. Hooks only
+/// run with the user's consent and only for programs in `HOOK_PROGRAMS`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostHook {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub required: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +131,15 @@ pub struct TemplateFeature {
     #[serde(default)]
     pub default: bool,
     pub dependencies: Option<Vec<String>>,
+    #[serde(default)]
+    pub conflicts_with: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureResolution {
+    pub features: Vec<String>,
+    pub auto_enabled: Vec<String>,
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -151,6 +197,12 @@ pub struct ValidationRule {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
     pub template_id: String,
@@ -159,6 +211,28 @@ pub struct ProjectConfig {
     pub output_path: String,
     pub features: Vec<String>,
     pub variables: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub keep_partial_on_error: bool,
+    /// Refuse to generate when a template references a variable the context
+    /// doesn't define, instead of rendering it empty
+    #[serde(default)]
+    pub strict_variables: bool,
+    /// Names of the template's post-generation hooks the user agreed to run;
+    /// any other hook is skipped
+    #[serde(default)]
+    pub approved_hooks: Vec<String>,
+}
+
+/// A template variable that doesn't resolve against the project context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateLintError {
+    /// Template file, relative to the template's `files` directory
+    pub file: String,
+    /// Path of the missing variable, when Handlebars reports one
+    pub variable: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -169,6 +243,24 @@ pub struct GenerationResult {
     pub warnings: Vec<String>,
     pub next_steps: Vec<NextStep>,
     pub duration_ms: u64,
+    pub resolved_features: Vec<String>,
+    pub auto_enabled_features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectPreview {
+    pub project_path: String,
+    pub files: Vec<PreviewFile>,
+    pub warnings: Vec<String>,
+    pub resolved_features: Vec<String>,
+    pub auto_enabled_features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewFile {
+    pub output_path: String,
+    pub content_preview: String,
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -191,27 +283,57 @@ pub struct GenerationProgress {
 // Template Engine
 // ============================================
 
+/// Default number of template files rendered and written at once
+pub const DEFAULT_GENERATION_CONCURRENCY: usize = 8;
+
 pub struct TemplateEngine {
     templates_dir: PathBuf,
-    handlebars: Handlebars<'static>,
+    handlebars: Arc<Handlebars<'static>>,
+    /// Same helpers with strict mode on, for linting
+    strict_handlebars: Handlebars<'static>,
     registry: Option<TemplateRegistry>,
+    generation_concurrency: usize,
+    preview_cache: Arc<RenderCache>,
 }
 
 impl TemplateEngine {
     pub fn new(templates_dir: PathBuf) -> Self {
-        let mut handlebars = Handlebars::new();
-        handlebars.set_strict_mode(false);
-        
-        // Register custom helpers
-        Self::register_helpers(&mut handlebars);
-        
         Self {
             templates_dir,
-            handlebars,
+            handlebars: Arc::new(Self::new_handlebars()),
+            strict_handlebars: {
+                let mut handlebars = Self::new_handlebars();
+                handlebars.set_strict_mode(true);
+                handlebars
+            },
             registry: None,
+            generation_concurrency: DEFAULT_GENERATION_CONCURRENCY,
+            preview_cache: shared_render_cache(),
         }
     }
 
+    /// Set how many files `generate_project` processes in parallel.
+    pub fn set_generation_concurrency(&mut self, concurrency: usize) {
+        self.generation_concurrency = concurrency.max(1);
+    }
+
+    /// Use a dedicated cache for rendered previews instead of the shared one.
+    #[cfg(test)]
+    pub fn set_preview_cache(&mut self, cache: Arc<RenderCache>) {
+        self.preview_cache = cache;
+    }
+
+    /// A Handlebars registry with the template helpers registered. Also used
+    /// to resolve config template placeholders.
+    pub fn new_handlebars() -> Handlebars<'static> {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(false);
+
+        // Register custom helpers
+        Self::register_helpers(&mut handlebars);
+        handlebars
+    }
+
     fn register_helpers(handlebars: &mut Handlebars) {
         // lowercase helper
         handlebars.register_helper("lowercase", Box::new(|h: &handlebars::Helper, _: &Handlebars, _: &handlebars::Context, _: &mut handlebars::RenderContext, out: &mut dyn handlebars::Output| {
@@ -258,6 +380,48 @@ impl TemplateEngine {
             out.write(&result)?;
             Ok(())
         }));
+
+        // {{#if_feature "auth"}}...{{else}}...{{/if_feature}}, driven by the feature_* flags
+        handlebars.register_helper("if_feature", Box::new(if_feature_helper));
+
+        // {{#if (eq a b)}} or {{eq a b}}
+        handlebars_helper!(eq: |a: Json, b: Json| a == b);
+        handlebars.register_helper("eq", Box::new(eq));
+
+        // {{default value "fallback"}}
+        handlebars_helper!(default_value: |value: Json, fallback: Json| {
+            match value {
+                serde_json::Value::Null => fallback.clone(),
+                serde_json::Value::String(s) if s.is_empty() => fallback.clone(),
+                other => other.clone(),
+            }
+        });
+        handlebars.register_helper("default", Box::new(default_value));
+
+        // {{year}}
+        handlebars.register_helper("year", Box::new(|_: &handlebars::Helper, _: &Handlebars, _: &handlebars::Context, _: &mut handlebars::RenderContext, out: &mut dyn handlebars::Output| {
+            out.write(&chrono::Local::now().format("%Y").to_string())?;
+            Ok(())
+        }));
+
+        // {{formatDate "%d/%m/%Y"}} for today, {{formatDate some_date "%B %Y"}} for an RFC 3339 or YYYY-MM-DD value
+        handlebars.register_helper("formatDate", Box::new(|h: &handlebars::Helper, _: &Handlebars, _: &handlebars::Context, _: &mut handlebars::RenderContext, out: &mut dyn handlebars::Output| {
+            let (date, format) = match (h.param(0), h.param(1)) {
+                (Some(date), Some(format)) => (date.value().as_str(), format.value().as_str().unwrap_or("%Y-%m-%d")),
+                (Some(format), None) => (None, format.value().as_str().unwrap_or("%Y-%m-%d")),
+                _ => (None, "%Y-%m-%d"),
+            };
+            out.write(&format_date(date, format))?;
+            Ok(())
+        }));
+
+        // {{formatNumber 1234567.891 2}} -> 1,234,567.89
+        handlebars.register_helper("formatNumber", Box::new(|h: &handlebars::Helper, _: &Handlebars, _: &handlebars::Context, _: &mut handlebars::RenderContext, out: &mut dyn handlebars::Output| {
+            let value = h.param(0).and_then(|v| v.value().as_f64()).unwrap_or(0.0);
+            let decimals = h.param(1).and_then(|v| v.value().as_u64()).unwrap_or(0) as usize;
+            out.write(&format_number(value, decimals))?;
+            Ok(())
+        }));
     }
 
     // ============================================
@@ -294,36 +458,42 @@ impl TemplateEngine {
                     name: "SaaS Starter".to_string(),
                     category: TemplateCategory::Saas,
                     path: "saas".to_string(),
+                    source: TemplateSource::Local,
                 },
                 TemplateEntry {
                     id: "ecommerce-store".to_string(),
                     name: "E-commerce Store".to_string(),
                     category: TemplateCategory::Ecommerce,
                     path: "ecommerce".to_string(),
+                    source: TemplateSource::Local,
                 },
                 TemplateEntry {
                     id: "mobile-app".to_string(),
                     name: "Mobile App".to_string(),
                     category: TemplateCategory::Mobile,
                     path: "mobile".to_string(),
+                    source: TemplateSource::Local,
                 },
                 TemplateEntry {
                     id: "rest-api".to_string(),
                     name: "REST API".to_string(),
                     category: TemplateCategory::Api,
                     path: "api".to_string(),
+                    source: TemplateSource::Local,
                 },
                 TemplateEntry {
                     id: "admin-dashboard".to_string(),
                     name: "Admin Dashboard".to_string(),
                     category: TemplateCategory::Dashboard,
                     path: "dashboard".to_string(),
+                    source: TemplateSource::Local,
                 },
                 TemplateEntry {
                     id: "landing-page".to_string(),
                     name: "Landing Page".to_string(),
                     category: TemplateCategory::Landing,
                     path: "landing".to_string(),
+                    source: TemplateSource::Local,
                 },
             ],
         }
@@ -395,6 +565,7 @@ impl TemplateEngine {
             estimated_time: "30 minutes".to_string(),
             preview_image: None,
             author: None,
+            hooks: vec![],
         }
     }
 
@@ -452,12 +623,13 @@ impl TemplateEngine {
 
     fn find_template_path(&self, template_id: &str) -> Result<PathBuf, String> {
         // First check registry
-        if let Some(registry) = &self.registry {
-            if let Some(entry) = registry.templates.iter().find(|t| t.id == template_id) {
-                let path = self.templates_dir.join(&entry.path);
-                if path.exists() {
-                    return Ok(path);
-                }
+        if let Some(entry) = self.find_registry_entry(template_id) {
+            let path = match entry.source {
+                TemplateSource::Local => self.templates_dir.join(&entry.path),
+                _ => self.remote_cache_path(&entry.id)?,
+            };
+            if path.exists() {
+                return Ok(path);
             }
         }
 
@@ -470,6 +642,106 @@ impl TemplateEngine {
         Err(format!("Template not found: {}", template_id))
     }
 
+    fn find_registry_entry(&self, template_id: &str) -> Option<&TemplateEntry> {
+        self.registry.as_ref()
+            .and_then(|r| r.templates.iter().find(|t| t.id == template_id))
+    }
+
+    // ============================================
+    // Remote Templates
+    // ============================================
+
+    /// Where a remote template is cached. Ids come from the registry, which may
+    /// be remote too, so anything but a plain directory name is refused.
+    fn remote_cache_path(&self, template_id: &str) -> Result<PathBuf, String> {
+        let mut components = Path::new(template_id).components();
+        match (components.next(), components.next()) {
+            (Some(std::path::Component::Normal(_)), None) if !template_id.ends_with(".download") => {
+                Ok(self.templates_dir.join(".cache").join(template_id))
+            }
+            _ => Err(format!("Invalid template id: {}", template_id)),
+        }
+    }
+
+    /// Make sure a remote template is available locally, downloading it on first use.
+    ///
+    /// Local templates are left untouched. Once cached, a remote template is used
+    /// as-is until `refresh_remote_template` is called, so generation keeps working offline.
+    pub async fn ensure_template_available(&self, template_id: &str) -> Result<(), String> {
+        let entry = match self.find_registry_entry(template_id) {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        if matches!(entry.source, TemplateSource::Local) {
+            return Ok(());
+        }
+        if self.remote_cache_path(&entry.id)?.exists() {
+            return Ok(());
+        }
+
+        self.fetch_remote_template(entry).await
+    }
+
+    /// Re-download a remote template, keeping the cached copy if the fetch fails.
+    pub async fn refresh_remote_template(&self, template_id: &str) -> Result<(), String> {
+        let entry = self.find_registry_entry(template_id)
+            .ok_or_else(|| format!("Template not found: {}", template_id))?;
+        if matches!(entry.source, TemplateSource::Local) {
+            return Ok(());
+        }
+
+        let cache_path = self.remote_cache_path(&entry.id)?;
+        match self.fetch_remote_template(entry).await {
+            Ok(()) => Ok(()),
+            Err(e) if cache_path.exists() => {
+                eprintln!("Using cached copy of {}: {}", entry.id, e);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn fetch_remote_template(&self, entry: &TemplateEntry) -> Result<(), String> {
+        let cache_path = self.remote_cache_path(&entry.id)?;
+        let staging_path = cache_path.with_file_name(format!("{}.download", entry.id));
+
+        if staging_path.exists() {
+            tokio::fs::remove_dir_all(&staging_path)
+                .await
+                .map_err(|e| format!("Failed to clear template download: {}", e))?;
+        }
+        tokio::fs::create_dir_all(&staging_path)
+            .await
+            .map_err(|e| format!("Failed to create template cache: {}", e))?;
+
+        let fetched = match &entry.source {
+            TemplateSource::Local => Ok(()),
+            TemplateSource::Git { url, reference } => {
+                fetch_git_template(url, reference.as_deref(), &staging_path).await
+            }
+            TemplateSource::Http { url, checksum } => {
+                fetch_http_template(url, checksum.as_deref(), &staging_path).await
+            }
+        };
+
+        if let Err(e) = fetched {
+            let _ = tokio::fs::remove_dir_all(&staging_path).await;
+            return Err(e);
+        }
+
+        // Swap the new download in only once it is complete
+        if cache_path.exists() {
+            tokio::fs::remove_dir_all(&cache_path)
+                .await
+                .map_err(|e| format!("Failed to replace cached template: {}", e))?;
+        }
+        tokio::fs::rename(&staging_path, &cache_path)
+            .await
+            .map_err(|e| format!("Failed to store template in cache: {}", e))?;
+
+        Ok(())
+    }
+
     // ============================================
     // Project Generation
     // ============================================
@@ -483,6 +755,15 @@ impl TemplateEngine {
         let mut files_created = Vec::new();
         let mut warnings = Vec::new();
 
+        // Files finish in any order when generated in parallel; never report
+        // less progress than has already been reported
+        let reported = std::sync::atomic::AtomicU8::new(0);
+        let progress_callback = move |mut progress: GenerationProgress| {
+            let previous = reported.fetch_max(progress.percent, std::sync::atomic::Ordering::SeqCst);
+            progress.percent = progress.percent.max(previous);
+            progress_callback(progress);
+        };
+
         // Stage 1: Validate config
         progress_callback(GenerationProgress {
             stage: "validate".to_string(),
@@ -491,7 +772,16 @@ impl TemplateEngine {
             message: "Validating configuration...".to_string(),
         });
 
-        self.validate_config(&config)?;
+        let (config, metadata, resolution) = self.prepare_generation(config).await?;
+        warnings.extend(resolution.warnings.iter().cloned());
+
+        if config.strict_variables {
+            let errors = self.lint_files(&config).await?;
+            if !errors.is_empty() {
+                let details: Vec<String> = errors.iter().map(format_lint_error).collect();
+                return Err(format!("Undefined template variables: {}", details.join("; ")));
+            }
+        }
 
         // Stage 2: Prepare output directory
         progress_callback(GenerationProgress {
@@ -505,77 +795,41 @@ impl TemplateEngine {
         if output_path.exists() {
             return Err(format!("Directory already exists: {}", output_path.display()));
         }
+
+        // Remember the deepest existing ancestor so rollback also removes parents we create
+        let existing_ancestor = output_path.ancestors()
+            .find(|p| p.exists())
+            .map(|p| p.to_path_buf());
+
         tokio::fs::create_dir_all(&output_path)
             .await
             .map_err(|e| format!("Failed to create directory: {}", e))?;
 
-        // Stage 3: Load template
-        progress_callback(GenerationProgress {
-            stage: "load".to_string(),
-            percent: 15,
-            current_file: None,
-            message: "Loading template...".to_string(),
-        });
-
-        let template_path = self.find_template_path(&config.template_id)?;
-        let files_dir = template_path.join("files");
-
-        // Stage 4: Build context
-        progress_callback(GenerationProgress {
-            stage: "context".to_string(),
-            percent: 20,
-            current_file: None,
-            message: "Building template context...".to_string(),
-        });
-
-        let context = self.build_context(&config);
-
-        // Stage 5: Copy and process files
-        if files_dir.exists() {
-            let file_list = self.collect_template_files(&files_dir).await?;
-            let total_files = file_list.len();
-
-            for (i, file_path) in file_list.iter().enumerate() {
-                let progress = 20 + ((i as f32 / total_files as f32) * 60.0) as u8;
-                let relative_path = file_path.strip_prefix(&files_dir)
-                    .map_err(|e| e.to_string())?;
-
-                progress_callback(GenerationProgress {
-                    stage: "generate".to_string(),
-                    percent: progress,
-                    current_file: Some(relative_path.to_string_lossy().to_string()),
-                    message: format!("Processing {} of {} files...", i + 1, total_files),
-                });
-
-                // Check if file should be included based on features
-                if !self.should_include_file(&relative_path, &config.features) {
-                    continue;
-                }
-
-                let output_file = self.process_file(
-                    &file_path,
-                    &output_path,
-                    &relative_path,
-                    &context,
-                ).await?;
-
-                files_created.push(output_file);
+        let generated = self.generate_files(
+            &config,
+            &metadata,
+            &output_path,
+            &progress_callback,
+            &mut files_created,
+            &mut warnings,
+        ).await;
+
+        if let Err(e) = generated {
+            if config.keep_partial_on_error {
+                return Err(format!("{} (partial output kept at {})", e, output_path.display()));
             }
-        } else {
-            // Create minimal project structure
-            files_created.extend(self.create_minimal_project(&output_path, &context).await?);
-        }
-
-        // Stage 6: Run post-generation hooks
-        progress_callback(GenerationProgress {
-            stage: "hooks".to_string(),
-            percent: 85,
-            current_file: None,
-            message: "Running post-generation hooks...".to_string(),
-        });
 
-        if let Err(e) = self.run_post_hooks(&output_path, &config).await {
-            warnings.push(format!("Post-hook warning: {}", e));
+            let rolled_back = self.rollback_generation(
+                &output_path,
+                existing_ancestor.as_deref(),
+                &files_created,
+            ).await;
+            return Err(format!(
+                "{}. Rolled back {} file(s): {}",
+                e,
+                rolled_back.len(),
+                rolled_back.join(", ")
+            ));
         }
 
         // Stage 7: Initialize Git
@@ -607,139 +861,520 @@ impl TemplateEngine {
             warnings,
             next_steps,
             duration_ms: start.elapsed().as_millis() as u64,
+            resolved_features: resolution.features,
+            auto_enabled_features: resolution.auto_enabled,
         })
     }
 
-    fn validate_config(&self, config: &ProjectConfig) -> Result<(), String> {
-        if config.project_name.is_empty() {
-            return Err("Project name is required".to_string());
-        }
+    /// Validate the config and resolve features; shared by generation and preview.
+    async fn prepare_generation(
+        &self,
+        config: ProjectConfig,
+    ) -> Result<(ProjectConfig, TemplateMetadata, FeatureResolution), String> {
+        self.validate_config(&config)?;
 
-        // Validate project name format
-        let name_regex = regex::Regex::new(r"^[a-z][a-z0-9-]*$").unwrap();
-        if !name_regex.is_match(&config.project_name) {
-            return Err("Invalid project name format".to_string());
-        }
+        self.ensure_template_available(&config.template_id).await?;
 
-        if config.output_path.is_empty() {
-            return Err("Output path is required".to_string());
+        let metadata = self.get_template_metadata(&config.template_id).await?;
+        let resolution = resolve_features(&metadata.features, &config.features)?;
+
+        let mut config = config;
+        config.features = resolution.features.clone();
+
+        let field_errors = self.validate_variables(&config).await?;
+        if !field_errors.is_empty() {
+            let details: Vec<String> = field_errors.iter()
+                .map(|e| format!("{}: {}", e.field, e.message))
+                .collect();
+            return Err(format!("Invalid configuration: {}", details.join("; ")));
         }
 
-        Ok(())
+        Ok((config, metadata, resolution))
     }
 
-    fn build_context(&self, config: &ProjectConfig) -> serde_json::Value {
-        let mut context = serde_json::json!({
-            "project_name": config.project_name,
-            "project_description": config.project_description.clone().unwrap_or_default(),
-            "features": config.features,
-            "year": chrono::Utc::now().format("%Y").to_string(),
-            "date": chrono::Utc::now().format("%Y-%m-%d").to_string(),
-        });
+    /// Render every template file the config would generate with strict mode
+    /// on and report each variable the resolved context doesn't define.
+    /// Generation itself stays lenient unless `strict_variables` is set.
+    pub async fn lint_template(&self, config: ProjectConfig) -> Result<Vec<TemplateLintError>, String> {
+        let (config, _metadata, _resolution) = self.prepare_generation(config).await?;
+        self.lint_files(&config).await
+    }
 
-        // Add feature flags
-        for feature in &config.features {
-            context[format!("feature_{}", feature)] = serde_json::Value::Bool(true);
+    async fn lint_files(&self, config: &ProjectConfig) -> Result<Vec<TemplateLintError>, String> {
+        let files_dir = self.find_template_path(&config.template_id)?.join("files");
+        if !files_dir.exists() {
+            return Ok(Vec::new());
         }
 
-        // Add custom variables
-        if let serde_json::Value::Object(ref mut map) = context {
-            for (key, value) in &config.variables {
-                map.insert(key.clone(), value.clone());
+        let context = self.build_context(config);
+        let mut file_list = self.collect_template_files(&files_dir).await?;
+        file_list.sort();
+
+        let mut errors = Vec::new();
+        for file_path in &file_list {
+            let relative_path = file_path.strip_prefix(&files_dir)
+                .map_err(|e| e.to_string())?;
+            if !is_handlebars_file(file_path)
+                || !self.should_generate_file(file_path, relative_path, config, &context).await?
+            {
+                continue;
+            }
+
+            let content = match tokio::fs::read_to_string(file_path).await {
+                Ok(content) => content,
+                // Rendering passes non-UTF-8 files through untouched
+                Err(e) if e.kind() == std::io::ErrorKind::InvalidData => continue,
+                Err(e) => return Err(format!("Failed to read file: {}", e)),
+            };
+            let (body, offset) = match parse_front_matter(&content) {
+                Some(front_matter) => (front_matter.body, front_matter.lines),
+                None => (content.as_str(), 0),
+            };
+            let mut file_errors = lint_template_source(
+                &self.strict_handlebars,
+                &relative_path.to_string_lossy(),
+                body,
+                &context,
+            );
+            // Report lines of the file as written, front matter included
+            for error in &mut file_errors {
+                error.line = error.line.map(|line| line + offset);
             }
+            errors.extend(file_errors);
         }
 
-        context
+        Ok(errors)
     }
 
-    async fn collect_template_files(&self, dir: &Path) -> Result<Vec<PathBuf>, String> {
-        let mut files = Vec::new();
-        let mut stack = vec![dir.to_path_buf()];
-
-        while let Some(current) = stack.pop() {
-            let mut entries = tokio::fs::read_dir(&current)
-                .await
-                .map_err(|e| format!("Failed to read directory: {}", e))?;
+    /// Run the generation pipeline without writing anything, hooks and git included.
+    pub async fn preview_project(&self, config: ProjectConfig) -> Result<ProjectPreview, String> {
+        let (config, _metadata, resolution) = self.prepare_generation(config).await?;
+        let mut warnings = resolution.warnings.clone();
 
-            while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
-                let path = entry.path();
-                if path.is_dir() {
-                    stack.push(path);
-                } else {
-                    files.push(path);
-                }
-            }
+        let output_path = PathBuf::from(&config.output_path).join(&config.project_name);
+        if output_path.exists() {
+            warnings.push(format!("Directory already exists: {}", output_path.display()));
         }
 
-        Ok(files)
-    }
+        let template_path = self.find_template_path(&config.template_id)?;
+        let files_dir = template_path.join("files");
+        let context = self.build_context(&config);
 
-    fn should_include_file(&self, path: &Path, features: &[String]) -> bool {
-        let path_str = path.to_string_lossy();
-        
-        // Check for feature-specific directories
-        if path_str.contains("__feature_") {
-            for feature in features {
-                if path_str.contains(&format!("__feature_{}__", feature)) {
-                    return true;
-                }
+        let cache_key = RenderCache::key(&[
+            template_path.to_string_lossy().as_bytes(),
+            output_path.to_string_lossy().as_bytes(),
+            context.to_string().as_bytes(),
+        ]);
+        let files = match self.preview_cache.get(&cache_key) {
+            Some(cached) => serde_json::from_str(&cached).map_err(|e| e.to_string())?,
+            None => {
+                let (files, sources) = self.render_preview_files(&files_dir, &output_path, &config, &context).await?;
+                let serialized = serde_json::to_string(&files).map_err(|e| e.to_string())?;
+                self.preview_cache.insert(&cache_key, serialized, sources);
+                files
             }
-            return false;
-        }
+        };
 
-        true
+        Ok(ProjectPreview {
+            project_path: output_path.to_string_lossy().to_string(),
+            files,
+            warnings,
+            resolved_features: resolution.features,
+            auto_enabled_features: resolution.auto_enabled,
+        })
     }
 
-    async fn process_file(
+    /// Render preview contents, returning the template files and directories
+    /// they came from so the cached result can be invalidated when any change.
+    async fn render_preview_files(
         &self,
-        source: &Path,
-        output_dir: &Path,
-        relative_path: &Path,
+        files_dir: &Path,
+        output_path: &Path,
+        config: &ProjectConfig,
         context: &serde_json::Value,
-    ) -> Result<String, String> {
-        // Process output path (remove .hbs extension, replace variables)
-        let mut output_path = output_dir.join(relative_path);
-        let file_name = output_path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("");
-
-        // Remove .hbs extension
-        if file_name.ends_with(".hbs") {
-            output_path = output_path.with_file_name(&file_name[..file_name.len() - 4]);
-        }
+    ) -> Result<(Vec<PreviewFile>, Vec<SourceStamp>), String> {
+        let mut files = Vec::new();
+        let mut sources = vec![SourceStamp::capture(files_dir)];
+        if files_dir.exists() {
+            let mut file_list = self.collect_template_files(files_dir).await?;
+            file_list.sort();
 
-        // Remove feature markers from path
-        let output_path_str = output_path.to_string_lossy()
-            .replace("__feature_", "")
-            .replace("__", "/");
-        let output_path = PathBuf::from(output_path_str);
+            let dirs: BTreeSet<&Path> = file_list.iter().filter_map(|f| f.parent()).collect();
+            sources.extend(dirs.into_iter().map(SourceStamp::capture));
 
-        // Create parent directories
-        if let Some(parent) = output_path.parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
-        }
+            for file_path in &file_list {
+                sources.push(SourceStamp::capture(file_path));
+                let relative_path = file_path.strip_prefix(files_dir)
+                    .map_err(|e| e.to_string())?;
+                if !self.should_generate_file(file_path, relative_path, config, context).await? {
+                    continue;
+                }
 
-        // Read source file
-        let content = tokio::fs::read_to_string(source)
-            .await
-            .map_err(|e| format!("Failed to read file: {}", e))?;
+                let target = safe_output_path(output_path, relative_path)?;
+                let (content_preview, truncated) = match self.render_file(file_path, context).await? {
+                    RenderedFile::Text(content) => truncate_preview(&content),
+                    RenderedFile::Binary(bytes) => (format!("(binary file, {} bytes)", bytes.len()), false),
+                };
 
-        // Process template if it's a .hbs file
-        let processed = if file_name.ends_with(".hbs") {
-            self.handlebars.render_template(&content, context)
-                .map_err(|e| format!("Template error: {}", e))?
+                files.push(PreviewFile {
+                    output_path: target.to_string_lossy().to_string(),
+                    content_preview,
+                    truncated,
+                });
+            }
         } else {
-            content
-        };
+            for name in ["README.md", "package.json", ".gitignore"] {
+                files.push(PreviewFile {
+                    output_path: output_path.join(name).to_string_lossy().to_string(),
+                    content_preview: String::new(),
+                    truncated: false,
+                });
+            }
+        }
 
-        // Write output file
-        tokio::fs::write(&output_path, processed)
-            .await
-            .map_err(|e| format!("Failed to write file: {}", e))?;
+        Ok((files, sources))
+    }
+
+    async fn generate_files(
+        &self,
+        config: &ProjectConfig,
+        metadata: &TemplateMetadata,
+        output_path: &Path,
+        progress_callback: &impl Fn(GenerationProgress),
+        files_created: &mut Vec<String>,
+        warnings: &mut Vec<String>,
+    ) -> Result<(), String> {
+        // Stage 3: Load template
+        progress_callback(GenerationProgress {
+            stage: "load".to_string(),
+            percent: 15,
+            current_file: None,
+            message: "Loading template...".to_string(),
+        });
+
+        let template_path = self.find_template_path(&config.template_id)?;
+        let files_dir = template_path.join("files");
+
+        // Stage 4: Build context
+        progress_callback(GenerationProgress {
+            stage: "context".to_string(),
+            percent: 20,
+            current_file: None,
+            message: "Building template context...".to_string(),
+        });
+
+        let context = self.build_context(config);
+
+        // Stage 5: Copy and process files
+        if files_dir.exists() {
+            let mut files = Vec::new();
+            for file_path in self.collect_template_files(&files_dir).await? {
+                let relative_path = file_path.strip_prefix(&files_dir)
+                    .map_err(|e| e.to_string())?
+                    .to_path_buf();
+
+                // Check if file should be included based on features
+                if self.should_generate_file(&file_path, &relative_path, config, &context).await? {
+                    files.push((file_path, relative_path));
+                }
+            }
+
+            self.process_files(
+                files,
+                output_path,
+                &context,
+                progress_callback,
+                files_created,
+            ).await?;
+        } else {
+            // Create minimal project structure
+            files_created.extend(self.create_minimal_project(output_path, &context).await?);
+        }
+
+        // Stage 6: Run post-generation hooks
+        progress_callback(GenerationProgress {
+            stage: "hooks".to_string(),
+            percent: 85,
+            current_file: None,
+            message: "Running post-generation hooks...".to_string(),
+        });
+
+        warnings.extend(self.run_post_hooks(output_path, &metadata.hooks, &config.approved_hooks).await?);
+
+        Ok(())
+    }
+
+    /// Remove everything written by a failed generation, returning the removed files.
+    async fn rollback_generation(
+        &self,
+        output_path: &Path,
+        existing_ancestor: Option<&Path>,
+        files_created: &[String],
+    ) -> Vec<String> {
+        let mut rolled_back = Vec::new();
+
+        if let Err(e) = tokio::fs::remove_dir_all(output_path).await {
+            // Fall back to removing the files we know about one by one
+            eprintln!("Failed to roll back {}: {}", output_path.display(), e);
+            for file in files_created {
+                if tokio::fs::remove_file(file).await.is_ok() {
+                    rolled_back.push(file.clone());
+                }
+            }
+            return rolled_back;
+        }
+        rolled_back.extend(files_created.iter().cloned());
+        rolled_back.push(output_path.to_string_lossy().to_string());
+
+        // Remove parent directories that only existed because of this generation
+        if let Some(ancestor) = existing_ancestor {
+            for dir in output_path.ancestors().skip(1) {
+                if dir == ancestor {
+                    break;
+                }
+                if tokio::fs::remove_dir(dir).await.is_err() {
+                    break;
+                }
+                rolled_back.push(dir.to_string_lossy().to_string());
+            }
+        }
+
+        rolled_back
+    }
+
+    fn validate_config(&self, config: &ProjectConfig) -> Result<(), String> {
+        if config.project_name.is_empty() {
+            return Err("Project name is required".to_string());
+        }
+
+        // The name becomes the project directory under output_path
+        crate::input_validation::validate_resource_name(&config.project_name, "Project name")?;
+
+        if config.output_path.is_empty() {
+            return Err("Output path is required".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Validate `config.variables` against the template's config schema.
+    ///
+    /// `project_name` and `description` are taken from the config itself, so
+    /// schemas may declare them like any other field.
+    pub async fn validate_variables(&self, config: &ProjectConfig) -> Result<Vec<FieldError>, String> {
+        let schema = self.get_config_schema(&config.template_id).await?;
+
+        let mut values = config.variables.clone();
+        values.entry("project_name".to_string())
+            .or_insert_with(|| serde_json::Value::String(config.project_name.clone()));
+        if let Some(description) = &config.project_description {
+            values.entry("description".to_string())
+                .or_insert_with(|| serde_json::Value::String(description.clone()));
+        }
+        for feature in &config.features {
+            values.entry(format!("feature_{}", feature))
+                .or_insert(serde_json::Value::Bool(true));
+        }
+
+        Ok(validate_schema_values(&schema, &values))
+    }
+
+    fn build_context(&self, config: &ProjectConfig) -> serde_json::Value {
+        let mut context = serde_json::json!({
+            "project_name": config.project_name,
+            "project_description": config.project_description.clone().unwrap_or_default(),
+            "features": config.features,
+            "year": chrono::Utc::now().format("%Y").to_string(),
+            "date": chrono::Utc::now().format("%Y-%m-%d").to_string(),
+        });
+
+        // Add feature flags
+        for feature in &config.features {
+            context[format!("feature_{}", feature)] = serde_json::Value::Bool(true);
+        }
+
+        // Add custom variables
+        if let serde_json::Value::Object(ref mut map) = context {
+            for (key, value) in &config.variables {
+                map.insert(key.clone(), value.clone());
+            }
+        }
+
+        context
+    }
+
+    async fn collect_template_files(&self, dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut files = Vec::new();
+        let mut stack = vec![dir.to_path_buf()];
+
+        while let Some(current) = stack.pop() {
+            let mut entries = tokio::fs::read_dir(&current)
+                .await
+                .map_err(|e| format!("Failed to read directory: {}", e))?;
+
+            while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+                let path = entry.path();
+                // file_type() doesn't follow symlinks, so a link can't pull in
+                // files from outside the template
+                let file_type = entry.file_type().await.map_err(|e| e.to_string())?;
+                if file_type.is_symlink() {
+                    return Err(format!("Refusing to follow symlink in template: {}", path.display()));
+                }
+                if file_type.is_dir() {
+                    stack.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn should_include_file(&self, path: &Path, features: &[String]) -> bool {
+        let path_str = path.to_string_lossy();
+        
+        // Check for feature-specific directories
+        if path_str.contains("__feature_") {
+            for feature in features {
+                if path_str.contains(&format!("__feature_{}__", feature)) {
+                    return true;
+                }
+            }
+            return false;
+        }
+
+        true
+    }
+
+    /// Path markers first, then the `when` condition in the file's front
+    /// matter, if it has one.
+    async fn should_generate_file(
+        &self,
+        source: &Path,
+        relative_path: &Path,
+        config: &ProjectConfig,
+        context: &serde_json::Value,
+    ) -> Result<bool, String> {
+        if !self.should_include_file(relative_path, &config.features) {
+            return Ok(false);
+        }
+        if !is_handlebars_file(source) {
+            return Ok(true);
+        }
+
+        // Unreadable and non-UTF-8 files are left for rendering to deal with
+        let Ok(content) = tokio::fs::read_to_string(source).await else {
+            return Ok(true);
+        };
+        match parse_front_matter(&content) {
+            Some(front_matter) => evaluate_condition(&front_matter.when, context)
+                .map_err(|e| format!("Invalid `when` in {}: {}", relative_path.display(), e)),
+            None => Ok(true),
+        }
+    }
+
+    /// Render and write `(source, relative_path)` pairs with a bounded task set.
+    ///
+    /// Parent directories are created once, up front, so tasks never race on
+    /// them. Progress is reported as files complete, while `files_created`
+    /// follows template path order regardless of completion order. On the
+    /// first error the remaining tasks are aborted and drained before
+    /// returning, so nothing is written once the caller starts rolling back.
+    async fn process_files(
+        &self,
+        mut files: Vec<(PathBuf, PathBuf)>,
+        output_dir: &Path,
+        context: &serde_json::Value,
+        progress_callback: &impl Fn(GenerationProgress),
+        files_created: &mut Vec<String>,
+    ) -> Result<(), String> {
+        files.sort();
+
+        let jobs: Vec<(PathBuf, PathBuf, PathBuf)> = files.into_iter()
+            .map(|(source, relative_path)| {
+                let output_path = safe_output_path(output_dir, &relative_path)?;
+                Ok((source, relative_path, output_path))
+            })
+            .collect::<Result<_, String>>()?;
+
+        // Create parent directories
+        let parents: BTreeSet<&Path> = jobs.iter()
+            .filter_map(|(_, _, output_path)| output_path.parent())
+            .collect();
+        for parent in parents {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+
+        let total_files = jobs.len();
+        let context = Arc::new(context.clone());
+        let mut written: Vec<Option<String>> = vec![None; total_files];
+        let mut pending = jobs.into_iter().enumerate();
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut completed = 0;
+        let mut failure: Option<String> = None;
+
+        loop {
+            // Keep the task set topped up until something fails
+            while failure.is_none() && tasks.len() < self.generation_concurrency {
+                let Some((index, (source, relative_path, output_path))) = pending.next() else {
+                    break;
+                };
+                let handlebars = Arc::clone(&self.handlebars);
+                let context = Arc::clone(&context);
+                tasks.spawn(async move {
+                    let result = write_template_file(&handlebars, &source, &output_path, &context).await;
+                    (index, relative_path, output_path, result)
+                });
+            }
+
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+
+            match joined {
+                Ok((index, relative_path, output_path, Ok(()))) => {
+                    completed += 1;
+                    written[index] = Some(output_path.to_string_lossy().to_string());
+
+                    if failure.is_none() {
+                        progress_callback(GenerationProgress {
+                            stage: "generate".to_string(),
+                            percent: 20 + ((completed as f32 / total_files as f32) * 60.0) as u8,
+                            current_file: Some(relative_path.to_string_lossy().to_string()),
+                            message: format!("Processed {} of {} files...", completed, total_files),
+                        });
+                    }
+                }
+                Ok((_, _, _, Err(e))) => {
+                    if failure.is_none() {
+                        failure = Some(e);
+                        tasks.abort_all();
+                    }
+                }
+                Err(e) if e.is_cancelled() => {}
+                Err(e) => {
+                    if failure.is_none() {
+                        failure = Some(format!("File generation task failed: {}", e));
+                        tasks.abort_all();
+                    }
+                }
+            }
+        }
+
+        // Report everything that reached disk, even on failure, so rollback can see it
+        files_created.extend(written.into_iter().flatten());
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
 
-        Ok(output_path.to_string_lossy().to_string())
+    async fn render_file(&self, source: &Path, context: &serde_json::Value) -> Result<RenderedFile, String> {
+        render_template_file(&self.handlebars, source, context).await
     }
 
     async fn create_minimal_project(&self, output_path: &Path, context: &serde_json::Value) -> Result<Vec<String>, String> {
@@ -807,9 +1442,39 @@ dist/
         Ok(files)
     }
 
-    async fn run_post_hooks(&self, _output_path: &Path, _config: &ProjectConfig) -> Result<(), String> {
-        // TODO: Implement post-generation hooks
-        Ok(())
+    /// Run the template's post-generation hooks in order.
+    ///
+    /// Hooks the user hasn't approved are skipped. Approved hooks run without a
+    /// shell and must invoke an allowlisted program. Failures of optional hooks
+    /// are returned as warnings; a failing required hook aborts generation.
+    async fn run_post_hooks(
+        &self,
+        output_path: &Path,
+        hooks: &[PostHook],
+        approved: &[String],
+    ) -> Result<Vec<String>, String> {
+        let mut warnings = Vec::new();
+
+        for hook in hooks {
+            if !approved.contains(&hook.name) {
+                warnings.push(format!("Skipped post-hook '{}': not approved", hook.name));
+                continue;
+            }
+
+            let result = match parse_hook_command(&hook.command) {
+                Ok(args) => run_hook(output_path, &args).await,
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = result {
+                if hook.required {
+                    return Err(format!("Required hook '{}' failed: {}", hook.name, e));
+                }
+                warnings.push(format!("Post-hook warning: {}: {}", hook.name, e));
+            }
+        }
+
+        Ok(warnings)
     }
 
     async fn init_git(&self, output_path: &Path) -> Result<(), String> {
@@ -871,68 +1536,1577 @@ dist/
 }
 
 // ============================================
-// Helper Functions
+// Front Matter
 // ============================================
 
-fn to_camel_case(s: &str) -> String {
-    let mut result = String::new();
-    let mut capitalize_next = false;
-    
-    for (i, c) in s.chars().enumerate() {
-        if c == '-' || c == '_' || c == ' ' {
-            capitalize_next = true;
-        } else if capitalize_next {
-            result.push(c.to_ascii_uppercase());
-            capitalize_next = false;
-        } else if i == 0 {
-            result.push(c.to_ascii_lowercase());
-        } else {
-            result.push(c);
+/// A leading `---` (YAML) or `+++` (TOML) block declaring when the file is
+/// generated:
+///
+/// ```text
+/// ---
+/// when: feature_auth && !feature_sso
+/// ---
+/// ```
+struct FrontMatter<'a> {
+    when: String,
+    /// Lines taken by the block, delimiters included
+    lines: usize,
+    body: &'a str,
+}
+
+/// Blocks without a `when` key are the file's own (e.g. Markdown front
+/// matter) and stay part of the body.
+fn parse_front_matter(content: &str) -> Option<FrontMatter<'_>> {
+    let mut lines = content.split_inclusive('\n');
+    let first = lines.next()?;
+    let delimiter = ["---", "+++"].into_iter().find(|d| first.trim_end() == *d)?;
+
+    let mut offset = first.len();
+    let mut when = None;
+    for (index, line) in lines.enumerate() {
+        offset += line.len();
+        let line = line.trim();
+        if line == delimiter {
+            return when.map(|when| FrontMatter {
+                when,
+                lines: index + 2,
+                body: &content[offset..],
+            });
+        }
+
+        let value = line.strip_prefix("when")
+            .map(str::trim_start)
+            .and_then(|rest| rest.strip_prefix(':').or_else(|| rest.strip_prefix('=')));
+        if let Some(value) = value {
+            let value = value.trim();
+            let unquoted = ['"', '\'']
+                .into_iter()
+                .find_map(|q| value.strip_prefix(q).and_then(|v| v.strip_suffix(q)));
+            when = Some(unquoted.unwrap_or(value).to_string());
         }
     }
-    
-    result
+    None
 }
 
-fn to_pascal_case(s: &str) -> String {
-    let camel = to_camel_case(s);
-    let mut chars = camel.chars();
-    match chars.next() {
-        None => String::new(),
-        Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
-    }
+#[derive(Debug, PartialEq)]
+enum ConditionToken {
+    Name(String),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
 }
 
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    
-    for (i, c) in s.chars().enumerate() {
-        if c == '-' || c == ' ' {
-            result.push('_');
-        } else if c.is_uppercase() && i > 0 {
-            result.push('_');
-            result.push(c.to_ascii_lowercase());
-        } else {
-            result.push(c.to_ascii_lowercase());
+fn tokenize_condition(expr: &str) -> Result<Vec<ConditionToken>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '!' => tokens.push(ConditionToken::Not),
+            '(' => tokens.push(ConditionToken::Open),
+            ')' => tokens.push(ConditionToken::Close),
+            '&' | '|' => {
+                if chars.next() != Some(c) {
+                    return Err(format!("expected `{}{}`", c, c));
+                }
+                tokens.push(if c == '&' { ConditionToken::And } else { ConditionToken::Or });
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || matches!(next, '_' | '-' | '.')) {
+                        break;
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+                tokens.push(ConditionToken::Name(name));
+            }
+            c => return Err(format!("unexpected `{}`", c)),
         }
     }
-    
-    result
+    Ok(tokens)
 }
 
-fn to_kebab_case(s: &str) -> String {
-    let mut result = String::new();
-    
-    for (i, c) in s.chars().enumerate() {
-        if c == '_' || c == ' ' {
-            result.push('-');
-        } else if c.is_uppercase() && i > 0 {
-            result.push('-');
-            result.push(c.to_ascii_lowercase());
-        } else {
-            result.push(c.to_ascii_lowercase());
+/// Evaluate a `when` condition: feature flags and variables combined with
+/// `!`, `&&`, `||` and parentheses. Names resolve against the render context
+/// (dotted paths reach into objects); undefined names are false.
+fn evaluate_condition(expr: &str, context: &serde_json::Value) -> Result<bool, String> {
+    struct Parser<'a> {
+        tokens: std::iter::Peekable<std::vec::IntoIter<ConditionToken>>,
+        context: &'a serde_json::Value,
+    }
+
+    impl Parser<'_> {
+        fn or(&mut self) -> Result<bool, String> {
+            let mut value = self.and()?;
+            while self.tokens.next_if_eq(&ConditionToken::Or).is_some() {
+                // Evaluate both sides so syntax errors aren't hidden by short-circuiting
+                let rhs = self.and()?;
+                value = value || rhs;
+            }
+            Ok(value)
+        }
+
+        fn and(&mut self) -> Result<bool, String> {
+            let mut value = self.not()?;
+            while self.tokens.next_if_eq(&ConditionToken::And).is_some() {
+                let rhs = self.not()?;
+                value = value && rhs;
+            }
+            Ok(value)
+        }
+
+        fn not(&mut self) -> Result<bool, String> {
+            if self.tokens.next_if_eq(&ConditionToken::Not).is_some() {
+                return self.not().map(|value| !value);
+            }
+            match self.tokens.next() {
+                Some(ConditionToken::Open) => {
+                    let value = self.or()?;
+                    match self.tokens.next() {
+                        Some(ConditionToken::Close) => Ok(value),
+                        _ => Err("missing `)`".to_string()),
+                    }
+                }
+                Some(ConditionToken::Name(name)) => Ok(match name.as_str() {
+                    "true" => true,
+                    "false" => false,
+                    _ => is_truthy(name.split('.')
+                        .try_fold(self.context, |value, key| value.get(key))),
+                }),
+                Some(token) => Err(format!("unexpected {:?}", token)),
+                None => Err("unexpected end of condition".to_string()),
+            }
         }
     }
-    
-    result
+
+    let mut parser = Parser {
+        tokens: tokenize_condition(expr)?.into_iter().peekable(),
+        context,
+    };
+    let value = parser.or()?;
+    match parser.tokens.next() {
+        None => Ok(value),
+        Some(token) => Err(format!("unexpected {:?}", token)),
+    }
+}
+
+// ============================================
+// Post-Generation Hooks
+// ============================================
+
+/// Programs a template hook may invoke
+const HOOK_PROGRAMS: &[&str] = &[
+    "npm", "pnpm", "yarn", "bun", "cargo", "go", "pip", "poetry", "bundle", "composer", "dotnet", "git",
+];
+
+/// Split a hook command into program and arguments. Hooks never go through a
+/// shell, so anything that would need one is refused rather than mangled.
+fn parse_hook_command(command: &str) -> Result<Vec<String>, String> {
+    const SHELL_CHARS: &[char] = &[
+        ';', '&', '|', '<', '>', '$', '`', '(', ')', '{', '}', '*', '?', '~', '"', '\'', '\\', '\n',
+    ];
+    if let Some(c) = command.chars().find(|c| SHELL_CHARS.contains(c)) {
+        return Err(format!("Hook command uses unsupported character '{}'", c.escape_default()));
+    }
+
+    let args: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+    match args.first() {
+        Some(program) if HOOK_PROGRAMS.contains(&program.as_str()) => Ok(args),
+        Some(program) => Err(format!("Hook program is not allowed: {}", program)),
+        None => Err("Hook command is empty".to_string()),
+    }
+}
+
+async fn run_hook(output_path: &Path, args: &[String]) -> Result<(), String> {
+    // Package managers are batch scripts on Windows, which only cmd can start
+    let mut command = if cfg!(target_os = "windows") {
+        let mut c = tokio::process::Command::new("cmd");
+        c.arg("/C").args(args);
+        c
+    } else {
+        let mut c = tokio::process::Command::new(&args[0]);
+        c.args(&args[1..]);
+        c
+    };
+
+    let output = command
+        .current_dir(output_path)
+        .output()
+        .await
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// ============================================
+// File Helpers
+// ============================================
+
+const PREVIEW_MAX_CHARS: usize = 4000;
+
+/// Extensions that are always copied byte-for-byte, even if they happen to be valid UTF-8.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "avif",
+    "woff", "woff2", "ttf", "otf", "eot",
+    "pdf", "zip", "gz", "tar", "mp3", "mp4", "webm", "wasm",
+];
+
+enum RenderedFile {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Read a template file and render it if it's a `.hbs` file.
+///
+/// Binary assets (known extensions or non-UTF-8 content) are passed through untouched.
+async fn render_template_file(
+    handlebars: &Handlebars<'_>,
+    source: &Path,
+    context: &serde_json::Value,
+) -> Result<RenderedFile, String> {
+    let bytes = tokio::fs::read(source)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    if is_binary_extension(source) {
+        return Ok(RenderedFile::Binary(bytes));
+    }
+
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => return Ok(RenderedFile::Binary(e.into_bytes())),
+    };
+
+    if is_handlebars_file(source) {
+        let body = parse_front_matter(&content).map_or(content.as_str(), |front_matter| front_matter.body);
+        handlebars.render_template(body, context)
+            .map(RenderedFile::Text)
+            .map_err(|e| format!("Template error: {}", e))
+    } else {
+        Ok(RenderedFile::Text(content))
+    }
+}
+
+/// Render a template file and write it to `output_path`; the parent must exist.
+async fn write_template_file(
+    handlebars: &Handlebars<'_>,
+    source: &Path,
+    output_path: &Path,
+    context: &serde_json::Value,
+) -> Result<(), String> {
+    match render_template_file(handlebars, source, context).await? {
+        RenderedFile::Text(content) => tokio::fs::write(output_path, content).await,
+        RenderedFile::Binary(bytes) => tokio::fs::write(output_path, bytes).await,
+    }
+    .map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Most errors reported for a single template file
+const MAX_LINT_ERRORS_PER_FILE: usize = 50;
+
+/// Render `content` in strict mode until it succeeds. Strict rendering stops
+/// at the first missing variable, so each one is stubbed into the context
+/// before rendering again to find the next.
+fn lint_template_source(
+    handlebars: &Handlebars<'_>,
+    file: &str,
+    content: &str,
+    context: &serde_json::Value,
+) -> Vec<TemplateLintError> {
+    let mut context = context.clone();
+    let mut errors: Vec<TemplateLintError> = Vec::new();
+
+    while errors.len() < MAX_LINT_ERRORS_PER_FILE {
+        let error = match handlebars.render_template(content, &context) {
+            Ok(_) => break,
+            Err(e) => e,
+        };
+        let variable = match error.reason() {
+            RenderErrorReason::MissingVariable(path) => path.clone(),
+            _ => None,
+        };
+        let repeated = variable.is_some()
+            && errors.iter().any(|e| e.variable == variable && e.line == error.line_no);
+        if repeated {
+            break;
+        }
+
+        errors.push(TemplateLintError {
+            file: file.to_string(),
+            variable: variable.clone(),
+            line: error.line_no,
+            column: error.column_no,
+            message: error.reason().to_string(),
+        });
+
+        match variable {
+            Some(path) if stub_variable(&mut context, &path) => {}
+            _ => break,
+        }
+    }
+
+    errors
+}
+
+/// Define `path` (dot-separated) as an empty string. False if it can't be
+/// placed, e.g. under a value that isn't an object.
+fn stub_variable(context: &mut serde_json::Value, path: &str) -> bool {
+    let mut current = context;
+    let mut segments = path.split('.').filter(|s| !s.is_empty() && *s != "this").peekable();
+    while let Some(segment) = segments.next() {
+        let Some(map) = current.as_object_mut() else {
+            return false;
+        };
+        if segments.peek().is_none() {
+            return map.insert(segment.to_string(), serde_json::Value::String(String::new())).is_none();
+        }
+        current = map.entry(segment.to_string())
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    }
+    false
+}
+
+fn format_lint_error(error: &TemplateLintError) -> String {
+    let location = match (error.line, error.column) {
+        (Some(line), Some(column)) => format!("{}:{}:{}", error.file, line, column),
+        (Some(line), None) => format!("{}:{}", error.file, line),
+        _ => error.file.clone(),
+    };
+    match &error.variable {
+        Some(variable) => format!("{} ({})", location, variable),
+        None => format!("{} ({})", location, error.message),
+    }
+}
+
+fn is_binary_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| BINARY_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn is_handlebars_file(path: &Path) -> bool {
+    path.extension().map(|e| e == "hbs").unwrap_or(false)
+}
+
+/// Map a template file's relative path to its output path: drop `.hbs` and
+/// turn feature markers (`__feature_auth__x`) into directories.
+fn rewrite_output_path(relative_path: &Path) -> PathBuf {
+    let mut path = relative_path.to_path_buf();
+    if is_handlebars_file(&path) {
+        path.set_extension("");
+    }
+
+    let rewritten = path.to_string_lossy()
+        .replace("__feature_", "")
+        .replace("__", "/");
+    PathBuf::from(rewritten)
+}
+
+/// Rewrite a template file's path and resolve it under `output_dir`,
+/// rejecting names like `__..__etc__passwd` that would land outside it.
+fn safe_output_path(output_dir: &Path, relative_path: &Path) -> Result<PathBuf, String> {
+    resolve_output_path(&rewrite_output_path(relative_path), output_dir).map_err(|e| e.to_string())
+}
+
+fn truncate_preview(content: &str) -> (String, bool) {
+    match content.char_indices().nth(PREVIEW_MAX_CHARS) {
+        Some((idx, _)) => (content[..idx].to_string(), true),
+        None => (content.to_string(), false),
+    }
+}
+
+// ============================================
+// Remote Template Fetching
+// ============================================
+
+async fn fetch_git_template(url: &str, reference: Option<&str>, dest: &Path) -> Result<(), String> {
+    // Both come from the registry; never let them be read as git options
+    if url.starts_with('-') {
+        return Err(format!("Invalid template URL: {}", url));
+    }
+    if reference.is_some_and(|r| r.starts_with('-')) {
+        return Err(format!("Invalid template reference: {}", reference.unwrap_or_default()));
+    }
+
+    let mut command = tokio::process::Command::new("git");
+    command.args(["clone", "--depth", "1"]);
+    if let Some(reference) = reference {
+        command.args(["--branch", reference]);
+    }
+
+    let output = command
+        .arg("--")
+        .arg(url)
+        .arg(dest)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run git clone: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to clone template: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    // The template is a snapshot; drop the repository metadata
+    let _ = tokio::fs::remove_dir_all(dest.join(".git")).await;
+
+    Ok(())
+}
+
+async fn fetch_http_template(url: &str, checksum: Option<&str>, dest: &Path) -> Result<(), String> {
+    let bytes = crate::marketplace::fetch_package(url)
+        .await
+        .map_err(|e| format!("Failed to download template: {}", e))?;
+
+    if let Some(expected) = checksum {
+        verify_checksum(&bytes, expected)?;
+    }
+
+    let dest = dest.to_path_buf();
+    tokio::task::spawn_blocking(move || unpack_tarball(&bytes, &dest))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn verify_checksum(bytes: &[u8], expected: &str) -> Result<(), String> {
+    use sha2::{Digest, Sha256};
+
+    let expected = expected.trim_start_matches("sha256:").to_lowercase();
+    let actual = hex::encode(Sha256::digest(bytes));
+    if actual != expected {
+        return Err(format!("Template checksum mismatch: expected {}, got {}", expected, actual));
+    }
+
+    Ok(())
+}
+
+fn unpack_tarball(bytes: &[u8], dest: &Path) -> Result<(), String> {
+    let decoder = flate2::read::GzDecoder::new(bytes);
+    let mut archive = tar::Archive::new(decoder);
+    // `unpack` refuses entries that would escape `dest`
+    archive.unpack(dest)
+        .map_err(|e| format!("Failed to extract template: {}", e))?;
+
+    // Tarballs usually wrap everything in a single top-level directory; flatten it
+    let entries: Vec<PathBuf> = std::fs::read_dir(dest)
+        .map_err(|e| e.to_string())?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .collect();
+    if let [only] = entries.as_slice() {
+        if only.is_dir() {
+            for entry in std::fs::read_dir(only).map_err(|e| e.to_string())? {
+                let entry = entry.map_err(|e| e.to_string())?;
+                std::fs::rename(entry.path(), dest.join(entry.file_name()))
+                    .map_err(|e| e.to_string())?;
+            }
+            std::fs::remove_dir(only).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================
+// Schema Validation
+// ============================================
+
+/// Check each schema field against the supplied values, collecting one error per field.
+pub fn validate_schema_values(
+    schema: &ConfigSchema,
+    values: &HashMap<String, serde_json::Value>,
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    for field in &schema.fields {
+        // Fields whose controlling field is unset are hidden in the wizard and skipped here
+        if let Some(parent) = &field.depends_on {
+            if !is_truthy(values.get(parent)) {
+                continue;
+            }
+        }
+
+        let value = values.get(&field.id)
+            .filter(|v| !is_empty_value(v))
+            .or(field.default.as_ref());
+
+        let value = match value {
+            Some(v) => v,
+            None => {
+                if field.required {
+                    errors.push(FieldError {
+                        field: field.id.clone(),
+                        message: format!("{} is required", field.name),
+                    });
+                }
+                continue;
+            }
+        };
+
+        if let Err(message) = validate_field_value(field, value) {
+            errors.push(FieldError { field: field.id.clone(), message });
+        }
+    }
+
+    errors
+}
+
+fn validate_field_value(field: &ConfigField, value: &serde_json::Value) -> Result<(), String> {
+    match field.field_type {
+        ConfigFieldType::Text | ConfigFieldType::Color | ConfigFieldType::File => {
+            if !value.is_string() {
+                return Err(format!("{} must be text", field.name));
+            }
+        }
+        ConfigFieldType::Number => {
+            if !value.is_number() {
+                return Err(format!("{} must be a number", field.name));
+            }
+        }
+        ConfigFieldType::Boolean => {
+            if !value.is_boolean() {
+                return Err(format!("{} must be true or false", field.name));
+            }
+        }
+        ConfigFieldType::Select => {
+            let selected = value.as_str()
+                .ok_or_else(|| format!("{} must be one of the available options", field.name))?;
+            check_option(field, selected)?;
+        }
+        ConfigFieldType::MultiSelect => {
+            let items = value.as_array()
+                .ok_or_else(|| format!("{} must be a list of options", field.name))?;
+            for item in items {
+                let selected = item.as_str()
+                    .ok_or_else(|| format!("{} must be a list of options", field.name))?;
+                check_option(field, selected)?;
+            }
+        }
+    }
+
+    let rule = match &field.validation {
+        Some(rule) => rule,
+        None => return Ok(()),
+    };
+
+    if let Some(text) = value.as_str() {
+        let length = text.chars().count();
+        if rule.min_length.map(|min| length < min).unwrap_or(false)
+            || rule.max_length.map(|max| length > max).unwrap_or(false)
+        {
+            return Err(rule.message.clone());
+        }
+        if let Some(pattern) = &rule.pattern {
+            let regex = regex::Regex::new(pattern)
+                .map_err(|e| format!("Invalid pattern for {}: {}", field.name, e))?;
+            if !regex.is_match(text) {
+                return Err(rule.message.clone());
+            }
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if rule.min.map(|min| number < min as f64).unwrap_or(false)
+            || rule.max.map(|max| number > max as f64).unwrap_or(false)
+        {
+            return Err(rule.message.clone());
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        if rule.min_length.map(|min| items.len() < min).unwrap_or(false)
+            || rule.max_length.map(|max| items.len() > max).unwrap_or(false)
+        {
+            return Err(rule.message.clone());
+        }
+    }
+
+    Ok(())
+}
+
+fn check_option(field: &ConfigField, selected: &str) -> Result<(), String> {
+    match &field.options {
+        Some(options) if !options.iter().any(|o| o.value == selected) => {
+            Err(format!("'{}' is not a valid option for {}", selected, field.name))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn is_empty_value(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => true,
+        serde_json::Value::String(s) => s.trim().is_empty(),
+        _ => false,
+    }
+}
+
+fn is_truthy(value: Option<&serde_json::Value>) -> bool {
+    match value {
+        None | Some(serde_json::Value::Null) => false,
+        Some(serde_json::Value::Bool(b)) => *b,
+        Some(serde_json::Value::String(s)) => !s.is_empty(),
+        Some(serde_json::Value::Array(a)) => !a.is_empty(),
+        Some(_) => true,
+    }
+}
+
+// ============================================
+// Feature Resolution
+// ============================================
+
+/// Expand the selected features with their transitive dependencies.
+///
+/// Fails on unknown features, dependencies on features the template does not
+/// declare, and required features that end up disabled. Conflicts between
+/// enabled features are reported as warnings.
+pub fn resolve_features(
+    available: &[TemplateFeature],
+    selected: &[String],
+) -> Result<FeatureResolution, String> {
+    let by_id: HashMap<&str, &TemplateFeature> = available.iter()
+        .map(|f| (f.id.as_str(), f))
+        .collect();
+
+    let mut features: Vec<String> = Vec::new();
+    let mut auto_enabled = Vec::new();
+    let mut warnings = Vec::new();
+
+    // Templates without a feature list (e.g. default metadata) accept any selection as-is
+    if available.is_empty() {
+        for feature in selected {
+            if !features.contains(feature) {
+                features.push(feature.clone());
+            }
+        }
+        return Ok(FeatureResolution { features, auto_enabled, warnings });
+    }
+
+    // A selected feature stays the user's choice even when another selection reaches it first
+    let chosen: HashSet<&str> = selected.iter().map(String::as_str).collect();
+    let mut stack: Vec<(String, Option<String>)> = selected.iter()
+        .rev()
+        .map(|f| (f.clone(), None))
+        .collect();
+
+    while let Some((id, required_by)) = stack.pop() {
+        if features.contains(&id) {
+            continue;
+        }
+
+        let feature = match by_id.get(id.as_str()) {
+            Some(f) => f,
+            None => {
+                return Err(match required_by {
+                    Some(parent) => format!("Feature '{}' depends on unknown feature '{}'", parent, id),
+                    None => format!("Unknown feature: {}", id),
+                });
+            }
+        };
+
+        if required_by.is_some() && !chosen.contains(id.as_str()) {
+            auto_enabled.push(id.clone());
+        }
+        features.push(id.clone());
+
+        if let Some(deps) = &feature.dependencies {
+            for dep in deps.iter().rev() {
+                if !features.contains(dep) {
+                    stack.push((dep.clone(), Some(id.clone())));
+                }
+            }
+        }
+    }
+
+    let missing: Vec<&str> = available.iter()
+        .filter(|f| f.required && !features.contains(&f.id))
+        .map(|f| f.id.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing required features: {}", missing.join(", ")));
+    }
+
+    let mut reported: Vec<(String, String)> = Vec::new();
+    for feature in &features {
+        let conflicts = by_id.get(feature.as_str())
+            .and_then(|f| f.conflicts_with.as_ref());
+        for other in conflicts.into_iter().flatten() {
+            if !features.contains(other) {
+                continue;
+            }
+            // Report each conflicting pair once, whichever side declares it
+            let pair = if feature < other {
+                (feature.clone(), other.clone())
+            } else {
+                (other.clone(), feature.clone())
+            };
+            if !reported.contains(&pair) {
+                warnings.push(format!("Feature '{}' conflicts with '{}'", pair.0, pair.1));
+                reported.push(pair);
+            }
+        }
+    }
+
+    Ok(FeatureResolution { features, auto_enabled, warnings })
+}
+
+// ============================================
+// Helper Functions
+// ============================================
+
+fn format_date(date: Option<&str>, format: &str) -> String {
+    let parsed = date.and_then(|d| {
+        chrono::DateTime::parse_from_rfc3339(d)
+            .map(|dt| dt.date_naive())
+            .or_else(|_| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d"))
+            .ok()
+    });
+
+    match (date, parsed) {
+        // Leave unparseable input alone rather than silently substituting today
+        (Some(original), None) => original.to_string(),
+        (_, Some(d)) => d.format(format).to_string(),
+        (None, None) => chrono::Local::now().date_naive().format(format).to_string(),
+    }
+}
+
+fn format_number(value: f64, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = match formatted.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut grouped = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    let sign = if value < 0.0 && formatted.chars().any(|c| c != '0' && c != '.') { "-" } else { "" };
+    match fraction {
+        Some(f) => format!("{}{}.{}", sign, grouped, f),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+/// Block helper behind `{{#if_feature "name"}}`; a named fn because the
+/// render call needs lifetimes a closure signature cannot express.
+fn if_feature_helper<'reg, 'rc>(
+    h: &handlebars::Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc handlebars::Context,
+    rc: &mut handlebars::RenderContext<'reg, 'rc>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let feature = h.param(0).and_then(|v| v.value().as_str()).unwrap_or("");
+    let enabled = ctx.data()
+        .get(format!("feature_{}", feature))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let branch = if enabled { h.template() } else { h.inverse() };
+    if let Some(template) = branch {
+        template.render(r, ctx, rc, out)?;
+    }
+    Ok(())
+}
+
+fn to_camel_case(s: &str) -> String {
+    let mut result = String::new();
+    let mut capitalize_next = false;
+    
+    for (i, c) in s.chars().enumerate() {
+        if c == '-' || c == '_' || c == ' ' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            result.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else if i == 0 {
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    
+    result
+}
+
+fn to_pascal_case(s: &str) -> String {
+    let camel = to_camel_case(s);
+    let mut chars = camel.chars();
+    match chars.next() {
+        None => String::new(),
+        Some(c) => c.to_uppercase().collect::<String>() + chars.as_str(),
+    }
+}
+
+fn to_snake_case(s: &str) -> String {
+    let mut result = String::new();
+    
+    for (i, c) in s.chars().enumerate() {
+        if c == '-' || c == ' ' {
+            result.push('_');
+        } else if c.is_uppercase() && i > 0 {
+            result.push('_');
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c.to_ascii_lowercase());
+        }
+    }
+    
+    result
+}
+
+fn to_kebab_case(s: &str) -> String {
+    let mut result = String::new();
+    
+    for (i, c) in s.chars().enumerate() {
+        if c == '_' || c == ' ' {
+            result.push('-');
+        } else if c.is_uppercase() && i > 0 {
+            result.push('-');
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c.to_ascii_lowercase());
+        }
+    }
+    
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature(id: &str, required: bool, deps: &[&str], conflicts: &[&str]) -> TemplateFeature {
+        TemplateFeature {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            required,
+            default: false,
+            dependencies: if deps.is_empty() { None } else { Some(deps.iter().map(|d| d.to_string()).collect()) },
+            conflicts_with: if conflicts.is_empty() { None } else { Some(conflicts.iter().map(|c| c.to_string()).collect()) },
+        }
+    }
+
+    fn field(id: &str, field_type: ConfigFieldType, required: bool) -> ConfigField {
+        ConfigField {
+            id: id.to_string(),
+            name: id.to_string(),
+            field_type,
+            required,
+            default: None,
+            validation: None,
+            options: None,
+            depends_on: None,
+            help_text: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_schema_values_required_and_type() {
+        let schema = ConfigSchema {
+            fields: vec![
+                field("app_title", ConfigFieldType::Text, true),
+                field("port", ConfigFieldType::Number, false),
+            ],
+        };
+        let mut values = HashMap::new();
+        values.insert("port".to_string(), serde_json::json!("3000"));
+
+        let errors = validate_schema_values(&schema, &values);
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["app_title", "port"]);
+    }
+
+    #[test]
+    fn test_validate_schema_values_rules() {
+        let mut port = field("port", ConfigFieldType::Number, true);
+        port.validation = Some(ValidationRule {
+            pattern: None,
+            min: Some(1024),
+            max: Some(65535),
+            min_length: None,
+            max_length: None,
+            message: "Port must be between 1024 and 65535".to_string(),
+        });
+        let schema = ConfigSchema { fields: vec![port] };
+
+        let mut values = HashMap::new();
+        values.insert("port".to_string(), serde_json::json!(80));
+        let errors = validate_schema_values(&schema, &values);
+        assert_eq!(errors[0].message, "Port must be between 1024 and 65535");
+
+        values.insert("port".to_string(), serde_json::json!(8080));
+        assert!(validate_schema_values(&schema, &values).is_empty());
+    }
+
+    #[test]
+    fn test_validate_schema_values_skips_hidden_fields() {
+        let mut stripe_key = field("stripe_key", ConfigFieldType::Text, true);
+        stripe_key.depends_on = Some("feature_stripe_payments".to_string());
+        let schema = ConfigSchema { fields: vec![stripe_key] };
+
+        let mut values = HashMap::new();
+        assert!(validate_schema_values(&schema, &values).is_empty());
+
+        values.insert("feature_stripe_payments".to_string(), serde_json::json!(true));
+        assert_eq!(validate_schema_values(&schema, &values).len(), 1);
+    }
+
+    fn render(template: &str, context: serde_json::Value) -> String {
+        let engine = TemplateEngine::new(std::env::temp_dir());
+        engine.handlebars.render_template(template, &context).unwrap()
+    }
+
+    #[test]
+    fn test_if_feature_helper() {
+        let template = "{{#if_feature \"auth\"}}login{{else}}public{{/if_feature}}";
+        assert_eq!(render(template, serde_json::json!({ "feature_auth": true })), "login");
+        assert_eq!(render(template, serde_json::json!({ "feature_blog": true })), "public");
+        assert_eq!(render("{{#if_feature \"auth\"}}login{{/if_feature}}", serde_json::json!({})), "");
+    }
+
+    #[test]
+    fn test_eq_helper() {
+        let template = "{{#if (eq database \"postgres\")}}pg{{else}}other{{/if}}";
+        assert_eq!(render(template, serde_json::json!({ "database": "postgres" })), "pg");
+        assert_eq!(render(template, serde_json::json!({ "database": "sqlite" })), "other");
+    }
+
+    #[test]
+    fn test_default_helper() {
+        let template = "{{default port 3000}}";
+        assert_eq!(render(template, serde_json::json!({ "port": 8080 })), "8080");
+        assert_eq!(render(template, serde_json::json!({})), "3000");
+        assert_eq!(render("{{default title \"Untitled\"}}", serde_json::json!({ "title": "" })), "Untitled");
+    }
+
+    #[test]
+    fn test_date_helpers() {
+        let year = chrono::Local::now().format("%Y").to_string();
+        assert_eq!(render("{{year}}", serde_json::json!({})), year);
+        assert_eq!(render("{{formatDate \"%Y\"}}", serde_json::json!({})), year);
+        assert_eq!(
+            render("{{formatDate released \"%d/%m/%Y\"}}", serde_json::json!({ "released": "2024-03-09" })),
+            "09/03/2024"
+        );
+    }
+
+    #[test]
+    fn test_format_number_helper() {
+        assert_eq!(render("{{formatNumber 1234567}}", serde_json::json!({})), "1,234,567");
+        assert_eq!(render("{{formatNumber price 2}}", serde_json::json!({ "price": 1234.5 })), "1,234.50");
+        assert_eq!(format_number(-999.0, 0), "-999");
+    }
+
+    // 1x1 transparent PNG
+    const PNG_FIXTURE: &[u8] = &[
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D,
+        0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+        0x08, 0x06, 0x00, 0x00, 0x00, 0x1F, 0x15, 0xC4, 0x89, 0x00, 0x00, 0x00,
+        0x0D, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x00, 0x01, 0x00, 0x00,
+        0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00, 0x00, 0x00, 0x00, 0x49,
+        0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+    ];
+
+    #[tokio::test]
+    async fn test_process_files_copies_binary_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let files_dir = dir.path().join("template").join("files");
+        let output_dir = dir.path().join("output");
+        std::fs::create_dir_all(files_dir.join("public")).unwrap();
+        std::fs::write(files_dir.join("public/logo.png"), PNG_FIXTURE).unwrap();
+        std::fs::write(files_dir.join("README.md.hbs"), "# {{project_name}}").unwrap();
+
+        let engine = TemplateEngine::new(dir.path().to_path_buf());
+        let context = serde_json::json!({ "project_name": "demo" });
+
+        let mut files_created = Vec::new();
+        engine.process_files(
+            vec![
+                (files_dir.join("public/logo.png"), PathBuf::from("public/logo.png")),
+                (files_dir.join("README.md.hbs"), PathBuf::from("README.md.hbs")),
+            ],
+            &output_dir,
+            &context,
+            &|_| {},
+            &mut files_created,
+        ).await.unwrap();
+        let [readme, png] = files_created.as_slice() else {
+            panic!("unexpected files: {:?}", files_created);
+        };
+        assert_eq!(std::fs::read(png).unwrap(), PNG_FIXTURE);
+
+        assert!(readme.ends_with("README.md"));
+        assert_eq!(std::fs::read_to_string(readme).unwrap(), "# demo");
+    }
+
+    #[tokio::test]
+    async fn test_preview_cached_until_template_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let readme = dir.path().join("demo").join("files").join("README.md.hbs");
+        std::fs::create_dir_all(readme.parent().unwrap()).unwrap();
+        std::fs::write(&readme, "# {{project_name}}").unwrap();
+
+        let cache = Arc::new(RenderCache::new(1.0, 10, 60));
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        engine.set_preview_cache(cache.clone());
+        let config = ProjectConfig {
+            template_id: "demo".to_string(),
+            project_name: "shop".to_string(),
+            project_description: None,
+            output_path: dir.path().join("out").to_string_lossy().to_string(),
+            features: vec![],
+            variables: HashMap::new(),
+            keep_partial_on_error: false,
+            strict_variables: false,
+            approved_hooks: vec![],
+        };
+
+        let first = engine.preview_project(config.clone()).await.unwrap();
+        let second = engine.preview_project(config.clone()).await.unwrap();
+        assert_eq!(first.files[0].content_preview, "# shop");
+        assert_eq!(second.files[0].content_preview, "# shop");
+        let stats = cache.stats();
+        assert_eq!((stats.hit_count, stats.miss_count), (1, 1));
+
+        std::fs::write(&readme, "# {{uppercase project_name}}").unwrap();
+        let file = std::fs::File::options().write(true).open(&readme).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(5)).unwrap();
+
+        let edited = engine.preview_project(config).await.unwrap();
+        assert_eq!(edited.files[0].content_preview, "# SHOP");
+        assert_eq!(cache.stats().miss_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_lint_reports_undefined_variables_with_locations() {
+        let dir = tempfile::tempdir().unwrap();
+        let files_dir = dir.path().join("demo").join("files");
+        std::fs::create_dir_all(files_dir.join("src")).unwrap();
+        std::fs::write(files_dir.join("README.md.hbs"), "# {{project_name}}\n{{#if feature_auth}}auth{{/if}}").unwrap();
+        std::fs::write(
+            files_dir.join("src/config.ts.hbs"),
+            "export const name = '{{project_nmae}}';\nexport const port = {{server.port}};\n",
+        ).unwrap();
+
+        let engine = TemplateEngine::new(dir.path().to_path_buf());
+        let mut config = ProjectConfig {
+            template_id: "demo".to_string(),
+            project_name: "shop".to_string(),
+            project_description: None,
+            output_path: dir.path().join("out").to_string_lossy().to_string(),
+            features: vec![],
+            variables: HashMap::new(),
+            keep_partial_on_error: false,
+            strict_variables: false,
+            approved_hooks: vec![],
+        };
+
+        let errors = engine.lint_template(config.clone()).await.unwrap();
+        let found: Vec<_> = errors.iter()
+            .map(|e| (e.file.as_str(), e.variable.as_deref(), e.line))
+            .collect();
+        assert_eq!(found, vec![
+            ("src/config.ts.hbs", Some("project_nmae"), Some(1)),
+            ("src/config.ts.hbs", Some("server.port"), Some(2)),
+        ]);
+
+        // Lenient generation renders the typo as empty
+        let result = engine.generate_project(config.clone(), |_| {}).await.unwrap();
+        let generated = std::fs::read_to_string(PathBuf::from(&result.project_path).join("src/config.ts")).unwrap();
+        assert!(generated.starts_with("export const name = '';"));
+
+        config.project_name = "strict-shop".to_string();
+        config.strict_variables = true;
+        let err = engine.generate_project(config.clone(), |_| {}).await.unwrap_err();
+        assert!(err.contains("src/config.ts.hbs:1:"), "{}", err);
+        assert!(err.contains("(server.port)"), "{}", err);
+        assert!(!dir.path().join("out/strict-shop").exists());
+
+        config.variables.insert("project_nmae".to_string(), serde_json::json!("shop"));
+        config.variables.insert("server".to_string(), serde_json::json!({ "port": 3000 }));
+        assert!(engine.lint_template(config.clone()).await.unwrap().is_empty());
+        engine.generate_project(config, |_| {}).await.unwrap();
+    }
+
+    #[test]
+    fn test_evaluate_condition() {
+        let context = serde_json::json!({
+            "feature_auth": true,
+            "feature_sso": false,
+            "database": { "kind": "postgres" },
+        });
+        let eval = |expr: &str| evaluate_condition(expr, &context).unwrap();
+
+        assert!(eval("feature_auth"));
+        assert!(!eval("feature_payments"));
+        assert!(eval("feature_auth && !feature_sso"));
+        assert!(!eval("feature_auth && feature_sso"));
+        assert!(eval("feature_sso || !(feature_payments && feature_auth)"));
+        assert!(eval("database.kind && !database.replicas"));
+        assert!(!eval("database.kind.name"));
+        assert!(!eval("!true || false"));
+
+        for invalid in ["", "feature_auth &", "(feature_auth", "feature_auth feature_sso", "a == b"] {
+            assert!(evaluate_condition(invalid, &context).is_err(), "accepted {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_front_matter() {
+        let yaml = parse_front_matter("---\nwhen: feature_auth && !feature_sso\n---\nbody").unwrap();
+        assert_eq!((yaml.when.as_str(), yaml.lines, yaml.body), ("feature_auth && !feature_sso", 3, "body"));
+
+        let toml = parse_front_matter("+++\r\ntitle = \"x\"\r\nwhen = \"feature_auth\"\r\n+++\r\nbody").unwrap();
+        assert_eq!((toml.when.as_str(), toml.lines, toml.body), ("feature_auth", 4, "body"));
+
+        // Front matter of the generated file itself is left alone
+        assert!(parse_front_matter("---\ntitle: Docs\n---\n# Docs").is_none());
+        assert!(parse_front_matter("---\nwhen: feature_auth\n").is_none());
+        assert!(parse_front_matter("# {{project_name}}").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_front_matter_conditions_select_generated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let files_dir = dir.path().join("demo").join("files");
+        std::fs::create_dir_all(&files_dir).unwrap();
+        let files = [
+            ("auth.ts.hbs", "---\nwhen: feature_auth\n---\nauth for {{project_name}}"),
+            ("password.ts.hbs", "---\nwhen: feature_auth && !feature_sso\n---\npassword"),
+            ("sso.ts.hbs", "+++\nwhen = \"feature_auth && feature_sso\"\n+++\nsso"),
+            ("public.ts.hbs", "---\nwhen: \"!feature_auth || region\"\n---\npublic"),
+            ("docs.md.hbs", "---\ntitle: {{project_name}}\n---\n# Docs"),
+        ];
+        for (name, content) in files {
+            std::fs::write(files_dir.join(name), content).unwrap();
+        }
+
+        let engine = TemplateEngine::new(dir.path().to_path_buf());
+        let generate = |project_name: &str, features: &[&str]| {
+            let config = ProjectConfig {
+                template_id: "demo".to_string(),
+                project_name: project_name.to_string(),
+                project_description: None,
+                output_path: dir.path().join("out").to_string_lossy().to_string(),
+                features: features.iter().map(|f| f.to_string()).collect(),
+                variables: HashMap::new(),
+                keep_partial_on_error: false,
+                strict_variables: false,
+                approved_hooks: vec![],
+            };
+            let engine = &engine;
+            async move {
+                let result = engine.generate_project(config, |_| {}).await.unwrap();
+                let mut names: Vec<String> = result.files_created.iter()
+                    .map(|f| Path::new(f).file_name().unwrap().to_string_lossy().to_string())
+                    .collect();
+                names.sort();
+                (PathBuf::from(result.project_path), names)
+            }
+        };
+
+        let (path, names) = generate("with-auth", &["auth"]).await;
+        assert_eq!(names, vec!["auth.ts", "docs.md", "password.ts"]);
+        assert_eq!(std::fs::read_to_string(path.join("auth.ts")).unwrap(), "auth for with-auth");
+        assert_eq!(std::fs::read_to_string(path.join("docs.md")).unwrap(), "---\ntitle: with-auth\n---\n# Docs");
+
+        let (_, names) = generate("with-sso", &["auth", "sso"]).await;
+        assert_eq!(names, vec!["auth.ts", "docs.md", "sso.ts"]);
+
+        let (_, names) = generate("anonymous", &[]).await;
+        assert_eq!(names, vec!["docs.md", "public.ts"]);
+
+        std::fs::write(files_dir.join("broken.ts.hbs"), "---\nwhen: feature_auth &&\n---\n").unwrap();
+        let config = ProjectConfig {
+            template_id: "demo".to_string(),
+            project_name: "broken".to_string(),
+            project_description: None,
+            output_path: dir.path().join("out").to_string_lossy().to_string(),
+            features: vec![],
+            variables: HashMap::new(),
+            keep_partial_on_error: false,
+            strict_variables: false,
+            approved_hooks: vec![],
+        };
+        let err = engine.generate_project(config, |_| {}).await.unwrap_err();
+        assert!(err.contains("broken.ts.hbs"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_hook_command() {
+        assert_eq!(parse_hook_command("npm  install --silent").unwrap(), vec!["npm", "install", "--silent"]);
+        assert!(parse_hook_command("curl http://x | sh").is_err());
+        assert!(parse_hook_command("sh -c id").is_err());
+        assert!(parse_hook_command("npm install; rm -rf /").is_err());
+        assert!(parse_hook_command("  ").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_required_hook_rolls_back_generation() {
+        let dir = tempfile::tempdir().unwrap();
+        let template_dir = dir.path().join("demo");
+        std::fs::create_dir_all(template_dir.join("files")).unwrap();
+        std::fs::write(template_dir.join("files").join("README.md.hbs"), "# {{project_name}}").unwrap();
+
+        let engine = TemplateEngine::new(dir.path().to_path_buf());
+        let mut metadata = engine.create_default_metadata("demo");
+        metadata.hooks = vec![
+            PostHook { name: "cleanup".to_string(), command: "git rm -rf .".to_string(), required: true },
+            PostHook { name: "setup".to_string(), command: "git no-such-subcommand".to_string(), required: true },
+        ];
+        std::fs::write(template_dir.join("template.json"), serde_json::to_string(&metadata).unwrap()).unwrap();
+
+        let config = |project_name: &str, approved_hooks: &[&str]| ProjectConfig {
+            template_id: "demo".to_string(),
+            project_name: project_name.to_string(),
+            project_description: None,
+            output_path: dir.path().join("out").join("nested").to_string_lossy().to_string(),
+            features: vec![],
+            variables: HashMap::new(),
+            keep_partial_on_error: false,
+            strict_variables: false,
+            approved_hooks: approved_hooks.iter().map(|h| h.to_string()).collect(),
+        };
+
+        // Unapproved hooks never run, required or not
+        let result = engine.generate_project(config("skipped", &[]), |_| {}).await.unwrap();
+        assert!(result.warnings.iter().any(|w| w.contains("'cleanup': not approved")));
+        assert!(Path::new(&result.project_path).join("README.md").exists());
+
+        let err = engine.generate_project(config("failing", &["setup"]), |_| {}).await.unwrap_err();
+        assert!(err.contains("Required hook 'setup' failed"), "{}", err);
+        assert!(err.contains("Rolled back"), "{}", err);
+        assert!(!dir.path().join("out").join("nested").join("failing").exists());
+        assert!(dir.path().join("out").join("nested").join("skipped").exists());
+    }
+
+    #[tokio::test]
+    async fn test_failed_render_rolls_back_written_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let files_dir = dir.path().join("demo").join("files");
+        std::fs::create_dir_all(files_dir.join("src")).unwrap();
+        for i in 0..20 {
+            std::fs::write(files_dir.join("src").join(format!("file{:02}.txt.hbs", i)), "{{project_name}}").unwrap();
+        }
+        std::fs::write(files_dir.join("src").join("file10.txt.hbs"), "{{#if project_name}}unclosed").unwrap();
+
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        engine.set_generation_concurrency(4);
+        let config = |keep_partial_on_error: bool| ProjectConfig {
+            template_id: "demo".to_string(),
+            project_name: "app".to_string(),
+            project_description: None,
+            output_path: dir.path().join("out").join("nested").to_string_lossy().to_string(),
+            features: vec![],
+            variables: HashMap::new(),
+            keep_partial_on_error,
+            strict_variables: false,
+            approved_hooks: vec![],
+        };
+
+        let err = engine.generate_project(config(false), |_| {}).await.unwrap_err();
+        assert!(err.contains("Rolled back"), "{}", err);
+        assert!(!dir.path().join("out").exists());
+
+        let err = engine.generate_project(config(true), |_| {}).await.unwrap_err();
+        assert!(err.contains("partial output kept"), "{}", err);
+        assert!(dir.path().join("out").join("nested").join("app").exists());
+    }
+
+    #[tokio::test]
+    async fn test_process_files_parallel_order_is_stable() {
+        let dir = tempfile::tempdir().unwrap();
+        let files_dir = dir.path().join("files");
+
+        let mut files = Vec::new();
+        for i in 0..200 {
+            let relative_path = PathBuf::from(format!("dir{}/file{:03}.txt.hbs", i % 10, i));
+            std::fs::create_dir_all(files_dir.join(relative_path.parent().unwrap())).unwrap();
+            std::fs::write(files_dir.join(&relative_path), format!("{{{{project_name}}}} {}", i)).unwrap();
+            files.push((files_dir.join(&relative_path), relative_path));
+        }
+        // Submission order must not matter
+        files.reverse();
+
+        let mut engine = TemplateEngine::new(dir.path().to_path_buf());
+        engine.set_generation_concurrency(16);
+        let context = serde_json::json!({ "project_name": "demo" });
+
+        let mut runs = Vec::new();
+        for run in 0..2 {
+            let output_dir = dir.path().join(format!("out{}", run));
+            let percents = std::sync::Mutex::new(Vec::new());
+            let mut files_created = Vec::new();
+
+            engine.process_files(
+                files.clone(),
+                &output_dir,
+                &context,
+                &|p: GenerationProgress| percents.lock().unwrap().push(p.percent),
+                &mut files_created,
+            ).await.unwrap();
+
+            let percents = percents.into_inner().unwrap();
+            assert_eq!(percents.len(), 200);
+            assert!(percents.windows(2).all(|w| w[0] <= w[1]));
+
+            let relative: Vec<String> = files_created.iter()
+                .map(|f| Path::new(f).strip_prefix(&output_dir).unwrap().to_string_lossy().to_string())
+                .collect();
+            runs.push(relative);
+        }
+
+        let mut expected: Vec<String> = files.iter()
+            .map(|(_, relative_path)| rewrite_output_path(relative_path).to_string_lossy().to_string())
+            .collect();
+        expected.sort();
+        assert_eq!(runs[0], expected);
+        assert_eq!(runs[0], runs[1]);
+
+        let sample = dir.path().join("out0/dir7/file117.txt");
+        assert_eq!(std::fs::read_to_string(sample).unwrap(), "demo 117");
+    }
+
+    #[tokio::test]
+    async fn test_process_files_rejects_escaping_output_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let files_dir = dir.path().join("files");
+        let output_dir = dir.path().join("output");
+        std::fs::create_dir_all(&files_dir).unwrap();
+
+        let engine = TemplateEngine::new(dir.path().to_path_buf());
+        let context = serde_json::json!({ "project_name": "demo" });
+
+        for name in ["__..__etc__passwd", "__..__..__escape.txt.hbs", "__feature_auth__..__..__escape.txt"] {
+            std::fs::write(files_dir.join(name), "owned").unwrap();
+            let mut files_created = Vec::new();
+            let result = engine.process_files(
+                vec![(files_dir.join(name), PathBuf::from(name))],
+                &output_dir,
+                &context,
+                &|_| {},
+                &mut files_created,
+            ).await;
+            assert!(result.unwrap_err().contains("Path traversal"), "{} was written", name);
+            assert!(files_created.is_empty());
+        }
+        assert!(!dir.path().join("escape.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_collect_template_files_refuses_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+
+        let files_dir = dir.path().join("files");
+        std::fs::create_dir_all(&files_dir).unwrap();
+        std::fs::write(files_dir.join("README.md"), "# demo").unwrap();
+
+        let engine = TemplateEngine::new(dir.path().to_path_buf());
+        assert_eq!(engine.collect_template_files(&files_dir).await.unwrap().len(), 1);
+
+        std::os::unix::fs::symlink(outside.path(), files_dir.join("linked")).unwrap();
+        let err = engine.collect_template_files(&files_dir).await.unwrap_err();
+        assert!(err.contains("symlink"));
+    }
+
+    #[test]
+    fn test_rewrite_output_path() {
+        assert_eq!(rewrite_output_path(Path::new("src/index.ts.hbs")), PathBuf::from("src/index.ts"));
+        assert_eq!(rewrite_output_path(Path::new("__feature_auth__login.ts")), PathBuf::from("auth/login.ts"));
+        assert_eq!(rewrite_output_path(Path::new("README.md")), PathBuf::from("README.md"));
+    }
+
+    #[test]
+    fn test_truncate_preview() {
+        let (preview, truncated) = truncate_preview("short");
+        assert_eq!(preview, "short");
+        assert!(!truncated);
+
+        let long = "x".repeat(PREVIEW_MAX_CHARS + 10);
+        let (preview, truncated) = truncate_preview(&long);
+        assert_eq!(preview.len(), PREVIEW_MAX_CHARS);
+        assert!(truncated);
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(verify_checksum(b"hello", digest).is_ok());
+        assert!(verify_checksum(b"hello", &format!("sha256:{}", digest.to_uppercase())).is_ok());
+        assert!(verify_checksum(b"hello!", digest).is_err());
+    }
+
+    #[test]
+    fn test_template_source_defaults_to_local() {
+        let entry: TemplateEntry = serde_json::from_str(
+            r#"{"id": "saas-starter", "name": "SaaS Starter", "category": "saas", "path": "saas"}"#
+        ).unwrap();
+        assert!(matches!(entry.source, TemplateSource::Local));
+
+        let entry: TemplateEntry = serde_json::from_str(
+            r#"{"id": "team", "name": "Team", "category": "custom", "path": "team",
+                "source": {"type": "git", "url": "https://example.com/team.git"}}"#
+        ).unwrap();
+        assert!(matches!(entry.source, TemplateSource::Git { reference: None, .. }));
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    async fn engine_with_registry(templates_dir: &Path, entries: serde_json::Value) -> TemplateEngine {
+        let registry = serde_json::json!({ "version": "1", "templates": entries });
+        std::fs::write(templates_dir.join("registry.json"), registry.to_string()).unwrap();
+        let mut engine = TemplateEngine::new(templates_dir.to_path_buf());
+        engine.load_registry().await.unwrap();
+        engine
+    }
+
+    #[tokio::test]
+    async fn test_fetch_git_template_caches_and_refreshes() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join("files")).unwrap();
+        std::fs::write(repo.join("files").join("README.md.hbs"), "# {{project_name}}").unwrap();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "template"]);
+
+        let templates_dir = dir.path().join("templates");
+        std::fs::create_dir_all(&templates_dir).unwrap();
+        let engine = engine_with_registry(&templates_dir, serde_json::json!([{
+            "id": "team", "name": "Team", "category": "custom", "path": "team",
+            "source": { "type": "git", "url": repo.to_string_lossy() },
+        }])).await;
+
+        engine.ensure_template_available("team").await.unwrap();
+        let cached = templates_dir.join(".cache").join("team");
+        assert!(cached.join("files").join("README.md.hbs").exists());
+        assert!(!cached.join(".git").exists());
+        assert_eq!(engine.find_template_path("team").unwrap(), cached);
+
+        // A failed refresh keeps serving the cached copy
+        std::fs::remove_dir_all(&repo).unwrap();
+        engine.refresh_remote_template("team").await.unwrap();
+        assert!(cached.join("files").join("README.md.hbs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_remote_template_ids_and_urls_cannot_escape() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates_dir = dir.path().join("templates");
+        std::fs::create_dir_all(templates_dir.join(".cache")).unwrap();
+        let victim = dir.path().join("victim");
+        std::fs::create_dir_all(&victim).unwrap();
+        std::fs::write(victim.join("keep.txt"), "keep").unwrap();
+
+        let source = serde_json::json!({ "type": "http", "url": "http://127.0.0.1:9/t.tar.gz" });
+        let ids = ["../../victim", "../victim", "/tmp/victim", "a/b", "..", "team.download"];
+        let entries: Vec<serde_json::Value> = ids.iter()
+            .map(|id| serde_json::json!({ "id": id, "name": id, "category": "custom", "path": id, "source": source }))
+            .collect();
+        let engine = engine_with_registry(&templates_dir, serde_json::Value::Array(entries)).await;
+
+        for id in ids {
+            let err = engine.ensure_template_available(id).await.unwrap_err();
+            assert!(err.contains("Invalid template id"), "{}: {}", id, err);
+            assert!(engine.refresh_remote_template(id).await.is_err());
+        }
+        assert!(victim.join("keep.txt").exists());
+
+        let dest = dir.path().join("dest");
+        let err = fetch_git_template("--upload-pack=touch /tmp/pwned", None, &dest).await.unwrap_err();
+        assert!(err.contains("Invalid template URL"), "{}", err);
+        let err = fetch_git_template("https://example.com/t.git", Some("--upload-pack=x"), &dest).await.unwrap_err();
+        assert!(err.contains("Invalid template reference"), "{}", err);
+        for url in ["http://example.com/t.tar.gz", "file:///tmp/t.tar.gz"] {
+            let err = fetch_http_template(url, None, &dest).await.unwrap_err();
+            assert!(err.contains("only https URLs are allowed"), "{}: {}", url, err);
+        }
+    }
+
+    #[test]
+    fn test_resolve_features_expands_transitive_dependencies() {
+        let available = vec![
+            feature("auth", false, &["database"], &[]),
+            feature("database", false, &[], &[]),
+            feature("stripe_payments", false, &["auth"], &[]),
+        ];
+
+        let resolution = resolve_features(&available, &["stripe_payments".to_string()]).unwrap();
+        assert_eq!(resolution.features, vec!["stripe_payments", "auth", "database"]);
+        assert_eq!(resolution.auto_enabled, vec!["auth", "database"]);
+        assert!(resolution.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_features_keeps_selected_dependencies_user_chosen() {
+        let available = vec![
+            feature("auth", false, &["database"], &[]),
+            feature("database", false, &[], &[]),
+            feature("stripe_payments", false, &["auth"], &[]),
+        ];
+
+        let selected = vec!["stripe_payments".to_string(), "auth".to_string()];
+        let resolution = resolve_features(&available, &selected).unwrap();
+        assert_eq!(resolution.features, vec!["stripe_payments", "auth", "database"]);
+        assert_eq!(resolution.auto_enabled, vec!["database"]);
+    }
+
+    #[test]
+    fn test_resolve_features_missing_required() {
+        let available = vec![
+            feature("core", true, &[], &[]),
+            feature("blog", false, &[], &[]),
+        ];
+
+        let result = resolve_features(&available, &["blog".to_string()]);
+        assert!(result.unwrap_err().contains("core"));
+    }
+
+    #[test]
+    fn test_resolve_features_unknown_dependency() {
+        let available = vec![feature("auth", false, &["oauth"], &[])];
+
+        let result = resolve_features(&available, &["auth".to_string()]);
+        assert!(result.unwrap_err().contains("oauth"));
+    }
+
+    #[test]
+    fn test_resolve_features_warns_on_conflicts() {
+        let available = vec![
+            feature("stripe_payments", false, &[], &["paddle_payments"]),
+            feature("paddle_payments", false, &[], &["stripe_payments"]),
+        ];
+
+        let selected = vec!["stripe_payments".to_string(), "paddle_payments".to_string()];
+        let resolution = resolve_features(&available, &selected).unwrap();
+        assert_eq!(resolution.warnings.len(), 1);
+    }
 }
@@ -8,7 +8,7 @@
 // - Safe file operations
 
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use regex::Regex;
 use once_cell::sync::Lazy;
 
@@ -144,6 +144,65 @@ pub fn sanitize_template_path(path: &str, base_dir: &Path) -> SanitizeResult<Pat
     Ok(full_path)
 }
 
+/// Resolve a generated file's path under `output_root`, rejecting any path
+/// that would land outside it. Only plain components are accepted, and the
+/// deepest existing ancestor is canonicalized so symlinks already on disk
+/// can't redirect the write elsewhere.
+pub fn resolve_output_path(relative: &Path, output_root: &Path) -> SanitizeResult<PathBuf> {
+    let display = relative.to_string_lossy();
+    if display.contains('\0') {
+        return Err(SanitizeError::PathTraversal("Path contains null byte".to_string()));
+    }
+
+    let mut clean = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => clean.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(SanitizeError::PathTraversal(
+                    format!("Output path escapes output directory: {}", display)
+                ));
+            }
+        }
+    }
+    if clean.as_os_str().is_empty() {
+        return Err(SanitizeError::PathTraversal(format!("Empty output path: {}", display)));
+    }
+
+    let output_path = output_root.join(&clean);
+    let root_canonical = canonicalize_existing_prefix(output_root)?;
+    let target_canonical = canonicalize_existing_prefix(&output_path)?;
+    if target_canonical == root_canonical || !target_canonical.starts_with(&root_canonical) {
+        return Err(SanitizeError::PathTraversal(
+            format!("Output path escapes output directory: {}", target_canonical.display())
+        ));
+    }
+
+    Ok(output_path)
+}
+
+/// Canonicalize the deepest existing ancestor of `path` and re-append the rest
+fn canonicalize_existing_prefix(path: &Path) -> SanitizeResult<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+
+    let base = if existing.as_os_str().is_empty() { Path::new(".") } else { existing };
+    let mut canonical = base.canonicalize()
+        .map_err(|e| SanitizeError::IoError(e.to_string()))?;
+    canonical.extend(rest.iter().rev());
+    Ok(canonical)
+}
+
 /// Validate that a path is within allowed template directories
 pub fn validate_template_directory(path: &Path, workspace_dir: &Path) -> SanitizeResult<()> {
     let path_str = path.to_string_lossy();
@@ -415,6 +474,33 @@ mod tests {
         assert!(result.is_err());
     }
     
+    #[test]
+    fn test_resolve_output_path_stays_under_root() {
+        let root = tempfile::tempdir().unwrap();
+        let output = root.path().join("out");
+
+        let resolved = resolve_output_path(Path::new("src/./index.ts"), &output).unwrap();
+        assert_eq!(resolved, output.join("src/index.ts"));
+
+        for malicious in ["/../etc/passwd", "../etc/passwd", "src/../../escape", "/etc/passwd", ""] {
+            let result = resolve_output_path(Path::new(malicious), &output);
+            assert!(matches!(result, Err(SanitizeError::PathTraversal(_))), "{:?} was accepted", malicious);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_output_path_rejects_symlinked_dirs() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let output = root.path().join("out");
+        std::fs::create_dir_all(&output).unwrap();
+        std::os::unix::fs::symlink(outside.path(), output.join("link")).unwrap();
+
+        let result = resolve_output_path(Path::new("link/passwd"), &output);
+        assert!(matches!(result, Err(SanitizeError::PathTraversal(_))));
+    }
+
     #[test]
     fn test_validate_variable_name() {
         assert!(validate_variable_name("project_name").is_ok());
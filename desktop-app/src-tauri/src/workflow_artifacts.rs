@@ -0,0 +1,390 @@
+// Workflow Artifacts - Files produced by Python workflows
+//
+// Provides:
+// - Copying declared artifacts out of the workflow's working directory
+// - A per-workflow manifest with sizes and content types
+// - Age-based retention alongside execution history
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::python_bridge::WorkflowArtifact;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Lists, under `root`, the workspace artifact directories ever used, so
+/// retention reaches workspaces from earlier sessions
+const WORKSPACE_ROOTS_FILE: &str = "workspaces.json";
+
+/// Directory under a workspace that holds its workflow artifacts
+const WORKSPACE_ARTIFACTS_DIR: &str = ".smartspec/artifacts";
+
+// ============================================
+// Types
+// ============================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredArtifact {
+    pub name: String,
+    /// Where the copy lives
+    pub path: String,
+    /// Where the workflow wrote it
+    pub source_path: String,
+    pub kind: Option<String>,
+    pub size_bytes: u64,
+    pub content_type: String,
+    pub collected_at: String,
+    /// `validate-spec` result, for spec artifacts
+    #[serde(default)]
+    pub validation: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    workflow_id: String,
+    created_at: String,
+    artifacts: Vec<StoredArtifact>,
+}
+
+// ============================================
+// Store
+// ============================================
+
+/// Artifacts are copied into `<workspace>/.smartspec/artifacts/<workflow_id>`
+/// when the workflow ran in a workspace, otherwise under `root`.
+pub struct ArtifactStore {
+    root: PathBuf,
+    /// Artifact directories of workflows run this session
+    dirs: Mutex<HashMap<String, PathBuf>>,
+    /// `<workspace>/.smartspec/artifacts` directories, persisted under `root`
+    workspace_roots: Mutex<Vec<PathBuf>>,
+}
+
+impl ArtifactStore {
+    pub fn new(root: PathBuf) -> Self {
+        let workspace_roots = std::fs::read_to_string(root.join(WORKSPACE_ROOTS_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            root,
+            dirs: Mutex::new(HashMap::new()),
+            workspace_roots: Mutex::new(workspace_roots),
+        }
+    }
+
+    /// Choose and remember the artifact directory for a workflow
+    pub fn register(&self, workflow_id: &str, workspace_path: Option<&Path>) -> Result<PathBuf> {
+        let name = dir_name(workflow_id)?;
+        let dir = match workspace_path {
+            Some(workspace) => {
+                let artifacts_root = workspace.join(WORKSPACE_ARTIFACTS_DIR);
+                self.remember_workspace_root(&artifacts_root)?;
+                artifacts_root.join(name)
+            }
+            None => self.root.join(name),
+        };
+        self.dirs.lock().unwrap().insert(workflow_id.to_string(), dir.clone());
+        Ok(dir)
+    }
+
+    fn remember_workspace_root(&self, artifacts_root: &Path) -> Result<()> {
+        let mut roots = self.workspace_roots.lock().unwrap();
+        if roots.iter().any(|root| root == artifacts_root) {
+            return Ok(());
+        }
+        roots.push(artifacts_root.to_path_buf());
+        std::fs::create_dir_all(&self.root)
+            .with_context(|| format!("Failed to create artifact directory {:?}", self.root))?;
+        std::fs::write(self.root.join(WORKSPACE_ROOTS_FILE), serde_json::to_string_pretty(&*roots)?)
+            .context("Failed to record workspace artifact directory")
+    }
+
+    /// The registered directory, else wherever an earlier session left it
+    fn dir_of(&self, workflow_id: &str) -> Result<PathBuf> {
+        let name = dir_name(workflow_id)?;
+        if let Some(dir) = self.dirs.lock().unwrap().get(workflow_id) {
+            return Ok(dir.clone());
+        }
+        let earlier = self.workspace_roots.lock().unwrap().iter()
+            .map(|root| root.join(name))
+            .find(|dir| dir.join(MANIFEST_FILE).is_file());
+        Ok(earlier.unwrap_or_else(|| self.root.join(name)))
+    }
+
+    /// Copy a declared artifact into the workflow's artifact directory.
+    /// Relative paths resolve against `working_dir`; nothing outside it is
+    /// collected.
+    pub fn collect(
+        &self,
+        workflow_id: &str,
+        working_dir: &Path,
+        declared: &WorkflowArtifact,
+    ) -> Result<StoredArtifact> {
+        let working_dir = working_dir.canonicalize()
+            .with_context(|| format!("Working directory not found: {:?}", working_dir))?;
+        let source = working_dir.join(&declared.path).canonicalize()
+            .with_context(|| format!("Artifact not found: {}", declared.path))?;
+        if !source.starts_with(&working_dir) {
+            anyhow::bail!("Artifact is outside the workflow directory: {}", declared.path);
+        }
+        if !source.is_file() {
+            anyhow::bail!("Artifact is not a file: {}", declared.path);
+        }
+
+        let dir = self.dir_of(workflow_id)?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create artifact directory {:?}", dir))?;
+
+        let mut manifest = read_manifest(&dir)?.unwrap_or_else(|| Manifest {
+            workflow_id: workflow_id.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            artifacts: Vec::new(),
+        });
+        let source_path = source.to_string_lossy().to_string();
+        if let Some(existing) = manifest.artifacts.iter().find(|a| a.source_path == source_path) {
+            return Ok(existing.clone());
+        }
+
+        let name = unique_name(&manifest, &source);
+        let target = dir.join(&name);
+        let size_bytes = std::fs::copy(&source, &target)
+            .with_context(|| format!("Failed to copy artifact {}", declared.path))?;
+
+        let artifact = StoredArtifact {
+            name,
+            path: target.to_string_lossy().to_string(),
+            source_path,
+            kind: declared.kind.clone(),
+            size_bytes,
+            content_type: content_type_for(&target).to_string(),
+            collected_at: chrono::Utc::now().to_rfc3339(),
+            validation: None,
+        };
+        manifest.artifacts.push(artifact.clone());
+        write_manifest(&dir, &manifest)?;
+        Ok(artifact)
+    }
+
+    /// Attach a `validate-spec` result to a collected artifact
+    pub fn set_validation(&self, workflow_id: &str, name: &str, validation: serde_json::Value) -> Result<()> {
+        let dir = self.dir_of(workflow_id)?;
+        let mut manifest = read_manifest(&dir)?
+            .with_context(|| format!("No artifacts for workflow {}", workflow_id))?;
+        if let Some(artifact) = manifest.artifacts.iter_mut().find(|a| a.name == name) {
+            artifact.validation = Some(validation);
+        }
+        write_manifest(&dir, &manifest)
+    }
+
+    pub fn list(&self, workflow_id: &str) -> Result<Vec<StoredArtifact>> {
+        Ok(read_manifest(&self.dir_of(workflow_id)?)?
+            .map(|manifest| manifest.artifacts)
+            .unwrap_or_default())
+    }
+
+    /// Remove artifact directories created more than `days` ago, both under
+    /// the root and in every workspace that has held artifacts. Returns how
+    /// many went.
+    pub fn delete_older_than(&self, days: i64) -> Result<usize> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(days);
+
+        let mut roots = self.workspace_roots.lock().unwrap().clone();
+        roots.push(self.root.clone());
+        let mut dirs = Vec::new();
+        for root in &roots {
+            if let Ok(entries) = std::fs::read_dir(root) {
+                dirs.extend(entries.flatten()
+                    .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                    .map(|entry| entry.path()));
+            }
+        }
+        dirs.sort();
+        dirs.dedup();
+
+        let mut deleted = 0;
+        for dir in dirs {
+            let Some(manifest) = read_manifest(&dir)? else {
+                continue;
+            };
+            let created_at = chrono::DateTime::parse_from_rfc3339(&manifest.created_at)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .unwrap_or(chrono::DateTime::UNIX_EPOCH);
+            if created_at < cutoff {
+                std::fs::remove_dir_all(&dir)
+                    .with_context(|| format!("Failed to delete artifacts in {:?}", dir))?;
+                self.dirs.lock().unwrap().remove(&manifest.workflow_id);
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+/// A workflow id names a directory, so it must be one plain path component
+fn dir_name(workflow_id: &str) -> Result<&str> {
+    let mut components = Path::new(workflow_id).components();
+    match (components.next(), components.next()) {
+        (Some(std::path::Component::Normal(_)), None) if !workflow_id.contains(['/', '\\']) => Ok(workflow_id),
+        _ => anyhow::bail!("Invalid workflow id: {:?}", workflow_id),
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<Option<Manifest>> {
+    let path = dir.join(MANIFEST_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {:?}", path))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .with_context(|| format!("Invalid artifact manifest {:?}", path))
+}
+
+fn write_manifest(dir: &Path, manifest: &Manifest) -> Result<()> {
+    let content = serde_json::to_string_pretty(manifest)?;
+    std::fs::write(dir.join(MANIFEST_FILE), content).context("Failed to write artifact manifest")
+}
+
+/// The source file name, suffixed when another artifact already took it
+fn unique_name(manifest: &Manifest, source: &Path) -> String {
+    let file_name = source.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "artifact".to_string());
+    let taken = |name: &str| name == MANIFEST_FILE || manifest.artifacts.iter().any(|a| a.name == name);
+    if !taken(&file_name) {
+        return file_name;
+    }
+
+    let stem = source.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = source.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..)
+        .map(|n| format!("{}-{}{}", stem, n, ext))
+        .find(|name| !taken(name))
+        .unwrap()
+}
+
+pub fn content_type_for(path: &Path) -> &'static str {
+    let ext = path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "md" | "markdown" => "text/markdown",
+        "txt" | "log" => "text/plain",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "html" | "htm" => "text/html",
+        "csv" => "text/csv",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Spec artifacts get run through `validate-spec` when collected
+pub fn is_spec(artifact: &StoredArtifact) -> bool {
+    artifact.kind.as_deref() == Some("spec")
+        || (artifact.kind.is_none() && artifact.content_type == "text/markdown")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declared(path: &str, kind: Option<&str>) -> WorkflowArtifact {
+        WorkflowArtifact {
+            path: path.to_string(),
+            kind: kind.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_collect_copies_into_workspace_and_records_manifest() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(workspace.path().join("specs/a")).unwrap();
+        std::fs::create_dir_all(workspace.path().join("specs/b")).unwrap();
+        std::fs::write(workspace.path().join("specs/a/spec.md"), "# Spec A").unwrap();
+        std::fs::write(workspace.path().join("specs/b/spec.md"), "# Spec B!").unwrap();
+
+        let store = ArtifactStore::new(root.path().to_path_buf());
+        let dir = store.register("wf-1", Some(workspace.path())).unwrap();
+        assert!(dir.starts_with(workspace.path().join(".smartspec/artifacts")));
+
+        let a = store.collect("wf-1", workspace.path(), &declared("specs/a/spec.md", None)).unwrap();
+        let b = store.collect("wf-1", workspace.path(), &declared("specs/b/spec.md", Some("spec"))).unwrap();
+        assert_eq!((a.name.as_str(), a.size_bytes), ("spec.md", 8));
+        assert_eq!((b.name.as_str(), b.size_bytes), ("spec-1.md", 9));
+        assert_eq!(a.content_type, "text/markdown");
+        assert!(is_spec(&a) && is_spec(&b));
+
+        // Declaring the same file again doesn't copy it twice
+        store.collect("wf-1", workspace.path(), &declared("specs/a/spec.md", None)).unwrap();
+        assert_eq!(store.list("wf-1").unwrap().len(), 2);
+
+        assert!(store.collect("wf-1", workspace.path(), &declared("../outside.md", None)).is_err());
+        assert!(store.list("unknown").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_older_than_removes_expired_artifacts() {
+        let workdir = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(workdir.path().join("report.json"), "{}").unwrap();
+
+        let store = ArtifactStore::new(root.path().to_path_buf());
+        for workflow_id in ["old", "new"] {
+            store.register(workflow_id, None).unwrap();
+            store.collect(workflow_id, workdir.path(), &declared("report.json", Some("report"))).unwrap();
+        }
+        let old_dir = root.path().join("old");
+        let mut manifest = read_manifest(&old_dir).unwrap().unwrap();
+        manifest.created_at = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        write_manifest(&old_dir, &manifest).unwrap();
+
+        assert_eq!(store.delete_older_than(30).unwrap(), 1);
+        assert!(!old_dir.exists());
+        assert_eq!(store.list("new").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_workflow_ids_cannot_escape_artifact_root() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        let store = ArtifactStore::new(root.path().join("artifacts"));
+
+        for workflow_id in ["..", ".", "", "../victim", "a/b", "a\\b", "/tmp/x"] {
+            assert!(store.register(workflow_id, None).is_err(), "{:?}", workflow_id);
+            assert!(store.register(workflow_id, Some(workspace.path())).is_err(), "{:?}", workflow_id);
+            assert!(store.list(workflow_id).is_err(), "{:?}", workflow_id);
+        }
+        assert!(store.register("wf-1", None).is_ok());
+    }
+
+    #[test]
+    fn test_retention_reaches_workspaces_from_earlier_sessions() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("spec.md"), "# Spec").unwrap();
+
+        let store = ArtifactStore::new(root.path().to_path_buf());
+        store.register("old", Some(workspace.path())).unwrap();
+        store.collect("old", workspace.path(), &declared("spec.md", None)).unwrap();
+        let old_dir = workspace.path().join(WORKSPACE_ARTIFACTS_DIR).join("old");
+        let mut manifest = read_manifest(&old_dir).unwrap().unwrap();
+        manifest.created_at = (chrono::Utc::now() - chrono::Duration::days(40)).to_rfc3339();
+        write_manifest(&old_dir, &manifest).unwrap();
+
+        // A fresh store knows nothing about this session's registrations
+        let restarted = ArtifactStore::new(root.path().to_path_buf());
+        assert_eq!(restarted.list("old").unwrap().len(), 1);
+        assert_eq!(restarted.delete_older_than(30).unwrap(), 1);
+        assert!(!old_dir.exists());
+        assert!(workspace.path().join("spec.md").exists());
+    }
+}
@@ -0,0 +1,491 @@
+// Workspace Access - Team membership and invite tokens
+//
+// Provides:
+// - A per-workspace member list with roles, stored in the workspace database
+// - Member management gated by the enterprise RBAC and the signed-in user's workspace role
+// - Expiring invite tokens that stop working once the member is removed
+
+use anyhow::{anyhow, bail, Result};
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::enterprise::{EnterpriseService, PermissionAction};
+use crate::multi_workspace::{WorkspaceMember, WorkspaceRole};
+use crate::workspace_db::{WorkspaceDbManager, WorkspaceMetadata};
+
+/// Invite lifetime when the caller does not choose one
+pub const DEFAULT_INVITE_TTL_HOURS: i64 = 72;
+
+/// Upper bound on invite lifetime
+pub const MAX_INVITE_TTL_HOURS: i64 = 24 * 30;
+
+// ============================================
+// Types
+// ============================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewMember {
+    pub user_id: String,
+    pub name: String,
+    pub email: String,
+    pub role: WorkspaceRole,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceInvite {
+    pub id: String,
+    pub workspace_id: String,
+    pub user_id: String,
+    pub role: WorkspaceRole,
+    /// Only returned here; the database keeps a hash
+    pub token: String,
+    pub created_by: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamWorkspace {
+    pub workspace: WorkspaceMetadata,
+    pub owner: WorkspaceMember,
+}
+
+// ============================================
+// Helpers
+// ============================================
+
+fn with_workspace<T>(
+    db_manager: &WorkspaceDbManager,
+    workspace_id: &str,
+    f: impl FnOnce(&Connection) -> Result<T>,
+) -> Result<T> {
+    let db = db_manager.open_workspace(workspace_id)?;
+    let db = db.lock().map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+    f(&db.conn)
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn parse_role(s: &str) -> Result<WorkspaceRole> {
+    WorkspaceRole::parse(s).map_err(|e| anyhow!(e))
+}
+
+type MemberRow = (String, String, String, String, i64, i64);
+
+const MEMBER_COLUMNS: &str = "user_id, name, email, role, added_at, last_active_at";
+
+fn member_row(row: &rusqlite::Row) -> rusqlite::Result<MemberRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+}
+
+fn into_member((user_id, name, email, role, joined_at, last_active_at): MemberRow) -> Result<WorkspaceMember> {
+    Ok(WorkspaceMember {
+        user_id,
+        name,
+        email,
+        role: parse_role(&role)?,
+        joined_at,
+        last_active_at,
+    })
+}
+
+fn find_member(conn: &Connection, user_id: &str) -> Result<Option<WorkspaceMember>> {
+    conn.query_row(
+        &format!("SELECT {} FROM workspace_members WHERE user_id = ?1", MEMBER_COLUMNS),
+        params![user_id],
+        member_row,
+    )
+    .optional()?
+    .map(into_member)
+    .transpose()
+}
+
+fn insert_member(conn: &Connection, member: &NewMember, added_by: &str) -> Result<WorkspaceMember> {
+    if find_member(conn, &member.user_id)?.is_some() {
+        bail!("User {} is already a member", member.user_id);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    conn.execute(
+        "INSERT INTO workspace_members (user_id, name, email, role, added_by, added_at, last_active_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+        params![member.user_id, member.name, member.email, member.role.as_str(), added_by, now],
+    )?;
+
+    Ok(WorkspaceMember {
+        user_id: member.user_id.clone(),
+        name: member.name.clone(),
+        email: member.email.clone(),
+        role: member.role.clone(),
+        joined_at: now,
+        last_active_at: now,
+    })
+}
+
+/// The signed-in user must pass the enterprise gate for updating workspaces
+/// and be an admin or owner here. Nobody may grant a role above their own.
+fn require_manager(
+    conn: &Connection,
+    enterprise: &EnterpriseService,
+    granting: Option<&WorkspaceRole>,
+) -> Result<WorkspaceMember> {
+    let actor_id = &enterprise
+        .require_session_permission(PermissionAction::Update, "workspace")
+        .map_err(|e| anyhow!(e))?;
+
+    let actor = find_member(conn, actor_id)?
+        .ok_or_else(|| anyhow!("Permission denied: {} is not a member of this workspace", actor_id))?;
+
+    if actor.role.rank() < WorkspaceRole::Admin.rank() {
+        bail!("Permission denied: {} may not manage members", actor_id);
+    }
+    if let Some(role) = granting {
+        if role.rank() > actor.role.rank() {
+            bail!("Permission denied: {} may not grant the {} role", actor_id, role.as_str());
+        }
+    }
+
+    Ok(actor)
+}
+
+/// Look up a member the actor is allowed to change
+fn require_managed_member(conn: &Connection, actor: &WorkspaceMember, user_id: &str) -> Result<WorkspaceMember> {
+    let member = find_member(conn, user_id)?
+        .ok_or_else(|| anyhow!("Member not found: {}", user_id))?;
+
+    if member.role == WorkspaceRole::Owner {
+        bail!("Cannot change the workspace owner");
+    }
+    if member.role.rank() > actor.role.rank() {
+        bail!("Permission denied: {} outranks {}", user_id, actor.user_id);
+    }
+
+    Ok(member)
+}
+
+// ============================================
+// Members
+// ============================================
+
+/// Create a workspace database whose first member, its owner, is the signed-in user
+pub fn create_team_workspace(
+    db_manager: &WorkspaceDbManager,
+    enterprise: &EnterpriseService,
+    name: &str,
+    git_remote: Option<&str>,
+) -> Result<TeamWorkspace> {
+    let owner_id = enterprise
+        .require_session_permission(PermissionAction::Create, "workspace")
+        .map_err(|e| anyhow!(e))?;
+    let user = enterprise.session_user().map_err(|e| anyhow!(e))?;
+
+    let workspace = db_manager.create_workspace(name, git_remote)?;
+    let owner = NewMember {
+        user_id: owner_id.clone(),
+        name: user.full_name.clone().unwrap_or_else(|| user.email.clone()),
+        email: user.email.clone(),
+        role: WorkspaceRole::Owner,
+    };
+    let owner = with_workspace(db_manager, &workspace.id, |conn| insert_member(conn, &owner, &owner_id))?;
+
+    Ok(TeamWorkspace { workspace, owner })
+}
+
+pub fn list_members(db_manager: &WorkspaceDbManager, workspace_id: &str) -> Result<Vec<WorkspaceMember>> {
+    with_workspace(db_manager, workspace_id, |conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM workspace_members ORDER BY added_at, user_id",
+            MEMBER_COLUMNS
+        ))?;
+        let rows = stmt
+            .query_map([], member_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter().map(into_member).collect()
+    })
+}
+
+pub fn add_member(
+    db_manager: &WorkspaceDbManager,
+    enterprise: &EnterpriseService,
+    workspace_id: &str,
+    member: &NewMember,
+) -> Result<WorkspaceMember> {
+    with_workspace(db_manager, workspace_id, |conn| {
+        let actor = require_manager(conn, enterprise, Some(&member.role))?;
+        insert_member(conn, member, &actor.user_id)
+    })
+}
+
+pub fn update_member_role(
+    db_manager: &WorkspaceDbManager,
+    enterprise: &EnterpriseService,
+    workspace_id: &str,
+    user_id: &str,
+    role: WorkspaceRole,
+) -> Result<WorkspaceMember> {
+    with_workspace(db_manager, workspace_id, |conn| {
+        let actor = require_manager(conn, enterprise, Some(&role))?;
+        let mut member = require_managed_member(conn, &actor, user_id)?;
+
+        conn.execute(
+            "UPDATE workspace_members SET role = ?1 WHERE user_id = ?2",
+            params![role.as_str(), user_id],
+        )?;
+        member.role = role;
+        Ok(member)
+    })
+}
+
+/// Remove a member and revoke any invites issued to them.
+/// Returns the number of invites revoked.
+pub fn remove_member(
+    db_manager: &WorkspaceDbManager,
+    enterprise: &EnterpriseService,
+    workspace_id: &str,
+    user_id: &str,
+) -> Result<usize> {
+    with_workspace(db_manager, workspace_id, |conn| {
+        let actor = require_manager(conn, enterprise, None)?;
+        require_managed_member(conn, &actor, user_id)?;
+
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM workspace_members WHERE user_id = ?1", params![user_id])?;
+        let revoked = tx.execute(
+            "UPDATE workspace_invites SET revoked_at = ?1 WHERE user_id = ?2 AND revoked_at IS NULL",
+            params![chrono::Utc::now().timestamp(), user_id],
+        )?;
+        tx.commit()?;
+
+        Ok(revoked)
+    })
+}
+
+// ============================================
+// Invites
+// ============================================
+
+/// Share the workspace with `member`, adding them first if needed, and issue
+/// an invite token that expires after `ttl_hours`
+pub fn share_workspace(
+    db_manager: &WorkspaceDbManager,
+    enterprise: &EnterpriseService,
+    workspace_id: &str,
+    member: &NewMember,
+    ttl_hours: i64,
+) -> Result<WorkspaceInvite> {
+    if !(1..=MAX_INVITE_TTL_HOURS).contains(&ttl_hours) {
+        bail!("Invite expiry must be between 1 and {} hours", MAX_INVITE_TTL_HOURS);
+    }
+
+    with_workspace(db_manager, workspace_id, |conn| {
+        let actor = require_manager(conn, enterprise, Some(&member.role))?;
+
+        let role = match find_member(conn, &member.user_id)? {
+            Some(existing) => {
+                if existing.role.rank() > actor.role.rank() {
+                    bail!("Permission denied: {} outranks {}", existing.user_id, actor.user_id);
+                }
+                existing.role
+            }
+            None => insert_member(conn, member, &actor.user_id)?.role,
+        };
+
+        let token = generate_token();
+        let now = chrono::Utc::now().timestamp();
+        let invite = WorkspaceInvite {
+            id: Uuid::new_v4().to_string(),
+            workspace_id: workspace_id.to_string(),
+            user_id: member.user_id.clone(),
+            role,
+            token,
+            created_by: actor.user_id.clone(),
+            created_at: now,
+            expires_at: now + ttl_hours * 3600,
+        };
+
+        conn.execute(
+            "INSERT INTO workspace_invites (id, token_hash, user_id, role, created_by, created_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                invite.id,
+                hash_token(&invite.token),
+                invite.user_id,
+                invite.role.as_str(),
+                invite.created_by,
+                invite.created_at,
+                invite.expires_at,
+            ],
+        )?;
+
+        Ok(invite)
+    })
+}
+
+/// Resolve an invite token to the member it grants access to
+pub fn authorize_invite(db_manager: &WorkspaceDbManager, workspace_id: &str, token: &str) -> Result<WorkspaceMember> {
+    with_workspace(db_manager, workspace_id, |conn| {
+        authorize_invite_at(conn, token, chrono::Utc::now().timestamp())
+    })
+}
+
+fn authorize_invite_at(conn: &Connection, token: &str, now: i64) -> Result<WorkspaceMember> {
+    let invite = conn
+        .query_row(
+            "SELECT user_id, expires_at, revoked_at FROM workspace_invites WHERE token_hash = ?1",
+            params![hash_token(token)],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, Option<i64>>(2)?)),
+        )
+        .optional()?;
+
+    let (user_id, expires_at, revoked_at) = invite.ok_or_else(|| anyhow!("Invalid invite token"))?;
+    if revoked_at.is_some() {
+        bail!("Invite token has been revoked");
+    }
+    if expires_at <= now {
+        bail!("Invite token has expired");
+    }
+
+    let mut member = find_member(conn, &user_id)?
+        .ok_or_else(|| anyhow!("{} is no longer a member of this workspace", user_id))?;
+
+    conn.execute(
+        "UPDATE workspace_members SET last_active_at = ?1 WHERE user_id = ?2",
+        params![now, user_id],
+    )?;
+    member.last_active_at = now;
+
+    Ok(member)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enterprise::{RoleScope, SessionUser};
+
+    fn new_member(user_id: &str, role: WorkspaceRole) -> NewMember {
+        NewMember {
+            user_id: user_id.to_string(),
+            name: user_id.to_string(),
+            email: format!("{}@example.com", user_id),
+            role,
+        }
+    }
+
+    fn act_as(enterprise: &mut EnterpriseService, user_id: &str) {
+        enterprise.sign_in(SessionUser {
+            id: user_id.to_string(),
+            email: format!("{}@example.com", user_id),
+            full_name: None,
+            is_admin: false,
+        });
+    }
+
+    /// olive is an enterprise admin, adam a manager, ed a developer
+    fn enterprise_with_roles() -> EnterpriseService {
+        let mut enterprise = EnterpriseService::new();
+        enterprise.assign_role("olive", "admin", RoleScope::Global, "system").unwrap();
+        enterprise.assign_role("adam", "manager", RoleScope::Global, "olive").unwrap();
+        enterprise.assign_role("ed", "developer", RoleScope::Global, "olive").unwrap();
+        act_as(&mut enterprise, "olive");
+        enterprise
+    }
+
+    #[test]
+    fn test_add_remove_and_role_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = WorkspaceDbManager::with_base_dir(dir.path()).unwrap();
+
+        // Nobody is allowed anything until roles are assigned
+        let mut fresh = EnterpriseService::new();
+        act_as(&mut fresh, "olive");
+        assert!(create_team_workspace(&manager, &fresh, "test-team-members", None).is_err());
+
+        let mut enterprise = enterprise_with_roles();
+
+        let team = create_team_workspace(&manager, &enterprise, "test-team-members", None).unwrap();
+        let ws = team.workspace.id.clone();
+        assert_eq!((team.owner.user_id.as_str(), &team.owner.role), ("olive", &WorkspaceRole::Owner));
+
+        add_member(&manager, &enterprise, &ws, &new_member("adam", WorkspaceRole::Admin)).unwrap();
+        act_as(&mut enterprise, "adam");
+        add_member(&manager, &enterprise, &ws, &new_member("ed", WorkspaceRole::Editor)).unwrap();
+        assert!(add_member(&manager, &enterprise, &ws, &new_member("ed", WorkspaceRole::Viewer)).is_err());
+
+        // Admins cannot mint owners or touch the owner
+        assert!(add_member(&manager, &enterprise, &ws, &new_member("x", WorkspaceRole::Owner)).is_err());
+        assert!(remove_member(&manager, &enterprise, &ws, "olive").is_err());
+
+        // Editors fail the enterprise gate before their workspace role is looked at
+        act_as(&mut enterprise, "ed");
+        let err = add_member(&manager, &enterprise, &ws, &new_member("y", WorkspaceRole::Viewer)).unwrap_err();
+        assert!(err.to_string().contains("Permission denied"));
+
+        let ids: Vec<String> = list_members(&manager, &ws).unwrap().into_iter().map(|m| m.user_id).collect();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.contains(&"ed".to_string()));
+
+        act_as(&mut enterprise, "adam");
+        let updated = update_member_role(&manager, &enterprise, &ws, "ed", WorkspaceRole::Viewer).unwrap();
+        assert_eq!(updated.role, WorkspaceRole::Viewer);
+
+        remove_member(&manager, &enterprise, &ws, "ed").unwrap();
+        assert_eq!(list_members(&manager, &ws).unwrap().len(), 2);
+        assert!(remove_member(&manager, &enterprise, &ws, "ed").is_err());
+
+        // A workspace admin whose enterprise role is read-only can't manage members
+        enterprise.revoke_role("adam", "manager").unwrap();
+        enterprise.assign_role("adam", "viewer", RoleScope::Global, "olive").unwrap();
+        let err = add_member(&manager, &enterprise, &ws, &new_member("z", WorkspaceRole::Viewer)).unwrap_err();
+        assert!(err.to_string().contains("Permission denied"));
+        act_as(&mut enterprise, "olive");
+        add_member(&manager, &enterprise, &ws, &new_member("z", WorkspaceRole::Viewer)).unwrap();
+    }
+
+    #[test]
+    fn test_removed_member_token_no_longer_authorizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = WorkspaceDbManager::with_base_dir(dir.path()).unwrap();
+        let enterprise = enterprise_with_roles();
+        let team = create_team_workspace(&manager, &enterprise, "test-team-invites", None).unwrap();
+        let ws = team.workspace.id.clone();
+
+        let invite = share_workspace(&manager, &enterprise, &ws, &new_member("vic", WorkspaceRole::Viewer), 24).unwrap();
+        assert_eq!(invite.expires_at - invite.created_at, 24 * 3600);
+        assert_eq!(invite.created_by, "olive");
+        assert!(share_workspace(&manager, &enterprise, &ws, &new_member("vic", WorkspaceRole::Viewer), 0).is_err());
+
+        let member = authorize_invite(&manager, &ws, &invite.token).unwrap();
+        assert_eq!(member.user_id, "vic");
+        assert!(authorize_invite(&manager, &ws, "not-a-token").is_err());
+
+        // Expired tokens are rejected
+        let expired = with_workspace(&manager, &ws, |conn| {
+            authorize_invite_at(conn, &invite.token, invite.expires_at)
+        });
+        assert!(expired.unwrap_err().to_string().contains("expired"));
+
+        let second = share_workspace(&manager, &enterprise, &ws, &new_member("vic", WorkspaceRole::Viewer), 1).unwrap();
+        assert_eq!(remove_member(&manager, &enterprise, &ws, "vic").unwrap(), 2);
+
+        for token in [&invite.token, &second.token] {
+            let err = authorize_invite(&manager, &ws, token).unwrap_err();
+            assert!(err.to_string().contains("revoked"));
+        }
+
+        // Re-adding the user does not bring old tokens back
+        add_member(&manager, &enterprise, &ws, &new_member("vic", WorkspaceRole::Viewer)).unwrap();
+        assert!(authorize_invite(&manager, &ws, &invite.token).is_err());
+    }
+}
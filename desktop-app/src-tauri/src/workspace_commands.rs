@@ -5,12 +5,15 @@
 use std::sync::Arc;
 use tauri::State;
 
-use crate::workspace_db::{WorkspaceDbManager, WorkspaceMetadata, WorkspaceDbStats};
+use crate::models::{Page, PageRequest};
+use crate::workspace_db::{WorkspaceDbManager, WorkspaceMetadata, WorkspaceDbStats, FtsRebuildStats, WorkspaceArchiveManifest, BackupGeneration, BusyRetryConfig};
 use crate::workspace_data::{
-    WorkspaceDataOps, Job, Task, ChatSession, ChatMessage, Knowledge, MemoryLong,
+    WorkspaceDataOps, Job, JobDeletion, JobProgress, Task, TaskGraph, ChatSession, ChatMessage, ChatSearchResult, Knowledge, KnowledgeDuplicateGroup, KnowledgeVersion, TagFacets, MemoryLong,
     CreateJobRequest, CreateTaskRequest, CreateChatSessionRequest, CreateChatMessageRequest,
-    CreateKnowledgeRequest, CreateMemoryLongRequest,
+    CreateKnowledgeRequest, UpdateKnowledgeRequest, CreateMemoryLongRequest,
 };
+use crate::workspace_manager::CreateWorkspaceRequest;
+use crate::workspace_registry::{WorkspaceRegistry, WorkspaceFull, ReconcileReport};
 
 // ============================================
 // State Types
@@ -38,15 +41,26 @@ impl AppState {
 // Workspace Management Commands
 // ============================================
 
+/// Create a workspace with its container workspace; see `create_workspace_full`
 #[tauri::command]
 pub async fn create_workspace(
     state: State<'_, AppState>,
     name: String,
     git_remote: Option<String>,
 ) -> Result<WorkspaceMetadata, String> {
-    state.db_manager
-        .create_workspace(&name, git_remote.as_deref())
-        .map_err(|e| e.to_string())
+    let db_manager = Arc::clone(&state.db_manager);
+    let request = CreateWorkspaceRequest {
+        name,
+        repository: git_remote,
+        image: None,
+        clone_repo: false,
+    };
+    tokio::task::spawn_blocking(move || {
+        WorkspaceRegistry::new(db_manager)?.create_workspace(&request)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map(|full| full.metadata)
 }
 
 #[tauri::command]
@@ -90,13 +104,50 @@ pub async fn update_workspace(
         .map_err(|e| e.to_string())
 }
 
+/// Move a workspace and its container workspace to the trash
 #[tauri::command]
 pub async fn delete_workspace(
     state: State<'_, AppState>,
     workspace_id: String,
 ) -> Result<(), String> {
+    let db_manager = Arc::clone(&state.db_manager);
+    tokio::task::spawn_blocking(move || {
+        WorkspaceRegistry::new(db_manager)?.delete_workspace(&workspace_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn restore_deleted_workspace(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<WorkspaceMetadata, String> {
+    let db_manager = Arc::clone(&state.db_manager);
+    tokio::task::spawn_blocking(move || {
+        WorkspaceRegistry::new(db_manager)?.restore_workspace(&workspace_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map(|full| full.metadata)
+}
+
+#[tauri::command]
+pub async fn list_trashed_workspaces(
+    state: State<'_, AppState>,
+) -> Result<Vec<WorkspaceMetadata>, String> {
     state.db_manager
-        .delete_workspace(&workspace_id)
+        .list_trashed_workspaces()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn purge_trash(
+    state: State<'_, AppState>,
+    older_than_days: Option<u32>,
+) -> Result<usize, String> {
+    state.db_manager
+        .purge_trash(older_than_days.unwrap_or(30))
         .map_err(|e| e.to_string())
 }
 
@@ -136,6 +187,65 @@ pub async fn get_workspace_stats(
         .map_err(|e| e.to_string())
 }
 
+// ============================================
+// Workspace Registry Commands
+// ============================================
+
+/// Create a database workspace together with its container workspace
+#[tauri::command]
+pub async fn create_workspace_full(
+    state: State<'_, AppState>,
+    request: CreateWorkspaceRequest,
+) -> Result<WorkspaceFull, String> {
+    let db_manager = Arc::clone(&state.db_manager);
+    tokio::task::spawn_blocking(move || {
+        WorkspaceRegistry::new(db_manager)?.create_workspace(&request)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Move a database workspace and its linked container workspace to the trash
+#[tauri::command]
+pub async fn delete_workspace_full(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<(), String> {
+    let db_manager = Arc::clone(&state.db_manager);
+    tokio::task::spawn_blocking(move || {
+        WorkspaceRegistry::new(db_manager)?.delete_workspace(&workspace_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_workspace_full(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<WorkspaceFull, String> {
+    let db_manager = Arc::clone(&state.db_manager);
+    tokio::task::spawn_blocking(move || {
+        WorkspaceRegistry::new(db_manager)?.get_workspace_full(&workspace_id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Report drift between the two workspace stores, repairing it when `fix`
+#[tauri::command]
+pub async fn reconcile_workspaces(
+    state: State<'_, AppState>,
+    fix: Option<bool>,
+) -> Result<ReconcileReport, String> {
+    let db_manager = Arc::clone(&state.db_manager);
+    tokio::task::spawn_blocking(move || {
+        WorkspaceRegistry::new(db_manager)?.reconcile(fix.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 // ============================================
 // Workspace Maintenance Commands
 // ============================================
@@ -164,6 +274,64 @@ pub async fn restore_workspace(
         .map_err(|e| e.to_string())
 }
 
+/// Back up what changed since the last backup, falling back to a full
+/// backup when there is no chain to extend
+#[tauri::command]
+pub async fn backup_workspace_incremental(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<BackupGeneration, String> {
+    state.db_manager
+        .backup_incremental(&workspace_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_backup_generations(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<Vec<BackupGeneration>, String> {
+    state.db_manager
+        .list_backup_generations(&workspace_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_backup_generation(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    generation: u32,
+) -> Result<(), String> {
+    state.db_manager
+        .restore_backup_generation(&workspace_id, generation)
+        .map_err(|e| e.to_string())
+}
+
+/// Export a workspace (database, project files, checkpoints) as one archive
+#[tauri::command]
+pub async fn export_workspace_archive(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    out_path: String,
+) -> Result<WorkspaceArchiveManifest, String> {
+    let path = std::path::PathBuf::from(out_path);
+    state.db_manager
+        .export_archive(&workspace_id, &path)
+        .map_err(|e| e.to_string())
+}
+
+/// Import an exported workspace under a new ID
+#[tauri::command]
+pub async fn import_workspace_archive(
+    state: State<'_, AppState>,
+    archive_path: String,
+) -> Result<WorkspaceMetadata, String> {
+    let path = std::path::PathBuf::from(archive_path);
+    state.db_manager
+        .import_archive(&path)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn vacuum_workspace(
     state: State<'_, AppState>,
@@ -194,6 +362,62 @@ pub async fn optimize_workspace(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn workspace_rebuild_search_index(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<FtsRebuildStats, String> {
+    state.db_manager
+        .rebuild_fts(&workspace_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn encrypt_workspace(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<(), String> {
+    state.db_manager
+        .encrypt_workspace(&workspace_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_busy_retry_config(
+    state: State<'_, AppState>,
+) -> Result<BusyRetryConfig, String> {
+    Ok(state.db_manager.busy_retry_config())
+}
+
+/// Save how writes that hit a locked database are retried
+#[tauri::command]
+pub async fn set_busy_retry_config(
+    state: State<'_, AppState>,
+    config: BusyRetryConfig,
+) -> Result<(), String> {
+    state.db_manager
+        .set_busy_retry_config(config)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_max_open_workspaces(
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    Ok(state.db_manager.max_open_workspaces())
+}
+
+/// Save how many workspace databases may stay open at once
+#[tauri::command]
+pub async fn set_max_open_workspaces(
+    state: State<'_, AppState>,
+    capacity: usize,
+) -> Result<(), String> {
+    state.db_manager
+        .set_max_open_workspaces(capacity)
+        .map_err(|e| e.to_string())
+}
+
 // ============================================
 // App Settings Commands
 // ============================================
@@ -255,14 +479,29 @@ pub async fn get_job(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn job_get_progress(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    job_id: String,
+) -> Result<JobProgress, String> {
+    state.data_ops
+        .get_job_progress(&workspace_id, &job_id)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn list_jobs(
     state: State<'_, AppState>,
     workspace_id: String,
     status: Option<String>,
-) -> Result<Vec<Job>, String> {
+    before_id: Option<String>,
+    after_id: Option<String>,
+    limit: Option<i64>,
+) -> Result<Page<Job>, String> {
+    let page = PageRequest { before_id, after_id, limit };
     state.data_ops
-        .list_jobs(&workspace_id, status.as_deref())
+        .list_jobs_page(&workspace_id, status.as_deref(), page)
         .map_err(|e| e.to_string())
 }
 
@@ -283,9 +522,10 @@ pub async fn delete_job(
     state: State<'_, AppState>,
     workspace_id: String,
     job_id: String,
-) -> Result<(), String> {
+    dry_run: Option<bool>,
+) -> Result<JobDeletion, String> {
     state.data_ops
-        .delete_job(&workspace_id, &job_id)
+        .delete_job(&workspace_id, &job_id, dry_run.unwrap_or(false))
         .map_err(|e| e.to_string())
 }
 
@@ -341,6 +581,53 @@ pub async fn update_task_status(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn reorder_tasks(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    job_id: String,
+    task_ids: Vec<String>,
+) -> Result<(), String> {
+    state.data_ops
+        .reorder_tasks(&workspace_id, &job_id, task_ids)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_task_dependency(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    task_id: String,
+    depends_on_task_id: String,
+) -> Result<(), String> {
+    state.data_ops
+        .add_task_dependency(&workspace_id, &task_id, &depends_on_task_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_task_dependency(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    task_id: String,
+    depends_on_task_id: String,
+) -> Result<(), String> {
+    state.data_ops
+        .remove_task_dependency(&workspace_id, &task_id, &depends_on_task_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_task_graph(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    job_id: String,
+) -> Result<TaskGraph, String> {
+    state.data_ops
+        .get_task_graph(&workspace_id, &job_id)
+        .map_err(|e| e.to_string())
+}
+
 // ============================================
 // Chat Session Commands
 // ============================================
@@ -413,10 +700,26 @@ pub async fn get_chat_messages(
     state: State<'_, AppState>,
     workspace_id: String,
     session_id: String,
+    limit: Option<i64>,
+    before_id: Option<String>,
+    after_id: Option<String>,
+) -> Result<Page<ChatMessage>, String> {
+    let page = PageRequest { before_id, after_id, limit };
+    state.data_ops
+        .get_chat_messages_page(&workspace_id, &session_id, page)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn chat_search_messages(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    query: String,
+    advanced: Option<bool>,
     limit: Option<i32>,
-) -> Result<Vec<ChatMessage>, String> {
+) -> Result<Vec<ChatSearchResult>, String> {
     state.data_ops
-        .get_chat_messages(&workspace_id, &session_id, limit)
+        .search_chat_messages(&workspace_id, &query, advanced.unwrap_or(false), limit)
         .map_err(|e| e.to_string())
 }
 
@@ -456,10 +759,11 @@ pub async fn search_knowledge(
     state: State<'_, AppState>,
     workspace_id: String,
     query: String,
+    advanced: Option<bool>,
     limit: Option<i32>,
 ) -> Result<Vec<Knowledge>, String> {
     state.data_ops
-        .search_knowledge(&workspace_id, &query, limit)
+        .search_knowledge(&workspace_id, &query, advanced.unwrap_or(false), limit)
         .map_err(|e| e.to_string())
 }
 
@@ -474,6 +778,86 @@ pub async fn list_knowledge(
         .map_err(|e| e.to_string())
 }
 
+/// Update an entry; its previous state is kept in its history
+#[tauri::command]
+pub async fn update_knowledge(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    id: i64,
+    request: UpdateKnowledgeRequest,
+) -> Result<Knowledge, String> {
+    state.data_ops
+        .update_knowledge(&workspace_id, id, request)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_knowledge_history(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    id: i64,
+) -> Result<Vec<KnowledgeVersion>, String> {
+    state.data_ops
+        .get_knowledge_history(&workspace_id, id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn revert_knowledge(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    id: i64,
+    version: i64,
+) -> Result<Knowledge, String> {
+    state.data_ops
+        .revert_knowledge(&workspace_id, id, version)
+        .map_err(|e| e.to_string())
+}
+
+/// Tags with usage counts for knowledge and long-term memory
+#[tauri::command]
+pub async fn get_tag_facets(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<TagFacets, String> {
+    state.data_ops
+        .get_tag_facets(&workspace_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_knowledge_by_tag(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    tag: String,
+) -> Result<Vec<Knowledge>, String> {
+    state.data_ops
+        .list_knowledge_by_tag(&workspace_id, &tag)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn find_duplicate_knowledge(
+    state: State<'_, AppState>,
+    workspace_id: String,
+) -> Result<Vec<KnowledgeDuplicateGroup>, String> {
+    state.data_ops
+        .find_duplicate_knowledge(&workspace_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Merge entries into the one with the richest content; the rest are deactivated
+#[tauri::command]
+pub async fn merge_knowledge(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    ids: Vec<i64>,
+) -> Result<Knowledge, String> {
+    state.data_ops
+        .merge_knowledge(&workspace_id, &ids)
+        .map_err(|e| e.to_string())
+}
+
 // ============================================
 // Memory Commands
 // ============================================
@@ -524,6 +908,17 @@ pub async fn increment_memory_access(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn list_memory_long_by_tag(
+    state: State<'_, AppState>,
+    workspace_id: String,
+    tag: String,
+) -> Result<Vec<MemoryLong>, String> {
+    state.data_ops
+        .list_memory_long_by_tag(&workspace_id, &tag)
+        .map_err(|e| e.to_string())
+}
+
 // ============================================
 // Command Registration Helper
 // ============================================
@@ -541,18 +936,33 @@ pub fn get_workspace_commands() -> impl Fn(tauri::ipc::Invoke<tauri::Wry>) -> bo
         open_workspace,
         close_workspace,
         get_workspace_stats,
+        // Workspace registry
+        create_workspace_full,
+        delete_workspace_full,
+        get_workspace_full,
+        reconcile_workspaces,
         // Workspace maintenance
         backup_workspace,
         restore_workspace,
+        backup_workspace_incremental,
+        list_backup_generations,
+        restore_backup_generation,
+        export_workspace_archive,
+        import_workspace_archive,
         vacuum_workspace,
         cleanup_expired_memory,
         optimize_workspace,
+        get_busy_retry_config,
+        set_busy_retry_config,
+        get_max_open_workspaces,
+        set_max_open_workspaces,
         // App settings
         get_app_setting,
         set_app_setting,
         // Jobs
         create_job,
         get_job,
+        job_get_progress,
         list_jobs,
         update_job_status,
         delete_job,
@@ -560,18 +970,31 @@ pub fn get_workspace_commands() -> impl Fn(tauri::ipc::Invoke<tauri::Wry>) -> bo
         create_task,
         list_tasks,
         update_task_status,
+        reorder_tasks,
+        add_task_dependency,
+        remove_task_dependency,
+        get_task_graph,
         // Chat sessions
         create_chat_session,
         list_chat_sessions,
         add_chat_message,
         get_chat_messages,
+        chat_search_messages,
         // Knowledge
         create_knowledge,
         search_knowledge,
         list_knowledge,
+        update_knowledge,
+        get_knowledge_history,
+        revert_knowledge,
+        get_tag_facets,
+        list_knowledge_by_tag,
+        find_duplicate_knowledge,
+        merge_knowledge,
         // Memory
         create_memory_long,
         get_relevant_memories,
         increment_memory_access,
+        list_memory_long_by_tag,
     ]
 }
@@ -5,13 +5,18 @@
 // - Chat session management
 // - Knowledge base operations
 // - Memory system operations
+// - Quality report history
+// - Search across all workspaces
 
 use anyhow::{Context, Result, anyhow};
 use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
-use crate::workspace_db::{WorkspaceDbManager, WorkspaceDb};
+use crate::models::{Page, PageCursor, PageRequest};
+use crate::workspace_db::{sanitize_fts_query, WorkspaceDbManager, WorkspaceDb, WorkspaceMetadata};
 
 // ============================================
 // Data Types
@@ -49,6 +54,57 @@ pub struct Task {
     pub completed_at: Option<String>,
 }
 
+/// `task_id` cannot start until `depends_on_task_id` is done
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskDependency {
+    pub task_id: String,
+    pub depends_on_task_id: String,
+}
+
+/// Rows affected (or, for a dry run, that would be affected) by `delete_job`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct JobDeletion {
+    pub job_id: String,
+    pub dry_run: bool,
+    pub tasks_deleted: usize,
+    pub task_dependencies_deleted: usize,
+    pub working_memory_deleted: usize,
+    /// Sessions unlinked from the job and marked inactive; messages are kept
+    pub chat_sessions_archived: usize,
+    /// Child jobs moved up to the deleted job's parent
+    pub child_jobs_reparented: usize,
+    /// File operations, checkpoints and skill runs unlinked from the job
+    pub history_records_detached: usize,
+}
+
+/// Progress rollup for one job. Cancelled tasks are left out entirely.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobProgress {
+    pub job_id: String,
+    pub total_tasks: i32,
+    pub completed_tasks: i32,
+    /// 0-100, weighted by `estimated_minutes` when any task has an estimate
+    pub percent_complete: f64,
+    pub estimate_weighted: bool,
+    pub estimated_minutes: i32,
+    pub actual_minutes: i32,
+    /// Actual / estimated minutes over completed tasks that have both
+    pub estimate_accuracy: Option<f64>,
+    pub eta_minutes: Option<i32>,
+    pub eta_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGraph {
+    /// Tasks in `order_index` order
+    pub tasks: Vec<Task>,
+    pub edges: Vec<TaskDependency>,
+    /// Task ids in an order that satisfies every edge; empty when cyclic
+    pub execution_order: Vec<String>,
+    /// Task ids forming a cycle, first id repeated at the end
+    pub cycle: Option<Vec<String>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatSession {
     pub id: String,
@@ -78,6 +134,17 @@ pub struct ChatMessage {
     pub created_at: String,
 }
 
+/// A chat message matching a search, with the session it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSearchResult {
+    pub message: ChatMessage,
+    pub session_title: Option<String>,
+    pub session_type: String,
+    pub job_id: Option<String>,
+    /// Excerpt around the match with hits wrapped in `**`
+    pub snippet: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Knowledge {
     pub id: i64,
@@ -93,6 +160,47 @@ pub struct Knowledge {
     pub updated_at: String,
 }
 
+/// A knowledge entry as it was before an update replaced it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeVersion {
+    pub id: i64,
+    pub knowledge_id: i64,
+    /// Numbered per entry from 1
+    pub version: i64,
+    pub knowledge_type: String,
+    pub title: String,
+    pub content: String,
+    pub tags_json: Option<String>,
+    pub file_refs_json: Option<String>,
+    /// When the entry took on this state
+    pub updated_at: Option<String>,
+    /// When the state was replaced
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagFacet {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// Tags in use with how many entries carry each, most used first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagFacets {
+    /// Active knowledge entries only
+    pub knowledge: Vec<TagFacet>,
+    pub memory: Vec<TagFacet>,
+}
+
+/// Active knowledge entries whose title and content match once case and
+/// whitespace are normalized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeDuplicateGroup {
+    pub content_hash: String,
+    /// Oldest first
+    pub entries: Vec<Knowledge>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryShort {
     pub id: i64,
@@ -121,6 +229,98 @@ pub struct MemoryLong {
     pub updated_at: String,
 }
 
+/// Key metrics of one quality report
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QualitySnapshot {
+    pub id: String,
+    pub project_id: String,
+    /// HEAD when the report was taken, if the workspace is a git repository
+    pub commit_sha: Option<String>,
+    pub overall_score: f64,
+    pub complexity: f64,
+    pub maintainability: f64,
+    pub documentation_coverage: f64,
+    pub code_coverage: Option<f64>,
+    pub test_count: u32,
+    pub issue_count: u32,
+    pub lines_of_code: u32,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub previous: f64,
+    pub current: f64,
+    pub change: f64,
+    /// Worse than the previous report by more than the regression threshold
+    pub regression: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityTrendPoint {
+    pub snapshot: QualitySnapshot,
+    /// Changes since the previous report; empty for the first one recorded
+    pub deltas: Vec<MetricDelta>,
+    pub regressed: bool,
+}
+
+/// Where a global search hit came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSource {
+    Knowledge,
+    Memory,
+    Chat,
+}
+
+/// Sources included in a global search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlobalSearchSources {
+    pub knowledge: bool,
+    pub memory: bool,
+    pub chat: bool,
+}
+
+impl Default for GlobalSearchSources {
+    fn default() -> Self {
+        Self { knowledge: true, memory: true, chat: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalSearchHit {
+    pub workspace_id: String,
+    pub workspace_name: String,
+    pub source: SearchSource,
+    /// Row id in the source table
+    pub item_id: i64,
+    /// Knowledge or memory title, or the chat session title
+    pub title: String,
+    /// Set for chat hits
+    pub session_id: Option<String>,
+    /// Excerpt around the match with hits wrapped in `**`
+    pub snippet: String,
+    /// bm25 relevance mapped into (0, 1); higher is better
+    pub score: f64,
+}
+
+/// A workspace left out of a global search because it could not be read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedWorkspace {
+    pub workspace_id: String,
+    pub workspace_name: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GlobalSearchResults {
+    /// Best first, across all workspaces and sources
+    pub hits: Vec<GlobalSearchHit>,
+    pub skipped: Vec<SkippedWorkspace>,
+}
+
 // ============================================
 // Create Request Types
 // ============================================
@@ -175,6 +375,16 @@ pub struct CreateKnowledgeRequest {
     pub created_by: Option<String>,
 }
 
+/// Fields left `None` keep their current value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateKnowledgeRequest {
+    pub knowledge_type: Option<String>,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub file_refs: Option<Vec<String>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateMemoryLongRequest {
     pub category: String,
@@ -209,7 +419,7 @@ impl WorkspaceDataOps {
         let job_id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "INSERT INTO jobs (id, name, description, branch_name, status, parent_job_id, created_at, updated_at)
              VALUES (?, ?, ?, ?, 'active', ?, ?, ?)",
             params![
@@ -221,7 +431,7 @@ impl WorkspaceDataOps {
                 now,
                 now,
             ],
-        ).context("Failed to create job")?;
+        )).context("Failed to create job")?;
         
         Ok(Job {
             id: job_id,
@@ -266,57 +476,70 @@ impl WorkspaceDataOps {
     }
     
     pub fn list_jobs(&self, workspace_id: &str, status: Option<&str>) -> Result<Vec<Job>> {
+        Ok(self.list_jobs_page(workspace_id, status, PageRequest::default())?.items)
+    }
+    
+    /// Jobs most recently updated first, keyed on (updated_at, id)
+    pub fn list_jobs_page(&self, workspace_id: &str, status: Option<&str>, page: PageRequest) -> Result<Page<Job>> {
+        let cursor = page.cursor().map_err(|e| anyhow!(e))?;
+        
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
         
-        let query = match status {
-            Some(_) => "SELECT id, name, description, branch_name, status, parent_job_id, metadata_json, created_at, updated_at, completed_at
-                        FROM jobs WHERE status = ? ORDER BY updated_at DESC",
-            None => "SELECT id, name, description, branch_name, status, parent_job_id, metadata_json, created_at, updated_at, completed_at
-                     FROM jobs ORDER BY updated_at DESC",
-        };
+        let mut query = "SELECT id, name, description, branch_name, status, parent_job_id, metadata_json, created_at, updated_at, completed_at
+                         FROM jobs WHERE 1=1".to_string();
+        let mut values: Vec<String> = Vec::new();
         
-        let mut stmt = db.conn.prepare(query).context("Failed to prepare query")?;
+        if let Some(s) = status {
+            query.push_str(" AND status = ?");
+            values.push(s.to_string());
+        }
         
-        let jobs = if let Some(s) = status {
-            stmt.query_map(params![s], |row| {
-                Ok(Job {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    description: row.get(2)?,
-                    branch_name: row.get(3)?,
-                    status: row.get(4)?,
-                    parent_job_id: row.get(5)?,
-                    metadata_json: row.get(6)?,
-                    created_at: row.get(7)?,
-                    updated_at: row.get(8)?,
-                    completed_at: row.get(9)?,
-                })
-            }).context("Failed to query jobs")?
-        } else {
-            stmt.query_map([], |row| {
-                Ok(Job {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    description: row.get(2)?,
-                    branch_name: row.get(3)?,
-                    status: row.get(4)?,
-                    parent_job_id: row.get(5)?,
-                    metadata_json: row.get(6)?,
-                    created_at: row.get(7)?,
-                    updated_at: row.get(8)?,
-                    completed_at: row.get(9)?,
-                })
-            }).context("Failed to query jobs")?
-        };
+        if let PageCursor::Before(id) | PageCursor::After(id) = cursor {
+            let updated_at: String = db.conn.query_row(
+                "SELECT updated_at FROM jobs WHERE id = ?",
+                params![id],
+                |row| row.get(0),
+            ).map_err(|_| anyhow!("Job cursor not found: {}", id))?;
+            
+            let op = if matches!(cursor, PageCursor::Before(_)) { "<" } else { ">" };
+            query.push_str(&format!(" AND (updated_at {0} ? OR (updated_at = ? AND id {0} ?))", op));
+            values.extend([updated_at.clone(), updated_at, id.to_string()]);
+        }
+        
+        let newer = matches!(cursor, PageCursor::After(_));
+        query.push_str(if newer { " ORDER BY updated_at ASC, id ASC" } else { " ORDER BY updated_at DESC, id DESC" });
+        query.push_str(&format!(" LIMIT {}", page.fetch_limit()));
+        
+        let mut stmt = db.conn.prepare(&query).context("Failed to prepare query")?;
+        
+        let jobs = stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+            Ok(Job {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                branch_name: row.get(3)?,
+                status: row.get(4)?,
+                parent_job_id: row.get(5)?,
+                metadata_json: row.get(6)?,
+                created_at: row.get(7)?,
+                updated_at: row.get(8)?,
+                completed_at: row.get(9)?,
+            })
+        }).context("Failed to query jobs")?;
         
         let mut result = Vec::new();
         for job in jobs {
             result.push(job.context("Failed to read job")?);
         }
         
-        Ok(result)
+        let mut page = Page::from_rows(result, page.limit, |j| j.id.clone());
+        if newer {
+            page.items.reverse();
+        }
+        
+        Ok(page)
     }
     
     pub fn update_job_status(&self, workspace_id: &str, job_id: &str, status: &str) -> Result<()> {
@@ -327,23 +550,66 @@ impl WorkspaceDataOps {
         let now = chrono::Utc::now().to_rfc3339();
         let completed_at = if status == "completed" { Some(now.clone()) } else { None };
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "UPDATE jobs SET status = ?, updated_at = ?, completed_at = COALESCE(?, completed_at) WHERE id = ?",
             params![status, now, completed_at, job_id],
-        ).context("Failed to update job status")?;
+        )).context("Failed to update job status")?;
         
         Ok(())
     }
     
-    pub fn delete_job(&self, workspace_id: &str, job_id: &str) -> Result<()> {
+    /// Delete a job and everything hanging off it in one transaction.
+    /// With `dry_run` nothing is changed and the counts are what would be.
+    pub fn delete_job(&self, workspace_id: &str, job_id: &str, dry_run: bool) -> Result<JobDeletion> {
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
         
-        db.conn.execute("DELETE FROM jobs WHERE id = ?", params![job_id])
-            .context("Failed to delete job")?;
+        let exists: i64 = db.conn.query_row(
+            "SELECT COUNT(*) FROM jobs WHERE id = ?",
+            params![job_id],
+            |row| row.get(0),
+        ).context("Failed to look up job")?;
+        if exists == 0 {
+            return Err(anyhow!("Job not found: {}", job_id));
+        }
         
-        Ok(())
+        let job_tasks = "SELECT id FROM tasks WHERE job_id = ?1";
+        let deletion = self.db_manager.with_write_retry(|| {
+            let tx = db.conn.unchecked_transaction()?;
+            let step = |table: &str, filter: &str, set: Option<&str>| {
+                job_delete_step(&tx, dry_run, table, filter, set, job_id)
+            };
+            
+            let deletion = JobDeletion {
+                job_id: job_id.to_string(),
+                dry_run,
+                task_dependencies_deleted: step(
+                    "task_dependencies",
+                    &format!("task_id IN ({0}) OR depends_on_task_id IN ({0})", job_tasks),
+                    None,
+                )?,
+                tasks_deleted: step("tasks", "job_id = ?1", None)?,
+                working_memory_deleted: step("memory_working", "job_id = ?1", None)?,
+                chat_sessions_archived: step("chat_sessions", "job_id = ?1", Some("job_id = NULL, is_active = 0"))?,
+                child_jobs_reparented: step(
+                    "jobs",
+                    "parent_job_id = ?1",
+                    Some("parent_job_id = (SELECT parent_job_id FROM jobs WHERE id = ?1)"),
+                )?,
+                history_records_detached: ["file_operations", "checkpoints", "skill_executions"].into_iter()
+                    .map(|table| step(table, "job_id = ?1", Some("job_id = NULL")))
+                    .sum::<rusqlite::Result<usize>>()?,
+            };
+            step("jobs", "id = ?1", None)?;
+            
+            if !dry_run {
+                tx.commit()?;
+            }
+            Ok(deletion)
+        }).context("Failed to delete job")?;
+        
+        Ok(deletion)
     }
     
     // ========================================
@@ -365,7 +631,7 @@ impl WorkspaceDataOps {
             |row| row.get(0),
         ).unwrap_or(1);
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "INSERT INTO tasks (id, job_id, title, description, status, priority, order_index, estimated_minutes, assignee, created_at, updated_at)
              VALUES (?, ?, ?, ?, 'pending', ?, ?, ?, ?, ?, ?)",
             params![
@@ -380,7 +646,7 @@ impl WorkspaceDataOps {
                 now,
                 now,
             ],
-        ).context("Failed to create task")?;
+        )).context("Failed to create task")?;
         
         Ok(Task {
             id: task_id,
@@ -445,14 +711,152 @@ impl WorkspaceDataOps {
         let now = chrono::Utc::now().to_rfc3339();
         let completed_at = if status == "completed" { Some(now.clone()) } else { None };
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "UPDATE tasks SET status = ?, updated_at = ?, completed_at = COALESCE(?, completed_at) WHERE id = ?",
             params![status, now, completed_at, task_id],
-        ).context("Failed to update task status")?;
+        )).context("Failed to update task status")?;
+        
+        Ok(())
+    }
+    
+    /// Set `order_index` to each task's position in `task_ids`. Every id must
+    /// belong to `job_id`; nothing changes otherwise.
+    pub fn reorder_tasks(&self, workspace_id: &str, job_id: &str, task_ids: Vec<String>) -> Result<()> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let job_tasks = Self::task_job_ids(&db.conn, &task_ids)?;
+        if let Some(id) = task_ids.iter().find(|id| job_tasks.get(*id).map(String::as_str) != Some(job_id)) {
+            return Err(anyhow!("Task {} does not belong to job {}", id, job_id));
+        }
+        
+        let now = chrono::Utc::now().to_rfc3339();
+        
+        for (index, id) in task_ids.iter().enumerate() {
+            self.db_manager.with_write_retry(|| db.conn.execute(
+                "UPDATE tasks SET order_index = ?, updated_at = ? WHERE id = ? AND job_id = ?",
+                params![index as i32 + 1, now, id, job_id],
+            )).context("Failed to reorder tasks")?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Record that `task_id` depends on `depends_on_task_id`. Both tasks must
+    /// be in the same job and the edge must not create a cycle.
+    pub fn add_task_dependency(&self, workspace_id: &str, task_id: &str, depends_on_task_id: &str) -> Result<()> {
+        if task_id == depends_on_task_id {
+            return Err(anyhow!("A task cannot depend on itself"));
+        }
+        
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let ids = vec![task_id.to_string(), depends_on_task_id.to_string()];
+        let job_tasks = Self::task_job_ids(&db.conn, &ids)?;
+        let job_id = job_tasks.get(task_id).ok_or_else(|| anyhow!("Task not found: {}", task_id))?;
+        let other_job = job_tasks.get(depends_on_task_id)
+            .ok_or_else(|| anyhow!("Task not found: {}", depends_on_task_id))?;
+        if job_id != other_job {
+            return Err(anyhow!("Dependencies must be between tasks of the same job"));
+        }
+        
+        let mut edges = Self::job_dependencies(&db.conn, job_id)?;
+        edges.push(TaskDependency {
+            task_id: task_id.to_string(),
+            depends_on_task_id: depends_on_task_id.to_string(),
+        });
+        let nodes: Vec<String> = edges.iter().map(|e| e.task_id.clone()).collect();
+        if let Some(cycle) = find_cycle(&nodes, &edges) {
+            return Err(anyhow!("Dependency would create a cycle: {}", cycle.join(" -> ")));
+        }
+        
+        self.db_manager.with_write_retry(|| db.conn.execute(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_task_id, created_at) VALUES (?, ?, ?)",
+            params![task_id, depends_on_task_id, chrono::Utc::now().to_rfc3339()],
+        )).context("Failed to add task dependency")?;
+        
+        Ok(())
+    }
+    
+    pub fn remove_task_dependency(&self, workspace_id: &str, task_id: &str, depends_on_task_id: &str) -> Result<()> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        self.db_manager.with_write_retry(|| db.conn.execute(
+            "DELETE FROM task_dependencies WHERE task_id = ? AND depends_on_task_id = ?",
+            params![task_id, depends_on_task_id],
+        )).context("Failed to remove task dependency")?;
         
         Ok(())
     }
     
+    pub fn get_job_progress(&self, workspace_id: &str, job_id: &str) -> Result<JobProgress> {
+        let tasks = self.list_tasks(workspace_id, job_id)?;
+        Ok(compute_job_progress(job_id, &tasks, chrono::Utc::now()))
+    }
+    
+    /// A job's tasks with their dependency edges and an execution order
+    pub fn get_task_graph(&self, workspace_id: &str, job_id: &str) -> Result<TaskGraph> {
+        let tasks = self.list_tasks(workspace_id, job_id)?;
+        
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        let edges = Self::job_dependencies(&db.conn, job_id)?;
+        
+        let nodes: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+        let cycle = find_cycle(&nodes, &edges);
+        let execution_order = if cycle.is_some() { Vec::new() } else { topological_order(&nodes, &edges) };
+        
+        Ok(TaskGraph { tasks, edges, execution_order, cycle })
+    }
+    
+    /// Map of task id -> job id for the given tasks that exist
+    fn task_job_ids(conn: &Connection, task_ids: &[String]) -> Result<HashMap<String, String>> {
+        let mut stmt = conn.prepare("SELECT job_id FROM tasks WHERE id = ?")
+            .context("Failed to prepare query")?;
+        let mut result = HashMap::new();
+        for id in task_ids {
+            let job_id: Option<String> = stmt.query_row(params![id], |row| row.get(0))
+                .map(Some)
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    e => Err(e),
+                })
+                .context("Failed to look up task")?;
+            if let Some(job_id) = job_id {
+                result.insert(id.clone(), job_id);
+            }
+        }
+        Ok(result)
+    }
+    
+    fn job_dependencies(conn: &Connection, job_id: &str) -> Result<Vec<TaskDependency>> {
+        let mut stmt = conn.prepare(
+            "SELECT d.task_id, d.depends_on_task_id
+             FROM task_dependencies d JOIN tasks t ON t.id = d.task_id
+             WHERE t.job_id = ? ORDER BY d.created_at"
+        ).context("Failed to prepare query")?;
+        
+        let edges = stmt.query_map(params![job_id], |row| {
+            Ok(TaskDependency {
+                task_id: row.get(0)?,
+                depends_on_task_id: row.get(1)?,
+            })
+        }).context("Failed to query task dependencies")?;
+        
+        let mut result = Vec::new();
+        for edge in edges {
+            result.push(edge.context("Failed to read task dependency")?);
+        }
+        
+        Ok(result)
+    }
+    
     // ========================================
     // Chat Session Operations
     // ========================================
@@ -466,7 +870,7 @@ impl WorkspaceDataOps {
         let now = chrono::Utc::now().to_rfc3339();
         let session_type = request.session_type.unwrap_or_else(|| "general".to_string());
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "INSERT INTO chat_sessions (id, job_id, title, type, model_id, is_active, message_count, token_count, created_at, updated_at)
              VALUES (?, ?, ?, ?, ?, 1, 0, 0, ?, ?)",
             params![
@@ -478,7 +882,7 @@ impl WorkspaceDataOps {
                 now,
                 now,
             ],
-        ).context("Failed to create chat session")?;
+        )).context("Failed to create chat session")?;
         
         Ok(ChatSession {
             id: session_id,
@@ -501,7 +905,7 @@ impl WorkspaceDataOps {
         
         let now = chrono::Utc::now().to_rfc3339();
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "INSERT INTO chat_messages (session_id, role, content, tool_calls_json, tool_results_json, model_id, tokens_input, tokens_output, latency_ms, created_at)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
@@ -516,16 +920,16 @@ impl WorkspaceDataOps {
                 request.latency_ms,
                 now,
             ],
-        ).context("Failed to add chat message")?;
+        )).context("Failed to add chat message")?;
         
         let message_id = db.conn.last_insert_rowid();
         
         // Update session stats
         let tokens = request.tokens_input.unwrap_or(0) + request.tokens_output.unwrap_or(0);
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "UPDATE chat_sessions SET message_count = message_count + 1, token_count = token_count + ?, updated_at = ? WHERE id = ?",
             params![tokens, now, request.session_id],
-        ).context("Failed to update session stats")?;
+        )).context("Failed to update session stats")?;
         
         Ok(ChatMessage {
             id: message_id,
@@ -543,38 +947,99 @@ impl WorkspaceDataOps {
     }
     
     pub fn get_chat_messages(&self, workspace_id: &str, session_id: &str, limit: Option<i32>) -> Result<Vec<ChatMessage>> {
+        let page = PageRequest { limit: limit.map(i64::from), ..PageRequest::default() };
+        Ok(self.get_chat_messages_page(workspace_id, session_id, page)?.items)
+    }
+    
+    /// Messages in chronological order. Without a cursor this is the latest
+    /// page; `before_id` continues into older history.
+    pub fn get_chat_messages_page(&self, workspace_id: &str, session_id: &str, page: PageRequest) -> Result<Page<ChatMessage>> {
+        let cursor = page.cursor().map_err(|e| anyhow!(e))?;
+        let cursor_id = match cursor {
+            PageCursor::Before(id) | PageCursor::After(id) => {
+                Some(id.parse::<i64>().map_err(|_| anyhow!("Invalid message cursor: {}", id))?)
+            }
+            PageCursor::Latest => None,
+        };
+        
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
         
-        let query = match limit {
-            Some(_) => "SELECT id, session_id, role, content, tool_calls_json, tool_results_json, model_id, tokens_input, tokens_output, latency_ms, created_at
-                        FROM chat_messages WHERE session_id = ? ORDER BY created_at DESC LIMIT ?",
-            None => "SELECT id, session_id, role, content, tool_calls_json, tool_results_json, model_id, tokens_input, tokens_output, latency_ms, created_at
-                     FROM chat_messages WHERE session_id = ? ORDER BY created_at",
+        // Ids only grow, so they give a stable order even as messages arrive
+        let newer = matches!(cursor, PageCursor::After(_));
+        let mut query = "SELECT id, session_id, role, content, tool_calls_json, tool_results_json, model_id, tokens_input, tokens_output, latency_ms, created_at
+                         FROM chat_messages WHERE session_id = ?".to_string();
+        let mut values: Vec<&dyn rusqlite::ToSql> = vec![&session_id];
+        
+        if let Some(id) = &cursor_id {
+            query.push_str(if newer { " AND id > ?" } else { " AND id < ?" });
+            values.push(id);
+        }
+        
+        query.push_str(if newer { " ORDER BY id ASC" } else { " ORDER BY id DESC" });
+        query.push_str(&format!(" LIMIT {}", page.fetch_limit()));
+        
+        let mut stmt = db.conn.prepare(&query).context("Failed to prepare query")?;
+        
+        let messages = stmt.query_map(values.as_slice(), |row| {
+            Ok(ChatMessage {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                role: row.get(2)?,
+                content: row.get(3)?,
+                tool_calls_json: row.get(4)?,
+                tool_results_json: row.get(5)?,
+                model_id: row.get(6)?,
+                tokens_input: row.get(7)?,
+                tokens_output: row.get(8)?,
+                latency_ms: row.get(9)?,
+                created_at: row.get(10)?,
+            })
+        }).context("Failed to query messages")?;
+        
+        let mut result = Vec::new();
+        for message in messages {
+            result.push(message.context("Failed to read message")?);
+        }
+        
+        let mut page = Page::from_rows(result, page.limit, |m| m.id.to_string());
+        if !newer {
+            page.items.reverse();
+        }
+        
+        Ok(page)
+    }
+    
+    /// Search message content across every session in the workspace, best
+    /// matches first. See `sanitize_fts_query` for how `advanced` changes the
+    /// reading of `query`.
+    pub fn search_chat_messages(&self, workspace_id: &str, query: &str, advanced: bool, limit: Option<i32>) -> Result<Vec<ChatSearchResult>> {
+        let Some(fts_query) = sanitize_fts_query(query, advanced) else {
+            return Ok(Vec::new());
         };
         
-        let mut stmt = db.conn.prepare(query).context("Failed to prepare query")?;
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
         
-        let messages = if let Some(l) = limit {
-            stmt.query_map(params![session_id, l], |row| {
-                Ok(ChatMessage {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    role: row.get(2)?,
-                    content: row.get(3)?,
-                    tool_calls_json: row.get(4)?,
-                    tool_results_json: row.get(5)?,
-                    model_id: row.get(6)?,
-                    tokens_input: row.get(7)?,
-                    tokens_output: row.get(8)?,
-                    latency_ms: row.get(9)?,
-                    created_at: row.get(10)?,
-                })
-            }).context("Failed to query messages")?
-        } else {
-            stmt.query_map(params![session_id], |row| {
-                Ok(ChatMessage {
+        let limit = limit.unwrap_or(20);
+        
+        let mut stmt = db.conn.prepare(
+            "SELECT m.id, m.session_id, m.role, m.content, m.tool_calls_json, m.tool_results_json, m.model_id, m.tokens_input, m.tokens_output, m.latency_ms, m.created_at,
+                    s.title, s.type, s.job_id,
+                    snippet(chat_messages_fts, 0, '**', '**', '…', 16)
+             FROM chat_messages_fts
+             JOIN chat_messages m ON m.id = chat_messages_fts.rowid
+             JOIN chat_sessions s ON s.id = m.session_id
+             WHERE chat_messages_fts MATCH ?
+             ORDER BY rank
+             LIMIT ?"
+        ).context("Failed to prepare search query")?;
+        
+        let results = stmt.query_map(params![fts_query, limit], |row| {
+            Ok(ChatSearchResult {
+                message: ChatMessage {
                     id: row.get(0)?,
                     session_id: row.get(1)?,
                     role: row.get(2)?,
@@ -586,18 +1051,17 @@ impl WorkspaceDataOps {
                     tokens_output: row.get(8)?,
                     latency_ms: row.get(9)?,
                     created_at: row.get(10)?,
-                })
-            }).context("Failed to query messages")?
-        };
+                },
+                session_title: row.get(11)?,
+                session_type: row.get(12)?,
+                job_id: row.get(13)?,
+                snippet: row.get(14)?,
+            })
+        }).context("Failed to search chat messages")?;
         
         let mut result = Vec::new();
-        for message in messages {
-            result.push(message.context("Failed to read message")?);
-        }
-        
-        // Reverse if limited (we queried DESC)
-        if limit.is_some() {
-            result.reverse();
+        for found in results {
+            result.push(found.context("Failed to read chat message")?);
         }
         
         Ok(result)
@@ -631,7 +1095,7 @@ impl WorkspaceDataOps {
                     created_at: row.get(8)?,
                     updated_at: row.get(9)?,
                 })
-            }).context("Failed to query sessions")?
+            }).context("Failed to query sessions")?.collect::<Vec<_>>()
         } else {
             stmt.query_map([], |row| {
                 Ok(ChatSession {
@@ -646,7 +1110,7 @@ impl WorkspaceDataOps {
                     created_at: row.get(8)?,
                     updated_at: row.get(9)?,
                 })
-            }).context("Failed to query sessions")?
+            }).context("Failed to query sessions")?.collect::<Vec<_>>()
         };
         
         let mut result = Vec::new();
@@ -661,16 +1125,46 @@ impl WorkspaceDataOps {
     // Knowledge Operations
     // ========================================
     
+    /// Create a knowledge entry. With the workspace's `knowledge.auto_dedup`
+    /// setting on, a duplicate of an active entry is merged into it instead.
     pub fn create_knowledge(&self, workspace_id: &str, request: CreateKnowledgeRequest) -> Result<Knowledge> {
+        let auto_dedup = self.db_manager.get_workspace_setting(workspace_id, KNOWLEDGE_AUTO_DEDUP_SETTING)?
+            .is_some_and(|value| value == "true");
+        
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
         
+        if auto_dedup {
+            let hash = knowledge_content_hash(&request.title, &request.content);
+            let existing = load_active_knowledge(&db.conn)?
+                .into_iter()
+                .find(|k| knowledge_content_hash(&k.title, &k.content) == hash);
+            if let Some(existing) = existing {
+                let tags = request.tags.map(|t| serde_json::to_string(&t).unwrap_or_default());
+                let file_refs = request.file_refs.map(|f| serde_json::to_string(&f).unwrap_or_default());
+                let mut merged = existing.clone();
+                merged.tags_json = union_json_lists([existing.tags_json.as_deref(), tags.as_deref()]);
+                merged.file_refs_json = union_json_lists([existing.file_refs_json.as_deref(), file_refs.as_deref()]);
+                if merged.tags_json == existing.tags_json && merged.file_refs_json == existing.file_refs_json {
+                    return Ok(existing);
+                }
+                merged.updated_at = chrono::Utc::now().to_rfc3339();
+                
+                self.db_manager.with_write_retry(|| {
+                    let tx = db.conn.unchecked_transaction()?;
+                    replace_knowledge(&tx, &existing, &merged)?;
+                    tx.commit()
+                }).context("Failed to merge duplicate knowledge")?;
+                return Ok(merged);
+            }
+        }
+        
         let now = chrono::Utc::now().to_rfc3339();
         let tags_json = request.tags.map(|t| serde_json::to_string(&t).unwrap_or_default());
         let file_refs_json = request.file_refs.map(|f| serde_json::to_string(&f).unwrap_or_default());
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "INSERT INTO knowledge (type, title, content, tags_json, file_refs_json, is_active, source, created_by, created_at, updated_at)
              VALUES (?, ?, ?, ?, ?, 1, ?, ?, ?, ?)",
             params![
@@ -684,7 +1178,7 @@ impl WorkspaceDataOps {
                 now,
                 now,
             ],
-        ).context("Failed to create knowledge")?;
+        )).context("Failed to create knowledge")?;
         
         let knowledge_id = db.conn.last_insert_rowid();
         
@@ -703,7 +1197,11 @@ impl WorkspaceDataOps {
         })
     }
     
-    pub fn search_knowledge(&self, workspace_id: &str, query: &str, limit: Option<i32>) -> Result<Vec<Knowledge>> {
+    pub fn search_knowledge(&self, workspace_id: &str, query: &str, advanced: bool, limit: Option<i32>) -> Result<Vec<Knowledge>> {
+        let Some(fts_query) = sanitize_fts_query(query, advanced) else {
+            return Ok(Vec::new());
+        };
+        
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
@@ -719,7 +1217,7 @@ impl WorkspaceDataOps {
              LIMIT ?"
         ).context("Failed to prepare search query")?;
         
-        let results = stmt.query_map(params![query, limit], |row| {
+        let results = stmt.query_map(params![fts_query, limit], |row| {
             Ok(Knowledge {
                 id: row.get(0)?,
                 knowledge_type: row.get(1)?,
@@ -772,7 +1270,7 @@ impl WorkspaceDataOps {
                     created_at: row.get(9)?,
                     updated_at: row.get(10)?,
                 })
-            }).context("Failed to query knowledge")?
+            }).context("Failed to query knowledge")?.collect::<Vec<_>>()
         } else {
             stmt.query_map([], |row| {
                 Ok(Knowledge {
@@ -788,7 +1286,7 @@ impl WorkspaceDataOps {
                     created_at: row.get(9)?,
                     updated_at: row.get(10)?,
                 })
-            }).context("Failed to query knowledge")?
+            }).context("Failed to query knowledge")?.collect::<Vec<_>>()
         };
         
         let mut result = Vec::new();
@@ -799,37 +1297,268 @@ impl WorkspaceDataOps {
         Ok(result)
     }
     
-    // ========================================
-    // Memory Operations
-    // ========================================
-    
-    pub fn create_memory_long(&self, workspace_id: &str, request: CreateMemoryLongRequest) -> Result<MemoryLong> {
+    /// Update a knowledge entry, keeping its previous state as a version
+    pub fn update_knowledge(&self, workspace_id: &str, id: i64, request: UpdateKnowledgeRequest) -> Result<Knowledge> {
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
             .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
         
-        let now = chrono::Utc::now().to_rfc3339();
-        let confidence = request.confidence.unwrap_or(1.0);
-        
-        db.conn.execute(
-            "INSERT INTO memory_long (category, title, content, source, confidence, access_count, created_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, 0, ?, ?)",
-            params![
-                request.category,
-                request.title,
-                request.content,
-                request.source,
-                confidence,
-                now,
-                now,
-            ],
-        ).context("Failed to create long-term memory")?;
+        let current = get_knowledge_row(&db.conn, id)?;
+        let mut updated = current.clone();
+        if let Some(knowledge_type) = request.knowledge_type {
+            updated.knowledge_type = knowledge_type;
+        }
+        if let Some(title) = request.title {
+            updated.title = title;
+        }
+        if let Some(content) = request.content {
+            updated.content = content;
+        }
+        if let Some(tags) = request.tags {
+            updated.tags_json = Some(serde_json::to_string(&tags)?);
+        }
+        if let Some(file_refs) = request.file_refs {
+            updated.file_refs_json = Some(serde_json::to_string(&file_refs)?);
+        }
+        updated.updated_at = chrono::Utc::now().to_rfc3339();
         
-        let memory_id = db.conn.last_insert_rowid();
+        self.db_manager.with_write_retry(|| {
+            let tx = db.conn.unchecked_transaction()?;
+            replace_knowledge(&tx, &current, &updated)?;
+            tx.commit()
+        }).context("Failed to update knowledge")?;
         
-        Ok(MemoryLong {
-            id: memory_id,
-            category: request.category,
+        Ok(updated)
+    }
+    
+    /// Prior versions of an entry, newest first
+    pub fn get_knowledge_history(&self, workspace_id: &str, id: i64) -> Result<Vec<KnowledgeVersion>> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        get_knowledge_row(&db.conn, id)?;
+        let mut stmt = db.conn.prepare(
+            "SELECT id, knowledge_id, version, type, title, content, tags_json, file_refs_json, updated_at, created_at
+             FROM knowledge_versions WHERE knowledge_id = ? ORDER BY version DESC"
+        ).context("Failed to prepare query")?;
+        
+        let results = stmt.query_map(params![id], knowledge_version_from_row)
+            .context("Failed to query knowledge history")?;
+        
+        let mut versions = Vec::new();
+        for version in results {
+            versions.push(version.context("Failed to read knowledge version")?);
+        }
+        
+        Ok(versions)
+    }
+    
+    /// Restore an entry to a prior version. The state being replaced becomes
+    /// a version itself, so a revert can be undone.
+    pub fn revert_knowledge(&self, workspace_id: &str, id: i64, version: i64) -> Result<Knowledge> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let current = get_knowledge_row(&db.conn, id)?;
+        let target = db.conn.query_row(
+            "SELECT id, knowledge_id, version, type, title, content, tags_json, file_refs_json, updated_at, created_at
+             FROM knowledge_versions WHERE knowledge_id = ? AND version = ?",
+            params![id, version],
+            knowledge_version_from_row,
+        ).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => anyhow!("Knowledge {} has no version {}", id, version),
+            e => anyhow!("Failed to get knowledge version: {}", e),
+        })?;
+        
+        let mut reverted = current.clone();
+        reverted.knowledge_type = target.knowledge_type;
+        reverted.title = target.title;
+        reverted.content = target.content;
+        reverted.tags_json = target.tags_json;
+        reverted.file_refs_json = target.file_refs_json;
+        reverted.updated_at = chrono::Utc::now().to_rfc3339();
+        
+        self.db_manager.with_write_retry(|| {
+            let tx = db.conn.unchecked_transaction()?;
+            replace_knowledge(&tx, &current, &reverted)?;
+            tx.commit()
+        }).context("Failed to revert knowledge")?;
+        
+        Ok(reverted)
+    }
+    
+    /// Tag counts across knowledge and long-term memory
+    pub fn get_tag_facets(&self, workspace_id: &str) -> Result<TagFacets> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let knowledge = query_tag_facets(
+            &db.conn,
+            "SELECT t.tag, COUNT(*) FROM knowledge_tags t
+             JOIN knowledge k ON k.id = t.knowledge_id
+             WHERE k.is_active = 1
+             GROUP BY t.tag ORDER BY COUNT(*) DESC, t.tag",
+        )?;
+        let memory = query_tag_facets(
+            &db.conn,
+            "SELECT tag, COUNT(*) FROM memory_long_tags
+             GROUP BY tag ORDER BY COUNT(*) DESC, tag",
+        )?;
+        
+        Ok(TagFacets { knowledge, memory })
+    }
+    
+    /// Active knowledge entries carrying `tag`, most recently updated first
+    pub fn list_knowledge_by_tag(&self, workspace_id: &str, tag: &str) -> Result<Vec<Knowledge>> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let mut stmt = db.conn.prepare(
+            "SELECT k.id, k.type, k.title, k.content, k.tags_json, k.file_refs_json, k.is_active, k.source, k.created_by, k.created_at, k.updated_at
+             FROM knowledge k JOIN knowledge_tags t ON t.knowledge_id = k.id
+             WHERE t.tag = ? AND k.is_active = 1
+             ORDER BY k.updated_at DESC"
+        ).context("Failed to prepare query")?;
+        
+        let results = stmt.query_map(params![tag.trim()], |row| {
+            Ok(Knowledge {
+                id: row.get(0)?,
+                knowledge_type: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                tags_json: row.get(4)?,
+                file_refs_json: row.get(5)?,
+                is_active: row.get(6)?,
+                source: row.get(7)?,
+                created_by: row.get(8)?,
+                created_at: row.get(9)?,
+                updated_at: row.get(10)?,
+            })
+        }).context("Failed to query knowledge")?;
+        
+        let mut result = Vec::new();
+        for knowledge in results {
+            result.push(knowledge.context("Failed to read knowledge")?);
+        }
+        
+        Ok(result)
+    }
+    
+    /// Groups of active entries that duplicate each other
+    pub fn find_duplicate_knowledge(&self, workspace_id: &str) -> Result<Vec<KnowledgeDuplicateGroup>> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let mut groups: Vec<KnowledgeDuplicateGroup> = Vec::new();
+        let mut by_hash: HashMap<String, usize> = HashMap::new();
+        for knowledge in load_active_knowledge(&db.conn)? {
+            let hash = knowledge_content_hash(&knowledge.title, &knowledge.content);
+            match by_hash.get(&hash) {
+                Some(&index) => groups[index].entries.push(knowledge),
+                None => {
+                    by_hash.insert(hash.clone(), groups.len());
+                    groups.push(KnowledgeDuplicateGroup {
+                        content_hash: hash,
+                        entries: vec![knowledge],
+                    });
+                }
+            }
+        }
+        groups.retain(|group| group.entries.len() > 1);
+        
+        Ok(groups)
+    }
+    
+    /// Merge active entries into the one with the longest content: it gains
+    /// the union of their tags and file refs, and the rest are deactivated.
+    /// Deactivated rows stay in the FTS index, which mirrors the table, and
+    /// searches skip them.
+    pub fn merge_knowledge(&self, workspace_id: &str, ids: &[i64]) -> Result<Knowledge> {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() < 2 {
+            return Err(anyhow!("At least two knowledge entries are needed to merge"));
+        }
+        
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let active = load_active_knowledge(&db.conn)?;
+        let entries: Vec<&Knowledge> = ids.iter()
+            .map(|id| active.iter()
+                .find(|k| k.id == *id)
+                .ok_or_else(|| anyhow!("Active knowledge entry not found: {}", id)))
+            .collect::<Result<_>>()?;
+        
+        // Ties go to the oldest entry
+        let original = entries.iter()
+            .copied()
+            .max_by_key(|k| (k.content.chars().count(), std::cmp::Reverse(k.id)))
+            .ok_or_else(|| anyhow!("Nothing to merge"))?;
+        let mut survivor = original.clone();
+        let others: Vec<i64> = ids.iter().copied().filter(|id| *id != survivor.id).collect();
+        
+        let ordered = std::iter::once(original).chain(entries.iter().copied().filter(|k| k.id != original.id));
+        let (tags, file_refs): (Vec<_>, Vec<_>) = ordered
+            .map(|k| (k.tags_json.clone(), k.file_refs_json.clone()))
+            .unzip();
+        survivor.tags_json = union_json_lists(tags.iter().map(|t| t.as_deref()));
+        survivor.file_refs_json = union_json_lists(file_refs.iter().map(|f| f.as_deref()));
+        survivor.updated_at = chrono::Utc::now().to_rfc3339();
+        
+        self.db_manager.with_write_retry(|| {
+            let tx = db.conn.unchecked_transaction()?;
+            replace_knowledge(&tx, original, &survivor)?;
+            for id in &others {
+                tx.execute(
+                    "UPDATE knowledge SET is_active = 0, updated_at = ? WHERE id = ?",
+                    params![survivor.updated_at, id],
+                )?;
+            }
+            tx.commit()
+        }).context("Failed to merge knowledge")?;
+        
+        Ok(survivor)
+    }
+    
+    // ========================================
+    // Memory Operations
+    // ========================================
+    
+    pub fn create_memory_long(&self, workspace_id: &str, request: CreateMemoryLongRequest) -> Result<MemoryLong> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let now = chrono::Utc::now().to_rfc3339();
+        let confidence = request.confidence.unwrap_or(1.0);
+        
+        self.db_manager.with_write_retry(|| db.conn.execute(
+            "INSERT INTO memory_long (category, title, content, source, confidence, access_count, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, 0, ?, ?)",
+            params![
+                request.category,
+                request.title,
+                request.content,
+                request.source,
+                confidence,
+                now,
+                now,
+            ],
+        )).context("Failed to create long-term memory")?;
+        
+        let memory_id = db.conn.last_insert_rowid();
+        
+        Ok(MemoryLong {
+            id: memory_id,
+            category: request.category,
             title: request.title,
             content: request.content,
             source: request.source,
@@ -871,7 +1600,7 @@ impl WorkspaceDataOps {
                     created_at: row.get(8)?,
                     updated_at: row.get(9)?,
                 })
-            }).context("Failed to query memories")?
+            }).context("Failed to query memories")?.collect::<Vec<_>>()
         } else {
             stmt.query_map(params![limit], |row| {
                 Ok(MemoryLong {
@@ -886,7 +1615,7 @@ impl WorkspaceDataOps {
                     created_at: row.get(8)?,
                     updated_at: row.get(9)?,
                 })
-            }).context("Failed to query memories")?
+            }).context("Failed to query memories")?.collect::<Vec<_>>()
         };
         
         let mut result = Vec::new();
@@ -897,6 +1626,42 @@ impl WorkspaceDataOps {
         Ok(result)
     }
     
+    /// Long-term memories carrying `tag`, most recently updated first
+    pub fn list_memory_long_by_tag(&self, workspace_id: &str, tag: &str) -> Result<Vec<MemoryLong>> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let mut stmt = db.conn.prepare(
+            "SELECT m.id, m.category, m.title, m.content, m.source, m.confidence, m.access_count, m.last_accessed_at, m.created_at, m.updated_at
+             FROM memory_long m JOIN memory_long_tags t ON t.memory_id = m.id
+             WHERE t.tag = ?
+             ORDER BY m.updated_at DESC"
+        ).context("Failed to prepare query")?;
+        
+        let results = stmt.query_map(params![tag.trim()], |row| {
+            Ok(MemoryLong {
+                id: row.get(0)?,
+                category: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                source: row.get(4)?,
+                confidence: row.get(5)?,
+                access_count: row.get(6)?,
+                last_accessed_at: row.get(7)?,
+                created_at: row.get(8)?,
+                updated_at: row.get(9)?,
+            })
+        }).context("Failed to query memories")?;
+        
+        let mut result = Vec::new();
+        for memory in results {
+            result.push(memory.context("Failed to read memory")?);
+        }
+        
+        Ok(result)
+    }
+    
     pub fn increment_memory_access(&self, workspace_id: &str, memory_id: i64) -> Result<()> {
         let workspace_db = self.db_manager.open_workspace(workspace_id)?;
         let db = workspace_db.lock()
@@ -904,11 +1669,1321 @@ impl WorkspaceDataOps {
         
         let now = chrono::Utc::now().to_rfc3339();
         
-        db.conn.execute(
+        self.db_manager.with_write_retry(|| db.conn.execute(
             "UPDATE memory_long SET access_count = access_count + 1, last_accessed_at = ? WHERE id = ?",
             params![now, memory_id],
-        ).context("Failed to increment memory access")?;
+        )).context("Failed to increment memory access")?;
         
         Ok(())
     }
+    
+    // ========================================
+    // Quality Snapshot Operations
+    // ========================================
+    
+    pub fn record_quality_snapshot(&self, workspace_id: &str, snapshot: &QualitySnapshot) -> Result<()> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        self.db_manager.with_write_retry(|| db.conn.execute(
+            "INSERT INTO quality_snapshots (id, project_id, commit_sha, overall_score, complexity, maintainability, documentation_coverage, code_coverage, test_count, issue_count, lines_of_code, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                snapshot.id,
+                snapshot.project_id,
+                snapshot.commit_sha,
+                snapshot.overall_score,
+                snapshot.complexity,
+                snapshot.maintainability,
+                snapshot.documentation_coverage,
+                snapshot.code_coverage,
+                snapshot.test_count,
+                snapshot.issue_count,
+                snapshot.lines_of_code,
+                snapshot.created_at,
+            ],
+        )).context("Failed to record quality snapshot")?;
+        
+        Ok(())
+    }
+    
+    /// The `limit` most recent snapshots for a project taken at or after
+    /// `since`, oldest first. The first point's deltas compare against the
+    /// report before it, even when that one is outside the window.
+    pub fn get_quality_trend(
+        &self,
+        workspace_id: &str,
+        project_id: &str,
+        since: Option<i64>,
+        limit: usize,
+        regression_threshold_pct: f64,
+    ) -> Result<Vec<QualityTrendPoint>> {
+        let workspace_db = self.db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        
+        let since = since.unwrap_or(i64::MIN);
+        let mut snapshots = Self::query_quality_snapshots(
+            &db.conn,
+            "project_id = ? AND created_at >= ?",
+            params![project_id, since, limit as i64 + 1],
+        )?;
+        let baseline = if snapshots.len() > limit {
+            snapshots.pop()
+        } else {
+            Self::query_quality_snapshots(
+                &db.conn,
+                "project_id = ? AND created_at < ?",
+                params![project_id, since, 1],
+            )?.pop()
+        };
+        snapshots.reverse();
+        
+        Ok(quality_trend(baseline.as_ref(), snapshots, regression_threshold_pct))
+    }
+    
+    /// Snapshots matching `filter`, newest first; the last parameter is the row limit
+    fn query_quality_snapshots(conn: &Connection, filter: &str, params: impl rusqlite::Params) -> Result<Vec<QualitySnapshot>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, project_id, commit_sha, overall_score, complexity, maintainability, documentation_coverage, code_coverage, test_count, issue_count, lines_of_code, created_at
+             FROM quality_snapshots WHERE {} ORDER BY created_at DESC, rowid DESC LIMIT ?",
+            filter
+        )).context("Failed to prepare query")?;
+        
+        let snapshots = stmt.query_map(params, |row| {
+            Ok(QualitySnapshot {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                commit_sha: row.get(2)?,
+                overall_score: row.get(3)?,
+                complexity: row.get(4)?,
+                maintainability: row.get(5)?,
+                documentation_coverage: row.get(6)?,
+                code_coverage: row.get(7)?,
+                test_count: row.get(8)?,
+                issue_count: row.get(9)?,
+                lines_of_code: row.get(10)?,
+                created_at: row.get(11)?,
+            })
+        }).context("Failed to query quality snapshots")?;
+        
+        let mut result = Vec::new();
+        for snapshot in snapshots {
+            result.push(snapshot.context("Failed to read quality snapshot")?);
+        }
+        
+        Ok(result)
+    }
+    
+    // ========================================
+    // Global Search Operations
+    // ========================================
+    
+    /// Search the enabled sources of one workspace, up to `limit` hits per source
+    pub fn search_workspace_sources(
+        &self,
+        workspace: &WorkspaceMetadata,
+        query: &str,
+        sources: GlobalSearchSources,
+        limit: usize,
+    ) -> Result<Vec<GlobalSearchHit>> {
+        let Some(fts_query) = sanitize_fts_query(query, false) else {
+            return Ok(Vec::new());
+        };
+        
+        // Searching every workspace shouldn't cache their connections or
+        // make them all look recently used
+        let db = self.db_manager.connect_workspace(&workspace.id)?;
+        
+        let queries = [
+            (sources.knowledge, SearchSource::Knowledge,
+             "SELECT k.id, k.title, NULL, snippet(knowledge_fts, 1, '**', '**', '…', 16), knowledge_fts.rank
+              FROM knowledge_fts
+              JOIN knowledge k ON k.id = knowledge_fts.rowid
+              WHERE knowledge_fts MATCH ? AND k.is_active = 1
+              ORDER BY rank
+              LIMIT ?"),
+            (sources.memory, SearchSource::Memory,
+             "SELECT m.id, m.title, NULL, snippet(memory_long_fts, 1, '**', '**', '…', 16), memory_long_fts.rank
+              FROM memory_long_fts
+              JOIN memory_long m ON m.id = memory_long_fts.rowid
+              WHERE memory_long_fts MATCH ?
+              ORDER BY rank
+              LIMIT ?"),
+            (sources.chat, SearchSource::Chat,
+             "SELECT m.id, COALESCE(s.title, 'Chat'), m.session_id, snippet(chat_messages_fts, 0, '**', '**', '…', 16), chat_messages_fts.rank
+              FROM chat_messages_fts
+              JOIN chat_messages m ON m.id = chat_messages_fts.rowid
+              JOIN chat_sessions s ON s.id = m.session_id
+              WHERE chat_messages_fts MATCH ?
+              ORDER BY rank
+              LIMIT ?"),
+        ];
+        
+        let mut result = Vec::new();
+        for (enabled, source, sql) in queries {
+            if !enabled {
+                continue;
+            }
+            let mut stmt = db.conn.prepare(sql).context("Failed to prepare search query")?;
+            let hits = stmt.query_map(params![fts_query, limit as i64], |row| {
+                let rank: f64 = row.get(4)?;
+                Ok(GlobalSearchHit {
+                    workspace_id: workspace.id.clone(),
+                    workspace_name: workspace.name.clone(),
+                    source,
+                    item_id: row.get(0)?,
+                    title: row.get(1)?,
+                    session_id: row.get(2)?,
+                    snippet: row.get(3)?,
+                    score: bm25_score(rank),
+                })
+            }).context("Failed to search workspace")?;
+            
+            for hit in hits {
+                result.push(hit.context("Failed to read search hit")?);
+            }
+        }
+        
+        Ok(result)
+    }
+    
+    /// Search every workspace in `workspaces`, at most
+    /// `MAX_CONCURRENT_WORKSPACE_SEARCHES` at a time. Workspaces that fail
+    /// to open or query are reported in `skipped` instead of failing the search.
+    pub fn search_all_workspaces(
+        &self,
+        workspaces: &[WorkspaceMetadata],
+        query: &str,
+        sources: GlobalSearchSources,
+        limit: usize,
+    ) -> GlobalSearchResults {
+        let mut results = GlobalSearchResults::default();
+        if limit == 0 || sanitize_fts_query(query, false).is_none() {
+            return results;
+        }
+        
+        for batch in workspaces.chunks(MAX_CONCURRENT_WORKSPACE_SEARCHES) {
+            let outcomes: Vec<Result<Vec<GlobalSearchHit>>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = batch.iter()
+                    .map(|workspace| scope.spawn(move || self.search_workspace_sources(workspace, query, sources, limit)))
+                    .collect();
+                handles.into_iter()
+                    .map(|handle| handle.join().unwrap_or_else(|_| Err(anyhow!("Workspace search panicked"))))
+                    .collect()
+            });
+            
+            for (workspace, outcome) in batch.iter().zip(outcomes) {
+                match outcome {
+                    Ok(hits) => results.hits.extend(hits),
+                    Err(e) => {
+                        eprintln!("Skipping workspace {} in global search: {:#}", workspace.id, e);
+                        results.skipped.push(SkippedWorkspace {
+                            workspace_id: workspace.id.clone(),
+                            workspace_name: workspace.name.clone(),
+                            error: format!("{:#}", e),
+                        });
+                    }
+                }
+            }
+        }
+        
+        results.hits.sort_by(|a, b| {
+            b.score.total_cmp(&a.score)
+                .then_with(|| a.workspace_name.cmp(&b.workspace_name))
+                .then_with(|| a.item_id.cmp(&b.item_id))
+        });
+        results.hits.truncate(limit);
+        results
+    }
+}
+
+// ============================================
+// Global Search
+// ============================================
+
+/// Workspaces searched in parallel by `search_all_workspaces`
+pub const MAX_CONCURRENT_WORKSPACE_SEARCHES: usize = 4;
+
+/// Default number of hits returned by a global search
+pub const DEFAULT_GLOBAL_SEARCH_LIMIT: usize = 50;
+
+/// FTS5 ranks are negated bm25 scores, unbounded. Map them into (0, 1) so
+/// hits from different sources and workspaces can be merged.
+fn bm25_score(rank: f64) -> f64 {
+    let relevance = (-rank).max(0.0);
+    relevance / (1.0 + relevance)
+}
+
+// ============================================
+// Knowledge Versions
+// ============================================
+
+/// Versions kept per knowledge entry; older ones are dropped
+pub const KNOWLEDGE_VERSION_LIMIT: i64 = 50;
+
+fn get_knowledge_row(conn: &Connection, id: i64) -> Result<Knowledge> {
+    conn.query_row(
+        "SELECT id, type, title, content, tags_json, file_refs_json, is_active, source, created_by, created_at, updated_at
+         FROM knowledge WHERE id = ?",
+        params![id],
+        |row| Ok(Knowledge {
+            id: row.get(0)?,
+            knowledge_type: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            tags_json: row.get(4)?,
+            file_refs_json: row.get(5)?,
+            is_active: row.get(6)?,
+            source: row.get(7)?,
+            created_by: row.get(8)?,
+            created_at: row.get(9)?,
+            updated_at: row.get(10)?,
+        }),
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => anyhow!("Knowledge not found: {}", id),
+        e => anyhow!("Failed to get knowledge: {}", e),
+    })
+}
+
+fn knowledge_version_from_row(row: &rusqlite::Row) -> rusqlite::Result<KnowledgeVersion> {
+    Ok(KnowledgeVersion {
+        id: row.get(0)?,
+        knowledge_id: row.get(1)?,
+        version: row.get(2)?,
+        knowledge_type: row.get(3)?,
+        title: row.get(4)?,
+        content: row.get(5)?,
+        tags_json: row.get(6)?,
+        file_refs_json: row.get(7)?,
+        updated_at: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+/// Record `current` as the entry's next version, then overwrite the row with
+/// `next`. The `knowledge_au` trigger reindexes FTS. Call inside a transaction.
+fn replace_knowledge(conn: &Connection, current: &Knowledge, next: &Knowledge) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO knowledge_versions (knowledge_id, version, type, title, content, tags_json, file_refs_json, updated_at, created_at)
+         SELECT ?, COALESCE(MAX(version), 0) + 1, ?, ?, ?, ?, ?, ?, ? FROM knowledge_versions WHERE knowledge_id = ?",
+        params![
+            current.id,
+            current.knowledge_type,
+            current.title,
+            current.content,
+            current.tags_json,
+            current.file_refs_json,
+            current.updated_at,
+            next.updated_at,
+            current.id,
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM knowledge_versions WHERE knowledge_id = ?1
+         AND version <= (SELECT MAX(version) FROM knowledge_versions WHERE knowledge_id = ?1) - ?2",
+        params![current.id, KNOWLEDGE_VERSION_LIMIT],
+    )?;
+    conn.execute(
+        "UPDATE knowledge SET type = ?, title = ?, content = ?, tags_json = ?, file_refs_json = ?, updated_at = ? WHERE id = ?",
+        params![
+            next.knowledge_type,
+            next.title,
+            next.content,
+            next.tags_json,
+            next.file_refs_json,
+            next.updated_at,
+            current.id,
+        ],
+    )?;
+    Ok(())
+}
+
+// ============================================
+// Tag Facets
+// ============================================
+
+/// Run a `(tag, count)` aggregate over one of the tag tables
+fn query_tag_facets(conn: &Connection, sql: &str) -> Result<Vec<TagFacet>> {
+    let mut stmt = conn.prepare(sql).context("Failed to prepare query")?;
+    let facets = stmt.query_map([], |row| {
+        Ok(TagFacet {
+            tag: row.get(0)?,
+            count: row.get(1)?,
+        })
+    }).context("Failed to query tag facets")?;
+    
+    facets.collect::<rusqlite::Result<Vec<_>>>().context("Failed to read tag facet")
+}
+
+// ============================================
+// Knowledge Deduplication
+// ============================================
+
+/// Workspace setting that makes `create_knowledge` merge duplicates
+pub const KNOWLEDGE_AUTO_DEDUP_SETTING: &str = "knowledge.auto_dedup";
+
+/// Hash of the title and content, ignoring case and whitespace differences
+pub fn knowledge_content_hash(title: &str, content: &str) -> String {
+    let normalize = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let digest = Sha256::digest(format!("{}\n{}", normalize(title), normalize(content)).as_bytes());
+    hex::encode(digest)
+}
+
+/// Union of JSON string arrays in first-seen order; `None` when all are absent.
+/// Values that aren't string arrays are skipped.
+fn union_json_lists<'a>(lists: impl IntoIterator<Item = Option<&'a str>>) -> Option<String> {
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    let mut any = false;
+    for list in lists.into_iter().flatten() {
+        any = true;
+        let values: Vec<String> = serde_json::from_str(list).unwrap_or_default();
+        merged.extend(values.into_iter().filter(|value| seen.insert(value.clone())));
+    }
+    any.then(|| serde_json::to_string(&merged).unwrap_or_default())
+}
+
+fn load_active_knowledge(conn: &Connection) -> Result<Vec<Knowledge>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, type, title, content, tags_json, file_refs_json, is_active, source, created_by, created_at, updated_at
+         FROM knowledge WHERE is_active = 1 ORDER BY id"
+    ).context("Failed to prepare query")?;
+    
+    let results = stmt.query_map([], |row| {
+        Ok(Knowledge {
+            id: row.get(0)?,
+            knowledge_type: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            tags_json: row.get(4)?,
+            file_refs_json: row.get(5)?,
+            is_active: row.get(6)?,
+            source: row.get(7)?,
+            created_by: row.get(8)?,
+            created_at: row.get(9)?,
+            updated_at: row.get(10)?,
+        })
+    }).context("Failed to query knowledge")?;
+    
+    let mut result = Vec::new();
+    for knowledge in results {
+        result.push(knowledge.context("Failed to read knowledge")?);
+    }
+    
+    Ok(result)
+}
+
+// ============================================
+// Job Deletion
+// ============================================
+
+/// Count the rows of `table` matching `filter`, or update them with `set`
+/// (deleting when `set` is None). `?1` in either clause is the job id.
+fn job_delete_step(
+    conn: &Connection,
+    dry_run: bool,
+    table: &str,
+    filter: &str,
+    set: Option<&str>,
+    job_id: &str,
+) -> rusqlite::Result<usize> {
+    if dry_run {
+        let count: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM {} WHERE {}", table, filter),
+            params![job_id],
+            |row| row.get(0),
+        )?;
+        return Ok(count as usize);
+    }
+    
+    let sql = match set {
+        Some(set) => format!("UPDATE {} SET {} WHERE {}", table, set, filter),
+        None => format!("DELETE FROM {} WHERE {}", table, filter),
+    };
+    conn.execute(&sql, params![job_id])
+}
+
+// ============================================
+// Job Progress
+// ============================================
+
+/// Roll up task counts and timings. Tasks without an estimate are weighted
+/// at the mean estimate; with no estimates at all the percent is count-based
+/// and the ETA uses the mean actual time of completed tasks instead.
+pub fn compute_job_progress(job_id: &str, tasks: &[Task], now: chrono::DateTime<chrono::Utc>) -> JobProgress {
+    let tasks: Vec<&Task> = tasks.iter().filter(|t| t.status != "cancelled").collect();
+    let (done, open): (Vec<&Task>, Vec<&Task>) = tasks.iter().partition(|t| t.status == "completed");
+    
+    let estimates: Vec<i32> = tasks.iter().filter_map(|t| t.estimated_minutes).collect();
+    let estimate_weighted = !estimates.is_empty();
+    let mean_estimate = if estimate_weighted {
+        estimates.iter().map(|&m| m as f64).sum::<f64>() / estimates.len() as f64
+    } else {
+        1.0
+    };
+    let weight = |t: &Task| t.estimated_minutes.map(|m| m as f64).unwrap_or(mean_estimate);
+    
+    let total_weight: f64 = tasks.iter().map(|t| weight(t)).sum();
+    let done_weight: f64 = done.iter().map(|t| weight(t)).sum();
+    let percent_complete = if total_weight > 0.0 {
+        (done_weight / total_weight * 100.0 * 10.0).round() / 10.0
+    } else if !tasks.is_empty() {
+        done.len() as f64 / tasks.len() as f64 * 100.0
+    } else {
+        0.0
+    };
+    
+    let (timed_actual, timed_estimate) = done.iter()
+        .filter_map(|t| Some((t.actual_minutes? as f64, t.estimated_minutes? as f64)))
+        .fold((0.0, 0.0), |(a, e), (actual, estimate)| (a + actual, e + estimate));
+    let estimate_accuracy = (timed_estimate > 0.0).then(|| timed_actual / timed_estimate);
+    
+    let remaining = if open.is_empty() {
+        Some(0.0)
+    } else if estimate_weighted {
+        let remaining_estimate: f64 = open.iter().map(|t| weight(t)).sum();
+        Some(remaining_estimate * estimate_accuracy.unwrap_or(1.0))
+    } else {
+        let actuals: Vec<f64> = done.iter().filter_map(|t| t.actual_minutes).map(|m| m as f64).collect();
+        (!actuals.is_empty()).then(|| actuals.iter().sum::<f64>() / actuals.len() as f64 * open.len() as f64)
+    };
+    let eta_minutes = remaining.map(|m| m.ceil() as i32);
+    
+    JobProgress {
+        job_id: job_id.to_string(),
+        total_tasks: tasks.len() as i32,
+        completed_tasks: done.len() as i32,
+        percent_complete,
+        estimate_weighted,
+        estimated_minutes: estimates.iter().sum(),
+        actual_minutes: tasks.iter().filter_map(|t| t.actual_minutes).sum(),
+        estimate_accuracy,
+        eta_minutes,
+        eta_at: eta_minutes.map(|m| (now + chrono::Duration::minutes(m as i64)).to_rfc3339()),
+    }
+}
+
+// ============================================
+// Task Graph
+// ============================================
+
+/// Find a dependency cycle, returned as task ids with the first repeated at
+/// the end. Edge endpoints missing from `nodes` are still followed.
+pub fn find_cycle(nodes: &[String], edges: &[TaskDependency]) -> Option<Vec<String>> {
+    let mut adjacency: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in edges {
+        adjacency.entry(edge.task_id.as_str()).or_default().push(edge.depends_on_task_id.as_str());
+    }
+    
+    let mut done: HashSet<&str> = HashSet::new();
+    for start in nodes.iter().map(String::as_str).chain(edges.iter().map(|e| e.task_id.as_str())) {
+        if done.contains(start) {
+            continue;
+        }
+        // Iterative DFS; `path` holds the current chain, `on_path` its members
+        let mut path: Vec<(&str, usize)> = vec![(start, 0)];
+        let mut on_path: HashSet<&str> = HashSet::from([start]);
+        
+        while let Some((node, next)) = path.last_mut() {
+            let children = adjacency.get(*node).map(Vec::as_slice).unwrap_or(&[]);
+            if let Some(&child) = children.get(*next) {
+                *next += 1;
+                if on_path.contains(child) {
+                    let from = path.iter().position(|(n, _)| *n == child).unwrap_or(0);
+                    let mut cycle: Vec<String> = path[from..].iter().map(|(n, _)| n.to_string()).collect();
+                    cycle.push(child.to_string());
+                    return Some(cycle);
+                }
+                if !done.contains(child) {
+                    path.push((child, 0));
+                    on_path.insert(child);
+                }
+            } else {
+                on_path.remove(*node);
+                done.insert(*node);
+                path.pop();
+            }
+        }
+    }
+    
+    None
+}
+
+/// Order `nodes` so every task follows its dependencies, keeping the given
+/// order among tasks that are free to run. Assumes the graph is acyclic.
+pub fn topological_order(nodes: &[String], edges: &[TaskDependency]) -> Vec<String> {
+    let mut remaining: HashMap<&str, usize> = nodes.iter().map(|n| (n.as_str(), 0)).collect();
+    for edge in edges {
+        if remaining.contains_key(edge.depends_on_task_id.as_str()) {
+            if let Some(count) = remaining.get_mut(edge.task_id.as_str()) {
+                *count += 1;
+            }
+        }
+    }
+    
+    let mut order = Vec::with_capacity(nodes.len());
+    while order.len() < nodes.len() {
+        let Some(next) = nodes.iter().find(|n| remaining.get(n.as_str()) == Some(&0)) else {
+            break;
+        };
+        remaining.remove(next.as_str());
+        for edge in edges.iter().filter(|e| e.depends_on_task_id == *next) {
+            if let Some(count) = remaining.get_mut(edge.task_id.as_str()) {
+                *count -= 1;
+            }
+        }
+        order.push(next.clone());
+    }
+    
+    order
+}
+
+// ============================================
+// Quality Trend
+// ============================================
+
+/// How much worse, as a percentage of the previous value, a metric may get
+/// before it is flagged as a regression
+pub const DEFAULT_QUALITY_REGRESSION_PCT: f64 = 5.0;
+
+type MetricValue = fn(&QualitySnapshot) -> Option<f64>;
+
+/// Tracked metrics and whether higher is better; `None` is neither
+const QUALITY_METRICS: &[(&str, MetricValue, Option<bool>)] = &[
+    ("overall_score", |s| Some(s.overall_score), Some(true)),
+    ("complexity", |s| Some(s.complexity), Some(false)),
+    ("maintainability", |s| Some(s.maintainability), Some(true)),
+    ("documentation_coverage", |s| Some(s.documentation_coverage), Some(true)),
+    ("code_coverage", |s| s.code_coverage, Some(true)),
+    ("test_count", |s| Some(s.test_count as f64), Some(true)),
+    ("issue_count", |s| Some(s.issue_count as f64), Some(false)),
+    ("lines_of_code", |s| Some(s.lines_of_code as f64), None),
+];
+
+/// Changes from `previous` to `current`. Getting worse from zero always
+/// counts as a regression.
+pub fn quality_deltas(previous: &QualitySnapshot, current: &QualitySnapshot, regression_threshold_pct: f64) -> Vec<MetricDelta> {
+    QUALITY_METRICS.iter()
+        .filter_map(|(metric, value, higher_is_better)| {
+            let (previous, current) = (value(previous)?, value(current)?);
+            let worse_by = match higher_is_better {
+                Some(true) => previous - current,
+                Some(false) => current - previous,
+                None => 0.0,
+            };
+            let regression = worse_by > 0.0
+                && (previous == 0.0 || worse_by / previous.abs() * 100.0 > regression_threshold_pct);
+            Some(MetricDelta {
+                metric: metric.to_string(),
+                previous,
+                current,
+                change: current - previous,
+                regression,
+            })
+        })
+        .collect()
+}
+
+/// Pair each snapshot, oldest first, with its change from the one before
+pub fn quality_trend(
+    baseline: Option<&QualitySnapshot>,
+    snapshots: Vec<QualitySnapshot>,
+    regression_threshold_pct: f64,
+) -> Vec<QualityTrendPoint> {
+    let mut points: Vec<QualityTrendPoint> = Vec::with_capacity(snapshots.len());
+    for snapshot in snapshots {
+        let previous = points.last().map(|p| &p.snapshot).or(baseline);
+        let deltas = previous
+            .map(|previous| quality_deltas(previous, &snapshot, regression_threshold_pct))
+            .unwrap_or_default();
+        points.push(QualityTrendPoint {
+            regressed: deltas.iter().any(|d| d.regression),
+            snapshot,
+            deltas,
+        });
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn edge(task: &str, depends_on: &str) -> TaskDependency {
+        TaskDependency { task_id: task.to_string(), depends_on_task_id: depends_on.to_string() }
+    }
+    
+    fn ids(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+    
+    fn setup() -> (tempfile::TempDir, Arc<WorkspaceDbManager>, WorkspaceDataOps, String, String) {
+        let base_dir = tempfile::tempdir().unwrap();
+        let db_manager = Arc::new(WorkspaceDbManager::with_base_dir(base_dir.path()).unwrap());
+        let ops = WorkspaceDataOps::new(Arc::clone(&db_manager));
+        let workspace = db_manager.create_workspace("test-task-graph", None).unwrap();
+        let job = ops.create_job(&workspace.id, CreateJobRequest {
+            name: "Job".to_string(),
+            description: None,
+            branch_name: None,
+            parent_job_id: None,
+        }).unwrap();
+        (base_dir, db_manager, ops, workspace.id, job.id)
+    }
+    
+    fn add_task(ops: &WorkspaceDataOps, workspace_id: &str, job_id: &str, title: &str) -> String {
+        ops.create_task(workspace_id, CreateTaskRequest {
+            job_id: job_id.to_string(),
+            title: title.to_string(),
+            description: None,
+            priority: None,
+            estimated_minutes: None,
+            assignee: None,
+        }).unwrap().id
+    }
+    
+    fn task(status: &str, estimated: Option<i32>, actual: Option<i32>) -> Task {
+        Task {
+            id: uuid::Uuid::new_v4().to_string(),
+            job_id: "job".to_string(),
+            title: "task".to_string(),
+            description: None,
+            status: status.to_string(),
+            priority: 0,
+            order_index: 0,
+            estimated_minutes: estimated,
+            actual_minutes: actual,
+            assignee: None,
+            metadata_json: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            completed_at: None,
+        }
+    }
+    
+    #[test]
+    fn test_job_progress_with_mixed_estimates() {
+        let tasks = vec![
+            task("completed", Some(60), Some(90)),
+            task("completed", None, Some(10)),
+            task("pending", Some(120), None),
+            task("in_progress", None, None),
+            task("cancelled", Some(500), None),
+        ];
+        let now = chrono::Utc::now();
+        let progress = compute_job_progress("job", &tasks, now);
+        
+        // Unestimated tasks weigh the mean estimate (90): done 150 of 360
+        assert_eq!(progress.total_tasks, 4);
+        assert_eq!(progress.completed_tasks, 2);
+        assert!(progress.estimate_weighted);
+        assert_eq!(progress.percent_complete, 41.7);
+        assert_eq!(progress.estimated_minutes, 180);
+        assert_eq!(progress.actual_minutes, 100);
+        // Only the task with both numbers informs accuracy: 90 / 60
+        assert_eq!(progress.estimate_accuracy, Some(1.5));
+        assert_eq!(progress.eta_minutes, Some(315));
+        assert_eq!(progress.eta_at, Some((now + chrono::Duration::minutes(315)).to_rfc3339()));
+    }
+    
+    #[test]
+    fn test_job_progress_without_estimates() {
+        let tasks = vec![
+            task("completed", None, Some(20)),
+            task("completed", None, Some(40)),
+            task("pending", None, None),
+            task("blocked", None, None),
+        ];
+        let progress = compute_job_progress("job", &tasks, chrono::Utc::now());
+        assert!(!progress.estimate_weighted);
+        assert_eq!(progress.percent_complete, 50.0);
+        assert_eq!(progress.estimate_accuracy, None);
+        assert_eq!(progress.eta_minutes, Some(60));
+        
+        let progress = compute_job_progress("job", &[task("pending", None, None)], chrono::Utc::now());
+        assert_eq!(progress.percent_complete, 0.0);
+        assert_eq!(progress.eta_minutes, None);
+        
+        let progress = compute_job_progress("job", &[], chrono::Utc::now());
+        assert_eq!(progress.percent_complete, 0.0);
+        assert_eq!(progress.eta_minutes, Some(0));
+    }
+    
+    #[test]
+    fn test_search_chat_messages_across_sessions() {
+        let (_base_dir, _, ops, workspace_id, job_id) = setup();
+        let message = |session_id: &str, content: &str| {
+            ops.add_chat_message(&workspace_id, CreateChatMessageRequest {
+                session_id: session_id.to_string(),
+                role: "user".to_string(),
+                content: content.to_string(),
+                tool_calls_json: None,
+                tool_results_json: None,
+                model_id: None,
+                tokens_input: None,
+                tokens_output: None,
+                latency_ms: None,
+            }).unwrap()
+        };
+        let session = |title: &str, job_id: Option<String>| {
+            ops.create_chat_session(&workspace_id, CreateChatSessionRequest {
+                job_id,
+                title: Some(title.to_string()),
+                session_type: None,
+                model_id: None,
+            }).unwrap()
+        };
+        
+        let planning = session("Planning", Some(job_id.clone()));
+        let review = session("Review", None);
+        let decided = message(&planning.id, "We decided to use OAuth for auth (PKCE flow)");
+        message(&planning.id, "Unrelated note about the build");
+        message(&review.id, "Is the auth decision final? \"NOT\" sure");
+        
+        let results = ops.search_chat_messages(&workspace_id, "auth decided", false, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message.id, decided.id);
+        assert_eq!(results[0].session_title.as_deref(), Some("Planning"));
+        assert_eq!(results[0].job_id.as_deref(), Some(job_id.as_str()));
+        assert!(results[0].snippet.contains("**auth**"));
+        
+        let sessions: HashSet<String> = ops.search_chat_messages(&workspace_id, "auth", false, None).unwrap()
+            .into_iter().map(|r| r.message.session_id).collect();
+        assert_eq!(sessions, HashSet::from([planning.id.clone(), review.id.clone()]));
+        
+        // FTS operators in user input are matched as words, not parsed
+        assert_eq!(ops.search_chat_messages(&workspace_id, "\"NOT\" sure (", false, None).unwrap().len(), 1);
+        assert!(ops.search_chat_messages(&workspace_id, "*", false, None).unwrap().is_empty());
+        assert_eq!(ops.search_chat_messages(&workspace_id, "auth", false, Some(1)).unwrap().len(), 1);
+        
+        // Advanced syntax is repaired rather than rejected
+        assert_eq!(ops.search_chat_messages(&workspace_id, "auth AND (decided OR", true, None).unwrap().len(), 1);
+        assert!(ops.search_chat_messages(&workspace_id, "( AND", true, None).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_chat_message_pages_are_stable_under_inserts() {
+        let (_base_dir, _, ops, workspace_id, _job_id) = setup();
+        let session = ops.create_chat_session(&workspace_id, CreateChatSessionRequest {
+            job_id: None,
+            title: None,
+            session_type: None,
+            model_id: None,
+        }).unwrap();
+        let message = |content: String| {
+            ops.add_chat_message(&workspace_id, CreateChatMessageRequest {
+                session_id: session.id.clone(),
+                role: "user".to_string(),
+                content,
+                tool_calls_json: None,
+                tool_results_json: None,
+                model_id: None,
+                tokens_input: None,
+                tokens_output: None,
+                latency_ms: None,
+            }).unwrap()
+        };
+        for i in 0..5 {
+            message(format!("m{}", i));
+        }
+        
+        let page = |before_id: Option<String>, after_id: Option<String>| {
+            ops.get_chat_messages_page(&workspace_id, &session.id, PageRequest { before_id, after_id, limit: Some(2) }).unwrap()
+        };
+        let contents = |page: &Page<ChatMessage>| page.items.iter().map(|m| m.content.clone()).collect::<Vec<_>>();
+        
+        let latest = page(None, None);
+        assert_eq!(contents(&latest), vec!["m3", "m4"]);
+        
+        // Messages arriving mid-scroll don't shift older pages
+        message("m5".to_string());
+        let older = page(latest.next_cursor.clone(), None);
+        assert_eq!(contents(&older), vec!["m1", "m2"]);
+        let oldest = page(older.next_cursor.clone(), None);
+        assert_eq!(contents(&oldest), vec!["m0"]);
+        assert!(oldest.next_cursor.is_none());
+        
+        let newest_seen = latest.items.last().unwrap().id.to_string();
+        let newer = page(None, Some(newest_seen));
+        assert_eq!(contents(&newer), vec!["m5"]);
+        
+        // The non-paginated call still returns the latest N in order
+        let last_two: Vec<String> = ops.get_chat_messages(&workspace_id, &session.id, Some(2)).unwrap()
+            .into_iter().map(|m| m.content).collect();
+        assert_eq!(last_two, vec!["m4", "m5"]);
+        assert_eq!(ops.get_chat_messages(&workspace_id, &session.id, None).unwrap().len(), 6);
+    }
+    
+    #[test]
+    fn test_job_pages_are_stable_under_inserts() {
+        let (_base_dir, _, ops, workspace_id, first_job) = setup();
+        let create = |name: &str| ops.create_job(&workspace_id, CreateJobRequest {
+            name: name.to_string(),
+            description: None,
+            branch_name: None,
+            parent_job_id: None,
+        }).unwrap();
+        for name in ["b", "c", "d"] {
+            create(name);
+        }
+        
+        let request = |before_id: Option<String>| PageRequest { before_id, after_id: None, limit: Some(3) };
+        let names = |page: &Page<Job>| page.items.iter().map(|j| j.name.clone()).collect::<Vec<_>>();
+        
+        let first = ops.list_jobs_page(&workspace_id, None, request(None)).unwrap();
+        assert_eq!(names(&first), vec!["d", "c", "b"]);
+        
+        create("e");
+        let second = ops.list_jobs_page(&workspace_id, None, request(first.next_cursor.clone())).unwrap();
+        assert_eq!(second.items.len(), 1);
+        assert_eq!(second.items[0].id, first_job);
+        assert!(second.next_cursor.is_none());
+        
+        assert_eq!(ops.list_jobs(&workspace_id, None).unwrap().len(), 5);
+        assert!(ops.list_jobs_page(&workspace_id, None, PageRequest {
+            before_id: Some("a".to_string()),
+            after_id: Some("b".to_string()),
+            limit: None,
+        }).is_err());
+    }
+    
+    #[test]
+    fn test_find_cycle_and_order() {
+        let nodes = ids(&["a", "b", "c", "d"]);
+        let acyclic = vec![edge("b", "a"), edge("c", "b"), edge("d", "a")];
+        assert_eq!(find_cycle(&nodes, &acyclic), None);
+        assert_eq!(topological_order(&ids(&["c", "d", "b", "a"]), &acyclic), ids(&["a", "d", "b", "c"]));
+        
+        let cyclic = vec![edge("a", "b"), edge("b", "c"), edge("c", "a")];
+        let cycle = find_cycle(&nodes, &cyclic).unwrap();
+        assert_eq!(cycle.first(), cycle.last());
+        assert_eq!(cycle.len(), 4);
+    }
+    
+    #[test]
+    fn test_reorder_tasks_persists() {
+        let (_base_dir, _, ops, workspace_id, job_id) = setup();
+        let a = add_task(&ops, &workspace_id, &job_id, "a");
+        let b = add_task(&ops, &workspace_id, &job_id, "b");
+        let c = add_task(&ops, &workspace_id, &job_id, "c");
+        
+        ops.reorder_tasks(&workspace_id, &job_id, vec![c.clone(), a.clone(), b.clone()]).unwrap();
+        let titles: Vec<String> = ops.list_tasks(&workspace_id, &job_id).unwrap()
+            .into_iter().map(|t| t.title).collect();
+        assert_eq!(titles, ids(&["c", "a", "b"]));
+        
+        // A task from another job is rejected without changing the order
+        let other_job = ops.create_job(&workspace_id, CreateJobRequest {
+            name: "Other".to_string(),
+            description: None,
+            branch_name: None,
+            parent_job_id: None,
+        }).unwrap();
+        let foreign = add_task(&ops, &workspace_id, &other_job.id, "x");
+        assert!(ops.reorder_tasks(&workspace_id, &job_id, vec![a.clone(), foreign]).is_err());
+        let titles: Vec<String> = ops.list_tasks(&workspace_id, &job_id).unwrap()
+            .into_iter().map(|t| t.title).collect();
+        assert_eq!(titles, ids(&["c", "a", "b"]));
+    }
+    
+    #[test]
+    fn test_delete_job_removes_dependents() {
+        let (_base_dir, _, ops, workspace_id, job_id) = setup();
+        let a = add_task(&ops, &workspace_id, &job_id, "a");
+        let b = add_task(&ops, &workspace_id, &job_id, "b");
+        ops.add_task_dependency(&workspace_id, &b, &a).unwrap();
+        
+        let child = ops.create_job(&workspace_id, CreateJobRequest {
+            name: "Child".to_string(),
+            description: None,
+            branch_name: None,
+            parent_job_id: Some(job_id.clone()),
+        }).unwrap();
+        let session = ops.create_chat_session(&workspace_id, CreateChatSessionRequest {
+            job_id: Some(job_id.clone()),
+            title: Some("Planning".to_string()),
+            session_type: None,
+            model_id: None,
+        }).unwrap();
+        ops.add_chat_message(&workspace_id, CreateChatMessageRequest {
+            session_id: session.id.clone(),
+            role: "user".to_string(),
+            content: "hello".to_string(),
+            tool_calls_json: None,
+            tool_results_json: None,
+            model_id: None,
+            tokens_input: None,
+            tokens_output: None,
+            latency_ms: None,
+        }).unwrap();
+        
+        let expected = JobDeletion {
+            job_id: job_id.clone(),
+            dry_run: true,
+            tasks_deleted: 2,
+            task_dependencies_deleted: 1,
+            working_memory_deleted: 0,
+            chat_sessions_archived: 1,
+            child_jobs_reparented: 1,
+            history_records_detached: 0,
+        };
+        assert_eq!(ops.delete_job(&workspace_id, &job_id, true).unwrap(), expected);
+        assert_eq!(ops.list_tasks(&workspace_id, &job_id).unwrap().len(), 2);
+        assert!(ops.get_job(&workspace_id, &job_id).is_ok());
+        
+        let deletion = ops.delete_job(&workspace_id, &job_id, false).unwrap();
+        assert_eq!(deletion, JobDeletion { dry_run: false, ..expected });
+        
+        assert!(ops.get_job(&workspace_id, &job_id).is_err());
+        assert!(ops.list_tasks(&workspace_id, &job_id).unwrap().is_empty());
+        assert!(ops.delete_job(&workspace_id, &job_id, true).is_err());
+        assert_eq!(ops.get_job(&workspace_id, &child.id).unwrap().parent_job_id, None);
+        assert!(ops.list_chat_sessions(&workspace_id, None).unwrap().iter().all(|s| s.id != session.id));
+        assert_eq!(ops.get_chat_messages(&workspace_id, &session.id, None).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_task_dependencies_reject_cycles_and_cross_job_edges() {
+        let (_base_dir, _, ops, workspace_id, job_id) = setup();
+        let a = add_task(&ops, &workspace_id, &job_id, "a");
+        let b = add_task(&ops, &workspace_id, &job_id, "b");
+        let c = add_task(&ops, &workspace_id, &job_id, "c");
+        
+        ops.add_task_dependency(&workspace_id, &b, &a).unwrap();
+        ops.add_task_dependency(&workspace_id, &c, &b).unwrap();
+        let err = ops.add_task_dependency(&workspace_id, &a, &c).unwrap_err();
+        assert!(err.to_string().contains("cycle"));
+        assert!(ops.add_task_dependency(&workspace_id, &a, &a).is_err());
+        
+        let other_job = ops.create_job(&workspace_id, CreateJobRequest {
+            name: "Other".to_string(),
+            description: None,
+            branch_name: None,
+            parent_job_id: None,
+        }).unwrap();
+        let foreign = add_task(&ops, &workspace_id, &other_job.id, "x");
+        assert!(ops.add_task_dependency(&workspace_id, &a, &foreign).is_err());
+        
+        let graph = ops.get_task_graph(&workspace_id, &job_id).unwrap();
+        assert_eq!(graph.edges.len(), 2);
+        assert_eq!(graph.cycle, None);
+        assert_eq!(graph.execution_order, vec![a.clone(), b.clone(), c.clone()]);
+        
+        ops.remove_task_dependency(&workspace_id, &c, &b).unwrap();
+        assert_eq!(ops.get_task_graph(&workspace_id, &job_id).unwrap().edges, vec![edge(&b, &a)]);
+    }
+    
+    fn snapshot(created_at: i64, overall_score: f64, issue_count: u32) -> QualitySnapshot {
+        QualitySnapshot {
+            id: uuid::Uuid::new_v4().to_string(),
+            project_id: "project".to_string(),
+            commit_sha: Some(format!("sha-{}", created_at)),
+            overall_score,
+            complexity: 10.0,
+            maintainability: 80.0,
+            documentation_coverage: 60.0,
+            code_coverage: None,
+            test_count: 40,
+            issue_count,
+            lines_of_code: 1000 + created_at as u32,
+            created_at,
+        }
+    }
+    
+    #[test]
+    fn test_quality_trend_is_chronological_with_deltas() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let db_manager = Arc::new(WorkspaceDbManager::with_base_dir(base_dir.path()).unwrap());
+        let ops = WorkspaceDataOps::new(Arc::clone(&db_manager));
+        let workspace_id = db_manager.create_workspace("test-quality-trend", None).unwrap().id;
+        
+        // Recorded out of order; two share a timestamp and keep insertion order
+        let recorded = [
+            snapshot(300, 84.0, 2),
+            snapshot(100, 80.0, 0),
+            snapshot(200, 85.0, 0),
+            snapshot(300, 75.0, 3),
+            snapshot(400, 76.0, 3),
+        ];
+        for s in &recorded {
+            ops.record_quality_snapshot(&workspace_id, s).unwrap();
+        }
+        let mut other = snapshot(250, 10.0, 9);
+        other.project_id = "other".to_string();
+        ops.record_quality_snapshot(&workspace_id, &other).unwrap();
+        
+        let trend = ops.get_quality_trend(&workspace_id, "project", None, 10, DEFAULT_QUALITY_REGRESSION_PCT).unwrap();
+        let ids: Vec<&str> = trend.iter().map(|p| p.snapshot.id.as_str()).collect();
+        assert_eq!(ids, vec![&recorded[1].id, &recorded[2].id, &recorded[0].id, &recorded[3].id, &recorded[4].id]);
+        assert_eq!(trend[0].snapshot, recorded[1]);
+        assert!(trend[0].deltas.is_empty());
+        
+        let delta = |point: &QualityTrendPoint, metric: &str| point.deltas.iter().find(|d| d.metric == metric).cloned().unwrap();
+        assert_eq!(delta(&trend[1], "overall_score").change, 5.0);
+        assert!(!trend[1].regressed);
+        // 85 -> 84 is within 5%, but new issues from zero always regress
+        assert!(!delta(&trend[2], "overall_score").regression);
+        assert!(delta(&trend[2], "issue_count").regression);
+        assert!(trend[2].regressed);
+        // 84 -> 75 drops more than 5%
+        assert!(delta(&trend[3], "overall_score").regression);
+        assert!(!trend[4].regressed);
+        assert!(!trend.iter().flat_map(|p| &p.deltas).any(|d| d.metric == "code_coverage"));
+        assert!(!trend.iter().flat_map(|p| &p.deltas).any(|d| d.metric == "lines_of_code" && d.regression));
+        
+        // A window still compares its first point against the report before it
+        let latest = ops.get_quality_trend(&workspace_id, "project", None, 2, DEFAULT_QUALITY_REGRESSION_PCT).unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].snapshot.id, recorded[3].id);
+        assert_eq!(delta(&latest[0], "overall_score").previous, 84.0);
+        
+        let since = ops.get_quality_trend(&workspace_id, "project", Some(300), 10, DEFAULT_QUALITY_REGRESSION_PCT).unwrap();
+        assert_eq!(since.len(), 3);
+        assert_eq!(delta(&since[0], "overall_score").previous, 85.0);
+    }
+
+    #[test]
+    fn test_search_all_workspaces_merges_and_skips() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let db_manager = Arc::new(WorkspaceDbManager::with_base_dir(base_dir.path()).unwrap());
+        let ops = WorkspaceDataOps::new(Arc::clone(&db_manager));
+        let alpha = db_manager.create_workspace("test-global-search-alpha", None).unwrap();
+        let beta = db_manager.create_workspace("test-global-search-beta", None).unwrap();
+        
+        ops.create_knowledge(&alpha.id, CreateKnowledgeRequest {
+            knowledge_type: "decision".to_string(),
+            title: "Payments deploys".to_string(),
+            content: "Roll out payments behind a canary".to_string(),
+            tags: None,
+            file_refs: None,
+            source: None,
+            created_by: None,
+        }).unwrap();
+        let memory = ops.create_memory_long(&alpha.id, CreateMemoryLongRequest {
+            category: "constraint".to_string(),
+            title: "Ledger".to_string(),
+            content: "Payments must be idempotent".to_string(),
+            source: None,
+            confidence: None,
+        }).unwrap();
+        let session = ops.create_chat_session(&beta.id, CreateChatSessionRequest {
+            job_id: None,
+            title: Some("Retries".to_string()),
+            session_type: None,
+            model_id: None,
+        }).unwrap();
+        for content in ["How should payments retry?", "Unrelated build chatter"] {
+            ops.add_chat_message(&beta.id, CreateChatMessageRequest {
+                session_id: session.id.clone(),
+                role: "user".to_string(),
+                content: content.to_string(),
+                tool_calls_json: None,
+                tool_results_json: None,
+                model_id: None,
+                tokens_input: None,
+                tokens_output: None,
+                latency_ms: None,
+            }).unwrap();
+        }
+        
+        let mut missing = alpha.clone();
+        missing.id = "missing-workspace".to_string();
+        let workspaces = [alpha.clone(), beta.clone(), missing];
+        
+        let results = ops.search_all_workspaces(&workspaces, "payments", GlobalSearchSources::default(), 10);
+        assert_eq!(results.hits.len(), 3);
+        assert!(results.hits.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(results.hits.iter().all(|h| h.score > 0.0 && h.score < 1.0 && h.snippet.contains("**")));
+        let tagged: HashSet<(String, SearchSource)> = results.hits.iter()
+            .map(|h| (h.workspace_name.clone(), h.source))
+            .collect();
+        assert_eq!(tagged, HashSet::from([
+            (alpha.name.clone(), SearchSource::Knowledge),
+            (alpha.name.clone(), SearchSource::Memory),
+            (beta.name.clone(), SearchSource::Chat),
+        ]));
+        let chat = results.hits.iter().find(|h| h.source == SearchSource::Chat).unwrap();
+        assert_eq!(chat.workspace_id, beta.id);
+        assert_eq!(chat.title, "Retries");
+        assert_eq!(chat.session_id.as_deref(), Some(session.id.as_str()));
+        assert_eq!(results.skipped.len(), 1);
+        assert_eq!(results.skipped[0].workspace_id, "missing-workspace");
+        
+        // Per-source toggles and the overall limit
+        let memory_only = GlobalSearchSources { knowledge: false, memory: true, chat: false };
+        let results = ops.search_all_workspaces(&workspaces[..2], "payments", memory_only, 10);
+        assert_eq!(results.hits.len(), 1);
+        assert_eq!(results.hits[0].item_id, memory.id);
+        assert!(results.skipped.is_empty());
+        assert_eq!(ops.search_all_workspaces(&workspaces[..2], "payments", GlobalSearchSources::default(), 2).hits.len(), 2);
+        assert!(ops.search_all_workspaces(&workspaces, "*", GlobalSearchSources::default(), 10).hits.is_empty());
+        
+        // Searching neither caches connections nor touches recency
+        db_manager.close_workspace(&alpha.id).unwrap();
+        let accessed = db_manager.get_workspace(&alpha.id).unwrap().last_accessed_at;
+        let open = db_manager.open_workspace_count();
+        ops.search_all_workspaces(&workspaces[..1], "payments", GlobalSearchSources::default(), 10);
+        assert_eq!(db_manager.open_workspace_count(), open);
+        assert_eq!(db_manager.get_workspace(&alpha.id).unwrap().last_accessed_at, accessed);
+    }
+    
+    fn knowledge(title: &str, content: &str, tags: &[&str]) -> CreateKnowledgeRequest {
+        CreateKnowledgeRequest {
+            knowledge_type: "decision".to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            tags: Some(ids(tags)),
+            file_refs: None,
+            source: None,
+            created_by: None,
+        }
+    }
+    
+    #[test]
+    fn test_find_duplicate_knowledge_normalizes_case_and_whitespace() {
+        let (_base_dir, _, ops, workspace_id, _) = setup();
+        let first = ops.create_knowledge(&workspace_id, knowledge("Use SQLite", "One file per workspace", &[])).unwrap();
+        let second = ops.create_knowledge(&workspace_id, knowledge("use sqlite ", "One  file\nper WORKSPACE", &[])).unwrap();
+        ops.create_knowledge(&workspace_id, knowledge("Use SQLite", "Shared server database", &[])).unwrap();
+        
+        let groups = ops.find_duplicate_knowledge(&workspace_id).unwrap();
+        assert_eq!(groups.len(), 1);
+        let grouped: Vec<i64> = groups[0].entries.iter().map(|k| k.id).collect();
+        assert_eq!(grouped, vec![first.id, second.id]);
+        assert_eq!(groups[0].content_hash, knowledge_content_hash("USE SQLITE", "one file per workspace"));
+    }
+    
+    #[test]
+    fn test_merge_knowledge_unions_tags_and_deactivates_rest() {
+        let (_base_dir, _, ops, workspace_id, _) = setup();
+        let short = ops.create_knowledge(&workspace_id, knowledge("Retries", "Retry twice", &["network", "retry"])).unwrap();
+        let rich = ops.create_knowledge(&workspace_id, knowledge("Retries", "Retry twice with exponential backoff", &["backoff"])).unwrap();
+        
+        assert!(ops.merge_knowledge(&workspace_id, &[short.id]).is_err());
+        let merged = ops.merge_knowledge(&workspace_id, &[short.id, rich.id]).unwrap();
+        assert_eq!(merged.id, rich.id);
+        let tags: Vec<String> = serde_json::from_str(merged.tags_json.as_deref().unwrap()).unwrap();
+        assert_eq!(tags, ids(&["backoff", "network", "retry"]));
+        
+        let active: Vec<i64> = ops.list_knowledge(&workspace_id, None).unwrap().iter().map(|k| k.id).collect();
+        assert_eq!(active, vec![rich.id]);
+        let found: Vec<i64> = ops.search_knowledge(&workspace_id, "retry", false, None).unwrap().iter().map(|k| k.id).collect();
+        assert_eq!(found, vec![rich.id]);
+        assert!(ops.find_duplicate_knowledge(&workspace_id).unwrap().is_empty());
+        // Deactivated entries can't be merged again
+        assert!(ops.merge_knowledge(&workspace_id, &[short.id, rich.id]).is_err());
+    }
+    
+    #[test]
+    fn test_create_knowledge_auto_dedup() {
+        let (_base_dir, db_manager, ops, workspace_id, _) = setup();
+        let first = ops.create_knowledge(&workspace_id, knowledge("Style", "Use rustfmt", &["rust"])).unwrap();
+        let plain = ops.create_knowledge(&workspace_id, knowledge("Style", "Use rustfmt", &[])).unwrap();
+        assert_ne!(plain.id, first.id);
+        
+        db_manager.set_workspace_setting(&workspace_id, KNOWLEDGE_AUTO_DEDUP_SETTING, "true").unwrap();
+        let deduped = ops.create_knowledge(&workspace_id, knowledge("style", "use  rustfmt", &["fmt", "rust"])).unwrap();
+        assert_eq!(deduped.id, first.id);
+        assert_eq!(deduped.tags_json.as_deref(), Some(r#"["rust","fmt"]"#));
+        assert_eq!(ops.list_knowledge(&workspace_id, None).unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn test_update_knowledge_history_and_revert() {
+        let (_base_dir, _, ops, workspace_id, _) = setup();
+        let entry = ops.create_knowledge(&workspace_id, knowledge("Cache", "Cache responses in redis", &["cache"])).unwrap();
+        
+        ops.update_knowledge(&workspace_id, entry.id, UpdateKnowledgeRequest {
+            content: Some("Cache responses in memcached".to_string()),
+            ..Default::default()
+        }).unwrap();
+        let latest = ops.update_knowledge(&workspace_id, entry.id, UpdateKnowledgeRequest {
+            title: Some("Response cache".to_string()),
+            tags: Some(ids(&["cache", "perf"])),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(latest.content, "Cache responses in memcached");
+        
+        let history = ops.get_knowledge_history(&workspace_id, entry.id).unwrap();
+        let versions: Vec<(i64, &str, &str)> = history.iter()
+            .map(|v| (v.version, v.title.as_str(), v.content.as_str()))
+            .collect();
+        assert_eq!(versions, vec![
+            (2, "Cache", "Cache responses in memcached"),
+            (1, "Cache", "Cache responses in redis"),
+        ]);
+        
+        let reverted = ops.revert_knowledge(&workspace_id, entry.id, 1).unwrap();
+        assert_eq!((reverted.title.as_str(), reverted.content.as_str()), ("Cache", "Cache responses in redis"));
+        assert_eq!(reverted.tags_json.as_deref(), Some(r#"["cache"]"#));
+        // The reverted-away state is kept too
+        let history = ops.get_knowledge_history(&workspace_id, entry.id).unwrap();
+        assert_eq!((history[0].version, history[0].title.as_str()), (3, "Response cache"));
+        
+        // FTS follows the live row
+        let found = |query: &str| ops.search_knowledge(&workspace_id, query, false, None).unwrap().len();
+        assert_eq!(found("redis"), 1);
+        assert_eq!(found("memcached"), 0);
+        
+        assert!(ops.revert_knowledge(&workspace_id, entry.id, 9).is_err());
+        assert!(ops.get_knowledge_history(&workspace_id, entry.id + 100).is_err());
+    }
+    
+    #[test]
+    fn test_knowledge_versions_are_capped() {
+        let (_base_dir, _, ops, workspace_id, _) = setup();
+        let entry = ops.create_knowledge(&workspace_id, knowledge("Counter", "0", &[])).unwrap();
+        let updates = KNOWLEDGE_VERSION_LIMIT + 5;
+        for n in 1..=updates {
+            ops.update_knowledge(&workspace_id, entry.id, UpdateKnowledgeRequest {
+                content: Some(n.to_string()),
+                ..Default::default()
+            }).unwrap();
+        }
+        
+        let history = ops.get_knowledge_history(&workspace_id, entry.id).unwrap();
+        assert_eq!(history.len() as i64, KNOWLEDGE_VERSION_LIMIT);
+        assert_eq!(history[0].version, updates);
+        assert_eq!(history.last().unwrap().version, updates - KNOWLEDGE_VERSION_LIMIT + 1);
+    }
+    
+    #[test]
+    fn test_tag_facets_follow_create_update_and_delete() {
+        let (_base_dir, db_manager, ops, workspace_id, _) = setup();
+        let facet = |tag: &str, count: i64| TagFacet { tag: tag.to_string(), count };
+        
+        let api = ops.create_knowledge(&workspace_id, knowledge("API", "Version the REST API", &["api", "rest"])).unwrap();
+        let db = ops.create_knowledge(&workspace_id, knowledge("DB", "Migrate with versioned scripts", &[" api ", "sql"])).unwrap();
+        ops.create_knowledge(&workspace_id, knowledge("Untagged", "No tags here", &[])).unwrap();
+        assert_eq!(ops.get_tag_facets(&workspace_id).unwrap().knowledge, vec![facet("api", 2), facet("rest", 1), facet("sql", 1)]);
+        
+        ops.update_knowledge(&workspace_id, db.id, UpdateKnowledgeRequest {
+            tags: Some(ids(&["sql", "migrations"])),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(ops.get_tag_facets(&workspace_id).unwrap().knowledge, vec![facet("api", 1), facet("migrations", 1), facet("rest", 1), facet("sql", 1)]);
+        let tagged: Vec<i64> = ops.list_knowledge_by_tag(&workspace_id, "sql").unwrap().iter().map(|k| k.id).collect();
+        assert_eq!(tagged, vec![db.id]);
+        
+        let memory = ops.create_memory_long(&workspace_id, CreateMemoryLongRequest {
+            category: "learning".to_string(),
+            title: "Flaky tests".to_string(),
+            content: "Retry network tests once".to_string(),
+            source: None,
+            confidence: None,
+        }).unwrap();
+        {
+            let handle = db_manager.open_workspace(&workspace_id).unwrap();
+            let conn = &handle.lock().unwrap().conn;
+            conn.execute("DELETE FROM knowledge WHERE id = ?", params![api.id]).unwrap();
+            conn.execute("UPDATE memory_long SET tags_json = ? WHERE id = ?", params![r#"["testing","ci"]"#, memory.id]).unwrap();
+        }
+        let facets = ops.get_tag_facets(&workspace_id).unwrap();
+        assert_eq!(facets.knowledge, vec![facet("migrations", 1), facet("sql", 1)]);
+        assert_eq!(facets.memory, vec![facet("ci", 1), facet("testing", 1)]);
+        assert_eq!(ops.list_memory_long_by_tag(&workspace_id, "ci").unwrap().len(), 1);
+        assert!(ops.list_knowledge_by_tag(&workspace_id, "api").unwrap().is_empty());
+    }
 }
@@ -6,11 +6,45 @@
 // - Git integration for version control
 // - Docker orchestration for isolated environments
 
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::net::TcpListener;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::docker_manager::{ComposeServiceInfo, DockerManager};
+use crate::input_validation::{validate_branch_name, validate_resource_name};
+use crate::workspace_db::default_base_dir;
+
+/// Host ports handed out to branch containers
+const HOST_PORT_RANGE: RangeInclusive<u16> = 3000..=3999;
+
+const DEFAULT_SANDBOX_IMAGE: &str = "smartspec/sandbox-nodejs:latest";
+
+/// Compose files picked up from the workspace root, in Compose's own lookup order
+const WORKSPACE_COMPOSE_FILES: &[&str] = &[
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+/// Docker refuses memory limits below 6 MiB
+const MIN_MEMORY_LIMIT_BYTES: u64 = 6 * 1024 * 1024;
+
+/// Serializes read-modify-write of `workspaces.json`; managers are created
+/// per command, so this can't live on the instance
+static REGISTRY_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Keeps temp file names unique across threads writing the same file
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 // ============================================
 // Types and Structures
@@ -25,6 +59,8 @@ pub struct Workspace {
     pub updated_at: String,
     pub default_image: String,
     pub branches: HashMap<String, BranchConfig>,
+    #[serde(default)]
+    pub defaults: WorkspaceDefaults,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +74,32 @@ pub struct BranchConfig {
     pub parent_branch: Option<String>,
     pub created_at: String,
     pub last_active: String,
+    /// Overrides `WorkspaceDefaults::memory_limit` for this branch
+    #[serde(default)]
+    pub memory_limit: Option<String>,
+    /// Overrides `WorkspaceDefaults::cpu_limit` for this branch
+    #[serde(default)]
+    pub cpu_limit: Option<f32>,
+    /// Set when the branch runs as a compose project instead of a single container
+    #[serde(default)]
+    pub compose: Option<ComposeConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposeConfig {
+    /// Project name passed to `docker compose -p`
+    pub project: String,
+    pub file: PathBuf,
+    /// Whether SmartSpec generated `file` (and may rewrite it) rather than the user
+    pub generated: bool,
+    pub services: Vec<ComposeServiceState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComposeServiceState {
+    pub name: String,
+    pub container_id: Option<String>,
+    pub status: ContainerStatus,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,11 +139,24 @@ pub struct WorkspaceInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkspaceDefaults {
     pub image: String,
+    /// Docker memory size such as `"2g"` or `"512m"`; empty means unlimited
     pub memory_limit: String,
+    /// Number of CPUs, e.g. `1.5`; zero means unlimited
     pub cpu_limit: f32,
     pub auto_start: bool,
 }
 
+impl Default for WorkspaceDefaults {
+    fn default() -> Self {
+        Self {
+            image: DEFAULT_SANDBOX_IMAGE.to_string(),
+            memory_limit: "2g".to_string(),
+            cpu_limit: 2.0,
+            auto_start: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateWorkspaceRequest {
     pub name: String,
@@ -97,6 +172,51 @@ pub struct CreateBranchRequest {
     pub from_branch: String,
     pub image: Option<String>,
     pub auto_start: bool,
+    #[serde(default)]
+    pub memory_limit: Option<String>,
+    #[serde(default)]
+    pub cpu_limit: Option<f32>,
+}
+
+/// Result of merging one branch into another
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "status", content = "conflicts", rename_all = "snake_case")]
+pub enum MergeOutcome {
+    Clean,
+    /// The merge stopped and is still in progress; resolve and continue, or abort
+    Conflicts(Vec<ConflictedFile>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConflictedFile {
+    pub path: String,
+    pub kind: ConflictKind,
+    /// Marker regions still in the file; empty for delete/modify conflicts
+    pub markers: Vec<ConflictRegion>,
+}
+
+/// Unmerged states from `git status --porcelain`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    BothModified,
+    BothAdded,
+    BothDeleted,
+    AddedByUs,
+    AddedByThem,
+    DeletedByUs,
+    DeletedByThem,
+}
+
+/// One `<<<<<<<` ... `>>>>>>>` block; line numbers are 1-based and inclusive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConflictRegion {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub ours: String,
+    /// Only present with `merge.conflictStyle = diff3`
+    pub base: Option<String>,
+    pub theirs: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,13 +246,19 @@ pub struct WorkspaceManager {
 }
 
 impl WorkspaceManager {
-    /// Create a new WorkspaceManager instance
+    /// Create a new WorkspaceManager instance in the default data directory
     pub fn new() -> Result<Self, String> {
-        let home = dirs::home_dir().ok_or("Cannot find home directory")?;
+        Self::with_base_dir(default_base_dir().map_err(|e| e.to_string())?)
+    }
+    
+    /// Create a WorkspaceManager over the data directory `root`, the same
+    /// directory `WorkspaceDbManager` uses
+    pub fn with_base_dir(root: impl AsRef<Path>) -> Result<Self, String> {
+        let root = root.as_ref();
         
-        let base_dir = home.join("SmartSpec").join("workspaces");
-        let cache_dir = home.join("SmartSpec").join("cache");
-        let config_dir = home.join("SmartSpec").join("config");
+        let base_dir = root.join("workspaces");
+        let cache_dir = root.join("cache");
+        let config_dir = root.join("config");
         
         // Create directories if they don't exist
         fs::create_dir_all(&base_dir).map_err(|e| format!("Failed to create workspaces dir: {}", e))?;
@@ -168,6 +294,8 @@ impl WorkspaceManager {
     
     /// Create a new workspace
     pub fn create_workspace(&self, request: &CreateWorkspaceRequest) -> Result<Workspace, String> {
+        // The name becomes a directory under base_dir
+        validate_resource_name(&request.name, "Workspace name")?;
         let workspace_path = self.base_dir.join(&request.name);
         
         // Check if workspace already exists
@@ -196,7 +324,7 @@ impl WorkspaceManager {
         
         let now = chrono::Utc::now().to_rfc3339();
         let default_image = request.image.clone()
-            .unwrap_or_else(|| "smartspec/sandbox-nodejs:latest".to_string());
+            .unwrap_or_else(|| DEFAULT_SANDBOX_IMAGE.to_string());
         
         // Create workspace config
         let workspace = Workspace {
@@ -207,6 +335,10 @@ impl WorkspaceManager {
             updated_at: now.clone(),
             default_image: default_image.clone(),
             branches: HashMap::new(),
+            defaults: WorkspaceDefaults {
+                image: default_image.clone(),
+                ..WorkspaceDefaults::default()
+            },
         };
         
         // Save workspace config
@@ -229,12 +361,7 @@ impl WorkspaceManager {
         // Load workspace to get container info
         if let Ok(workspace) = self.load_workspace(name) {
             if delete_containers {
-                // Stop and remove all containers
-                for (_, branch) in workspace.branches {
-                    if let Some(container_id) = branch.container_id {
-                        let _ = self.remove_container(&container_id, true);
-                    }
-                }
+                self.remove_branch_containers(workspace);
             }
         }
         
@@ -248,6 +375,63 @@ impl WorkspaceManager {
         Ok(())
     }
     
+    /// Move a workspace directory to `destination` instead of deleting it.
+    /// Its containers are removed; the files can be brought back with
+    /// `restore_workspace`.
+    pub fn trash_workspace(&self, name: &str, destination: &Path) -> Result<(), String> {
+        validate_resource_name(name, "Workspace name")?;
+        let workspace_path = self.base_dir.join(name);
+        
+        if !workspace_path.exists() {
+            return Err(format!("Workspace '{}' not found", name));
+        }
+        if destination.exists() {
+            return Err(format!("Trash destination already exists: {}", destination.display()));
+        }
+        
+        if let Ok(workspace) = self.load_workspace(name) {
+            self.remove_branch_containers(workspace);
+        }
+        
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create trash directory: {}", e))?;
+        }
+        fs::rename(&workspace_path, destination)
+            .map_err(|e| format!("Failed to move workspace to trash: {}", e))?;
+        
+        self.unregister_workspace(name)
+    }
+    
+    /// Move a workspace trashed with `trash_workspace` back and register it
+    pub fn restore_workspace(&self, name: &str, source: &Path) -> Result<Workspace, String> {
+        validate_resource_name(name, "Workspace name")?;
+        let workspace_path = self.base_dir.join(name);
+        
+        if workspace_path.exists() {
+            return Err(format!("Workspace '{}' already exists", name));
+        }
+        
+        fs::rename(source, &workspace_path)
+            .map_err(|e| format!("Failed to restore workspace from trash: {}", e))?;
+        
+        let workspace = self.load_workspace(name)?;
+        self.register_workspace(&workspace)?;
+        Ok(workspace)
+    }
+    
+    /// Stop and remove the containers of every branch
+    fn remove_branch_containers(&self, workspace: Workspace) {
+        for (_, branch) in workspace.branches {
+            if let Some(container_id) = branch.container_id {
+                let _ = self.remove_container(&container_id, true);
+            }
+            if let Some(compose) = branch.compose {
+                let _ = DockerManager::compose_down(&compose.project, true);
+            }
+        }
+    }
+    
     /// List all workspaces
     pub fn list_workspaces(&self) -> Result<WorkspaceList, String> {
         let mut workspaces = Vec::new();
@@ -261,7 +445,12 @@ impl WorkspaceManager {
             
             if path.is_dir() {
                 if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if let Ok(workspace) = self.load_workspace(name) {
+                    if let Ok(mut workspace) = self.load_workspace(name) {
+                        // Keep the recorded statuses if Docker can't be reached
+                        if let Err(e) = self.reconcile_branches(&mut workspace) {
+                            eprintln!("Failed to reconcile containers for '{}': {}", name, e);
+                        }
+
                         let running = workspace.branches.values()
                             .filter(|b| b.status == ContainerStatus::Running)
                             .count();
@@ -283,15 +472,55 @@ impl WorkspaceManager {
         Ok(WorkspaceList { workspaces, total })
     }
     
+    /// Refresh every branch's container status from Docker and persist any changes
+    pub fn reconcile_status(&self, name: &str) -> Result<Workspace, String> {
+        let mut workspace = self.load_workspace(name)?;
+        self.reconcile_branches(&mut workspace)?;
+        Ok(workspace)
+    }
+
+    /// Returns whether any branch changed; the config is saved only if so
+    fn reconcile_branches(&self, workspace: &mut Workspace) -> Result<bool, String> {
+        let mut changed = false;
+
+        for branch in workspace.branches.values_mut() {
+            if branch.compose.is_some() {
+                changed |= Self::refresh_compose_services(branch)?;
+                continue;
+            }
+
+            let Some(container_id) = branch.container_id.clone() else {
+                continue;
+            };
+
+            let state = self.inspect_container_state(&container_id)?;
+            let status = status_from_docker_state(state.as_deref());
+            if state.is_none() {
+                // The container is gone, so the id can never be started again
+                branch.container_id = None;
+                branch.container_name = None;
+                changed = true;
+            }
+            if branch.status != status {
+                branch.status = status;
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.save_workspace_config(workspace)?;
+        }
+
+        Ok(changed)
+    }
+
     /// Load workspace configuration
     pub fn load_workspace(&self, name: &str) -> Result<Workspace, String> {
         let config_path = self.base_dir.join(name).join(".workspace").join("config.json");
         
-        let content = fs::read_to_string(&config_path)
-            .map_err(|e| format!("Failed to read workspace config: {}", e))?;
-        
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse workspace config: {}", e))
+        read_json_with_backup(&config_path)
+            .map_err(|e| format!("Failed to load workspace config: {}", e))?
+            .ok_or_else(|| format!("Failed to read workspace config: {:?} not found", config_path))
     }
     
     /// Save workspace configuration
@@ -301,7 +530,7 @@ impl WorkspaceManager {
         let content = serde_json::to_string_pretty(workspace)
             .map_err(|e| format!("Failed to serialize workspace config: {}", e))?;
         
-        fs::write(&config_path, content)
+        write_json_atomically(&config_path, &content)
             .map_err(|e| format!("Failed to write workspace config: {}", e))
     }
     
@@ -311,6 +540,10 @@ impl WorkspaceManager {
     
     /// Create a new branch with container
     pub fn create_branch(&self, request: &CreateBranchRequest) -> Result<BranchConfig, String> {
+        // Both names end up as git arguments
+        validate_branch_name(&request.branch_name)?;
+        validate_branch_name(&request.from_branch)?;
+        
         let mut workspace = self.load_workspace(&request.workspace)?;
         
         // Check if branch already exists
@@ -323,6 +556,14 @@ impl WorkspaceManager {
         
         let now = chrono::Utc::now().to_rfc3339();
         let image = request.image.clone().unwrap_or(workspace.default_image.clone());
+
+        // Reject bad overrides before touching git or Docker state
+        if let Some(memory) = &request.memory_limit {
+            parse_memory_limit(memory)?;
+        }
+        if let Some(cpus) = request.cpu_limit {
+            validate_cpu_limit(cpus)?;
+        }
         
         // Allocate ports
         let ports = self.allocate_ports(3)?;
@@ -350,6 +591,9 @@ impl WorkspaceManager {
             parent_branch: Some(request.from_branch.clone()),
             created_at: now.clone(),
             last_active: now,
+            memory_limit: request.memory_limit.clone(),
+            cpu_limit: request.cpu_limit,
+            compose: None,
         };
         
         // Create and optionally start container
@@ -402,18 +646,22 @@ impl WorkspaceManager {
         Ok(result)
     }
     
-    /// Merge branch
+    /// Merge branch. On conflicts the merge is left in progress and the source
+    /// branch is kept, even with `delete_source`.
     pub fn merge_branch(
         &self,
         workspace_name: &str,
         source_branch: &str,
         target_branch: &str,
         delete_source: bool,
-    ) -> Result<(), String> {
+    ) -> Result<MergeOutcome, String> {
         let mut workspace = self.load_workspace(workspace_name)?;
         
         // Merge git branches
-        self.merge_git_branches(&workspace.path, source_branch, target_branch)?;
+        let outcome = self.merge_git_branches(&workspace.path, source_branch, target_branch)?;
+        if outcome != MergeOutcome::Clean {
+            return Ok(outcome);
+        }
         
         if delete_source {
             // Stop and remove container
@@ -422,6 +670,9 @@ impl WorkspaceManager {
                     let _ = self.stop_container(container_id);
                     let _ = self.remove_container(container_id, false);
                 }
+                if let Some(compose) = &branch.compose {
+                    let _ = DockerManager::compose_down(&compose.project, true);
+                }
             }
             
             // Delete git branch
@@ -434,9 +685,174 @@ impl WorkspaceManager {
         workspace.updated_at = chrono::Utc::now().to_rfc3339();
         self.save_workspace_config(&workspace)?;
         
-        Ok(())
+        Ok(MergeOutcome::Clean)
+    }
+    
+    /// Abandon an in-progress merge, restoring the target branch
+    pub fn abort_merge(&self, workspace_name: &str) -> Result<(), String> {
+        let workspace = self.load_workspace(workspace_name)?;
+        git_merge_abort(&workspace.path)
     }
     
+    /// Commit an in-progress merge once its conflicts are resolved
+    pub fn continue_merge(&self, workspace_name: &str) -> Result<MergeOutcome, String> {
+        let workspace = self.load_workspace(workspace_name)?;
+        git_merge_continue(&workspace.path)
+    }
+    
+    // ========================================
+    // Compose Operations
+    // ========================================
+
+    /// Run a branch as a compose project. `compose_file` (relative to the
+    /// workspace) wins, then a compose file in the workspace root, and
+    /// otherwise an app + Postgres + Redis stack is generated.
+    pub fn enable_compose(
+        &self,
+        workspace_name: &str,
+        branch_name: &str,
+        compose_file: Option<PathBuf>,
+    ) -> Result<ComposeConfig, String> {
+        let mut workspace = self.load_workspace(workspace_name)?;
+        let branch = workspace.branches.get(branch_name)
+            .ok_or(format!("Branch '{}' not found", branch_name))?;
+
+        let user_file = compose_file
+            .map(|file| workspace.path.join(file))
+            .or_else(|| {
+                WORKSPACE_COMPOSE_FILES.iter()
+                    .map(|name| workspace.path.join(name))
+                    .find(|path| path.is_file())
+            });
+
+        let (file, generated) = match user_file {
+            Some(file) if file.is_file() => (file, false),
+            Some(file) => return Err(format!("Compose file not found: {}", file.display())),
+            None => (self.write_generated_compose(&workspace, branch)?, true),
+        };
+
+        let compose = ComposeConfig {
+            project: compose_project_name(&workspace.name, branch_name),
+            file,
+            generated,
+            services: Vec::new(),
+        };
+
+        if let Some(branch) = workspace.branches.get_mut(branch_name) {
+            branch.compose = Some(compose.clone());
+        }
+        workspace.updated_at = chrono::Utc::now().to_rfc3339();
+        self.save_workspace_config(&workspace)?;
+
+        Ok(compose)
+    }
+
+    /// Start the branch's compose project, replacing its single container if it had one
+    pub fn compose_up(&self, workspace_name: &str, branch_name: &str) -> Result<BranchConfig, String> {
+        let mut workspace = self.load_workspace(workspace_name)?;
+        if workspace.branches.get(branch_name).ok_or(format!("Branch '{}' not found", branch_name))?.compose.is_none() {
+            self.enable_compose(workspace_name, branch_name, None)?;
+            workspace = self.load_workspace(workspace_name)?;
+        }
+
+        let branch = workspace.branches.get(branch_name)
+            .ok_or(format!("Branch '{}' not found", branch_name))?
+            .clone();
+        let compose = branch.compose.clone().ok_or("Compose is not enabled for this branch")?;
+
+        // The single container uses the same host ports as the app service
+        if let Some(container_id) = &branch.container_id {
+            let _ = self.remove_container(container_id, true);
+        }
+        if compose.generated {
+            self.write_generated_compose(&workspace, &branch)?;
+        }
+
+        DockerManager::compose_up(&compose.project, &compose.file)?;
+
+        let branch = workspace.branches.get_mut(branch_name)
+            .ok_or(format!("Branch '{}' not found", branch_name))?;
+        branch.container_id = None;
+        branch.container_name = None;
+        Self::refresh_compose_services(branch)?;
+        branch.last_active = chrono::Utc::now().to_rfc3339();
+        let result = branch.clone();
+
+        workspace.updated_at = chrono::Utc::now().to_rfc3339();
+        self.save_workspace_config(&workspace)?;
+
+        Ok(result)
+    }
+
+    /// Stop and remove the branch's compose containers, keeping named volumes
+    pub fn compose_down(&self, workspace_name: &str, branch_name: &str) -> Result<BranchConfig, String> {
+        let mut workspace = self.load_workspace(workspace_name)?;
+        let branch = workspace.branches.get_mut(branch_name)
+            .ok_or(format!("Branch '{}' not found", branch_name))?;
+        let compose = branch.compose.as_mut().ok_or("Compose is not enabled for this branch")?;
+
+        DockerManager::compose_down(&compose.project, false)?;
+        compose.services.clear();
+        branch.status = ContainerStatus::None;
+        let result = branch.clone();
+
+        workspace.updated_at = chrono::Utc::now().to_rfc3339();
+        self.save_workspace_config(&workspace)?;
+
+        Ok(result)
+    }
+
+    /// Current services of the branch's compose project
+    pub fn compose_ps(&self, workspace_name: &str, branch_name: &str) -> Result<Vec<ComposeServiceState>, String> {
+        let mut workspace = self.load_workspace(workspace_name)?;
+        let branch = workspace.branches.get_mut(branch_name)
+            .ok_or(format!("Branch '{}' not found", branch_name))?;
+        if branch.compose.is_none() {
+            return Err("Compose is not enabled for this branch".to_string());
+        }
+
+        let changed = Self::refresh_compose_services(branch)?;
+        let services = branch.compose.as_ref().map(|c| c.services.clone()).unwrap_or_default();
+        if changed {
+            self.save_workspace_config(&workspace)?;
+        }
+
+        Ok(services)
+    }
+
+    /// Update a compose branch's services and overall status from `docker compose ps`
+    fn refresh_compose_services(branch: &mut BranchConfig) -> Result<bool, String> {
+        let Some(compose) = branch.compose.as_mut() else {
+            return Ok(false);
+        };
+
+        let services = compose_service_states(&DockerManager::compose_ps(&compose.project)?);
+        let status = compose_branch_status(&services);
+
+        let changed = branch.status != status
+            || compose.services.len() != services.len()
+            || compose.services.iter().zip(&services).any(|(a, b)| {
+                a.name != b.name || a.container_id != b.container_id || a.status != b.status
+            });
+
+        compose.services = services;
+        branch.status = status;
+        Ok(changed)
+    }
+
+    fn write_generated_compose(&self, workspace: &Workspace, branch: &BranchConfig) -> Result<PathBuf, String> {
+        let dir = workspace.path.join(".workspace").join("compose");
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create compose directory: {}", e))?;
+
+        let path = dir.join(format!("{}.yml", sanitize_name(&branch.name)));
+        let content = generate_compose_file(workspace, branch, &self.cache_dir)?;
+        fs::write(&path, content)
+            .map_err(|e| format!("Failed to write compose file: {}", e))?;
+
+        Ok(path)
+    }
+
     // ========================================
     // Container Operations
     // ========================================
@@ -448,6 +864,31 @@ impl WorkspaceManager {
         branch: &BranchConfig,
         container_name: &str,
     ) -> Result<String, String> {
+        let args = self.container_create_args(workspace, branch, container_name)?;
+
+        let output = Command::new("docker")
+            .args(&args)
+            .output()
+            .map_err(|e| format!("Failed to create container: {}", e))?;
+        
+        if !output.status.success() {
+            return Err(format!(
+                "Docker create failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        
+        let container_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(container_id)
+    }
+
+    /// Arguments for `docker create`, including validated resource limits
+    fn container_create_args(
+        &self,
+        workspace: &Workspace,
+        branch: &BranchConfig,
+        container_name: &str,
+    ) -> Result<Vec<String>, String> {
         let mut args = vec![
             "create".to_string(),
             "--name".to_string(),
@@ -475,24 +916,27 @@ impl WorkspaceManager {
         // Set working directory
         args.push("-w".to_string());
         args.push("/workspace/project".to_string());
+
+        // Resource limits; branch overrides win over workspace defaults
+        let memory = branch.memory_limit.as_deref().unwrap_or(&workspace.defaults.memory_limit);
+        if let Some(bytes) = parse_memory_limit(memory)? {
+            // Swap equal to memory means the container gets no swap at all
+            args.push("--memory".to_string());
+            args.push(bytes.to_string());
+            args.push("--memory-swap".to_string());
+            args.push(bytes.to_string());
+        }
+
+        let cpus = branch.cpu_limit.unwrap_or(workspace.defaults.cpu_limit);
+        if validate_cpu_limit(cpus)? {
+            args.push("--cpus".to_string());
+            args.push(cpus.to_string());
+        }
         
         // Add image
         args.push(branch.image.clone());
-        
-        let output = Command::new("docker")
-            .args(&args)
-            .output()
-            .map_err(|e| format!("Failed to create container: {}", e))?;
-        
-        if !output.status.success() {
-            return Err(format!(
-                "Docker create failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
-        
-        let container_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        Ok(container_id)
+
+        Ok(args)
     }
     
     /// Start a container
@@ -529,6 +973,25 @@ impl WorkspaceManager {
         Ok(())
     }
     
+    /// Docker's `State.Status` for a container, or `None` if it no longer exists
+    fn inspect_container_state(&self, container_id: &str) -> Result<Option<String>, String> {
+        let output = Command::new("docker")
+            .args(["inspect", "-f", "{{.State.Status}}", container_id])
+            .output()
+            .map_err(|e| format!("Failed to inspect container: {}", e))?;
+
+        if output.status.success() {
+            return Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()));
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("No such object") || stderr.contains("No such container") {
+            Ok(None)
+        } else {
+            Err(format!("Docker inspect failed: {}", stderr))
+        }
+    }
+
     /// Remove a container
     fn remove_container(&self, container_id: &str, force: bool) -> Result<(), String> {
         let mut args = vec!["rm"];
@@ -639,25 +1102,8 @@ impl WorkspaceManager {
         Ok(())
     }
     
-    fn merge_git_branches(&self, path: &PathBuf, source: &str, target: &str) -> Result<(), String> {
-        // Checkout target branch
-        self.checkout_git_branch(path, target)?;
-        
-        // Merge source into target
-        let output = Command::new("git")
-            .args(["merge", source, "--no-ff", "-m", &format!("Merge {} into {}", source, target)])
-            .current_dir(path)
-            .output()
-            .map_err(|e| format!("Failed to merge branches: {}", e))?;
-        
-        if !output.status.success() {
-            return Err(format!(
-                "Git merge failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
-        
-        Ok(())
+    fn merge_git_branches(&self, path: &PathBuf, source: &str, target: &str) -> Result<MergeOutcome, String> {
+        git_merge(path, source, target)
     }
     
     fn delete_git_branch(&self, path: &PathBuf, branch: &str) -> Result<(), String> {
@@ -687,56 +1133,538 @@ impl WorkspaceManager {
         format!("smartspec-{}-{}-{}", workspace, sanitized_branch, short_hash)
     }
     
+    /// Allocate a contiguous block of host ports that are free on this machine
+    /// and not already assigned to any branch in any workspace
     fn allocate_ports(&self, count: usize) -> Result<Vec<u16>, String> {
-        // Simple port allocation - in production, check for availability
-        let base_port = 3000 + (rand::random::<u16>() % 1000);
-        Ok((0..count).map(|i| base_port + i as u16).collect())
+        let reserved = self.reserved_ports();
+        let span = HOST_PORT_RANGE.end() - HOST_PORT_RANGE.start() + 1;
+        let first = HOST_PORT_RANGE.start() + rand::random::<u16>() % span;
+        find_free_port_block(count, HOST_PORT_RANGE, &reserved, first)
+    }
+
+    /// Host ports recorded in branch configs across all workspaces
+    fn reserved_ports(&self) -> HashSet<u16> {
+        let Ok(entries) = fs::read_dir(&self.base_dir) else {
+            return HashSet::new();
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str().map(String::from))
+            .filter_map(|name| self.load_workspace(&name).ok())
+            .flat_map(|workspace| workspace.branches.into_values())
+            .flat_map(|branch| branch.ports.into_iter().map(|port| port.host))
+            .collect()
     }
     
-    fn register_workspace(&self, workspace: &Workspace) -> Result<(), String> {
+    /// Entries of the `workspaces.json` registry, name to path
+    pub fn registered_workspaces(&self) -> Result<HashMap<String, String>, String> {
         let registry_path = self.config_dir.join("workspaces.json");
         
-        let mut registry: HashMap<String, String> = if registry_path.exists() {
-            let content = fs::read_to_string(&registry_path)
-                .map_err(|e| format!("Failed to read registry: {}", e))?;
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            HashMap::new()
-        };
+        match read_json_with_backup(&registry_path) {
+            Ok(registry) => Ok(registry.unwrap_or_default()),
+            Err(e) => {
+                // Neither copy is usable; start over rather than refuse to run
+                eprintln!("Discarding unreadable workspace registry: {}", e);
+                Ok(HashMap::new())
+            }
+        }
+    }
+    
+    fn write_registry(&self, registry: &HashMap<String, String>) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(registry)
+            .map_err(|e| format!("Failed to serialize registry: {}", e))?;
+        
+        write_json_atomically(&self.config_dir.join("workspaces.json"), &content)
+            .map_err(|e| format!("Failed to write registry: {}", e))
+    }
+    
+    /// Names of the workspace directories that hold a workspace config,
+    /// whether or not the registry lists them
+    pub fn workspace_names(&self) -> Result<Vec<String>, String> {
+        let entries = fs::read_dir(&self.base_dir)
+            .map_err(|e| format!("Failed to read workspaces directory: {}", e))?;
+        
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().join(".workspace").join("config.json").is_file())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+    
+    pub fn register_workspace(&self, workspace: &Workspace) -> Result<(), String> {
+        let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut registry = self.registered_workspaces()?;
         
         registry.insert(
             workspace.name.clone(),
             workspace.path.to_string_lossy().to_string(),
         );
         
-        let content = serde_json::to_string_pretty(&registry)
-            .map_err(|e| format!("Failed to serialize registry: {}", e))?;
-        
-        fs::write(&registry_path, content)
-            .map_err(|e| format!("Failed to write registry: {}", e))
+        self.write_registry(&registry)
     }
     
-    fn unregister_workspace(&self, name: &str) -> Result<(), String> {
-        let registry_path = self.config_dir.join("workspaces.json");
+    pub fn unregister_workspace(&self, name: &str) -> Result<(), String> {
+        let _guard = REGISTRY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut registry = self.registered_workspaces()?;
         
-        if !registry_path.exists() {
+        if registry.remove(name).is_none() {
             return Ok(());
         }
         
-        let content = fs::read_to_string(&registry_path)
-            .map_err(|e| format!("Failed to read registry: {}", e))?;
-        
-        let mut registry: HashMap<String, String> = serde_json::from_str(&content)
-            .unwrap_or_default();
-        
-        registry.remove(name);
-        
-        let content = serde_json::to_string_pretty(&registry)
-            .map_err(|e| format!("Failed to serialize registry: {}", e))?;
-        
-        fs::write(&registry_path, content)
-            .map_err(|e| format!("Failed to write registry: {}", e))
+        self.write_registry(&registry)
+    }
+}
+
+// ============================================
+// Config Files
+// ============================================
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Replace `path` without ever leaving it half-written: the content goes to a
+/// synced temp file in the same directory, which is renamed over the target.
+/// The previous version is kept as `<file>.bak` if it still parses.
+fn write_json_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)?;
+
+    let previous_is_valid = fs::read_to_string(path)
+        .ok()
+        .is_some_and(|previous| serde_json::from_str::<serde_json::Value>(&previous).is_ok());
+    if previous_is_valid {
+        fs::copy(path, backup_path(path))?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = path.with_file_name(tmp_name);
+    let result = (|| {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+
+    // Persist the rename itself; not possible on every platform
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Read a JSON file written by `write_json_atomically`, falling back to its
+/// `.bak` when the primary is corrupt. `None` if the file doesn't exist.
+fn read_json_with_backup<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    let error = match serde_json::from_str(&content) {
+        Ok(value) => return Ok(Some(value)),
+        Err(e) => format!("{:?}: {}", path, e),
+    };
+
+    let backup = fs::read_to_string(backup_path(path)).map_err(|_| error.clone())?;
+    let value = serde_json::from_str(&backup).map_err(|_| error.clone())?;
+    eprintln!("Recovered {:?} from backup after: {}", path, error);
+    Ok(Some(value))
+}
+
+// ============================================
+// Resource Limits
+// ============================================
+
+/// Parse a Docker memory size (`"2g"`, `"512m"`, `"1.5GiB"`) into bytes.
+/// An empty string means no limit.
+fn parse_memory_limit(value: &str) -> Result<Option<u64>, String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = || format!(
+        "Invalid memory limit '{}': expected a size like \"512m\" or \"2g\"",
+        value
+    );
+
+    let split = trimmed.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => return Err(invalid()),
+    };
+
+    let bytes = (number * multiplier as f64) as u64;
+    if bytes < MIN_MEMORY_LIMIT_BYTES {
+        return Err(format!("Invalid memory limit '{}': Docker requires at least 6m", value));
+    }
+
+    Ok(Some(bytes))
+}
+
+/// Check a CPU limit; returns whether it should be applied (zero means unlimited)
+fn validate_cpu_limit(cpus: f32) -> Result<bool, String> {
+    if !cpus.is_finite() || cpus < 0.0 {
+        return Err(format!("Invalid CPU limit '{}': expected a positive number like 1.5", cpus));
     }
+    Ok(cpus > 0.0)
+}
+
+// ============================================
+// Compose Files
+// ============================================
+
+/// Lowercase letters, digits and dashes, as Compose requires for project names
+fn sanitize_name(name: &str) -> String {
+    let sanitized: String = name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '-' })
+        .collect();
+    sanitized.trim_matches('-').to_string()
+}
+
+fn compose_project_name(workspace: &str, branch: &str) -> String {
+    format!("smartspec-{}-{}", sanitize_name(workspace), sanitize_name(branch))
+}
+
+/// YAML-safe scalar; JSON strings are valid YAML
+fn yaml_str(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// App service mirroring `create_container`, plus Postgres and Redis
+fn generate_compose_file(workspace: &Workspace, branch: &BranchConfig, cache_dir: &Path) -> Result<String, String> {
+    let mut app = vec![
+        format!("    image: {}", yaml_str(&branch.image)),
+        "    working_dir: /workspace/project".to_string(),
+        "    tty: true".to_string(),
+        "    stdin_open: true".to_string(),
+        "    volumes:".to_string(),
+        format!("      - {}", yaml_str(&format!("{}:/workspace/project", workspace.path.to_string_lossy()))),
+        format!("      - {}", yaml_str(&format!("{}:/home/sandbox/.npm", cache_dir.join("npm").to_string_lossy()))),
+        format!("      - {}", yaml_str(&format!("{}:/home/sandbox/.local/share/pnpm", cache_dir.join("pnpm").to_string_lossy()))),
+    ];
+
+    if !branch.ports.is_empty() {
+        app.push("    ports:".to_string());
+        for port in &branch.ports {
+            app.push(format!("      - \"{}:{}/{}\"", port.host, port.container, port.protocol));
+        }
+    }
+
+    let memory = branch.memory_limit.as_deref().unwrap_or(&workspace.defaults.memory_limit);
+    if let Some(bytes) = parse_memory_limit(memory)? {
+        app.push(format!("    mem_limit: {}", bytes));
+        app.push(format!("    memswap_limit: {}", bytes));
+    }
+    let cpus = branch.cpu_limit.unwrap_or(workspace.defaults.cpu_limit);
+    if validate_cpu_limit(cpus)? {
+        app.push(format!("    cpus: {}", cpus));
+    }
+
+    Ok(format!(
+        r#"# Generated by SmartSpec for branch {branch}. Add a compose file to the
+# workspace root to use your own services instead.
+services:
+  app:
+{app}
+    environment:
+      DATABASE_URL: "postgres://smartspec:smartspec@db:5432/app"
+      REDIS_URL: "redis://cache:6379"
+    depends_on:
+      - db
+      - cache
+  db:
+    image: "postgres:16-alpine"
+    environment:
+      POSTGRES_USER: "smartspec"
+      POSTGRES_PASSWORD: "smartspec"
+      POSTGRES_DB: "app"
+    volumes:
+      - "db-data:/var/lib/postgresql/data"
+  cache:
+    image: "redis:7-alpine"
+volumes:
+  db-data:
+"#,
+        branch = yaml_str(&branch.name),
+        app = app.join("\n"),
+    ))
+}
+
+fn compose_service_states(services: &[ComposeServiceInfo]) -> Vec<ComposeServiceState> {
+    let mut states: Vec<ComposeServiceState> = services.iter()
+        .map(|service| ComposeServiceState {
+            name: service.service.clone(),
+            container_id: Some(service.container_id.clone()).filter(|id| !id.is_empty()),
+            status: status_from_docker_state(Some(&service.state)),
+        })
+        .collect();
+    states.sort_by(|a, b| a.name.cmp(&b.name));
+    states
+}
+
+/// A compose branch is running if any service is, and stopped if all have stopped
+fn compose_branch_status(services: &[ComposeServiceState]) -> ContainerStatus {
+    if services.is_empty() {
+        ContainerStatus::None
+    } else if services.iter().any(|s| s.status == ContainerStatus::Running) {
+        ContainerStatus::Running
+    } else if services.iter().all(|s| s.status == ContainerStatus::Created) {
+        ContainerStatus::Created
+    } else {
+        ContainerStatus::Stopped
+    }
+}
+
+// ============================================
+// Git Merge
+// ============================================
+
+/// Check out `target` and merge `source` into it. A merge that stops on
+/// conflicts is reported as `MergeOutcome::Conflicts`; any other failure
+/// (dirty tree, unknown branch) is an error.
+pub fn git_merge(path: &Path, source: &str, target: &str) -> Result<MergeOutcome, String> {
+    run_git(path, &["checkout", target])?;
+    
+    let output = Command::new("git")
+        .args(["merge", source, "--no-ff", "-m", &format!("Merge {} into {}", source, target)])
+        .current_dir(path)
+        .output()
+        .map_err(|e| format!("Failed to merge branches: {}", e))?;
+    
+    if output.status.success() {
+        return Ok(MergeOutcome::Clean);
+    }
+    
+    let conflicts = conflicted_files(path)?;
+    if conflicts.is_empty() {
+        return Err(format!(
+            "Git merge failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(MergeOutcome::Conflicts(conflicts))
+}
+
+pub fn git_merge_abort(path: &Path) -> Result<(), String> {
+    run_git(path, &["merge", "--abort"]).map(|_| ())
+}
+
+/// Stage resolved files and commit the merge. Files that still contain
+/// conflict markers are returned as conflicts and nothing is committed.
+pub fn git_merge_continue(path: &Path) -> Result<MergeOutcome, String> {
+    if run_git(path, &["rev-parse", "-q", "--verify", "MERGE_HEAD"]).is_err() {
+        return Err("No merge in progress".to_string());
+    }
+    
+    let conflicts = conflicted_files(path)?;
+    let unresolved: Vec<ConflictedFile> = conflicts.iter()
+        .filter(|file| !file.markers.is_empty())
+        .cloned()
+        .collect();
+    if !unresolved.is_empty() {
+        return Ok(MergeOutcome::Conflicts(unresolved));
+    }
+    
+    if !conflicts.is_empty() {
+        // `add -A` also stages deletions chosen during resolution
+        let mut args = vec!["add", "-A", "--"];
+        args.extend(conflicts.iter().map(|file| file.path.as_str()));
+        run_git(path, &args)?;
+    }
+    
+    run_git(path, &["commit", "--no-edit"]).map(|_| MergeOutcome::Clean)
+}
+
+fn run_git(path: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(format!(
+            "Git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn conflicted_files(path: &Path) -> Result<Vec<ConflictedFile>, String> {
+    let status = run_git(path, &["status", "--porcelain"])?;
+    
+    Ok(parse_unmerged_paths(&status)
+        .into_iter()
+        .map(|(file, kind)| {
+            let markers = fs::read(path.join(&file))
+                .map(|bytes| parse_conflict_markers(&String::from_utf8_lossy(&bytes)))
+                .unwrap_or_default();
+            ConflictedFile { path: file, kind, markers }
+        })
+        .collect())
+}
+
+/// Unmerged entries from `git status --porcelain` (v1) output
+fn parse_unmerged_paths(status: &str) -> Vec<(String, ConflictKind)> {
+    status.lines()
+        .filter_map(|line| {
+            let kind = match line.get(..2)? {
+                "UU" => ConflictKind::BothModified,
+                "AA" => ConflictKind::BothAdded,
+                "DD" => ConflictKind::BothDeleted,
+                "AU" => ConflictKind::AddedByUs,
+                "UA" => ConflictKind::AddedByThem,
+                "DU" => ConflictKind::DeletedByUs,
+                "UD" => ConflictKind::DeletedByThem,
+                _ => return None,
+            };
+            let file = line.get(3..)?;
+            // Paths with special characters are quoted C-style
+            let file = file.strip_prefix('"')
+                .and_then(|f| f.strip_suffix('"'))
+                .map(|f| f.replace("\\\"", "\"").replace("\\\\", "\\"))
+                .unwrap_or_else(|| file.to_string());
+            Some((file, kind))
+        })
+        .collect()
+}
+
+fn parse_conflict_markers(content: &str) -> Vec<ConflictRegion> {
+    #[derive(PartialEq)]
+    enum Section { Ours, Base, Theirs }
+    
+    let is_marker = |line: &str, marker: &str| {
+        line.strip_prefix(marker)
+            .map(|rest| rest.is_empty() || rest.starts_with(' '))
+            .unwrap_or(false)
+    };
+    
+    let mut regions = Vec::new();
+    let mut current: Option<(ConflictRegion, Section)> = None;
+    
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        match current.as_mut() {
+            None => {
+                if is_marker(line, "<<<<<<<") {
+                    current = Some((
+                        ConflictRegion {
+                            start_line: line_number,
+                            end_line: line_number,
+                            ours: String::new(),
+                            base: None,
+                            theirs: String::new(),
+                        },
+                        Section::Ours,
+                    ));
+                }
+            }
+            Some((region, section)) => {
+                if *section == Section::Ours && is_marker(line, "|||||||") {
+                    region.base = Some(String::new());
+                    *section = Section::Base;
+                } else if *section != Section::Theirs && line == "=======" {
+                    *section = Section::Theirs;
+                } else if *section == Section::Theirs && is_marker(line, ">>>>>>>") {
+                    region.end_line = line_number;
+                    if let Some((region, _)) = current.take() {
+                        regions.push(region);
+                    }
+                } else {
+                    let text = match section {
+                        Section::Ours => &mut region.ours,
+                        Section::Base => region.base.get_or_insert_with(String::new),
+                        Section::Theirs => &mut region.theirs,
+                    };
+                    text.push_str(line);
+                    text.push('\n');
+                }
+            }
+        }
+    }
+    
+    regions
+}
+
+// ============================================
+// Status Reconciliation
+// ============================================
+
+/// Map Docker's `State.Status` (`None` when the container is gone) to a branch status
+fn status_from_docker_state(state: Option<&str>) -> ContainerStatus {
+    match state {
+        None => ContainerStatus::None,
+        Some("running") | Some("restarting") => ContainerStatus::Running,
+        Some("created") => ContainerStatus::Created,
+        Some("removing") | Some("dead") => ContainerStatus::Removed,
+        Some(_) => ContainerStatus::Stopped,
+    }
+}
+
+// ============================================
+// Port Allocation
+// ============================================
+
+/// Find `count` consecutive ports in `range`, trying block starts from `first`
+/// and wrapping around. A port qualifies if it is not `reserved` and can be
+/// bound on 127.0.0.1 right now.
+fn find_free_port_block(
+    count: usize,
+    range: RangeInclusive<u16>,
+    reserved: &HashSet<u16>,
+    first: u16,
+) -> Result<Vec<u16>, String> {
+    let (start, end) = (*range.start() as usize, *range.end() as usize);
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    if end < start || end - start + 1 < count {
+        return Err(format!("Port range {}-{} cannot fit {} ports", start, end, count));
+    }
+
+    let last_base = end + 1 - count;
+    let bases = last_base - start + 1;
+    let offset = (first as usize).clamp(start, last_base) - start;
+
+    for i in 0..bases {
+        let base = start + (offset + i) % bases;
+        let block: Vec<u16> = (base..base + count).map(|p| p as u16).collect();
+        let free = block.iter().all(|port| {
+            !reserved.contains(port) && TcpListener::bind(("127.0.0.1", *port)).is_ok()
+        });
+        if free {
+            return Ok(block);
+        }
+    }
+
+    Err(format!(
+        "No free block of {} ports available between {} and {}",
+        count, start, end
+    ))
 }
 
 // ============================================
@@ -744,3 +1672,338 @@ impl WorkspaceManager {
 // ============================================
 // dirs = "5"
 // rand = "0.8"
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An OS-assigned port that stays bound for the listener's lifetime
+    fn held_port() -> (TcpListener, u16) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, port)
+    }
+
+    fn test_manager(root: &std::path::Path) -> WorkspaceManager {
+        WorkspaceManager {
+            base_dir: root.join("workspaces"),
+            cache_dir: root.join("cache"),
+            config_dir: root.join("config"),
+        }
+    }
+
+    fn test_workspace(root: &std::path::Path) -> (Workspace, BranchConfig) {
+        let workspace = Workspace {
+            name: "demo".to_string(),
+            path: root.join("workspaces/demo"),
+            repository: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            default_image: DEFAULT_SANDBOX_IMAGE.to_string(),
+            branches: HashMap::new(),
+            defaults: WorkspaceDefaults {
+                memory_limit: "2g".to_string(),
+                cpu_limit: 1.5,
+                ..WorkspaceDefaults::default()
+            },
+        };
+        let branch = BranchConfig {
+            name: "feature".to_string(),
+            container_id: None,
+            container_name: None,
+            image: DEFAULT_SANDBOX_IMAGE.to_string(),
+            ports: vec![],
+            status: ContainerStatus::None,
+            parent_branch: None,
+            created_at: String::new(),
+            last_active: String::new(),
+            memory_limit: None,
+            cpu_limit: None,
+            compose: None,
+        };
+        (workspace, branch)
+    }
+
+    fn flag<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+        args.iter().position(|a| a == name).map(|i| args[i + 1].as_str())
+    }
+
+    #[test]
+    fn test_create_args_include_resource_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = test_manager(dir.path());
+        let (workspace, mut branch) = test_workspace(dir.path());
+
+        let args = manager.container_create_args(&workspace, &branch, "c1").unwrap();
+        assert_eq!(flag(&args, "--memory"), Some("2147483648"));
+        assert_eq!(flag(&args, "--memory-swap"), Some("2147483648"));
+        assert_eq!(flag(&args, "--cpus"), Some("1.5"));
+        assert_eq!(args.last().unwrap(), DEFAULT_SANDBOX_IMAGE);
+
+        // Branch overrides replace the workspace defaults
+        branch.memory_limit = Some("512m".to_string());
+        branch.cpu_limit = Some(0.0);
+        let args = manager.container_create_args(&workspace, &branch, "c1").unwrap();
+        assert_eq!(flag(&args, "--memory"), Some("536870912"));
+        assert_eq!(flag(&args, "--cpus"), None);
+
+        branch.memory_limit = Some("lots".to_string());
+        let err = manager.container_create_args(&workspace, &branch, "c1").unwrap_err();
+        assert!(err.contains("Invalid memory limit 'lots'"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_memory_limit() {
+        assert_eq!(parse_memory_limit("").unwrap(), None);
+        assert_eq!(parse_memory_limit("2g").unwrap(), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_memory_limit("1.5GiB").unwrap(), Some(1536 * 1024 * 1024));
+        assert_eq!(parse_memory_limit(" 256 MB ").unwrap(), Some(256 * 1024 * 1024));
+        assert!(parse_memory_limit("2x").is_err());
+        assert!(parse_memory_limit("g").is_err());
+        assert!(parse_memory_limit("1k").is_err());
+        assert!(validate_cpu_limit(-1.0).is_err());
+        assert!(validate_cpu_limit(f32::NAN).is_err());
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&status.stderr));
+    }
+
+    #[test]
+    fn test_parse_unmerged_paths() {
+        let status = "UU src/app.rs\nM  README.md\nAA new.txt\nUD gone.rs\n?? scratch\nDU \"with \\\"quote\\\".txt\"\n";
+        assert_eq!(
+            parse_unmerged_paths(status),
+            vec![
+                ("src/app.rs".to_string(), ConflictKind::BothModified),
+                ("new.txt".to_string(), ConflictKind::BothAdded),
+                ("gone.rs".to_string(), ConflictKind::DeletedByThem),
+                ("with \"quote\".txt".to_string(), ConflictKind::DeletedByUs),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_conflict_markers() {
+        let content = "keep\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\nmid\n<<<<<<< HEAD\na\n||||||| base\nb\n=======\n>>>>>>> feature\n";
+        let regions = parse_conflict_markers(content);
+        assert_eq!(regions.len(), 2);
+        assert_eq!((regions[0].start_line, regions[0].end_line), (2, 6));
+        assert_eq!(regions[0].ours, "ours\n");
+        assert_eq!(regions[0].theirs, "theirs\n");
+        assert_eq!(regions[0].base, None);
+        assert_eq!(regions[1].base.as_deref(), Some("b\n"));
+        assert_eq!(regions[1].theirs, "");
+
+        // Lines that merely start like markers are content
+        assert!(parse_conflict_markers("<<<<<<<<< not a marker\n=======\n").is_empty());
+    }
+
+    #[test]
+    fn test_merge_conflict_resolve_and_continue() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        git(repo, &["init", "-q", "-b", "main"]);
+        git(repo, &["config", "user.name", "Test"]);
+        git(repo, &["config", "user.email", "test@example.com"]);
+        git(repo, &["config", "commit.gpgsign", "false"]);
+        fs::write(repo.join("file.txt"), "one\n").unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-q", "-m", "base"]);
+        git(repo, &["checkout", "-q", "-b", "feature"]);
+        fs::write(repo.join("file.txt"), "feature\n").unwrap();
+        git(repo, &["commit", "-q", "-am", "feature"]);
+        git(repo, &["checkout", "-q", "main"]);
+        fs::write(repo.join("file.txt"), "main\n").unwrap();
+        git(repo, &["commit", "-q", "-am", "main"]);
+
+        let MergeOutcome::Conflicts(conflicts) = git_merge(repo, "feature", "main").unwrap() else {
+            panic!("expected conflicts");
+        };
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, "file.txt");
+        assert_eq!(conflicts[0].kind, ConflictKind::BothModified);
+        assert_eq!(conflicts[0].markers[0].ours, "main\n");
+        assert_eq!(conflicts[0].markers[0].theirs, "feature\n");
+
+        // Still unresolved
+        assert!(matches!(git_merge_continue(repo).unwrap(), MergeOutcome::Conflicts(_)));
+
+        fs::write(repo.join("file.txt"), "both\n").unwrap();
+        assert_eq!(git_merge_continue(repo).unwrap(), MergeOutcome::Clean);
+        assert!(git_merge_continue(repo).is_err());
+    }
+
+    #[test]
+    fn test_compose_project_name() {
+        assert_eq!(compose_project_name("My App", "feature/Login_v2"), "smartspec-my-app-feature-login_v2");
+        assert_eq!(compose_project_name("app", "-x-"), "smartspec-app-x");
+    }
+
+    #[test]
+    fn test_generated_compose_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (workspace, mut branch) = test_workspace(dir.path());
+        branch.ports.push(PortMapping { host: 3100, container: 3000, protocol: "tcp".to_string() });
+
+        let yaml = generate_compose_file(&workspace, &branch, Path::new("/cache")).unwrap();
+        assert!(yaml.contains("\"3100:3000/tcp\""));
+        assert!(yaml.contains(&format!("{}:/workspace/project", workspace.path.to_string_lossy())));
+        assert!(yaml.contains("mem_limit: 2147483648"));
+        assert!(yaml.contains("memswap_limit: 2147483648"));
+        assert!(yaml.contains("cpus: 1.5"));
+        assert!(yaml.contains("  db:\n") && yaml.contains("  cache:\n"));
+
+        branch.memory_limit = Some("huge".to_string());
+        assert!(generate_compose_file(&workspace, &branch, Path::new("/cache")).is_err());
+    }
+
+    #[test]
+    fn test_compose_status_from_ps() {
+        let info = |service: &str, state: &str| ComposeServiceInfo {
+            service: service.to_string(),
+            container_id: format!("{}-id", service),
+            name: format!("p-{}-1", service),
+            state: state.to_string(),
+        };
+
+        let services = compose_service_states(&[info("db", "running"), info("app", "exited")]);
+        assert_eq!(services[0].name, "app");
+        assert_eq!(services[0].status, ContainerStatus::Stopped);
+        assert_eq!(compose_branch_status(&services), ContainerStatus::Running);
+
+        let stopped = compose_service_states(&[info("app", "exited"), info("db", "exited")]);
+        assert_eq!(compose_branch_status(&stopped), ContainerStatus::Stopped);
+        assert_eq!(compose_branch_status(&[]), ContainerStatus::None);
+    }
+
+    #[test]
+    fn test_status_from_docker_state() {
+        assert_eq!(status_from_docker_state(Some("running")), ContainerStatus::Running);
+        assert_eq!(status_from_docker_state(Some("restarting")), ContainerStatus::Running);
+        assert_eq!(status_from_docker_state(Some("created")), ContainerStatus::Created);
+        assert_eq!(status_from_docker_state(Some("exited")), ContainerStatus::Stopped);
+        assert_eq!(status_from_docker_state(Some("paused")), ContainerStatus::Stopped);
+        assert_eq!(status_from_docker_state(Some("dead")), ContainerStatus::Removed);
+        assert_eq!(status_from_docker_state(None), ContainerStatus::None);
+    }
+
+    #[test]
+    fn test_allocation_skips_bound_and_reserved_ports() {
+        let (_listener, port) = held_port();
+        let reserved: HashSet<u16> = [port + 1].into_iter().collect();
+
+        let block = find_free_port_block(2, port..=port + 10, &reserved, port).unwrap();
+        assert_eq!(block.len(), 2);
+        assert_eq!(block[1], block[0] + 1);
+        assert!(block[0] > port + 1, "allocated {:?} despite port {} in use", block, port);
+    }
+
+    #[test]
+    fn test_allocation_wraps_around_range() {
+        let (_listener, port) = held_port();
+        let block = find_free_port_block(1, port - 1..=port, &HashSet::new(), port).unwrap();
+        assert_eq!(block, vec![port - 1]);
+    }
+
+    #[test]
+    fn test_allocation_fails_when_range_exhausted() {
+        let (_listener, port) = held_port();
+        let reserved: HashSet<u16> = [port + 1].into_iter().collect();
+
+        let err = find_free_port_block(1, port..=port + 1, &reserved, port).unwrap_err();
+        assert!(err.contains("No free block of 1 ports"), "{}", err);
+        assert!(find_free_port_block(3, port..=port + 1, &reserved, port).is_err());
+    }
+
+    #[test]
+    fn test_create_rejects_unsafe_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = test_manager(dir.path());
+
+        for name in ["../evil", "nested/evil", "Evil", "con"] {
+            let request = CreateWorkspaceRequest {
+                name: name.to_string(),
+                repository: None,
+                image: None,
+                clone_repo: false,
+            };
+            assert!(manager.create_workspace(&request).is_err(), "accepted {:?}", name);
+        }
+        assert!(!dir.path().join("evil").exists());
+        assert!(!dir.path().join("workspaces").exists());
+
+        let request = CreateBranchRequest {
+            workspace: "demo".to_string(),
+            branch_name: "feature;rm -rf ~".to_string(),
+            from_branch: "main".to_string(),
+            image: None,
+            auto_start: false,
+            memory_limit: None,
+            cpu_limit: None,
+        };
+        let err = manager.create_branch(&request).unwrap_err();
+        assert!(err.contains("Invalid branch name"), "{}", err);
+    }
+
+    #[test]
+    fn test_config_writes_recover_from_partial_write() {
+        let root = tempfile::tempdir().unwrap();
+        let manager = test_manager(root.path());
+        let (mut workspace, _) = test_workspace(root.path());
+
+        manager.save_workspace_config(&workspace).unwrap();
+        workspace.default_image = "node:20".to_string();
+        manager.save_workspace_config(&workspace).unwrap();
+
+        // A crash mid-write leaves a truncated config behind
+        let config_path = workspace.path.join(".workspace/config.json");
+        let content = fs::read_to_string(&config_path).unwrap();
+        fs::write(&config_path, &content[..content.len() / 2]).unwrap();
+
+        let loaded = manager.load_workspace("demo").unwrap();
+        assert_eq!(loaded.default_image, DEFAULT_SANDBOX_IMAGE);
+        // Saving over the corrupt file keeps the good backup
+        manager.save_workspace_config(&loaded).unwrap();
+        assert_eq!(manager.load_workspace("demo").unwrap().default_image, DEFAULT_SANDBOX_IMAGE);
+
+        manager.register_workspace(&workspace).unwrap();
+        workspace.name = "other".to_string();
+        manager.register_workspace(&workspace).unwrap();
+        let registry_path = root.path().join("config/workspaces.json");
+        fs::write(&registry_path, "{\"demo\": ").unwrap();
+
+        let registry = manager.registered_workspaces().unwrap();
+        assert_eq!(registry.keys().collect::<Vec<_>>(), vec!["demo"]);
+        let leftovers: Vec<_> = fs::read_dir(root.path().join("config")).unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_registry_updates_are_serialized() {
+        let root = tempfile::tempdir().unwrap();
+        let (workspace, _) = test_workspace(root.path());
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let manager = test_manager(root.path());
+                let mut workspace = workspace.clone();
+                workspace.name = format!("ws-{}", i);
+                std::thread::spawn(move || manager.register_workspace(&workspace).unwrap())
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(test_manager(root.path()).registered_workspaces().unwrap().len(), 8);
+    }
+}
@@ -0,0 +1,442 @@
+// Workspace Registry - One view over the two workspace registries
+//
+// Provides:
+// - Creating, trashing and restoring a workspace in both the database index
+//   and the container registry (`workspaces.json`)
+// - Detecting and repairing workspaces known to only one of them
+// - Database stats and branch containers of a workspace in one call
+//
+// The two sides are linked by name: a database workspace records the
+// container workspace it belongs to in its metadata, and an unlinked
+// database workspace with the same name as a container workspace is taken
+// to be its counterpart.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::input_validation::validate_resource_name;
+use crate::workspace_db::{WorkspaceDbManager, WorkspaceDbStats, WorkspaceMetadata};
+use crate::workspace_manager::{CreateWorkspaceRequest, Workspace, WorkspaceManager};
+
+/// Key in a database workspace's `metadata_json` naming its container workspace
+const CONTAINER_LINK_KEY: &str = "container_workspace";
+
+/// Where a trashed container workspace is kept, inside the trashed database
+/// workspace so purging the trash removes both
+const CONTAINER_TRASH_DIR: &str = ".container-workspace";
+
+/// Longest container name derived from a display name, leaving room for a
+/// numeric suffix within the 64 character limit
+const MAX_DERIVED_NAME_LEN: usize = 56;
+
+// ============================================
+// Types
+// ============================================
+
+/// A database workspace with its stats and, when it has one, its container
+/// workspace as last recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceFull {
+    pub metadata: WorkspaceMetadata,
+    /// `None` when the database can't be opened
+    pub stats: Option<WorkspaceDbStats>,
+    pub container: Option<Workspace>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// A database workspace links to a container workspace that is gone
+    DatabaseOnly,
+    /// A container workspace has no database workspace
+    ContainerOnly,
+    /// A container workspace directory missing from `workspaces.json`
+    Unregistered,
+    /// A `workspaces.json` entry without its directory
+    StaleRegistryEntry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceDrift {
+    pub kind: DriftKind,
+    /// Container workspace name
+    pub name: String,
+    pub workspace_id: Option<String>,
+    pub fixed: bool,
+    /// Why the repair failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconcileReport {
+    pub drift: Vec<WorkspaceDrift>,
+    /// Workspaces present on both sides
+    pub linked: usize,
+}
+
+// ============================================
+// Registry
+// ============================================
+
+pub struct WorkspaceRegistry {
+    db: Arc<WorkspaceDbManager>,
+    containers: WorkspaceManager,
+}
+
+impl WorkspaceRegistry {
+    /// Both registries over the database manager's data directory
+    pub fn new(db: Arc<WorkspaceDbManager>) -> Result<Self, String> {
+        let containers = WorkspaceManager::with_base_dir(db.base_dir())?;
+        Ok(Self { db, containers })
+    }
+
+    /// Create a container workspace and the database workspace linked to it.
+    /// The request name is the database workspace's display name; the
+    /// container workspace gets it too when it is a free, valid resource name,
+    /// otherwise a name derived from it. The container workspace is removed
+    /// again if the database side fails.
+    pub fn create_workspace(&self, request: &CreateWorkspaceRequest) -> Result<WorkspaceFull, String> {
+        let container = self.containers.create_workspace(&CreateWorkspaceRequest {
+            name: self.free_container_name(&request.name)?,
+            ..request.clone()
+        })?;
+
+        let linked = self.db
+            .create_workspace(&request.name, request.repository.as_deref())
+            .and_then(|metadata| link_container(&self.db, &metadata, &container.name));
+        let metadata = match linked {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                let _ = self.containers.delete_workspace(&container.name, false);
+                return Err(format!("Failed to register workspace database: {}", e));
+            }
+        };
+
+        let stats = self.db.get_workspace_stats(&metadata.id).ok();
+        Ok(WorkspaceFull {
+            metadata,
+            stats,
+            container: Some(container),
+        })
+    }
+
+    /// Move a database workspace and its container workspace to the trash.
+    /// The container workspace's containers are removed, its files kept
+    /// until the trash is purged.
+    pub fn delete_workspace(&self, workspace_id: &str) -> Result<(), String> {
+        let metadata = self.db.get_workspace(workspace_id).map_err(|e| e.to_string())?;
+        let container = self.container_name(&metadata);
+
+        self.db.delete_workspace(workspace_id).map_err(|e| e.to_string())?;
+
+        if self.containers.workspace_names()?.contains(&container) {
+            let trashed = self.db.trashed_workspace_path(workspace_id).join(CONTAINER_TRASH_DIR);
+            if let Err(e) = self.containers.trash_workspace(&container, &trashed) {
+                let _ = self.db.restore_deleted_workspace(workspace_id);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Bring a trashed workspace back, container workspace included
+    pub fn restore_workspace(&self, workspace_id: &str) -> Result<WorkspaceFull, String> {
+        let metadata = self.db.get_workspace(workspace_id).map_err(|e| e.to_string())?;
+        let container = self.container_name(&metadata);
+        let trashed = self.db.trashed_workspace_path(workspace_id).join(CONTAINER_TRASH_DIR);
+
+        // The container workspace first: restoring the database side moves
+        // the trashed directory holding it
+        let has_container = trashed.exists();
+        if has_container {
+            self.containers.restore_workspace(&container, &trashed)?;
+        }
+        if let Err(e) = self.db.restore_deleted_workspace(workspace_id) {
+            if has_container {
+                let _ = self.containers.trash_workspace(&container, &trashed);
+            }
+            return Err(e.to_string());
+        }
+        self.get_workspace_full(workspace_id)
+    }
+
+    pub fn get_workspace_full(&self, workspace_id: &str) -> Result<WorkspaceFull, String> {
+        let metadata = self.db.get_workspace(workspace_id).map_err(|e| e.to_string())?;
+        let stats = self.db.get_workspace_stats(workspace_id).ok();
+        let container = self.containers.load_workspace(&self.container_name(&metadata)).ok();
+        Ok(WorkspaceFull { metadata, stats, container })
+    }
+
+    /// Compare the registries and, with `fix`, repair what only one side has:
+    /// container workspaces get a database workspace (or are linked to an
+    /// unlinked one of the same name), missing container workspaces are
+    /// recreated empty, and `workspaces.json` is made to match the directories.
+    pub fn reconcile(&self, fix: bool) -> Result<ReconcileReport, String> {
+        let mut report = ReconcileReport::default();
+        let databases = self.db.list_workspaces().map_err(|e| e.to_string())?;
+        let containers = self.containers.workspace_names()?;
+        let container_set: HashSet<&str> = containers.iter().map(String::as_str).collect();
+
+        // The registry file against the directories first, so recreating a
+        // container workspace below registers it again
+        let registered = self.containers.registered_workspaces()?;
+        for name in &containers {
+            if registered.contains_key(name) {
+                continue;
+            }
+            let result = if fix {
+                self.containers.load_workspace(name)
+                    .and_then(|workspace| self.containers.register_workspace(&workspace))
+            } else {
+                Ok(())
+            };
+            report.drift.push(drift(DriftKind::Unregistered, name, None, fix, result));
+        }
+        let mut stale: Vec<&String> = registered.keys()
+            .filter(|name| !container_set.contains(name.as_str()))
+            .collect();
+        stale.sort();
+        for name in stale {
+            let result = if fix {
+                self.containers.unregister_workspace(name)
+            } else {
+                Ok(())
+            };
+            report.drift.push(drift(DriftKind::StaleRegistryEntry, name, None, fix, result));
+        }
+
+        // Database workspaces that link to a container workspace
+        let mut claimed = HashSet::new();
+        for metadata in &databases {
+            let Some(name) = linked_container(metadata) else {
+                continue;
+            };
+            claimed.insert(name.clone());
+            if container_set.contains(name.as_str()) {
+                report.linked += 1;
+                continue;
+            }
+            let result = if fix {
+                self.containers.create_workspace(&CreateWorkspaceRequest {
+                    name: name.clone(),
+                    repository: metadata.git_remote.clone(),
+                    image: None,
+                    clone_repo: false,
+                }).map(|_| ())
+            } else {
+                Ok(())
+            };
+            report.drift.push(drift(DriftKind::DatabaseOnly, &name, Some(&metadata.id), fix, result));
+        }
+
+        for name in containers.iter().filter(|name| !claimed.contains(*name)) {
+            let same_name = databases.iter().find(|m| &m.name == name && linked_container(m).is_none());
+            let result = match (fix, same_name) {
+                (false, _) => Ok(()),
+                (true, Some(metadata)) => link_container(&self.db, metadata, name).map(|_| ()),
+                (true, None) => self.containers.load_workspace(name)
+                    .map_err(anyhow::Error::msg)
+                    .and_then(|workspace| self.db.create_workspace(name, workspace.repository.as_deref()))
+                    .and_then(|metadata| link_container(&self.db, &metadata, name).map(|_| ())),
+            };
+            report.drift.push(drift(DriftKind::ContainerOnly, name, same_name.map(|m| m.id.as_str()), fix, result));
+        }
+
+        Ok(report)
+    }
+
+    /// The linked container workspace, or the one sharing the workspace's name
+    fn container_name(&self, metadata: &WorkspaceMetadata) -> String {
+        linked_container(metadata).unwrap_or_else(|| metadata.name.clone())
+    }
+
+    /// `name` if it is a valid container workspace name nobody uses yet,
+    /// otherwise a free name derived from it
+    fn free_container_name(&self, name: &str) -> Result<String, String> {
+        let taken: HashSet<String> = self.containers.workspace_names()?.into_iter().collect();
+        let is_free = |candidate: &str| {
+            !taken.contains(candidate)
+                && validate_resource_name(candidate, "Workspace name").is_ok()
+                && !self.containers.get_base_dir().join(candidate).exists()
+        };
+        if is_free(name) {
+            return Ok(name.to_string());
+        }
+
+        let base = container_slug(name);
+        (1..)
+            .map(|n| if n == 1 { base.clone() } else { format!("{}-{}", base, n) })
+            .find(|candidate| is_free(candidate))
+            .ok_or_else(|| format!("No free container workspace name for '{}'", name))
+    }
+}
+
+/// Lowercase letters, digits and single hyphens, starting with a letter
+fn container_slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    if !slug.starts_with(|c: char| c.is_ascii_lowercase()) {
+        slug.insert_str(0, "ws-");
+    }
+    slug.truncate(MAX_DERIVED_NAME_LEN);
+    slug.trim_end_matches('-').to_string()
+}
+
+fn linked_container(metadata: &WorkspaceMetadata) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(metadata.metadata_json.as_deref()?).ok()?;
+    json.get(CONTAINER_LINK_KEY)?.as_str().map(str::to_string)
+}
+
+/// Record the container workspace in the database workspace's metadata,
+/// keeping any other keys
+fn link_container(db: &WorkspaceDbManager, metadata: &WorkspaceMetadata, name: &str) -> anyhow::Result<WorkspaceMetadata> {
+    let mut json = metadata.metadata_json.as_deref()
+        .and_then(|json| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(json).ok())
+        .unwrap_or_default();
+    json.insert(CONTAINER_LINK_KEY.to_string(), serde_json::Value::String(name.to_string()));
+    db.set_workspace_metadata_json(&metadata.id, Some(&serde_json::Value::Object(json).to_string()))
+}
+
+fn drift<E: ToString>(
+    kind: DriftKind,
+    name: &str,
+    workspace_id: Option<&str>,
+    fix: bool,
+    result: Result<(), E>,
+) -> WorkspaceDrift {
+    let error = result.err().map(|e| e.to_string());
+    WorkspaceDrift {
+        kind,
+        name: name.to_string(),
+        workspace_id: workspace_id.map(str::to_string),
+        fixed: fix && error.is_none(),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(base: &std::path::Path) -> WorkspaceRegistry {
+        WorkspaceRegistry::new(Arc::new(WorkspaceDbManager::with_base_dir(base).unwrap())).unwrap()
+    }
+
+    fn request(name: &str) -> CreateWorkspaceRequest {
+        CreateWorkspaceRequest {
+            name: name.to_string(),
+            repository: None,
+            image: None,
+            clone_repo: false,
+        }
+    }
+
+    fn kinds(report: &ReconcileReport) -> Vec<(DriftKind, &str)> {
+        report.drift.iter().map(|d| (d.kind, d.name.as_str())).collect()
+    }
+
+    #[test]
+    fn test_create_get_and_delete_cascade() {
+        let base = tempfile::tempdir().unwrap();
+        let registry = registry(base.path());
+
+        let created = registry.create_workspace(&request("shop-api")).unwrap();
+        assert_eq!(linked_container(&created.metadata).as_deref(), Some("shop-api"));
+        assert!(registry.containers.registered_workspaces().unwrap().contains_key("shop-api"));
+
+        let full = registry.get_workspace_full(&created.metadata.id).unwrap();
+        assert_eq!(full.container.unwrap().name, "shop-api");
+        assert_eq!(full.stats.unwrap().job_count, 0);
+        assert!(registry.reconcile(false).unwrap().drift.is_empty());
+
+        registry.delete_workspace(&created.metadata.id).unwrap();
+        assert!(registry.containers.workspace_names().unwrap().is_empty());
+        assert!(registry.containers.registered_workspaces().unwrap().is_empty());
+        assert!(registry.db.list_workspaces().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_trashes_container_files_until_restored_or_purged() {
+        let base = tempfile::tempdir().unwrap();
+        let registry = registry(base.path());
+
+        let created = registry.create_workspace(&request("shop-api")).unwrap();
+        let source = created.container.unwrap().path.join("main.rs");
+        std::fs::write(&source, "fn main() {}").unwrap();
+
+        registry.delete_workspace(&created.metadata.id).unwrap();
+        assert!(registry.containers.workspace_names().unwrap().is_empty());
+        assert!(registry.reconcile(false).unwrap().drift.is_empty());
+
+        let restored = registry.restore_workspace(&created.metadata.id).unwrap();
+        assert_eq!(restored.container.unwrap().name, "shop-api");
+        assert_eq!(std::fs::read_to_string(&source).unwrap(), "fn main() {}");
+        assert!(registry.containers.registered_workspaces().unwrap().contains_key("shop-api"));
+        assert!(registry.reconcile(false).unwrap().drift.is_empty());
+
+        registry.delete_workspace(&created.metadata.id).unwrap();
+        assert_eq!(registry.db.purge_trash(0).unwrap(), 1);
+        assert!(!source.exists());
+        assert!(!registry.db.trashed_workspace_path(&created.metadata.id).exists());
+    }
+
+    #[test]
+    fn test_display_names_get_a_derived_container_name() {
+        let base = tempfile::tempdir().unwrap();
+        let registry = registry(base.path());
+
+        let notes = registry.create_workspace(&request("Scratch notes!")).unwrap();
+        assert_eq!(notes.metadata.name, "Scratch notes!");
+        assert_eq!(notes.container.unwrap().name, "scratch-notes");
+
+        let again = registry.create_workspace(&request("Scratch notes!")).unwrap();
+        assert_eq!(linked_container(&again.metadata).as_deref(), Some("scratch-notes-2"));
+
+        assert_eq!(container_slug("2024 Plan"), "ws-2024-plan");
+        assert_eq!(container_slug("***"), "ws");
+    }
+
+    #[test]
+    fn test_reconcile_reports_and_repairs_drift() {
+        let base = tempfile::tempdir().unwrap();
+        let registry = registry(base.path());
+
+        // Container workspace with no database side
+        registry.containers.create_workspace(&request("billing")).unwrap();
+        // Same-name pair created separately, not yet linked
+        registry.containers.create_workspace(&request("docs")).unwrap();
+        let docs = registry.db.create_workspace("docs", None).unwrap();
+        // Linked workspace whose container directory was removed by hand
+        let gone = registry.create_workspace(&request("gone")).unwrap();
+        std::fs::remove_dir_all(&gone.container.unwrap().path).unwrap();
+        // Database-only workspaces without a link are not drift
+        registry.db.create_workspace("Scratch notes", None).unwrap();
+
+        let report = registry.reconcile(false).unwrap();
+        assert_eq!(kinds(&report), vec![
+            (DriftKind::StaleRegistryEntry, "gone"),
+            (DriftKind::DatabaseOnly, "gone"),
+            (DriftKind::ContainerOnly, "billing"),
+            (DriftKind::ContainerOnly, "docs"),
+        ]);
+        assert!(report.drift.iter().all(|d| !d.fixed));
+        assert_eq!(report.drift[3].workspace_id.as_deref(), Some(docs.id.as_str()));
+
+        let report = registry.reconcile(true).unwrap();
+        assert!(report.drift.iter().all(|d| d.fixed && d.error.is_none()), "{:?}", report.drift);
+
+        let report = registry.reconcile(false).unwrap();
+        assert!(report.drift.is_empty(), "{:?}", report.drift);
+        assert_eq!(report.linked, 3);
+        // The unlinked same-name workspace was linked rather than duplicated
+        assert_eq!(registry.db.list_workspaces().unwrap().iter().filter(|m| m.name == "docs").count(), 1);
+        assert_eq!(linked_container(&registry.db.get_workspace(&docs.id).unwrap()).as_deref(), Some("docs"));
+    }
+}
@@ -0,0 +1,779 @@
+// Workspace Sync - Conflict-aware sync with the project's git remote
+//
+// Provides:
+// - Divergence checks for the project branch, with fast-forward or push
+// - Three-way merge of knowledge and long-term memory, last write wins
+// - Conflict records kept until the user resolves them
+//
+// Protocol (every step is safe to repeat, so a failed sync resumes on the
+// next run):
+// 1. Fetch all branches from the remote
+// 2. Fast-forward the project branch when it is only behind, push when it
+//    is only ahead; a diverged branch is reported for the user to merge
+// 3. Merge synced rows against the hashes recorded at the last sync. A row
+//    changed on one side takes that side; a row changed on both takes the
+//    newer edit and is recorded as a conflict
+// 4. Commit the merged rows to the `smartspec-sync` branch on top of the
+//    remote's, push it, then record the merged hashes as the new base
+
+use anyhow::{Context, Result, anyhow, bail};
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::git_manager::{Divergence, GitManager};
+use crate::workspace_db::{WorkspaceDbManager, WorkspaceMetadata};
+
+/// Branch on the project remote that carries workspace data
+pub const SYNC_BRANCH: &str = "smartspec-sync";
+
+const RECORDS_FILE: &str = "records.json";
+
+struct SyncTable {
+    name: &'static str,
+    /// Columns that travel, besides `sync_id` and `updated_at`
+    columns: &'static [&'static str],
+}
+
+/// Local bookkeeping such as access counts and embeddings stays out
+const SYNC_TABLES: &[SyncTable] = &[
+    SyncTable {
+        name: "knowledge",
+        columns: &["type", "title", "content", "tags_json", "file_refs_json", "is_active", "source", "created_by", "created_at"],
+    },
+    SyncTable {
+        name: "memory_long",
+        columns: &["category", "title", "content", "tags_json", "source", "confidence", "created_at"],
+    },
+];
+
+// ============================================
+// Types
+// ============================================
+
+/// One synced row, as stored on the sync branch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncRecord {
+    pub table: String,
+    pub sync_id: String,
+    /// Decides which edit wins a conflict; not part of the hash
+    pub updated_at: String,
+    pub data: BTreeMap<String, serde_json::Value>,
+}
+
+impl SyncRecord {
+    pub fn hash(&self) -> String {
+        let json = serde_json::to_vec(&(&self.table, &self.data)).unwrap_or_default();
+        hex::encode(Sha256::digest(&json))
+    }
+
+    fn key(&self) -> (String, String) {
+        (self.table.clone(), self.sync_id.clone())
+    }
+
+    fn item(&self) -> SyncItem {
+        SyncItem {
+            table: self.table.clone(),
+            sync_id: self.sync_id.clone(),
+            title: self.data.get("title").and_then(|t| t.as_str()).unwrap_or_default().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncSide {
+    Local,
+    Remote,
+}
+
+impl SyncSide {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SyncSide::Local => "local",
+            SyncSide::Remote => "remote",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(SyncSide::Local),
+            "remote" => Ok(SyncSide::Remote),
+            _ => Err(format!("Invalid sync side: {}", s)),
+        }
+    }
+}
+
+/// A row edited on both sides since the last sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    /// Id in `sync_conflicts`; 0 until recorded
+    pub id: i64,
+    pub local: SyncRecord,
+    pub remote: SyncRecord,
+    /// The side last-write-wins kept
+    pub winner: SyncSide,
+    pub created_at: Option<String>,
+    pub resolved_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncItem {
+    pub table: String,
+    pub sync_id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitSyncAction {
+    None,
+    FastForwarded,
+    Pushed,
+    /// Local and remote both have new commits; the user has to merge
+    NeedsMerge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitSyncResult {
+    pub branch: String,
+    /// Before any fast-forward or push
+    pub divergence: Divergence,
+    pub action: GitSyncAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub workspace_id: String,
+    pub remote: String,
+    /// `None` while the project has no commits
+    pub git: Option<GitSyncResult>,
+    pub pulled: Vec<SyncItem>,
+    pub pushed: Vec<SyncItem>,
+    pub conflicts: Vec<SyncConflict>,
+    pub synced_at: String,
+}
+
+/// Outcome of merging local and remote rows
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// Remote rows to write locally
+    pub pull: Vec<SyncRecord>,
+    /// Local rows the remote lacks or has older
+    pub push: Vec<SyncRecord>,
+    pub conflicts: Vec<SyncConflict>,
+    /// Every row after the merge, ordered by table and sync id
+    pub merged: Vec<SyncRecord>,
+}
+
+// ============================================
+// Merge
+// ============================================
+
+/// Three-way merge against `base`, the hash of each row at the last sync.
+/// Rows deleted on one side are not propagated.
+pub fn reconcile(
+    local: Vec<SyncRecord>,
+    remote: Vec<SyncRecord>,
+    base: &HashMap<(String, String), String>,
+) -> SyncPlan {
+    let mut remote: BTreeMap<(String, String), SyncRecord> = remote.into_iter()
+        .map(|record| (record.key(), record))
+        .collect();
+    let mut merged: BTreeMap<(String, String), SyncRecord> = BTreeMap::new();
+    let mut plan = SyncPlan::default();
+
+    for local in local {
+        let key = local.key();
+        let Some(remote) = remote.remove(&key) else {
+            plan.push.push(local.clone());
+            merged.insert(key, local);
+            continue;
+        };
+
+        let (local_hash, remote_hash) = (local.hash(), remote.hash());
+        let base_hash = base.get(&key);
+        let kept = if local_hash == remote_hash {
+            remote
+        } else if base_hash == Some(&local_hash) {
+            plan.pull.push(remote.clone());
+            remote
+        } else if base_hash == Some(&remote_hash) {
+            plan.push.push(local.clone());
+            local
+        } else {
+            let winner = newer_side(&local, &local_hash, &remote, &remote_hash);
+            let kept = match winner {
+                SyncSide::Local => {
+                    plan.push.push(local.clone());
+                    local.clone()
+                }
+                SyncSide::Remote => {
+                    plan.pull.push(remote.clone());
+                    remote.clone()
+                }
+            };
+            plan.conflicts.push(SyncConflict {
+                id: 0,
+                local,
+                remote,
+                winner,
+                created_at: None,
+                resolved_at: None,
+            });
+            kept
+        };
+        merged.insert(key, kept);
+    }
+
+    for (key, remote) in remote {
+        plan.pull.push(remote.clone());
+        merged.insert(key, remote);
+    }
+
+    plan.merged = merged.into_values().collect();
+    plan
+}
+
+/// Later `updated_at` wins. Ties and unreadable timestamps fall back to the
+/// hash, so both machines pick the same row.
+fn newer_side(local: &SyncRecord, local_hash: &str, remote: &SyncRecord, remote_hash: &str) -> SyncSide {
+    match (parse_timestamp(&local.updated_at), parse_timestamp(&remote.updated_at)) {
+        (Some(l), Some(r)) if l > r => SyncSide::Local,
+        (Some(l), Some(r)) if l < r => SyncSide::Remote,
+        _ if local_hash > remote_hash => SyncSide::Local,
+        _ => SyncSide::Remote,
+    }
+}
+
+/// RFC 3339, or SQLite's `CURRENT_TIMESTAMP` format in UTC
+fn parse_timestamp(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|t| t.and_utc())
+        })
+}
+
+// ============================================
+// Database
+// ============================================
+
+fn sync_table(name: &str) -> Result<&'static SyncTable> {
+    SYNC_TABLES.iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| anyhow!("Table {} is not synced", name))
+}
+
+/// All synced rows in the workspace
+pub fn load_records(conn: &Connection) -> Result<Vec<SyncRecord>> {
+    let mut records = Vec::new();
+    for table in SYNC_TABLES {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT sync_id, updated_at, {} FROM {} WHERE sync_id IS NOT NULL ORDER BY sync_id",
+            table.columns.join(", "),
+            table.name
+        )).context("Failed to prepare sync query")?;
+
+        let rows = stmt.query_map([], |row| {
+            let mut data = BTreeMap::new();
+            for (i, column) in table.columns.iter().enumerate() {
+                data.insert(column.to_string(), json_value(row.get_ref(i + 2)?));
+            }
+            Ok(SyncRecord {
+                table: table.name.to_string(),
+                sync_id: row.get(0)?,
+                updated_at: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                data,
+            })
+        }).context("Failed to load synced rows")?;
+
+        for record in rows {
+            records.push(record.context("Failed to read synced row")?);
+        }
+    }
+    Ok(records)
+}
+
+fn json_value(value: ValueRef<'_>) -> serde_json::Value {
+    match value {
+        ValueRef::Null | ValueRef::Blob(_) => serde_json::Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned().into(),
+    }
+}
+
+fn sql_value(value: Option<&serde_json::Value>) -> Value {
+    match value {
+        Some(serde_json::Value::Bool(b)) => Value::Integer(*b as i64),
+        Some(serde_json::Value::Number(n)) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        Some(serde_json::Value::String(s)) => Value::Text(s.clone()),
+        _ => Value::Null,
+    }
+}
+
+/// Insert or overwrite rows by sync id
+pub fn apply_records(conn: &Connection, records: &[SyncRecord]) -> Result<()> {
+    for record in records {
+        let table = sync_table(&record.table)?;
+        let columns: Vec<&str> = table.columns.iter().copied().chain(["updated_at"]).collect();
+        let sql = format!(
+            "INSERT INTO {} (sync_id, {}) VALUES (?{}) ON CONFLICT(sync_id) DO UPDATE SET {}",
+            table.name,
+            columns.join(", "),
+            ", ?".repeat(columns.len()),
+            columns.iter().map(|c| format!("{0} = excluded.{0}", c)).collect::<Vec<_>>().join(", ")
+        );
+
+        let mut values = vec![Value::Text(record.sync_id.clone())];
+        values.extend(table.columns.iter().map(|c| sql_value(record.data.get(*c))));
+        values.push(Value::Text(record.updated_at.clone()));
+
+        conn.execute(&sql, params_from_iter(values))
+            .with_context(|| format!("Failed to apply synced {} {}", record.table, record.sync_id))?;
+    }
+    Ok(())
+}
+
+pub fn load_base(conn: &Connection) -> Result<HashMap<(String, String), String>> {
+    let mut stmt = conn.prepare("SELECT table_name, sync_id, hash FROM sync_state")
+        .context("Failed to prepare sync state query")?;
+    let rows = stmt.query_map([], |row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)))
+        .context("Failed to load sync state")?;
+
+    let mut base = HashMap::new();
+    for row in rows {
+        let (key, hash) = row.context("Failed to read sync state")?;
+        base.insert(key, hash);
+    }
+    Ok(base)
+}
+
+/// Replace the merge base with the hashes of `merged`
+pub fn save_base(conn: &Connection, merged: &[SyncRecord]) -> Result<()> {
+    let tx = conn.unchecked_transaction().context("Failed to begin sync state transaction")?;
+    tx.execute("DELETE FROM sync_state", []).context("Failed to clear sync state")?;
+    for record in merged {
+        tx.execute(
+            "INSERT INTO sync_state (table_name, sync_id, hash) VALUES (?, ?, ?)",
+            params![record.table, record.sync_id, record.hash()],
+        ).context("Failed to save sync state")?;
+    }
+    tx.commit().context("Failed to commit sync state")
+}
+
+/// Store conflicts and fill in their ids. A conflict already recorded by an
+/// earlier, interrupted sync keeps its original row.
+pub fn record_conflicts(conn: &Connection, conflicts: &mut [SyncConflict]) -> Result<()> {
+    for conflict in conflicts.iter_mut() {
+        let (local_hash, remote_hash) = (conflict.local.hash(), conflict.remote.hash());
+        conn.execute(
+            "INSERT OR IGNORE INTO sync_conflicts (table_name, sync_id, local_hash, remote_hash, local_json, remote_json, winner)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                conflict.local.table,
+                conflict.local.sync_id,
+                local_hash,
+                remote_hash,
+                serde_json::to_string(&conflict.local)?,
+                serde_json::to_string(&conflict.remote)?,
+                conflict.winner.as_str(),
+            ],
+        ).context("Failed to record sync conflict")?;
+
+        let (id, created_at, resolved_at) = conn.query_row(
+            "SELECT id, created_at, resolved_at FROM sync_conflicts
+             WHERE table_name = ? AND sync_id = ? AND local_hash = ? AND remote_hash = ?",
+            params![conflict.local.table, conflict.local.sync_id, local_hash, remote_hash],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).context("Failed to read sync conflict")?;
+        conflict.id = id;
+        conflict.created_at = created_at;
+        conflict.resolved_at = resolved_at;
+    }
+    Ok(())
+}
+
+/// id, local_json, remote_json, winner, created_at, resolved_at
+type ConflictRow = (i64, String, String, String, Option<String>, Option<String>);
+
+fn read_conflict(row: &rusqlite::Row<'_>) -> rusqlite::Result<ConflictRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+}
+
+fn parse_conflict(
+    (id, local_json, remote_json, winner, created_at, resolved_at): ConflictRow,
+) -> Result<SyncConflict> {
+    Ok(SyncConflict {
+        id,
+        local: serde_json::from_str(&local_json).context("Invalid local version in sync conflict")?,
+        remote: serde_json::from_str(&remote_json).context("Invalid remote version in sync conflict")?,
+        winner: SyncSide::parse(&winner).map_err(|e| anyhow!(e))?,
+        created_at,
+        resolved_at,
+    })
+}
+
+/// Conflicts not yet resolved, oldest first
+fn open_conflicts(conn: &Connection) -> Result<Vec<SyncConflict>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, local_json, remote_json, winner, created_at, resolved_at
+         FROM sync_conflicts WHERE resolved_at IS NULL ORDER BY id"
+    ).context("Failed to prepare sync conflict query")?;
+    let rows = stmt.query_map([], read_conflict).context("Failed to query sync conflicts")?;
+
+    let mut conflicts = Vec::new();
+    for row in rows {
+        conflicts.push(parse_conflict(row.context("Failed to read sync conflict")?)?);
+    }
+    Ok(conflicts)
+}
+
+/// Settle a conflict on `keep`. Keeping the side that lost rewrites the row
+/// with a fresh `updated_at`, so the next sync publishes it.
+fn resolve_conflict(conn: &Connection, conflict_id: i64, keep: SyncSide) -> Result<()> {
+    let conflict = conn.query_row(
+        "SELECT id, local_json, remote_json, winner, created_at, resolved_at
+         FROM sync_conflicts WHERE id = ?",
+        params![conflict_id],
+        read_conflict,
+    ).optional().context("Failed to read sync conflict")?;
+    let Some(conflict) = conflict else {
+        bail!("Sync conflict {} not found", conflict_id);
+    };
+    let conflict = parse_conflict(conflict)?;
+    if conflict.resolved_at.is_some() {
+        bail!("Sync conflict {} is already resolved", conflict_id);
+    }
+
+    let tx = conn.unchecked_transaction().context("Failed to begin transaction")?;
+    if keep != conflict.winner {
+        let mut record = match keep {
+            SyncSide::Local => conflict.local,
+            SyncSide::Remote => conflict.remote,
+        };
+        record.updated_at = chrono::Utc::now().to_rfc3339();
+        apply_records(&tx, &[record])?;
+    }
+    tx.execute(
+        "UPDATE sync_conflicts SET resolved_at = ? WHERE id = ?",
+        params![chrono::Utc::now().to_rfc3339(), conflict_id],
+    ).context("Failed to resolve sync conflict")?;
+    tx.commit().context("Failed to commit conflict resolution")
+}
+
+// ============================================
+// Sync
+// ============================================
+
+pub fn list_sync_conflicts(db_manager: &WorkspaceDbManager, workspace_id: &str) -> Result<Vec<SyncConflict>> {
+    let workspace_db = db_manager.open_workspace(workspace_id)?;
+    let db = workspace_db.lock()
+        .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+    open_conflicts(&db.conn)
+}
+
+pub fn resolve_sync_conflict(
+    db_manager: &WorkspaceDbManager,
+    workspace_id: &str,
+    conflict_id: i64,
+    keep: SyncSide,
+) -> Result<()> {
+    let workspace_db = db_manager.open_workspace(workspace_id)?;
+    let db = workspace_db.lock()
+        .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+    resolve_conflict(&db.conn, conflict_id, keep)
+}
+
+/// Bring the checked-out project branch in line with the remote where that
+/// needs no merge
+fn sync_project_branch<F>(git: &GitManager, remote_name: &str, token_for_host: &F) -> Result<Option<GitSyncResult>>
+where
+    F: Fn(&str) -> Result<Option<String>, String>,
+{
+    if git.head_commit()?.is_none() {
+        return Ok(None);
+    }
+    let branch = git.get_current_branch()?;
+    let divergence = git.divergence(&branch, remote_name)?;
+
+    let action = match divergence {
+        Divergence::UpToDate => GitSyncAction::None,
+        Divergence::Behind { .. } => {
+            git.fast_forward(&branch, remote_name)?;
+            GitSyncAction::FastForwarded
+        }
+        Divergence::Ahead { .. } | Divergence::NoUpstream => {
+            git.push_branch(&branch, remote_name, token_for_host)?;
+            GitSyncAction::Pushed
+        }
+        Divergence::Diverged { .. } => GitSyncAction::NeedsMerge,
+    };
+
+    Ok(Some(GitSyncResult { branch, divergence, action }))
+}
+
+/// Git manager for a workspace's `project` checkout
+pub fn project_git(workspace: &WorkspaceMetadata) -> GitManager {
+    let project_dir = Path::new(&workspace.path).join("project");
+    GitManager::new(project_dir.to_string_lossy().to_string())
+}
+
+/// Run the sync protocol for a workspace whose `project` directory is a
+/// git checkout with `remote_name` configured
+pub fn sync_workspace<F>(
+    db_manager: &WorkspaceDbManager,
+    workspace_id: &str,
+    remote_name: &str,
+    token_for_host: F,
+) -> Result<SyncReport>
+where
+    F: Fn(&str) -> Result<Option<String>, String>,
+{
+    let workspace = db_manager.get_workspace(workspace_id)?;
+    let git = project_git(&workspace);
+    if !git.repo_exists() {
+        bail!("Workspace project is not a git repository");
+    }
+
+    git.fetch(remote_name, &token_for_host)?;
+    let git_result = sync_project_branch(&git, remote_name, &token_for_host)?;
+
+    let remote_ref = format!("refs/remotes/{}/{}", remote_name, SYNC_BRANCH);
+    let remote_records: Vec<SyncRecord> = match git.read_file_at(&remote_ref, RECORDS_FILE)? {
+        Some(bytes) => serde_json::from_slice(&bytes).context("Invalid sync records on remote")?,
+        None => Vec::new(),
+    };
+
+    // The lock is released while pushing; rows edited meanwhile differ from
+    // the new base and go out with the next sync
+    let plan = {
+        let workspace_db = db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+
+        let mut plan = reconcile(load_records(&db.conn)?, remote_records, &load_base(&db.conn)?);
+        let tx = db.conn.unchecked_transaction().context("Failed to begin sync transaction")?;
+        apply_records(&tx, &plan.pull)?;
+        record_conflicts(&tx, &mut plan.conflicts)?;
+        tx.commit().context("Failed to commit pulled rows")?;
+        plan
+    };
+
+    if !plan.push.is_empty() {
+        let content = serde_json::to_vec_pretty(&plan.merged).context("Failed to serialize sync records")?;
+        git.commit_file_to_ref(
+            &format!("refs/heads/{}", SYNC_BRANCH),
+            &remote_ref,
+            RECORDS_FILE,
+            &content,
+            &format!("Sync workspace {}", workspace.name),
+        )?;
+        git.push_branch(SYNC_BRANCH, remote_name, &token_for_host)?;
+    }
+
+    {
+        let workspace_db = db_manager.open_workspace(workspace_id)?;
+        let db = workspace_db.lock()
+            .map_err(|_| anyhow!("Failed to acquire workspace database lock"))?;
+        save_base(&db.conn, &plan.merged)?;
+    }
+
+    Ok(SyncReport {
+        workspace_id: workspace_id.to_string(),
+        remote: remote_name.to_string(),
+        git: git_result,
+        pulled: plan.pull.iter().map(SyncRecord::item).collect(),
+        pushed: plan.push.iter().map(SyncRecord::item).collect(),
+        conflicts: plan.conflicts,
+        synced_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Repository, Signature};
+    use std::sync::Arc;
+    use crate::workspace_data::{CreateKnowledgeRequest, WorkspaceDataOps};
+
+    fn record(sync_id: &str, content: &str, updated_at: &str) -> SyncRecord {
+        SyncRecord {
+            table: "knowledge".to_string(),
+            sync_id: sync_id.to_string(),
+            updated_at: updated_at.to_string(),
+            data: BTreeMap::from([("content".to_string(), serde_json::Value::from(content))]),
+        }
+    }
+
+    fn ids(records: &[SyncRecord]) -> Vec<&str> {
+        records.iter().map(|r| r.sync_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_reconcile_three_way() {
+        let old = "2026-01-01T00:00:00Z";
+        let new = "2026-01-02T00:00:00Z";
+        let base: HashMap<(String, String), String> = ["same", "local_edit", "remote_edit", "both"].iter()
+            .map(|id| (("knowledge".to_string(), id.to_string()), record(id, "v1", old).hash()))
+            .collect();
+
+        let local = vec![
+            record("same", "v1", old),
+            record("local_edit", "v2", new),
+            record("remote_edit", "v1", old),
+            record("both", "local", new),
+            record("local_new", "v1", old),
+        ];
+        let remote = vec![
+            record("same", "v1", new),
+            record("local_edit", "v1", old),
+            record("remote_edit", "v2", new),
+            record("both", "remote", "2026-01-01 12:00:00"),
+            record("remote_new", "v1", old),
+        ];
+
+        let plan = reconcile(local, remote, &base);
+        assert_eq!(ids(&plan.pull), vec!["remote_edit", "remote_new"]);
+        assert_eq!(ids(&plan.push), vec!["local_edit", "both", "local_new"]);
+        assert_eq!(plan.conflicts.len(), 1);
+        assert_eq!(plan.conflicts[0].winner, SyncSide::Local);
+        assert_eq!(plan.conflicts[0].remote.data["content"], "remote");
+        assert_eq!(ids(&plan.merged), vec!["both", "local_edit", "local_new", "remote_edit", "remote_new", "same"]);
+        assert_eq!(plan.merged[0].data["content"], "local");
+
+        // Merging the result again is a no-op
+        let merged_base = plan.merged.iter().map(|r| (r.key(), r.hash())).collect();
+        let again = reconcile(plan.merged.clone(), plan.merged.clone(), &merged_base);
+        assert!(again.pull.is_empty() && again.push.is_empty() && again.conflicts.is_empty());
+
+        // Equal timestamps still pick the same side from either machine
+        let a = record("tie", "a", old);
+        let b = record("tie", "b", old);
+        let from_a = reconcile(vec![a.clone()], vec![b.clone()], &HashMap::new());
+        let from_b = reconcile(vec![b], vec![a], &HashMap::new());
+        assert_eq!(from_a.merged, from_b.merged);
+    }
+
+    fn commit_file(dir: &Path, name: &str) {
+        let repo = Repository::open(dir).unwrap();
+        std::fs::write(dir.join(name), name).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(name)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &sig, &sig, name, &tree, &parents).unwrap();
+    }
+
+    fn edit_knowledge(db_manager: &WorkspaceDbManager, workspace_id: &str, sync_id: &str, content: &str, updated_at: &str) {
+        let workspace_db = db_manager.open_workspace(workspace_id).unwrap();
+        let db = workspace_db.lock().unwrap();
+        db.conn.execute(
+            "UPDATE knowledge SET content = ?, updated_at = ? WHERE sync_id = ?",
+            params![content, updated_at, sync_id],
+        ).unwrap();
+    }
+
+    fn knowledge_content(db_manager: &WorkspaceDbManager, workspace_id: &str, sync_id: &str) -> String {
+        let workspace_db = db_manager.open_workspace(workspace_id).unwrap();
+        let db = workspace_db.lock().unwrap();
+        db.conn.query_row("SELECT content FROM knowledge WHERE sync_id = ?", params![sync_id], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_sync_with_diverged_remote() {
+        let no_token = |_: &str| Ok(None);
+        let remote_dir = tempfile::tempdir().unwrap();
+        Repository::init_bare(remote_dir.path()).unwrap();
+        let remote_url = remote_dir.path().to_string_lossy().to_string();
+
+        let base_dir = tempfile::tempdir().unwrap();
+        let db_manager = Arc::new(WorkspaceDbManager::with_base_dir(base_dir.path()).unwrap());
+        let ops = WorkspaceDataOps::new(Arc::clone(&db_manager));
+        let alpha = db_manager.create_workspace("test-sync-alpha", None).unwrap();
+        let beta = db_manager.create_workspace("test-sync-beta", None).unwrap();
+        let alpha_project = Path::new(&alpha.path).join("project");
+        let beta_project = Path::new(&beta.path).join("project");
+
+        Repository::init(&alpha_project).unwrap().remote("origin", &remote_url).unwrap();
+        commit_file(&alpha_project, "README.md");
+        let knowledge = ops.create_knowledge(&alpha.id, CreateKnowledgeRequest {
+            knowledge_type: "decision".to_string(),
+            title: "Auth".to_string(),
+            content: "Use OAuth".to_string(),
+            tags: None,
+            file_refs: None,
+            source: None,
+            created_by: None,
+        }).unwrap();
+
+        // First sync publishes the branch and the row
+        let report = sync_workspace(&db_manager, &alpha.id, "origin", no_token).unwrap();
+        let git = report.git.unwrap();
+        assert_eq!((git.divergence, git.action), (Divergence::NoUpstream, GitSyncAction::Pushed));
+        assert_eq!(report.pushed.len(), 1);
+        assert_eq!(report.pushed[0].title, "Auth");
+        let sync_id = report.pushed[0].sync_id.clone();
+
+        Repository::clone(&remote_url, &beta_project).unwrap();
+        let report = sync_workspace(&db_manager, &beta.id, "origin", no_token).unwrap();
+        assert_eq!(report.git.unwrap().action, GitSyncAction::None);
+        assert_eq!(report.pulled.len(), 1);
+        assert!(report.pushed.is_empty());
+        assert_eq!(knowledge_content(&db_manager, &beta.id, &sync_id), "Use OAuth");
+
+        // Both sides commit and edit the same row; beta's edit is newer
+        commit_file(&alpha_project, "alpha.txt");
+        edit_knowledge(&db_manager, &alpha.id, &sync_id, "Use OAuth with PKCE", "2030-01-01T00:00:00Z");
+        let report = sync_workspace(&db_manager, &alpha.id, "origin", no_token).unwrap();
+        assert_eq!(report.git.unwrap().action, GitSyncAction::Pushed);
+        assert_eq!(report.pushed.len(), 1);
+
+        commit_file(&beta_project, "beta.txt");
+        edit_knowledge(&db_manager, &beta.id, &sync_id, "Use SAML", "2030-01-02T00:00:00Z");
+        let report = sync_workspace(&db_manager, &beta.id, "origin", no_token).unwrap();
+        let git = report.git.unwrap();
+        assert_eq!(git.divergence, Divergence::Diverged { ahead: 1, behind: 1 });
+        assert_eq!(git.action, GitSyncAction::NeedsMerge);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].winner, SyncSide::Local);
+        assert_eq!(report.conflicts[0].remote.data["content"], "Use OAuth with PKCE");
+        assert_eq!(report.pushed.len(), 1);
+
+        // Re-running changes nothing and keeps a single conflict record
+        let report = sync_workspace(&db_manager, &beta.id, "origin", no_token).unwrap();
+        assert!(report.pulled.is_empty() && report.pushed.is_empty() && report.conflicts.is_empty());
+        let conflicts = list_sync_conflicts(&db_manager, &beta.id).unwrap();
+        assert_eq!(conflicts.len(), 1);
+
+        let report = sync_workspace(&db_manager, &alpha.id, "origin", no_token).unwrap();
+        assert_eq!(report.pulled.len(), 1);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(knowledge_content(&db_manager, &alpha.id, &sync_id), "Use SAML");
+
+        // Keeping the losing version rewrites it and the next sync publishes it
+        resolve_sync_conflict(&db_manager, &beta.id, conflicts[0].id, SyncSide::Remote).unwrap();
+        assert!(list_sync_conflicts(&db_manager, &beta.id).unwrap().is_empty());
+        assert!(resolve_sync_conflict(&db_manager, &beta.id, conflicts[0].id, SyncSide::Remote).is_err());
+        assert_eq!(knowledge_content(&db_manager, &beta.id, &sync_id), "Use OAuth with PKCE");
+        assert_eq!(sync_workspace(&db_manager, &beta.id, "origin", no_token).unwrap().pushed.len(), 1);
+        sync_workspace(&db_manager, &alpha.id, "origin", no_token).unwrap();
+        assert_eq!(knowledge_content(&db_manager, &alpha.id, &sync_id), "Use OAuth with PKCE");
+        assert_eq!(knowledge.id, ops.search_knowledge(&alpha.id, "PKCE", false, None).unwrap()[0].id);
+    }
+}
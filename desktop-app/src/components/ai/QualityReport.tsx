@@ -10,6 +10,7 @@ import {
   getImpactColor,
   getScoreColor,
 } from '../../services/aiService';
+import { useWorkspace } from '../../services/workspaceService';
 
 interface QualityReportProps {
   projectId: string;
@@ -18,6 +19,13 @@ interface QualityReportProps {
 
 export function QualityReport({ projectId, className = '' }: QualityReportProps) {
   const { qualityReport, isAnalyzing, error, loadQualityReport, runQualityAnalysis } = useAi();
+  const { currentWorkspace } = useWorkspace();
+
+  const analyze = () => {
+    if (currentWorkspace) {
+      runQualityAnalysis(currentWorkspace.id, projectId, []);
+    }
+  };
 
   useEffect(() => {
     loadQualityReport(projectId);
@@ -45,7 +53,8 @@ export function QualityReport({ projectId, className = '' }: QualityReportProps)
           Run a quality analysis to see insights about your code
         </p>
         <button
-          onClick={() => runQualityAnalysis(projectId, [])}
+          onClick={analyze}
+          disabled={!currentWorkspace}
           className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700"
         >
           Run Analysis
@@ -63,7 +72,8 @@ export function QualityReport({ projectId, className = '' }: QualityReportProps)
             Quality Report
           </h2>
           <button
-            onClick={() => runQualityAnalysis(projectId, [])}
+            onClick={analyze}
+            disabled={!currentWorkspace}
             className="px-3 py-1.5 text-sm bg-blue-600 text-white rounded-lg hover:bg-blue-700"
           >
             Re-analyze
@@ -0,0 +1,128 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type {
+  Config,
+  ConfigTemplate,
+  ConfigTemplateEntry,
+  ConfigTemplateVariable,
+} from "../types/database";
+
+export function useConfigTemplates() {
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  // Create template
+  const createTemplate = useCallback(
+    async (
+      name: string,
+      variables: ConfigTemplateVariable[],
+      entries: ConfigTemplateEntry[],
+      description?: string
+    ): Promise<ConfigTemplate | null> => {
+      setLoading(true);
+      setError(null);
+      try {
+        return await invoke<ConfigTemplate>("create_config_template_db", {
+          name,
+          description,
+          variables,
+          entries,
+        });
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err);
+        setError(message);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    []
+  );
+
+  // Update template
+  const updateTemplate = useCallback(
+    async (template: ConfigTemplate): Promise<ConfigTemplate | null> => {
+      setLoading(true);
+      setError(null);
+      try {
+        return await invoke<ConfigTemplate>("update_config_template_db", {
+          template,
+        });
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err);
+        setError(message);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    []
+  );
+
+  // List templates
+  const listTemplates = useCallback(async (): Promise<ConfigTemplate[]> => {
+    setLoading(true);
+    setError(null);
+    try {
+      return await invoke<ConfigTemplate[]>("list_config_templates_db");
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err);
+      setError(message);
+      return [];
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  // Delete template
+  const deleteTemplate = useCallback(async (id: string): Promise<boolean> => {
+    setLoading(true);
+    setError(null);
+    try {
+      await invoke("delete_config_template_db", { id });
+      return true;
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err);
+      setError(message);
+      return false;
+    } finally {
+      setLoading(false);
+    }
+  }, []);
+
+  // Resolve a template and upsert its configs into a workflow
+  const applyTemplate = useCallback(
+    async (
+      workflowId: string,
+      templateId: string,
+      vars: Record<string, string>
+    ): Promise<Config[] | null> => {
+      setLoading(true);
+      setError(null);
+      try {
+        return await invoke<Config[]>("apply_config_template", {
+          workflowId,
+          templateId,
+          vars,
+        });
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err);
+        setError(message);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    []
+  );
+
+  return {
+    loading,
+    error,
+    createTemplate,
+    updateTemplate,
+    listTemplates,
+    deleteTemplate,
+    applyTemplate,
+  };
+}
@@ -1,9 +1,11 @@
 import { useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import type {
+  ConfigDiff,
   Execution,
   ExecutionFilter,
   ExecutionStatus,
+  Page,
 } from "../types/database";
 
 export function useExecutionDatabase() {
@@ -12,13 +14,18 @@ export function useExecutionDatabase() {
 
   // Create execution
   const createExecution = useCallback(
-    async (workflowId: string, workflowName: string): Promise<Execution | null> => {
+    async (
+      workflowId: string,
+      workflowName: string,
+      configSnapshot?: Record<string, any>
+    ): Promise<Execution | null> => {
       setLoading(true);
       setError(null);
       try {
         const execution = await invoke<Execution>("create_execution_db", {
           workflowId,
           workflowName,
+          configSnapshot,
         });
         return execution;
       } catch (err) {
@@ -53,20 +60,19 @@ export function useExecutionDatabase() {
     []
   );
 
-  // List executions
-  const listExecutions = useCallback(
-    async (filter: ExecutionFilter = {}): Promise<Execution[]> => {
+  // List executions, one page at a time
+  const listExecutionsPage = useCallback(
+    async (filter: ExecutionFilter = {}): Promise<Page<Execution>> => {
       setLoading(true);
       setError(null);
       try {
-        const executions = await invoke<Execution[]>("list_executions_db", {
+        return await invoke<Page<Execution>>("list_executions_db", {
           filter,
         });
-        return executions;
       } catch (err) {
         const message = err instanceof Error ? err.message : String(err);
         setError(message);
-        return [];
+        return { items: [], next_cursor: null };
       } finally {
         setLoading(false);
       }
@@ -74,6 +80,13 @@ export function useExecutionDatabase() {
     []
   );
 
+  // List executions
+  const listExecutions = useCallback(
+    async (filter: ExecutionFilter = {}): Promise<Execution[]> =>
+      (await listExecutionsPage(filter)).items,
+    [listExecutionsPage]
+  );
+
   // Update execution status
   const updateExecutionStatus = useCallback(
     async (
@@ -138,14 +151,31 @@ export function useExecutionDatabase() {
     []
   );
 
+  // Compare the config snapshots of two executions
+  const diffExecutionConfigs = useCallback(
+    async (idA: string, idB: string): Promise<ConfigDiff | null> => {
+      setError(null);
+      try {
+        return await invoke<ConfigDiff>("diff_execution_configs", { idA, idB });
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err);
+        setError(message);
+        return null;
+      }
+    },
+    []
+  );
+
   return {
     loading,
     error,
     createExecution,
     getExecution,
     listExecutions,
+    listExecutionsPage,
     updateExecutionStatus,
     deleteExecution,
     deleteOldExecutions,
+    diffExecutionConfigs,
   };
 }
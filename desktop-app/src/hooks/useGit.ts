@@ -1,5 +1,36 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useState } from "react";
+import type { DiffHunk } from "../services/cliService";
+
+export type FileChangeKind = "added" | "modified" | "deleted" | "renamed" | "type_change";
+
+export interface FileDiff {
+  path: string;
+  old_path?: string;
+  change: FileChangeKind;
+  binary: boolean;
+  hunks: DiffHunk[];
+}
+
+export interface StatusEntry {
+  path: string;
+  old_path?: string;
+  change: FileChangeKind;
+}
+
+export interface RepoStatus {
+  branch?: string;
+  has_commits: boolean;
+  staged: StatusEntry[];
+  unstaged: StatusEntry[];
+  untracked: string[];
+  conflicted: string[];
+}
+
+/** Rejection value of `pushBranch`; prompt for a token on `auth_required` */
+export type PushError =
+  | { kind: "auth_required"; host: string }
+  | { kind: "failed"; message: string };
 
 export interface GitHook {
   initialized: boolean;
@@ -14,6 +45,10 @@ export interface GitHook {
   pushBranch: (branchName: string, remoteName?: string) => Promise<void>;
   refreshStatus: () => Promise<void>;
   listBranches: () => Promise<string[]>;
+  diff: (staged: boolean) => Promise<FileDiff[]>;
+  status: () => Promise<RepoStatus>;
+  stash: (message?: string) => Promise<string | null>;
+  stashPop: (index?: number) => Promise<void>;
 }
 
 export function useGit(): GitHook {
@@ -76,7 +111,7 @@ export function useGit(): GitHook {
 
   const pushBranch = async (branchName: string, remoteName: string = "origin") => {
     try {
-      await invoke("git_push_branch", { branchName, remoteName });
+      await invoke("git_push_branch", { branchName, remote: remoteName });
       await refreshStatus();
     } catch (error) {
       console.error("Failed to push branch:", error);
@@ -113,6 +148,45 @@ export function useGit(): GitHook {
     }
   };
 
+  const diff = async (staged: boolean): Promise<FileDiff[]> => {
+    try {
+      return await invoke<FileDiff[]>("git_diff", { staged });
+    } catch (error) {
+      console.error("Failed to get diff:", error);
+      throw error;
+    }
+  };
+
+  const status = async (): Promise<RepoStatus> => {
+    try {
+      return await invoke<RepoStatus>("git_status");
+    } catch (error) {
+      console.error("Failed to get status:", error);
+      throw error;
+    }
+  };
+
+  const stash = async (message?: string): Promise<string | null> => {
+    try {
+      const stashId = await invoke<string | null>("git_stash", { message });
+      await refreshStatus();
+      return stashId;
+    } catch (error) {
+      console.error("Failed to stash changes:", error);
+      throw error;
+    }
+  };
+
+  const stashPop = async (index?: number) => {
+    try {
+      await invoke("git_stash_pop", { index });
+      await refreshStatus();
+    } catch (error) {
+      console.error("Failed to pop stash:", error);
+      throw error;
+    }
+  };
+
   return {
     initialized,
     currentBranch,
@@ -126,5 +200,9 @@ export function useGit(): GitHook {
     pushBranch,
     refreshStatus,
     listBranches,
+    diff,
+    status,
+    stash,
+    stashPop,
   };
 }
@@ -4,6 +4,7 @@ import type {
   WorkflowArgs,
   WorkflowExecution,
   OutputMessage,
+  StoredArtifact,
 } from "../types/workflow";
 
 export function useWorkflowExecution() {
@@ -103,6 +104,11 @@ export function useWorkflowExecution() {
     }
   }, []);
 
+  // Get files the workflow produced
+  const getArtifacts = useCallback(async (workflowId: string) => {
+    return invoke<StoredArtifact[]>("get_workflow_artifacts", { workflowId });
+  }, []);
+
   // Poll for output
   const pollOutput = useCallback(async (workflowId: string) => {
     try {
@@ -172,6 +178,7 @@ export function useWorkflowExecution() {
     startWorkflow,
     stopWorkflow,
     getStatus,
+    getArtifacts,
     getExecution: (id: string) => executions.get(id),
   };
 }
@@ -4,10 +4,11 @@
 // - Smart suggestions
 // - Code completion
 // - Quality analysis
-// - Auto-documentation
+// - Auto-documentation as reviewable diffs
 
 import { invoke } from '@tauri-apps/api/core';
 import React, { createContext, useContext, useState, useCallback, ReactNode } from 'react';
+import type { CodeSuggestion } from './cliService';
 
 // ============================================
 // Types
@@ -126,30 +127,51 @@ export interface QualityMetrics {
   lines_of_code: number;
 }
 
-export interface DocumentationRequest {
-  content: string;
-  language: string;
-  doc_type: DocumentationType;
-  style: DocumentationStyle;
+export interface QualitySnapshot {
+  id: string;
+  project_id: string;
+  commit_sha: string | null;
+  overall_score: number;
+  complexity: number;
+  maintainability: number;
+  documentation_coverage: number;
+  code_coverage: number | null;
+  test_count: number;
+  issue_count: number;
+  lines_of_code: number;
+  created_at: number;
 }
 
-export type DocumentationType = 'function' | 'class' | 'module' | 'api' | 'readme';
-export type DocumentationStyle = 'jsdoc' | 'docstring' | 'markdown' | 'rustdoc' | 'javadoc';
+export interface MetricDelta {
+  metric: string;
+  previous: number;
+  current: number;
+  change: number;
+  regression: boolean;
+}
 
-export interface DocumentationResult {
-  documentation: string;
-  summary: string;
-  parameters: ParameterDoc[];
-  returns?: string;
-  examples: string[];
+export interface QualityTrendPoint {
+  snapshot: QualitySnapshot;
+  deltas: MetricDelta[];
+  regressed: boolean;
 }
 
-export interface ParameterDoc {
-  name: string;
-  param_type: string;
-  description: string;
-  optional: boolean;
-  default?: string;
+export interface CodeFinding {
+  id: string;
+  file: string;
+  start_line: number;
+  end_line: number;
+  severity: Impact;
+  message: string;
+  suggested_fix: string | null;
+}
+
+export interface FileAnalysis {
+  file: string;
+  content_hash: string;
+  findings: CodeFinding[];
+  from_cache: boolean;
+  skipped: string | null;
 }
 
 export interface AiSettings {
@@ -166,12 +188,12 @@ export interface AiSettings {
 // API Functions
 // ============================================
 
-export async function analyzeCode(
-  projectId: string,
-  content: string,
-  filePath: string
-): Promise<Suggestion[]> {
-  return invoke('ai_analyze_code', { projectId, content, filePath });
+export async function analyzeCode(projectId: string, filePaths: string[]): Promise<FileAnalysis[]> {
+  return invoke('ai_analyze_code', { projectId, filePaths });
+}
+
+export async function predictBugs(projectId: string, filePaths: string[]): Promise<FileAnalysis[]> {
+  return invoke('ai_predict_bugs', { projectId, filePaths });
 }
 
 export async function getSuggestions(projectId: string): Promise<Suggestion[]> {
@@ -191,20 +213,33 @@ export async function getCompletions(request: CompletionRequest): Promise<Comple
 }
 
 export async function analyzeQuality(
+  workspaceId: string,
   projectId: string,
   files: [string, string][]
 ): Promise<QualityReport> {
-  return invoke('ai_analyze_quality', { projectId, files });
+  return invoke('ai_analyze_quality', { workspaceId, projectId, files });
 }
 
 export async function getQualityReport(projectId: string): Promise<QualityReport> {
   return invoke('ai_get_quality_report', { projectId });
 }
 
-export async function generateDocumentation(
-  request: DocumentationRequest
-): Promise<DocumentationResult> {
-  return invoke('ai_generate_documentation', { request });
+export async function getQualityTrend(
+  workspaceId: string,
+  projectId: string,
+  options: { since?: number; limit?: number; regressionThreshold?: number } = {}
+): Promise<QualityTrendPoint[]> {
+  return invoke('ai_get_quality_trend', {
+    workspaceId,
+    projectId,
+    since: options.since ?? null,
+    limit: options.limit ?? null,
+    regressionThreshold: options.regressionThreshold ?? null,
+  });
+}
+
+export async function generateDocs(filePath: string): Promise<CodeSuggestion> {
+  return invoke('ai_generate_docs', { filePath });
 }
 
 export async function getAiSettings(): Promise<AiSettings> {
@@ -227,13 +262,13 @@ interface AiContextValue {
   error: string | null;
   
   // Actions
-  analyze: (projectId: string, content: string, filePath: string) => Promise<void>;
+  analyze: (projectId: string, filePaths: string[]) => Promise<void>;
   loadSuggestions: (projectId: string) => Promise<void>;
   dismiss: (projectId: string, suggestionId: string) => Promise<void>;
   apply: (projectId: string, suggestionId: string) => Promise<void>;
-  runQualityAnalysis: (projectId: string, files: [string, string][]) => Promise<void>;
+  runQualityAnalysis: (workspaceId: string, projectId: string, files: [string, string][]) => Promise<void>;
   loadQualityReport: (projectId: string) => Promise<void>;
-  generateDocs: (request: DocumentationRequest) => Promise<DocumentationResult>;
+  generateDocs: (filePath: string) => Promise<CodeSuggestion>;
   loadSettings: () => Promise<void>;
   saveSettings: (settings: AiSettings) => Promise<void>;
 }
@@ -247,12 +282,13 @@ export function AiProvider({ children }: { children: ReactNode }) {
   const [isAnalyzing, setIsAnalyzing] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
-  const analyze = useCallback(async (projectId: string, content: string, filePath: string) => {
+  const analyze = useCallback(async (projectId: string, filePaths: string[]) => {
     setIsAnalyzing(true);
     setError(null);
     try {
-      const newSuggestions = await analyzeCode(projectId, content, filePath);
-      setSuggestions(prev => [...prev, ...newSuggestions]);
+      await analyzeCode(projectId, filePaths);
+      // Findings are stored as suggestions keyed by stable id, so reload rather than append
+      setSuggestions(await getSuggestions(projectId));
     } catch (e) {
       setError(String(e));
     } finally {
@@ -289,11 +325,11 @@ export function AiProvider({ children }: { children: ReactNode }) {
     }
   }, []);
 
-  const runQualityAnalysis = useCallback(async (projectId: string, files: [string, string][]) => {
+  const runQualityAnalysis = useCallback(async (workspaceId: string, projectId: string, files: [string, string][]) => {
     setIsAnalyzing(true);
     setError(null);
     try {
-      const report = await analyzeQuality(projectId, files);
+      const report = await analyzeQuality(workspaceId, projectId, files);
       setQualityReport(report);
     } catch (e) {
       setError(String(e));
@@ -312,8 +348,8 @@ export function AiProvider({ children }: { children: ReactNode }) {
     }
   }, []);
 
-  const generateDocs = useCallback(async (request: DocumentationRequest) => {
-    return generateDocumentation(request);
+  const generateDocsForFile = useCallback(async (filePath: string) => {
+    return generateDocs(filePath);
   }, []);
 
   const loadSettings = useCallback(async () => {
@@ -346,7 +382,7 @@ export function AiProvider({ children }: { children: ReactNode }) {
     apply,
     runQualityAnalysis,
     loadQualityReport,
-    generateDocs,
+    generateDocs: generateDocsForFile,
     loadSettings,
     saveSettings,
   };
@@ -125,6 +125,7 @@ export async function logout(navigate?: (path: string) => void): Promise<void> {
   } catch (error) {
     console.error('Failed to clear credentials:', error);
   }
+  await invoke('ent_sign_out').catch((error) => console.error('Failed to end session:', error));
   
   // Clear cache
   cachedToken = null;
@@ -154,6 +155,8 @@ export async function verifyToken(): Promise<boolean> {
     if (response.ok) {
       const user = await response.json();
       await setUser(user);
+      // Permission checks in the app act on the user the backend verifies here
+      await invoke('ent_sign_in').catch((error) => console.error('Failed to start session:', error));
       return true;
     }
 
@@ -389,3 +392,43 @@ export async function hasApiKey(provider: LLMProvider): Promise<boolean> {
   const key = await getApiKey(provider);
   return key !== null && key.length > 0;
 }
+
+// ============================================
+// Git Host Tokens
+// ============================================
+
+/**
+ * Store a personal access token for a git host (e.g. "github.com")
+ */
+export async function setGitToken(host: string, token: string): Promise<void> {
+  try {
+    await invoke('set_git_token', { host, token });
+  } catch (error) {
+    console.error(`Failed to set git token for ${host}:`, error);
+    throw error;
+  }
+}
+
+/**
+ * Check if a token is stored for a git host
+ */
+export async function hasGitToken(host: string): Promise<boolean> {
+  try {
+    return await invoke<boolean>('has_git_token', { host });
+  } catch (error) {
+    console.error(`Failed to check git token for ${host}:`, error);
+    return false;
+  }
+}
+
+/**
+ * Delete the token stored for a git host
+ */
+export async function deleteGitToken(host: string): Promise<void> {
+  try {
+    await invoke('delete_git_token', { host });
+  } catch (error) {
+    console.error(`Failed to delete git token for ${host}:`, error);
+    throw error;
+  }
+}
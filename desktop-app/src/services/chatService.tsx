@@ -7,6 +7,7 @@
 // - Session management
 
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import React, { createContext, useContext, useState, useEffect, useCallback, ReactNode } from 'react';
 
 // ============================================
@@ -60,6 +61,8 @@ export interface RetrievedContext {
   title: string;
   content: string;
   relevance_score: number;
+  text_score: number;
+  confidence?: number;
   source: string;
 }
 
@@ -128,6 +131,13 @@ export interface LlmServiceConfig {
   };
 }
 
+export interface FallbackPolicy {
+  /** Models tried in order after a transient failure of the primary */
+  fallback_models: string[];
+  /** Cap on completion calls per message, the primary model included */
+  max_attempts: number;
+}
+
 export interface MediaAttachment {
   id?: string;
   type: 'image' | 'video' | 'audio';
@@ -206,6 +216,31 @@ export async function clearSessionMemory(
   return invoke('clear_session_memory', { workspaceId, sessionId });
 }
 
+export interface SummarizationConfig {
+  keep_recent_n: number;
+  prompt: string;
+  model_id: string | null;
+  max_tokens: number;
+}
+
+export interface SessionSummary {
+  summary: WorkingMemory;
+  summarized_count: number;
+}
+
+/** Fold older turns into a working-memory summary; null when there was nothing to do or no API key */
+export async function summarizeSession(
+  workspaceId: string,
+  sessionId: string,
+  keepRecentN?: number
+): Promise<SessionSummary | null> {
+  return invoke('summarize_session', { workspaceId, sessionId, keepRecentN });
+}
+
+export async function setSummarizationConfig(config: SummarizationConfig): Promise<void> {
+  return invoke('set_summarization_config', { config });
+}
+
 export async function addWorkingMemory(
   workspaceId: string,
   request: {
@@ -322,6 +357,14 @@ export async function getModelForMode(mode: string): Promise<string> {
   return invoke('get_model_for_mode', { mode });
 }
 
+export async function getFallbackPolicy(): Promise<FallbackPolicy> {
+  return invoke('get_fallback_policy');
+}
+
+export async function setFallbackPolicy(policy: FallbackPolicy): Promise<void> {
+  return invoke('set_fallback_policy', { policy });
+}
+
 export async function estimateTokens(text: string): Promise<number> {
   return invoke('estimate_tokens', { text });
 }
@@ -361,6 +404,71 @@ export async function testProviderConnection(
   return invoke('test_provider_connection', { providerId, apiKey });
 }
 
+export interface ChatServiceResponse {
+  message: string;
+  skill_used: string | null;
+  tokens_used: number;
+  context_tokens: number;
+  retrieved_context_count: number;
+  model_used: string;
+  cancelled: boolean;
+}
+
+export type ChatStreamEvent =
+  | { type: 'delta'; content: string }
+  | { type: 'done'; cancelled: boolean };
+
+/** Send a message and receive the reply piece by piece through `onDelta` */
+export async function sendMessageStream(
+  workspaceId: string,
+  sessionId: string,
+  message: string,
+  onDelta: (content: string) => void,
+  modelId?: string
+): Promise<ChatServiceResponse> {
+  const unlisten = await listen<ChatStreamEvent>(`chat://stream/${sessionId}`, (event) => {
+    if (event.payload.type === 'delta') {
+      onDelta(event.payload.content);
+    }
+  });
+  try {
+    return await invoke('chat_send_message_stream', { workspaceId, sessionId, message, modelId });
+  } finally {
+    unlisten();
+  }
+}
+
+export interface UsageBreakdown {
+  key: string;
+  requests: number;
+  input_tokens: number;
+  output_tokens: number;
+  cost_usd: number;
+}
+
+export interface ChatUsageStats {
+  total_requests: number;
+  total_input_tokens: number;
+  total_output_tokens: number;
+  total_cost_usd: number;
+  by_session: UsageBreakdown[];
+  by_model: UsageBreakdown[];
+  by_day: UsageBreakdown[];
+}
+
+/** Token and cost totals for a workspace's chats, or one session when given */
+export async function getChatUsageStats(
+  workspaceId: string,
+  sessionId?: string
+): Promise<ChatUsageStats> {
+  return invoke('chat_get_usage_stats', { workspaceId, sessionId });
+}
+
+/** Stop the reply streaming for a session; resolves false if none was */
+export async function cancelMessageStream(sessionId: string): Promise<boolean> {
+  return invoke('chat_cancel_stream', { sessionId });
+}
+
 // ============================================
 // Chat Context
 // ============================================
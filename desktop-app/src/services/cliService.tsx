@@ -69,12 +69,24 @@ export interface DiffLine {
   content: string;
   old_line_no?: number;
   new_line_no?: number;
+  no_newline?: boolean;
 }
 
 export type DiffLineType = 'Context' | 'Addition' | 'Deletion';
 
 export type SuggestionStatus = 'Pending' | 'Accepted' | 'Rejected' | 'Modified';
 
+export type ExecutionTarget = 'Host' | { Sandbox: { container_id: string } };
+
+export interface InputSuggestion {
+  value: string;
+  label: string;
+  kind: InputSuggestionKind;
+  score: number;
+}
+
+export type InputSuggestionKind = 'Command' | 'History' | 'Path';
+
 export interface FileNode {
   name: string;
   path: string;
@@ -91,6 +103,7 @@ export interface FileContent {
   language: string;
   line_count: number;
   size: number;
+  is_binary: boolean;
 }
 
 export interface SearchResult {
@@ -118,12 +131,20 @@ export async function getWorkspace(): Promise<string | null> {
   return invoke('cli_get_workspace');
 }
 
+export async function setSandbox(containerId: string | null): Promise<void> {
+  return invoke('cli_set_sandbox', { containerId });
+}
+
+export async function getExecutionTarget(): Promise<ExecutionTarget> {
+  return invoke('cli_get_execution_target');
+}
+
 export async function parseCommand(input: string): Promise<CliCommandInfo> {
   return invoke('cli_parse_command', { input });
 }
 
-export async function executeCommand(input: string): Promise<CommandResult> {
-  return invoke('cli_execute_command', { input });
+export async function executeCommand(input: string, steps?: string[]): Promise<CommandResult> {
+  return invoke('cli_execute_command', { input, steps });
 }
 
 export async function getHistory(): Promise<string[]> {
@@ -134,6 +155,10 @@ export async function searchHistory(query: string): Promise<string[]> {
   return invoke('cli_search_history', { query });
 }
 
+export async function getInputSuggestions(input: string, cursor?: number, limit?: number): Promise<InputSuggestion[]> {
+  return invoke('cli_get_suggestions', { input, cursor, limit });
+}
+
 // Suggestion APIs
 export async function addSuggestion(suggestion: CodeSuggestion): Promise<void> {
   return invoke('cli_add_suggestion', { suggestion });
@@ -184,6 +209,14 @@ export async function generateDiff(original: string, modified: string): Promise<
   return invoke('cli_generate_diff', { original, modified });
 }
 
+export async function getFileDiff(path: string, modified: string, contextLines?: number): Promise<DiffHunk[]> {
+  return invoke('cli_get_diff', { path, modified, contextLines });
+}
+
+export async function applyFileDiff(path: string, hunks: DiffHunk[]): Promise<FileContent> {
+  return invoke('cli_apply_diff', { path, hunks });
+}
+
 // ============================================
 // CLI Context
 // ============================================
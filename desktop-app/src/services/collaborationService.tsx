@@ -9,6 +9,7 @@
 
 import { invoke } from '@tauri-apps/api/core';
 import React, { createContext, useContext, useState, useCallback, useEffect, ReactNode } from 'react';
+import type { Page } from '../types/database';
 
 // ============================================
 // Types
@@ -37,9 +38,15 @@ export interface Comment {
   mentions: string[];
   reactions: Reaction[];
   replies: Comment[];
+  parent_comment_id?: string;
+  /** Set on the thread's root comment */
   resolved: boolean;
   created_at: number;
   updated_at: number;
+  edited_at?: number;
+  /** Deleted, but kept because it still has replies */
+  deleted: boolean;
+  sequence: number;
 }
 
 export type CommentTarget = 'task' | 'spec' | 'document' | 'project' | 'line';
@@ -60,6 +67,32 @@ export interface Review {
   comments: ReviewComment[];
   requested_at: number;
   completed_at?: number;
+  requester_id?: string;
+  transitions: ReviewTransition[];
+}
+
+export interface ReviewTransition {
+  from: ReviewStatus;
+  to: ReviewStatus;
+  at: number;
+}
+
+export interface ReviewPolicy {
+  required_approvals: number;
+  /** Rejections always block */
+  changes_requested_blocks: boolean;
+}
+
+export interface ReviewSummary {
+  target_type: ReviewTarget;
+  target_id: string;
+  policy: ReviewPolicy;
+  approvals: number;
+  changes_requested: number;
+  rejections: number;
+  pending: number;
+  can_merge: boolean;
+  blockers: string[];
 }
 
 export type ReviewTarget = 'spec' | 'document' | 'task';
@@ -100,12 +133,39 @@ export interface Activity {
   user_id: string;
   user_name: string;
   activity_type: ActivityType;
-  target_type: string;
+  target_type: EntityType;
   target_id: string;
   target_name: string;
   description: string;
   metadata: Record<string, unknown>;
   timestamp: number;
+  event: ActivityEvent;
+}
+
+export type EntityType = 'job' | 'task' | 'spec' | 'document' | 'project' | 'line' | 'comment' | 'review';
+
+export interface EntityRef {
+  entity_type: EntityType;
+  id: string;
+}
+
+export type ActivityEvent =
+  | { kind: 'comment_added'; comment_id: string; parent_comment_id: string | null }
+  | { kind: 'comment_edited'; comment_id: string }
+  | { kind: 'comment_deleted'; comment_id: string }
+  | { kind: 'thread_resolved'; comment_id: string }
+  | { kind: 'thread_reopened'; comment_id: string }
+  | { kind: 'review_requested'; review_id: string; reviewer_id: string }
+  | { kind: 'review_submitted'; review_id: string; status: ReviewStatus };
+
+export interface ActivityFilter {
+  actor_id?: string;
+  /** Matches the target or the comment/review an activity concerns */
+  entity_types?: EntityType[];
+  /** Only activity concerning this entity */
+  entity?: EntityRef;
+  start_time?: number;
+  end_time?: number;
 }
 
 export type ActivityType =
@@ -145,8 +205,13 @@ export async function updateUserStatus(userId: string, status: UserStatus): Prom
 }
 
 // Comment APIs
-export async function addComment(targetType: CommentTarget, targetId: string, content: string): Promise<Comment> {
-  return invoke('collab_add_comment', { targetType, targetId, content });
+export async function addComment(
+  targetType: CommentTarget,
+  targetId: string,
+  content: string,
+  parentCommentId?: string,
+): Promise<Comment> {
+  return invoke('collab_add_comment', { targetType, targetId, content, parentCommentId });
 }
 
 export async function getComments(targetType: CommentTarget, targetId: string): Promise<Comment[]> {
@@ -157,6 +222,14 @@ export async function addReply(commentId: string, content: string): Promise<Comm
   return invoke('collab_add_reply', { commentId, content });
 }
 
+export async function editComment(commentId: string, content: string): Promise<Comment> {
+  return invoke('collab_edit_comment', { commentId, content });
+}
+
+export async function deleteComment(commentId: string): Promise<void> {
+  return invoke('collab_delete_comment', { commentId });
+}
+
 export async function addReaction(commentId: string, emoji: string): Promise<void> {
   return invoke('collab_add_reaction', { commentId, emoji });
 }
@@ -165,6 +238,10 @@ export async function resolveComment(commentId: string): Promise<void> {
   return invoke('collab_resolve_comment', { commentId });
 }
 
+export async function unresolveComment(commentId: string): Promise<void> {
+  return invoke('collab_unresolve_comment', { commentId });
+}
+
 // Review APIs
 export async function requestReview(targetType: ReviewTarget, targetId: string, reviewerId: string): Promise<Review> {
   return invoke('collab_request_review', { targetType, targetId, reviewerId });
@@ -186,6 +263,18 @@ export async function submitReview(
   return invoke('collab_submit_review', { reviewId, status, comments });
 }
 
+export async function setReviewPolicy(
+  targetType: ReviewTarget,
+  targetId: string,
+  policy: ReviewPolicy
+): Promise<ReviewSummary> {
+  return invoke('collab_set_review_policy', { targetType, targetId, policy });
+}
+
+export async function getReviewSummary(targetType: ReviewTarget, targetId: string): Promise<ReviewSummary> {
+  return invoke('collab_get_review_summary', { targetType, targetId });
+}
+
 // Notification APIs
 export async function getNotifications(userId: string, unreadOnly: boolean): Promise<Notification[]> {
   return invoke('collab_get_notifications', { userId, unreadOnly });
@@ -204,8 +293,11 @@ export async function getUnreadCount(userId: string): Promise<number> {
 }
 
 // Activity APIs
-export async function getActivityFeed(limit?: number): Promise<Activity[]> {
-  return invoke('collab_get_activity_feed', { limit });
+export async function getActivityFeed(
+  filter: ActivityFilter = {},
+  options: { beforeId?: string; afterId?: string; limit?: number } = {}
+): Promise<Page<Activity>> {
+  return invoke('collab_get_activity_feed', { filter, ...options });
 }
 
 export async function getUserActivity(userId: string, limit?: number): Promise<Activity[]> {
@@ -242,8 +334,11 @@ interface CollaborationContextValue {
   addCommentToTarget: (targetType: CommentTarget, targetId: string, content: string) => Promise<Comment>;
   loadComments: (targetType: CommentTarget, targetId: string) => Promise<Comment[]>;
   replyToComment: (commentId: string, content: string) => Promise<Comment>;
+  editCommentById: (commentId: string, content: string) => Promise<Comment>;
+  deleteCommentById: (commentId: string) => Promise<void>;
   reactToComment: (commentId: string, emoji: string) => Promise<void>;
   resolveCommentById: (commentId: string) => Promise<void>;
+  unresolveCommentById: (commentId: string) => Promise<void>;
   
   // Review actions
   requestReviewFrom: (targetType: ReviewTarget, targetId: string, reviewerId: string) => Promise<Review>;
@@ -347,6 +442,14 @@ export function CollaborationProvider({ children }: { children: ReactNode }) {
     return addReply(commentId, content);
   }, []);
 
+  const editCommentById = useCallback(async (commentId: string, content: string) => {
+    return editComment(commentId, content);
+  }, []);
+
+  const deleteCommentById = useCallback(async (commentId: string) => {
+    return deleteComment(commentId);
+  }, []);
+
   const reactToComment = useCallback(async (commentId: string, emoji: string) => {
     return addReaction(commentId, emoji);
   }, []);
@@ -355,6 +458,10 @@ export function CollaborationProvider({ children }: { children: ReactNode }) {
     return resolveComment(commentId);
   }, []);
 
+  const unresolveCommentById = useCallback(async (commentId: string) => {
+    return unresolveComment(commentId);
+  }, []);
+
   const requestReviewFrom = useCallback(async (
     targetType: ReviewTarget,
     targetId: string,
@@ -414,8 +521,8 @@ export function CollaborationProvider({ children }: { children: ReactNode }) {
 
   const loadActivityFeed = useCallback(async () => {
     try {
-      const activities = await getActivityFeed(50);
-      setActivityFeed(activities);
+      const activities = await getActivityFeed({}, { limit: 50 });
+      setActivityFeed(activities.items);
     } catch (e) {
       setError(String(e));
     }
@@ -437,8 +544,11 @@ export function CollaborationProvider({ children }: { children: ReactNode }) {
     addCommentToTarget,
     loadComments,
     replyToComment,
+    editCommentById,
+    deleteCommentById,
     reactToComment,
     resolveCommentById,
+    unresolveCommentById,
     requestReviewFrom,
     loadPendingReviews,
     submitReviewResult,
@@ -93,6 +93,32 @@ export interface TimelineEntry {
   metadata: Record<string, unknown>;
 }
 
+export type TimelineGranularity = 'hour' | 'day' | 'week' | 'month';
+
+export interface TimelineBucket {
+  start: number;
+  end: number;
+  label: string;
+  created: number;
+  completed: number;
+}
+
+export interface ProjectTimeline {
+  entries: TimelineEntry[];
+  buckets: TimelineBucket[];
+  granularity: TimelineGranularity;
+  timezone: string;
+}
+
+export interface TimelineOptions {
+  limit?: number;
+  granularity?: TimelineGranularity;
+  /** IANA name; defaults to the browser's zone */
+  timezone?: string;
+  start?: number;
+  end?: number;
+}
+
 export type TimelineEventType =
   | 'task_created'
   | 'task_updated'
@@ -129,6 +155,8 @@ export interface BurndownPoint {
   remaining: number;
   completed: number;
   ideal: number;
+  /** Tasks in scope at `date`; rises when work is added mid-sprint */
+  scope: number;
 }
 
 export interface BoardData {
@@ -212,8 +240,12 @@ export async function toggleSubtask(taskId: string, subtaskId: string): Promise<
   return invoke('dashboard_toggle_subtask', { taskId, subtaskId });
 }
 
-export async function getTimeline(projectId: string, limit?: number): Promise<TimelineEntry[]> {
-  return invoke('dashboard_get_timeline', { projectId, limit });
+export async function getTimeline(projectId: string, options: TimelineOptions = {}): Promise<ProjectTimeline> {
+  return invoke('dashboard_get_timeline', {
+    projectId,
+    ...options,
+    timezone: options.timezone ?? Intl.DateTimeFormat().resolvedOptions().timeZone,
+  });
 }
 
 export async function getMetrics(projectId: string): Promise<ProjectMetrics> {
@@ -224,6 +256,16 @@ export async function getBoardData(projectId: string): Promise<BoardData> {
   return invoke('dashboard_get_board_data', { projectId });
 }
 
+/** Omit `jobId` for the whole workspace; `start`/`end` are unix seconds */
+export async function getJobBurndown(
+  workspaceId: string,
+  jobId?: string,
+  start?: number,
+  end?: number,
+): Promise<BurndownPoint[]> {
+  return invoke('dashboard_get_burndown', { workspaceId, jobId, start, end });
+}
+
 // ============================================
 // Dashboard Context
 // ============================================
@@ -300,12 +342,12 @@ export function DashboardProvider({ children }: { children: ReactNode }) {
         getProject(projectId),
         getBoardData(projectId),
         getMetrics(projectId),
-        getTimeline(projectId, 50),
+        getTimeline(projectId, { limit: 50 }),
       ]);
       setCurrentProject(project);
       setBoardData(board);
       setMetrics(projectMetrics);
-      setTimeline(projectTimeline);
+      setTimeline(projectTimeline.entries);
       setSelectedTask(null);
     } catch (e) {
       setError(String(e));
@@ -431,8 +473,8 @@ export function DashboardProvider({ children }: { children: ReactNode }) {
   const refreshTimeline = useCallback(async () => {
     if (!currentProject) return;
     try {
-      const projectTimeline = await getTimeline(currentProject.id, 50);
-      setTimeline(projectTimeline);
+      const projectTimeline = await getTimeline(currentProject.id, { limit: 50 });
+      setTimeline(projectTimeline.entries);
     } catch (e) {
       setError(String(e));
     }
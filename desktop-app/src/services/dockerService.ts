@@ -6,6 +6,7 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 
 // Types
 export type ContainerStatus = 
@@ -76,6 +77,40 @@ export interface SandboxConfig {
   cpu_limit?: number;
 }
 
+export interface BranchWorkspaceSummary {
+  name: string;
+  path: string;
+  repository?: string;
+  branch_count: number;
+  running_containers: number;
+  last_active: string;
+}
+
+export interface ComposeServiceState {
+  name: string;
+  container_id?: string;
+  status: "none" | "created" | "running" | "stopped" | "removed";
+}
+
+export interface BranchWorkspaceList {
+  workspaces: BranchWorkspaceSummary[];
+  total: number;
+}
+
+export interface ImageProgress {
+  id?: string;
+  status: string;
+  item_percent?: number;
+  /** Overall progress (0-100); never decreases within one pull or build */
+  percent: number;
+  done: boolean;
+}
+
+interface DockerProgressEvent {
+  request_id: string;
+  progress: ImageProgress;
+}
+
 // Docker Service Class
 class DockerService {
   /**
@@ -209,6 +244,49 @@ class DockerService {
     }
   }
 
+  /**
+   * Pull a Docker image, reporting progress as layers download
+   */
+  async pullImageWithProgress(
+    image: string,
+    onProgress: (progress: ImageProgress) => void
+  ): Promise<string> {
+    return this.withProgress(onProgress, (requestId) =>
+      invoke<string>("docker_pull_image_streaming", { image, requestId })
+    );
+  }
+
+  /**
+   * Build a Docker image, reporting progress per build step
+   */
+  async buildImageWithProgress(
+    contextDir: string,
+    tag: string,
+    onProgress: (progress: ImageProgress) => void,
+    dockerfile?: string
+  ): Promise<string> {
+    return this.withProgress(onProgress, (requestId) =>
+      invoke<string>("docker_build_image_streaming", { contextDir, tag, dockerfile, requestId })
+    );
+  }
+
+  private async withProgress(
+    onProgress: (progress: ImageProgress) => void,
+    run: (requestId: string) => Promise<string>
+  ): Promise<string> {
+    const requestId = crypto.randomUUID();
+    const unlisten = await listen<DockerProgressEvent>("docker:progress", (event) => {
+      if (event.payload.request_id === requestId) {
+        onProgress(event.payload.progress);
+      }
+    });
+    try {
+      return await run(requestId);
+    } finally {
+      unlisten();
+    }
+  }
+
   /**
    * Remove a Docker image
    */
@@ -268,6 +346,39 @@ class DockerService {
       throw error;
     }
   }
+
+  /**
+   * Start a branch's compose project (generated if the workspace has none)
+   */
+  async composeUp(workspace: string, branch: string): Promise<unknown> {
+    return await invoke("docker_compose_up", { workspace, branch });
+  }
+
+  /**
+   * Stop and remove a branch's compose project
+   */
+  async composeDown(workspace: string, branch: string): Promise<unknown> {
+    return await invoke("docker_compose_down", { workspace, branch });
+  }
+
+  /**
+   * List a branch's compose services and their statuses
+   */
+  async composePs(workspace: string, branch: string): Promise<ComposeServiceState[]> {
+    return await invoke<ComposeServiceState[]>("docker_compose_ps", { workspace, branch });
+  }
+
+  /**
+   * Reconcile branch container statuses with Docker (safe to poll)
+   */
+  async reconcileWorkspaces(): Promise<BranchWorkspaceList> {
+    try {
+      return await invoke<BranchWorkspaceList>("docker_reconcile_workspaces");
+    } catch (error) {
+      console.error("Failed to reconcile workspaces:", error);
+      throw error;
+    }
+  }
 }
 
 // Export singleton instance
@@ -13,6 +13,14 @@ import React, { createContext, useContext, useState, useCallback, useEffect, Rea
 // Types
 // ============================================
 
+/** The signed-in user, as verified by the backend; RBAC checks act on this user */
+export interface SessionUser {
+  id: string;
+  email: string;
+  full_name?: string;
+  is_admin: boolean;
+}
+
 export interface SsoConfig {
   id: string;
   provider: SsoProvider;
@@ -21,17 +29,55 @@ export interface SsoConfig {
   tenant_id?: string;
   domain?: string;
   metadata_url?: string;
+  issuer?: string;
+  oidc_endpoints?: OidcEndpoints;
+  saml_metadata?: SamlMetadata;
   created_at: number;
   updated_at: number;
 }
 
 export type SsoProvider = 'saml' | 'oidc' | 'azure_ad' | 'okta' | 'google' | 'github';
 
+export interface OidcEndpoints {
+  issuer: string;
+  authorization_endpoint: string;
+  token_endpoint: string;
+  jwks_uri: string;
+  userinfo_endpoint?: string;
+  end_session_endpoint?: string;
+}
+
+export interface SamlMetadata {
+  entity_id: string;
+  sso_url: string;
+  signing_certificate: string;
+  certificate_expires_at: number;
+}
+
+export interface SsoCheck {
+  name: string;
+  passed: boolean;
+  detail: string;
+}
+
+export interface SsoValidationReport {
+  valid: boolean;
+  checks: SsoCheck[];
+  config?: SsoConfig;
+}
+
+export interface SsoSourceOptions {
+  issuer?: string;
+  metadataUrl?: string;
+  metadataXml?: string;
+}
+
 export interface Role {
   id: string;
   name: string;
   description: string;
   permissions: Permission[];
+  parent_role?: string;
   is_system: boolean;
   created_at: number;
   updated_at: number;
@@ -66,6 +112,13 @@ export interface UserRole {
   assigned_by: string;
 }
 
+export interface EffectivePermission {
+  permission: Permission;
+  granted_by: string;
+  assigned_role: string;
+  scope: RoleScope;
+}
+
 export type RoleScope = 
   | { type: 'global' }
   | { type: 'organization'; org_id: string }
@@ -85,6 +138,72 @@ export interface AuditLog {
   user_agent?: string;
   status: AuditStatus;
   error_message?: string;
+  prev_hash: string;
+  hash: string;
+}
+
+export type AuditCategory = 'auth' | 'data' | 'admin' | 'system';
+
+export interface ReportFilter {
+  start_time?: number;
+  end_time?: number;
+  categories?: AuditCategory[];
+}
+
+export interface ReportHeader {
+  title: string;
+  organization?: string;
+  workspace?: string;
+  generated_at: number;
+  filter: ReportFilter;
+}
+
+export interface ComplianceControl {
+  control: string;
+  passed: boolean;
+  detail: string;
+}
+
+export interface AuditCategorySummary {
+  category: AuditCategory;
+  total: number;
+  failures: number;
+}
+
+export interface ComplianceReport {
+  header: ReportHeader;
+  controls: ComplianceControl[];
+  audit_summary: AuditCategorySummary[];
+  audit_integrity: AuditIntegrityReport;
+}
+
+export type ReportKind = 'compliance' | 'audit_logs';
+export type ReportFormat = 'csv' | 'pdf';
+
+export interface ReportExportResult {
+  path: string;
+  count: number;
+}
+
+export interface BrokenAuditLink {
+  index: number;
+  log_id: string;
+  reason: string;
+}
+
+export interface AuditIntegrityReport {
+  valid: boolean;
+  entries_checked: number;
+  head_hash: string;
+  first_broken?: BrokenAuditLink;
+}
+
+export interface AuditExport {
+  exported_at: number;
+  hash_algorithm: string;
+  genesis_hash: string;
+  head_hash: string;
+  entries: AuditLog[];
 }
 
 export type AuditAction = 
@@ -158,9 +277,10 @@ export async function configureSso(
   provider: SsoProvider,
   clientId: string,
   tenantId?: string,
-  domain?: string
-): Promise<SsoConfig> {
-  return invoke('ent_configure_sso', { provider, clientId, tenantId, domain });
+  domain?: string,
+  source: SsoSourceOptions = {}
+): Promise<SsoValidationReport> {
+  return invoke('ent_configure_sso', { provider, clientId, tenantId, domain, ...source });
 }
 
 export async function getSsoConfigs(): Promise<SsoConfig[]> {
@@ -171,13 +291,35 @@ export async function disableSso(configId: string): Promise<void> {
   return invoke('ent_disable_sso', { configId });
 }
 
+// Session
+export async function signIn(): Promise<SessionUser> {
+  return invoke('ent_sign_in');
+}
+
+export async function signOut(): Promise<void> {
+  return invoke('ent_sign_out');
+}
+
+export async function getSession(): Promise<SessionUser | null> {
+  return invoke('ent_get_session');
+}
+
+export async function bootstrapAdmin(): Promise<UserRole> {
+  return invoke('ent_bootstrap_admin');
+}
+
 // RBAC
 export async function createRole(
   name: string,
   description: string,
-  permissions: Permission[]
+  permissions: Permission[],
+  parentRole?: string
 ): Promise<Role> {
-  return invoke('ent_create_role', { name, description, permissions });
+  return invoke('ent_create_role', { name, description, permissions, parentRole });
+}
+
+export async function setRoleParent(roleId: string, parentRole?: string): Promise<Role> {
+  return invoke('ent_set_role_parent', { roleId, parentRole });
 }
 
 export async function getRoles(): Promise<Role[]> {
@@ -188,10 +330,9 @@ export async function assignRole(
   userId: string,
   roleId: string,
   scopeType: string,
-  scopeId?: string,
-  assignedBy?: string
+  scopeId?: string
 ): Promise<UserRole> {
-  return invoke('ent_assign_role', { userId, roleId, scopeType, scopeId, assignedBy: assignedBy || 'system' });
+  return invoke('ent_assign_role', { userId, roleId, scopeType, scopeId });
 }
 
 export async function revokeRole(userId: string, roleId: string): Promise<void> {
@@ -210,6 +351,10 @@ export async function checkPermission(
   return invoke('ent_check_permission', { userId, resource, action });
 }
 
+export async function getPermissions(userId: string): Promise<EffectivePermission[]> {
+  return invoke('ent_get_permissions', { userId });
+}
+
 // Audit
 export async function logAction(params: {
   userId: string;
@@ -227,6 +372,14 @@ export async function queryAuditLogs(query: AuditQuery): Promise<AuditQueryResul
   return invoke('ent_query_audit_logs', { query });
 }
 
+export async function exportAuditLogs(): Promise<AuditExport> {
+  return invoke('ent_export_audit_logs');
+}
+
+export async function verifyAuditIntegrity(): Promise<AuditIntegrityReport> {
+  return invoke('ent_verify_audit_integrity');
+}
+
 // Compliance
 export async function getComplianceSettings(): Promise<ComplianceSettings> {
   return invoke('ent_get_compliance_settings');
@@ -236,6 +389,25 @@ export async function updateComplianceSettings(settings: ComplianceSettings): Pr
   return invoke('ent_update_compliance_settings', { settings });
 }
 
+export async function getComplianceReport(
+  filter?: ReportFilter,
+  organization?: string,
+  workspace?: string
+): Promise<ComplianceReport> {
+  return invoke('ent_get_compliance_report', { filter, organization, workspace });
+}
+
+export async function exportReport(
+  report: ReportKind,
+  format: ReportFormat,
+  path: string,
+  filter?: ReportFilter,
+  organization?: string,
+  workspace?: string
+): Promise<ReportExportResult> {
+  return invoke('ent_export_report', { report, format, path, filter, organization, workspace });
+}
+
 export async function requestDataExport(userId: string, exportType: ExportType): Promise<DataExportRequest> {
   return invoke('ent_request_data_export', { userId, exportType });
 }
@@ -261,7 +433,7 @@ interface EnterpriseContextValue {
   
   // SSO
   loadSsoConfigs: () => Promise<void>;
-  addSsoConfig: (provider: SsoProvider, clientId: string, tenantId?: string, domain?: string) => Promise<void>;
+  addSsoConfig: (provider: SsoProvider, clientId: string, tenantId?: string, domain?: string, source?: SsoSourceOptions) => Promise<SsoValidationReport>;
   removeSsoConfig: (configId: string) => Promise<void>;
   
   // RBAC
@@ -296,10 +468,13 @@ export function EnterpriseProvider({ children }: { children: ReactNode }) {
     }
   }, []);
 
-  const addSsoConfig = useCallback(async (provider: SsoProvider, clientId: string, tenantId?: string, domain?: string) => {
+  const addSsoConfig = useCallback(async (provider: SsoProvider, clientId: string, tenantId?: string, domain?: string, source?: SsoSourceOptions) => {
     try {
-      await configureSso(provider, clientId, tenantId, domain);
-      await loadSsoConfigs();
+      const report = await configureSso(provider, clientId, tenantId, domain, source);
+      if (report.valid) {
+        await loadSsoConfigs();
+      }
+      return report;
     } catch (e) {
       setError(String(e));
       throw e;
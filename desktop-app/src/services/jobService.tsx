@@ -42,7 +42,8 @@ export type JobStatus =
   | 'Blocked'
   | 'Review'
   | 'Completed'
-  | 'Cancelled';
+  | 'Cancelled'
+  | 'Failed';
 
 export type JobPriority = 'Low' | 'Medium' | 'High' | 'Critical';
 
@@ -61,6 +62,7 @@ export interface Task {
   line_end?: number;
   dependencies: string[];
   created_at: string;
+  started_at?: string;
   completed_at?: string;
 }
 
@@ -87,6 +89,33 @@ export interface CommitInfo {
   date: string;
 }
 
+export type ConflictKind =
+  | 'both_modified'
+  | 'both_added'
+  | 'both_deleted'
+  | 'added_by_us'
+  | 'added_by_them'
+  | 'deleted_by_us'
+  | 'deleted_by_them';
+
+export interface ConflictRegion {
+  start_line: number;
+  end_line: number;
+  ours: string;
+  base?: string;
+  theirs: string;
+}
+
+export interface ConflictedFile {
+  path: string;
+  kind: ConflictKind;
+  markers: ConflictRegion[];
+}
+
+export type MergeOutcome =
+  | { status: 'clean' }
+  | { status: 'conflicts'; conflicts: ConflictedFile[] };
+
 export interface JobStats {
   total_jobs: number;
   by_status: Record<string, number>;
@@ -162,6 +191,18 @@ export async function pauseJob(jobId: string): Promise<Job> {
   return invoke('job_pause', { jobId });
 }
 
+export async function resumeJob(jobId: string): Promise<Job> {
+  return invoke('job_resume', { jobId });
+}
+
+export async function setJobWorkspace(path: string): Promise<number> {
+  return invoke('job_set_workspace', { path });
+}
+
+export async function shutdownJobScheduler(): Promise<void> {
+  return invoke('job_scheduler_shutdown');
+}
+
 export async function completeJob(jobId: string): Promise<Job> {
   return invoke('job_complete', { jobId });
 }
@@ -183,6 +224,14 @@ export async function reorderTasks(jobId: string, taskIds: string[]): Promise<vo
   return invoke('task_reorder', { jobId, taskIds });
 }
 
+export async function addTaskDependency(jobId: string, taskId: string, dependsOn: string): Promise<Task> {
+  return invoke('task_add_dependency', { jobId, taskId, dependsOn });
+}
+
+export async function removeTaskDependency(jobId: string, taskId: string, dependsOn: string): Promise<Task> {
+  return invoke('task_remove_dependency', { jobId, taskId, dependsOn });
+}
+
 export async function deleteTask(jobId: string, taskId: string): Promise<void> {
   return invoke('task_delete', { jobId, taskId });
 }
@@ -200,10 +249,18 @@ export async function checkoutBranch(branchName: string): Promise<void> {
   return invoke('branch_checkout', { branchName });
 }
 
-export async function mergeBranch(source: string, target: string): Promise<void> {
+export async function mergeBranch(source: string, target: string): Promise<MergeOutcome> {
   return invoke('branch_merge', { source, target });
 }
 
+export async function abortMerge(): Promise<void> {
+  return invoke('branch_merge_abort');
+}
+
+export async function continueMerge(): Promise<MergeOutcome> {
+  return invoke('branch_merge_continue');
+}
+
 export async function deleteBranch(branchName: string): Promise<void> {
   return invoke('branch_delete', { branchName });
 }
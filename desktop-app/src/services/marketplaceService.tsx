@@ -40,8 +40,18 @@ export interface MarketplaceItem {
   updated_at: number;
   verified: boolean;
   featured: boolean;
+  package?: PackageInfo;
 }
 
+export interface PackageInfo {
+  download_url: string;
+  checksum?: string;
+  signature?: string;
+  signing_key_id?: string;
+}
+
+export type Verification = 'verified' | 'unverified';
+
 export type MarketplaceItemType = 'plugin' | 'template' | 'theme' | 'integration';
 
 export interface Author {
@@ -77,6 +87,7 @@ export interface SearchResult {
   page: number;
   per_page: number;
   total_pages: number;
+  from_cache: boolean;
 }
 
 export interface InstalledItem {
@@ -84,6 +95,8 @@ export interface InstalledItem {
   version: string;
   installed_at: number;
   auto_update: boolean;
+  package_path?: string;
+  verification?: Verification;
 }
 
 export interface Review {
@@ -140,6 +153,10 @@ export async function getFeaturedItems(): Promise<MarketplaceItem[]> {
   return invoke('marketplace_get_featured');
 }
 
+export async function setIndexUrl(url: string | null, ttlSecs?: number): Promise<void> {
+  return invoke('marketplace_set_index_url', { url, ttlSecs });
+}
+
 export async function getCategories(): Promise<CategoryInfo[]> {
   return invoke('marketplace_get_categories');
 }
@@ -148,6 +165,22 @@ export async function installItem(itemId: string): Promise<InstalledItem> {
   return invoke('marketplace_install', { itemId });
 }
 
+export async function setAllowUnverified(allow: boolean): Promise<void> {
+  return invoke('marketplace_set_allow_unverified', { allow });
+}
+
+export async function listTrustedKeys(): Promise<string[]> {
+  return invoke('marketplace_list_trusted_keys');
+}
+
+export async function addTrustedKey(keyId: string, publicKey: string): Promise<void> {
+  return invoke('marketplace_add_trusted_key', { keyId, publicKey });
+}
+
+export async function removeTrustedKey(keyId: string): Promise<void> {
+  return invoke('marketplace_remove_trusted_key', { keyId });
+}
+
 export async function uninstallItem(itemId: string): Promise<void> {
   return invoke('marketplace_uninstall', { itemId });
 }
@@ -3,11 +3,12 @@
 // Provides:
 // - Workspace management
 // - Workspace switching
-// - Member management
+// - Team workspaces: members, roles and invite tokens
 // - Sync operations
 
 import { invoke } from '@tauri-apps/api/core';
 import { createContext, useContext, useState, useCallback, useEffect, ReactNode } from 'react';
+import type { WorkspaceMetadata } from './workspaceService';
 
 // ============================================
 // Types
@@ -45,6 +46,22 @@ export interface WorkspaceMember {
 
 export type WorkspaceRole = 'owner' | 'admin' | 'editor' | 'viewer';
 
+export interface WorkspaceInvite {
+  id: string;
+  workspace_id: string;
+  user_id: string;
+  role: WorkspaceRole;
+  token: string;
+  created_by: string;
+  created_at: number;
+  expires_at: number;
+}
+
+export interface TeamWorkspace {
+  workspace: WorkspaceMetadata;
+  owner: WorkspaceMember;
+}
+
 export interface WorkspaceSettings {
   default_template?: string;
   auto_backup: boolean;
@@ -96,6 +113,84 @@ export interface RecentWorkspace {
   project_count: number;
 }
 
+export type SearchSource = 'knowledge' | 'memory' | 'chat';
+
+export interface GlobalSearchHit {
+  workspace_id: string;
+  workspace_name: string;
+  source: SearchSource;
+  item_id: number;
+  title: string;
+  session_id?: string;
+  snippet: string;
+  score: number;
+}
+
+export interface SkippedWorkspace {
+  workspace_id: string;
+  workspace_name: string;
+  error: string;
+}
+
+export interface GlobalSearchResults {
+  hits: GlobalSearchHit[];
+  skipped: SkippedWorkspace[];
+}
+
+export type Divergence =
+  | { state: 'up_to_date' }
+  | { state: 'ahead'; commits: number }
+  | { state: 'behind'; commits: number }
+  | { state: 'diverged'; ahead: number; behind: number }
+  | { state: 'no_upstream' };
+
+export type GitSyncAction = 'none' | 'fast_forwarded' | 'pushed' | 'needs_merge';
+
+export type SyncSide = 'local' | 'remote';
+
+export interface SyncRecord {
+  table: string;
+  sync_id: string;
+  updated_at: string;
+  data: Record<string, unknown>;
+}
+
+export interface SyncItem {
+  table: string;
+  sync_id: string;
+  title: string;
+}
+
+export interface SyncConflict {
+  id: number;
+  local: SyncRecord;
+  remote: SyncRecord;
+  winner: SyncSide;
+  created_at?: string;
+  resolved_at?: string;
+}
+
+export interface SyncReport {
+  workspace_id: string;
+  remote: string;
+  git?: {
+    branch: string;
+    divergence: Divergence;
+    action: GitSyncAction;
+  };
+  pulled: SyncItem[];
+  pushed: SyncItem[];
+  conflicts: SyncConflict[];
+  synced_at: string;
+}
+
+export interface GlobalSearchOptions {
+  limit?: number;
+  includeKnowledge?: boolean;
+  includeMemory?: boolean;
+  includeChat?: boolean;
+}
+
 // ============================================
 // API Functions
 // ============================================
@@ -140,6 +235,15 @@ export async function getRecentWorkspaces(limit?: number): Promise<RecentWorkspa
   return invoke('mw_get_recent_workspaces', { limit });
 }
 
+/** The signed-in user becomes the owner */
+export async function createTeamWorkspace(name: string, gitRemote?: string): Promise<TeamWorkspace> {
+  return invoke('mw_create_team_workspace', { name, gitRemote });
+}
+
+export async function listMembers(workspaceId: string): Promise<WorkspaceMember[]> {
+  return invoke('mw_list_members', { workspaceId });
+}
+
 export async function addMember(
   workspaceId: string,
   userId: string,
@@ -150,7 +254,8 @@ export async function addMember(
   return invoke('mw_add_member', { workspaceId, userId, name, email, role });
 }
 
-export async function removeMember(workspaceId: string, userId: string): Promise<void> {
+/** Resolves to the number of invite tokens revoked */
+export async function removeMember(workspaceId: string, userId: string): Promise<number> {
   return invoke('mw_remove_member', { workspaceId, userId });
 }
 
@@ -158,10 +263,22 @@ export async function updateMemberRole(
   workspaceId: string,
   userId: string,
   role: WorkspaceRole
-): Promise<void> {
+): Promise<WorkspaceMember> {
   return invoke('mw_update_member_role', { workspaceId, userId, role });
 }
 
+export async function shareWorkspace(
+  workspaceId: string,
+  recipient: { userId: string; name: string; email: string; role: WorkspaceRole },
+  expiresInHours?: number
+): Promise<WorkspaceInvite> {
+  return invoke('mw_share_workspace', { workspaceId, ...recipient, expiresInHours });
+}
+
+export async function authorizeInvite(workspaceId: string, token: string): Promise<WorkspaceMember> {
+  return invoke('mw_authorize_invite', { workspaceId, token });
+}
+
 export async function enableSync(workspaceId: string): Promise<void> {
   return invoke('mw_enable_sync', { workspaceId });
 }
@@ -189,6 +306,29 @@ export async function updateWorkspaceSettings(
   return invoke('mw_update_workspace_settings', { workspaceId, settings });
 }
 
+export async function searchAllWorkspaces(
+  query: string,
+  options: GlobalSearchOptions = {}
+): Promise<GlobalSearchResults> {
+  return invoke('mw_search_all', { query, ...options });
+}
+
+export async function syncWorkspace(workspaceId: string, remote?: string): Promise<SyncReport> {
+  return invoke('mw_sync_workspace', { workspaceId, remote });
+}
+
+export async function listSyncConflicts(workspaceId: string): Promise<SyncConflict[]> {
+  return invoke('mw_list_sync_conflicts', { workspaceId });
+}
+
+export async function resolveSyncConflict(
+  workspaceId: string,
+  conflictId: number,
+  keep: SyncSide
+): Promise<void> {
+  return invoke('mw_resolve_sync_conflict', { workspaceId, conflictId, keep });
+}
+
 // ============================================
 // Multi-workspace Context
 // ============================================
@@ -5,6 +5,7 @@
 // - Cache management
 // - Database optimization
 // - Performance monitoring hooks
+// - System health diagnostics
 
 import { invoke } from '@tauri-apps/api/core';
 import React, { createContext, useContext, useState, useEffect, useCallback, useRef, ReactNode } from 'react';
@@ -36,7 +37,9 @@ export interface Recommendation {
   level: 'Info' | 'Warning' | 'Critical';
   category: string;
   message: string;
+  /** Command that applies the recommendation */
   action?: string;
+  action_args: Record<string, unknown> | null;
 }
 
 export interface DatabaseStats {
@@ -56,6 +59,66 @@ export interface CacheStats {
   miss_count: number;
   eviction_count: number;
   oldest_entry_age_secs: number;
+  render_hit_count: number;
+  render_miss_count: number;
+}
+
+export type MemoryPressure = 'normal' | 'high' | 'critical';
+
+export interface MemoryStats {
+  rss_mb: number;
+  used_mb: number;
+  available_mb: number;
+  total_mb: number;
+  usage_percent: number;
+  timestamp: number;
+}
+
+export interface CacheSize {
+  name: string;
+  entries: number;
+}
+
+export interface MemoryMetrics {
+  stats: MemoryStats;
+  pressure: MemoryPressure;
+  high_watermark_mb: number;
+  critical_watermark_mb: number;
+  caches: CacheSize[];
+}
+
+export interface PressureRelief {
+  level: MemoryPressure;
+  rss_before_mb: number;
+  rss_after_mb: number;
+  evictions: { name: string; evicted: number }[];
+  timestamp: number;
+}
+
+/** Payload of the `memory:pressure` event */
+export interface MemoryAlert {
+  level: 'warning' | 'critical';
+  message: string;
+  current_mb: number;
+  threshold_mb: number;
+  timestamp: number;
+}
+
+export type HealthStatus = 'ok' | 'degraded' | 'down';
+
+export interface ComponentHealth {
+  name: string;
+  status: HealthStatus;
+  latency_ms: number;
+  detail?: string;
+}
+
+export interface HealthReport {
+  status: HealthStatus;
+  components: ComponentHealth[];
+  app_version: string;
+  os: string;
+  checked_at: string;
 }
 
 export interface OptimizationResult {
@@ -105,6 +168,36 @@ export async function getCacheStats(): Promise<CacheStats> {
   return invoke('perf_get_cache_stats');
 }
 
+export async function resizeRenderCache(maxSizeMb: number, maxEntries: number): Promise<CacheStats> {
+  return invoke('perf_resize_render_cache', { maxSizeMb, maxEntries });
+}
+
+// Recommendation APIs
+export async function getRecommendations(): Promise<Recommendation[]> {
+  return invoke('perf_get_recommendations');
+}
+
+/** Run the command a recommendation names, with its arguments */
+export async function applyRecommendation(recommendation: Recommendation): Promise<unknown> {
+  if (!recommendation.action) {
+    throw new Error('Recommendation has no action');
+  }
+  return invoke(recommendation.action, recommendation.action_args ?? {});
+}
+
+// Memory APIs
+export async function getMemoryMetrics(): Promise<MemoryMetrics> {
+  return invoke('perf_get_metrics');
+}
+
+export async function setMemoryThresholds(warningMb: number, criticalMb: number): Promise<void> {
+  return invoke('set_memory_thresholds', { warningMb, criticalMb });
+}
+
+export async function relieveMemoryPressure(level?: MemoryPressure): Promise<PressureRelief> {
+  return invoke('relieve_memory_pressure', { level });
+}
+
 // Database APIs
 export async function optimizeDatabase(dbPath: string): Promise<OptimizationResult> {
   return invoke('perf_optimize_database', { dbPath });
@@ -114,6 +207,11 @@ export async function getDatabaseStats(dbPath: string): Promise<DatabaseStats> {
   return invoke('perf_get_database_stats', { dbPath });
 }
 
+// Diagnostics
+export async function systemHealthCheck(): Promise<HealthReport> {
+  return invoke('system_health_check');
+}
+
 // ============================================
 // Performance Context
 // ============================================
@@ -19,6 +19,7 @@ export interface Plugin {
   state: PluginState;
   settings: Record<string, unknown>;
   permissions: Permission[];
+  entry_path: string;
   installed_at: number;
   updated_at: number;
 }
@@ -127,6 +128,31 @@ export interface PluginContext {
   settings: Record<string, unknown>;
 }
 
+/** A requested permission as shown in the install prompt */
+export interface PermissionRequest {
+  permission: Permission;
+  description: string;
+  /** Whether the current allowlist lets the plugin be enabled with it */
+  allowed: boolean;
+}
+
+export interface PluginInstallPreview {
+  manifest: PluginManifest;
+  permissions: PermissionRequest[];
+}
+
+export interface PluginLimits {
+  timeout_ms: number;
+  max_output_bytes: number;
+  max_memory_bytes: number | null;
+  max_cpu_seconds: number | null;
+}
+
+export interface PluginOutput {
+  output: unknown;
+  duration_ms: number;
+}
+
 // ============================================
 // API Functions
 // ============================================
@@ -135,6 +161,21 @@ export async function installPlugin(manifest: PluginManifest, wasmPath: string):
   return invoke('plugin_install', { manifest, wasmPath });
 }
 
+export async function previewPluginInstall(pluginDir: string): Promise<PluginInstallPreview> {
+  return invoke('plugin_preview_install', { pluginDir });
+}
+
+export async function installPluginFromDir(
+  pluginDir: string,
+  approvedPermissions: Permission[]
+): Promise<Plugin> {
+  return invoke('plugin_install_from_dir', { pluginDir, approvedPermissions });
+}
+
+export async function grantPermissions(pluginId: string, permissions: Permission[]): Promise<void> {
+  return invoke('plugin_grant_permissions', { pluginId, permissions });
+}
+
 export async function uninstallPlugin(pluginId: string): Promise<void> {
   return invoke('plugin_uninstall', { pluginId });
 }
@@ -193,6 +234,34 @@ export async function getPluginTemplate(): Promise<string> {
   return invoke('plugin_get_template');
 }
 
+export async function invokePlugin(pluginId: string, handler: string, input: unknown): Promise<PluginOutput> {
+  return invoke('plugin_invoke', { pluginId, handler, input });
+}
+
+export async function getPluginConfig(pluginId: string, key?: string): Promise<unknown> {
+  return invoke('plugin_get_config', { pluginId, key });
+}
+
+export async function setPluginConfig(pluginId: string, key: string, value: unknown): Promise<void> {
+  return invoke('plugin_set_config', { pluginId, key, value });
+}
+
+export async function getPluginLimits(): Promise<PluginLimits> {
+  return invoke('plugin_get_limits');
+}
+
+export async function setPluginLimits(limits: PluginLimits): Promise<void> {
+  return invoke('plugin_set_limits', { limits });
+}
+
+export async function getPermissionAllowlist(): Promise<Permission[]> {
+  return invoke('plugin_get_permission_allowlist');
+}
+
+export async function setPermissionAllowlist(permissions: Permission[]): Promise<void> {
+  return invoke('plugin_set_permission_allowlist', { permissions });
+}
+
 // ============================================
 // Plugin Context
 // ============================================
@@ -44,6 +44,16 @@ export interface Canvas {
   grid_size: number;
   components: CanvasComponent[];
   connections: Connection[];
+  groups?: ComponentGroup[];
+}
+
+export interface ComponentGroup {
+  id: string;
+  name: string;
+  component_ids: string[];
+  frame: boolean;
+  locked: boolean;
+  visible: boolean;
 }
 
 export interface CanvasComponent {
@@ -160,6 +170,19 @@ export interface ComponentUpdate {
   style?: ComponentStyle;
 }
 
+export interface GroupUpdate {
+  name?: string;
+  frame?: boolean;
+  locked?: boolean;
+  visible?: boolean;
+}
+
+export type AlignEdge = 'left' | 'right' | 'top' | 'bottom' | 'center_x' | 'center_y';
+
+export type Axis = 'horizontal' | 'vertical';
+
+export type ExportFormat = 'markdown' | 'json' | 'svg' | 'png' | 'mermaid' | 'drawio';
+
 // ============================================
 // API Functions
 // ============================================
@@ -226,6 +249,52 @@ export async function duplicateComponent(
   return invoke('spec_duplicate_component', { documentId, componentId, offsetX, offsetY });
 }
 
+export async function groupComponents(
+  documentId: string,
+  componentIds: string[],
+  name?: string,
+  frame?: boolean
+): Promise<string> {
+  return invoke('spec_group_components', { documentId, componentIds, name, frame });
+}
+
+export async function ungroup(documentId: string, groupId: string): Promise<string[]> {
+  return invoke('spec_ungroup', { documentId, groupId });
+}
+
+export async function moveGroup(
+  documentId: string,
+  groupId: string,
+  dx: number,
+  dy: number
+): Promise<void> {
+  return invoke('spec_move_group', { documentId, groupId, dx, dy });
+}
+
+export async function updateGroup(
+  documentId: string,
+  groupId: string,
+  updates: GroupUpdate
+): Promise<void> {
+  return invoke('spec_update_group', { documentId, groupId, updates });
+}
+
+export async function alignComponents(
+  documentId: string,
+  componentIds: string[],
+  edge: AlignEdge
+): Promise<CanvasComponent[]> {
+  return invoke('spec_align_components', { documentId, componentIds, edge });
+}
+
+export async function distributeComponents(
+  documentId: string,
+  componentIds: string[],
+  axis: Axis
+): Promise<CanvasComponent[]> {
+  return invoke('spec_distribute_components', { documentId, componentIds, axis });
+}
+
 export async function addConnection(
   documentId: string,
   fromComponent: string,
@@ -256,6 +325,10 @@ export async function exportJson(documentId: string): Promise<string> {
   return invoke('spec_export_json', { documentId });
 }
 
+export async function exportDocument(documentId: string, format: ExportFormat): Promise<string> {
+  return invoke('spec_export', { documentId, format });
+}
+
 // ============================================
 // Spec Builder Context
 // ============================================
@@ -51,6 +51,14 @@ export interface TemplateMetadata {
   estimated_time: string;
   preview_image?: string;
   author?: string;
+  /** Commands the template wants to run after generation; each needs the user's approval */
+  hooks?: PostHook[];
+}
+
+export interface PostHook {
+  name: string;
+  command: string;
+  required: boolean;
 }
 
 export interface TechStack {
@@ -109,6 +117,19 @@ export interface ProjectConfig {
   output_path: string;
   features: string[];
   variables: Record<string, unknown>;
+  keep_partial_on_error?: boolean;
+  /** Fail generation on variables the context doesn't define */
+  strict_variables?: boolean;
+  /** Names of post-generation hooks the user agreed to run */
+  approved_hooks?: string[];
+}
+
+export interface TemplateLintError {
+  file: string;
+  variable: string | null;
+  line: number | null;
+  column: number | null;
+  message: string;
 }
 
 export interface GenerationResult {
@@ -134,6 +155,11 @@ export interface GenerationProgress {
   message: string;
 }
 
+export interface GenerationProgressEvent {
+  generation_id: string;
+  progress: GenerationProgress;
+}
+
 export interface ValidationResult {
   valid: boolean;
   errors: ValidationError[];
@@ -217,6 +243,31 @@ export async function generateProject(config: ProjectConfig): Promise<Generation
   return invoke('template_generate_project', { config });
 }
 
+export async function generateProjectStreaming(
+  config: ProjectConfig,
+  onProgress: (progress: GenerationProgress) => void,
+  event = 'template:progress'
+): Promise<GenerationResult> {
+  const generationId = crypto.randomUUID();
+  const unlisten = await listen<GenerationProgressEvent>(event, (e) => {
+    if (e.payload.generation_id === generationId) {
+      onProgress(e.payload.progress);
+    }
+  });
+  try {
+    return await invoke('template_generate_project_streaming', { config, generationId, event });
+  } finally {
+    unlisten();
+  }
+}
+
+export async function lintTemplate(
+  templateId: string,
+  config: ProjectConfig
+): Promise<TemplateLintError[]> {
+  return invoke('template_lint', { templateId, config });
+}
+
 export async function validateConfig(config: ProjectConfig): Promise<ValidationResult> {
   return invoke('template_validate_config', { config });
 }
@@ -21,11 +21,27 @@ export interface WorkflowRequest {
   platform: string;
 }
 
+export interface WorkflowArgs {
+  spec_id: string;
+  category: string;
+  mode: string;
+  platform: string;
+}
+
 export interface DetectedIntent {
   detected: boolean;
   workflow: string | null;
   params: Record<string, string>;
   confidence: number;
+  args: WorkflowArgs | null;
+}
+
+export interface TriggerPattern {
+  workflow: string;
+  /** Regex matched case-insensitively against the message */
+  pattern: string;
+  /** Confidence contributed by a match (0.0 - 1.0) */
+  weight: number;
 }
 
 export type WorkflowEventType = 
@@ -34,6 +50,7 @@ export type WorkflowEventType =
   | 'Progress'
   | 'Output'
   | 'ApprovalRequest'
+  | 'ApprovalResolved'
   | 'Completed'
   | 'Failed';
 
@@ -67,10 +84,39 @@ export interface WorkflowEventOutput {
 export interface WorkflowEventApprovalRequest {
   type: 'ApprovalRequest';
   workflow_id: string;
+  approval_id: string;
   artifact_type: string;
   artifact_path: string;
   preview: string;
   next_command: string;
+  /** When the approval resolves on its own (RFC 3339), if the policy has a deadline */
+  expires_at: string | null;
+}
+
+export interface WorkflowEventApprovalResolved {
+  type: 'ApprovalResolved';
+  workflow_id: string;
+  approval_id: string;
+  approved: boolean;
+  /** True when the approval policy resolved it rather than the user */
+  automatic: boolean;
+  reason: string | null;
+}
+
+export type ApprovalPolicy =
+  | { type: 'AlwaysAsk' }
+  | { type: 'AutoApprove' }
+  | { type: 'AutoRejectAfter'; timeout_ms: number };
+
+export interface PendingApproval {
+  approval_id: string;
+  workflow_id: string;
+  workflow_name: string;
+  artifact_type: string;
+  artifact_path: string;
+  next_command: string;
+  created_at: string;
+  expires_at: string | null;
 }
 
 export interface WorkflowEventCompleted {
@@ -91,6 +137,7 @@ export type WorkflowEvent =
   | WorkflowEventProgress
   | WorkflowEventOutput
   | WorkflowEventApprovalRequest
+  | WorkflowEventApprovalResolved
   | WorkflowEventCompleted
   | WorkflowEventFailed;
 
@@ -165,9 +212,9 @@ class WorkflowService {
   /**
    * Detect if a user message contains a workflow intent
    */
-  async detectIntent(message: string): Promise<DetectedIntent> {
+  async detectIntent(message: string, workspaceId?: string): Promise<DetectedIntent> {
     try {
-      return await invoke<DetectedIntent>('workflow_detect_intent', { message });
+      return await invoke<DetectedIntent>('workflow_detect_intent', { message, workspaceId });
     } catch (error) {
       console.error('Failed to detect intent:', error);
       return {
@@ -175,16 +222,32 @@ class WorkflowService {
         workflow: null,
         params: {},
         confidence: 0,
+        args: null,
       };
     }
   }
 
   /**
-   * Check if message should trigger a workflow instead of LLM chat
+   * Check if message should trigger a workflow instead of LLM chat.
+   * The backend only reports intents above its confidence threshold.
+   */
+  async shouldTriggerWorkflow(message: string, workspaceId?: string): Promise<boolean> {
+    const intent = await this.detectIntent(message, workspaceId);
+    return intent.detected;
+  }
+
+  /**
+   * Get the user-defined trigger patterns for a workspace
    */
-  async shouldTriggerWorkflow(message: string): Promise<boolean> {
-    const intent = await this.detectIntent(message);
-    return intent.detected && intent.confidence >= 0.7;
+  async getIntentPatterns(workspaceId: string): Promise<TriggerPattern[]> {
+    return invoke<TriggerPattern[]>('workflow_get_intent_patterns', { workspaceId });
+  }
+
+  /**
+   * Replace the user-defined trigger patterns for a workspace
+   */
+  async setIntentPatterns(workspaceId: string, patterns: TriggerPattern[]): Promise<void> {
+    await invoke('workflow_set_intent_patterns', { workspaceId, patterns });
   }
 
   // ========================================
@@ -194,8 +257,8 @@ class WorkflowService {
   /**
    * Execute a workflow from a detected intent
    */
-  async executeFromIntent(message: string): Promise<string | null> {
-    const intent = await this.detectIntent(message);
+  async executeFromIntent(message: string, workspaceId?: string): Promise<string | null> {
+    const intent = await this.detectIntent(message, workspaceId);
     
     if (!intent.detected || !intent.workflow) {
       return null;
@@ -205,7 +268,7 @@ class WorkflowService {
       workflow: intent.workflow,
       args: intent.params,
       prompt: message,
-      platform: 'kilo',
+      platform: intent.args?.platform ?? 'kilo',
     };
 
     return this.execute(request);
@@ -265,16 +328,21 @@ class WorkflowService {
       throw new Error('No pending approval');
     }
 
-    const { workflow_id, next_command } = this.state.pendingApproval;
+    const { workflow_id, approval_id } = this.state.pendingApproval;
     this.state.pendingApproval = null;
     this.state.status = 'running';
 
     try {
-      const newWorkflowId = await invoke<string>('workflow_approve', {
+      // The backend runs the next step it recorded with the approval request
+      const newWorkflowId = await invoke<string | null>('workflow_approve', {
         workflowId: workflow_id,
-        nextCommand: next_command,
+        approvalId: approval_id,
       });
-      this.state.workflowId = newWorkflowId;
+      if (newWorkflowId) {
+        this.state.workflowId = newWorkflowId;
+      } else {
+        this.state.status = 'completed';
+      }
       return newWorkflowId;
     } catch (error) {
       this.state.status = 'failed';
@@ -301,6 +369,27 @@ class WorkflowService {
     });
   }
 
+  /**
+   * Set how approval requests from a workflow are handled
+   */
+  async setApprovalPolicy(workflowName: string, policy: ApprovalPolicy): Promise<void> {
+    await invoke('workflow_set_approval_policy', { workflowName, policy });
+  }
+
+  /**
+   * Get how approval requests from a workflow are handled
+   */
+  async getApprovalPolicy(workflowName: string): Promise<ApprovalPolicy> {
+    return invoke<ApprovalPolicy>('workflow_get_approval_policy', { workflowName });
+  }
+
+  /**
+   * List approvals waiting on the user, oldest first
+   */
+  async listPendingApprovals(): Promise<PendingApproval[]> {
+    return invoke<PendingApproval[]>('workflow_list_pending_approvals');
+  }
+
   // ========================================
   // Event Handling
   // ========================================
@@ -364,6 +453,12 @@ class WorkflowService {
         this.state.pendingApproval = event;
         break;
 
+      case 'ApprovalResolved':
+        if (this.state.pendingApproval?.approval_id === event.approval_id) {
+          this.state.pendingApproval = null;
+        }
+        break;
+
       case 'Completed':
         this.state.status = 'completed';
         break;
@@ -3,6 +3,7 @@
 
 import { invoke } from '@tauri-apps/api/core';
 import React, { createContext, useContext, useState, useEffect, ReactNode } from 'react';
+import type { Page } from '../types/database';
 
 // ============================================
 // Types
@@ -17,6 +18,7 @@ export interface WorkspaceMetadata {
   last_accessed_at: string;
   is_active: boolean;
   metadata_json: string | null;
+  deleted_at: string | null;
 }
 
 export interface WorkspaceDbStats {
@@ -29,6 +31,38 @@ export interface WorkspaceDbStats {
   memory_long_count: number;
   total_tokens_used: number;
   db_size_bytes: number;
+  schema_version: number;
+}
+
+export type BackupKind = 'full' | 'incremental';
+
+export interface BackupGeneration {
+  generation: number;
+  kind: BackupKind;
+  file: string;
+  /** Change log range (from_seq, to_seq] covered by this generation */
+  from_seq: number;
+  to_seq: number;
+  schema_version: number;
+  size_bytes: number;
+  sha256: string;
+  created_at: string;
+  /** Set when a full backup was taken because the chain was broken */
+  fallback_reason: string | null;
+}
+
+export interface ArchivedFile {
+  path: string;
+  size_bytes: number;
+  sha256: string;
+}
+
+export interface WorkspaceArchiveManifest {
+  format_version: number;
+  exported_at: string;
+  workspace: WorkspaceMetadata;
+  schema_version: number;
+  files: ArchivedFile[];
 }
 
 export interface Job {
@@ -61,6 +95,42 @@ export interface Task {
   completed_at: string | null;
 }
 
+export interface JobDeletion {
+  job_id: string;
+  dry_run: boolean;
+  tasks_deleted: number;
+  task_dependencies_deleted: number;
+  working_memory_deleted: number;
+  chat_sessions_archived: number;
+  child_jobs_reparented: number;
+  history_records_detached: number;
+}
+
+export interface JobProgress {
+  job_id: string;
+  total_tasks: number;
+  completed_tasks: number;
+  percent_complete: number;
+  estimate_weighted: boolean;
+  estimated_minutes: number;
+  actual_minutes: number;
+  estimate_accuracy: number | null;
+  eta_minutes: number | null;
+  eta_at: string | null;
+}
+
+export interface TaskDependency {
+  task_id: string;
+  depends_on_task_id: string;
+}
+
+export interface TaskGraph {
+  tasks: Task[];
+  edges: TaskDependency[];
+  execution_order: string[];
+  cycle: string[] | null;
+}
+
 export interface ChatSession {
   id: string;
   job_id: string | null;
@@ -88,6 +158,15 @@ export interface ChatMessage {
   created_at: string;
 }
 
+export interface ChatSearchResult {
+  message: ChatMessage;
+  session_title: string | null;
+  session_type: string;
+  job_id: string | null;
+  /** Excerpt around the match with hits wrapped in `**` */
+  snippet: string;
+}
+
 export interface Knowledge {
   id: number;
   knowledge_type: 'decision' | 'constraint' | 'pattern' | 'reference' | 'note';
@@ -102,6 +181,48 @@ export interface Knowledge {
   updated_at: string;
 }
 
+export interface KnowledgeVersion {
+  id: number;
+  knowledge_id: number;
+  /** Numbered per entry from 1 */
+  version: number;
+  knowledge_type: Knowledge['knowledge_type'];
+  title: string;
+  content: string;
+  tags_json: string | null;
+  file_refs_json: string | null;
+  /** When the entry took on this state */
+  updated_at: string | null;
+  /** When the state was replaced */
+  created_at: string;
+}
+
+export interface UpdateKnowledgeRequest {
+  knowledge_type?: Knowledge['knowledge_type'];
+  title?: string;
+  content?: string;
+  tags?: string[];
+  file_refs?: string[];
+}
+
+export interface TagFacet {
+  tag: string;
+  count: number;
+}
+
+/** Tags in use with how many entries carry each, most used first */
+export interface TagFacets {
+  /** Active knowledge entries only */
+  knowledge: TagFacet[];
+  memory: TagFacet[];
+}
+
+export interface KnowledgeDuplicateGroup {
+  content_hash: string;
+  /** Oldest first */
+  entries: Knowledge[];
+}
+
 export interface MemoryLong {
   id: number;
   category: 'decision' | 'pattern' | 'constraint' | 'learning' | 'reference';
@@ -115,6 +236,67 @@ export interface MemoryLong {
   updated_at: string;
 }
 
+export type MaintenanceOp = 'vacuum' | 'analyze' | 'cleanup_expired' | 'rebuild_fts';
+
+export interface ContainerWorkspace {
+  name: string;
+  path: string;
+  repository: string | null;
+  created_at: string;
+  updated_at: string;
+  default_image: string;
+}
+
+export interface WorkspaceFull {
+  metadata: WorkspaceMetadata;
+  stats: WorkspaceDbStats | null;
+  container: ContainerWorkspace | null;
+}
+
+export type DriftKind =
+  | 'database_only'
+  | 'container_only'
+  | 'unregistered'
+  | 'stale_registry_entry';
+
+export interface WorkspaceDrift {
+  kind: DriftKind;
+  name: string;
+  workspace_id: string | null;
+  fixed: boolean;
+  error: string | null;
+}
+
+export interface ReconcileReport {
+  drift: WorkspaceDrift[];
+  linked: number;
+}
+
+export interface MaintenanceConfig {
+  enabled: boolean;
+  vacuum_interval_hours: number;
+  analyze_interval_hours: number;
+  cleanup_interval_hours: number;
+  fts_rebuild_interval_hours: number;
+  idle_minutes: number;
+  jitter_ratio: number;
+  poll_interval_secs: number;
+}
+
+export interface BusyRetryConfig {
+  max_retries: number;
+  base_delay_ms: number;
+}
+
+export interface MaintenanceRun {
+  workspace_id: string;
+  op: MaintenanceOp;
+  success: boolean;
+  detail: string | null;
+  duration_ms: number;
+  ran_at: string;
+}
+
 // ============================================
 // Workspace Management
 // ============================================
@@ -162,6 +344,33 @@ export async function getWorkspaceStats(workspaceId: string): Promise<WorkspaceD
   return invoke('get_workspace_stats', { workspaceId });
 }
 
+// ============================================
+// Workspace Registry
+// ============================================
+
+export async function createWorkspaceFull(
+  name: string,
+  repository?: string,
+  image?: string,
+  cloneRepo = false
+): Promise<WorkspaceFull> {
+  return invoke('create_workspace_full', {
+    request: { name, repository, image, clone_repo: cloneRepo },
+  });
+}
+
+export async function deleteWorkspaceFull(workspaceId: string): Promise<void> {
+  return invoke('delete_workspace_full', { workspaceId });
+}
+
+export async function getWorkspaceFull(workspaceId: string): Promise<WorkspaceFull> {
+  return invoke('get_workspace_full', { workspaceId });
+}
+
+export async function reconcileWorkspaces(fix?: boolean): Promise<ReconcileReport> {
+  return invoke('reconcile_workspaces', { fix });
+}
+
 // ============================================
 // Workspace Maintenance
 // ============================================
@@ -180,6 +389,32 @@ export async function restoreWorkspace(
   return invoke('restore_workspace', { workspaceId, backupPath });
 }
 
+export async function backupWorkspaceIncremental(workspaceId: string): Promise<BackupGeneration> {
+  return invoke('backup_workspace_incremental', { workspaceId });
+}
+
+export async function listBackupGenerations(workspaceId: string): Promise<BackupGeneration[]> {
+  return invoke('list_backup_generations', { workspaceId });
+}
+
+export async function restoreBackupGeneration(
+  workspaceId: string,
+  generation: number
+): Promise<void> {
+  return invoke('restore_backup_generation', { workspaceId, generation });
+}
+
+export async function exportWorkspaceArchive(
+  workspaceId: string,
+  outPath: string
+): Promise<WorkspaceArchiveManifest> {
+  return invoke('export_workspace_archive', { workspaceId, outPath });
+}
+
+export async function importWorkspaceArchive(archivePath: string): Promise<WorkspaceMetadata> {
+  return invoke('import_workspace_archive', { archivePath });
+}
+
 export async function vacuumWorkspace(workspaceId: string): Promise<void> {
   return invoke('vacuum_workspace', { workspaceId });
 }
@@ -192,6 +427,41 @@ export async function optimizeWorkspace(workspaceId: string): Promise<void> {
   return invoke('optimize_workspace', { workspaceId });
 }
 
+export async function getBusyRetryConfig(): Promise<BusyRetryConfig> {
+  return invoke('get_busy_retry_config');
+}
+
+export async function setBusyRetryConfig(config: BusyRetryConfig): Promise<void> {
+  return invoke('set_busy_retry_config', { config });
+}
+
+export async function getMaxOpenWorkspaces(): Promise<number> {
+  return invoke('get_max_open_workspaces');
+}
+
+export async function setMaxOpenWorkspaces(capacity: number): Promise<void> {
+  return invoke('set_max_open_workspaces', { capacity });
+}
+
+export async function getMaintenanceConfig(): Promise<MaintenanceConfig> {
+  return invoke('maintenance_get_config');
+}
+
+export async function setMaintenanceConfig(config: MaintenanceConfig): Promise<void> {
+  return invoke('maintenance_set_config', { config });
+}
+
+export async function setMaintenanceEnabled(enabled: boolean): Promise<MaintenanceConfig> {
+  return invoke('maintenance_set_enabled', { enabled });
+}
+
+export async function runMaintenanceNow(
+  workspaceId: string,
+  ops?: MaintenanceOp[]
+): Promise<MaintenanceRun[]> {
+  return invoke('maintenance_run_now', { workspaceId, ops });
+}
+
 // ============================================
 // App Settings
 // ============================================
@@ -228,11 +498,22 @@ export async function getJob(workspaceId: string, jobId: string): Promise<Job> {
   return invoke('get_job', { workspaceId, jobId });
 }
 
+export async function getJobProgress(workspaceId: string, jobId: string): Promise<JobProgress> {
+  return invoke('job_get_progress', { workspaceId, jobId });
+}
+
 export async function listJobs(
   workspaceId: string,
   status?: string
 ): Promise<Job[]> {
-  return invoke('list_jobs', { workspaceId, status });
+  return (await listJobsPage(workspaceId, { status })).items;
+}
+
+export async function listJobsPage(
+  workspaceId: string,
+  options: { status?: string; beforeId?: string; afterId?: string; limit?: number } = {}
+): Promise<Page<Job>> {
+  return invoke('list_jobs', { workspaceId, ...options });
 }
 
 export async function updateJobStatus(
@@ -243,8 +524,12 @@ export async function updateJobStatus(
   return invoke('update_job_status', { workspaceId, jobId, status });
 }
 
-export async function deleteJob(workspaceId: string, jobId: string): Promise<void> {
-  return invoke('delete_job', { workspaceId, jobId });
+export async function deleteJob(
+  workspaceId: string,
+  jobId: string,
+  dryRun = false
+): Promise<JobDeletion> {
+  return invoke('delete_job', { workspaceId, jobId, dryRun });
 }
 
 // ============================================
@@ -283,6 +568,34 @@ export async function updateTaskStatus(
   return invoke('update_task_status', { workspaceId, taskId, status });
 }
 
+export async function reorderTasks(
+  workspaceId: string,
+  jobId: string,
+  taskIds: string[]
+): Promise<void> {
+  return invoke('reorder_tasks', { workspaceId, jobId, taskIds });
+}
+
+export async function addTaskDependency(
+  workspaceId: string,
+  taskId: string,
+  dependsOnTaskId: string
+): Promise<void> {
+  return invoke('add_task_dependency', { workspaceId, taskId, dependsOnTaskId });
+}
+
+export async function removeTaskDependency(
+  workspaceId: string,
+  taskId: string,
+  dependsOnTaskId: string
+): Promise<void> {
+  return invoke('remove_task_dependency', { workspaceId, taskId, dependsOnTaskId });
+}
+
+export async function getTaskGraph(workspaceId: string, jobId: string): Promise<TaskGraph> {
+  return invoke('get_task_graph', { workspaceId, jobId });
+}
+
 // ============================================
 // Chat Session Operations
 // ============================================
@@ -341,7 +654,25 @@ export async function getChatMessages(
   sessionId: string,
   limit?: number
 ): Promise<ChatMessage[]> {
-  return invoke('get_chat_messages', { workspaceId, sessionId, limit });
+  return (await getChatMessagesPage(workspaceId, sessionId, { limit })).items;
+}
+
+export async function getChatMessagesPage(
+  workspaceId: string,
+  sessionId: string,
+  options: { beforeId?: string; afterId?: string; limit?: number } = {}
+): Promise<Page<ChatMessage>> {
+  return invoke('get_chat_messages', { workspaceId, sessionId, ...options });
+}
+
+/** With `advanced`, AND/OR/NOT, parentheses, "phrases" and prefix* are honoured */
+export async function searchChatMessages(
+  workspaceId: string,
+  query: string,
+  limit?: number,
+  advanced: boolean = false
+): Promise<ChatSearchResult[]> {
+  return invoke('chat_search_messages', { workspaceId, query, advanced, limit });
 }
 
 // ============================================
@@ -373,9 +704,10 @@ export async function createKnowledge(
 export async function searchKnowledge(
   workspaceId: string,
   query: string,
-  limit?: number
+  limit?: number,
+  advanced: boolean = false
 ): Promise<Knowledge[]> {
-  return invoke('search_knowledge', { workspaceId, query, limit });
+  return invoke('search_knowledge', { workspaceId, query, advanced, limit });
 }
 
 export async function listKnowledge(
@@ -385,6 +717,58 @@ export async function listKnowledge(
   return invoke('list_knowledge', { workspaceId, knowledgeType });
 }
 
+/** Update an entry; its previous state is kept in its history */
+export async function updateKnowledge(
+  workspaceId: string,
+  id: number,
+  request: UpdateKnowledgeRequest
+): Promise<Knowledge> {
+  return invoke('update_knowledge', { workspaceId, id, request });
+}
+
+/** Prior versions of an entry, newest first */
+export async function getKnowledgeHistory(
+  workspaceId: string,
+  id: number
+): Promise<KnowledgeVersion[]> {
+  return invoke('get_knowledge_history', { workspaceId, id });
+}
+
+export async function revertKnowledge(
+  workspaceId: string,
+  id: number,
+  version: number
+): Promise<Knowledge> {
+  return invoke('revert_knowledge', { workspaceId, id, version });
+}
+
+export async function getTagFacets(
+  workspaceId: string
+): Promise<TagFacets> {
+  return invoke('get_tag_facets', { workspaceId });
+}
+
+export async function listKnowledgeByTag(
+  workspaceId: string,
+  tag: string
+): Promise<Knowledge[]> {
+  return invoke('list_knowledge_by_tag', { workspaceId, tag });
+}
+
+export async function findDuplicateKnowledge(
+  workspaceId: string
+): Promise<KnowledgeDuplicateGroup[]> {
+  return invoke('find_duplicate_knowledge', { workspaceId });
+}
+
+/** Merge entries into the one with the richest content; the rest are deactivated */
+export async function mergeKnowledge(
+  workspaceId: string,
+  ids: number[]
+): Promise<Knowledge> {
+  return invoke('merge_knowledge', { workspaceId, ids });
+}
+
 // ============================================
 // Memory Operations
 // ============================================
@@ -422,6 +806,13 @@ export async function incrementMemoryAccess(
   return invoke('increment_memory_access', { workspaceId, memoryId });
 }
 
+export async function listMemoryLongByTag(
+  workspaceId: string,
+  tag: string
+): Promise<MemoryLong[]> {
+  return invoke('list_memory_long_by_tag', { workspaceId, tag });
+}
+
 // ============================================
 // Workspace Context Hook
 // ============================================
@@ -35,6 +35,22 @@ export interface Execution {
   error?: string;
   started_at: number;
   completed_at?: number;
+  config_snapshot?: Record<string, any>;
+}
+
+export interface ConfigChange {
+  /** Dotted for object keys, bracketed for array items, e.g. `steps[id=lint].enabled` */
+  path: string;
+  old?: any;
+  new?: any;
+}
+
+export interface ConfigDiff {
+  execution_a: string;
+  execution_b: string;
+  added: ConfigChange[];
+  removed: ConfigChange[];
+  changed: ConfigChange[];
 }
 
 export interface Config {
@@ -48,6 +64,34 @@ export interface Config {
   updated_at: number;
 }
 
+export interface ConfigTemplateVariable {
+  name: string;
+  description?: string;
+  /** Used when the variable isn't provided */
+  default?: string;
+  /** Defaults to true */
+  required?: boolean;
+}
+
+export interface ConfigTemplateEntry {
+  key: string;
+  /** May contain `{{variable}}` placeholders and template helpers */
+  value: string;
+  value_type: ConfigValueType;
+  description?: string;
+}
+
+/** A reusable config parameter set, shared across workflows and workspaces */
+export interface ConfigTemplate {
+  id: string;
+  name: string;
+  description?: string;
+  variables: ConfigTemplateVariable[];
+  entries: ConfigTemplateEntry[];
+  created_at: number;
+  updated_at: number;
+}
+
 export interface WorkflowFilter {
   name?: string;
   limit?: number;
@@ -59,6 +103,16 @@ export interface ExecutionFilter {
   status?: ExecutionStatus;
   limit?: number;
   offset?: number;
+  /** Keyset cursors from a previous page's `next_cursor` */
+  before_id?: string;
+  after_id?: string;
+}
+
+/** One page of a keyset-paginated listing */
+export interface Page<T> {
+  items: T[];
+  /** Pass back as the same cursor (`before_id` or `after_id`) to continue */
+  next_cursor: string | null;
 }
 
 export interface DatabaseStats {
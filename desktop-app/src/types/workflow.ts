@@ -3,11 +3,15 @@ export interface WorkflowArgs {
   category: string;
   mode: string;
   platform: string;
+  workspace_path?: string | null;
 }
 
 export interface Workflow {
   name: string;
   description: string;
+  category: string | null;
+  /** The entry exactly as the bridge listed it */
+  raw: Record<string, any>;
 }
 
 export type OutputMessageType =
@@ -16,6 +20,7 @@ export type OutputMessageType =
   | "progress"
   | "output"
   | "error"
+  | "artifact"
   | "completed"
   | "failed";
 
@@ -23,6 +28,9 @@ export interface OutputMessage {
   type: OutputMessageType;
   workflow_id: string;
   timestamp: string;
+  schema_version: number;
+  /** The message exactly as the bridge sent it */
+  raw: Record<string, any>;
   [key: string]: any;
 }
 
@@ -31,9 +39,11 @@ export interface StartedMessage extends OutputMessage {
   workflow_name: string;
 }
 
+export type LogLevel = "debug" | "info" | "warning" | "error" | "other";
+
 export interface LogMessage extends OutputMessage {
   type: "log";
-  level: string;
+  level: LogLevel;
   message: string;
 }
 
@@ -55,9 +65,40 @@ export interface ErrorMessage extends OutputMessage {
   message: string;
 }
 
+export interface WorkflowArtifact {
+  path: string;
+  kind: string | null;
+}
+
+export interface ArtifactMessage extends OutputMessage {
+  type: "artifact";
+  path: string;
+  kind: string | null;
+}
+
+export interface StoredArtifact {
+  name: string;
+  path: string;
+  source_path: string;
+  kind: string | null;
+  size_bytes: number;
+  content_type: string;
+  collected_at: string;
+  /** validate-spec result, for spec artifacts */
+  validation: Record<string, any> | null;
+}
+
+export interface WorkflowResult {
+  success: boolean;
+  spec_id: string | null;
+  output: any;
+  artifacts: WorkflowArtifact[];
+  simulated: boolean;
+}
+
 export interface CompletedMessage extends OutputMessage {
   type: "completed";
-  result: any;
+  result: WorkflowResult;
 }
 
 export interface FailedMessage extends OutputMessage {